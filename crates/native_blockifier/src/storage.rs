@@ -58,6 +58,9 @@ impl PapyrusStorage {
                 growth_step: 2 << 30,     // 2GB
                 max_object_size: 1 << 30, // 1GB
             },
+            index_events_by_key: false,
+            history_pruning_config: None,
+            compression_config: Default::default(),
         };
         let (reader, writer) = papyrus_storage::open_storage(storage_config)?;
         log::debug!("Initialized Blockifier storage.");
@@ -1,10 +1,29 @@
+use alloy_primitives::{Address, B256, U256};
+use alloy_rpc_types_eth::Log;
+use alloy_sol_types::SolEvent;
+use mockito::Matcher;
 use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
 use starknet_api::block::{BlockHash, BlockHashAndNumber, BlockNumber};
-use starknet_api::felt;
+use starknet_api::core::{ContractAddress, EntryPointSelector, EthAddress, Nonce};
+use starknet_api::transaction::fields::Fee;
+use starknet_api::transaction::{L1HandlerTransaction, L1TransactionHash};
+use starknet_api::{calldata, felt};
 
-use crate::ethereum_base_layer_contract::{EthereumBaseLayerConfig, EthereumBaseLayerContract};
+use crate::constants::{
+    CONSUMED_MESSAGE_TO_L2_EVENT_IDENTIFIER,
+    LOG_MESSAGE_TO_L2_EVENT_IDENTIFIER,
+    MESSAGE_TO_L2_CANCELED_EVENT_IDENTIFIER,
+    MESSAGE_TO_L2_CANCELLATION_STARTED_EVENT_IDENTIFIER,
+};
+use crate::ethereum_base_layer_contract::{
+    EthereumBaseLayerConfig,
+    EthereumBaseLayerContract,
+    EthereumBaseLayerError,
+    Starknet,
+};
 use crate::test_utils::get_test_ethereum_node;
-use crate::BaseLayerContract;
+use crate::{BaseLayerContract, EventData, L1Event};
 
 // TODO: move to global test_utils crate and use everywhere instead of relying on the
 // confusing `#[ignore]` api to mark slow tests.
@@ -23,6 +42,7 @@ async fn latest_proved_block_ethereum() {
     let config = EthereumBaseLayerConfig {
         node_url: node_handle.0.endpoint().parse().unwrap(),
         starknet_contract_address,
+        ..Default::default()
     };
     let contract = EthereumBaseLayerContract::new(config);
 
@@ -46,3 +66,248 @@ async fn latest_proved_block_ethereum() {
         assert_eq!(latest_block, expected);
     }
 }
+
+#[tokio::test]
+// Note: the test requires ganache-cli installed, otherwise it is ignored.
+async fn l1_block_references_are_chained() {
+    if !in_ci() {
+        return;
+    }
+
+    let (node_handle, starknet_contract_address) = get_test_ethereum_node();
+    let config = EthereumBaseLayerConfig {
+        node_url: node_handle.0.endpoint().parse().unwrap(),
+        starknet_contract_address,
+        ..Default::default()
+    };
+    let contract = EthereumBaseLayerContract::new(config);
+
+    let latest_block_number = contract.latest_l1_block_number(0).await.unwrap().unwrap();
+    let mut parent = contract.l1_block_reference(0).await.unwrap().unwrap();
+    for block_number in 1..=latest_block_number {
+        let block = contract.l1_block_reference(block_number).await.unwrap().unwrap();
+        assert_eq!(block.number, block_number);
+        assert_eq!(block.parent_hash, parent.hash);
+        parent = block;
+    }
+    assert_eq!(contract.l1_block_reference(latest_block_number + 1).await.unwrap(), None);
+}
+
+const FROM_ADDRESS: u64 = 0x1234;
+const TO_ADDRESS: u64 = 0x5678;
+const SELECTOR: u64 = 0x9abc;
+
+fn event_data(nonce: u64) -> EventData {
+    EventData {
+        from_address: EthAddress::try_from(felt!(FROM_ADDRESS)).unwrap(),
+        to_address: ContractAddress::from(TO_ADDRESS),
+        entry_point_selector: EntryPointSelector(felt!(SELECTOR)),
+        payload: calldata![felt!(1_u8), felt!(2_u8)],
+        nonce: Nonce(felt!(nonce)),
+    }
+}
+
+fn log_message_to_l2(nonce: u64, fee: u64) -> Starknet::LogMessageToL2 {
+    Starknet::LogMessageToL2 {
+        fromAddress: Address::left_padding_from(&FROM_ADDRESS.to_be_bytes()),
+        toAddress: U256::from(TO_ADDRESS),
+        selector: U256::from(SELECTOR),
+        payload: vec![U256::from(1), U256::from(2)],
+        nonce: U256::from(nonce),
+        fee: U256::from(fee),
+    }
+}
+
+fn consumed_message_to_l2(nonce: u64) -> Starknet::ConsumedMessageToL2 {
+    Starknet::ConsumedMessageToL2 {
+        fromAddress: Address::left_padding_from(&FROM_ADDRESS.to_be_bytes()),
+        toAddress: U256::from(TO_ADDRESS),
+        selector: U256::from(SELECTOR),
+        payload: vec![U256::from(1), U256::from(2)],
+        nonce: U256::from(nonce),
+    }
+}
+
+// The mocked logs of each block are emitted by a single transaction.
+fn tx_hash_of_block(block_number: u64) -> B256 {
+    B256::left_padding_from(&block_number.to_be_bytes())
+}
+
+fn log_of(contract: &EthereumBaseLayerContract, event: &impl SolEvent, block_number: u64) -> Log {
+    Log {
+        inner: alloy_primitives::Log {
+            address: contract.config.starknet_contract_address,
+            data: event.encode_log_data(),
+        },
+        block_number: Some(block_number),
+        transaction_hash: Some(tx_hash_of_block(block_number)),
+        ..Default::default()
+    }
+}
+
+fn contract_with_mock_node(
+    server: &mockito::ServerGuard,
+    max_logs_block_range: u64,
+) -> EthereumBaseLayerContract {
+    EthereumBaseLayerContract::new(EthereumBaseLayerConfig {
+        node_url: server.url().parse().unwrap(),
+        max_logs_block_range,
+        ..Default::default()
+    })
+}
+
+// Mocks a single `eth_getLogs` request for the given range, answering with the given result or
+// with an error response if it is None.
+fn mock_get_logs(
+    server: &mut mockito::ServerGuard,
+    from_block: u64,
+    to_block: u64,
+    logs: Option<Vec<Log>>,
+) -> mockito::Mock {
+    let request_body = json!({
+        "method": "eth_getLogs",
+        "params": [{"fromBlock": format!("{from_block:#x}"), "toBlock": format!("{to_block:#x}")}],
+    });
+    server
+        .mock("POST", "/")
+        .match_body(Matcher::PartialJson(request_body))
+        .with_body_from_request(move |request| {
+            let request: Value = serde_json::from_slice(request.body().unwrap()).unwrap();
+            let response = match &logs {
+                Some(logs) => json!({"jsonrpc": "2.0", "id": request["id"], "result": logs}),
+                None => json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "error": {"code": -32005, "message": "query returned more than 10000 results"},
+                }),
+            };
+            serde_json::to_vec(&response).unwrap()
+        })
+        .expect(1)
+        .create()
+}
+
+#[test]
+fn l1_handler_tx_from_event_data() {
+    let tx = L1HandlerTransaction::from(event_data(7));
+    assert_eq!(tx.calldata, calldata![felt!(FROM_ADDRESS), felt!(1_u8), felt!(2_u8)]);
+    assert_eq!(tx.contract_address, ContractAddress::from(TO_ADDRESS));
+    assert_eq!(tx.entry_point_selector, EntryPointSelector(felt!(SELECTOR)));
+    assert_eq!(tx.nonce, Nonce(felt!(7_u8)));
+    assert_eq!(tx.version, L1HandlerTransaction::VERSION);
+}
+
+#[tokio::test]
+async fn events_are_decoded() {
+    let mut server = mockito::Server::new_async().await;
+    let contract = contract_with_mock_node(&server, 100);
+    let cancellation_started = Starknet::MessageToL2CancellationStarted {
+        fromAddress: Address::left_padding_from(&FROM_ADDRESS.to_be_bytes()),
+        toAddress: U256::from(TO_ADDRESS),
+        selector: U256::from(SELECTOR),
+        payload: vec![U256::from(1), U256::from(2)],
+        nonce: U256::from(2),
+    };
+    let canceled = Starknet::MessageToL2Canceled {
+        fromAddress: Address::left_padding_from(&FROM_ADDRESS.to_be_bytes()),
+        toAddress: U256::from(TO_ADDRESS),
+        selector: U256::from(SELECTOR),
+        payload: vec![U256::from(1), U256::from(2)],
+        nonce: U256::from(3),
+    };
+    let logs = vec![
+        log_of(&contract, &log_message_to_l2(0, 1000), 3),
+        log_of(&contract, &consumed_message_to_l2(1), 4),
+        log_of(&contract, &cancellation_started, 5),
+        log_of(&contract, &canceled, 5),
+    ];
+    let mock = mock_get_logs(&mut server, 3, 5, Some(logs));
+
+    let events = contract
+        .events(
+            3,
+            5,
+            &[
+                LOG_MESSAGE_TO_L2_EVENT_IDENTIFIER,
+                CONSUMED_MESSAGE_TO_L2_EVENT_IDENTIFIER,
+                MESSAGE_TO_L2_CANCELLATION_STARTED_EVENT_IDENTIFIER,
+                MESSAGE_TO_L2_CANCELED_EVENT_IDENTIFIER,
+            ],
+        )
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(
+        events,
+        [
+            L1Event::LogMessageToL2 {
+                tx: event_data(0).into(),
+                fee: Fee(1000),
+                l1_tx_hash: L1TransactionHash(tx_hash_of_block(3).0),
+            },
+            L1Event::ConsumedMessageToL2(event_data(1)),
+            L1Event::MessageToL2CancellationStarted(event_data(2)),
+            L1Event::MessageToL2Canceled(event_data(3)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn events_range_is_split() {
+    let mut server = mockito::Server::new_async().await;
+    let contract = contract_with_mock_node(&server, 10);
+    let mocks = [
+        mock_get_logs(
+            &mut server,
+            0,
+            9,
+            Some(vec![log_of(&contract, &consumed_message_to_l2(0), 5)]),
+        ),
+        // The node rejects the range, so it is split in two.
+        mock_get_logs(&mut server, 10, 19, None),
+        mock_get_logs(&mut server, 10, 14, Some(vec![])),
+        mock_get_logs(
+            &mut server,
+            15,
+            19,
+            Some(vec![log_of(&contract, &consumed_message_to_l2(1), 17)]),
+        ),
+        mock_get_logs(
+            &mut server,
+            20,
+            25,
+            Some(vec![log_of(&contract, &consumed_message_to_l2(2), 25)]),
+        ),
+    ];
+
+    let events = contract.events(0, 25, &[CONSUMED_MESSAGE_TO_L2_EVENT_IDENTIFIER]).await.unwrap();
+
+    for mock in mocks {
+        mock.assert_async().await;
+    }
+    assert_eq!(
+        events,
+        [0, 1, 2].map(|nonce| L1Event::ConsumedMessageToL2(event_data(nonce))).to_vec()
+    );
+}
+
+#[tokio::test]
+async fn events_of_a_rejected_block_fail() {
+    let mut server = mockito::Server::new_async().await;
+    let contract = contract_with_mock_node(&server, 10);
+    let _mock = mock_get_logs(&mut server, 3, 3, None);
+
+    let result = contract.events(3, 3, &[CONSUMED_MESSAGE_TO_L2_EVENT_IDENTIFIER]).await;
+    assert!(matches!(result, Err(EthereumBaseLayerError::RpcError(_))), "{result:?}");
+}
+
+#[tokio::test]
+async fn unknown_event_identifier() {
+    let contract = EthereumBaseLayerContract::new(EthereumBaseLayerConfig::default());
+    let result = contract.events(0, 1, &["LogMessageToL1"]).await;
+    assert!(
+        matches!(result, Err(EthereumBaseLayerError::UnknownEventIdentifier(ref identifier)) if identifier == "LogMessageToL1"),
+        "{result:?}"
+    );
+}
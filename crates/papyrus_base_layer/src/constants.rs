@@ -0,0 +1,9 @@
+/// Names of the Starknet core contract events, used to select which events to fetch from the base
+/// layer.
+pub type EventIdentifier = &'static str;
+
+pub const LOG_MESSAGE_TO_L2_EVENT_IDENTIFIER: EventIdentifier = "LogMessageToL2";
+pub const CONSUMED_MESSAGE_TO_L2_EVENT_IDENTIFIER: EventIdentifier = "ConsumedMessageToL2";
+pub const MESSAGE_TO_L2_CANCELLATION_STARTED_EVENT_IDENTIFIER: EventIdentifier =
+    "MessageToL2CancellationStarted";
+pub const MESSAGE_TO_L2_CANCELED_EVENT_IDENTIFIER: EventIdentifier = "MessageToL2Canceled";
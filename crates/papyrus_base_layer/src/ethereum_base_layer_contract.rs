@@ -4,9 +4,11 @@ use std::future::IntoFuture;
 use alloy_dyn_abi::SolType;
 use alloy_json_rpc::RpcError;
 pub(crate) use alloy_primitives::Address as EthereumContractAddress;
+use alloy_primitives::{B256, U256};
 use alloy_provider::network::Ethereum;
 use alloy_provider::{Provider, ProviderBuilder, RootProvider};
-use alloy_sol_types::{sol, sol_data};
+use alloy_rpc_types_eth::{Filter, Log};
+use alloy_sol_types::{sol, sol_data, SolEvent};
 use alloy_transport::TransportErrorKind;
 use alloy_transport_http::{Client, Http};
 use async_trait::async_trait;
@@ -14,11 +16,22 @@ use papyrus_config::dumping::{ser_param, ser_required_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializationType, SerializedParam};
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockHash, BlockHashAndNumber, BlockNumber};
+use starknet_api::core::{ContractAddress, EntryPointSelector, EthAddress, Nonce};
 use starknet_api::hash::StarkHash;
+use starknet_api::transaction::fields::{Calldata, Fee};
+use starknet_api::transaction::L1TransactionHash;
+use starknet_api::StarknetApiError;
 use starknet_types_core::felt;
+use tracing::debug;
 use url::Url;
 
-use crate::{BaseLayerContract, L1Event};
+use crate::constants::{
+    CONSUMED_MESSAGE_TO_L2_EVENT_IDENTIFIER,
+    LOG_MESSAGE_TO_L2_EVENT_IDENTIFIER,
+    MESSAGE_TO_L2_CANCELED_EVENT_IDENTIFIER,
+    MESSAGE_TO_L2_CANCELLATION_STARTED_EVENT_IDENTIFIER,
+};
+use crate::{BaseLayerContract, EventData, L1BlockHash, L1BlockReference, L1Event, PriceSample};
 
 type EthereumBaseLayerResult<T> = Result<T, EthereumBaseLayerError>;
 
@@ -79,18 +92,101 @@ impl BaseLayerContract for EthereumBaseLayerContract {
         }))
     }
 
+    /// Fetches the logs in chunks of at most `max_logs_block_range` blocks. A chunk which is
+    /// rejected by the node, e.g., due to a limit on the number of logs in a response, is split in
+    /// two and each half is fetched separately.
     async fn events(
         &self,
-        _from_block: u64,
-        _until_block: u64,
-        _event_identifiers: &[&str],
+        from_block: u64,
+        until_block: u64,
+        event_identifiers: &[&str],
     ) -> EthereumBaseLayerResult<Vec<L1Event>> {
-        todo!("Implmeneted in a subsequent commit")
+        let event_signatures = event_identifiers
+            .iter()
+            .map(|identifier| event_signature(identifier))
+            .collect::<EthereumBaseLayerResult<Vec<_>>>()?;
+        let filter = Filter::new()
+            .address(self.config.starknet_contract_address)
+            .event_signature(event_signatures);
+
+        let max_range = self.config.max_logs_block_range.max(1);
+        // Ranges waiting to be fetched, the next one is at the end.
+        let mut ranges: Vec<_> = (from_block..=until_block)
+            .step_by(usize::try_from(max_range).expect("Range should fit in usize."))
+            .map(|from| (from, until_block.min(from.saturating_add(max_range - 1))))
+            .collect();
+        ranges.reverse();
+        let mut events = Vec::new();
+        while let Some((from, until)) = ranges.pop() {
+            let range_filter = filter.clone().from_block(from).to_block(until);
+            match self.contract.provider().get_logs(&range_filter).await {
+                Ok(logs) => {
+                    for log in logs {
+                        events.push(parse_event(log)?);
+                    }
+                }
+                Err(RpcError::ErrorResp(err)) if from < until => {
+                    let middle = from + (until - from) / 2;
+                    debug!(
+                        "Failed to fetch the logs of L1 blocks {from} to {until}: {err}. Fetching \
+                         {from} to {middle} and {} to {until} separately.",
+                        middle + 1
+                    );
+                    ranges.push((middle + 1, until));
+                    ranges.push((from, middle));
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(events)
     }
 
     async fn latest_l1_block_number(&self, finality: u64) -> EthereumBaseLayerResult<Option<u64>> {
         Ok(self.contract.provider().get_block_number().await?.checked_sub(finality))
     }
+
+    async fn l1_block_reference(
+        &self,
+        block_number: u64,
+    ) -> EthereumBaseLayerResult<Option<L1BlockReference>> {
+        let hydrate = false;
+        let Some(block) = self
+            .contract
+            .provider()
+            .get_block_by_number(block_number.into(), hydrate)
+            .await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(L1BlockReference {
+            number: block_number,
+            hash: L1BlockHash(block.header.hash.0),
+            parent_hash: L1BlockHash(block.header.parent_hash.0),
+        }))
+    }
+
+    /// Blocks from before the introduction of the base fee (EIP-1559) or of blobs (EIP-4844) have
+    /// a zero price for it.
+    async fn get_price_sample(
+        &self,
+        block_number: u64,
+    ) -> EthereumBaseLayerResult<Option<PriceSample>> {
+        // Only the header is needed, so the transactions are fetched as hashes.
+        let hydrate = false;
+        let Some(block) = self
+            .contract
+            .provider()
+            .get_block_by_number(block_number.into(), hydrate)
+            .await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(PriceSample {
+            timestamp: block.header.timestamp,
+            base_fee_per_gas: block.header.base_fee_per_gas.unwrap_or_default(),
+            blob_fee: block.header.blob_fee().unwrap_or_default(),
+        }))
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -104,13 +200,122 @@ pub enum EthereumBaseLayerError {
     #[error(transparent)]
     Serde(#[from] serde_json::Error),
     #[error(transparent)]
+    StarknetApiError(#[from] StarknetApiError),
+    #[error(transparent)]
     TypeError(#[from] alloy_sol_types::Error),
+    #[error("Unhandled L1 event with signature {0}.")]
+    UnhandledL1Event(B256),
+    #[error("Unknown event identifier: {0}.")]
+    UnknownEventIdentifier(String),
+}
+
+fn event_signature(identifier: &str) -> EthereumBaseLayerResult<B256> {
+    match identifier {
+        LOG_MESSAGE_TO_L2_EVENT_IDENTIFIER => Ok(Starknet::LogMessageToL2::SIGNATURE_HASH),
+        CONSUMED_MESSAGE_TO_L2_EVENT_IDENTIFIER => {
+            Ok(Starknet::ConsumedMessageToL2::SIGNATURE_HASH)
+        }
+        MESSAGE_TO_L2_CANCELLATION_STARTED_EVENT_IDENTIFIER => {
+            Ok(Starknet::MessageToL2CancellationStarted::SIGNATURE_HASH)
+        }
+        MESSAGE_TO_L2_CANCELED_EVENT_IDENTIFIER => {
+            Ok(Starknet::MessageToL2Canceled::SIGNATURE_HASH)
+        }
+        _ => Err(EthereumBaseLayerError::UnknownEventIdentifier(identifier.to_string())),
+    }
+}
+
+fn parse_event(log: Log) -> EthereumBaseLayerResult<L1Event> {
+    let validate = true;
+    // Only logs of pending blocks have no transaction hash, and these are never fetched.
+    let l1_tx_hash = L1TransactionHash(log.transaction_hash.unwrap_or_default().0);
+    let log = log.inner;
+    let signature = log.topics().first().copied().unwrap_or_default();
+    match signature {
+        Starknet::LogMessageToL2::SIGNATURE_HASH => {
+            let event = Starknet::LogMessageToL2::decode_log_data(&log.data, validate)?;
+            let fee = u128::try_from(event.fee)
+                .map_err(|_| StarknetApiError::OutOfRange { string: event.fee.to_string() })?;
+            let event_data = EventData::try_from_message(
+                event.fromAddress,
+                event.toAddress,
+                event.selector,
+                &event.payload,
+                event.nonce,
+            )?;
+            Ok(L1Event::LogMessageToL2 { tx: event_data.into(), fee: Fee(fee), l1_tx_hash })
+        }
+        Starknet::ConsumedMessageToL2::SIGNATURE_HASH => {
+            let event = Starknet::ConsumedMessageToL2::decode_log_data(&log.data, validate)?;
+            Ok(L1Event::ConsumedMessageToL2(EventData::try_from_message(
+                event.fromAddress,
+                event.toAddress,
+                event.selector,
+                &event.payload,
+                event.nonce,
+            )?))
+        }
+        Starknet::MessageToL2CancellationStarted::SIGNATURE_HASH => {
+            let event =
+                Starknet::MessageToL2CancellationStarted::decode_log_data(&log.data, validate)?;
+            Ok(L1Event::MessageToL2CancellationStarted(EventData::try_from_message(
+                event.fromAddress,
+                event.toAddress,
+                event.selector,
+                &event.payload,
+                event.nonce,
+            )?))
+        }
+        Starknet::MessageToL2Canceled::SIGNATURE_HASH => {
+            let event = Starknet::MessageToL2Canceled::decode_log_data(&log.data, validate)?;
+            Ok(L1Event::MessageToL2Canceled(EventData::try_from_message(
+                event.fromAddress,
+                event.toAddress,
+                event.selector,
+                &event.payload,
+                event.nonce,
+            )?))
+        }
+        _ => Err(EthereumBaseLayerError::UnhandledL1Event(signature)),
+    }
+}
+
+impl EventData {
+    fn try_from_message(
+        from_address: EthereumContractAddress,
+        to_address: U256,
+        selector: U256,
+        payload: &[U256],
+        nonce: U256,
+    ) -> EthereumBaseLayerResult<Self> {
+        Ok(Self {
+            from_address: EthAddress::try_from(StarkHash::from_bytes_be_slice(
+                from_address.as_slice(),
+            ))?,
+            to_address: ContractAddress::try_from(felt_from_u256(to_address)?)?,
+            entry_point_selector: EntryPointSelector(felt_from_u256(selector)?),
+            payload: Calldata(
+                payload.iter().copied().map(felt_from_u256).collect::<Result<Vec<_>, _>>()?.into(),
+            ),
+            nonce: Nonce(felt_from_u256(nonce)?),
+        })
+    }
+}
+
+fn felt_from_u256(num: U256) -> Result<StarkHash, StarknetApiError> {
+    let felt = StarkHash::from_bytes_be(&num.to_be_bytes());
+    // Values which are not smaller than the field prime are reduced.
+    if U256::from_be_bytes(felt.to_bytes_be()) != num {
+        return Err(StarknetApiError::OutOfRange { string: num.to_string() });
+    }
+    Ok(felt)
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct EthereumBaseLayerConfig {
     pub node_url: Url,
     pub starknet_contract_address: EthereumContractAddress,
+    pub max_logs_block_range: u64,
 }
 
 impl SerializeConfig for EthereumBaseLayerConfig {
@@ -128,6 +333,12 @@ impl SerializeConfig for EthereumBaseLayerConfig {
                 "Starknet contract address in ethereum.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "max_logs_block_range",
+                &self.max_logs_block_range,
+                "Maximal number of Ethereum blocks to fetch logs from in a single request.",
+                ParamPrivacyInput::Public,
+            ),
         ])
     }
 }
@@ -140,6 +351,7 @@ impl Default for EthereumBaseLayerConfig {
         Self {
             node_url: "https://mainnet.infura.io/v3/<your_api_key>".parse().unwrap(),
             starknet_contract_address,
+            max_logs_block_range: 2000,
         }
     }
 }
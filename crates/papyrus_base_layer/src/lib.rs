@@ -1,10 +1,15 @@
+use std::iter;
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockHashAndNumber;
 use starknet_api::core::{ContractAddress, EntryPointSelector, EthAddress, Nonce};
+use starknet_api::hash::StarkHash;
 use starknet_api::transaction::fields::{Calldata, Fee};
-use starknet_api::transaction::L1HandlerTransaction;
+use starknet_api::transaction::{L1HandlerTransaction, L1TransactionHash};
 
+pub mod constants;
 pub mod ethereum_base_layer_contract;
 
 #[cfg(any(feature = "testing", test))]
@@ -34,13 +39,46 @@ pub trait BaseLayerContract {
     ) -> Result<Vec<L1Event>, Self::Error>;
 
     async fn latest_l1_block_number(&self, finality: u64) -> Result<Option<u64>, Self::Error>;
+
+    /// Get the hash and parent hash of an L1 block, or None if it doesn't exist yet.
+    async fn l1_block_reference(
+        &self,
+        block_number: u64,
+    ) -> Result<Option<L1BlockReference>, Self::Error>;
+
+    /// Get the gas prices of an L1 block, or None if it doesn't exist yet.
+    async fn get_price_sample(&self, block_number: u64)
+    -> Result<Option<PriceSample>, Self::Error>;
+}
+
+/// The gas prices of an L1 block, in wei.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PriceSample {
+    pub timestamp: u64,
+    pub base_fee_per_gas: u128,
+    pub blob_fee: u128,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct L1BlockHash(pub [u8; 32]);
+
+/// Identifies an L1 block and links it to its parent, which allows detecting L1 reorgs.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct L1BlockReference {
+    pub number: u64,
+    pub hash: L1BlockHash,
+    pub parent_hash: L1BlockHash,
 }
 
 /// Wraps Starknet L1 events with Starknet API types.
+///
+/// The payload of [`EventData`] is the payload of the message as sent on L1, i.e., without the
+/// sender address which L1 handler transactions prepend to their calldata. A message sent to L2 is
+/// accompanied by the hash of the L1 transaction that sent it.
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum L1Event {
     ConsumedMessageToL2(EventData),
-    LogMessageToL2 { tx: L1HandlerTransaction, fee: Fee },
+    LogMessageToL2 { tx: L1HandlerTransaction, fee: Fee, l1_tx_hash: L1TransactionHash },
     MessageToL2CancellationStarted(EventData),
     MessageToL2Canceled(EventData),
 }
@@ -54,3 +92,20 @@ pub struct EventData {
     pub payload: Calldata,
     pub nonce: Nonce,
 }
+
+/// Recovers the L1 handler transaction of a message; its calldata is the message payload prefixed
+/// with the sender address.
+impl From<EventData> for L1HandlerTransaction {
+    fn from(event_data: EventData) -> Self {
+        let EventData { from_address, to_address, entry_point_selector, payload, nonce } =
+            event_data;
+        let calldata = iter::once(StarkHash::from(from_address)).chain(payload.0.iter().copied());
+        L1HandlerTransaction {
+            version: L1HandlerTransaction::VERSION,
+            nonce,
+            contract_address: to_address,
+            entry_point_selector,
+            calldata: Calldata(Arc::new(calldata.collect())),
+        }
+    }
+}
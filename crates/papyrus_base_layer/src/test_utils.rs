@@ -1,10 +1,16 @@
 use std::fs::File;
 use std::process::Command;
+use std::sync::Mutex;
 
 pub(crate) use alloy_primitives::Address as EthereumContractAddress;
+use async_trait::async_trait;
 use ethers::utils::{Ganache, GanacheInstance};
+use starknet_api::block::BlockHashAndNumber;
 use tar::Archive;
 use tempfile::{tempdir, TempDir};
+use thiserror::Error;
+
+use crate::{BaseLayerContract, L1BlockHash, L1BlockReference, L1Event, PriceSample};
 
 type TestEthereumNodeHandle = (GanacheInstance, TempDir);
 
@@ -61,3 +67,132 @@ pub fn get_test_ethereum_node() -> (TestEthereumNodeHandle, EthereumContractAddr
 
     ((ganache, ganache_db), SN_CONTRACT_ADDR.to_string().parse().unwrap())
 }
+
+#[derive(Debug, Error)]
+#[error("Fake base layer is unavailable.")]
+pub struct FakeBaseLayerError;
+
+/// An in-memory stand-in for the Starknet core contract on L1, holding the events and the gas
+/// prices of each L1 block.
+#[derive(Debug, Default)]
+pub struct FakeBaseLayerContract {
+    state: Mutex<FakeBaseLayerState>,
+}
+
+#[derive(Debug, Default)]
+struct FakeBaseLayerState {
+    blocks: Vec<FakeL1Block>,
+    n_created_blocks: u64,
+    unavailable: bool,
+}
+
+#[derive(Debug)]
+struct FakeL1Block {
+    reference: L1BlockReference,
+    events: Vec<L1Event>,
+    price_sample: PriceSample,
+}
+
+impl FakeBaseLayerContract {
+    /// Adds a block with the given events and zero gas prices.
+    pub fn add_block(&self, events: impl IntoIterator<Item = L1Event>) {
+        self.push_block(events.into_iter().collect(), 0, 0);
+    }
+
+    /// Adds a block without events and with the given gas prices.
+    pub fn add_block_with_prices(&self, base_fee_per_gas: u128, blob_fee: u128) {
+        self.push_block(Vec::new(), base_fee_per_gas, blob_fee);
+    }
+
+    /// Drops all the blocks from `block_number` onwards.
+    pub fn reorg(&self, block_number: usize) {
+        self.state.lock().unwrap().blocks.truncate(block_number);
+    }
+
+    /// While unavailable, all the queries for L1 blocks fail.
+    pub fn set_unavailable(&self, unavailable: bool) {
+        self.state.lock().unwrap().unavailable = unavailable;
+    }
+
+    fn push_block(&self, events: Vec<L1Event>, base_fee_per_gas: u128, blob_fee: u128) {
+        let mut state = self.state.lock().unwrap();
+        // Blocks created on different forks of L1 have different hashes.
+        state.n_created_blocks += 1;
+        let mut hash = [0; 32];
+        hash[..8].copy_from_slice(&state.n_created_blocks.to_be_bytes());
+        let number = u64::try_from(state.blocks.len()).unwrap();
+        let reference = L1BlockReference {
+            number,
+            hash: L1BlockHash(hash),
+            parent_hash: state.blocks.last().map(|block| block.reference.hash).unwrap_or_default(),
+        };
+        let price_sample = PriceSample { timestamp: number, base_fee_per_gas, blob_fee };
+        state.blocks.push(FakeL1Block { reference, events, price_sample });
+    }
+
+    // Runs the given function on the blocks, or fails if the base layer is unavailable.
+    fn with_blocks<T>(&self, f: impl FnOnce(&[FakeL1Block]) -> T) -> Result<T, FakeBaseLayerError> {
+        let state = self.state.lock().unwrap();
+        if state.unavailable {
+            return Err(FakeBaseLayerError);
+        }
+        Ok(f(&state.blocks))
+    }
+
+    fn get_block<T>(
+        &self,
+        block_number: u64,
+        f: impl FnOnce(&FakeL1Block) -> T,
+    ) -> Result<Option<T>, FakeBaseLayerError> {
+        let index = usize::try_from(block_number).unwrap();
+        self.with_blocks(|blocks| blocks.get(index).map(f))
+    }
+}
+
+#[async_trait]
+impl BaseLayerContract for FakeBaseLayerContract {
+    type Error = FakeBaseLayerError;
+
+    async fn latest_proved_block(
+        &self,
+        _finality: u64,
+    ) -> Result<Option<BlockHashAndNumber>, Self::Error> {
+        unimplemented!("The fake base layer doesn't hold Starknet state updates.")
+    }
+
+    async fn events(
+        &self,
+        from_block: u64,
+        until_block: u64,
+        _event_identifiers: &[&str],
+    ) -> Result<Vec<L1Event>, Self::Error> {
+        self.with_blocks(|blocks| {
+            blocks
+                .iter()
+                .filter(|block| (from_block..=until_block).contains(&block.reference.number))
+                .flat_map(|block| block.events.clone())
+                .collect()
+        })
+    }
+
+    async fn latest_l1_block_number(&self, finality: u64) -> Result<Option<u64>, Self::Error> {
+        self.with_blocks(|blocks| {
+            let n_blocks = u64::try_from(blocks.len()).unwrap();
+            n_blocks.checked_sub(1).and_then(|block_number| block_number.checked_sub(finality))
+        })
+    }
+
+    async fn l1_block_reference(
+        &self,
+        block_number: u64,
+    ) -> Result<Option<L1BlockReference>, Self::Error> {
+        self.get_block(block_number, |block| block.reference)
+    }
+
+    async fn get_price_sample(
+        &self,
+        block_number: u64,
+    ) -> Result<Option<PriceSample>, Self::Error> {
+        self.get_block(block_number, |block| block.price_sample)
+    }
+}
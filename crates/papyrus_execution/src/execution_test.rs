@@ -3,17 +3,25 @@
 use std::sync::Arc;
 
 use assert_matches::assert_matches;
-use blockifier::execution::call_info::Retdata;
+use blockifier::execution::call_info::{CallExecution, Retdata};
 use blockifier::execution::errors::ConstructorEntryPointExecutionError;
 use blockifier::execution::stack_trace::gen_tx_execution_error_trace;
 use blockifier::transaction::errors::TransactionExecutionError as BlockifierTransactionExecutionError;
 use blockifier::versioned_constants::VersionedConstants;
 use indexmap::indexmap;
+use papyrus_common::state::StorageEntry;
 use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageReader;
 use pretty_assertions::assert_eq;
 use starknet_api::abi::abi_utils::get_storage_var_address;
-use starknet_api::block::{BlockNumber, StarknetVersion};
-use starknet_api::core::{ChainId, CompiledClassHash, EntryPointSelector};
+use starknet_api::block::{
+    BlockNumber,
+    BlockTimestamp,
+    GasPrice,
+    GasPricePerToken,
+    StarknetVersion,
+};
+use starknet_api::core::{ChainId, CompiledClassHash, ContractAddress, EntryPointSelector};
 use starknet_api::state::{StateNumber, ThinStateDiff};
 use starknet_api::transaction::fields::{Calldata, Fee};
 use starknet_api::{calldata, class_hash, contract_address, felt, nonce};
@@ -21,8 +29,11 @@ use starknet_types_core::felt::Felt;
 
 use crate::execution_utils::selector_from_name;
 use crate::objects::{
+    BlockOverrides,
+    ContractOverride,
     DeclareTransactionTrace,
     DeployAccountTransactionTrace,
+    ExecutionOverrides,
     FeeEstimation,
     FunctionInvocationResult,
     InvokeTransactionTrace,
@@ -37,6 +48,7 @@ use crate::test_utils::{
     ACCOUNT_ADDRESS,
     ACCOUNT_CLASS_HASH,
     ACCOUNT_INITIAL_BALANCE,
+    BLOCK_TIMESTAMP,
     CHAIN_ID,
     CONTRACT_ADDRESS,
     DEPRECATED_CONTRACT_ADDRESS,
@@ -49,8 +61,10 @@ use crate::testing_instances::get_test_execution_config;
 use crate::{
     estimate_fee,
     execute_call,
+    simulate_transactions,
     ExecutableTransactionInput,
     ExecutionError,
+    ExecutionResult,
     FeeEstimationResult,
     RevertedTransaction,
 };
@@ -75,6 +89,7 @@ fn execute_call_cairo0() {
         selector_from_name("without_arg"),
         Calldata::default(),
         &get_test_execution_config(),
+        &ExecutionOverrides::default(),
         true,
     )
     .unwrap()
@@ -92,6 +107,7 @@ fn execute_call_cairo0() {
         selector_from_name("with_arg"),
         Calldata(Arc::new(vec![Felt::from(25u128)])),
         &get_test_execution_config(),
+        &ExecutionOverrides::default(),
         true,
     )
     .unwrap()
@@ -109,6 +125,7 @@ fn execute_call_cairo0() {
         selector_from_name("return_result"),
         Calldata(Arc::new(vec![Felt::from(123u128)])),
         &get_test_execution_config(),
+        &ExecutionOverrides::default(),
         true,
     )
     .unwrap()
@@ -126,6 +143,7 @@ fn execute_call_cairo0() {
         selector_from_name("test_storage_read_write"),
         Calldata(Arc::new(vec![Felt::from(123u128), Felt::from(456u128)])),
         &get_test_execution_config(),
+        &ExecutionOverrides::default(),
         true,
     )
     .unwrap()
@@ -154,6 +172,7 @@ fn execute_call_cairo1() {
         selector_from_name("test_storage_read_write"),
         calldata,
         &get_test_execution_config(),
+        &ExecutionOverrides::default(),
         true,
     )
     .unwrap()
@@ -162,6 +181,108 @@ fn execute_call_cairo1() {
     assert_eq!(retdata, Retdata(vec![value]));
 }
 
+#[test]
+fn execute_call_with_state_overrides() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    // Override a storage slot of the fee token directly.
+    let sequencer_balance_key =
+        get_storage_var_address("ERC20_balances", &[*SEQUENCER_ADDRESS.0.key()]);
+    let overrides = ExecutionOverrides {
+        state: vec![ContractOverride {
+            address: *TEST_ERC20_CONTRACT_ADDRESS,
+            storage: vec![StorageEntry { key: sequencer_balance_key, value: felt!(7_u8) }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let retdata = execute_call_with_overrides(
+        storage_reader.clone(),
+        *TEST_ERC20_CONTRACT_ADDRESS,
+        "balanceOf",
+        calldata![*SEQUENCER_ADDRESS.0.key()],
+        &overrides,
+    )
+    .unwrap()
+    .retdata;
+    assert_eq!(retdata, Retdata(vec![felt!(7_u8), felt!(0_u8)]));
+
+    // Override the balance using the shortcut. The balance is split into the low and high 128 bits.
+    let balance = Felt::from(u128::MAX) + felt!(5_u8);
+    let overrides = ExecutionOverrides {
+        state: vec![ContractOverride {
+            address: *ACCOUNT_ADDRESS,
+            balance: Some(balance),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let retdata = execute_call_with_overrides(
+        storage_reader.clone(),
+        *TEST_ERC20_CONTRACT_ADDRESS,
+        "balanceOf",
+        calldata![*ACCOUNT_ADDRESS.0.key()],
+        &overrides,
+    )
+    .unwrap()
+    .retdata;
+    assert_eq!(retdata, Retdata(vec![felt!(4_u8), felt!(1_u8)]));
+
+    // Call an address without a deployed contract by overriding its class hash.
+    let undeployed_address = contract_address!("0x999");
+    let result = execute_call_with_overrides(
+        storage_reader.clone(),
+        undeployed_address,
+        "return_result",
+        calldata![felt!(123_u8)],
+        &ExecutionOverrides::default(),
+    );
+    assert_matches!(result, Err(ExecutionError::ContractNotFound { .. }));
+
+    let overrides = ExecutionOverrides {
+        state: vec![ContractOverride {
+            address: undeployed_address,
+            class_hash: Some(class_hash!("0x1")),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let retdata = execute_call_with_overrides(
+        storage_reader,
+        undeployed_address,
+        "return_result",
+        calldata![felt!(123_u8)],
+        &overrides,
+    )
+    .unwrap()
+    .retdata;
+    assert_eq!(retdata, Retdata(vec![felt!(123_u8)]));
+}
+
+#[allow(clippy::result_large_err)]
+fn execute_call_with_overrides(
+    storage_reader: StorageReader,
+    contract_address: ContractAddress,
+    entry_point_name: &str,
+    calldata: Calldata,
+    overrides: &ExecutionOverrides,
+) -> ExecutionResult<CallExecution> {
+    execute_call(
+        storage_reader,
+        None,
+        &CHAIN_ID,
+        StateNumber::unchecked_right_after_block(BlockNumber(0)),
+        BlockNumber(0),
+        &contract_address,
+        selector_from_name(entry_point_name),
+        calldata,
+        &get_test_execution_config(),
+        overrides,
+        true,
+    )
+}
+
 // TODO(yair): Compare to the expected fee instead of asserting that it is not zero (all
 // estimate_fee tests).
 #[test]
@@ -237,7 +358,89 @@ fn estimate_fee_reverted() {
     assert_matches!(failed_estimation, RevertedTransaction { index: 1, revert_reason: _ })
 }
 
+#[test]
+fn estimate_fee_with_block_overrides() {
+    let tx = TxsScenarioBuilder::default()
+        .invoke_deprecated(*ACCOUNT_ADDRESS, *DEPRECATED_CONTRACT_ADDRESS, None, false)
+        .collect();
+    let overridden_gas_price = GasPricePerToken {
+        price_in_wei: GasPrice(GAS_PRICE.price_in_wei.0 * 2),
+        price_in_fri: GAS_PRICE.price_in_fri,
+    };
+    let overrides = ExecutionOverrides {
+        block: BlockOverrides {
+            l1_gas_price: Some(overridden_gas_price),
+            timestamp: Some(BlockTimestamp(BLOCK_TIMESTAMP.0 + 1000)),
+            block_number: Some(BlockNumber(100)),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let fees = estimate_fees(tx.clone()).expect("Fee estimation should succeed.");
+    let overridden_fees =
+        estimate_fees_with_overrides(tx, &overrides).expect("Fee estimation should succeed.");
+    for (fee, overridden_fee) in fees.into_iter().zip(overridden_fees) {
+        assert_eq!(overridden_fee.l1_gas_price, overridden_gas_price.price_in_wei);
+        assert!(overridden_fee.overall_fee > fee.overall_fee);
+    }
+}
+
+#[test]
+fn simulate_with_nonce_override() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    // The account's nonce in the storage is 0.
+    let nonce = nonce!(5_u128);
+    let tx = TxsScenarioBuilder::default()
+        .invoke_deprecated(*ACCOUNT_ADDRESS, *DEPRECATED_CONTRACT_ADDRESS, Some(nonce), false)
+        .collect();
+    let simulate = |overrides: &ExecutionOverrides| {
+        simulate_transactions(
+            tx.clone(),
+            None,
+            &CHAIN_ID,
+            storage_reader.clone(),
+            None,
+            StateNumber::unchecked_right_after_block(BlockNumber(0)),
+            BlockNumber(1),
+            &get_test_execution_config(),
+            overrides,
+            true,
+            true,
+            true,
+        )
+    };
+
+    assert_matches!(
+        simulate(&ExecutionOverrides::default()),
+        Err(ExecutionError::TransactionExecutionError { transaction_index: 0, .. })
+    );
+
+    let overrides = ExecutionOverrides {
+        state: vec![ContractOverride {
+            address: *ACCOUNT_ADDRESS,
+            nonce: Some(nonce),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let simulation_results = simulate(&overrides).expect("Simulation should succeed.");
+    assert_eq!(
+        simulation_results[0].induced_state_diff.nonces,
+        indexmap! {*ACCOUNT_ADDRESS => nonce!(6_u128)}
+    );
+}
+
 fn estimate_fees(txs: Vec<ExecutableTransactionInput>) -> FeeEstimationResult {
+    estimate_fees_with_overrides(txs, &ExecutionOverrides::default())
+}
+
+fn estimate_fees_with_overrides(
+    txs: Vec<ExecutableTransactionInput>,
+    overrides: &ExecutionOverrides,
+) -> FeeEstimationResult {
     let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
     prepare_storage(storage_writer);
 
@@ -249,6 +452,7 @@ fn estimate_fees(txs: Vec<ExecutableTransactionInput>) -> FeeEstimationResult {
         StateNumber::unchecked_right_after_block(BlockNumber(0)),
         BlockNumber(1),
         &get_test_execution_config(),
+        overrides,
         false,
         // TODO(yair): Add test for blob fee estimation.
         true,
@@ -19,7 +19,7 @@ pub mod testing_instances;
 
 pub mod objects;
 use std::cell::Cell;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, LazyLock};
 
 use blockifier::blockifier::block::{pre_process_block, validated_gas_prices};
@@ -31,7 +31,7 @@ use blockifier::execution::entry_point::{
     CallType as BlockifierCallType,
     EntryPointExecutionContext,
 };
-use blockifier::state::cached_state::CachedState;
+use blockifier::state::cached_state::{CachedState, StateMaps};
 use blockifier::transaction::account_transaction::ExecutionFlags;
 use blockifier::transaction::errors::TransactionExecutionError as BlockifierTransactionExecutionError;
 use blockifier::transaction::objects::{
@@ -45,12 +45,18 @@ use blockifier::versioned_constants::{VersionedConstants, VersionedConstantsErro
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use cairo_vm::types::builtin_name::BuiltinName;
 use execution_utils::{get_trace_constructor, induced_state_diff};
-use objects::{PriceUnit, TransactionSimulationOutput};
+use objects::{
+    ContractOverride,
+    ExecutionOverrides,
+    PriceUnit,
+    TransactionSimulationOutput,
+};
 use papyrus_config::dumping::{ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_storage::header::HeaderStorageReader;
 use papyrus_storage::{StorageError, StorageReader};
 use serde::{Deserialize, Serialize};
+use starknet_api::abi::abi_utils::get_fee_token_var_address;
 use starknet_api::block::{
     BlockHashAndNumber,
     BlockInfo,
@@ -228,14 +234,22 @@ pub fn execute_call(
     entry_point_selector: EntryPointSelector,
     calldata: Calldata,
     execution_config: &ExecutionConfig,
+    overrides: &ExecutionOverrides,
     override_kzg_da_to_false: bool,
 ) -> ExecutionResult<CallExecution> {
-    verify_contract_exists(
-        *contract_address,
-        &storage_reader,
-        state_number,
-        maybe_pending_data.as_ref(),
-    )?;
+    // A contract may be called at an address with no deployed contract if its class hash is
+    // overridden.
+    let is_class_hash_overridden = overrides.state.iter().any(|contract_override| {
+        contract_override.address == *contract_address && contract_override.class_hash.is_some()
+    });
+    if !is_class_hash_overridden {
+        verify_contract_exists(
+            *contract_address,
+            &storage_reader,
+            state_number,
+            maybe_pending_data.as_ref(),
+        )?;
+    }
 
     // TODO(yair): check if this is the correct value.
     let mut remaining_gas = execution_config.default_initial_gas_cost;
@@ -262,9 +276,8 @@ pub fn execute_call(
         &mut cached_state,
         block_context_number,
         chain_id.clone(),
-        &storage_reader,
-        maybe_pending_data.as_ref(),
         execution_config,
+        overrides,
         override_kzg_da_to_false,
     )?;
     // TODO(yair): fix when supporting v3 transactions
@@ -310,6 +323,8 @@ fn verify_contract_exists(
     Ok(())
 }
 
+/// Creates the context of the block the execution runs in, reading it from the storage and the
+/// pending data of the given state, and applies the overrides to the block context and the state.
 // TODO(Dan, Yair): consider box large elements (because of BadDeclareTransaction) or use ID
 // instead.
 #[allow(clippy::result_large_err)]
@@ -317,12 +332,13 @@ fn create_block_context(
     cached_state: &mut CachedState<ExecutionStateReader>,
     block_context_number: BlockNumber,
     chain_id: ChainId,
-    storage_reader: &StorageReader,
-    maybe_pending_data: Option<&PendingData>,
     execution_config: &ExecutionConfig,
+    overrides: &ExecutionOverrides,
     // TODO(shahak): Remove this once we stop supporting rpc v0.6.
     override_kzg_da_to_false: bool,
 ) -> ExecutionResult<BlockContext> {
+    let storage_reader = cached_state.state.storage_reader.clone();
+    let block_overrides = &overrides.block;
     let (
         block_number,
         block_timestamp,
@@ -331,7 +347,7 @@ fn create_block_context(
         l2_gas_price,
         sequencer_address,
         l1_da_mode,
-    ) = match maybe_pending_data {
+    ) = match &cached_state.state.maybe_pending_data {
         Some(pending_data) => (
             block_context_number.unchecked_next(),
             pending_data.timestamp,
@@ -369,11 +385,15 @@ fn create_block_context(
         }
     };
 
+    let l1_gas_price = block_overrides.l1_gas_price.unwrap_or(l1_gas_price);
+    let l1_data_gas_price = block_overrides.l1_data_gas_price.unwrap_or(l1_data_gas_price);
+    let l2_gas_price = block_overrides.l2_gas_price.unwrap_or(l2_gas_price);
+
     let block_info = BlockInfo {
-        block_timestamp,
+        block_timestamp: block_overrides.timestamp.unwrap_or(block_timestamp),
         sequencer_address: sequencer_address.0,
         use_kzg_da,
-        block_number,
+        block_number: block_overrides.block_number.unwrap_or(block_number),
         // TODO(yair): What to do about blocks pre 0.13.1 where the data gas price were 0?
         gas_prices: validated_gas_prices(
             NonzeroGasPrice::new(l1_gas_price.price_in_wei).unwrap_or(NonzeroGasPrice::MIN),
@@ -403,7 +423,9 @@ fn create_block_context(
         versioned_constants.clone(),
         BouncerConfig::max(),
     );
-    let next_block_number = block_context.block_info().block_number;
+    // The block hash table is updated according to the executed block and not the overridden
+    // block number, since the hash of 10 blocks ago is only known for the former.
+    let next_block_number = block_number;
 
     pre_process_block(
         cached_state,
@@ -411,9 +433,54 @@ fn create_block_context(
         next_block_number,
         &versioned_constants.os_constants,
     )?;
+    apply_state_overrides(cached_state, &overrides.state, execution_config)?;
     Ok(block_context)
 }
 
+/// Writes the state overrides into the cache of the given state, so they shadow the values read
+/// from the storage.
+// TODO(Dan, Yair): consider box large elements (because of BadDeclareTransaction) or use ID
+// instead.
+#[allow(clippy::result_large_err)]
+fn apply_state_overrides(
+    cached_state: &mut CachedState<ExecutionStateReader>,
+    state_overrides: &[ContractOverride],
+    execution_config: &ExecutionConfig,
+) -> ExecutionResult<()> {
+    let mut state_maps = StateMaps::default();
+    for contract_override in state_overrides {
+        let address = contract_override.address;
+        if let Some(nonce) = contract_override.nonce {
+            state_maps.nonces.insert(address, nonce);
+        }
+        if let Some(class_hash) = contract_override.class_hash {
+            state_maps.class_hashes.insert(address, class_hash);
+        }
+        for entry in &contract_override.storage {
+            state_maps.storage.insert((address, entry.key), entry.value);
+        }
+        if let Some(balance) = contract_override.balance {
+            // The fee tokens store the balance as a Uint256 in two consecutive storage cells.
+            let balance_bytes = balance.to_bytes_be();
+            let balance_high = Felt::from_bytes_be_slice(&balance_bytes[..16]);
+            let balance_low = Felt::from_bytes_be_slice(&balance_bytes[16..]);
+            let low_key = get_fee_token_var_address(address);
+            let high_key = low_key
+                .next_storage_key()
+                .map_err(blockifier::state::errors::StateError::StarknetApiError)?;
+            for fee_token_address in [
+                execution_config.eth_fee_contract_address,
+                execution_config.strk_fee_contract_address,
+            ] {
+                state_maps.storage.insert((fee_token_address, low_key), balance_low);
+                state_maps.storage.insert((fee_token_address, high_key), balance_high);
+            }
+        }
+    }
+    cached_state.update_cache(&state_maps, HashMap::new());
+    Ok(())
+}
+
 /// The size of the json string representing the abi of a class or deprecated class.
 pub type AbiSize = usize;
 
@@ -623,6 +690,7 @@ pub fn estimate_fee(
     state_number: StateNumber,
     block_context_block_number: BlockNumber,
     execution_config: &ExecutionConfig,
+    overrides: &ExecutionOverrides,
     validate: bool,
     override_kzg_da_to_false: bool,
 ) -> ExecutionResult<FeeEstimationResult> {
@@ -635,6 +703,7 @@ pub fn estimate_fee(
         state_number,
         block_context_block_number,
         execution_config,
+        overrides,
         false,
         validate,
         override_kzg_da_to_false,
@@ -676,6 +745,7 @@ fn execute_transactions(
     state_number: StateNumber,
     block_context_block_number: BlockNumber,
     execution_config: &ExecutionConfig,
+    overrides: &ExecutionOverrides,
     charge_fee: bool,
     validate: bool,
     override_kzg_da_to_false: bool,
@@ -692,9 +762,8 @@ fn execute_transactions(
         &mut cached_state,
         block_context_block_number,
         chain_id.clone(),
-        &storage_reader,
-        maybe_pending_data.as_ref(),
         execution_config,
+        overrides,
         override_kzg_da_to_false,
     )?;
 
@@ -973,6 +1042,7 @@ pub fn simulate_transactions(
     state_number: StateNumber,
     block_context_block_number: BlockNumber,
     execution_config: &ExecutionConfig,
+    overrides: &ExecutionOverrides,
     charge_fee: bool,
     validate: bool,
     override_kzg_da_to_false: bool,
@@ -987,6 +1057,7 @@ pub fn simulate_transactions(
         state_number,
         block_context_block_number,
         execution_config,
+        overrides,
         charge_fee,
         validate,
         override_kzg_da_to_false,
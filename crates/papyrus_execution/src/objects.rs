@@ -23,7 +23,7 @@ use papyrus_common::state::{
     StorageEntry,
 };
 use serde::{Deserialize, Serialize};
-use starknet_api::block::{BlockTimestamp, FeeType, GasPrice, GasPricePerToken};
+use starknet_api::block::{BlockNumber, BlockTimestamp, FeeType, GasPrice, GasPricePerToken};
 use starknet_api::contract_class::EntryPointType;
 use starknet_api::core::{
     ClassHash,
@@ -515,6 +515,55 @@ pub struct PendingData {
     pub classes: PendingClasses,
 }
 
+/// Overrides applied on top of the state and block context before executing.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ExecutionOverrides {
+    /// Overrides of the state of specific contracts.
+    pub state: Vec<ContractOverride>,
+    /// Overrides of the block context the execution runs in.
+    pub block: BlockOverrides,
+}
+
+/// Overrides of the state of a single contract. Fields that are not set keep their stored value.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ContractOverride {
+    /// The address of the overridden contract.
+    pub address: ContractAddress,
+    /// The nonce to set for the contract.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<Nonce>,
+    /// The class hash to set at the address. Allows calling an address with no deployed contract.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class_hash: Option<ClassHash>,
+    /// The balance to set for the contract in both the ETH and the STRK fee tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance: Option<Felt>,
+    /// Storage slots to set for the contract.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub storage: Vec<StorageEntry>,
+}
+
+/// Overrides of the block context. Fields that are not set keep the value of the executed block.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BlockOverrides {
+    /// The timestamp of the block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<BlockTimestamp>,
+    /// The number of the block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<BlockNumber>,
+    /// The L1 gas price of the block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub l1_gas_price: Option<GasPricePerToken>,
+    /// The L1 data gas price of the block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub l1_data_gas_price: Option<GasPricePerToken>,
+    /// The L2 gas price of the block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub l2_gas_price: Option<GasPricePerToken>,
+}
+
 /// The unit of the fee.
 #[derive(
     Debug, Default, Clone, Copy, Eq, Hash, PartialEq, Deserialize, Serialize, PartialOrd, Ord,
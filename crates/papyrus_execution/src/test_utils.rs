@@ -47,7 +47,7 @@ use starknet_api::{calldata, class_hash, contract_address, felt, nonce};
 use starknet_types_core::felt::Felt;
 
 use crate::execution_utils::selector_from_name;
-use crate::objects::{PendingData, TransactionSimulationOutput};
+use crate::objects::{ExecutionOverrides, PendingData, TransactionSimulationOutput};
 use crate::testing_instances::get_test_execution_config;
 use crate::{simulate_transactions, ExecutableTransactionInput, OnlyQuery, SierraSize};
 
@@ -218,6 +218,7 @@ pub fn execute_simulate_transactions(
         StateNumber::unchecked_right_after_block(BlockNumber(0)),
         BlockNumber(1),
         &get_test_execution_config(),
+        &ExecutionOverrides::default(),
         charge_fee,
         validate,
         // TODO: Consider testing without overriding DA (It's already tested in the RPC)
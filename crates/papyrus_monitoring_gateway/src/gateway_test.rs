@@ -1,4 +1,5 @@
 use std::net::{SocketAddr, TcpListener};
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use axum::body::Body;
@@ -21,6 +22,7 @@ const PUBLIC_TEST_CONFIG_PRESENTATION: &str = "public_general_config_presentatio
 const SECRET: &str = "abcd";
 const TEST_VERSION: &str = "1.2.3-dev";
 const TEST_PEER_ID: &str = "peer_id";
+const TEST_STORAGE_BACKUP_DIR: &str = "./backup";
 
 // TODO(dan): consider using a proper fixture.
 fn setup_app() -> Router {
@@ -34,6 +36,7 @@ fn setup_app() -> Router {
         SECRET.to_string(),
         None,
         TEST_PEER_ID.to_string(),
+        PathBuf::from(TEST_STORAGE_BACKUP_DIR),
     )
 }
 
@@ -81,6 +84,52 @@ async fn mmap_files_stats() {
     assert!(!body["deprecated_contract_class"].is_null());
 }
 
+async fn request_storage_backup(backup_dir: &std::path::Path, secret: &str) -> Response {
+    let ((storage_reader, _), _temp_dir) = test_utils::get_test_storage();
+    let app = app(
+        String::from("https://default_url"),
+        storage_reader,
+        TEST_VERSION,
+        serde_json::Value::default(),
+        serde_json::Value::default(),
+        SECRET.to_string(),
+        None,
+        TEST_PEER_ID.to_string(),
+        backup_dir.to_path_buf(),
+    );
+    app.oneshot(
+        Request::builder()
+            .method("POST")
+            .uri(format!("/{MONITORING_PREFIX}/storageBackup/{secret}").as_str())
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn storage_backup() {
+    let backup_temp_dir = tempfile::tempdir().unwrap();
+    let response = request_storage_backup(backup_temp_dir.path(), SECRET).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let backup_dir: PathBuf = serde_json::from_slice(&body).unwrap();
+    assert!(backup_dir.starts_with(backup_temp_dir.path()));
+    assert!(backup_dir.join("mdbx.dat").exists());
+}
+
+#[tokio::test]
+async fn storage_backup_with_wrong_secret() {
+    let backup_temp_dir = tempfile::tempdir().unwrap();
+    let response = request_storage_backup(backup_temp_dir.path(), "wrong_secret").await;
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(std::fs::read_dir(backup_temp_dir.path()).unwrap().count(), 0);
+}
+
 #[tokio::test]
 async fn version() {
     let app = setup_app();
@@ -176,6 +225,7 @@ async fn with_metrics() {
         String::new(),
         Some(prometheus_handle),
         TEST_PEER_ID.to_string(),
+        PathBuf::from(TEST_STORAGE_BACKUP_DIR),
     );
 
     // Register a metric.
@@ -7,13 +7,15 @@ mod gateway_test;
 
 use std::collections::{BTreeMap, HashMap};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use axum::extract::Path;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::{Json, Router};
 use metrics_exporter_prometheus::{BuildError, PrometheusBuilder, PrometheusHandle};
 use metrics_process::Collector;
@@ -44,6 +46,7 @@ pub struct MonitoringGatewayConfig {
     #[serde(default = "random_secret")]
     pub present_full_config_secret: String,
     pub starknet_url: String,
+    pub storage_backup_dir: PathBuf,
 }
 
 fn random_secret() -> String {
@@ -61,6 +64,7 @@ impl Default for MonitoringGatewayConfig {
             // A constant value for testing purposes.
             present_full_config_secret: String::from("qwerty"),
             starknet_url: String::from("https://alpha-mainnet.starknet.io/"),
+            storage_backup_dir: PathBuf::from("./backup"),
         }
     }
 }
@@ -89,7 +93,7 @@ impl SerializeConfig for MonitoringGatewayConfig {
             ser_generated_param(
                 "present_full_config_secret",
                 SerializationType::String,
-                "A secret for presenting the full general config.",
+                "A secret for presenting the full general config and for backing up the storage.",
                 ParamPrivacyInput::Private,
             ),
             ser_param(
@@ -98,6 +102,14 @@ impl SerializeConfig for MonitoringGatewayConfig {
                 "The URL of a centralized Starknet gateway.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "storage_backup_dir",
+                &self.storage_backup_dir,
+                "The directory in which the storage backups that are requested from the \
+                 monitoring gateway are created, each in a sub-directory named by the time it was \
+                 requested at.",
+                ParamPrivacyInput::Public,
+            ),
         ])
     }
 }
@@ -173,6 +185,7 @@ impl MonitoringServer {
             self.config.present_full_config_secret.clone(),
             self.prometheus_handle.clone(),
             self.own_peer_id.clone(),
+            self.config.storage_backup_dir.clone(),
         );
         debug!("Starting monitoring gateway.");
         axum::Server::bind(&server_address).serve(app.into_make_service()).await
@@ -189,6 +202,7 @@ fn app(
     present_full_config_secret: String,
     prometheus_handle: Option<PrometheusHandle>,
     own_peer_id: String,
+    storage_backup_dir: PathBuf,
 ) -> Router {
     let is_ready_retry_config =
         RetryConfig { retry_base_millis: 50, retry_max_delay_millis: 1000, max_retries: 0 };
@@ -208,6 +222,8 @@ fn app(
 
     let db_tables_stats_reader = storage_reader.clone();
     let mmap_files_stats_reader = storage_reader.clone();
+    let storage_backup_reader = storage_reader.clone();
+    let storage_backup_secret = present_full_config_secret.clone();
 
     Router::new()
         .route(
@@ -218,6 +234,18 @@ fn app(
             format!("/{MONITORING_PREFIX}/mmapFilesStats").as_str(),
             get(move || mmap_files_stats(mmap_files_stats_reader)),
         )
+        .route(
+            // The "*secret" captures the end of the path and stores it in "secret".
+            format!("/{MONITORING_PREFIX}/storageBackup/*secret").as_str(),
+            post(move |secret| {
+                storage_backup(
+                    storage_backup_reader,
+                    storage_backup_dir,
+                    secret,
+                    storage_backup_secret,
+                )
+            }),
+        )
         .route(
             format!("/{MONITORING_PREFIX}/nodeConfig").as_str(),
             get(move || node_config(public_general_config_presentation)),
@@ -278,6 +306,28 @@ async fn mmap_files_stats(
     Ok(storage_reader.mmap_files_stats().into())
 }
 
+/// Backs up the storage to a new directory in the storage backup directory and returns the path of
+/// the backup. The node keeps running while the storage is backed up.
+#[instrument(skip(storage_reader, given_secret, expected_secret), level = "debug", ret)]
+async fn storage_backup(
+    storage_reader: StorageReader,
+    storage_backup_dir: PathBuf,
+    given_secret: Path<String>,
+    expected_secret: String,
+) -> Result<Json<PathBuf>, ServerError> {
+    if given_secret.to_string() != expected_secret {
+        return Err(ServerError::WrongSecret);
+    }
+    let request_time =
+        SystemTime::now().duration_since(UNIX_EPOCH).expect("Current time should be after epoch");
+    let backup_dir = storage_backup_dir.join(request_time.as_millis().to_string());
+    let backup_path = backup_dir.clone();
+    tokio::task::spawn_blocking(move || storage_reader.backup(&backup_path))
+        .await
+        .expect("The storage backup task should not panic")?;
+    Ok(backup_dir.into())
+}
+
 /// Returns the node config.
 #[instrument(level = "debug", ret)]
 async fn node_config(
@@ -324,6 +374,8 @@ async fn node_version(version: &'static str) -> String {
 enum ServerError {
     #[error(transparent)]
     StorageError(#[from] StorageError),
+    #[error("Wrong secret.")]
+    WrongSecret,
 }
 
 impl IntoResponse for ServerError {
@@ -331,6 +383,9 @@ impl IntoResponse for ServerError {
         let (status, error_message) = match self {
             // TODO(dan): consider using a generic error message instead.
             ServerError::StorageError(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            ServerError::WrongSecret => {
+                (StatusCode::FORBIDDEN, ServerError::WrongSecret.to_string())
+            }
         };
         (status, error_message).into_response()
     }
@@ -5,12 +5,20 @@
 //! 1. TestConfig - these are prefixed with `--test.` in the command.
 //! 2. NodeConfig - any argument lacking the above prefix is assumed to be in NodeConfig.
 
+use std::collections::HashSet;
+use std::time::Duration;
+
 use clap::Parser;
 use futures::stream::StreamExt;
 use papyrus_consensus::config::ConsensusConfig;
-use papyrus_consensus::simulation_network_receiver::NetworkReceiver;
+use papyrus_consensus::simulation_network_receiver::{
+    ByzantineConfig,
+    NetworkReceiver,
+    PartitionConfig,
+    ProposalNetworkReceiver,
+};
 use papyrus_consensus::stream_handler::StreamHandler;
-use papyrus_consensus::types::BroadcastVoteChannel;
+use papyrus_consensus::types::{BroadcastVoteChannel, ValidatorId};
 use papyrus_consensus_orchestrator::papyrus_consensus_context::PapyrusConsensusContext;
 use papyrus_network::gossipsub_impl::Topic;
 use papyrus_network::network_manager::{BroadcastTopicChannels, NetworkManager};
@@ -20,6 +28,7 @@ use papyrus_p2p_sync::BUFFER_SIZE;
 use papyrus_protobuf::consensus::{ProposalPart, StreamMessage};
 use papyrus_storage::StorageReader;
 use starknet_api::block::BlockNumber;
+use starknet_api::hash::StarkHash;
 use tokio::task::JoinHandle;
 
 /// Test configuration for consensus.
@@ -38,6 +47,86 @@ pub struct TestConfig {
     pub invalid_probability: f64,
     #[arg(long = "sync_topic", help = "The network topic for sync messages.")]
     pub sync_topic: String,
+    #[arg(
+        long = "byzantine_validators",
+        value_delimiter = ',',
+        value_parser = parse_validator_id,
+        help = "Comma separated IDs of the validators which behave byzantine."
+    )]
+    pub byzantine_validators: Vec<ValidatorId>,
+    #[arg(
+        long = "equivocation_probability",
+        help = "The probability of a byzantine validator voting for a different block."
+    )]
+    pub equivocation_probability: f64,
+    #[arg(
+        long = "withhold_proposal_probability",
+        help = "The probability of a byzantine validator withholding its proposal."
+    )]
+    pub withhold_proposal_probability: f64,
+    #[arg(
+        long = "invalid_proposal_probability",
+        help = "The probability of a byzantine validator proposing wrong content."
+    )]
+    pub invalid_proposal_probability: f64,
+    #[arg(
+        long = "partition_group",
+        value_delimiter = ',',
+        value_parser = parse_validator_id,
+        help = "Comma separated IDs of the validators in this node's network segment. Empty means \
+                no partition."
+    )]
+    pub partition_group: Vec<ValidatorId>,
+    #[arg(
+        long = "partition_start_height",
+        help = "The first height at which the network is partitioned."
+    )]
+    pub partition_start_height: u64,
+    #[arg(long = "partition_num_heights", help = "The number of heights which are partitioned.")]
+    pub partition_num_heights: u64,
+    #[arg(
+        long = "partition_rounds",
+        help = "The number of rounds in each partitioned height in which the network is \
+                partitioned."
+    )]
+    pub partition_rounds: u32,
+    #[arg(
+        long = "cross_group_delay",
+        value_parser = parse_duration_secs,
+        help = "The delay (seconds) of messages between network segments when not partitioned."
+    )]
+    pub cross_group_delay: Duration,
+}
+
+impl TestConfig {
+    fn byzantine_config(&self) -> ByzantineConfig {
+        ByzantineConfig {
+            byzantine_validators: self.byzantine_validators.iter().copied().collect(),
+            equivocation_probability: self.equivocation_probability,
+            withhold_proposal_probability: self.withhold_proposal_probability,
+            invalid_proposal_probability: self.invalid_proposal_probability,
+        }
+    }
+
+    fn partition_config(&self) -> PartitionConfig {
+        PartitionConfig {
+            local_group: self.partition_group.iter().copied().collect::<HashSet<_>>(),
+            partition_heights: self.partition_start_height
+                ..self.partition_start_height + self.partition_num_heights,
+            partition_rounds: self.partition_rounds,
+            cross_group_delay: self.cross_group_delay,
+        }
+    }
+}
+
+fn parse_validator_id(s: &str) -> Result<ValidatorId, String> {
+    let felt = StarkHash::from_hex(s).map_err(|e| e.to_string())?;
+    ValidatorId::try_from(felt).map_err(|e| e.to_string())
+}
+
+fn parse_duration_secs(s: &str) -> Result<Duration, String> {
+    let secs = s.parse::<f64>().map_err(|e| e.to_string())?;
+    Duration::try_from_secs_f64(secs).map_err(|e| e.to_string())
 }
 
 impl Default for TestConfig {
@@ -48,6 +137,15 @@ impl Default for TestConfig {
             drop_probability: 0.0,
             invalid_probability: 0.0,
             sync_topic: "consensus_test_sync".to_string(),
+            byzantine_validators: Vec::new(),
+            equivocation_probability: 0.0,
+            withhold_proposal_probability: 0.0,
+            invalid_proposal_probability: 0.0,
+            partition_group: Vec::new(),
+            partition_start_height: 0,
+            partition_num_heights: 0,
+            partition_rounds: 0,
+            cross_group_delay: Duration::ZERO,
         }
     }
 }
@@ -68,8 +166,19 @@ fn build_consensus(
         broadcasted_messages_receiver: inbound_network_receiver,
         broadcast_topic_client: outbound_network_sender,
     } = proposal_network_channels;
+    let inbound_network_receiver = ProposalNetworkReceiver::new(
+        inbound_network_receiver,
+        test_config.random_seed,
+        consensus_config.validator_id,
+        test_config.byzantine_config(),
+        test_config.partition_config(),
+    );
     let (outbound_internal_sender, inbound_internal_receiver, stream_handler_task_handle) =
-        StreamHandler::get_channels(inbound_network_receiver, outbound_network_sender);
+        StreamHandler::get_channels(
+            consensus_config.stream_handler.clone(),
+            inbound_network_receiver,
+            outbound_network_sender,
+        );
     // TODO(matan): connect this to an actual channel.
     let sync_channels = network_manager
         .register_broadcast_topic(Topic::new(test_config.sync_topic.clone()), BUFFER_SIZE)?;
@@ -90,6 +199,11 @@ fn build_consensus(
         test_config.random_seed,
         test_config.drop_probability,
         test_config.invalid_probability,
+    )
+    .with_byzantine_faults(
+        consensus_config.validator_id,
+        test_config.byzantine_config(),
+        test_config.partition_config(),
     );
     let broadcast_vote_channels = BroadcastVoteChannel {
         broadcasted_messages_receiver: Box::new(network_receiver),
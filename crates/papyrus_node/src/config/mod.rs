@@ -34,6 +34,7 @@ use papyrus_rpc::RpcConfig;
 use papyrus_storage::db::DbConfig;
 use papyrus_storage::StorageConfig;
 use papyrus_sync::sources::central::CentralSourceConfig;
+use papyrus_sync::sources::sequencer_pending::SequencerPendingSourceConfig;
 use papyrus_sync::SyncConfig;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
@@ -59,6 +60,9 @@ pub struct NodeConfig {
     pub storage: StorageConfig,
     /// None if the syncing should be disabled.
     pub sync: Option<SyncConfig>,
+    /// None if the pending data shouldn't be taken from the batcher of a local sequencer. Can't be
+    /// set together with sync.collect_pending_data.
+    pub sequencer_pending_source: Option<SequencerPendingSourceConfig>,
     /// One of p2p_sync or sync must be None.
     /// If P2P sync is active, then network must be active too.
     // TODO(yair): Change NodeConfig to have an option of enum of SyncConfig or P2PSyncConfig.
@@ -80,6 +84,7 @@ impl Default for NodeConfig {
             monitoring_gateway: MonitoringGatewayConfig::default(),
             storage: StorageConfig::default(),
             sync: Some(SyncConfig::default()),
+            sequencer_pending_source: None,
             p2p_sync: None,
             consensus: None,
             network: None,
@@ -97,6 +102,7 @@ impl SerializeConfig for NodeConfig {
             append_sub_config_name(self.monitoring_gateway.dump(), "monitoring_gateway"),
             append_sub_config_name(self.storage.dump(), "storage"),
             ser_optional_sub_config(&self.sync, "sync"),
+            ser_optional_sub_config(&self.sequencer_pending_source, "sequencer_pending_source"),
             ser_optional_sub_config(&self.p2p_sync, "p2p_sync"),
             ser_optional_sub_config(&self.consensus, "consensus"),
             ser_optional_sub_config(&self.network, "network"),
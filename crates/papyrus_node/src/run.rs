@@ -31,6 +31,7 @@ use papyrus_storage::{open_storage, StorageReader, StorageWriter};
 use papyrus_sync::sources::base_layer::EthereumBaseLayerSource;
 use papyrus_sync::sources::central::{CentralError, CentralSource, CentralSourceConfig};
 use papyrus_sync::sources::pending::PendingSource;
+use papyrus_sync::sources::sequencer_pending::SequencerPendingSource;
 use papyrus_sync::{StateSync, SyncConfig};
 use starknet_api::block::{BlockHash, BlockHashAndNumber};
 use starknet_api::felt;
@@ -80,6 +81,7 @@ pub struct PapyrusTaskHandles {
     pub storage_metrics_handle: Option<JoinHandle<anyhow::Result<()>>>,
     pub rpc_server_handle: Option<JoinHandle<anyhow::Result<()>>>,
     pub sync_client_handle: Option<JoinHandle<anyhow::Result<()>>>,
+    pub sequencer_pending_source_handle: Option<JoinHandle<anyhow::Result<()>>>,
     pub monitoring_server_handle: Option<JoinHandle<anyhow::Result<()>>>,
     pub p2p_sync_server_handle: Option<JoinHandle<anyhow::Result<()>>>,
     pub consensus_handle: Option<JoinHandle<anyhow::Result<()>>>,
@@ -200,8 +202,11 @@ fn spawn_consensus(
     } = proposal_network_channels;
 
     // TODO(Matan): receive the handle for the StreamHandler and pass it into run_consensus below.
-    let (outbound_internal_sender, inbound_internal_receiver, _) =
-        StreamHandler::get_channels(inbound_network_receiver, outbound_network_sender);
+    let (outbound_internal_sender, inbound_internal_receiver, _) = StreamHandler::get_channels(
+        config.stream_handler.clone(),
+        inbound_network_receiver,
+        outbound_network_sender,
+    );
 
     let context = PapyrusConsensusContext::new(
         storage_reader.clone(),
@@ -293,24 +298,52 @@ async fn spawn_sync_client(
                 .register_sqmr_protocol_client(Protocol::Transaction.into(), BUFFER_SIZE);
             let class_client_sender =
                 network_manager.register_sqmr_protocol_client(Protocol::Class.into(), BUFFER_SIZE);
+            let event_client_sender =
+                network_manager.register_sqmr_protocol_client(Protocol::Event.into(), BUFFER_SIZE);
             let p2p_sync_client_channels = P2PSyncClientChannels::new(
                 header_client_sender,
                 state_diff_client_sender,
                 transaction_client_sender,
                 class_client_sender,
+                event_client_sender,
             );
             let p2p_sync = P2PSyncClient::new(
                 p2p_sync_client_config,
+                config.storage.db_config.chain_id.clone(),
                 storage_reader,
                 storage_writer,
                 p2p_sync_client_channels,
                 futures::stream::pending().boxed(),
+                // The classes are only stored in the node's storage.
+                None,
             );
             tokio::spawn(async move { Ok(p2p_sync.run().await?) })
         }
     }
 }
 
+fn spawn_sequencer_pending_source(
+    config: &NodeConfig,
+    storage_reader: StorageReader,
+    pending_data: Arc<RwLock<PendingData>>,
+) -> JoinHandle<anyhow::Result<()>> {
+    let Some(sequencer_pending_source_config) = &config.sequencer_pending_source else {
+        return tokio::spawn(future::pending());
+    };
+    if config.sync.as_ref().is_some_and(|sync_config| sync_config.collect_pending_data) {
+        panic!(
+            "One of --sequencer_pending_source.#is_none or --sync.collect_pending_data must be \
+             turned off"
+        );
+    }
+    let sequencer_pending_source =
+        SequencerPendingSource::new(sequencer_pending_source_config, storage_reader);
+    tokio::spawn(async move {
+        sequencer_pending_source.run(pending_data).await;
+        Ok(())
+    })
+}
+
 fn spawn_p2p_sync_server(
     network_manager: Option<&mut NetworkManager>,
     storage_reader: StorageReader,
@@ -405,6 +438,18 @@ async fn run_threads(
         )
     };
 
+    // Pending data from the local sequencer task.
+    let sequencer_pending_source_handle =
+        if let Some(handle) = tasks.sequencer_pending_source_handle {
+            handle
+        } else {
+            spawn_sequencer_pending_source(
+                &config,
+                resources.storage_reader.clone(),
+                resources.pending_data.clone(),
+            )
+        };
+
     // Sync task.
     let sync_client_handle = if let Some(handle) = tasks.sync_client_handle {
         handle
@@ -447,6 +492,10 @@ async fn run_threads(
             error!("Sync stopped.");
             res??
         }
+        res = sequencer_pending_source_handle => {
+            error!("Sequencer pending source stopped.");
+            res??
+        }
         res = p2p_sync_server_handle => {
             error!("P2P Sync server stopped");
             res??
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use futures::future::BoxFuture;
 use futures::{FutureExt, StreamExt};
@@ -9,8 +9,13 @@ use papyrus_storage::class::{ClassStorageReader, ClassStorageWriter};
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{StorageError, StorageReader, StorageWriter};
 use starknet_api::block::BlockNumber;
-use starknet_api::core::ClassHash;
+use starknet_api::core::{ClassHash, CompiledClassHash};
 use starknet_api::state::{DeclaredClasses, DeprecatedDeclaredClasses};
+use starknet_class_manager_types::{
+    ClassManagerClientError,
+    ExecutableClass,
+    SharedClassManagerClient,
+};
 use starknet_state_sync_types::state_sync_types::SyncBlock;
 
 use super::stream_builder::{
@@ -20,32 +25,142 @@ use super::stream_builder::{
     DataStreamBuilder,
     ParseDataError,
 };
+use super::verification::DataVerifier;
 use super::{P2PSyncClientError, NETWORK_DATA_TIMEOUT};
 
-impl BlockData for (DeclaredClasses, DeprecatedDeclaredClasses, BlockNumber) {
-    fn write_to_storage(
+/// The classes declared in a block.
+pub(crate) enum BlockClasses {
+    /// Classes downloaded from peers. If there's a class manager, the declared classes come with
+    /// the executable classes they were compiled to when they were verified, so that they're added
+    /// to the class manager only when the block is written.
+    Downloaded {
+        block_number: BlockNumber,
+        declared_classes: DeclaredClasses,
+        deprecated_declared_classes: DeprecatedDeclaredClasses,
+        executable_classes: HashMap<ClassHash, (CompiledClassHash, ExecutableClass)>,
+    },
+    /// The classes of an internal block. These were added to the class manager before the block
+    /// was sent to the sync, so they're read from it to be written to the storage.
+    Internal {
+        block_number: BlockNumber,
+        declared_class_hashes: Vec<ClassHash>,
+        deprecated_declared_class_hashes: Vec<ClassHash>,
+    },
+}
+
+impl BlockData for BlockClasses {
+    fn write_to_storage<'a>(
         self: Box<Self>,
-        storage_writer: &mut StorageWriter,
-    ) -> Result<(), StorageError> {
-        storage_writer
-            .begin_rw_txn()?
-            .append_classes(
-                self.2,
-                &self.0.iter().map(|(class_hash, class)| (*class_hash, class)).collect::<Vec<_>>(),
-                &self
-                    .1
-                    .iter()
-                    .map(|(class_hash, deprecated_class)| (*class_hash, deprecated_class))
-                    .collect::<Vec<_>>(),
-            )?
-            .commit()
+        storage_writer: &'a mut StorageWriter,
+        class_manager_client: Option<&'a SharedClassManagerClient>,
+    ) -> BoxFuture<'a, Result<(), P2PSyncClientError>> {
+        async move {
+            let (block_number, declared_classes, deprecated_declared_classes) = match *self {
+                BlockClasses::Downloaded {
+                    block_number,
+                    declared_classes,
+                    deprecated_declared_classes,
+                    executable_classes,
+                } => {
+                    if let Some(class_manager_client) = class_manager_client {
+                        add_classes_to_class_manager(
+                            class_manager_client,
+                            &declared_classes,
+                            &deprecated_declared_classes,
+                            executable_classes,
+                        )
+                        .await?;
+                    }
+                    (block_number, declared_classes, deprecated_declared_classes)
+                }
+                BlockClasses::Internal {
+                    block_number,
+                    declared_class_hashes,
+                    deprecated_declared_class_hashes,
+                } => {
+                    let class_manager_client = class_manager_client
+                        .expect("Internal blocks are only sent to a sync with a class manager");
+                    let (declared_classes, deprecated_declared_classes) =
+                        get_classes_from_class_manager(
+                            class_manager_client,
+                            declared_class_hashes,
+                            deprecated_declared_class_hashes,
+                        )
+                        .await?;
+                    (block_number, declared_classes, deprecated_declared_classes)
+                }
+            };
+            storage_writer
+                .begin_rw_txn()?
+                .append_classes(
+                    block_number,
+                    &declared_classes
+                        .iter()
+                        .map(|(class_hash, class)| (*class_hash, class))
+                        .collect::<Vec<_>>(),
+                    &deprecated_declared_classes
+                        .iter()
+                        .map(|(class_hash, deprecated_class)| (*class_hash, deprecated_class))
+                        .collect::<Vec<_>>(),
+                )?
+                .commit()?;
+            Ok(())
+        }
+        .boxed()
     }
 }
 
+async fn add_classes_to_class_manager(
+    class_manager_client: &SharedClassManagerClient,
+    declared_classes: &DeclaredClasses,
+    deprecated_declared_classes: &DeprecatedDeclaredClasses,
+    mut executable_classes: HashMap<ClassHash, (CompiledClassHash, ExecutableClass)>,
+) -> Result<(), ClassManagerClientError> {
+    for (class_hash, class) in declared_classes {
+        let (executable_class_hash, executable_class) =
+            executable_classes.remove(class_hash).expect(
+                "Declared classes are compiled when they're verified if there's a class manager",
+            );
+        class_manager_client
+            .add_class_and_executable_unsafe(
+                *class_hash,
+                class.clone(),
+                executable_class_hash,
+                executable_class,
+            )
+            .await?;
+    }
+    for (class_hash, deprecated_class) in deprecated_declared_classes {
+        class_manager_client.add_deprecated_class(*class_hash, deprecated_class.clone()).await?;
+    }
+    Ok(())
+}
+
+async fn get_classes_from_class_manager(
+    class_manager_client: &SharedClassManagerClient,
+    declared_class_hashes: Vec<ClassHash>,
+    deprecated_declared_class_hashes: Vec<ClassHash>,
+) -> Result<(DeclaredClasses, DeprecatedDeclaredClasses), ClassManagerClientError> {
+    let mut declared_classes = DeclaredClasses::new();
+    for class_hash in declared_class_hashes {
+        declared_classes.insert(class_hash, class_manager_client.get_sierra(class_hash).await?);
+    }
+    let mut deprecated_declared_classes = DeprecatedDeclaredClasses::new();
+    for class_hash in deprecated_declared_class_hashes {
+        let ExecutableClass::V0(deprecated_class) =
+            class_manager_client.get_executable(class_hash).await?
+        else {
+            panic!("The class manager holds a Cairo 1 class for the deprecated class {class_hash}");
+        };
+        deprecated_declared_classes.insert(class_hash, deprecated_class);
+    }
+    Ok((declared_classes, deprecated_declared_classes))
+}
+
 pub(crate) struct ClassStreamBuilder;
 
 impl DataStreamBuilder<(ApiContractClass, ClassHash)> for ClassStreamBuilder {
-    type Output = (DeclaredClasses, DeprecatedDeclaredClasses, BlockNumber);
+    type Output = BlockClasses;
 
     const TYPE_DESCRIPTION: &'static str = "classes";
     const BLOCK_NUMBER_LIMIT: BlockNumberLimit = BlockNumberLimit::StateDiffMarker;
@@ -56,6 +171,7 @@ impl DataStreamBuilder<(ApiContractClass, ClassHash)> for ClassStreamBuilder {
         >,
         block_number: BlockNumber,
         storage_reader: &'a StorageReader,
+        verifier: &'a DataVerifier,
     ) -> BoxFuture<'a, Result<Option<Self::Output>, ParseDataError>> {
         async move {
             let (target_class_len, declared_classes, deprecated_declared_classes) = {
@@ -122,7 +238,22 @@ impl DataStreamBuilder<(ApiContractClass, ClassHash)> for ClassStreamBuilder {
 
                 current_class_len += 1;
             }
-            Ok(Some((declared_classes_result, deprecated_declared_classes_result, block_number)))
+
+            let mut executable_classes = HashMap::new();
+            for (class_hash, class) in &declared_classes_result {
+                let compiled_class_hash = declared_classes[class_hash];
+                if let Some(executable_class) =
+                    verifier.verify_declared_class(*class_hash, class, compiled_class_hash).await?
+                {
+                    executable_classes.insert(*class_hash, (compiled_class_hash, executable_class));
+                }
+            }
+            Ok(Some(BlockClasses::Downloaded {
+                block_number,
+                declared_classes: declared_classes_result,
+                deprecated_declared_classes: deprecated_declared_classes_result,
+                executable_classes,
+            }))
         }
         .boxed()
     }
@@ -131,11 +262,14 @@ impl DataStreamBuilder<(ApiContractClass, ClassHash)> for ClassStreamBuilder {
         storage_reader.begin_ro_txn()?.get_class_marker()
     }
 
-    // TODO(Eitan): Implement this function once we have a class manager component.
     fn convert_sync_block_to_block_data(
-        _block_number: BlockNumber,
-        _sync_block: SyncBlock,
-    ) -> Option<(DeclaredClasses, DeprecatedDeclaredClasses, BlockNumber)> {
-        None
+        block_number: BlockNumber,
+        sync_block: SyncBlock,
+    ) -> Option<BlockClasses> {
+        Some(BlockClasses::Internal {
+            block_number,
+            declared_class_hashes: sync_block.state_diff.declared_classes.into_keys().collect(),
+            deprecated_declared_class_hashes: sync_block.state_diff.deprecated_declared_classes,
+        })
     }
 }
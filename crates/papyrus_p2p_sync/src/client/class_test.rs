@@ -1,6 +1,10 @@
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
+use async_trait::async_trait;
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use futures::FutureExt;
+use indexmap::IndexMap;
 use papyrus_common::pending_classes::ApiContractClass;
 use papyrus_protobuf::sync::{
     BlockHashOrNumber,
@@ -16,13 +20,26 @@ use papyrus_test_utils::{get_rng, GetTestInstance};
 use rand::{Rng, RngCore};
 use rand_chacha::ChaCha8Rng;
 use starknet_api::block::BlockNumber;
+use starknet_api::contract_class::SierraVersion;
 use starknet_api::core::{ClassHash, CompiledClassHash};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
-use starknet_api::state::SierraContractClass;
+use starknet_api::state::{SierraContractClass, ThinStateDiff};
+use starknet_class_manager_types::{
+    Class,
+    ClassId,
+    ClassManagerClient,
+    ClassManagerClientResult,
+    ClassManagerError,
+    ExecutableClass,
+    ExecutableClassHash,
+};
+use starknet_state_sync_types::state_sync_types::SyncBlock;
 
 use super::test_utils::{
+    config_with_query_lengths,
     random_header,
     run_test,
+    run_test_with_class_manager_client,
     wait_for_marker,
     Action,
     DataType,
@@ -149,6 +166,244 @@ async fn class_basic_flow() {
     .await;
 }
 
+#[tokio::test]
+async fn class_compiled_to_declared_hash_is_added_to_class_manager() {
+    let class_manager_client = Arc::new(FakeClassManagerClient::new(executable_class()));
+    let compiled_class_hash = class_manager_client.executable_class.compiled_class_hash();
+    let (class_hash, class) = run_declared_class_test(
+        compiled_class_hash,
+        class_manager_client.clone(),
+        vec![Action::CheckStorage(Box::new(|reader| {
+            async move {
+                wait_for_marker(
+                    DataType::Class,
+                    &reader,
+                    BlockNumber(1),
+                    SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+                    TIMEOUT_FOR_TEST,
+                )
+                .await;
+            }
+            .boxed()
+        }))],
+    )
+    .await;
+
+    assert_eq!(class_manager_client.get_sierra(class_hash).await.unwrap(), class);
+    assert_eq!(
+        class_manager_client.get_executable(class_hash).await.unwrap(),
+        class_manager_client.executable_class
+    );
+}
+
+#[tokio::test]
+async fn wrong_compiled_class_hash_reports_peer_without_adding_class() {
+    let class_manager_client = Arc::new(FakeClassManagerClient::new(executable_class()));
+    let (class_hash, _class) = run_declared_class_test(
+        CompiledClassHash(1u64.into()),
+        class_manager_client.clone(),
+        vec![Action::ValidateReportSent(DataType::Class)],
+    )
+    .await;
+
+    assert!(class_manager_client.get_sierra(class_hash).await.is_err());
+}
+
+#[tokio::test]
+async fn internal_block_classes_are_read_from_class_manager() {
+    let mut rng = get_rng();
+    let class_manager_client = Arc::new(FakeClassManagerClient::new(executable_class()));
+    let class_hash = ClassHash(rng.next_u64().into());
+    let class = SierraContractClass::get_test_instance(&mut rng);
+    let compiled_class_hash =
+        class_manager_client.add_class(class_hash, class.clone()).await.unwrap();
+    let deprecated_class_hash = ClassHash(rng.next_u64().into());
+    let deprecated_class = DeprecatedContractClass::get_test_instance(&mut rng);
+    class_manager_client
+        .add_deprecated_class(deprecated_class_hash, deprecated_class.clone())
+        .await
+        .unwrap();
+    let sync_block = SyncBlock {
+        block_number: BlockNumber(0),
+        state_diff: ThinStateDiff {
+            declared_classes: IndexMap::from([(class_hash, compiled_class_hash)]),
+            deprecated_declared_classes: vec![deprecated_class_hash],
+            ..Default::default()
+        },
+        transaction_hashes: vec![],
+    };
+
+    run_test_with_class_manager_client(
+        config_with_query_lengths(&HashMap::from([
+            (DataType::Header, 1),
+            (DataType::StateDiff, 1),
+            (DataType::Class, 1),
+        ])),
+        Some(class_manager_client),
+        vec![
+            Action::RunP2pSync,
+            Action::SendInternalBlock(BlockNumber(0), sync_block),
+            Action::CheckStorage(Box::new(move |reader| {
+                async move {
+                    wait_for_marker(
+                        DataType::Class,
+                        &reader,
+                        BlockNumber(1),
+                        SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+                        TIMEOUT_FOR_TEST,
+                    )
+                    .await;
+
+                    let txn = reader.begin_ro_txn().unwrap();
+                    assert_eq!(txn.get_class(&class_hash).unwrap().unwrap(), class);
+                    assert_eq!(
+                        txn.get_deprecated_class(&deprecated_class_hash).unwrap().unwrap(),
+                        deprecated_class
+                    );
+                }
+                .boxed()
+            })),
+        ],
+    )
+    .await;
+}
+
+// Syncs a block that declares a single class with the given compiled class hash, while the given
+// class manager compiles it to its own executable class, and then runs the given actions. Returns
+// the class.
+async fn run_declared_class_test(
+    declared_compiled_class_hash: CompiledClassHash,
+    class_manager_client: Arc<FakeClassManagerClient>,
+    final_actions: Vec<Action>,
+) -> (ClassHash, Class) {
+    let mut rng = get_rng();
+    let class_hash = ClassHash(rng.next_u64().into());
+    let state_diff_chunk = StateDiffChunk::DeclaredClass(DeclaredClass {
+        class_hash,
+        compiled_class_hash: declared_compiled_class_hash,
+    });
+    let class = SierraContractClass::get_test_instance(&mut rng);
+
+    let mut actions = vec![
+        Action::RunP2pSync,
+        // We already validate the query content in other tests.
+        Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+        Action::SendHeader(DataOrFin(Some(random_header(&mut rng, BlockNumber(0), Some(1), None)))),
+        Action::SendHeader(DataOrFin(None)),
+        Action::ReceiveQuery(Box::new(|_query| ()), DataType::StateDiff),
+        Action::SendStateDiff(DataOrFin(Some(state_diff_chunk))),
+        Action::ReceiveQuery(Box::new(|_query| ()), DataType::Class),
+        Action::SendClass(DataOrFin(Some((
+            ApiContractClass::ContractClass(class.clone()),
+            class_hash,
+        )))),
+    ];
+    actions.extend(final_actions);
+    run_test_with_class_manager_client(
+        config_with_query_lengths(&HashMap::from([
+            (DataType::Header, 1),
+            (DataType::StateDiff, 1),
+            (DataType::Class, 1),
+        ])),
+        Some(class_manager_client),
+        actions,
+    )
+    .await;
+    (class_hash, class)
+}
+
+fn executable_class() -> ExecutableClass {
+    // The segment lengths of a random instance don't match its bytecode, which fails hashing it.
+    let casm = CasmContractClass {
+        bytecode_segment_lengths: None,
+        ..CasmContractClass::get_test_instance(&mut get_rng())
+    };
+    ExecutableClass::V1((casm, SierraVersion::default()))
+}
+
+// A class manager that compiles every class to the same executable class, and keeps the classes
+// added to it in memory.
+struct FakeClassManagerClient {
+    executable_class: ExecutableClass,
+    classes: Mutex<HashMap<ClassId, (Class, ExecutableClass)>>,
+    deprecated_classes: Mutex<HashMap<ClassId, DeprecatedContractClass>>,
+}
+
+impl FakeClassManagerClient {
+    fn new(executable_class: ExecutableClass) -> Self {
+        Self {
+            executable_class,
+            classes: Mutex::new(HashMap::new()),
+            deprecated_classes: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl ClassManagerClient for FakeClassManagerClient {
+    async fn add_class(
+        &self,
+        class_id: ClassId,
+        class: Class,
+    ) -> ClassManagerClientResult<ExecutableClassHash> {
+        let executable_class = self.compile_class(class.clone()).await?;
+        let executable_class_hash = executable_class.compiled_class_hash();
+        self.add_class_and_executable_unsafe(
+            class_id,
+            class,
+            executable_class_hash,
+            executable_class,
+        )
+        .await?;
+        Ok(executable_class_hash)
+    }
+
+    async fn compile_class(&self, _class: Class) -> ClassManagerClientResult<ExecutableClass> {
+        Ok(self.executable_class.clone())
+    }
+
+    async fn add_class_and_executable_unsafe(
+        &self,
+        class_id: ClassId,
+        class: Class,
+        _executable_class_hash: ExecutableClassHash,
+        executable_class: ExecutableClass,
+    ) -> ClassManagerClientResult<()> {
+        self.classes.lock().unwrap().insert(class_id, (class, executable_class));
+        Ok(())
+    }
+
+    async fn get_executable(&self, class_id: ClassId) -> ClassManagerClientResult<ExecutableClass> {
+        if let Some((_class, executable_class)) = self.classes.lock().unwrap().get(&class_id) {
+            return Ok(executable_class.clone());
+        }
+        self.deprecated_classes
+            .lock()
+            .unwrap()
+            .get(&class_id)
+            .map(|deprecated_class| ExecutableClass::V0(deprecated_class.clone()))
+            .ok_or(ClassManagerError::ClassNotFound { class_id }.into())
+    }
+
+    async fn get_sierra(&self, class_id: ClassId) -> ClassManagerClientResult<Class> {
+        self.classes
+            .lock()
+            .unwrap()
+            .get(&class_id)
+            .map(|(class, _executable_class)| class.clone())
+            .ok_or(ClassManagerError::ClassNotFound { class_id }.into())
+    }
+
+    async fn add_deprecated_class(
+        &self,
+        class_id: ClassId,
+        class: DeprecatedContractClass,
+    ) -> ClassManagerClientResult<()> {
+        self.deprecated_classes.lock().unwrap().insert(class_id, class);
+        Ok(())
+    }
+}
+
 // We define this new trait here so we can use the get_class_hash function in the test.
 // we need to define this trait because StateDiffChunk is defined in an other crate.
 trait GetClassHash {
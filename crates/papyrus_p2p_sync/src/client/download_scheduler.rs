@@ -0,0 +1,137 @@
+use std::cmp::{max, min};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use starknet_api::block::BlockNumber;
+
+// The range size is chosen so that a query to a peer with the measured throughput takes roughly
+// this long. Shorter queries waste round trips, while longer queries make a slow peer delay the
+// blocks that come after its range.
+pub(crate) const TARGET_QUERY_DURATION: Duration = Duration::from_secs(5);
+
+/// A range of blocks [start, end) that is downloaded by a single query.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct BlockRange {
+    pub start: BlockNumber,
+    pub end: BlockNumber,
+}
+
+impl BlockRange {
+    pub fn len(&self) -> u64 {
+        self.end.0 - self.start.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// The part of the range that starts at the given block. Empty if the block is after the range.
+    pub fn starting_at(&self, block_number: BlockNumber) -> Self {
+        Self { start: max(self.start, block_number).min(self.end), end: self.end }
+    }
+}
+
+/// Splits the blocks a stream needs into ranges that are downloaded concurrently.
+///
+/// The network assigns each query to the next peer in turn, so concurrent queries are served by
+/// different peers, and a range that is sent again after a failure is served by a different peer
+/// than the one that failed it. The sync doesn't know which peer serves each query, so the
+/// throughput is estimated over all the peers together.
+pub(crate) struct DownloadScheduler {
+    // All the blocks below this block number were assigned to a query.
+    next_unassigned_block: BlockNumber,
+    // Ranges whose query failed and that should be assigned to a new query, keyed by their start.
+    ranges_to_retry: BTreeMap<BlockNumber, BlockNumber>,
+    max_range_size: u64,
+    // The amount of blocks that a peer can send in TARGET_QUERY_DURATION, according to the queries
+    // that finished so far.
+    estimated_range_size: u64,
+}
+
+impl DownloadScheduler {
+    pub fn new(start_block_number: BlockNumber, max_range_size: u64) -> Self {
+        Self {
+            next_unassigned_block: start_block_number,
+            ranges_to_retry: BTreeMap::new(),
+            max_range_size,
+            estimated_range_size: max_range_size,
+        }
+    }
+
+    fn range_size(&self) -> u64 {
+        self.estimated_range_size.clamp(1, max(self.max_range_size, 1))
+    }
+
+    /// Returns the next range that should be queried, or None if all the blocks below
+    /// `block_number_limit` were assigned. A limit of None means there's no limit.
+    pub fn next_range(&mut self, block_number_limit: Option<BlockNumber>) -> Option<BlockRange> {
+        let range_size = self.range_size();
+        if let Some((start, end)) = self.ranges_to_retry.pop_first() {
+            let range = BlockRange { start, end: min(end, BlockNumber(start.0 + range_size)) };
+            if range.end < end {
+                self.ranges_to_retry.insert(range.end, end);
+            }
+            return Some(range);
+        }
+        let num_available_blocks = match block_number_limit {
+            Some(limit) => limit.0.saturating_sub(self.next_unassigned_block.0),
+            None => u64::MAX,
+        };
+        let range_size = min(range_size, num_available_blocks);
+        if range_size == 0 {
+            return None;
+        }
+        let start = self.next_unassigned_block;
+        self.next_unassigned_block = BlockNumber(start.0 + range_size);
+        Some(BlockRange { start, end: self.next_unassigned_block })
+    }
+
+    /// Updates the throughput estimation with a query that downloaded its entire range.
+    pub fn range_finished(&mut self, range: BlockRange, elapsed: Duration) {
+        let measured_range_size = match elapsed.as_millis() {
+            0 => u64::MAX,
+            elapsed_millis => u64::try_from(
+                u128::from(range.len()) * TARGET_QUERY_DURATION.as_millis() / elapsed_millis,
+            )
+            .unwrap_or(u64::MAX),
+        };
+        // Average with the previous estimation so that a single slow peer won't shrink the ranges
+        // of all the peers.
+        let previous_range_size = min(self.estimated_range_size, self.max_range_size);
+        let measured_range_size = min(measured_range_size, self.max_range_size);
+        self.estimated_range_size = previous_range_size / 2
+            + measured_range_size / 2
+            + (previous_range_size % 2 + measured_range_size % 2) / 2;
+    }
+
+    /// Marks the given blocks as blocks that should be downloaded by a new query. If the query
+    /// failed because of the peer, the range size is reduced, since the peer may have failed
+    /// because its range was too big to send within the timeout.
+    pub fn range_failed(&mut self, unfinished_range: BlockRange, is_bad_peer: bool) {
+        if is_bad_peer {
+            self.estimated_range_size = max(self.range_size() / 2, 1);
+        }
+        if unfinished_range.is_empty() {
+            return;
+        }
+        // If nothing was assigned after this range, the range can be merged into the blocks that
+        // weren't assigned yet, and the next range will be sized according to the current limit.
+        if unfinished_range.end == self.next_unassigned_block {
+            self.next_unassigned_block = unfinished_range.start;
+            return;
+        }
+        self.ranges_to_retry.insert(unfinished_range.start, unfinished_range.end);
+    }
+
+    /// Stops scheduling the blocks below the given block number, since they were received from a
+    /// different source.
+    pub fn skip_until(&mut self, block_number: BlockNumber) {
+        self.next_unassigned_block = max(self.next_unassigned_block, block_number);
+        let ranges_to_retry = std::mem::take(&mut self.ranges_to_retry);
+        self.ranges_to_retry = ranges_to_retry
+            .into_iter()
+            .filter(|(_start, end)| *end > block_number)
+            .map(|(start, end)| (max(start, block_number), end))
+            .collect();
+    }
+}
@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use starknet_api::block::BlockNumber;
+
+use super::download_scheduler::{BlockRange, DownloadScheduler, TARGET_QUERY_DURATION};
+
+const MAX_RANGE_SIZE: u64 = 10;
+
+fn range(start: u64, end: u64) -> BlockRange {
+    BlockRange { start: BlockNumber(start), end: BlockNumber(end) }
+}
+
+#[test]
+fn ranges_are_limited_by_size_and_block_number_limit() {
+    let mut scheduler = DownloadScheduler::new(BlockNumber(0), MAX_RANGE_SIZE);
+
+    assert_eq!(scheduler.next_range(None), Some(range(0, 10)));
+    assert_eq!(scheduler.next_range(Some(BlockNumber(14))), Some(range(10, 14)));
+    assert_eq!(scheduler.next_range(Some(BlockNumber(14))), None);
+    assert_eq!(scheduler.next_range(Some(BlockNumber(30))), Some(range(14, 24)));
+}
+
+#[test]
+fn failed_range_is_retried_before_new_ranges() {
+    let mut scheduler = DownloadScheduler::new(BlockNumber(0), MAX_RANGE_SIZE);
+    assert_eq!(scheduler.next_range(None), Some(range(0, 10)));
+    assert_eq!(scheduler.next_range(None), Some(range(10, 20)));
+
+    scheduler.range_failed(range(3, 10), false);
+
+    assert_eq!(scheduler.next_range(None), Some(range(3, 10)));
+    assert_eq!(scheduler.next_range(None), Some(range(20, 30)));
+}
+
+#[test]
+fn failed_last_range_is_merged_into_unassigned_blocks() {
+    let mut scheduler = DownloadScheduler::new(BlockNumber(0), MAX_RANGE_SIZE);
+    assert_eq!(scheduler.next_range(Some(BlockNumber(5))), Some(range(0, 5)));
+
+    scheduler.range_failed(range(2, 5), false);
+
+    // The new range is sized according to the new limit and not according to the failed range.
+    assert_eq!(scheduler.next_range(Some(BlockNumber(20))), Some(range(2, 12)));
+}
+
+#[test]
+fn bad_peer_shrinks_ranges() {
+    let mut scheduler = DownloadScheduler::new(BlockNumber(0), MAX_RANGE_SIZE);
+    assert_eq!(scheduler.next_range(None), Some(range(0, 10)));
+    assert_eq!(scheduler.next_range(None), Some(range(10, 20)));
+
+    scheduler.range_failed(range(0, 10), true);
+
+    assert_eq!(scheduler.next_range(None), Some(range(0, 5)));
+    assert_eq!(scheduler.next_range(None), Some(range(5, 10)));
+    assert_eq!(scheduler.next_range(None), Some(range(20, 25)));
+}
+
+#[test]
+fn range_size_adapts_to_throughput() {
+    let mut scheduler = DownloadScheduler::new(BlockNumber(0), MAX_RANGE_SIZE);
+    assert_eq!(scheduler.next_range(None), Some(range(0, 10)));
+
+    // A peer that sends 2 blocks in TARGET_QUERY_DURATION.
+    scheduler.range_finished(range(0, 10), TARGET_QUERY_DURATION * 5);
+    assert_eq!(scheduler.next_range(None), Some(range(10, 16)));
+
+    // A fast peer restores the range size gradually.
+    scheduler.range_finished(range(10, 16), Duration::ZERO);
+    assert_eq!(scheduler.next_range(None), Some(range(16, 24)));
+}
+
+#[test]
+fn fast_peers_keep_the_max_range_size() {
+    const ODD_MAX_RANGE_SIZE: u64 = 5;
+    let mut scheduler = DownloadScheduler::new(BlockNumber(0), ODD_MAX_RANGE_SIZE);
+    assert_eq!(scheduler.next_range(None), Some(range(0, 5)));
+
+    scheduler.range_finished(range(0, 5), Duration::ZERO);
+    assert_eq!(scheduler.next_range(None), Some(range(5, 10)));
+}
+
+#[test]
+fn skipped_blocks_are_not_scheduled() {
+    let mut scheduler = DownloadScheduler::new(BlockNumber(0), MAX_RANGE_SIZE);
+    assert_eq!(scheduler.next_range(None), Some(range(0, 10)));
+    assert_eq!(scheduler.next_range(None), Some(range(10, 20)));
+    scheduler.range_failed(range(0, 10), false);
+
+    scheduler.skip_until(BlockNumber(4));
+
+    assert_eq!(scheduler.next_range(None), Some(range(4, 10)));
+    assert_eq!(scheduler.next_range(None), Some(range(20, 30)));
+}
@@ -0,0 +1,123 @@
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt};
+use papyrus_network::network_manager::ClientResponsesManager;
+use papyrus_protobuf::sync::DataOrFin;
+use papyrus_storage::body::{BodyStorageReader, BodyStorageWriter};
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::{StorageError, StorageReader, StorageWriter};
+use starknet_api::block::BlockNumber;
+use starknet_api::transaction::{Event, TransactionHash};
+use starknet_class_manager_types::SharedClassManagerClient;
+use starknet_state_sync_types::state_sync_types::SyncBlock;
+
+use super::stream_builder::{
+    BadPeerError,
+    BlockData,
+    BlockNumberLimit,
+    DataStreamBuilder,
+    ParseDataError,
+};
+use super::verification::DataVerifier;
+use super::{P2PSyncClientError, NETWORK_DATA_TIMEOUT};
+
+// The events of each transaction in the block, in the order of the block's transactions.
+impl BlockData for (Vec<Vec<Event>>, BlockNumber) {
+    fn write_to_storage<'a>(
+        self: Box<Self>,
+        storage_writer: &'a mut StorageWriter,
+        _class_manager_client: Option<&'a SharedClassManagerClient>,
+    ) -> BoxFuture<'a, Result<(), P2PSyncClientError>> {
+        async move {
+            storage_writer.begin_rw_txn()?.append_events(self.1, self.0)?.commit()?;
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+pub(crate) struct EventStreamBuilder;
+
+impl DataStreamBuilder<(Event, TransactionHash)> for EventStreamBuilder {
+    type Output = (Vec<Vec<Event>>, BlockNumber);
+
+    const TYPE_DESCRIPTION: &'static str = "events";
+    const BLOCK_NUMBER_LIMIT: BlockNumberLimit = BlockNumberLimit::BodyMarker;
+
+    fn parse_data_for_block<'a>(
+        events_response_manager: &'a mut ClientResponsesManager<
+            DataOrFin<(Event, TransactionHash)>,
+        >,
+        block_number: BlockNumber,
+        storage_reader: &'a StorageReader,
+        verifier: &'a DataVerifier,
+    ) -> BoxFuture<'a, Result<Option<Self::Output>, ParseDataError>> {
+        async move {
+            let (header, transaction_hashes) = {
+                let txn = storage_reader.begin_ro_txn()?;
+                (
+                    txn.get_block_header(block_number)?
+                        .expect("A header with number lower than the body marker is missing"),
+                    txn.get_block_transaction_hashes(block_number)?
+                        .expect("A body with number lower than the body marker is missing"),
+                )
+            };
+            let target_event_len = header.n_events;
+            let mut current_event_len = 0;
+            let mut events = vec![Vec::new(); transaction_hashes.len()];
+            let mut current_transaction_offset = 0;
+
+            while current_event_len < target_event_len {
+                let maybe_event =
+                    tokio::time::timeout(NETWORK_DATA_TIMEOUT, events_response_manager.next())
+                        .await?
+                        .ok_or(P2PSyncClientError::ReceiverChannelTerminated {
+                            type_description: Self::TYPE_DESCRIPTION,
+                        })?;
+                let Some((event, transaction_hash)) = maybe_event?.0 else {
+                    if current_event_len == 0 {
+                        return Ok(None);
+                    } else {
+                        return Err(ParseDataError::BadPeer(BadPeerError::NotEnoughEvents {
+                            expected: target_event_len,
+                            actual: current_event_len,
+                            block_number: block_number.0,
+                        }));
+                    }
+                };
+
+                // The events are sent in the order of their transactions, so the event's
+                // transaction is either the previous event's transaction or one after it.
+                let Some(offset) = transaction_hashes[current_transaction_offset..]
+                    .iter()
+                    .position(|block_transaction_hash| *block_transaction_hash == transaction_hash)
+                else {
+                    return Err(ParseDataError::BadPeer(
+                        BadPeerError::UnexpectedEventTransactionHash {
+                            transaction_hash,
+                            block_number,
+                        },
+                    ));
+                };
+                current_transaction_offset += offset;
+                events[current_transaction_offset].push(event);
+                current_event_len += 1;
+            }
+
+            verifier.verify_events(&header, &events, &transaction_hashes)?;
+            Ok(Some((events, block_number)))
+        }
+        .boxed()
+    }
+
+    fn get_start_block_number(storage_reader: &StorageReader) -> Result<BlockNumber, StorageError> {
+        storage_reader.begin_ro_txn()?.get_event_marker()
+    }
+
+    // SyncBlock doesn't contain the events of the block.
+    fn convert_sync_block_to_block_data(
+        _block_number: BlockNumber,
+        _sync_block: SyncBlock,
+    ) -> Option<(Vec<Vec<Event>>, BlockNumber)> {
+        None
+    }
+}
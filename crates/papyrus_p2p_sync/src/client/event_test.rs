@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use futures::FutureExt;
+use papyrus_protobuf::sync::{BlockHashOrNumber, DataOrFin, Direction, Query, SignedBlockHeader};
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_test_utils::{get_rng, get_test_body, GetTestInstance};
+use rand_chacha::ChaCha8Rng;
+use starknet_api::block::{BlockNumber, StarknetVersion};
+use starknet_api::block_hash::event_commitment::{calculate_event_commitment, EventLeafElement};
+use starknet_api::core::EventCommitment;
+use starknet_api::felt;
+use starknet_api::transaction::{Event, FullTransaction};
+use starknet_types_core::hash::Poseidon;
+
+use super::test_utils::{
+    random_header,
+    run_test,
+    wait_for_marker,
+    Action,
+    DataType,
+    SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+    TIMEOUT_FOR_TEST,
+};
+
+// The amount of events each transaction emitted, for each block.
+const NUM_EVENTS_PER_TRANSACTION: [&[usize]; 2] = [&[2, 0, 1], &[1]];
+
+struct TestBlock {
+    header: SignedBlockHeader,
+    transactions: Vec<FullTransaction>,
+    events: Vec<Vec<Event>>,
+}
+
+fn event_commitment(transactions: &[FullTransaction], events: &[Vec<Event>]) -> EventCommitment {
+    let event_leaf_elements = transactions
+        .iter()
+        .zip(events)
+        .flat_map(|(transaction, transaction_events)| {
+            transaction_events.iter().map(|event| EventLeafElement {
+                event: event.clone(),
+                transaction_hash: transaction.transaction_hash,
+            })
+        })
+        .collect::<Vec<_>>();
+    calculate_event_commitment::<Poseidon>(&event_leaf_elements)
+}
+
+fn create_test_blocks(rng: &mut ChaCha8Rng) -> Vec<TestBlock> {
+    let num_transactions = NUM_EVENTS_PER_TRANSACTION.iter().map(|block| block.len()).sum();
+    let body = get_test_body(num_transactions, None, None, None);
+    let mut transactions = body
+        .transactions
+        .into_iter()
+        .zip(body.transaction_outputs)
+        .zip(body.transaction_hashes)
+        .map(|((transaction, transaction_output), transaction_hash)| FullTransaction {
+            transaction,
+            transaction_output,
+            transaction_hash,
+        });
+
+    NUM_EVENTS_PER_TRANSACTION
+        .iter()
+        .enumerate()
+        .map(|(i, num_events_per_transaction)| {
+            let block_transactions =
+                transactions.by_ref().take(num_events_per_transaction.len()).collect::<Vec<_>>();
+            let events = num_events_per_transaction
+                .iter()
+                .map(|num_events| {
+                    std::iter::repeat_with(|| Event::get_test_instance(rng))
+                        .take(*num_events)
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            let mut header = random_header(
+                rng,
+                BlockNumber(i.try_into().unwrap()),
+                Some(0),
+                Some(block_transactions.len()),
+            );
+            header.block_header.block_header_without_hash.starknet_version =
+                StarknetVersion::V0_13_2;
+            header.block_header.n_events = events.iter().map(Vec::len).sum();
+            header.block_header.event_commitment =
+                Some(event_commitment(&block_transactions, &events));
+            TestBlock { header, transactions: block_transactions, events }
+        })
+        .collect()
+}
+
+// Returns the actions that sync the headers and the transactions of the given blocks.
+fn sync_headers_and_transactions(blocks: &[TestBlock]) -> Vec<Action> {
+    let mut actions = vec![
+        Action::RunP2pSync,
+        // We already validate the header query content in other tests.
+        Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+    ];
+    for block in blocks {
+        actions.push(Action::SendHeader(DataOrFin(Some(block.header.clone()))));
+    }
+    actions.push(Action::SendHeader(DataOrFin(None)));
+
+    // We already validate the transaction query content in other tests.
+    actions.push(Action::ReceiveQuery(Box::new(|_query| ()), DataType::Transaction));
+    for block in blocks {
+        for transaction in &block.transactions {
+            actions.push(Action::SendTransaction(DataOrFin(Some(transaction.clone()))));
+        }
+    }
+    actions.push(Action::SendTransaction(DataOrFin(None)));
+    actions
+}
+
+fn query_lengths(num_blocks: usize) -> HashMap<DataType, u64> {
+    let num_blocks = u64::try_from(num_blocks).unwrap();
+    HashMap::from([
+        (DataType::Header, num_blocks),
+        (DataType::Transaction, num_blocks),
+        (DataType::Event, num_blocks),
+    ])
+}
+
+#[tokio::test]
+async fn event_basic_flow() {
+    let mut rng = get_rng();
+    let blocks = create_test_blocks(&mut rng);
+    let num_blocks = blocks.len();
+
+    let mut actions = sync_headers_and_transactions(&blocks);
+    actions.push(Action::ReceiveQuery(
+        Box::new(move |query| {
+            assert_eq!(
+                query,
+                Query {
+                    start_block: BlockHashOrNumber::Number(BlockNumber(0)),
+                    direction: Direction::Forward,
+                    limit: num_blocks.try_into().unwrap(),
+                    step: 1,
+                }
+            )
+        }),
+        DataType::Event,
+    ));
+    for (i, block) in blocks.into_iter().enumerate() {
+        // Check that the events aren't written before all of the block's events were sent.
+        actions.push(Action::CheckStorage(Box::new(move |reader| {
+            async move {
+                assert_eq!(
+                    u64::try_from(i).unwrap(),
+                    reader.begin_ro_txn().unwrap().get_event_marker().unwrap().0
+                );
+            }
+            .boxed()
+        })));
+        for (transaction, transaction_events) in block.transactions.iter().zip(&block.events) {
+            for event in transaction_events {
+                actions.push(Action::SendEvent(DataOrFin(Some((
+                    event.clone(),
+                    transaction.transaction_hash,
+                )))));
+            }
+        }
+        // Check that a block's events are written before the entire query finished.
+        actions.push(Action::CheckStorage(Box::new(move |reader| {
+            async move {
+                let block_number = BlockNumber(i.try_into().unwrap());
+                wait_for_marker(
+                    DataType::Event,
+                    &reader,
+                    block_number.unchecked_next(),
+                    SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+                    TIMEOUT_FOR_TEST,
+                )
+                .await;
+
+                let transaction_outputs = reader
+                    .begin_ro_txn()
+                    .unwrap()
+                    .get_block_transaction_outputs(block_number)
+                    .unwrap()
+                    .unwrap();
+                for (transaction_output, expected_events) in
+                    transaction_outputs.iter().zip(block.events)
+                {
+                    assert_eq!(transaction_output.events(), expected_events);
+                }
+            }
+            .boxed()
+        })));
+    }
+
+    run_test(query_lengths(num_blocks), actions).await;
+}
+
+#[tokio::test]
+async fn wrong_event_commitment_reports_peer() {
+    let mut rng = get_rng();
+    let mut blocks = create_test_blocks(&mut rng);
+    blocks[0].header.block_header.event_commitment = Some(EventCommitment(felt!("0x1234")));
+    let num_blocks = blocks.len();
+
+    let mut actions = sync_headers_and_transactions(&blocks);
+    actions.push(Action::ReceiveQuery(Box::new(|_query| ()), DataType::Event));
+    for (transaction, transaction_events) in blocks[0].transactions.iter().zip(&blocks[0].events) {
+        for event in transaction_events {
+            actions.push(Action::SendEvent(DataOrFin(Some((
+                event.clone(),
+                transaction.transaction_hash,
+            )))));
+        }
+    }
+    actions.push(Action::ValidateReportSent(DataType::Event));
+
+    run_test(query_lengths(num_blocks), actions).await;
+}
+
+#[tokio::test]
+async fn event_of_transaction_not_in_block_reports_peer() {
+    let mut rng = get_rng();
+    let blocks = create_test_blocks(&mut rng);
+    let num_blocks = blocks.len();
+
+    let mut actions = sync_headers_and_transactions(&blocks);
+    actions.push(Action::ReceiveQuery(Box::new(|_query| ()), DataType::Event));
+    // The first transaction of the second block isn't in the first block.
+    actions.push(Action::SendEvent(DataOrFin(Some((
+        blocks[0].events[0][0].clone(),
+        blocks[1].transactions[0].transaction_hash,
+    )))));
+    actions.push(Action::ValidateReportSent(DataType::Event));
+
+    run_test(query_lengths(num_blocks), actions).await;
+}
@@ -15,6 +15,7 @@ use starknet_api::block::{
     BlockSignature,
 };
 use starknet_api::hash::StarkHash;
+use starknet_class_manager_types::SharedClassManagerClient;
 use starknet_state_sync_types::state_sync_types::SyncBlock;
 use tracing::debug;
 
@@ -25,47 +26,55 @@ use super::stream_builder::{
     DataStreamBuilder,
     ParseDataError,
 };
+use super::verification::DataVerifier;
 use super::{P2PSyncClientError, ALLOWED_SIGNATURES_LENGTH, NETWORK_DATA_TIMEOUT};
 
 impl BlockData for SignedBlockHeader {
     #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
-    fn write_to_storage(
+    fn write_to_storage<'a>(
         self: Box<Self>,
-        storage_writer: &mut StorageWriter,
-    ) -> Result<(), StorageError> {
-        storage_writer
-            .begin_rw_txn()?
-            .append_header(
-                self.block_header.block_header_without_hash.block_number,
-                &self.block_header,
-            )?
-            .append_block_signature(
-                self.block_header.block_header_without_hash.block_number,
-                self
-                    .signatures
-                    // In the future we will support multiple signatures.
-                    .first()
-                    // The verification that the size of the vector is 1 is done in the data
-                    // verification.
-                    .expect("Vec::first should return a value on a vector of size 1"),
-            )?
-            .commit()?;
-        gauge!(
-            papyrus_metrics::PAPYRUS_HEADER_MARKER,
-            self.block_header.block_header_without_hash.block_number.unchecked_next().0 as f64
-        );
-        // TODO(shahak): Fix code dup with central sync
-        let time_delta = Utc::now()
-            - Utc
-                .timestamp_opt(self.block_header.block_header_without_hash.timestamp.0 as i64, 0)
-                .single()
-                .expect("block timestamp should be valid");
-        let header_latency = time_delta.num_seconds();
-        debug!("Header latency: {}.", header_latency);
-        if header_latency >= 0 {
-            gauge!(papyrus_metrics::PAPYRUS_HEADER_LATENCY_SEC, header_latency as f64);
+        storage_writer: &'a mut StorageWriter,
+        _class_manager_client: Option<&'a SharedClassManagerClient>,
+    ) -> BoxFuture<'a, Result<(), P2PSyncClientError>> {
+        async move {
+            storage_writer
+                .begin_rw_txn()?
+                .append_header(
+                    self.block_header.block_header_without_hash.block_number,
+                    &self.block_header,
+                )?
+                .append_block_signature(
+                    self.block_header.block_header_without_hash.block_number,
+                    self
+                        .signatures
+                        // In the future we will support multiple signatures.
+                        .first()
+                        // The verification that the size of the vector is 1 is done in the data
+                        // verification.
+                        .expect("Vec::first should return a value on a vector of size 1"),
+                )?
+                .commit()?;
+            gauge!(
+                papyrus_metrics::PAPYRUS_HEADER_MARKER,
+                self.block_header.block_header_without_hash.block_number.unchecked_next().0 as f64
+            );
+            // TODO(shahak): Fix code dup with central sync
+            let time_delta = Utc::now()
+                - Utc
+                    .timestamp_opt(
+                        self.block_header.block_header_without_hash.timestamp.0 as i64,
+                        0,
+                    )
+                    .single()
+                    .expect("block timestamp should be valid");
+            let header_latency = time_delta.num_seconds();
+            debug!("Header latency: {}.", header_latency);
+            if header_latency >= 0 {
+                gauge!(papyrus_metrics::PAPYRUS_HEADER_LATENCY_SEC, header_latency as f64);
+            }
+            Ok(())
         }
-        Ok(())
+        .boxed()
     }
 }
 
@@ -82,7 +91,8 @@ impl DataStreamBuilder<SignedBlockHeader> for HeaderStreamBuilder {
             DataOrFin<SignedBlockHeader>,
         >,
         block_number: BlockNumber,
-        _storage_reader: &'a StorageReader,
+        storage_reader: &'a StorageReader,
+        verifier: &'a DataVerifier,
     ) -> BoxFuture<'a, Result<Option<Self::Output>, ParseDataError>> {
         async move {
             let maybe_signed_header =
@@ -94,8 +104,6 @@ impl DataStreamBuilder<SignedBlockHeader> for HeaderStreamBuilder {
             let Some(signed_block_header) = maybe_signed_header?.0 else {
                 return Ok(None);
             };
-            // TODO(shahak): Check that parent_hash is the same as the previous block's hash
-            // and handle reverts.
             if block_number
                 != signed_block_header.block_header.block_header_without_hash.block_number
             {
@@ -112,6 +120,15 @@ impl DataStreamBuilder<SignedBlockHeader> for HeaderStreamBuilder {
                     signatures: signed_block_header.signatures,
                 }));
             }
+            verifier.verify_header(&signed_block_header)?;
+            // If the previous header isn't in the storage yet, the parent hash is verified before
+            // the header is written.
+            if let Some(previous_block_hash) =
+                get_previous_block_hash(storage_reader, block_number)?
+            {
+                verifier
+                    .verify_parent_hash(&signed_block_header.block_header, previous_block_hash)?;
+            }
             Ok(Some(signed_block_header))
         }
         .boxed()
@@ -121,6 +138,18 @@ impl DataStreamBuilder<SignedBlockHeader> for HeaderStreamBuilder {
         storage_reader.begin_ro_txn()?.get_header_marker()
     }
 
+    fn verify_before_write(
+        signed_block_header: &SignedBlockHeader,
+        storage_reader: &StorageReader,
+        verifier: &DataVerifier,
+    ) -> Result<(), ParseDataError> {
+        let block_number = signed_block_header.block_header.block_header_without_hash.block_number;
+        if let Some(previous_block_hash) = get_previous_block_hash(storage_reader, block_number)? {
+            verifier.verify_parent_hash(&signed_block_header.block_header, previous_block_hash)?;
+        }
+        Ok(())
+    }
+
     // TODO(Eitan): Use real header once SyncBlock contains data required by full nodes
     // TODO(Eitan): Fill this with real header once SyncBlock has it.
     fn convert_sync_block_to_block_data(
@@ -142,3 +171,17 @@ impl DataStreamBuilder<SignedBlockHeader> for HeaderStreamBuilder {
         })
     }
 }
+
+// Returns None for the genesis block and for blocks whose previous header isn't in the storage.
+fn get_previous_block_hash(
+    storage_reader: &StorageReader,
+    block_number: BlockNumber,
+) -> Result<Option<BlockHash>, StorageError> {
+    let Some(previous_block_number) = block_number.prev() else {
+        return Ok(None);
+    };
+    Ok(storage_reader
+        .begin_ro_txn()?
+        .get_block_header(previous_block_number)?
+        .map(|header| header.block_hash))
+}
@@ -11,14 +11,31 @@ use papyrus_protobuf::sync::{
 };
 use papyrus_storage::header::HeaderStorageReader;
 use papyrus_test_utils::get_rng;
-use starknet_api::block::{BlockHeader, BlockHeaderWithoutHash, BlockNumber};
+use rand_chacha::ChaCha8Rng;
+use starknet_api::block::{
+    BlockHash,
+    BlockHeader,
+    BlockHeaderWithoutHash,
+    BlockNumber,
+    BlockSignature,
+};
+use starknet_api::core::SequencerPublicKey;
+use starknet_api::crypto::utils::{PublicKey, Signature};
+use starknet_api::felt;
+use starknet_crypto::{get_public_key, rfc6979_generate_k, sign};
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Poseidon, StarkHash};
 use tokio::time::timeout;
 
 use super::test_utils::{
+    config_with_query_lengths,
     create_block_hashes_and_signatures,
     random_header,
     run_test,
+    run_test_with_config,
+    set_block_hash_from_header,
     setup,
+    setup_with_num_concurrent_queries,
     wait_for_marker,
     Action,
     DataType,
@@ -29,6 +46,7 @@ use super::test_utils::{
     TIMEOUT_FOR_TEST,
     WAIT_PERIOD_FOR_NEW_DATA,
 };
+use super::{P2PSyncClientConfig, NETWORK_DATA_TIMEOUT};
 
 #[tokio::test]
 async fn signed_headers_basic_flow() {
@@ -198,6 +216,47 @@ async fn sync_sends_new_header_query_if_it_got_partial_responses() {
     }
 }
 
+#[tokio::test]
+async fn header_query_is_sent_again_if_peer_timed_out() {
+    let TestArgs {
+        p2p_sync,
+        mut mock_header_response_manager,
+        // The test will fail if we drop these
+        mock_state_diff_response_manager: _state_diff_receiver,
+        mock_transaction_response_manager: _transaction_receiver,
+        mock_class_response_manager: _class_receiver,
+        ..
+    } = setup();
+
+    // Create a future that will receive a query, let it time out and receive the next query.
+    let parse_queries_future = async move {
+        let mock_header_responses_manager = mock_header_response_manager.next().await.unwrap();
+
+        // Wait for the sync to wait for the first response. Then, simulate time has passed.
+        tokio::time::sleep(SLEEP_DURATION_TO_LET_SYNC_ADVANCE).await;
+        tokio::time::pause();
+        tokio::time::advance(NETWORK_DATA_TIMEOUT).await;
+        tokio::time::resume();
+
+        mock_header_responses_manager.assert_reported(TIMEOUT_FOR_TEST).await;
+        // First unwrap is for the timeout. Second unwrap is for the Option returned from Stream.
+        let mock_header_responses_manager =
+            timeout(TIMEOUT_FOR_TEST, mock_header_response_manager.next()).await.unwrap().unwrap();
+        let Ok(HeaderQuery(query)) = mock_header_responses_manager.query() else {
+            panic!("Failed to parse the header query");
+        };
+        assert_eq!(query.start_block, BlockHashOrNumber::Number(BlockNumber(0)));
+    };
+
+    tokio::select! {
+        sync_result = p2p_sync.run() => {
+            sync_result.unwrap();
+            panic!("P2P sync aborted with no failure.");
+        }
+        _ = parse_queries_future => {}
+    }
+}
+
 #[tokio::test]
 async fn wrong_block_number() {
     run_test(
@@ -224,4 +283,411 @@ async fn wrong_block_number() {
     .await;
 }
 
+#[tokio::test]
+async fn wrong_block_hash_reports_peer() {
+    let mut header = random_header(&mut get_rng(), BlockNumber(0), None, None);
+    set_block_hash_from_header(&mut header.block_header);
+    header.block_header.block_hash = BlockHash(felt!("0x1234"));
+
+    run_test_with_config(
+        P2PSyncClientConfig {
+            verify_blocks: true,
+            ..config_with_query_lengths(&HashMap::from([(DataType::Header, 1)]))
+        },
+        vec![
+            Action::RunP2pSync,
+            // We already validate the query content in other tests.
+            Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+            Action::SendHeader(DataOrFin(Some(header))),
+            Action::ValidateReportSent(DataType::Header),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn wrong_parent_hash_reports_peer() {
+    let mut rng = get_rng();
+    let mut first_header = random_header(&mut rng, BlockNumber(0), None, None);
+    set_block_hash_from_header(&mut first_header.block_header);
+    let mut second_header = random_header(&mut rng, BlockNumber(1), None, None);
+    second_header.block_header.block_header_without_hash.parent_hash = BlockHash(felt!("0x1234"));
+    set_block_hash_from_header(&mut second_header.block_header);
+
+    run_test_with_config(
+        P2PSyncClientConfig {
+            verify_blocks: true,
+            ..config_with_query_lengths(&HashMap::from([(DataType::Header, 1)]))
+        },
+        vec![
+            Action::RunP2pSync,
+            // We already validate the query content in other tests.
+            Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+            Action::SendHeader(DataOrFin(Some(first_header))),
+            Action::SendHeader(DataOrFin(None)),
+            Action::CheckStorage(Box::new(|reader| {
+                async move {
+                    wait_for_marker(
+                        DataType::Header,
+                        &reader,
+                        BlockNumber(1),
+                        SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+                        TIMEOUT_FOR_TEST,
+                    )
+                    .await;
+                }
+                .boxed()
+            })),
+            Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+            Action::SendHeader(DataOrFin(Some(second_header))),
+            Action::ValidateReportSent(DataType::Header),
+        ],
+    )
+    .await;
+}
+
+const SEQUENCER_PRIVATE_KEY: Felt = Felt::from_hex_unchecked("0x1234");
+
+// Returns a header whose hash is calculated from its content and that's signed by the sequencer
+// with the given private key.
+fn signed_header(
+    rng: &mut ChaCha8Rng,
+    block_number: BlockNumber,
+    parent_hash: BlockHash,
+    sequencer_private_key: Felt,
+) -> SignedBlockHeader {
+    let mut header = random_header(rng, block_number, None, None);
+    header.block_header.block_header_without_hash.parent_hash = parent_hash;
+    set_block_hash_from_header(&mut header.block_header);
+    let message_hash = Poseidon::hash_array(&[
+        header.block_header.block_hash.0,
+        header.block_header.state_diff_commitment.unwrap().0.0,
+    ]);
+    let k = rfc6979_generate_k(&message_hash, &sequencer_private_key, None);
+    let signature = sign(&sequencer_private_key, &message_hash, &k).unwrap();
+    header.signatures = vec![BlockSignature(Signature { r: signature.r, s: signature.s })];
+    header
+}
+
+fn sequencer_public_key(sequencer_private_key: Felt) -> SequencerPublicKey {
+    SequencerPublicKey(PublicKey(get_public_key(&sequencer_private_key)))
+}
+
+#[tokio::test]
+async fn header_signed_by_sequencer_is_written() {
+    let header =
+        signed_header(&mut get_rng(), BlockNumber(0), BlockHash::default(), SEQUENCER_PRIVATE_KEY);
+
+    run_test_with_config(
+        P2PSyncClientConfig {
+            verify_blocks: true,
+            sequencer_public_key: Some(sequencer_public_key(SEQUENCER_PRIVATE_KEY)),
+            ..config_with_query_lengths(&HashMap::from([(DataType::Header, 1)]))
+        },
+        vec![
+            Action::RunP2pSync,
+            // We already validate the query content in other tests.
+            Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+            Action::SendHeader(DataOrFin(Some(header))),
+            Action::SendHeader(DataOrFin(None)),
+            Action::CheckStorage(Box::new(|reader| {
+                async move {
+                    wait_for_marker(
+                        DataType::Header,
+                        &reader,
+                        BlockNumber(1),
+                        SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+                        TIMEOUT_FOR_TEST,
+                    )
+                    .await;
+                }
+                .boxed()
+            })),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn wrong_signature_reports_peer() {
+    let mut header =
+        signed_header(&mut get_rng(), BlockNumber(0), BlockHash::default(), SEQUENCER_PRIVATE_KEY);
+    header.signatures[0].0.r = felt!("0x1234");
+
+    run_test_with_config(
+        P2PSyncClientConfig {
+            verify_blocks: true,
+            sequencer_public_key: Some(sequencer_public_key(SEQUENCER_PRIVATE_KEY)),
+            ..config_with_query_lengths(&HashMap::from([(DataType::Header, 1)]))
+        },
+        vec![
+            Action::RunP2pSync,
+            // We already validate the query content in other tests.
+            Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+            Action::SendHeader(DataOrFin(Some(header))),
+            Action::ValidateReportSent(DataType::Header),
+            Action::CheckStorage(Box::new(|reader| {
+                async move {
+                    assert_eq!(0, reader.begin_ro_txn().unwrap().get_header_marker().unwrap().0);
+                }
+                .boxed()
+            })),
+        ],
+    )
+    .await;
+}
+
+// A signed header whose parent hash doesn't match the storage means the chain was reverted.
+#[tokio::test]
+#[should_panic(expected = "RevertNotSupported")]
+async fn signed_header_with_wrong_parent_hash_fails_on_revert() {
+    let mut rng = get_rng();
+    let first_header =
+        signed_header(&mut rng, BlockNumber(0), BlockHash::default(), SEQUENCER_PRIVATE_KEY);
+    let second_header =
+        signed_header(&mut rng, BlockNumber(1), BlockHash(felt!("0x1234")), SEQUENCER_PRIVATE_KEY);
+
+    run_test_with_config(
+        P2PSyncClientConfig {
+            verify_blocks: true,
+            sequencer_public_key: Some(sequencer_public_key(SEQUENCER_PRIVATE_KEY)),
+            ..config_with_query_lengths(&HashMap::from([(DataType::Header, 1)]))
+        },
+        vec![
+            Action::RunP2pSync,
+            // We already validate the query content in other tests.
+            Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+            Action::SendHeader(DataOrFin(Some(first_header))),
+            Action::SendHeader(DataOrFin(None)),
+            Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+            Action::SendHeader(DataOrFin(Some(second_header))),
+            // Wait for the sync to fail.
+            Action::CheckStorage(Box::new(|_reader| futures::future::pending().boxed())),
+        ],
+    )
+    .await;
+}
+
+fn header_response(
+    block_number: u64,
+    (block_hash, signature): (BlockHash, BlockSignature),
+) -> DataOrFin<SignedBlockHeader> {
+    DataOrFin(Some(SignedBlockHeader {
+        block_header: BlockHeader {
+            block_hash,
+            block_header_without_hash: BlockHeaderWithoutHash {
+                block_number: BlockNumber(block_number),
+                ..Default::default()
+            },
+            state_diff_length: Some(0),
+            ..Default::default()
+        },
+        signatures: vec![signature],
+    }))
+}
+
+#[tokio::test]
+async fn concurrent_header_queries_are_written_in_order() {
+    let TestArgs {
+        p2p_sync,
+        storage_reader,
+        mut mock_header_response_manager,
+        // The test will fail if we drop these
+        mock_state_diff_response_manager: _state_diff_receiver,
+        mock_transaction_response_manager: _transaction_receiver,
+        mock_class_response_manager: _class_receiver,
+        ..
+    } = setup_with_num_concurrent_queries(2);
+    let block_hashes_and_signatures =
+        create_block_hashes_and_signatures((2 * HEADER_QUERY_LENGTH).try_into().unwrap());
+
+    let parse_queries_future = async move {
+        let mut first_responses_manager = mock_header_response_manager.next().await.unwrap();
+        let mut second_responses_manager = mock_header_response_manager.next().await.unwrap();
+        for (responses_manager, start_block_number) in
+            [(&first_responses_manager, 0), (&second_responses_manager, HEADER_QUERY_LENGTH)]
+        {
+            assert_eq!(
+                *responses_manager.query(),
+                Ok(HeaderQuery(Query {
+                    start_block: BlockHashOrNumber::Number(BlockNumber(start_block_number)),
+                    direction: Direction::Forward,
+                    limit: HEADER_QUERY_LENGTH,
+                    step: 1,
+                }))
+            );
+        }
+
+        // Answer the second query first. Its headers can't be written before the first query's.
+        for block_number in HEADER_QUERY_LENGTH..2 * HEADER_QUERY_LENGTH {
+            second_responses_manager
+                .send_response(header_response(
+                    block_number,
+                    block_hashes_and_signatures[usize::try_from(block_number).unwrap()],
+                ))
+                .await
+                .unwrap();
+        }
+        second_responses_manager.send_response(DataOrFin(None)).await.unwrap();
+        tokio::time::sleep(SLEEP_DURATION_TO_LET_SYNC_ADVANCE).await;
+        assert_eq!(
+            storage_reader.begin_ro_txn().unwrap().get_header_marker().unwrap(),
+            BlockNumber(0)
+        );
+
+        for block_number in 0..HEADER_QUERY_LENGTH {
+            first_responses_manager
+                .send_response(header_response(
+                    block_number,
+                    block_hashes_and_signatures[usize::try_from(block_number).unwrap()],
+                ))
+                .await
+                .unwrap();
+        }
+        first_responses_manager.send_response(DataOrFin(None)).await.unwrap();
+        wait_for_marker(
+            DataType::Header,
+            &storage_reader,
+            BlockNumber(2 * HEADER_QUERY_LENGTH),
+            SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+            TIMEOUT_FOR_TEST,
+        )
+        .await;
+
+        let txn = storage_reader.begin_ro_txn().unwrap();
+        for (block_number, (block_hash, _signature)) in
+            block_hashes_and_signatures.into_iter().enumerate()
+        {
+            let block_header = txn
+                .get_block_header(BlockNumber(block_number.try_into().unwrap()))
+                .unwrap()
+                .unwrap();
+            assert_eq!(block_hash, block_header.block_hash);
+        }
+    };
+
+    tokio::select! {
+        sync_result = p2p_sync.run() => {
+            sync_result.unwrap();
+            panic!("P2P sync aborted with no failure.");
+        }
+        _ = parse_queries_future => {}
+    }
+}
+
+#[tokio::test]
+async fn queries_are_sent_only_within_the_lookahead_window() {
+    const NUM_CONCURRENT_QUERIES: u64 = 2;
+    let TestArgs {
+        p2p_sync,
+        mut mock_header_response_manager,
+        // The test will fail if we drop these
+        mock_state_diff_response_manager: _state_diff_receiver,
+        mock_transaction_response_manager: _transaction_receiver,
+        mock_class_response_manager: _class_receiver,
+        ..
+    } = setup_with_num_concurrent_queries(NUM_CONCURRENT_QUERIES.try_into().unwrap());
+    let block_hashes_and_signatures = create_block_hashes_and_signatures(
+        (NUM_CONCURRENT_QUERIES * HEADER_QUERY_LENGTH).try_into().unwrap(),
+    );
+
+    let parse_queries_future = async move {
+        let mut first_responses_manager = mock_header_response_manager.next().await.unwrap();
+        let mut second_responses_manager = mock_header_response_manager.next().await.unwrap();
+
+        // Answer the second query. The blocks after it are outside the window until the first
+        // query's blocks are written.
+        for block_number in HEADER_QUERY_LENGTH..2 * HEADER_QUERY_LENGTH {
+            second_responses_manager
+                .send_response(header_response(
+                    block_number,
+                    block_hashes_and_signatures[usize::try_from(block_number).unwrap()],
+                ))
+                .await
+                .unwrap();
+        }
+        second_responses_manager.send_response(DataOrFin(None)).await.unwrap();
+        assert!(
+            timeout(WAIT_PERIOD_FOR_NEW_DATA, mock_header_response_manager.next()).await.is_err()
+        );
+
+        for block_number in 0..HEADER_QUERY_LENGTH {
+            first_responses_manager
+                .send_response(header_response(
+                    block_number,
+                    block_hashes_and_signatures[usize::try_from(block_number).unwrap()],
+                ))
+                .await
+                .unwrap();
+        }
+        first_responses_manager.send_response(DataOrFin(None)).await.unwrap();
+
+        // First unwrap is for the timeout. Second unwrap is for the Option returned from Stream.
+        let third_responses_manager =
+            timeout(TIMEOUT_FOR_TEST, mock_header_response_manager.next()).await.unwrap().unwrap();
+        let Ok(HeaderQuery(query)) = third_responses_manager.query() else {
+            panic!("Failed to parse the header query");
+        };
+        assert_eq!(
+            query.start_block,
+            BlockHashOrNumber::Number(BlockNumber(NUM_CONCURRENT_QUERIES * HEADER_QUERY_LENGTH))
+        );
+    };
+
+    tokio::select! {
+        sync_result = p2p_sync.run() => {
+            sync_result.unwrap();
+            panic!("P2P sync aborted with no failure.");
+        }
+        _ = parse_queries_future => {}
+    }
+}
+
+#[tokio::test]
+async fn range_of_bad_peer_is_queried_again_with_smaller_size() {
+    let TestArgs {
+        p2p_sync,
+        mut mock_header_response_manager,
+        // The test will fail if we drop these
+        mock_state_diff_response_manager: _state_diff_receiver,
+        mock_transaction_response_manager: _transaction_receiver,
+        mock_class_response_manager: _class_receiver,
+        ..
+    } = setup_with_num_concurrent_queries(2);
+    let block_hashes_and_signatures = create_block_hashes_and_signatures(1);
+
+    let parse_queries_future = async move {
+        let _first_responses_manager = mock_header_response_manager.next().await.unwrap();
+        let mut second_responses_manager = mock_header_response_manager.next().await.unwrap();
+
+        // Send a header of the wrong block for the second range.
+        second_responses_manager
+            .send_response(header_response(0, block_hashes_and_signatures[0]))
+            .await
+            .unwrap();
+        second_responses_manager.assert_reported(TIMEOUT_FOR_TEST).await;
+
+        // The failed range is queried again, with half the range size.
+        let retry_responses_manager =
+            timeout(TIMEOUT_FOR_TEST, mock_header_response_manager.next()).await.unwrap().unwrap();
+        assert_eq!(
+            *retry_responses_manager.query(),
+            Ok(HeaderQuery(Query {
+                start_block: BlockHashOrNumber::Number(BlockNumber(HEADER_QUERY_LENGTH)),
+                direction: Direction::Forward,
+                limit: HEADER_QUERY_LENGTH / 2,
+                step: 1,
+            }))
+        );
+    };
+
+    tokio::select! {
+        sync_result = p2p_sync.run() => {
+            sync_result.unwrap();
+            panic!("P2P sync aborted with no failure.");
+        }
+        _ = parse_queries_future => {}
+    }
+}
+
 // TODO(shahak): Add more negative tests.
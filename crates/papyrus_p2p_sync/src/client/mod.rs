@@ -1,6 +1,12 @@
 mod class;
 #[cfg(test)]
 mod class_test;
+mod download_scheduler;
+#[cfg(test)]
+mod download_scheduler_test;
+mod event;
+#[cfg(test)]
+mod event_test;
 mod header;
 #[cfg(test)]
 mod header_test;
@@ -13,23 +19,28 @@ mod test_utils;
 mod transaction;
 #[cfg(test)]
 mod transaction_test;
+mod verification;
+#[cfg(test)]
+mod verification_test;
 
 use std::collections::BTreeMap;
 use std::time::Duration;
 
 use class::ClassStreamBuilder;
+use event::EventStreamBuilder;
 use futures::channel::mpsc::{Receiver, SendError, Sender};
 use futures::stream::BoxStream;
 use futures::{SinkExt as _, Stream};
 use header::HeaderStreamBuilder;
 use papyrus_common::pending_classes::ApiContractClass;
 use papyrus_config::converters::deserialize_milliseconds_to_duration;
-use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::dumping::{ser_optional_param, ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_network::network_manager::SqmrClientSender;
 use papyrus_protobuf::sync::{
     ClassQuery,
     DataOrFin,
+    EventQuery,
     HeaderQuery,
     SignedBlockHeader,
     StateDiffChunk,
@@ -38,15 +49,17 @@ use papyrus_protobuf::sync::{
 };
 use papyrus_storage::{StorageError, StorageReader, StorageWriter};
 use serde::{Deserialize, Serialize};
-use starknet_api::block::BlockNumber;
-use starknet_api::core::ClassHash;
-use starknet_api::transaction::FullTransaction;
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::{ChainId, ClassHash, SequencerPublicKey};
+use starknet_api::transaction::{Event, FullTransaction, TransactionHash};
+use starknet_class_manager_types::{ClassManagerClientError, SharedClassManagerClient};
 use starknet_state_sync_types::state_sync_types::SyncBlock;
 use state_diff::StateDiffStreamBuilder;
 use stream_builder::{DataStreamBuilder, DataStreamResult};
 use tokio_stream::StreamExt;
 use tracing::{info, instrument};
 use transaction::TransactionStreamFactory;
+use verification::DataVerifier;
 
 const STEP: u64 = 1;
 const ALLOWED_SIGNATURES_LENGTH: usize = 1;
@@ -59,14 +72,18 @@ pub struct P2PSyncClientConfig {
     pub num_block_state_diffs_per_query: u64,
     pub num_block_transactions_per_query: u64,
     pub num_block_classes_per_query: u64,
+    pub num_block_events_per_query: u64,
+    pub num_concurrent_queries: usize,
     #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
     pub wait_period_for_new_data: Duration,
     pub buffer_size: usize,
+    pub verify_blocks: bool,
+    pub sequencer_public_key: Option<SequencerPublicKey>,
 }
 
 impl SerializeConfig for P2PSyncClientConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        BTreeMap::from_iter([
+        let mut config = BTreeMap::from_iter([
             ser_param(
                 "num_headers_per_query",
                 &self.num_headers_per_query,
@@ -92,6 +109,23 @@ impl SerializeConfig for P2PSyncClientConfig {
                 "The maximum amount of block's classes to ask from peers in each iteration.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "num_block_events_per_query",
+                &self.num_block_events_per_query,
+                "The maximum amount of blocks to ask their events from peers in each iteration.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "num_concurrent_queries",
+                &self.num_concurrent_queries,
+                "The maximum amount of queries each data type sends to peers concurrently. \
+                 Consecutive queries are sent to different peers, and the responses are written \
+                 to the storage in order. The size of the queries is adapted to the throughput \
+                 measured over all the peers together, since the sync doesn't know which peer \
+                 serves each query, so a slow peer shrinks the queries sent to the other peers as \
+                 well.",
+                ParamPrivacyInput::Public,
+            ),
             ser_param(
                 "wait_period_for_new_data",
                 &self.wait_period_for_new_data.as_millis(),
@@ -105,7 +139,23 @@ impl SerializeConfig for P2PSyncClientConfig {
                 "Size of the buffer for read from the storage and for incoming responses.",
                 ParamPrivacyInput::Public,
             ),
-        ])
+            ser_param(
+                "verify_blocks",
+                &self.verify_blocks,
+                "Whether to verify the hashes, signatures and commitments of the data received \
+                 from peers.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
+        config.extend(ser_optional_param(
+            &self.sequencer_public_key,
+            SequencerPublicKey::default(),
+            "sequencer_public_key",
+            "The public key of the sequencer, used for verifying the signatures of the headers \
+             received from peers. If it isn't set, the signatures aren't verified.",
+            ParamPrivacyInput::Public,
+        ));
+        config
     }
 }
 
@@ -118,9 +168,13 @@ impl Default for P2PSyncClientConfig {
             num_block_state_diffs_per_query: 100,
             num_block_transactions_per_query: 100,
             num_block_classes_per_query: 100,
+            num_block_events_per_query: 100,
+            num_concurrent_queries: 4,
             wait_period_for_new_data: Duration::from_millis(50),
             // TODO(eitan): split this by protocol
             buffer_size: 100000,
+            verify_blocks: true,
+            sequencer_public_key: None,
         }
     }
 }
@@ -136,20 +190,31 @@ pub enum P2PSyncClientError {
          field."
     )]
     OldHeaderInStorage { block_number: BlockNumber, missing_field: &'static str },
+    #[error(
+        "The signed header of {block_number} has the parent hash {parent_hash}, which doesn't \
+         match the hash of the previous block in the storage {previous_block_hash}. The chain was \
+         reverted, and reverts aren't supported by P2P sync."
+    )]
+    RevertNotSupported {
+        block_number: BlockNumber,
+        parent_hash: BlockHash,
+        previous_block_hash: BlockHash,
+    },
     #[error("The sender end of the response receivers for {type_description:?} was closed.")]
     ReceiverChannelTerminated { type_description: &'static str },
     #[error(transparent)]
-    NetworkTimeout(#[from] tokio::time::error::Elapsed),
-    #[error(transparent)]
     StorageError(#[from] StorageError),
     #[error(transparent)]
     SendError(#[from] SendError),
+    #[error(transparent)]
+    ClassManagerClientError(#[from] ClassManagerClientError),
 }
 
 type HeaderSqmrSender = SqmrClientSender<HeaderQuery, DataOrFin<SignedBlockHeader>>;
 type StateSqmrDiffSender = SqmrClientSender<StateDiffQuery, DataOrFin<StateDiffChunk>>;
 type TransactionSqmrSender = SqmrClientSender<TransactionQuery, DataOrFin<FullTransaction>>;
 type ClassSqmrSender = SqmrClientSender<ClassQuery, DataOrFin<(ApiContractClass, ClassHash)>>;
+type EventSqmrSender = SqmrClientSender<EventQuery, DataOrFin<(Event, TransactionHash)>>;
 
 pub struct P2PSyncClientChannels {
     header_sender: HeaderSqmrSender,
@@ -157,6 +222,7 @@ pub struct P2PSyncClientChannels {
     transaction_sender: TransactionSqmrSender,
     #[allow(dead_code)]
     class_sender: ClassSqmrSender,
+    event_sender: EventSqmrSender,
 }
 
 impl P2PSyncClientChannels {
@@ -165,68 +231,104 @@ impl P2PSyncClientChannels {
         state_diff_sender: StateSqmrDiffSender,
         transaction_sender: TransactionSqmrSender,
         class_sender: ClassSqmrSender,
+        event_sender: EventSqmrSender,
     ) -> Self {
-        Self { header_sender, state_diff_sender, transaction_sender, class_sender }
+        Self { header_sender, state_diff_sender, transaction_sender, class_sender, event_sender }
     }
     pub(crate) fn create_stream(
         self,
         storage_reader: StorageReader,
         config: P2PSyncClientConfig,
+        verifier: DataVerifier,
         internal_blocks_receivers: InternalBlocksReceivers,
     ) -> impl Stream<Item = DataStreamResult> + Send + 'static {
         let header_stream = HeaderStreamBuilder::create_stream(
             self.header_sender,
             storage_reader.clone(),
+            verifier.clone(),
             Some(internal_blocks_receivers.header_receiver),
             config.wait_period_for_new_data,
             config.num_headers_per_query,
+            config.num_concurrent_queries,
         );
 
         let state_diff_stream = StateDiffStreamBuilder::create_stream(
             self.state_diff_sender,
             storage_reader.clone(),
+            verifier.clone(),
             Some(internal_blocks_receivers.state_diff_receiver),
             config.wait_period_for_new_data,
             config.num_block_state_diffs_per_query,
+            config.num_concurrent_queries,
         );
 
         let transaction_stream = TransactionStreamFactory::create_stream(
             self.transaction_sender,
             storage_reader.clone(),
+            verifier.clone(),
             Some(internal_blocks_receivers.transaction_receiver),
             config.wait_period_for_new_data,
             config.num_block_transactions_per_query,
+            config.num_concurrent_queries,
         );
 
         let class_stream = ClassStreamBuilder::create_stream(
             self.class_sender,
             storage_reader.clone(),
-            None,
+            verifier.clone(),
+            Some(internal_blocks_receivers.class_receiver),
             config.wait_period_for_new_data,
             config.num_block_classes_per_query,
+            config.num_concurrent_queries,
+        );
+
+        let event_stream = EventStreamBuilder::create_stream(
+            self.event_sender,
+            storage_reader.clone(),
+            verifier,
+            None,
+            config.wait_period_for_new_data,
+            config.num_block_events_per_query,
+            config.num_concurrent_queries,
         );
 
-        header_stream.merge(state_diff_stream).merge(transaction_stream).merge(class_stream)
+        header_stream
+            .merge(state_diff_stream)
+            .merge(transaction_stream)
+            .merge(class_stream)
+            .merge(event_stream)
     }
 }
 
 pub struct P2PSyncClient {
     config: P2PSyncClientConfig,
+    chain_id: ChainId,
     storage_reader: StorageReader,
     storage_writer: StorageWriter,
     p2p_sync_channels: P2PSyncClientChannels,
     internal_blocks_receiver: BoxStream<'static, (BlockNumber, SyncBlock)>,
+    class_manager_client: Option<SharedClassManagerClient>,
 }
 
 impl P2PSyncClient {
     pub fn new(
         config: P2PSyncClientConfig,
+        chain_id: ChainId,
         storage_reader: StorageReader,
         storage_writer: StorageWriter,
         p2p_sync_channels: P2PSyncClientChannels,
         internal_blocks_receiver: BoxStream<'static, (BlockNumber, SyncBlock)>,
+        class_manager_client: Option<SharedClassManagerClient>,
     ) -> Self {
-        Self { config, storage_reader, storage_writer, p2p_sync_channels, internal_blocks_receiver }
+        Self {
+            config,
+            chain_id,
+            storage_reader,
+            storage_writer,
+            p2p_sync_channels,
+            internal_blocks_receiver,
+            class_manager_client,
+        }
     }
 
     #[instrument(skip(self), level = "debug", err)]
@@ -239,13 +341,20 @@ impl P2PSyncClient {
         } = InternalBlocksChannels::new();
         let P2PSyncClient {
             config,
+            chain_id,
             storage_reader,
             mut storage_writer,
             p2p_sync_channels,
             mut internal_blocks_receiver,
+            class_manager_client,
         } = self;
-        let mut data_stream =
-            p2p_sync_channels.create_stream(storage_reader, config, internal_blocks_receivers);
+        let verifier = DataVerifier::new(chain_id, &config, class_manager_client.clone());
+        let mut data_stream = p2p_sync_channels.create_stream(
+            storage_reader,
+            config,
+            verifier,
+            internal_blocks_receivers,
+        );
 
         loop {
             tokio::select! {
@@ -255,11 +364,12 @@ impl P2PSyncClient {
                 }
                 data = data_stream.next() => {
                     let data = data.expect("Sync data stream should never end")?;
-                    data.write_to_storage(&mut storage_writer)?;
+                    data.write_to_storage(&mut storage_writer, class_manager_client.as_ref())
+                        .await?;
                 }
             }
             let data = data_stream.next().await.expect("Sync data stream should never end")?;
-            data.write_to_storage(&mut storage_writer)?;
+            data.write_to_storage(&mut storage_writer, class_manager_client.as_ref()).await?;
         }
     }
 }
@@ -268,7 +378,6 @@ pub(crate) struct InternalBlocksReceivers {
     header_receiver: Receiver<(BlockNumber, SyncBlock)>,
     state_diff_receiver: Receiver<(BlockNumber, SyncBlock)>,
     transaction_receiver: Receiver<(BlockNumber, SyncBlock)>,
-    #[allow(dead_code)]
     class_receiver: Receiver<(BlockNumber, SyncBlock)>,
 }
 
@@ -276,7 +385,6 @@ pub struct InternalBlocksSenders {
     header_sender: Sender<(BlockNumber, SyncBlock)>,
     state_diff_sender: Sender<(BlockNumber, SyncBlock)>,
     transaction_sender: Sender<(BlockNumber, SyncBlock)>,
-    #[allow(dead_code)]
     class_sender: Sender<(BlockNumber, SyncBlock)>,
 }
 impl InternalBlocksSenders {
@@ -12,6 +12,7 @@ use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
 use papyrus_storage::{StorageError, StorageReader, StorageWriter};
 use starknet_api::block::BlockNumber;
 use starknet_api::state::ThinStateDiff;
+use starknet_class_manager_types::SharedClassManagerClient;
 use starknet_state_sync_types::state_sync_types::SyncBlock;
 
 use super::stream_builder::BadPeerError;
@@ -21,21 +22,32 @@ use crate::client::stream_builder::{
     DataStreamBuilder,
     ParseDataError,
 };
+use crate::client::verification::DataVerifier;
 use crate::client::{P2PSyncClientError, NETWORK_DATA_TIMEOUT};
 
 impl BlockData for (ThinStateDiff, BlockNumber) {
-    #[latency_histogram("p2p_sync_state_diff_write_to_storage_latency_seconds", true)]
-    #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
-    fn write_to_storage(
+    fn write_to_storage<'a>(
         self: Box<Self>,
-        storage_writer: &mut StorageWriter,
-    ) -> Result<(), StorageError> {
-        storage_writer.begin_rw_txn()?.append_state_diff(self.1, self.0)?.commit()?;
-        gauge!(papyrus_metrics::PAPYRUS_STATE_MARKER, self.1.unchecked_next().0 as f64);
-        Ok(())
+        storage_writer: &'a mut StorageWriter,
+        _class_manager_client: Option<&'a SharedClassManagerClient>,
+    ) -> BoxFuture<'a, Result<(), P2PSyncClientError>> {
+        async move { Ok(write_state_diff(storage_writer, self.1, self.0)?) }.boxed()
     }
 }
 
+// The write is synchronous, so it's timed outside of the future that `write_to_storage` returns.
+#[latency_histogram("p2p_sync_state_diff_write_to_storage_latency_seconds", true)]
+#[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
+fn write_state_diff(
+    storage_writer: &mut StorageWriter,
+    block_number: BlockNumber,
+    state_diff: ThinStateDiff,
+) -> Result<(), StorageError> {
+    storage_writer.begin_rw_txn()?.append_state_diff(block_number, state_diff)?.commit()?;
+    gauge!(papyrus_metrics::PAPYRUS_STATE_MARKER, block_number.unchecked_next().0 as f64);
+    Ok(())
+}
+
 pub(crate) struct StateDiffStreamBuilder;
 
 impl DataStreamBuilder<StateDiffChunk> for StateDiffStreamBuilder {
@@ -51,17 +63,18 @@ impl DataStreamBuilder<StateDiffChunk> for StateDiffStreamBuilder {
         >,
         block_number: BlockNumber,
         storage_reader: &'a StorageReader,
+        verifier: &'a DataVerifier,
     ) -> BoxFuture<'a, Result<Option<Self::Output>, ParseDataError>> {
         async move {
             let mut result = ThinStateDiff::default();
             let mut prev_result_len = 0;
             let mut current_state_diff_len = 0;
-            let target_state_diff_len = storage_reader
+            let header = storage_reader
                 .begin_ro_txn()?
                 .get_block_header(block_number)?
-                .expect("A header with number lower than the header marker is missing")
-                .state_diff_length
-                .ok_or(P2PSyncClientError::OldHeaderInStorage {
+                .expect("A header with number lower than the header marker is missing");
+            let target_state_diff_len =
+                header.state_diff_length.ok_or(P2PSyncClientError::OldHeaderInStorage {
                     block_number,
                     missing_field: "state_diff_length",
                 })?;
@@ -103,6 +116,7 @@ impl DataStreamBuilder<StateDiffChunk> for StateDiffStreamBuilder {
             }
 
             validate_deprecated_declared_classes_non_conflicting(&result)?;
+            verifier.verify_state_diff(&header, &result)?;
             Ok(Some((result, block_number)))
         }
         .boxed()
@@ -20,14 +20,18 @@ use starknet_api::state::{StorageKey, ThinStateDiff};
 use starknet_types_core::felt::Felt;
 
 use super::test_utils::{
+    config_with_query_lengths,
     random_header,
     run_test,
+    run_test_with_config,
+    set_block_hash_from_header,
     wait_for_marker,
     Action,
     DataType,
     SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
     TIMEOUT_FOR_TEST,
 };
+use super::P2PSyncClientConfig;
 
 #[tokio::test]
 async fn state_diff_basic_flow() {
@@ -300,6 +304,38 @@ async fn state_diff_conflicting() {
     .await;
 }
 
+#[tokio::test]
+async fn wrong_state_diff_commitment_reports_peer() {
+    // The state diff commitment of the random header doesn't match the state diff.
+    let mut header = random_header(&mut get_rng(), BlockNumber(0), Some(1), None);
+    set_block_hash_from_header(&mut header.block_header);
+
+    run_test_with_config(
+        P2PSyncClientConfig {
+            verify_blocks: true,
+            ..config_with_query_lengths(&HashMap::from([
+                (DataType::Header, 1),
+                (DataType::StateDiff, 1),
+            ]))
+        },
+        vec![
+            Action::RunP2pSync,
+            // We already validate the query content in other tests.
+            Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+            Action::SendHeader(DataOrFin(Some(header))),
+            Action::SendHeader(DataOrFin(None)),
+            Action::ReceiveQuery(Box::new(|_query| ()), DataType::StateDiff),
+            Action::SendStateDiff(DataOrFin(Some(StateDiffChunk::ContractDiff(ContractDiff {
+                contract_address: ContractAddress::default(),
+                nonce: Some(Nonce::default()),
+                ..Default::default()
+            })))),
+            Action::ValidateReportSent(DataType::StateDiff),
+        ],
+    )
+    .await;
+}
+
 async fn validate_state_diff_fails(
     header_state_diff_lengths: Vec<usize>,
     state_diff_chunks: Vec<Option<StateDiffChunk>>,
@@ -1,39 +1,56 @@
 use std::cmp::min;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::time::Duration;
 
 use async_stream::stream;
 use futures::channel::mpsc::Receiver;
 use futures::future::BoxFuture;
-use futures::stream::BoxStream;
+use futures::stream::{BoxStream, SelectAll};
 use futures::{FutureExt, StreamExt};
 use papyrus_network::network_manager::{ClientResponsesManager, SqmrClientSender};
 use papyrus_protobuf::converters::ProtobufConversionError;
 use papyrus_protobuf::sync::{BlockHashOrNumber, DataOrFin, Direction, Query};
+use papyrus_storage::body::BodyStorageReader;
 use papyrus_storage::header::HeaderStorageReader;
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{StorageError, StorageReader, StorageWriter};
-use starknet_api::block::{BlockNumber, BlockSignature};
-use starknet_api::core::ClassHash;
+use starknet_api::block::{BlockHash, BlockNumber, BlockSignature, StarknetVersion};
+use starknet_api::core::{
+    ClassHash,
+    CompiledClassHash,
+    EventCommitment,
+    ReceiptCommitment,
+    StateDiffCommitment,
+    TransactionCommitment,
+};
+use starknet_api::transaction::TransactionHash;
+use starknet_class_manager_types::SharedClassManagerClient;
 use starknet_state_sync_types::state_sync_types::SyncBlock;
+use tokio::time::Instant;
 use tracing::{debug, info, warn};
 
+use super::download_scheduler::{BlockRange, DownloadScheduler};
+use super::verification::DataVerifier;
 use super::{P2PSyncClientError, STEP};
 
 pub type DataStreamResult = Result<Box<dyn BlockData>, P2PSyncClientError>;
 
 pub(crate) trait BlockData: Send {
-    fn write_to_storage(
+    /// Writes the block's data. Classes are added to the class manager, if there is one, before
+    /// the storage marks the block's classes as written.
+    fn write_to_storage<'a>(
         // This is Box<Self> in order to allow using it with `Box<dyn BlockData>`.
         self: Box<Self>,
-        storage_writer: &mut StorageWriter,
-    ) -> Result<(), StorageError>;
+        storage_writer: &'a mut StorageWriter,
+        class_manager_client: Option<&'a SharedClassManagerClient>,
+    ) -> BoxFuture<'a, Result<(), P2PSyncClientError>>;
 }
 
 pub(crate) enum BlockNumberLimit {
     Unlimited,
     HeaderMarker,
     StateDiffMarker,
+    BodyMarker,
 }
 
 pub(crate) trait DataStreamBuilder<InputFromNetwork>
@@ -51,12 +68,23 @@ where
         client_response_manager: &'a mut ClientResponsesManager<DataOrFin<InputFromNetwork>>,
         block_number: BlockNumber,
         storage_reader: &'a StorageReader,
+        verifier: &'a DataVerifier,
     ) -> BoxFuture<'a, Result<Option<Self::Output>, ParseDataError>>;
 
     fn get_start_block_number(storage_reader: &StorageReader) -> Result<BlockNumber, StorageError>;
 
-    // TODO(Eitan): Remove option on return once we have a class manager component.
-    /// Returning None happens when internal blocks are disabled for this stream.
+    /// Verifies a downloaded block against the blocks before it. Called right before the block is
+    /// yielded, when all the blocks before it are already in the storage.
+    fn verify_before_write(
+        _block: &Self::Output,
+        _storage_reader: &StorageReader,
+        _verifier: &DataVerifier,
+    ) -> Result<(), ParseDataError> {
+        Ok(())
+    }
+
+    /// Returns None if the stream's data isn't in the SyncBlock, in which case internal blocks
+    /// aren't used for this stream.
     fn convert_sync_block_to_block_data(
         block_number: BlockNumber,
         sync_block: SyncBlock,
@@ -80,8 +108,7 @@ where
                 let block_data =
                     match Self::convert_sync_block_to_block_data(block_number, sync_block) {
                         Some(block_data) => block_data,
-                        // If None is received then we don't use internal blocks for this stream
-                        // TODO(Eitan): Remove this once we have a class manager component.
+                        // If None is received then we don't use internal blocks for this stream.
                         None => return None,
                     };
                 if block_number == current_block_number {
@@ -93,12 +120,80 @@ where
         None
     }
 
+    /// Downloads the given range from the query's responses. Yields each block as soon as it's
+    /// parsed and ends with a single event describing how the query ended.
+    fn download_range(
+        mut client_response_manager: ClientResponsesManager<DataOrFin<InputFromNetwork>>,
+        range: BlockRange,
+        storage_reader: StorageReader,
+        verifier: DataVerifier,
+        wait_period_for_new_data: Duration,
+    ) -> BoxStream<'static, RangeDownloadEvent<Self::Output>> {
+        stream! {
+            let start_time = Instant::now();
+            for block_number in range.start.iter_up_to(range.end) {
+                match Self::parse_data_for_block(
+                    &mut client_response_manager, block_number, &storage_reader, &verifier
+                ).await {
+                    Ok(Some(output)) => yield RangeDownloadEvent::Block(block_number, output),
+                    Ok(None) => {
+                        debug!(
+                            "Query for {:?} on {:?} returned with partial data. Waiting {:?} before \
+                             sending another query.",
+                            Self::TYPE_DESCRIPTION, block_number, wait_period_for_new_data
+                        );
+                        tokio::time::sleep(wait_period_for_new_data).await;
+                        yield RangeDownloadEvent::Unfinished {
+                            unfinished_range: range.starting_at(block_number),
+                            is_bad_peer: false,
+                        };
+                        return;
+                    },
+                    Err(ParseDataError::BadPeer(err)) => {
+                        warn!(
+                            "Query for {:?} on {:?} returned with bad peer error: {:?}. reporting \
+                             peer and retrying query.",
+                            Self::TYPE_DESCRIPTION, block_number, err
+                        );
+                        client_response_manager.report_peer();
+                        yield RangeDownloadEvent::Unfinished {
+                            unfinished_range: range.starting_at(block_number),
+                            is_bad_peer: true,
+                        };
+                        return;
+                    },
+                    Err(ParseDataError::Fatal(err)) => {
+                        yield RangeDownloadEvent::Fatal(err);
+                        return;
+                    },
+                }
+            }
+
+            // Consume the None message signaling the end of the query.
+            match client_response_manager.next().await {
+                Some(Ok(DataOrFin(None))) => {
+                    debug!("Query sent to network for {:?} finished", Self::TYPE_DESCRIPTION);
+                    yield RangeDownloadEvent::Finished { range, elapsed: start_time.elapsed() };
+                },
+                Some(_) => yield RangeDownloadEvent::Fatal(P2PSyncClientError::TooManyResponses),
+                None => yield RangeDownloadEvent::Fatal(
+                    P2PSyncClientError::ReceiverChannelTerminated {
+                        type_description: Self::TYPE_DESCRIPTION
+                    }
+                ),
+            }
+        }
+        .boxed()
+    }
+
     fn create_stream<TQuery>(
         mut sqmr_sender: SqmrClientSender<TQuery, DataOrFin<InputFromNetwork>>,
         storage_reader: StorageReader,
+        verifier: DataVerifier,
         mut internal_block_receiver: Option<Receiver<(BlockNumber, SyncBlock)>>,
         wait_period_for_new_data: Duration,
         num_blocks_per_query: u64,
+        num_concurrent_queries: usize,
     ) -> BoxStream<'static, DataStreamResult>
     where
         TQuery: From<Query> + Send + 'static,
@@ -107,92 +202,130 @@ where
         stream! {
             let mut current_block_number = Self::get_start_block_number(&storage_reader)?;
             let mut internal_blocks_received = HashMap::new();
-            'send_query_and_parse_responses: loop {
-                let limit = match Self::BLOCK_NUMBER_LIMIT {
-                    BlockNumberLimit::Unlimited => num_blocks_per_query,
-                    BlockNumberLimit::HeaderMarker | BlockNumberLimit::StateDiffMarker => {
-                        let (last_block_number, description) = match Self::BLOCK_NUMBER_LIMIT {
-                            BlockNumberLimit::HeaderMarker => (storage_reader.begin_ro_txn()?.get_header_marker()?, "header"),
-                            BlockNumberLimit::StateDiffMarker => (storage_reader.begin_ro_txn()?.get_state_marker()?, "state diff"),
-                            _ => unreachable!(),
-                        };
-                        let limit = min(last_block_number.0 - current_block_number.0, num_blocks_per_query);
-                        if limit == 0 {
-                            debug!("{:?} sync is waiting for a new {}", Self::TYPE_DESCRIPTION, description);
-                            tokio::time::sleep(wait_period_for_new_data).await;
-                            continue;
-                        }
-                        limit
-                    },
+            let mut download_scheduler =
+                DownloadScheduler::new(current_block_number, num_blocks_per_query);
+            // Blocks that were downloaded before the blocks preceding them. They are kept until
+            // all the blocks before them are yielded, since the blocks are written to the storage
+            // in order.
+            let mut downloaded_blocks: BTreeMap<BlockNumber, Self::Output> = BTreeMap::new();
+            let mut range_downloads =
+                SelectAll::<BoxStream<'static, RangeDownloadEvent<Self::Output>>>::new();
+            // A range download is removed from range_downloads only when it's polled after its
+            // last event, so the queries that didn't end are counted separately.
+            let mut num_active_queries = 0;
+            // Blocks are only downloaded up to this amount of blocks above the next block to
+            // write, so that the blocks that wait for a slow query to finish don't pile up.
+            let lookahead_window = num_blocks_per_query.saturating_mul(
+                u64::try_from(num_concurrent_queries).expect("Failed converting usize to u64"),
+            );
+            loop {
+                let (block_number_limit, description) = match Self::BLOCK_NUMBER_LIMIT {
+                    BlockNumberLimit::Unlimited => (None, ""),
+                    BlockNumberLimit::HeaderMarker => (Some(storage_reader.begin_ro_txn()?.get_header_marker()?), "header"),
+                    BlockNumberLimit::StateDiffMarker => (Some(storage_reader.begin_ro_txn()?.get_state_marker()?), "state diff"),
+                    BlockNumberLimit::BodyMarker => (Some(storage_reader.begin_ro_txn()?.get_body_marker()?), "body"),
                 };
 
-                if let Some(block) = Self::get_internal_block_at(&mut internal_blocks_received, &mut internal_block_receiver, current_block_number)
-                {
-                    debug!("Sync received internally {:?} for block {}.", Self::TYPE_DESCRIPTION, current_block_number);
-                    yield Ok(Box::<dyn BlockData>::from(Box::new(block)));
+                // Internal blocks are only used for blocks below the limit, so that the blocks
+                // this stream depends on are already in the storage.
+                while block_number_limit.is_none_or(|limit| current_block_number < limit) {
+                    if let Some(block) = downloaded_blocks.remove(&current_block_number) {
+                        match Self::verify_before_write(&block, &storage_reader, &verifier) {
+                            Ok(()) => {},
+                            // The peer that sent the block can't be reported at this point, since
+                            // its query may have already finished.
+                            Err(ParseDataError::BadPeer(err)) => {
+                                warn!(
+                                    "Downloaded {:?} for block {} don't match the blocks before \
+                                     it: {:?}. Downloading them again.",
+                                    Self::TYPE_DESCRIPTION, current_block_number, err
+                                );
+                                download_scheduler.range_failed(
+                                    BlockRange {
+                                        start: current_block_number,
+                                        end: current_block_number.unchecked_next(),
+                                    },
+                                    true,
+                                );
+                                break;
+                            },
+                            Err(ParseDataError::Fatal(err)) => {
+                                yield Err(err);
+                                return;
+                            },
+                        }
+                        info!("Added {:?} for block {}.", Self::TYPE_DESCRIPTION, current_block_number);
+                        yield Ok(Box::<dyn BlockData>::from(Box::new(block)));
+                    } else if let Some(block) = Self::get_internal_block_at(&mut internal_blocks_received, &mut internal_block_receiver, current_block_number) {
+                        debug!("Sync received internally {:?} for block {}.", Self::TYPE_DESCRIPTION, current_block_number);
+                        yield Ok(Box::<dyn BlockData>::from(Box::new(block)));
+                        download_scheduler.skip_until(current_block_number.unchecked_next());
+                    } else {
+                        break;
+                    }
                     current_block_number = current_block_number.unchecked_next();
-                    continue 'send_query_and_parse_responses;
                 }
 
-                let end_block_number = current_block_number.0 + limit;
-                debug!(
-                    "Sync downloading {:?} for blocks [{}, {}) from network.",
-                    Self::TYPE_DESCRIPTION,
-                    current_block_number.0,
-                    end_block_number,
-                );
-
-                // TODO(shahak): Use the report callback.
-                let mut client_response_manager = sqmr_sender
-                    .send_new_query(
-                        TQuery::from(Query {
-                            start_block: BlockHashOrNumber::Number(current_block_number),
-                            direction: Direction::Forward,
-                            limit,
-                            step: STEP,
-                        })
-                    ).await?;
-                while current_block_number.0 < end_block_number {
-                    match Self::parse_data_for_block(
-                        &mut client_response_manager, current_block_number, &storage_reader
-                    ).await {
-                        Ok(Some(output)) => yield Ok(Box::<dyn BlockData>::from(Box::new(output))),
-                        Ok(None) => {
-                            debug!(
-                                "Query for {:?} on {:?} returned with partial data. Waiting {:?} before \
-                                 sending another query.",
-                                Self::TYPE_DESCRIPTION, current_block_number, wait_period_for_new_data
-                            );
-                            tokio::time::sleep(wait_period_for_new_data).await;
-                            continue 'send_query_and_parse_responses;
-                        },
-                        Err(ParseDataError::BadPeer(err)) => {
-                            warn!(
-                                "Query for {:?} on {:?} returned with bad peer error: {:?}. reporting \
-                                 peer and retrying query.",
-                                Self::TYPE_DESCRIPTION, current_block_number, err
-                            );
-                            client_response_manager.report_peer();
-                            continue 'send_query_and_parse_responses;
-                        },
-                        Err(ParseDataError::Fatal(err)) => {
-                            yield Err(err);
-                            return;
-                        },
-                    }
-                    info!("Added {:?} for block {}.", Self::TYPE_DESCRIPTION, current_block_number);
-                    current_block_number = current_block_number.unchecked_next();
+                let lookahead_limit =
+                    BlockNumber(current_block_number.0.saturating_add(lookahead_window));
+                let download_limit = block_number_limit
+                    .map_or(lookahead_limit, |limit| min(limit, lookahead_limit));
+                while num_active_queries < num_concurrent_queries {
+                    let Some(range) = download_scheduler.next_range(Some(download_limit)) else {
+                        break;
+                    };
+                    debug!(
+                        "Sync downloading {:?} for blocks [{}, {}) from network.",
+                        Self::TYPE_DESCRIPTION,
+                        range.start.0,
+                        range.end.0,
+                    );
+                    let client_response_manager = sqmr_sender
+                        .send_new_query(
+                            TQuery::from(Query {
+                                start_block: BlockHashOrNumber::Number(range.start),
+                                direction: Direction::Forward,
+                                limit: range.len(),
+                                step: STEP,
+                            })
+                        ).await?;
+                    range_downloads.push(Self::download_range(
+                        client_response_manager,
+                        range,
+                        storage_reader.clone(),
+                        verifier.clone(),
+                        wait_period_for_new_data,
+                    ));
+                    num_active_queries += 1;
                 }
 
-                // Consume the None message signaling the end of the query.
-                match client_response_manager.next().await {
-                    Some(Ok(DataOrFin(None))) => {
-                        debug!("Query sent to network for {:?} finished", Self::TYPE_DESCRIPTION);
+                let Some(event) = range_downloads.next().await else {
+                    debug!("{:?} sync is waiting for a new {}", Self::TYPE_DESCRIPTION, description);
+                    tokio::time::sleep(wait_period_for_new_data).await;
+                    continue;
+                };
+                match event {
+                    RangeDownloadEvent::Block(block_number, block) => {
+                        // The block might have already been received internally.
+                        if block_number >= current_block_number {
+                            downloaded_blocks.insert(block_number, block);
+                        }
+                    },
+                    RangeDownloadEvent::Finished { range, elapsed } => {
+                        num_active_queries -= 1;
+                        download_scheduler.range_finished(range, elapsed);
+                    },
+                    RangeDownloadEvent::Unfinished { unfinished_range, is_bad_peer } => {
+                        num_active_queries -= 1;
+                        download_scheduler.range_failed(
+                            unfinished_range.starting_at(current_block_number),
+                            is_bad_peer,
+                        );
+                    },
+                    RangeDownloadEvent::Fatal(err) => {
+                        yield Err(err);
+                        return;
                     },
-                    Some(_) => Err(P2PSyncClientError::TooManyResponses)?,
-                    None => Err(P2PSyncClientError::ReceiverChannelTerminated {
-                        type_description: Self::TYPE_DESCRIPTION
-                    })?,
                 }
             }
         }
@@ -200,6 +333,21 @@ where
     }
 }
 
+pub(crate) enum RangeDownloadEvent<Output> {
+    Block(BlockNumber, Output),
+    /// The entire range was downloaded.
+    Finished {
+        range: BlockRange,
+        elapsed: Duration,
+    },
+    /// The query ended before downloading the given part of its range.
+    Unfinished {
+        unfinished_range: BlockRange,
+        is_bad_peer: bool,
+    },
+    Fatal(P2PSyncClientError),
+}
+
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum BadPeerError {
     #[error(
@@ -227,6 +375,8 @@ pub(crate) enum BadPeerError {
     EmptyStateDiffPart,
     #[error(transparent)]
     ProtobufConversionError(#[from] ProtobufConversionError),
+    #[error("The peer didn't send the next response in time.")]
+    NetworkTimeout(tokio::time::error::Elapsed),
     #[error(
         "Expected to receive {expected} classes for {block_number} from the network. Got {actual} \
          classes instead"
@@ -236,6 +386,101 @@ pub(crate) enum BadPeerError {
     ClassNotInStateDiff { class_hash: ClassHash },
     #[error("Received two classes with the same hash: {class_hash}.")]
     DuplicateClass { class_hash: ClassHash },
+    #[error("The class declared with hash {expected} has the hash {actual}.")]
+    ClassHashMismatch { expected: ClassHash, actual: ClassHash },
+    #[error("Failed compiling the class {class_hash}: {error}.")]
+    ClassCompilationFailed { class_hash: ClassHash, error: String },
+    #[error(
+        "The class {class_hash} was compiled to a class with hash {actual}, while the state diff \
+         declares it with the compiled class hash {expected}."
+    )]
+    CompiledClassHashMismatch {
+        class_hash: ClassHash,
+        expected: CompiledClassHash,
+        actual: CompiledClassHash,
+    },
+    #[error(
+        "Expected to receive {expected} events for {block_number} from the network. Got {actual} \
+         events instead"
+    )]
+    NotEnoughEvents { expected: usize, actual: usize, block_number: u64 },
+    #[error(
+        "Received an event of transaction {transaction_hash} for {block_number}, which is either \
+         not in the block or before the transaction of the previous event."
+    )]
+    UnexpectedEventTransactionHash { transaction_hash: TransactionHash, block_number: BlockNumber },
+    #[error(
+        "The events of {block_number} don't match the event commitment in the header. Expected \
+         {expected:?}, calculated {actual:?}."
+    )]
+    EventCommitmentMismatch {
+        block_number: BlockNumber,
+        expected: EventCommitment,
+        actual: EventCommitment,
+    },
+    #[error(
+        "The hash of {block_number} is {expected}, but the hash calculated from its header is \
+         {actual}."
+    )]
+    BlockHashMismatch { block_number: BlockNumber, expected: BlockHash, actual: BlockHash },
+    #[error(
+        "The parent hash of {block_number} is {parent_hash}, but the hash of the previous block \
+         is {previous_block_hash}."
+    )]
+    ParentHashMismatch {
+        block_number: BlockNumber,
+        parent_hash: BlockHash,
+        previous_block_hash: BlockHash,
+    },
+    #[error(
+        "The header of {block_number} has no state diff commitment, so its signature can't be \
+         verified."
+    )]
+    MissingStateDiffCommitment { block_number: BlockNumber },
+    #[error(
+        "The header of {block_number} is missing a commitment or the state diff length, which are \
+         needed to calculate its hash."
+    )]
+    MissingCommitments { block_number: BlockNumber },
+    #[error(
+        "The header of {block_number} has the Starknet version {starknet_version}, whose block \
+         hash isn't calculated from the header."
+    )]
+    OldStarknetVersion { block_number: BlockNumber, starknet_version: StarknetVersion },
+    #[error("The signature {signature:?} of {block_number} wasn't signed by the sequencer.")]
+    InvalidBlockSignature { block_number: BlockNumber, signature: BlockSignature },
+    #[error(
+        "The hash {transaction_hash} of a transaction in {block_number} doesn't match the \
+         transaction's content."
+    )]
+    TransactionHashMismatch { transaction_hash: TransactionHash, block_number: BlockNumber },
+    #[error(
+        "The transactions of {block_number} don't match the transaction commitment in the \
+         header. Expected {expected:?}, calculated {actual:?}."
+    )]
+    TransactionCommitmentMismatch {
+        block_number: BlockNumber,
+        expected: TransactionCommitment,
+        actual: TransactionCommitment,
+    },
+    #[error(
+        "The transaction outputs of {block_number} don't match the receipt commitment in the \
+         header. Expected {expected:?}, calculated {actual:?}."
+    )]
+    ReceiptCommitmentMismatch {
+        block_number: BlockNumber,
+        expected: ReceiptCommitment,
+        actual: ReceiptCommitment,
+    },
+    #[error(
+        "The state diff of {block_number} doesn't match the state diff commitment in the header. \
+         Expected {expected:?}, calculated {actual:?}."
+    )]
+    StateDiffCommitmentMismatch {
+        block_number: BlockNumber,
+        expected: StateDiffCommitment,
+        actual: StateDiffCommitment,
+    },
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -254,7 +499,7 @@ impl From<StorageError> for ParseDataError {
 
 impl From<tokio::time::error::Elapsed> for ParseDataError {
     fn from(err: tokio::time::error::Elapsed) -> Self {
-        ParseDataError::Fatal(P2PSyncClientError::NetworkTimeout(err))
+        ParseDataError::BadPeer(BadPeerError::NetworkTimeout(err))
     }
 }
 
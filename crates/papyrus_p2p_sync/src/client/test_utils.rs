@@ -16,6 +16,7 @@ use papyrus_network::network_manager::GenericReceiver;
 use papyrus_protobuf::sync::{
     ClassQuery,
     DataOrFin,
+    EventQuery,
     HeaderQuery,
     Query,
     SignedBlockHeader,
@@ -38,11 +39,18 @@ use starknet_api::block::{
     BlockHeaderWithoutHash,
     BlockNumber,
     BlockSignature,
+    StarknetVersion,
 };
-use starknet_api::core::ClassHash;
+use starknet_api::block_hash::block_hash_calculator::{
+    calculate_block_hash,
+    concat_counts,
+    BlockHeaderCommitments,
+};
+use starknet_api::core::{ChainId, ClassHash};
 use starknet_api::crypto::utils::Signature;
 use starknet_api::hash::StarkHash;
-use starknet_api::transaction::FullTransaction;
+use starknet_api::transaction::{Event, FullTransaction, TransactionHash};
+use starknet_class_manager_types::SharedClassManagerClient;
 use starknet_state_sync_types::state_sync_types::SyncBlock;
 use starknet_types_core::felt::Felt;
 use tokio::sync::oneshot;
@@ -55,19 +63,28 @@ pub const HEADER_QUERY_LENGTH: u64 = 5;
 pub const STATE_DIFF_QUERY_LENGTH: u64 = 3;
 pub const CLASS_DIFF_QUERY_LENGTH: u64 = 3;
 pub const TRANSACTION_QUERY_LENGTH: u64 = 3;
+pub const EVENT_QUERY_LENGTH: u64 = 3;
 pub const SLEEP_DURATION_TO_LET_SYNC_ADVANCE: Duration = Duration::from_millis(10);
 pub const WAIT_PERIOD_FOR_NEW_DATA: Duration = Duration::from_secs(1);
 pub const TIMEOUT_FOR_NEW_QUERY_AFTER_PARTIAL_RESPONSE: Duration =
     WAIT_PERIOD_FOR_NEW_DATA.saturating_add(Duration::from_secs(1));
 
 lazy_static! {
+    // A chain without blocks from before Starknet 0.13.2, so the hashes and commitments of all the
+    // blocks in the tests are verified.
+    pub static ref CHAIN_ID: ChainId = ChainId::Other("SN_P2P_SYNC_TEST".to_owned());
     static ref TEST_CONFIG: P2PSyncClientConfig = P2PSyncClientConfig {
         num_headers_per_query: HEADER_QUERY_LENGTH,
         num_block_state_diffs_per_query: STATE_DIFF_QUERY_LENGTH,
         num_block_transactions_per_query: TRANSACTION_QUERY_LENGTH,
         num_block_classes_per_query: CLASS_DIFF_QUERY_LENGTH,
+        num_block_events_per_query: EVENT_QUERY_LENGTH,
+        num_concurrent_queries: 1,
         wait_period_for_new_data: WAIT_PERIOD_FOR_NEW_DATA,
         buffer_size: BUFFER_SIZE,
+        // The test data is random, so it's only verified in the tests for the verification.
+        verify_blocks: false,
+        sequencer_public_key: None,
     };
 }
 pub(crate) type HeaderTestPayload =
@@ -78,6 +95,8 @@ pub(crate) type TransactionTestPayload =
     MockClientResponsesManager<TransactionQuery, DataOrFin<FullTransaction>>;
 pub(crate) type ClassTestPayload =
     MockClientResponsesManager<ClassQuery, DataOrFin<(ApiContractClass, ClassHash)>>;
+pub(crate) type EventTestPayload =
+    MockClientResponsesManager<EventQuery, DataOrFin<(Event, TransactionHash)>>;
 
 // TODO(Eitan): Use SqmrSubscriberChannels once there is a utility function for testing
 pub struct TestArgs {
@@ -90,10 +109,19 @@ pub struct TestArgs {
     pub mock_transaction_response_manager: GenericReceiver<TransactionTestPayload>,
     #[allow(dead_code)]
     pub mock_class_response_manager: GenericReceiver<ClassTestPayload>,
+    #[allow(dead_code)]
+    pub mock_event_response_manager: GenericReceiver<EventTestPayload>,
 }
 
 pub fn setup() -> TestArgs {
-    let p2p_sync_config = *TEST_CONFIG;
+    setup_with_config(*TEST_CONFIG)
+}
+
+pub fn setup_with_num_concurrent_queries(num_concurrent_queries: usize) -> TestArgs {
+    setup_with_config(P2PSyncClientConfig { num_concurrent_queries, ..*TEST_CONFIG })
+}
+
+fn setup_with_config(p2p_sync_config: P2PSyncClientConfig) -> TestArgs {
     let buffer_size = p2p_sync_config.buffer_size;
     let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
     let (header_sender, mock_header_response_manager) =
@@ -104,18 +132,23 @@ pub fn setup() -> TestArgs {
         mock_register_sqmr_protocol_client(buffer_size);
     let (class_sender, mock_class_response_manager) =
         mock_register_sqmr_protocol_client(buffer_size);
+    let (event_sender, mock_event_response_manager) =
+        mock_register_sqmr_protocol_client(buffer_size);
     let p2p_sync_channels = P2PSyncClientChannels {
         header_sender,
         state_diff_sender,
         transaction_sender,
         class_sender,
+        event_sender,
     };
     let p2p_sync = P2PSyncClient::new(
         p2p_sync_config,
+        CHAIN_ID.clone(),
         storage_reader.clone(),
         storage_writer,
         p2p_sync_channels,
         futures::stream::pending().boxed(),
+        None,
     );
     TestArgs {
         p2p_sync,
@@ -124,6 +157,7 @@ pub fn setup() -> TestArgs {
         mock_state_diff_response_manager,
         mock_transaction_response_manager,
         mock_class_response_manager,
+        mock_event_response_manager,
     }
 }
 
@@ -135,6 +169,7 @@ pub enum DataType {
     StateDiff,
     #[allow(dead_code)]
     Class,
+    Event,
 }
 
 pub enum Action {
@@ -155,19 +190,24 @@ pub enum Action {
     /// Send a class as a response to a query we got from ReceiveQuery. Will panic if didn't
     /// call ReceiveQuery with DataType::Class before.
     SendClass(DataOrFin<(ApiContractClass, ClassHash)>),
+    /// Send an event as a response to a query we got from ReceiveQuery. Will panic if didn't
+    /// call ReceiveQuery with DataType::Event before.
+    SendEvent(DataOrFin<(Event, TransactionHash)>),
     /// Perform custom validations on the storage. Returns back the storage reader it received as
     /// input
     CheckStorage(Box<dyn FnOnce(StorageReader) -> BoxFuture<'static, ()>>),
     /// Check that a report was sent on the current header query.
     ValidateReportSent(DataType),
     /// Sends an internal block to the sync.
-    #[allow(dead_code)]
     SendInternalBlock(BlockNumber, SyncBlock),
 }
 
-// TODO(shahak): add support for state diffs, transactions and classes.
-pub async fn run_test(max_query_lengths: HashMap<DataType, u64>, actions: Vec<Action>) {
-    let p2p_sync_config = P2PSyncClientConfig {
+/// Returns the test config with the given query lengths. Data types without a query length get a
+/// query length of 1.
+pub fn config_with_query_lengths(
+    max_query_lengths: &HashMap<DataType, u64>,
+) -> P2PSyncClientConfig {
+    P2PSyncClientConfig {
         num_headers_per_query: max_query_lengths.get(&DataType::Header).cloned().unwrap_or(1),
         num_block_state_diffs_per_query: max_query_lengths
             .get(&DataType::StateDiff)
@@ -178,9 +218,25 @@ pub async fn run_test(max_query_lengths: HashMap<DataType, u64>, actions: Vec<Ac
             .cloned()
             .unwrap_or(1),
         num_block_classes_per_query: max_query_lengths.get(&DataType::Class).cloned().unwrap_or(1),
-        wait_period_for_new_data: WAIT_PERIOD_FOR_NEW_DATA,
-        buffer_size: BUFFER_SIZE,
-    };
+        num_block_events_per_query: max_query_lengths.get(&DataType::Event).cloned().unwrap_or(1),
+        ..*TEST_CONFIG
+    }
+}
+
+// TODO(shahak): add support for state diffs, transactions and classes.
+pub async fn run_test(max_query_lengths: HashMap<DataType, u64>, actions: Vec<Action>) {
+    run_test_with_config(config_with_query_lengths(&max_query_lengths), actions).await;
+}
+
+pub async fn run_test_with_config(p2p_sync_config: P2PSyncClientConfig, actions: Vec<Action>) {
+    run_test_with_class_manager_client(p2p_sync_config, None, actions).await;
+}
+
+pub async fn run_test_with_class_manager_client(
+    p2p_sync_config: P2PSyncClientConfig,
+    class_manager_client: Option<SharedClassManagerClient>,
+    actions: Vec<Action>,
+) {
     let buffer_size = p2p_sync_config.buffer_size;
     let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
     let (header_sender, mut mock_header_network) = mock_register_sqmr_protocol_client(buffer_size);
@@ -189,25 +245,30 @@ pub async fn run_test(max_query_lengths: HashMap<DataType, u64>, actions: Vec<Ac
     let (transaction_sender, mut mock_transaction_network) =
         mock_register_sqmr_protocol_client(buffer_size);
     let (class_sender, mut mock_class_network) = mock_register_sqmr_protocol_client(buffer_size);
+    let (event_sender, mut mock_event_network) = mock_register_sqmr_protocol_client(buffer_size);
     let p2p_sync_channels = P2PSyncClientChannels {
         header_sender,
         state_diff_sender,
         transaction_sender,
         class_sender,
+        event_sender,
     };
     let (mut internal_block_sender, internal_block_receiver) = mpsc::channel(buffer_size);
     let p2p_sync = P2PSyncClient::new(
         p2p_sync_config,
+        CHAIN_ID.clone(),
         storage_reader.clone(),
         storage_writer,
         p2p_sync_channels,
         internal_block_receiver.boxed(),
+        class_manager_client,
     );
 
     let mut headers_current_query_responses_manager = None;
     let mut state_diff_current_query_responses_manager = None;
     let mut transaction_current_query_responses_manager = None;
     let mut class_current_query_responses_manager = None;
+    let mut event_current_query_responses_manager = None;
 
     let (sync_future_sender, sync_future_receiver) = oneshot::channel();
     let mut sync_future_sender = Some(sync_future_sender);
@@ -242,6 +303,12 @@ pub async fn run_test(max_query_lengths: HashMap<DataType, u64>, actions: Vec<Ac
                                     &mut class_current_query_responses_manager,
                                 ).await.0
                             }
+                            DataType::Event => {
+                                get_next_query_and_update_responses_manager(
+                                    &mut mock_event_network,
+                                    &mut event_current_query_responses_manager,
+                                ).await.0
+                            }
                         };
                         validate_query_fn(query);
                     }
@@ -265,6 +332,11 @@ pub async fn run_test(max_query_lengths: HashMap<DataType, u64>, actions: Vec<Ac
                             .expect("Called SendClass without calling ReceiveQuery");
                         responses_manager.send_response(class_or_fin).await.unwrap();
                     }
+                    Action::SendEvent(event_or_fin) => {
+                        let responses_manager = event_current_query_responses_manager.as_mut()
+                            .expect("Called SendEvent without calling ReceiveQuery");
+                        responses_manager.send_response(event_or_fin).await.unwrap();
+                    }
                     Action::CheckStorage(check_storage_fn) => {
                         // We tried avoiding the clone here but it causes lifetime issues.
                         check_storage_fn(storage_reader.clone()).await;
@@ -297,6 +369,13 @@ pub async fn run_test(max_query_lengths: HashMap<DataType, u64>, actions: Vec<Ac
                                 data type");
                         responses_manager.assert_reported(TIMEOUT_FOR_TEST).await;
                     }
+                    Action::ValidateReportSent(DataType::Event) => {
+                        let responses_manager = event_current_query_responses_manager.take()
+                            .expect(
+                                "Called ValidateReportSent without calling ReceiveQuery on the same
+                                data type");
+                        responses_manager.assert_reported(TIMEOUT_FOR_TEST).await;
+                    }
                     Action::SendInternalBlock(block_number, sync_block) => {
                         internal_block_sender.send((block_number, sync_block)).await.unwrap();
                     }
@@ -345,6 +424,27 @@ pub fn random_header(
     }
 }
 
+/// Sets the header's Starknet version to the first version whose block hash is calculated from the
+/// header, and sets the block hash to the hash calculated from the header. The header must have
+/// all of its commitments and its state diff length.
+pub fn set_block_hash_from_header(header: &mut BlockHeader) {
+    header.block_header_without_hash.starknet_version = StarknetVersion::V0_13_2;
+    let block_commitments = BlockHeaderCommitments {
+        transaction_commitment: header.transaction_commitment.unwrap(),
+        event_commitment: header.event_commitment.unwrap(),
+        receipt_commitment: header.receipt_commitment.unwrap(),
+        state_diff_commitment: header.state_diff_commitment.unwrap(),
+        concatenated_counts: concat_counts(
+            header.n_transactions,
+            header.n_events,
+            header.state_diff_length.unwrap(),
+            header.block_header_without_hash.l1_da_mode,
+        ),
+    };
+    header.block_hash =
+        calculate_block_hash(header.block_header_without_hash.clone(), block_commitments).unwrap();
+}
+
 pub fn create_block_hashes_and_signatures(n_blocks: u8) -> Vec<(BlockHash, BlockSignature)> {
     let mut bytes = [0u8; 32];
     (0u8..n_blocks)
@@ -380,6 +480,7 @@ pub(crate) async fn wait_for_marker(
             DataType::Transaction => txn.get_body_marker().unwrap(),
             DataType::StateDiff => txn.get_state_marker().unwrap(),
             DataType::Class => txn.get_class_marker().unwrap(),
+            DataType::Event => txn.get_event_marker().unwrap(),
         };
 
         if storage_marker >= expected_marker {
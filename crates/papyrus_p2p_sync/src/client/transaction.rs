@@ -8,6 +8,7 @@ use papyrus_storage::{StorageError, StorageReader, StorageWriter};
 use papyrus_test_utils::{get_rng, GetTestInstance};
 use starknet_api::block::{BlockBody, BlockNumber};
 use starknet_api::transaction::{FullTransaction, Transaction, TransactionOutput};
+use starknet_class_manager_types::SharedClassManagerClient;
 use starknet_state_sync_types::state_sync_types::SyncBlock;
 
 use super::stream_builder::{
@@ -17,21 +18,27 @@ use super::stream_builder::{
     DataStreamBuilder,
     ParseDataError,
 };
+use super::verification::DataVerifier;
 use super::{P2PSyncClientError, NETWORK_DATA_TIMEOUT};
 
 impl BlockData for (BlockBody, BlockNumber) {
-    fn write_to_storage(
+    fn write_to_storage<'a>(
         self: Box<Self>,
-        storage_writer: &mut StorageWriter,
-    ) -> Result<(), StorageError> {
-        storage_writer.begin_rw_txn()?.append_body(self.1, self.0)?.commit()
+        storage_writer: &'a mut StorageWriter,
+        _class_manager_client: Option<&'a SharedClassManagerClient>,
+    ) -> BoxFuture<'a, Result<(), P2PSyncClientError>> {
+        async move {
+            // The events are received and written separately by the events stream.
+            storage_writer.begin_rw_txn()?.append_body_without_events(self.1, self.0)?.commit()?;
+            Ok(())
+        }
+        .boxed()
     }
 }
 
 pub(crate) struct TransactionStreamFactory;
 
 impl DataStreamBuilder<FullTransaction> for TransactionStreamFactory {
-    // TODO(Eitan): Add events protocol to BlockBody or split their write to storage
     type Output = (BlockBody, BlockNumber);
 
     const TYPE_DESCRIPTION: &'static str = "transactions";
@@ -41,15 +48,16 @@ impl DataStreamBuilder<FullTransaction> for TransactionStreamFactory {
         transactions_response_manager: &'a mut ClientResponsesManager<DataOrFin<FullTransaction>>,
         block_number: BlockNumber,
         storage_reader: &'a StorageReader,
+        verifier: &'a DataVerifier,
     ) -> BoxFuture<'a, Result<Option<Self::Output>, ParseDataError>> {
         async move {
             let mut block_body = BlockBody::default();
             let mut current_transaction_len = 0;
-            let target_transaction_len = storage_reader
+            let header = storage_reader
                 .begin_ro_txn()?
                 .get_block_header(block_number)?
-                .expect("A header with number lower than the header marker is missing")
-                .n_transactions;
+                .expect("A header with number lower than the header marker is missing");
+            let target_transaction_len = header.n_transactions;
             while current_transaction_len < target_transaction_len {
                 let maybe_transaction = tokio::time::timeout(
                     NETWORK_DATA_TIMEOUT,
@@ -74,10 +82,10 @@ impl DataStreamBuilder<FullTransaction> for TransactionStreamFactory {
                 };
                 block_body.transactions.push(transaction);
                 block_body.transaction_outputs.push(transaction_output);
-                // TODO(eitan): Validate transaction hash from untrusted sources
                 block_body.transaction_hashes.push(transaction_hash);
                 current_transaction_len += 1;
             }
+            verifier.verify_transactions(&header, &block_body)?;
             Ok(Some((block_body, block_number)))
         }
         .boxed()
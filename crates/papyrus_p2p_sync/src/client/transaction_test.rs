@@ -1,4 +1,5 @@
 use std::cmp::min;
+use std::collections::HashMap;
 
 use futures::{FutureExt, StreamExt};
 use papyrus_protobuf::sync::{
@@ -10,20 +11,35 @@ use papyrus_protobuf::sync::{
     TransactionQuery,
 };
 use papyrus_storage::body::BodyStorageReader;
-use papyrus_test_utils::get_test_body;
+use papyrus_test_utils::{get_rng, get_test_body, GetTestInstance};
 use starknet_api::block::{BlockBody, BlockHeader, BlockHeaderWithoutHash, BlockNumber};
-use starknet_api::transaction::FullTransaction;
+use starknet_api::core::TransactionCommitment;
+use starknet_api::felt;
+use starknet_api::transaction::{
+    FullTransaction,
+    InvokeTransaction,
+    InvokeTransactionV1,
+    Transaction,
+    TransactionHash,
+    TransactionOutput,
+};
 
 use super::test_utils::{
+    config_with_query_lengths,
     create_block_hashes_and_signatures,
+    random_header,
+    run_test_with_config,
+    set_block_hash_from_header,
     setup,
+    Action,
     TestArgs,
     HEADER_QUERY_LENGTH,
     SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
     TRANSACTION_QUERY_LENGTH,
     WAIT_PERIOD_FOR_NEW_DATA,
 };
-use crate::client::test_utils::{wait_for_marker, DataType, TIMEOUT_FOR_TEST};
+use super::P2PSyncClientConfig;
+use crate::client::test_utils::{wait_for_marker, DataType, CHAIN_ID, TIMEOUT_FOR_TEST};
 
 #[tokio::test]
 async fn transaction_basic_flow() {
@@ -35,6 +51,7 @@ async fn transaction_basic_flow() {
         // The test will fail if we drop these
         mock_state_diff_response_manager: _mock_state_diff_response_manager,
         mock_class_response_manager: _mock_class_responses_manager,
+        mock_event_response_manager: _mock_event_response_manager,
         ..
     } = setup();
 
@@ -179,3 +196,62 @@ async fn transaction_basic_flow() {
         _ = parse_queries_future => {}
     }
 }
+
+// Syncs a header of a block with the given transaction, and sends the transaction. Checks that the
+// peer that sent the transaction is reported.
+async fn validate_transaction_fails(header: SignedBlockHeader, transaction: FullTransaction) {
+    run_test_with_config(
+        P2PSyncClientConfig {
+            verify_blocks: true,
+            ..config_with_query_lengths(&HashMap::from([
+                (DataType::Header, 1),
+                (DataType::Transaction, 1),
+            ]))
+        },
+        vec![
+            Action::RunP2pSync,
+            // We already validate the query content in other tests.
+            Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+            Action::SendHeader(DataOrFin(Some(header))),
+            Action::SendHeader(DataOrFin(None)),
+            Action::ReceiveQuery(Box::new(|_query| ()), DataType::Transaction),
+            Action::SendTransaction(DataOrFin(Some(transaction))),
+            Action::ValidateReportSent(DataType::Transaction),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn wrong_transaction_hash_reports_peer() {
+    let mut rng = get_rng();
+    let mut header = random_header(&mut rng, BlockNumber(0), None, Some(1));
+    set_block_hash_from_header(&mut header.block_header);
+    let transaction = FullTransaction {
+        transaction: Transaction::Invoke(InvokeTransaction::V1(
+            InvokeTransactionV1::get_test_instance(&mut rng),
+        )),
+        transaction_output: TransactionOutput::get_test_instance(&mut rng),
+        transaction_hash: TransactionHash(felt!("0x1234")),
+    };
+
+    validate_transaction_fails(header, transaction).await;
+}
+
+#[tokio::test]
+async fn wrong_transaction_commitment_reports_peer() {
+    let mut rng = get_rng();
+    let mut header = random_header(&mut rng, BlockNumber(0), None, Some(1));
+    header.block_header.transaction_commitment = Some(TransactionCommitment(felt!("0x1234")));
+    set_block_hash_from_header(&mut header.block_header);
+    let transaction = Transaction::Invoke(InvokeTransaction::V1(
+        InvokeTransactionV1::get_test_instance(&mut rng),
+    ));
+    let transaction = FullTransaction {
+        transaction_hash: transaction.calculate_transaction_hash(&CHAIN_ID).unwrap(),
+        transaction,
+        transaction_output: TransactionOutput::get_test_instance(&mut rng),
+    };
+
+    validate_transaction_fails(header, transaction).await;
+}
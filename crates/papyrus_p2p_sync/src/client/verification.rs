@@ -0,0 +1,371 @@
+use papyrus_common::class_hash::calculate_class_hash;
+use papyrus_protobuf::sync::SignedBlockHeader;
+use starknet_api::block::{verify_block_signature, BlockBody, BlockHash, BlockHeader, BlockNumber};
+use starknet_api::block_hash::block_hash_calculator::{
+    calculate_block_commitments,
+    calculate_block_hash,
+    concat_counts,
+    BlockHeaderCommitments,
+    TransactionHashingData,
+    TransactionOutputForHash,
+};
+use starknet_api::block_hash::event_commitment::{calculate_event_commitment, EventLeafElement};
+use starknet_api::block_hash::state_diff_hash::calculate_state_diff_hash;
+use starknet_api::core::{ChainId, ClassHash, CompiledClassHash, GlobalRoot, SequencerPublicKey};
+use starknet_api::state::{SierraContractClass, ThinStateDiff};
+use starknet_api::transaction::fields::TransactionSignature;
+use starknet_api::transaction::{
+    Event,
+    Transaction,
+    TransactionHash,
+    TransactionOptions,
+    TransactionOutput,
+};
+use starknet_api::transaction_hash::validate_transaction_hash;
+use starknet_class_manager_types::{
+    ClassManagerClientError,
+    ClassManagerError,
+    ExecutableClass,
+    SharedClassManagerClient,
+};
+use starknet_types_core::hash::Poseidon;
+
+use super::stream_builder::{BadPeerError, ParseDataError};
+use super::{P2PSyncClientConfig, P2PSyncClientError};
+
+/// Verifies the data received from peers. Headers are verified against their hash, their parent
+/// and the sequencer's signature, and the rest of the block's data is verified against the
+/// commitments in its header. Blocks from before Starknet 0.13.2 don't have a hash that's
+/// calculated from their header, so only their signatures and transaction hashes are verified.
+/// Which blocks these are is decided by their number, since the peer controls the rest of the
+/// header. Classes are verified against their hashes and compiled by the class manager, if there
+/// is one, to be verified against the compiled class hashes in the state diff.
+#[derive(Clone)]
+pub(crate) struct DataVerifier {
+    chain_id: ChainId,
+    first_block_with_commitments: BlockNumber,
+    sequencer_public_key: Option<SequencerPublicKey>,
+    verify_blocks: bool,
+    class_manager_client: Option<SharedClassManagerClient>,
+}
+
+impl DataVerifier {
+    pub fn new(
+        chain_id: ChainId,
+        config: &P2PSyncClientConfig,
+        class_manager_client: Option<SharedClassManagerClient>,
+    ) -> Self {
+        Self {
+            first_block_with_commitments: first_block_with_commitments(&chain_id),
+            chain_id,
+            sequencer_public_key: config.sequencer_public_key,
+            verify_blocks: config.verify_blocks,
+            class_manager_client,
+        }
+    }
+
+    pub fn verify_header(&self, signed_header: &SignedBlockHeader) -> Result<(), BadPeerError> {
+        if !self.verify_blocks {
+            return Ok(());
+        }
+        let header = &signed_header.block_header;
+        let block_number = header.block_header_without_hash.block_number;
+        if self.has_commitments(header) {
+            let block_hash = calculate_header_block_hash(header)?;
+            if block_hash != header.block_hash {
+                return Err(BadPeerError::BlockHashMismatch {
+                    block_number,
+                    expected: header.block_hash,
+                    actual: block_hash,
+                });
+            }
+        }
+
+        let Some(sequencer_public_key) = &self.sequencer_public_key else {
+            return Ok(());
+        };
+        let state_diff_commitment = header
+            .state_diff_commitment
+            .ok_or(BadPeerError::MissingStateDiffCommitment { block_number })?;
+        for signature in &signed_header.signatures {
+            let is_valid = verify_block_signature(
+                sequencer_public_key,
+                signature,
+                &GlobalRoot(state_diff_commitment.0.0),
+                &header.block_hash,
+            );
+            if !matches!(is_valid, Ok(true)) {
+                return Err(BadPeerError::InvalidBlockSignature {
+                    block_number,
+                    signature: *signature,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// A header whose signature was verified and whose hash was calculated from its content is
+    /// part of the chain, so if its parent hash doesn't match the previous block in the storage,
+    /// the chain was reverted. The hash of older blocks isn't calculated, so their parent hash
+    /// isn't covered by the signature.
+    pub fn verify_parent_hash(
+        &self,
+        header: &BlockHeader,
+        previous_block_hash: BlockHash,
+    ) -> Result<(), ParseDataError> {
+        if !self.verify_blocks
+            || header.block_header_without_hash.parent_hash == previous_block_hash
+        {
+            return Ok(());
+        }
+        let block_number = header.block_header_without_hash.block_number;
+        let parent_hash = header.block_header_without_hash.parent_hash;
+        if self.sequencer_public_key.is_some() && self.has_commitments(header) {
+            return Err(ParseDataError::Fatal(P2PSyncClientError::RevertNotSupported {
+                block_number,
+                parent_hash,
+                previous_block_hash,
+            }));
+        }
+        Err(ParseDataError::BadPeer(BadPeerError::ParentHashMismatch {
+            block_number,
+            parent_hash,
+            previous_block_hash,
+        }))
+    }
+
+    pub fn verify_transactions(
+        &self,
+        header: &BlockHeader,
+        block_body: &BlockBody,
+    ) -> Result<(), BadPeerError> {
+        if !self.verify_blocks {
+            return Ok(());
+        }
+        let block_number = header.block_header_without_hash.block_number;
+        for (transaction, transaction_hash) in
+            block_body.transactions.iter().zip(&block_body.transaction_hashes)
+        {
+            // Transactions from before Starknet 0.7 may have a hash that was calculated in a
+            // deprecated way, and validate_transaction_hash accepts those hashes as well.
+            let is_valid = validate_transaction_hash(
+                transaction,
+                &block_number,
+                &self.chain_id,
+                *transaction_hash,
+                &TransactionOptions::default(),
+            );
+            if !matches!(is_valid, Ok(true)) {
+                return Err(BadPeerError::TransactionHashMismatch {
+                    transaction_hash: *transaction_hash,
+                    block_number,
+                });
+            }
+        }
+
+        if !self.has_commitments(header) {
+            return Ok(());
+        }
+        let transactions_data = block_body
+            .transactions
+            .iter()
+            .zip(&block_body.transaction_outputs)
+            .zip(&block_body.transaction_hashes)
+            .map(|((transaction, transaction_output), transaction_hash)| TransactionHashingData {
+                transaction_signature: transaction_signature(transaction),
+                transaction_output: transaction_output_for_hash(transaction_output),
+                transaction_hash: *transaction_hash,
+            })
+            .collect::<Vec<_>>();
+        // The events and the state diff are received separately, and only the transaction and
+        // receipt commitments are checked here.
+        let commitments = calculate_block_commitments(
+            &transactions_data,
+            &ThinStateDiff::default(),
+            header.block_header_without_hash.l1_da_mode,
+            &header.block_header_without_hash.starknet_version,
+        );
+        let expected = header
+            .transaction_commitment
+            .ok_or(BadPeerError::MissingCommitments { block_number })?;
+        if expected != commitments.transaction_commitment {
+            return Err(BadPeerError::TransactionCommitmentMismatch {
+                block_number,
+                expected,
+                actual: commitments.transaction_commitment,
+            });
+        }
+        let expected =
+            header.receipt_commitment.ok_or(BadPeerError::MissingCommitments { block_number })?;
+        if expected != commitments.receipt_commitment {
+            return Err(BadPeerError::ReceiptCommitmentMismatch {
+                block_number,
+                expected,
+                actual: commitments.receipt_commitment,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn verify_state_diff(
+        &self,
+        header: &BlockHeader,
+        state_diff: &ThinStateDiff,
+    ) -> Result<(), BadPeerError> {
+        if !self.verify_blocks || !self.has_commitments(header) {
+            return Ok(());
+        }
+        let block_number = header.block_header_without_hash.block_number;
+        let expected = header
+            .state_diff_commitment
+            .ok_or(BadPeerError::MissingCommitments { block_number })?;
+        let actual = calculate_state_diff_hash(state_diff);
+        if actual != expected {
+            return Err(BadPeerError::StateDiffCommitmentMismatch {
+                block_number,
+                expected,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Verifies the events of each of the block's transactions against the event commitment in the
+    /// header. This is done regardless of `verify_blocks`, since the events aren't verified by
+    /// anything else. The events of blocks from before Starknet 0.13.2 are only checked against
+    /// the amount of events in the header.
+    pub fn verify_events(
+        &self,
+        header: &BlockHeader,
+        events: &[Vec<Event>],
+        transaction_hashes: &[TransactionHash],
+    ) -> Result<(), BadPeerError> {
+        if !self.has_commitments(header) {
+            return Ok(());
+        }
+        let block_number = header.block_header_without_hash.block_number;
+        let expected =
+            header.event_commitment.ok_or(BadPeerError::MissingCommitments { block_number })?;
+        let event_leaf_elements = events
+            .iter()
+            .zip(transaction_hashes)
+            .flat_map(|(transaction_events, transaction_hash)| {
+                transaction_events.iter().map(|event| EventLeafElement {
+                    event: event.clone(),
+                    transaction_hash: *transaction_hash,
+                })
+            })
+            .collect::<Vec<_>>();
+        let actual = calculate_event_commitment::<Poseidon>(&event_leaf_elements);
+        if actual != expected {
+            return Err(BadPeerError::EventCommitmentMismatch { block_number, expected, actual });
+        }
+        Ok(())
+    }
+
+    /// Verifies the class against the hash it's declared with, and compiles it with the class
+    /// manager, if there is one, without storing it. The compiled class is checked against the
+    /// compiled class hash declared in the state diff regardless of `verify_blocks`, since a class
+    /// that doesn't match its compiled class hash can't be executed. Returns the compiled class,
+    /// which is added to the class manager along with the class when the block is written.
+    pub async fn verify_declared_class(
+        &self,
+        class_hash: ClassHash,
+        class: &SierraContractClass,
+        compiled_class_hash: CompiledClassHash,
+    ) -> Result<Option<ExecutableClass>, ParseDataError> {
+        if self.verify_blocks {
+            let actual = calculate_class_hash(class);
+            if actual != class_hash {
+                return Err(ParseDataError::BadPeer(BadPeerError::ClassHashMismatch {
+                    expected: class_hash,
+                    actual,
+                }));
+            }
+        }
+        let Some(class_manager_client) = &self.class_manager_client else {
+            return Ok(None);
+        };
+        let executable_class = match class_manager_client.compile_class(class.clone()).await {
+            Ok(executable_class) => executable_class,
+            Err(ClassManagerClientError::ClassManagerError(
+                ClassManagerError::CompilationUtilError(error),
+            )) => {
+                return Err(ParseDataError::BadPeer(BadPeerError::ClassCompilationFailed {
+                    class_hash,
+                    error,
+                }));
+            }
+            Err(err) => return Err(ParseDataError::Fatal(err.into())),
+        };
+        let actual = executable_class.compiled_class_hash();
+        if actual != compiled_class_hash {
+            return Err(ParseDataError::BadPeer(BadPeerError::CompiledClassHashMismatch {
+                class_hash,
+                expected: compiled_class_hash,
+                actual,
+            }));
+        }
+        Ok(Some(executable_class))
+    }
+
+    fn has_commitments(&self, header: &BlockHeader) -> bool {
+        header.block_header_without_hash.block_number >= self.first_block_with_commitments
+    }
+}
+
+// Returns the first block of the chain whose hash is calculated from its header, which is the
+// first block of Starknet 0.13.2. Other chains are expected to start from a later version.
+fn first_block_with_commitments(chain_id: &ChainId) -> BlockNumber {
+    match chain_id {
+        ChainId::Mainnet => BlockNumber(671813),
+        ChainId::Sepolia => BlockNumber(86311),
+        ChainId::IntegrationSepolia | ChainId::Other(_) => BlockNumber(0),
+    }
+}
+
+// Returns the hash of the block calculated from its header, or an error if the header is missing
+// data needed to calculate it.
+fn calculate_header_block_hash(header: &BlockHeader) -> Result<BlockHash, BadPeerError> {
+    let block_number = header.block_header_without_hash.block_number;
+    let missing_commitments = || BadPeerError::MissingCommitments { block_number };
+    let block_commitments = BlockHeaderCommitments {
+        transaction_commitment: header.transaction_commitment.ok_or_else(missing_commitments)?,
+        event_commitment: header.event_commitment.ok_or_else(missing_commitments)?,
+        receipt_commitment: header.receipt_commitment.ok_or_else(missing_commitments)?,
+        state_diff_commitment: header.state_diff_commitment.ok_or_else(missing_commitments)?,
+        concatenated_counts: concat_counts(
+            header.n_transactions,
+            header.n_events,
+            header.state_diff_length.ok_or_else(missing_commitments)?,
+            header.block_header_without_hash.l1_da_mode,
+        ),
+    };
+    // Fails for versions whose block hash isn't calculated from the header.
+    calculate_block_hash(header.block_header_without_hash.clone(), block_commitments).map_err(
+        |_| BadPeerError::OldStarknetVersion {
+            block_number,
+            starknet_version: header.block_header_without_hash.starknet_version,
+        },
+    )
+}
+
+fn transaction_signature(transaction: &Transaction) -> TransactionSignature {
+    match transaction {
+        Transaction::Declare(tx) => tx.signature(),
+        Transaction::DeployAccount(tx) => tx.signature(),
+        Transaction::Invoke(tx) => tx.signature(),
+        Transaction::Deploy(_) | Transaction::L1Handler(_) => TransactionSignature::default(),
+    }
+}
+
+// The events aren't part of the receipt hash, so they're left empty.
+fn transaction_output_for_hash(transaction_output: &TransactionOutput) -> TransactionOutputForHash {
+    TransactionOutputForHash {
+        actual_fee: transaction_output.actual_fee(),
+        events: Vec::new(),
+        execution_status: transaction_output.execution_status().clone(),
+        gas_consumed: transaction_output.execution_resources().gas_consumed,
+        messages_sent: transaction_output.messages_sent().clone(),
+    }
+}
@@ -0,0 +1,138 @@
+use assert_matches::assert_matches;
+use papyrus_common::class_hash::calculate_class_hash;
+use papyrus_protobuf::sync::SignedBlockHeader;
+use papyrus_test_utils::get_rng;
+use starknet_api::block::{BlockHash, BlockNumber, BlockSignature, StarknetVersion};
+use starknet_api::core::{
+    ChainId,
+    ClassHash,
+    CompiledClassHash,
+    SequencerPublicKey,
+    StateDiffCommitment,
+};
+use starknet_api::crypto::utils::{PublicKey, Signature};
+use starknet_api::felt;
+use starknet_api::hash::PoseidonHash;
+use starknet_api::state::SierraContractClass;
+
+use super::stream_builder::{BadPeerError, ParseDataError};
+use super::test_utils::{random_header, set_block_hash_from_header};
+use super::verification::DataVerifier;
+use super::{P2PSyncClientConfig, P2PSyncClientError};
+
+fn verifier(chain_id: ChainId, sequencer_public_key: Option<SequencerPublicKey>) -> DataVerifier {
+    DataVerifier::new(
+        chain_id,
+        &P2PSyncClientConfig { verify_blocks: true, sequencer_public_key, ..Default::default() },
+        None,
+    )
+}
+
+// Returns the header of the first Mainnet block with its hash, state diff commitment and
+// signature, and the public key of the Mainnet sequencer. The rest of the header is random, since
+// the hash of blocks from before Starknet 0.13.2 isn't calculated from the header.
+fn mainnet_signed_header() -> (SignedBlockHeader, SequencerPublicKey) {
+    let mut header = random_header(&mut get_rng(), BlockNumber(0), None, None);
+    header.block_header.block_header_without_hash.starknet_version = StarknetVersion::V0_13_1;
+    header.block_header.block_hash =
+        BlockHash(felt!("0x7d5db04c5ca2aea828180dc441afb1580e3cee7547a3567ced3aa5bb8b273c0"));
+    header.block_header.state_diff_commitment = Some(StateDiffCommitment(PoseidonHash(felt!(
+        "0x64689c12248e1110af4b3af0e2b43cd51ad13e8855f10e37669e2a4baf919c6"
+    ))));
+    header.signatures = vec![BlockSignature(Signature {
+        r: felt!("0x1b382bbfd693011c9b7692bc932b23ed9c288deb27c8e75772e172abbe5950c"),
+        s: felt!("0xbe4438085057e1a7c704a0da3b30f7b8340fe3d24c86772abfd24aa597e42"),
+    })];
+    let sequencer_public_key = SequencerPublicKey(PublicKey(felt!(
+        "0x48253ff2c3bed7af18bde0b611b083b39445959102d4947c51c4db6aa4f4e58"
+    )));
+    (header, sequencer_public_key)
+}
+
+#[test]
+fn old_signed_header_is_verified_by_signature() {
+    let (mut header, sequencer_public_key) = mainnet_signed_header();
+    let verifier = verifier(ChainId::Mainnet, Some(sequencer_public_key));
+    verifier.verify_header(&header).unwrap();
+
+    header.signatures[0].0.r = felt!("0x1234");
+    assert_matches!(
+        verifier.verify_header(&header),
+        Err(BadPeerError::InvalidBlockSignature { .. })
+    );
+}
+
+// The parent hash of blocks from before Starknet 0.13.2 isn't covered by the signature.
+#[test]
+fn old_signed_header_with_wrong_parent_hash_reports_peer() {
+    let (mut header, sequencer_public_key) = mainnet_signed_header();
+    header.block_header.block_header_without_hash.block_number = BlockNumber(1);
+    header.block_header.block_header_without_hash.parent_hash = BlockHash(felt!("0x1234"));
+    let verifier = verifier(ChainId::Mainnet, Some(sequencer_public_key));
+
+    verifier.verify_header(&header).unwrap();
+    assert_matches!(
+        verifier.verify_parent_hash(&header.block_header, BlockHash::default()),
+        Err(ParseDataError::BadPeer(BadPeerError::ParentHashMismatch { .. }))
+    );
+}
+
+#[test]
+fn new_header_with_wrong_parent_hash_fails_on_revert() {
+    // The first Mainnet block of Starknet 0.13.2.
+    let mut header = random_header(&mut get_rng(), BlockNumber(671813), None, None);
+    header.block_header.block_header_without_hash.parent_hash = BlockHash(felt!("0x1234"));
+    set_block_hash_from_header(&mut header.block_header);
+    let verifier = verifier(ChainId::Mainnet, Some(SequencerPublicKey(PublicKey(felt!("0x1")))));
+
+    assert_matches!(
+        verifier.verify_parent_hash(&header.block_header, BlockHash::default()),
+        Err(ParseDataError::Fatal(P2PSyncClientError::RevertNotSupported { .. }))
+    );
+}
+
+#[test]
+fn new_header_without_commitments_reports_peer() {
+    let mut header = random_header(&mut get_rng(), BlockNumber(0), None, None);
+    set_block_hash_from_header(&mut header.block_header);
+    header.block_header.receipt_commitment = None;
+    let verifier = verifier(ChainId::Other("SN_TEST".to_owned()), None);
+
+    assert_matches!(
+        verifier.verify_header(&header),
+        Err(BadPeerError::MissingCommitments { block_number: BlockNumber(0) })
+    );
+}
+
+// The Starknet version is set by the peer, so it can't exempt a block from the verification.
+#[test]
+fn new_header_with_old_version_reports_peer() {
+    let mut header = random_header(&mut get_rng(), BlockNumber(0), None, None);
+    set_block_hash_from_header(&mut header.block_header);
+    header.block_header.block_header_without_hash.starknet_version = StarknetVersion::V0_13_1;
+    let verifier = verifier(ChainId::Other("SN_TEST".to_owned()), None);
+
+    assert_matches!(
+        verifier.verify_header(&header),
+        Err(BadPeerError::OldStarknetVersion { block_number: BlockNumber(0), .. })
+    );
+}
+
+#[tokio::test]
+async fn class_with_wrong_hash_reports_peer() {
+    let verifier = verifier(ChainId::Mainnet, None);
+    let class = SierraContractClass::default();
+    let class_hash = calculate_class_hash(&class);
+    let compiled_class_hash = CompiledClassHash(felt!("0x1"));
+    assert_eq!(
+        verifier.verify_declared_class(class_hash, &class, compiled_class_hash).await.unwrap(),
+        None
+    );
+
+    let wrong_class_hash = ClassHash(felt!("0x1234"));
+    assert_matches!(
+        verifier.verify_declared_class(wrong_class_hash, &class, compiled_class_hash).await,
+        Err(ParseDataError::BadPeer(BadPeerError::ClassHashMismatch { expected, actual }))
+        if expected == wrong_class_hash && actual == class_hash
+    );
+}
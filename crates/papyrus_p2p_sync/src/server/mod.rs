@@ -303,6 +303,12 @@ impl FetchBlockDataFromDb for (Event, TransactionHash) {
         block_number: BlockNumber,
         txn: &StorageTxn<'_, db::RO>,
     ) -> Result<Vec<Self>, P2PSyncServerError> {
+        // The transaction outputs of blocks above the event marker don't contain their events yet.
+        if txn.get_event_marker()? <= block_number {
+            return Err(P2PSyncServerError::BlockNotFound {
+                block_hash_or_number: BlockHashOrNumber::Number(block_number),
+            });
+        }
         let transaction_outputs = txn.get_block_transaction_outputs(block_number)?.ok_or(
             P2PSyncServerError::BlockNotFound {
                 block_hash_or_number: BlockHashOrNumber::Number(block_number),
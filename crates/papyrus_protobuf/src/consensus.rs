@@ -1,4 +1,4 @@
-use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::block::{BlockHash, BlockNumber, GasPricePerToken};
 use starknet_api::core::ContractAddress;
 use starknet_api::transaction::Transaction;
 
@@ -44,6 +44,10 @@ pub struct ProposalInit {
     pub valid_round: Option<u32>,
     /// Address of the one who proposed the block.
     pub proposer: ContractAddress,
+    /// The L1 gas price the proposer built the block with.
+    pub l1_gas_price: GasPricePerToken,
+    /// The L1 data gas price the proposer built the block with.
+    pub l1_data_gas_price: GasPricePerToken,
 }
 
 /// A temporary constant to use as a validator ID. Zero is not a valid contract address.
@@ -57,6 +61,8 @@ impl Default for ProposalInit {
             round: Default::default(),
             valid_round: Default::default(),
             proposer: ContractAddress::from(DEFAULT_VALIDATOR_ID),
+            l1_gas_price: Default::default(),
+            l1_data_gas_price: Default::default(),
         }
     }
 }
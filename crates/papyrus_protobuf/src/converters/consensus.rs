@@ -4,7 +4,7 @@ mod consensus_test;
 use std::convert::{TryFrom, TryInto};
 
 use prost::Message;
-use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::block::{BlockHash, BlockNumber, GasPricePerToken};
 use starknet_api::hash::StarkHash;
 use starknet_api::transaction::Transaction;
 
@@ -163,7 +163,42 @@ impl TryFrom<protobuf::ProposalInit> for ProposalInit {
             .proposer
             .ok_or(ProtobufConversionError::MissingField { field_description: "proposer" })?
             .try_into()?;
-        Ok(ProposalInit { height: BlockNumber(height), round, valid_round, proposer })
+        let l1_gas_price = GasPricePerToken {
+            price_in_wei: u128::from(value.l1_gas_price_wei.ok_or(
+                ProtobufConversionError::MissingField {
+                    field_description: "ProposalInit::l1_gas_price_wei",
+                },
+            )?)
+            .into(),
+            price_in_fri: u128::from(value.l1_gas_price_fri.ok_or(
+                ProtobufConversionError::MissingField {
+                    field_description: "ProposalInit::l1_gas_price_fri",
+                },
+            )?)
+            .into(),
+        };
+        let l1_data_gas_price = GasPricePerToken {
+            price_in_wei: u128::from(value.l1_data_gas_price_wei.ok_or(
+                ProtobufConversionError::MissingField {
+                    field_description: "ProposalInit::l1_data_gas_price_wei",
+                },
+            )?)
+            .into(),
+            price_in_fri: u128::from(value.l1_data_gas_price_fri.ok_or(
+                ProtobufConversionError::MissingField {
+                    field_description: "ProposalInit::l1_data_gas_price_fri",
+                },
+            )?)
+            .into(),
+        };
+        Ok(ProposalInit {
+            height: BlockNumber(height),
+            round,
+            valid_round,
+            proposer,
+            l1_gas_price,
+            l1_data_gas_price,
+        })
     }
 }
 
@@ -174,6 +209,10 @@ impl From<ProposalInit> for protobuf::ProposalInit {
             round: value.round,
             valid_round: value.valid_round,
             proposer: Some(value.proposer.into()),
+            l1_gas_price_wei: Some(value.l1_gas_price.price_in_wei.0.into()),
+            l1_gas_price_fri: Some(value.l1_gas_price.price_in_fri.0.into()),
+            l1_data_gas_price_wei: Some(value.l1_data_gas_price.price_in_wei.0.into()),
+            l1_data_gas_price_fri: Some(value.l1_data_gas_price.price_in_fri.0.into()),
         }
     }
 }
@@ -1,6 +1,6 @@
 use papyrus_test_utils::{auto_impl_get_test_instance, get_number_of_variants, GetTestInstance};
 use rand::Rng;
-use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::block::{BlockHash, BlockNumber, GasPricePerToken};
 use starknet_api::core::ContractAddress;
 use starknet_api::transaction::Transaction;
 
@@ -32,6 +32,8 @@ auto_impl_get_test_instance! {
         pub round: u32,
         pub valid_round: Option<u32>,
         pub proposer: ContractAddress,
+        pub l1_gas_price: GasPricePerToken,
+        pub l1_data_gas_price: GasPricePerToken,
     }
     pub struct ProposalFin {
         pub proposal_content_id: BlockHash,
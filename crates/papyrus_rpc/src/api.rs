@@ -57,6 +57,7 @@ pub fn get_methods_from_supported_apis(
     storage_reader: StorageReader,
     max_events_chunk_size: usize,
     max_events_keys: usize,
+    storage_proofs_enabled: bool,
     starting_block: BlockHashAndNumber,
     shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
     pending_data: Arc<RwLock<PendingData>>,
@@ -70,6 +71,7 @@ pub fn get_methods_from_supported_apis(
         storage_reader,
         max_events_chunk_size,
         max_events_keys,
+        storage_proofs_enabled,
         starting_block,
         shared_highest_block,
         pending_data,
@@ -109,6 +111,7 @@ pub trait JsonRpcServerTrait: Sized {
         storage_reader: StorageReader,
         max_events_chunk_size: usize,
         max_events_keys: usize,
+        storage_proofs_enabled: bool,
         starting_block: BlockHashAndNumber,
         shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
         pending_data: Arc<RwLock<PendingData>>,
@@ -126,6 +129,7 @@ struct JsonRpcServerImplGenerator {
     storage_reader: StorageReader,
     max_events_chunk_size: usize,
     max_events_keys: usize,
+    storage_proofs_enabled: bool,
     starting_block: BlockHashAndNumber,
     shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
     pending_data: Arc<RwLock<PendingData>>,
@@ -140,6 +144,7 @@ type JsonRpcServerImplParams = (
     StorageReader,
     usize,
     usize,
+    bool,
     BlockHashAndNumber,
     Arc<RwLock<Option<BlockHashAndNumber>>>,
     Arc<RwLock<PendingData>>,
@@ -155,6 +160,7 @@ impl JsonRpcServerImplGenerator {
             self.storage_reader,
             self.max_events_chunk_size,
             self.max_events_keys,
+            self.storage_proofs_enabled,
             self.starting_block,
             self.shared_highest_block,
             self.pending_data,
@@ -173,6 +179,7 @@ impl JsonRpcServerImplGenerator {
             storage_reader,
             max_events_chunk_size,
             max_events_keys,
+            storage_proofs_enabled,
             starting_block,
             shared_highest_block,
             pending_data,
@@ -186,6 +193,7 @@ impl JsonRpcServerImplGenerator {
                 storage_reader,
                 max_events_chunk_size,
                 max_events_keys,
+                storage_proofs_enabled,
                 starting_block,
                 shared_highest_block,
                 pending_data,
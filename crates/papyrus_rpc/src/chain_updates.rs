@@ -0,0 +1,189 @@
+//! Detection of the updates of the chain that the subscriptions are notified about.
+//!
+//! The storage and the pending data don't notify about their changes, so a single task polls them
+//! and broadcasts the updates to all the subscriptions: blocks that were accepted, blocks that were
+//! reverted, blocks that were accepted on L1 and updates of the pending block. The subscriptions
+//! read the data they notify about from the storage and the pending data themselves.
+#[cfg(test)]
+#[path = "chain_updates_test.rs"]
+mod chain_updates_test;
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use papyrus_storage::base_layer::BaseLayerStorageReader;
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::db::TransactionKind;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::{StorageReader, StorageResult, StorageScope, StorageTxn};
+use starknet_api::block::{BlockHash, BlockHashAndNumber, BlockNumber};
+use starknet_client::reader::PendingData;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, error};
+
+// The number of updates that a subscription may fall behind before it's closed.
+const UPDATES_CHANNEL_CAPACITY: usize = 1000;
+// The number of the most recent accepted blocks whose hashes are kept for detecting reverts. A
+// revert of more blocks is reported as a revert of the blocks whose hashes are kept.
+const MAX_REVERT_DEPTH: usize = 1024;
+
+/// An update of the chain.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ChainUpdate {
+    /// Blocks were accepted, up to the given block.
+    NewBlocks { latest_block_number: BlockNumber },
+    /// The given range of blocks (inclusive) was reverted.
+    Reorg { first_reverted: BlockHashAndNumber, last_reverted: BlockHashAndNumber },
+    /// Blocks were accepted on L1, up to the given marker.
+    AcceptedOnL1 { base_layer_marker: BlockNumber },
+    /// The pending block was updated.
+    Pending,
+}
+
+/// Returns the first block that isn't accepted yet. A block is accepted once its state diff and,
+/// unless the storage is state only, its body and its events are stored.
+pub(crate) fn get_accepted_block_marker<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    storage_scope: StorageScope,
+) -> StorageResult<BlockNumber> {
+    let state_marker = txn.get_state_marker()?;
+    match storage_scope {
+        StorageScope::StateOnly => Ok(state_marker),
+        // The events may be stored after the body, so the event marker is at most the body marker.
+        StorageScope::FullArchive => Ok(state_marker.min(txn.get_event_marker()?)),
+    }
+}
+
+/// Polls the storage and the pending data and broadcasts their updates.
+pub(crate) struct ChainUpdatesPoller {
+    storage_reader: StorageReader,
+    pending_data: Arc<RwLock<PendingData>>,
+    sender: broadcast::Sender<ChainUpdate>,
+    // The most recent accepted blocks, in ascending order.
+    recent_blocks: VecDeque<BlockHashAndNumber>,
+    base_layer_marker: BlockNumber,
+    // The parent hash and the number of transactions of the pending block.
+    pending_block: (BlockHash, usize),
+}
+
+impl ChainUpdatesPoller {
+    pub(crate) fn new(
+        storage_reader: StorageReader,
+        pending_data: Arc<RwLock<PendingData>>,
+    ) -> StorageResult<Self> {
+        let (sender, _) = broadcast::channel(UPDATES_CHANNEL_CAPACITY);
+        let txn = storage_reader.begin_ro_txn()?;
+        let mut recent_blocks = VecDeque::new();
+        if let Some(latest_block_number) =
+            get_accepted_block_marker(&txn, storage_reader.get_scope())?.prev()
+        {
+            if let Some(header) = txn.get_block_header(latest_block_number)? {
+                recent_blocks.push_back(BlockHashAndNumber {
+                    hash: header.block_hash,
+                    number: latest_block_number,
+                });
+            }
+        }
+        let base_layer_marker = txn.get_base_layer_block_marker()?;
+        drop(txn);
+        Ok(Self {
+            storage_reader,
+            pending_data,
+            sender,
+            recent_blocks,
+            base_layer_marker,
+            pending_block: (BlockHash::default(), 0),
+        })
+    }
+
+    /// Returns a sender whose receivers get the updates of the chain.
+    pub(crate) fn updates_sender(&self) -> broadcast::Sender<ChainUpdate> {
+        self.sender.clone()
+    }
+
+    /// Polls for updates forever, waiting the given interval between polls.
+    pub(crate) async fn run(mut self, poll_interval: Duration) {
+        loop {
+            if let Err(err) = self.poll().await {
+                error!("Failed to poll for updates of the chain: {err}.");
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Polls the storage and the pending data once and broadcasts their updates.
+    pub(crate) async fn poll(&mut self) -> StorageResult<()> {
+        self.poll_storage()?;
+        let pending_data = self.pending_data.read().await;
+        let pending_block =
+            (pending_data.block.parent_block_hash(), pending_data.block.transactions().len());
+        drop(pending_data);
+        if pending_block != self.pending_block {
+            self.pending_block = pending_block;
+            self.send(ChainUpdate::Pending);
+        }
+        Ok(())
+    }
+
+    fn poll_storage(&mut self) -> StorageResult<()> {
+        let txn = self.storage_reader.begin_ro_txn()?;
+        let accepted_block_marker =
+            get_accepted_block_marker(&txn, self.storage_reader.get_scope())?;
+
+        // The blocks whose hashes changed, or that are no longer accepted, were reverted.
+        let mut reverted_blocks = Vec::new();
+        while let Some(block) = self.recent_blocks.back() {
+            if block.number < accepted_block_marker
+                && txn.get_block_header(block.number)?.map(|header| header.block_hash)
+                    == Some(block.hash)
+            {
+                break;
+            }
+            reverted_blocks.extend(self.recent_blocks.pop_back());
+        }
+        if let (Some(first_reverted), Some(last_reverted)) =
+            (reverted_blocks.last(), reverted_blocks.first())
+        {
+            debug!("Blocks {} to {} were reverted.", first_reverted.number, last_reverted.number);
+            self.send(ChainUpdate::Reorg {
+                first_reverted: *first_reverted,
+                last_reverted: *last_reverted,
+            });
+        }
+
+        let first_new_block_number = match (self.recent_blocks.back(), reverted_blocks.last()) {
+            (Some(block), _) => block.number.unchecked_next(),
+            (None, Some(first_reverted)) => first_reverted.number,
+            (None, None) => BlockNumber(0),
+        };
+        let mut latest_block_number = None;
+        for block_number in first_new_block_number.iter_up_to(accepted_block_marker) {
+            let Some(header) = txn.get_block_header(block_number)? else {
+                break;
+            };
+            self.recent_blocks
+                .push_back(BlockHashAndNumber { hash: header.block_hash, number: block_number });
+            if self.recent_blocks.len() > MAX_REVERT_DEPTH {
+                self.recent_blocks.pop_front();
+            }
+            latest_block_number = Some(block_number);
+        }
+        if let Some(latest_block_number) = latest_block_number {
+            self.send(ChainUpdate::NewBlocks { latest_block_number });
+        }
+
+        let base_layer_marker = txn.get_base_layer_block_marker()?;
+        if base_layer_marker > self.base_layer_marker {
+            self.send(ChainUpdate::AcceptedOnL1 { base_layer_marker });
+        }
+        self.base_layer_marker = base_layer_marker;
+        Ok(())
+    }
+
+    fn send(&self, update: ChainUpdate) {
+        // Sending fails only when there are no subscriptions.
+        let _ = self.sender.send(update);
+    }
+}
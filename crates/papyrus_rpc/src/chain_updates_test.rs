@@ -0,0 +1,212 @@
+use papyrus_storage::base_layer::BaseLayerStorageWriter;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::state::StateStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageWriter;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{
+    BlockBody,
+    BlockHash,
+    BlockHashAndNumber,
+    BlockHeader,
+    BlockHeaderWithoutHash,
+    BlockNumber,
+};
+use starknet_api::state::ThinStateDiff;
+use tokio::sync::broadcast::error::TryRecvError;
+
+use crate::chain_updates::{ChainUpdate, ChainUpdatesPoller};
+use crate::test_utils::get_test_pending_data;
+
+fn append_block(storage_writer: &mut StorageWriter, block_number: u64, block_hash: u64) {
+    let block_number = BlockNumber(block_number);
+    let header = BlockHeader {
+        block_hash: BlockHash(block_hash.into()),
+        block_header_without_hash: BlockHeaderWithoutHash { block_number, ..Default::default() },
+        ..Default::default()
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &header)
+        .unwrap()
+        .append_body(block_number, BlockBody::default())
+        .unwrap()
+        .append_state_diff(block_number, ThinStateDiff::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+}
+
+fn revert_block(storage_writer: &mut StorageWriter, block_number: u64) {
+    let block_number = BlockNumber(block_number);
+    let (txn, _) = storage_writer.begin_rw_txn().unwrap().revert_state_diff(block_number).unwrap();
+    let (txn, _) = txn.revert_body(block_number).unwrap();
+    let (txn, _, _) = txn.revert_header(block_number).unwrap();
+    txn.commit().unwrap();
+}
+
+fn block_hash_and_number(block_number: u64, block_hash: u64) -> BlockHashAndNumber {
+    BlockHashAndNumber { hash: BlockHash(block_hash.into()), number: BlockNumber(block_number) }
+}
+
+#[tokio::test]
+async fn new_blocks() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    append_block(&mut storage_writer, 0, 0);
+    let mut poller = ChainUpdatesPoller::new(storage_reader, get_test_pending_data()).unwrap();
+    let mut updates = poller.updates_sender().subscribe();
+
+    poller.poll().await.unwrap();
+    assert_eq!(updates.try_recv(), Err(TryRecvError::Empty));
+
+    append_block(&mut storage_writer, 1, 1);
+    append_block(&mut storage_writer, 2, 2);
+    poller.poll().await.unwrap();
+    assert_eq!(
+        updates.try_recv().unwrap(),
+        ChainUpdate::NewBlocks { latest_block_number: BlockNumber(2) }
+    );
+    assert_eq!(updates.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[tokio::test]
+async fn block_without_body_is_not_accepted() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let mut poller = ChainUpdatesPoller::new(storage_reader, get_test_pending_data()).unwrap();
+    let mut updates = poller.updates_sender().subscribe();
+
+    let header = BlockHeader::default();
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &header)
+        .unwrap()
+        .append_state_diff(BlockNumber(0), ThinStateDiff::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+    poller.poll().await.unwrap();
+    assert_eq!(updates.try_recv(), Err(TryRecvError::Empty));
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(BlockNumber(0), BlockBody::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+    poller.poll().await.unwrap();
+    assert_eq!(
+        updates.try_recv().unwrap(),
+        ChainUpdate::NewBlocks { latest_block_number: BlockNumber(0) }
+    );
+}
+
+#[tokio::test]
+async fn block_without_events_is_not_accepted() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let mut poller = ChainUpdatesPoller::new(storage_reader, get_test_pending_data()).unwrap();
+    let mut updates = poller.updates_sender().subscribe();
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .append_body_without_events(BlockNumber(0), BlockBody::default())
+        .unwrap()
+        .append_state_diff(BlockNumber(0), ThinStateDiff::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+    poller.poll().await.unwrap();
+    assert_eq!(updates.try_recv(), Err(TryRecvError::Empty));
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_events(BlockNumber(0), vec![])
+        .unwrap()
+        .commit()
+        .unwrap();
+    poller.poll().await.unwrap();
+    assert_eq!(
+        updates.try_recv().unwrap(),
+        ChainUpdate::NewBlocks { latest_block_number: BlockNumber(0) }
+    );
+}
+
+#[tokio::test]
+async fn reorg() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    for block_number in 0..4 {
+        append_block(&mut storage_writer, block_number, block_number);
+    }
+    let mut poller = ChainUpdatesPoller::new(storage_reader, get_test_pending_data()).unwrap();
+    let mut updates = poller.updates_sender().subscribe();
+    // Fill the window of recent blocks beyond the latest block.
+    append_block(&mut storage_writer, 4, 4);
+    poller.poll().await.unwrap();
+    assert_eq!(
+        updates.try_recv().unwrap(),
+        ChainUpdate::NewBlocks { latest_block_number: BlockNumber(4) }
+    );
+
+    // Revert blocks 3 and 4 and replace block 3.
+    revert_block(&mut storage_writer, 4);
+    revert_block(&mut storage_writer, 3);
+    append_block(&mut storage_writer, 3, 13);
+    poller.poll().await.unwrap();
+    assert_eq!(
+        updates.try_recv().unwrap(),
+        ChainUpdate::Reorg {
+            first_reverted: block_hash_and_number(3, 3),
+            last_reverted: block_hash_and_number(4, 4),
+        }
+    );
+    assert_eq!(
+        updates.try_recv().unwrap(),
+        ChainUpdate::NewBlocks { latest_block_number: BlockNumber(3) }
+    );
+
+    // Revert the replacing block without replacing it.
+    revert_block(&mut storage_writer, 3);
+    poller.poll().await.unwrap();
+    assert_eq!(
+        updates.try_recv().unwrap(),
+        ChainUpdate::Reorg {
+            first_reverted: block_hash_and_number(3, 13),
+            last_reverted: block_hash_and_number(3, 13),
+        }
+    );
+    assert_eq!(updates.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[tokio::test]
+async fn accepted_on_l1_and_pending() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    append_block(&mut storage_writer, 0, 0);
+    let pending_data = get_test_pending_data();
+    let mut poller = ChainUpdatesPoller::new(storage_reader, pending_data.clone()).unwrap();
+    let mut updates = poller.updates_sender().subscribe();
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_base_layer_block_marker(&BlockNumber(1))
+        .unwrap()
+        .commit()
+        .unwrap();
+    poller.poll().await.unwrap();
+    assert_eq!(
+        updates.try_recv().unwrap(),
+        ChainUpdate::AcceptedOnL1 { base_layer_marker: BlockNumber(1) }
+    );
+
+    *pending_data.write().await.block.parent_block_hash_mutable() = BlockHash(1_u64.into());
+    poller.poll().await.unwrap();
+    assert_eq!(updates.try_recv().unwrap(), ChainUpdate::Pending);
+    assert_eq!(updates.try_recv(), Err(TryRecvError::Empty));
+}
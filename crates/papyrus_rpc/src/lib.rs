@@ -3,8 +3,10 @@
 #![cfg_attr(coverage_nightly, feature(coverage_attribute))]
 
 mod api;
+mod chain_updates;
 mod middleware;
 mod pending;
+mod rate_limiter;
 mod rpc_metrics;
 #[cfg(test)]
 mod rpc_test;
@@ -17,14 +19,25 @@ mod version_config;
 use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use jsonrpsee::core::RpcResult;
-use jsonrpsee::server::{ServerBuilder, ServerHandle};
+use jsonrpsee::server::{
+    serve_with_graceful_shutdown,
+    stop_channel,
+    BatchRequestConfig,
+    HttpBody,
+    HttpRequest,
+    RpcServiceBuilder,
+    ServerBuilder,
+    ServerHandle,
+};
 use jsonrpsee::types::error::ErrorCode::InternalError;
 use jsonrpsee::types::error::INTERNAL_ERROR_MSG;
 use jsonrpsee::types::ErrorObjectOwned;
 pub use latest::error;
 use papyrus_common::pending_classes::PendingClasses;
+use papyrus_config::converters::deserialize_milliseconds_to_duration;
 use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
 use papyrus_config::validators::validate_ascii;
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
@@ -41,16 +54,23 @@ use starknet_api::core::ChainId;
 use starknet_client::reader::PendingData;
 use starknet_client::writer::StarknetGatewayClient;
 use starknet_client::RetryConfig;
+use tokio::net::TcpListener;
 use tokio::sync::RwLock;
-use tracing::{debug, error, info, instrument};
+use tower::filter::AsyncFilter;
+use tower::Service;
+use tracing::{debug, error, info, instrument, warn};
 // Aliasing the latest version of the RPC.
 use v0_8 as latest;
 pub use v0_8::api::CompiledContractClass;
 use validator::Validate;
 
 use crate::api::get_methods_from_supported_apis;
-use crate::middleware::{deny_requests_with_unsupported_path, proxy_rpc_request};
+use crate::chain_updates::ChainUpdatesPoller;
+use crate::middleware::{deny_requests_with_unsupported_path, proxy_rpc_request, RateLimitLayer};
+pub use crate::rate_limiter::RateLimitConfig;
+use crate::rate_limiter::RateLimiter;
 use crate::syncing_state::get_last_synced_block;
+use crate::v0_8::subscriptions::get_subscriptions_module;
 pub use crate::v0_8::transaction::{
     InvokeTransaction as InvokeTransactionRPC0_8,
     InvokeTransactionV1 as InvokeTransactionV1RPC0_8,
@@ -66,6 +86,10 @@ const GENESIS_HASH: &str = "0x0";
 /// Maximum size of a supported transaction body - 10MB.
 pub const SERVER_MAX_BODY_SIZE: u32 = 10 * 1024 * 1024;
 
+// Accepting fails mostly when the process is out of file descriptors, which an immediate retry
+// won't fix.
+const ACCEPT_ERROR_SLEEP_DURATION: Duration = Duration::from_millis(100);
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Validate)]
 pub struct RpcConfig {
     #[validate(custom = "validate_ascii")]
@@ -73,10 +97,15 @@ pub struct RpcConfig {
     pub server_address: String,
     pub max_events_chunk_size: usize,
     pub max_events_keys: usize,
+    pub storage_proofs_enabled: bool,
     pub collect_metrics: bool,
     pub starknet_url: String,
     pub starknet_gateway_retry_config: RetryConfig,
     pub execution_config: ExecutionConfig,
+    pub max_subscriptions_per_connection: u32,
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub subscriptions_poll_interval: Duration,
+    pub rate_limit: RateLimitConfig,
 }
 
 impl Default for RpcConfig {
@@ -86,6 +115,7 @@ impl Default for RpcConfig {
             server_address: String::from("0.0.0.0:8080"),
             max_events_chunk_size: 1000,
             max_events_keys: 100,
+            storage_proofs_enabled: false,
             collect_metrics: false,
             starknet_url: String::from("https://alpha-mainnet.starknet.io/"),
             starknet_gateway_retry_config: RetryConfig {
@@ -94,6 +124,9 @@ impl Default for RpcConfig {
                 max_retries: 5,
             },
             execution_config: ExecutionConfig::default(),
+            max_subscriptions_per_connection: 16,
+            subscriptions_poll_interval: Duration::from_millis(500),
+            rate_limit: RateLimitConfig::default(),
         }
     }
 }
@@ -125,6 +158,14 @@ impl SerializeConfig for RpcConfig {
                 "Maximum number of keys supported by the node in get_events requests.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "storage_proofs_enabled",
+                &self.storage_proofs_enabled,
+                "If true, serve starknet_getStorageProof from the Patricia tries in the storage, \
+                 which the sync stores if sync.store_patricia_tries is enabled. Otherwise, the \
+                 method returns STORAGE_PROOF_NOT_SUPPORTED.",
+                ParamPrivacyInput::Public,
+            ),
             ser_param(
                 "collect_metrics",
                 &self.collect_metrics,
@@ -137,10 +178,24 @@ impl SerializeConfig for RpcConfig {
                 "URL for communicating with Starknet in write_api methods.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "max_subscriptions_per_connection",
+                &self.max_subscriptions_per_connection,
+                "Maximum number of subscriptions of a single WebSocket connection.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "subscriptions_poll_interval",
+                &self.subscriptions_poll_interval.as_millis(),
+                "Time in milliseconds between polls of the storage and the pending data for updates \
+                 to notify the subscriptions about.",
+                ParamPrivacyInput::Public,
+            ),
         ]);
 
         self_params_dump
             .append(&mut append_sub_config_name(self.execution_config.dump(), "execution_config"));
+        self_params_dump.append(&mut append_sub_config_name(self.rate_limit.dump(), "rate_limit"));
         let mut retry_config_dump = append_sub_config_name(
             self.starknet_gateway_retry_config.dump(),
             "starknet_gateway_retry_config",
@@ -211,12 +266,21 @@ pub async fn run_server(
 ) -> anyhow::Result<(SocketAddr, ServerHandle)> {
     let starting_block = get_last_synced_block(storage_reader.clone())?;
     debug!("Starting JSON-RPC.");
-    let methods = get_methods_from_supported_apis(
+    let chain_updates_poller =
+        ChainUpdatesPoller::new(storage_reader.clone(), pending_data.clone())?;
+    let subscriptions_module = get_subscriptions_module(
+        storage_reader.clone(),
+        pending_data.clone(),
+        chain_updates_poller.updates_sender(),
+        config.max_events_keys,
+    );
+    let mut methods = get_methods_from_supported_apis(
         &config.chain_id,
         config.execution_config,
         storage_reader,
         config.max_events_chunk_size,
         config.max_events_keys,
+        config.storage_proofs_enabled,
         starting_block,
         shared_highest_block,
         pending_data,
@@ -227,27 +291,64 @@ pub async fn run_server(
             config.starknet_gateway_retry_config,
         )?),
     );
-    let addr;
-    let handle;
-    let server_builder =
-        ServerBuilder::default().max_request_body_size(SERVER_MAX_BODY_SIZE).set_middleware(
+    methods.merge(subscriptions_module)?;
+    let rate_limiter = Arc::new(RateLimiter::new(&config.rate_limit)?);
+    let batch_request_config = match rate_limiter.is_enabled() {
+        true => BatchRequestConfig::Limit(rate_limiter.max_batch_size()),
+        false => BatchRequestConfig::Unlimited,
+    };
+    let rpc_middleware = RpcServiceBuilder::new()
+        .option_layer(config.collect_metrics.then(|| MetricLogger::new(&methods)))
+        .option_layer(rate_limiter.is_enabled().then(|| RateLimitLayer::new(rate_limiter.clone())));
+    let service_builder = ServerBuilder::default()
+        .max_request_body_size(SERVER_MAX_BODY_SIZE)
+        .max_subscriptions_per_connection(config.max_subscriptions_per_connection)
+        .set_batch_request_config(batch_request_config)
+        .set_rpc_middleware(rpc_middleware)
+        // The filters are layered with `layer_fn` since their layers can't be cloned for every
+        // connection.
+        .set_http_middleware(
             tower::ServiceBuilder::new()
-                .filter_async(deny_requests_with_unsupported_path)
-                .filter_async(proxy_rpc_request),
-        );
+                .layer_fn(|service| AsyncFilter::new(service, deny_requests_with_unsupported_path))
+                .layer_fn(|service| AsyncFilter::new(service, proxy_rpc_request)),
+        )
+        .to_service_builder();
 
-    if config.collect_metrics {
-        let server = server_builder
-            .set_logger(MetricLogger::new(&methods))
-            .build(&config.server_address)
-            .await?;
-        addr = server.local_addr()?;
-        handle = server.start(methods);
-    } else {
-        let server = server_builder.build(&config.server_address).await?;
-        addr = server.local_addr()?;
-        handle = server.start(methods);
-    }
+    // The server is served connection by connection, so that the rate limiter can identify the
+    // client of a request by the address it was received from.
+    let listener = TcpListener::bind(&config.server_address).await?;
+    let addr = listener.local_addr()?;
+    let (stop_handle, handle) = stop_channel();
+    tokio::spawn(async move {
+        loop {
+            let (socket, remote_address) = tokio::select! {
+                result = listener.accept() => match result {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        warn!("Failed to accept a JSON-RPC connection: {err}");
+                        tokio::time::sleep(ACCEPT_ERROR_SLEEP_DURATION).await;
+                        continue;
+                    }
+                },
+                _ = stop_handle.clone().shutdown() => break,
+            };
+            let service = service_builder.clone().build(methods.clone(), stop_handle.clone());
+            let rate_limiter = rate_limiter.clone();
+            let connection_service = tower::service_fn(move |request: HttpRequest<_>| {
+                let mut request = request.map(HttpBody::new);
+                let client_id = rate_limiter.client_id(remote_address.ip(), &request);
+                request.extensions_mut().insert(client_id);
+                let mut service = service.clone();
+                async move { service.call(request).await }
+            });
+            tokio::spawn(serve_with_graceful_shutdown(
+                socket,
+                connection_service,
+                stop_handle.clone().shutdown(),
+            ));
+        }
+    });
+    tokio::spawn(chain_updates_poller.run(config.subscriptions_poll_interval));
     info!(local_address = %addr, "JSON-RPC is running.");
     Ok((addr, handle))
 }
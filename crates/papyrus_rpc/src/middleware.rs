@@ -1,23 +1,40 @@
-use hyper::{Body, Request};
+use std::sync::Arc;
+use std::time::Instant;
+
+use futures_util::future::{ready, Either, Ready};
 use jsonrpsee::core::http_helpers::read_body;
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::server::ws::is_upgrade_request;
+use jsonrpsee::server::{HttpRequest, MethodResponse};
+use jsonrpsee::types::error::SERVER_IS_BUSY_CODE;
+use jsonrpsee::types::ErrorObjectOwned;
 use regex::Regex;
-use tower::BoxError;
+use tower::{BoxError, Layer};
 use tracing::{debug, instrument};
 
+use crate::internal_server_error;
+use crate::rate_limiter::{ClientId, RateLimiter};
+use crate::rpc_metrics::increment_rate_limited_requests;
 use crate::version_config::{VersionState, VERSION_CONFIG, VERSION_PATTERN};
 use crate::SERVER_MAX_BODY_SIZE;
 
+const RATE_LIMIT_EXCEEDED_MSG: &str = "Rate limit exceeded";
+
 /// [`Tower`] middleware intended to proxy method requests to the right version of the API.
 /// The middleware reads the JsonRPC request body and request path
 /// then prefixes the method name with the appropriate version identifier.
-/// It returns a new [`hyper::Request`] object with the new method name.
+/// It returns a new [`HttpRequest`] object with the new method name.
+/// WebSocket upgrade requests are passed as is, since their messages aren't part of the request.
 ///
 /// # Arguments
-/// * req - [`hyper::Request`] object passed by the server.
+/// * req - [`HttpRequest`] object passed by the server.
 ///
 /// [`Tower`]: https://crates.io/crates/tower
-pub(crate) async fn proxy_rpc_request(req: Request<Body>) -> Result<Request<Body>, BoxError> {
+pub(crate) async fn proxy_rpc_request(req: HttpRequest) -> Result<HttpRequest, BoxError> {
     debug!("proxy_rpc_request -> Request received: {:?}", req);
+    if is_upgrade_request(&req) {
+        return Ok(req);
+    }
     let uri = &req.uri().clone();
     let prefix = get_version_as_prefix(uri.path())?;
     let (parts, body) = req.into_parts();
@@ -34,19 +51,19 @@ pub(crate) async fn proxy_rpc_request(req: Request<Body>) -> Result<Request<Body
             add_version_to_method_name_in_body(vec_body, prefix, is_single)
         }
     }?;
-    Ok(Request::from_parts(parts, new_body.into()))
+    Ok(HttpRequest::from_parts(parts, new_body.into()))
 }
 
 /// ['Tower`] middleware intended to deny requests with unsupported paths.
 /// supported paths are paths that starts with '/rpc/' followed by a supported version id.
 ///
 /// # Arguments
-/// * req - [`hyper::Request`] object passed by the server.
+/// * req - [`HttpRequest`] object passed by the server.
 ///
 /// [`Tower`]: https://crates.io/crates/tower
 pub(crate) async fn deny_requests_with_unsupported_path(
-    req: Request<Body>,
-) -> Result<Request<Body>, BoxError> {
+    req: HttpRequest,
+) -> Result<HttpRequest, BoxError> {
     debug!("deny_requests_with_unsupported_path -> Request received: {:?}", req);
     let uri = req.uri();
     match is_supported_path(uri.path()) {
@@ -55,6 +72,65 @@ pub(crate) async fn deny_requests_with_unsupported_path(
     }
 }
 
+/// RPC middleware intended to limit the rate of calls of every client.
+/// The middleware charges the client of every call with the cost of its method, including the
+/// calls of batch requests and the calls sent over WebSocket connections. A call the client can't
+/// afford is answered with an error without being executed.
+/// The client is identified by the server when it receives the HTTP request, see
+/// [`RateLimiter::client_id`].
+#[derive(Clone)]
+pub(crate) struct RateLimitLayer {
+    rate_limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitLayer {
+    pub(crate) fn new(rate_limiter: Arc<RateLimiter>) -> Self {
+        Self { rate_limiter }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        RateLimit { service, rate_limiter: self.rate_limiter.clone() }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct RateLimit<S> {
+    service: S,
+    rate_limiter: Arc<RateLimiter>,
+}
+
+impl<'a, S> RpcServiceT<'a> for RateLimit<S>
+where
+    S: RpcServiceT<'a>,
+{
+    type Future = Either<S::Future, Ready<MethodResponse>>;
+
+    fn call(&self, request: jsonrpsee::types::Request<'a>) -> Self::Future {
+        let Some(client_id) = request.extensions().get::<ClientId>().cloned() else {
+            return Either::Right(ready(MethodResponse::error(
+                request.id,
+                internal_server_error("The client of the request wasn't identified"),
+            )));
+        };
+        // The method name is versioned, e.g. "starknet_V0_8_getEvents", unless it's of a
+        // subscription, e.g. "starknet_subscribeEvents".
+        let method = request.method_name().rsplit('_').next().unwrap_or_default();
+        let cost = self.rate_limiter.method_cost(method);
+        if !self.rate_limiter.try_consume(client_id, cost, Instant::now()) {
+            increment_rate_limited_requests();
+            return Either::Right(ready(MethodResponse::error(
+                request.id,
+                ErrorObjectOwned::owned(SERVER_IS_BUSY_CODE, RATE_LIMIT_EXCEEDED_MSG, None::<()>),
+            )));
+        }
+        Either::Left(self.service.call(request))
+    }
+}
+
 fn add_version_to_method_name_in_body(
     mut vec_body: Vec<jsonrpsee::types::Request<'_>>,
     prefix: &str,
@@ -0,0 +1,312 @@
+//! Per-client rate limiting of JSON-RPC requests.
+//!
+//! Every method is assigned a cost in units, and every client has a token bucket of units that
+//! refills at a constant rate. A call is rejected if the bucket of its client doesn't hold enough
+//! units to pay for it. The calls of a batch request and of a WebSocket connection are charged one
+//! by one.
+#[cfg(test)]
+#[path = "rate_limiter_test.rs"]
+mod rate_limiter_test;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use anyhow::anyhow;
+use jsonrpsee::server::HttpRequest;
+use papyrus_config::converters::{deserialize_optional_map, serialize_optional_map};
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+
+// The header to which proxies append the address they received the request from.
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+// Above this number of tracked clients, the clients whose bucket is full are forgotten.
+const MAX_TRACKED_CLIENTS: usize = 10000;
+
+// The costs of the methods that are more expensive than a storage read. Other methods cost
+// `default_method_cost` units.
+const DEFAULT_METHOD_COSTS: [(&str, u32); 8] = [
+    ("call", 5),
+    ("estimateFee", 10),
+    ("estimateMessageFee", 10),
+    ("getEvents", 5),
+    ("getStorageProof", 10),
+    ("simulateTransactions", 20),
+    ("traceBlockTransactions", 50),
+    ("traceTransaction", 10),
+];
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub cost_units_per_second: u32,
+    pub burst_seconds: u32,
+    pub default_method_cost: u32,
+    #[serde(deserialize_with = "deserialize_optional_map")]
+    pub method_costs: Option<HashMap<String, String>>,
+    pub api_key_header: String,
+    #[serde(deserialize_with = "deserialize_optional_map")]
+    pub api_keys: Option<HashMap<String, String>>,
+    pub trusted_proxies: String,
+    pub max_batch_size: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            enabled: false,
+            cost_units_per_second: 100,
+            burst_seconds: 2,
+            default_method_cost: 1,
+            method_costs: None,
+            api_key_header: String::from("x-api-key"),
+            api_keys: None,
+            trusted_proxies: String::new(),
+            max_batch_size: 100,
+        }
+    }
+}
+
+impl SerializeConfig for RateLimitConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "enabled",
+                &self.enabled,
+                "If true, limit the rate of requests of every client and the size of batch \
+                 requests.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "cost_units_per_second",
+                &self.cost_units_per_second,
+                "Cost units a client identified by its IP address is allowed to spend every \
+                 second.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "burst_seconds",
+                &self.burst_seconds,
+                "Number of seconds worth of cost units a client may accumulate and spend at once.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "default_method_cost",
+                &self.default_method_cost,
+                "Cost units of a method that has no explicit cost.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "method_costs",
+                &serialize_optional_map(&self.method_costs),
+                "'method1:cost1 method2:cost2 ...' cost units of methods, overriding the built-in \
+                 costs of the expensive methods.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "api_key_header",
+                &self.api_key_header,
+                "The header in which clients send their API key.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "api_keys",
+                &serialize_optional_map(&self.api_keys),
+                "'key1:units1 key2:units2 ...' API keys and the cost units their clients are \
+                 allowed to spend every second.",
+                ParamPrivacyInput::Private,
+            ),
+            ser_param(
+                "trusted_proxies",
+                &self.trusted_proxies,
+                "'address1 address2 ...' IP addresses of the proxies in front of the node. The \
+                 client of a request that one of them sent is identified by the x-forwarded-for \
+                 header, and any other client by the address it connected from.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_batch_size",
+                &self.max_batch_size,
+                "Maximum number of calls in a batch request.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+/// The identity of a client for the purpose of rate limiting.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum ClientId {
+    ApiKey(String),
+    Ip(IpAddr),
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    units_per_second: f64,
+    units: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(units_per_second: u32, burst_seconds: u32, now: Instant) -> Self {
+        let capacity = f64::from(units_per_second) * f64::from(burst_seconds);
+        Self {
+            capacity,
+            units_per_second: f64::from(units_per_second),
+            units: capacity,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.units = (self.units + elapsed * self.units_per_second).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    fn try_consume(&mut self, cost: u32, now: Instant) -> bool {
+        self.refill(now);
+        let cost = f64::from(cost);
+        if self.units < cost {
+            return false;
+        }
+        self.units -= cost;
+        true
+    }
+
+    fn is_full(&self) -> bool {
+        self.units >= self.capacity
+    }
+}
+
+pub(crate) struct RateLimiter {
+    enabled: bool,
+    cost_units_per_second: u32,
+    burst_seconds: u32,
+    default_method_cost: u32,
+    method_costs: HashMap<String, u32>,
+    api_key_header: String,
+    api_keys: HashMap<String, u32>,
+    trusted_proxies: HashSet<IpAddr>,
+    max_batch_size: u32,
+    buckets: Mutex<HashMap<ClientId, TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(config: &RateLimitConfig) -> anyhow::Result<Self> {
+        let mut method_costs = DEFAULT_METHOD_COSTS
+            .iter()
+            .map(|(method, cost)| (method.to_string(), *cost))
+            .collect::<HashMap<_, _>>();
+        method_costs.extend(parse_units_map(&config.method_costs, "method cost")?);
+        Ok(Self {
+            enabled: config.enabled,
+            cost_units_per_second: config.cost_units_per_second,
+            burst_seconds: config.burst_seconds,
+            default_method_cost: config.default_method_cost,
+            method_costs,
+            api_key_header: config.api_key_header.clone(),
+            api_keys: parse_units_map(&config.api_keys, "API key budget")?,
+            trusted_proxies: config
+                .trusted_proxies
+                .split_whitespace()
+                .map(|address| {
+                    address
+                        .parse::<IpAddr>()
+                        .map_err(|err| anyhow!("Invalid trusted proxy address {address}: {err}"))
+                })
+                .collect::<anyhow::Result<_>>()?,
+            max_batch_size: config.max_batch_size,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(crate) fn max_batch_size(&self) -> u32 {
+        self.max_batch_size
+    }
+
+    /// Returns the cost of a method, given its name without the "starknet_" prefix.
+    pub(crate) fn method_cost(&self, method: &str) -> u32 {
+        self.method_costs.get(method).copied().unwrap_or(self.default_method_cost)
+    }
+
+    /// Identifies the client by its API key if it sent a known one, and otherwise by its IP
+    /// address. The address is the one the request was received from, unless it was received from
+    /// a trusted proxy, in which case it's the last address in the x-forwarded-for header that
+    /// isn't of a trusted proxy.
+    pub(crate) fn client_id<Body>(
+        &self,
+        remote_address: IpAddr,
+        request: &HttpRequest<Body>,
+    ) -> ClientId {
+        let headers = request.headers();
+        if let Some(api_key) =
+            headers.get(self.api_key_header.as_str()).and_then(|value| value.to_str().ok())
+        {
+            if self.api_keys.contains_key(api_key) {
+                return ClientId::ApiKey(api_key.to_string());
+            }
+        }
+        if !self.trusted_proxies.contains(&remote_address) {
+            return ClientId::Ip(remote_address);
+        }
+        // Every proxy appends the address it received the request from, so the addresses before
+        // the last untrusted one could have been set by the client.
+        let forwarded_address = headers
+            .get_all(FORWARDED_FOR_HEADER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|addresses| addresses.split(','))
+            .map(|address| address.trim().parse::<IpAddr>().ok())
+            .rev()
+            .find(|address| {
+                address.map_or(true, |address| !self.trusted_proxies.contains(&address))
+            })
+            .flatten();
+        ClientId::Ip(forwarded_address.unwrap_or(remote_address))
+    }
+
+    /// Charges the client with the given cost. Returns false if the client doesn't have enough
+    /// units left, in which case nothing is charged.
+    pub(crate) fn try_consume(&self, client_id: ClientId, cost: u32, now: Instant) -> bool {
+        let units_per_second = match &client_id {
+            ClientId::ApiKey(api_key) => self.api_keys[api_key],
+            ClientId::Ip(_) => self.cost_units_per_second,
+        };
+        let mut buckets = self.buckets.lock().expect("Rate limiter lock should not be poisoned.");
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(&client_id) {
+            buckets.retain(|_, bucket| {
+                bucket.refill(now);
+                !bucket.is_full()
+            });
+        }
+        buckets
+            .entry(client_id)
+            .or_insert_with(|| TokenBucket::new(units_per_second, self.burst_seconds, now))
+            .try_consume(cost, now)
+    }
+}
+
+fn parse_units_map(
+    map: &Option<HashMap<String, String>>,
+    kind: &str,
+) -> anyhow::Result<HashMap<String, u32>> {
+    map.iter()
+        .flatten()
+        .map(|(key, units)| {
+            let units = units
+                .parse::<u32>()
+                // The key isn't part of the error since API keys are secret.
+                .map_err(|err| anyhow!("Invalid {kind} {units}: {err}"))?;
+            Ok((key.clone(), units))
+        })
+        .collect()
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use jsonrpsee::server::HttpRequest;
+use pretty_assertions::assert_eq;
+
+use super::{ClientId, RateLimitConfig, RateLimiter};
+
+const API_KEY: &str = "test_key";
+const API_KEY_UNITS_PER_SECOND: u32 = 50;
+const PROXY: &str = "10.0.0.1";
+const OTHER_PROXY: &str = "10.0.0.2";
+
+fn get_test_rate_limiter() -> RateLimiter {
+    RateLimiter::new(&RateLimitConfig {
+        enabled: true,
+        cost_units_per_second: 10,
+        burst_seconds: 2,
+        default_method_cost: 1,
+        method_costs: Some(HashMap::from([
+            ("getEvents".to_string(), "7".to_string()),
+            ("blockNumber".to_string(), "0".to_string()),
+        ])),
+        api_keys: Some(HashMap::from([(
+            API_KEY.to_string(),
+            API_KEY_UNITS_PER_SECOND.to_string(),
+        )])),
+        trusted_proxies: format!("{PROXY} {OTHER_PROXY}"),
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn method_cost() {
+    let rate_limiter = get_test_rate_limiter();
+    // Built-in cost.
+    assert_eq!(rate_limiter.method_cost("traceBlockTransactions"), 50);
+    // Costs set in the config override the built-in costs.
+    assert_eq!(rate_limiter.method_cost("getEvents"), 7);
+    assert_eq!(rate_limiter.method_cost("blockNumber"), 0);
+    // Default cost.
+    assert_eq!(rate_limiter.method_cost("getStorageAt"), 1);
+}
+
+#[test]
+fn invalid_config() {
+    let config = RateLimitConfig {
+        method_costs: Some(HashMap::from([("getEvents".to_string(), "a lot".to_string())])),
+        ..Default::default()
+    };
+    assert!(RateLimiter::new(&config).is_err());
+
+    let config = RateLimitConfig {
+        api_keys: Some(HashMap::from([(API_KEY.to_string(), "-1".to_string())])),
+        ..Default::default()
+    };
+    assert!(RateLimiter::new(&config).is_err());
+
+    let config = RateLimitConfig { trusted_proxies: "localhost".to_string(), ..Default::default() };
+    assert!(RateLimiter::new(&config).is_err());
+}
+
+fn get_request(headers: &[(&str, &str)]) -> HttpRequest<()> {
+    let mut request = HttpRequest::builder();
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    request.body(()).unwrap()
+}
+
+#[test]
+fn client_id() {
+    let rate_limiter = get_test_rate_limiter();
+    let client: IpAddr = "1.2.3.4".parse().unwrap();
+    let proxy: IpAddr = PROXY.parse().unwrap();
+
+    // Clients are identified by the address they connected from.
+    assert_eq!(rate_limiter.client_id(client, &get_request(&[])), ClientId::Ip(client));
+
+    // The forwarded address is ignored unless the request was sent by a trusted proxy.
+    let request = get_request(&[("x-forwarded-for", "5.6.7.8")]);
+    assert_eq!(rate_limiter.client_id(client, &request), ClientId::Ip(client));
+    assert_eq!(rate_limiter.client_id(proxy, &request), ClientId::Ip("5.6.7.8".parse().unwrap()));
+
+    // The addresses that precede the last untrusted one could have been set by the client.
+    let request = get_request(&[("x-forwarded-for", &format!("5.6.7.8, 1.2.3.4, {OTHER_PROXY}"))]);
+    assert_eq!(rate_limiter.client_id(proxy, &request), ClientId::Ip(client));
+
+    // A proxy that didn't forward a valid address is the client.
+    assert_eq!(rate_limiter.client_id(proxy, &get_request(&[])), ClientId::Ip(proxy));
+    let request = get_request(&[("x-forwarded-for", "1.2.3.4, unknown")]);
+    assert_eq!(rate_limiter.client_id(proxy, &request), ClientId::Ip(proxy));
+
+    // An unknown API key is ignored.
+    let request = get_request(&[("x-api-key", "unknown_key")]);
+    assert_eq!(rate_limiter.client_id(client, &request), ClientId::Ip(client));
+
+    let request = get_request(&[("x-api-key", API_KEY)]);
+    assert_eq!(rate_limiter.client_id(client, &request), ClientId::ApiKey(API_KEY.to_string()));
+}
+
+#[test]
+fn token_bucket_per_client() {
+    let rate_limiter = get_test_rate_limiter();
+    let now = Instant::now();
+    let client: IpAddr = "1.2.3.4".parse().unwrap();
+    let other_client: IpAddr = "5.6.7.8".parse().unwrap();
+
+    // The bucket starts full with 2 seconds worth of units.
+    assert!(rate_limiter.try_consume(ClientId::Ip(client), 15, now));
+    assert!(!rate_limiter.try_consume(ClientId::Ip(client), 6, now));
+    assert!(rate_limiter.try_consume(ClientId::Ip(client), 5, now));
+    assert!(!rate_limiter.try_consume(ClientId::Ip(client), 1, now));
+
+    // Other clients have their own budget.
+    assert!(rate_limiter.try_consume(ClientId::Ip(other_client), 20, now));
+
+    // The bucket refills over time, up to its capacity.
+    let later = now + Duration::from_millis(500);
+    assert!(rate_limiter.try_consume(ClientId::Ip(client), 5, later));
+    assert!(!rate_limiter.try_consume(ClientId::Ip(client), 1, later));
+    let much_later = later + Duration::from_secs(60);
+    assert!(!rate_limiter.try_consume(ClientId::Ip(client), 21, much_later));
+    assert!(rate_limiter.try_consume(ClientId::Ip(client), 20, much_later));
+
+    // Clients with an API key get the budget of their key.
+    let api_key_client = ClientId::ApiKey(API_KEY.to_string());
+    assert!(rate_limiter.try_consume(api_key_client.clone(), 2 * API_KEY_UNITS_PER_SECOND, now));
+    assert!(!rate_limiter.try_consume(api_key_client, 1, now));
+}
@@ -2,29 +2,40 @@
 mod rpc_metrics_test;
 
 use std::collections::HashSet;
-use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Instant;
 
-use jsonrpsee::server::logger::{HttpRequest, Logger, MethodKind, TransportProtocol};
-use jsonrpsee::types::Params;
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::server::MethodResponse;
+use jsonrpsee::types::Request;
 use jsonrpsee::Methods;
 use metrics::{histogram, increment_counter, register_counter, register_histogram};
 
+use crate::version_config::VERSION_0_8;
+
 // Name of the metrics.
 const INCOMING_REQUEST: &str = "rpc_incoming_requests";
 const FAILED_REQUESTS: &str = "rpc_failed_requests";
 const REQUEST_LATENCY: &str = "rpc_request_latency_seconds";
+const RATE_LIMITED_REQUESTS: &str = "rpc_rate_limited_requests";
 
 // Labels for the metrics.
 const METHOD_LABEL: &str = "method";
 const VERSION_LABEL: &str = "version";
 const ILLEGAL_METHOD: &str = "illegal_method";
 
+pub(crate) fn increment_rate_limited_requests() {
+    increment_counter!(RATE_LIMITED_REQUESTS);
+}
+
 // Register the metrics and returns a set of the method names.
 fn init_metrics(methods: &Methods) -> HashSet<String> {
     let mut methods_set: HashSet<String> = HashSet::new();
     register_counter!(INCOMING_REQUEST, METHOD_LABEL => ILLEGAL_METHOD);
     register_counter!(FAILED_REQUESTS, METHOD_LABEL => ILLEGAL_METHOD);
+    register_counter!(RATE_LIMITED_REQUESTS);
     for method in methods.method_names() {
         methods_set.insert(method.to_string());
         let (method_name, version) = get_method_and_version(method);
@@ -34,33 +45,24 @@ fn init_metrics(methods: &Methods) -> HashSet<String> {
     }
     methods_set
 }
+/// RPC middleware that records the number of calls, failed calls and latency of every method.
 #[derive(Clone)]
 pub(crate) struct MetricLogger {
     // A set of all the method names the node support.
-    methods_set: HashSet<String>,
+    methods_set: Arc<HashSet<String>>,
 }
 
 impl MetricLogger {
     pub(crate) fn new(methods: &Methods) -> Self {
-        let methods_set = init_metrics(methods);
+        let methods_set = Arc::new(init_metrics(methods));
         Self { methods_set }
     }
-}
 
-impl Logger for MetricLogger {
-    type Instant = Instant;
-
-    fn on_result(
-        &self,
-        method_name: &str,
-        success_or_error: jsonrpsee::helpers::MethodResponseResult,
-        started_at: Self::Instant,
-        _transport: TransportProtocol,
-    ) {
+    fn on_result(&self, method_name: &str, is_success: bool, started_at: Instant) {
         // To prevent creating metrics for illegal methods.
         if self.methods_set.contains(method_name) {
             let (method, version) = get_method_and_version(method_name);
-            if let jsonrpsee::helpers::MethodResponseResult::Failed(_) = success_or_error {
+            if !is_success {
                 increment_counter!(FAILED_REQUESTS, METHOD_LABEL=> method.clone(), VERSION_LABEL=> version.clone());
             }
             increment_counter!(INCOMING_REQUEST, METHOD_LABEL=> method.clone(), VERSION_LABEL=> version.clone());
@@ -71,50 +73,55 @@ impl Logger for MetricLogger {
             increment_counter!(FAILED_REQUESTS, METHOD_LABEL => ILLEGAL_METHOD);
         }
     }
+}
 
-    #[cfg_attr(coverage_nightly, coverage_attribute)]
-    fn on_request(&self, _transport: TransportProtocol) -> Self::Instant {
-        Instant::now()
-    }
+impl<S> tower::Layer<S> for MetricLogger {
+    type Service = MetricLoggerService<S>;
 
-    // Required methods.
-    #[cfg_attr(coverage_nightly, coverage_attribute)]
-    fn on_connect(&self, _remote_addr: SocketAddr, _request: &HttpRequest, _t: TransportProtocol) {}
-
-    #[cfg_attr(coverage_nightly, coverage_attribute)]
-    fn on_call(
-        &self,
-        _method_name: &str,
-        _params: Params<'_>,
-        _kind: MethodKind,
-        _transport: TransportProtocol,
-    ) {
+    fn layer(&self, service: S) -> Self::Service {
+        MetricLoggerService { service, logger: self.clone() }
     }
+}
 
-    #[cfg_attr(coverage_nightly, coverage_attribute)]
-    fn on_response(
-        &self,
-        _result: &str,
-        _started_at: Self::Instant,
-        _transport: TransportProtocol,
-    ) {
-    }
+#[derive(Clone)]
+pub(crate) struct MetricLoggerService<S> {
+    service: S,
+    logger: MetricLogger,
+}
 
-    #[cfg_attr(coverage_nightly, coverage_attribute)]
-    fn on_disconnect(&self, _remote_addr: SocketAddr, _transport: TransportProtocol) {}
+impl<'a, S> RpcServiceT<'a> for MetricLoggerService<S>
+where
+    S: RpcServiceT<'a>,
+    S::Future: 'a,
+{
+    type Future = BoxFuture<'a, MethodResponse>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        let started_at = Instant::now();
+        let method_name = request.method_name().to_string();
+        let logger = self.logger.clone();
+        let response = self.service.call(request);
+        async move {
+            let response = response.await;
+            logger.on_result(&method_name, response.is_success(), started_at);
+            response
+        }
+        .boxed()
+    }
 }
 
 // Given method_name returns (method, version).
 // Example: method_name: starknet_V0_6_0_blockNumber; output: (blockNumber, V0_6_0).
 fn get_method_and_version(method_name: &str) -> (String, String) {
     // The structure of method_name is in the following format: "starknet_V0_6_0_blockNumber".
-    // Only method in this format will arrive to this point in the code.
+    // Only method in this format will arrive to this point in the code, except for the methods of
+    // the subscriptions ("starknet_subscribeNewHeads") that are served only by the latest version.
     let last_underscore_index = method_name
         .rfind('_')
         .expect("method_name should be in the following format: starknet_V0_6_0_blockNumber");
 
     (
         method_name[last_underscore_index + 1..].to_string(),
-        method_name[9..last_underscore_index].to_string(),
+        method_name.get(9..last_underscore_index).unwrap_or(VERSION_0_8.name).to_string(),
     )
 }
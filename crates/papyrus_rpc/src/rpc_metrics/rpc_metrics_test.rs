@@ -1,8 +1,6 @@
-use std::sync::Arc;
 use std::time::Instant;
 
-use jsonrpsee::server::logger::{Logger, TransportProtocol};
-use jsonrpsee::Methods;
+use jsonrpsee::RpcModule;
 use metrics_exporter_prometheus::PrometheusBuilder;
 use papyrus_storage::body::BodyStorageWriter;
 use papyrus_storage::class::ClassStorageWriter;
@@ -38,6 +36,11 @@ fn get_method_and_version_test() {
     let (method, version) = get_method_and_version(method_name);
     assert_eq!(method, "blockNumber");
     assert_eq!(version, "V0_8_0");
+
+    let method_name = "starknet_subscribeNewHeads";
+    let (method, version) = get_method_and_version(method_name);
+    assert_eq!(method, "subscribeNewHeads");
+    assert_eq!(version, "V0_8");
 }
 
 // Ignored because server_metrics test is running in parallel and we are unable to install multiple
@@ -50,15 +53,9 @@ fn logger_test() {
     let labels = vec![(METHOD_LABEL, method.as_str()), (VERSION_LABEL, version.as_str())];
     let illegal_method_label = vec![(METHOD_LABEL, ILLEGAL_METHOD)];
     let handle = PrometheusBuilder::new().install_recorder().unwrap();
-    let callback = jsonrpsee::MethodCallback::Unsubscription(Arc::new(|_, _, _, _| {
-        jsonrpsee::MethodResponse {
-            result: String::new(),
-            success_or_error: jsonrpsee::helpers::MethodResponseResult::Success,
-        }
-    }));
-    let mut methods = Methods::new();
-    methods.verify_and_insert(full_method_name, callback).unwrap();
-    let logger = MetricLogger::new(&methods);
+    let mut module = RpcModule::new(());
+    module.register_method(full_method_name, |_, _, _| ()).unwrap();
+    let logger = MetricLogger::new(&module.into());
 
     // The counters are initialized with zero.
     assert_eq!(
@@ -79,12 +76,7 @@ fn logger_test() {
     );
 
     // Successful call.
-    logger.on_result(
-        full_method_name,
-        jsonrpsee::helpers::MethodResponseResult::Success,
-        Instant::now(),
-        TransportProtocol::Http,
-    );
+    logger.on_result(full_method_name, true, Instant::now());
     assert_eq!(
         prometheus_is_contained(handle.render(), INCOMING_REQUEST, &labels),
         Some(Counter(1f64))
@@ -99,12 +91,7 @@ fn logger_test() {
     );
 
     // Failed call.
-    logger.on_result(
-        full_method_name,
-        jsonrpsee::helpers::MethodResponseResult::Failed(0),
-        Instant::now(),
-        TransportProtocol::Http,
-    );
+    logger.on_result(full_method_name, false, Instant::now());
     assert_eq!(
         prometheus_is_contained(handle.render(), INCOMING_REQUEST, &labels),
         Some(Counter(2f64))
@@ -122,12 +109,7 @@ fn logger_test() {
     let bad_method_name = "starknet_V0_8_0_illegal_method";
     let (method, version) = get_method_and_version(bad_method_name);
     let bad_labels = vec![(METHOD_LABEL, method.as_str()), (VERSION_LABEL, version.as_str())];
-    logger.on_result(
-        bad_method_name,
-        jsonrpsee::helpers::MethodResponseResult::Failed(0),
-        Instant::now(),
-        TransportProtocol::Http,
-    );
+    logger.on_result(bad_method_name, false, Instant::now());
     assert_eq!(prometheus_is_contained(handle.render(), INCOMING_REQUEST, &bad_labels), None);
     assert_eq!(
         prometheus_is_contained(handle.render(), INCOMING_REQUEST, &illegal_method_label),
@@ -3,12 +3,16 @@ use std::{panic, vec};
 
 use assert_matches::assert_matches;
 use futures_util::future::join_all;
-use hyper::{header, Body, Request};
-use jsonrpsee::core::client::ClientT;
+use jsonrpsee::core::client::{ClientT, Subscription, SubscriptionClientT};
 use jsonrpsee::core::http_helpers::read_body;
-use jsonrpsee::core::{Error, RpcResult};
+use jsonrpsee::core::params::BatchRequestBuilder;
+use jsonrpsee::core::{ClientError, RpcResult};
 use jsonrpsee::http_client::HttpClientBuilder;
+use jsonrpsee::rpc_params;
+use jsonrpsee::server::{HttpBody, HttpRequest};
+use jsonrpsee::types::error::SERVER_IS_BUSY_CODE;
 use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::ws_client::WsClientBuilder;
 use papyrus_storage::base_layer::BaseLayerStorageWriter;
 use papyrus_storage::header::HeaderStorageWriter;
 use papyrus_storage::test_utils::get_test_storage;
@@ -25,6 +29,7 @@ use starknet_api::block::{
 use tower::BoxError;
 
 use crate::middleware::proxy_rpc_request;
+use crate::rate_limiter::RateLimitConfig;
 use crate::test_utils::{
     get_test_highest_block,
     get_test_pending_classes,
@@ -52,7 +57,7 @@ async fn run_server_no_blocks() {
     .await
     .unwrap();
     let client = HttpClientBuilder::default().build(format!("http://{addr:?}")).unwrap();
-    let res: Result<RpcResult<BlockNumber>, Error> =
+    let res: Result<RpcResult<BlockNumber>, ClientError> =
         client.request("starknet_blockNumber", [""]).await;
     let _expected_error = ErrorObjectOwned::owned(123, "", None::<u8>);
     // TODO(yair): fix this test:
@@ -67,7 +72,7 @@ async fn run_server_no_blocks() {
 
 /// Given an HTTP request, using the "read_body" function from jsonrpsee library,
 /// parse the body, make sure it's a formatted JSON and within the MAX_BODY_SIZE length.
-async fn get_json_rpc_body(request: Request<Body>) -> Vec<u8> {
+async fn get_json_rpc_body(request: HttpRequest) -> Vec<u8> {
     let (res_parts, res_body) = request.into_parts();
     let (body_bytes, _is_single) =
         read_body(&res_parts.headers, res_body, SERVER_MAX_BODY_SIZE).await.unwrap();
@@ -81,9 +86,9 @@ async fn call_proxy_request_get_method_in_out(
     let method_name = "myMethod";
     let params = serde_json::from_str(r#"[{"myParam": "myValue"}]"#).unwrap();
     let request_body = get_request_body(is_batch_request, params, method_name);
-    let req_no_version = Request::post(uri.clone())
-        .header(header::CONTENT_TYPE, "application/json")
-        .body(Body::from(request_body.unwrap()))
+    let req_no_version = HttpRequest::post(uri.clone())
+        .header("content-type", "application/json")
+        .body(HttpBody::from(request_body.unwrap()))
         .unwrap();
     let res = proxy_rpc_request(req_no_version).await?;
     let body_bytes = get_json_rpc_body(res).await;
@@ -109,7 +114,7 @@ fn digest_body_and_assert(
                 serde_json::from_slice::<Vec<jsonrpsee::types::Request<'_>>>(&body_bytes).unwrap();
             // assert params not altered by proxy middleware for all requests in batch
             body_batch.iter().for_each(|body| {
-                assert_eq!(params.to_string(), body.params.unwrap().to_string());
+                assert_eq!(params.to_string(), body.params.as_ref().unwrap().to_string());
             });
             Ok((method_name.to_string(), body_batch[0].method.to_string()))
         }
@@ -196,6 +201,90 @@ async fn test_version_middleware() {
     };
 }
 
+#[tokio::test]
+async fn test_version_middleware_passes_websocket_upgrade() {
+    let request = HttpRequest::get("http://localhost:8080/rpc/V0_8")
+        .header("connection", "Upgrade")
+        .header("upgrade", "websocket")
+        .body(HttpBody::empty())
+        .unwrap();
+    let request = proxy_rpc_request(request).await.unwrap();
+    assert_eq!(request.uri().path(), "/rpc/V0_8");
+    assert_eq!(request.headers()["upgrade"], "websocket");
+}
+
+#[tokio::test]
+async fn rate_limit() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let mut config = get_test_rpc_config();
+    config.rate_limit = RateLimitConfig {
+        enabled: true,
+        cost_units_per_second: 1,
+        burst_seconds: 2,
+        max_batch_size: 1,
+        ..Default::default()
+    };
+    let (addr, _handle) = run_server(
+        &config,
+        get_test_highest_block(),
+        get_test_pending_data(),
+        get_test_pending_classes(),
+        storage_reader,
+        "NODE VERSION",
+    )
+    .await
+    .unwrap();
+    let http_client =
+        HttpClientBuilder::default().build(format!("http://{addr}/rpc/V0_8")).unwrap();
+
+    // Batches larger than the maximum are rejected without charging the client.
+    let mut batch = BatchRequestBuilder::new();
+    batch.insert("starknet_chainId", rpc_params![]).unwrap();
+    batch.insert("starknet_chainId", rpc_params![]).unwrap();
+    assert!(http_client.batch_request::<String>(batch).await.is_err());
+
+    // The client can afford two calls that cost one unit each.
+    for _ in 0..2 {
+        let res: Result<String, ClientError> =
+            http_client.request("starknet_chainId", rpc_params![]).await;
+        assert!(res.is_ok());
+    }
+    let res: Result<String, ClientError> =
+        http_client.request("starknet_chainId", rpc_params![]).await;
+    assert_matches!(res, Err(ClientError::Call(err)) if err.code() == SERVER_IS_BUSY_CODE);
+
+    // The calls over WebSocket are charged from the budget of the same client.
+    let ws_client =
+        WsClientBuilder::default().build(format!("ws://{addr}/rpc/V0_8")).await.unwrap();
+    let res: Result<String, ClientError> =
+        ws_client.request("starknet_V0_8_chainId", rpc_params![]).await;
+    assert_matches!(res, Err(ClientError::Call(err)) if err.code() == SERVER_IS_BUSY_CODE);
+}
+
+#[tokio::test]
+async fn subscribe_over_websocket() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let (addr, _handle) = run_server(
+        &get_test_rpc_config(),
+        get_test_highest_block(),
+        get_test_pending_data(),
+        get_test_pending_classes(),
+        storage_reader,
+        "NODE VERSION",
+    )
+    .await
+    .unwrap();
+    let client = WsClientBuilder::default().build(format!("ws://{addr}/rpc/V0_8")).await.unwrap();
+    let subscription: Result<Subscription<serde_json::Value>, ClientError> = client
+        .subscribe(
+            "starknet_subscribePendingTransactions",
+            rpc_params![],
+            "starknet_unsubscribePendingTransactions",
+        )
+        .await;
+    assert!(subscription.is_ok());
+}
+
 #[test]
 fn get_block_status_test() {
     let (reader, mut writer) = get_test_storage().0;
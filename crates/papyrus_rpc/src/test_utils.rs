@@ -37,6 +37,7 @@ pub fn get_test_rpc_config() -> RpcConfig {
         server_address: String::from("127.0.0.1:0"),
         max_events_chunk_size: 10,
         max_events_keys: 10,
+        storage_proofs_enabled: true,
         collect_metrics: false,
         ..Default::default()
     }
@@ -82,6 +83,7 @@ pub(crate) fn get_test_rpc_server_and_storage_writer_from_params<T: JsonRpcServe
             storage_reader,
             config.max_events_chunk_size,
             config.max_events_keys,
+            config.storage_proofs_enabled,
             BlockHashAndNumber::default(),
             shared_highest_block,
             pending_data,
@@ -116,7 +118,7 @@ pub(crate) async fn raw_call<R: JsonRpcServerTrait, S: Serialize, T: for<'a> Des
         .raw_json_request(req.as_str(), 1)
         .await
         .unwrap_or_else(|_| panic!("request format, got: {req}"));
-    let json_resp: Value = serde_json::from_str(&resp_wrapper.result).unwrap();
+    let json_resp: Value = serde_json::from_str(&resp_wrapper).unwrap();
     let result: Result<T, jsonrpsee::types::ErrorObject<'_>> =
         match json_resp.get("result") {
             Some(resp) => Ok(serde_json::from_value::<T>(resp.clone())
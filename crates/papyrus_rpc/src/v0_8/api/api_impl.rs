@@ -5,7 +5,13 @@ use jsonrpsee::core::RpcResult;
 use jsonrpsee::types::ErrorObjectOwned;
 use jsonrpsee::RpcModule;
 use papyrus_common::pending_classes::{PendingClasses, PendingClassesTrait};
-use papyrus_execution::objects::{FeeEstimation, PendingData as ExecutionPendingData};
+use papyrus_execution::objects::{
+    BlockOverrides,
+    ContractOverride,
+    ExecutionOverrides,
+    FeeEstimation,
+    PendingData as ExecutionPendingData,
+};
 use papyrus_execution::{
     estimate_fee as exec_estimate_fee,
     execute_call,
@@ -14,10 +20,13 @@ use papyrus_execution::{
     ExecutableTransactionInput,
     ExecutionConfig,
 };
+use papyrus_storage::base_layer::BaseLayerStorageReader;
 use papyrus_storage::body::events::{EventIndex, EventsReader};
 use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
 use papyrus_storage::compiled_class::CasmStorageReader;
 use papyrus_storage::db::{TransactionKind, RO};
+use papyrus_storage::patricia::PatriciaStorageReader;
+use papyrus_storage::pruning::HistoryPruningStorageReader;
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{StorageError, StorageReader, StorageTxn};
 use starknet_api::block::{BlockHash, BlockHeaderWithoutHash, BlockNumber, BlockStatus};
@@ -37,6 +46,7 @@ use starknet_api::transaction::fields::Fee;
 use starknet_api::transaction::{
     EventContent,
     EventIndexInTransactionOutput,
+    L1TransactionHash,
     Transaction as StarknetApiTransaction,
     TransactionHash,
     TransactionOffsetInBlock,
@@ -73,6 +83,7 @@ use super::super::broadcasted_transaction::{
     BroadcastedTransaction,
 };
 use super::super::error::{
+    l1_transaction_not_indexed,
     ContractError,
     JsonRpcError,
     TransactionExecutionError,
@@ -83,11 +94,13 @@ use super::super::error::{
     INVALID_TRANSACTION_INDEX,
     NO_BLOCKS,
     PAGE_SIZE_TOO_BIG,
+    STORAGE_PROOF_NOT_SUPPORTED,
     TOO_MANY_KEYS_IN_FILTER,
     TRANSACTION_HASH_NOT_FOUND,
 };
 use super::super::execution::TransactionTrace;
 use super::super::state::{AcceptedStateUpdate, PendingStateUpdate, StateUpdate};
+use super::super::storage_proof::{get_storage_proof, ContractStorageKeys, StorageProof};
 use super::super::transaction::{
     get_block_tx_hashes_by_number,
     get_block_txs_by_number,
@@ -96,6 +109,7 @@ use super::super::transaction::{
     L1HandlerMsgHash,
     L1L2MsgHash,
     MessageFromL1,
+    MessageStatus,
     PendingTransactionFinalityStatus,
     PendingTransactionOutput,
     PendingTransactionReceipt,
@@ -158,6 +172,7 @@ pub struct JsonRpcServerImpl {
     pub storage_reader: StorageReader,
     pub max_events_chunk_size: usize,
     pub max_events_keys: usize,
+    pub storage_proofs_enabled: bool,
     pub starting_block: BlockHashAndNumber,
     pub shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
     pub pending_data: Arc<RwLock<PendingData>>,
@@ -283,6 +298,10 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 ))
             },
             |txn, block_number| {
+                // The receipts of a block whose events weren't stored yet would miss its events.
+                if block_number >= txn.get_event_marker().map_err(internal_server_error)? {
+                    return Err(ErrorObjectOwned::from(BLOCK_NOT_FOUND));
+                }
                 let transactions = get_block_txs_by_number(txn, block_number)?;
                 let transaction_hashes = get_block_tx_hashes_by_number(txn, block_number)?;
                 Ok(Transactions::FullWithReceipts(
@@ -516,6 +535,88 @@ impl JsonRpcServer for JsonRpcServerImpl {
         Ok(self.get_transaction_receipt(transaction_hash).await?.transaction_status())
     }
 
+    #[instrument(skip(self), level = "debug", err, ret)]
+    async fn get_messages_status(
+        &self,
+        transaction_hash: L1TransactionHash,
+    ) -> RpcResult<Vec<MessageStatus>> {
+        verify_storage_scope(&self.storage_reader)?;
+
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let nonces = txn
+            .get_l1_transaction_message_nonces(&transaction_hash)
+            .map_err(internal_server_error)?;
+        if nonces.is_empty() {
+            // Unless the messages are indexed from the first L1 block, the L1 transaction may be
+            // before the indexed L1 blocks.
+            let err = match txn.get_base_layer_messages_start().map_err(internal_server_error)? {
+                Some(0) => TRANSACTION_HASH_NOT_FOUND,
+                Some(start) => l1_transaction_not_indexed(format!(
+                    "The messages to L2 are indexed only from L1 block {start}."
+                )),
+                None => {
+                    l1_transaction_not_indexed("The messages to L2 aren't indexed.".to_string())
+                }
+            };
+            return Err(ErrorObjectOwned::from(err));
+        }
+
+        let mut statuses = Vec::with_capacity(nonces.len());
+        let mut unconsumed_nonces = Vec::new();
+        for nonce in nonces {
+            let Some(transaction_index) = txn
+                .get_l1_handler_transaction_idx_by_nonce(&nonce)
+                .map_err(internal_server_error)?
+            else {
+                unconsumed_nonces.push(nonce);
+                continue;
+            };
+            let l2_transaction_hash = txn
+                .get_transaction_hash_by_idx(&transaction_index)
+                .map_err(internal_server_error)?
+                .ok_or_else(|| internal_server_error_with_msg("Missing L1 handler transaction"))?;
+            let output = txn
+                .get_transaction_output(transaction_index)
+                .map_err(internal_server_error)?
+                .ok_or_else(|| internal_server_error_with_msg("Missing L1 handler output"))?;
+            let status = TransactionStatus {
+                finality_status: get_block_status(&txn, transaction_index.0)?.into(),
+                execution_status: output.execution_status().clone(),
+            };
+            statuses.push(MessageStatus::new(l2_transaction_hash, status));
+        }
+        if unconsumed_nonces.is_empty() {
+            return Ok(statuses);
+        }
+
+        // Messages that weren't consumed in any non-pending block might be consumed in the pending
+        // block. Messages that weren't consumed at all have no status.
+        let pending_data = read_pending_data(&self.pending_data, &txn).await?;
+        for client_transaction in pending_data.block.transactions() {
+            let ClientTransaction::L1Handler(l1_handler_tx) = client_transaction else {
+                continue;
+            };
+            if !unconsumed_nonces.contains(&l1_handler_tx.nonce) {
+                continue;
+            }
+            let Some(client_transaction_receipt) = pending_data
+                .block
+                .transaction_receipts()
+                .iter()
+                .find(|receipt| receipt.transaction_hash == l1_handler_tx.transaction_hash)
+            else {
+                continue;
+            };
+            let status = client_receipt_to_rpc_pending_receipt(
+                client_transaction,
+                client_transaction_receipt.clone(),
+            )?
+            .transaction_status();
+            statuses.push(MessageStatus::new(l1_handler_tx.transaction_hash, status));
+        }
+        Ok(statuses)
+    }
+
     #[instrument(skip(self), level = "debug", err, ret)]
     async fn get_transaction_receipt(
         &self,
@@ -713,7 +814,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
             return Ok(EventsChunk { events: vec![], continuation_token: None });
         };
         let from_block_number = match filter.from_block {
-            None => BlockNumber(0),
+            None => txn.get_history_pruning_marker().map_err(internal_server_error)?,
             Some(BlockId::Tag(Tag::Pending)) => latest_block_number.unchecked_next(),
             Some(block_id) => get_accepted_block_number(&txn, block_id)?,
         };
@@ -743,14 +844,24 @@ impl JsonRpcServer for JsonRpcServerImpl {
             );
         }
 
+        // The events of a block may be stored after its body, so only the blocks below the event
+        // marker are searched, and the pending block is searched only if it follows them.
+        let event_marker = txn.get_event_marker().map_err(internal_server_error)?;
+        let include_pending_block = include_pending_block && event_marker > latest_block_number;
+        if let Some(last_block_with_events) = event_marker.prev() {
+            to_block_number = to_block_number.min(last_block_with_events);
+        }
+
         // Collect the requested events.
         // Once we collected enough events, we continue to check if there are any more events
         // corresponding to the requested filter. If there are, we return a continuation token
         // pointing to the next relevant event. Otherwise, we return a continuation token None.
         let mut filtered_events = vec![];
-        if start_event_index.0.0 <= latest_block_number {
+        if start_event_index.0.0 < event_marker {
+            // An empty set of keys matches any key.
+            let first_keys = filter.keys.first().filter(|keys| !keys.is_empty());
             for ((from_address, event_index), content) in txn
-                .iter_events(filter.address, start_event_index, to_block_number)
+                .iter_events(filter.address, start_event_index, to_block_number, first_keys)
                 .map_err(internal_server_error)?
             {
                 let block_number = (event_index.0).0;
@@ -867,7 +978,13 @@ impl JsonRpcServer for JsonRpcServerImpl {
     }
 
     #[instrument(skip(self), level = "debug", err, ret)]
-    async fn call(&self, request: CallRequest, block_id: BlockId) -> RpcResult<Vec<Felt>> {
+    async fn call(
+        &self,
+        request: CallRequest,
+        block_id: BlockId,
+        state_override: Option<Vec<ContractOverride>>,
+        block_override: Option<BlockOverrides>,
+    ) -> RpcResult<Vec<Felt>> {
         let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
         let maybe_pending_data = if let BlockId::Tag(Tag::Pending) = block_id {
             Some(client_pending_data_to_execution_pending_data(
@@ -886,6 +1003,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
         let contract_address_copy = request.contract_address;
+        let overrides = execution_overrides(state_override, block_override);
 
         let res = tokio::task::spawn_blocking(move || {
             execute_call(
@@ -898,6 +1016,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 request.entry_point_selector,
                 request.calldata,
                 &execution_config,
+                &overrides,
                 DONT_IGNORE_L1_DA_MODE,
             )
         })
@@ -975,6 +1094,8 @@ impl JsonRpcServer for JsonRpcServerImpl {
         transactions: Vec<BroadcastedTransaction>,
         simulation_flags: Vec<SimulationFlag>,
         block_id: BlockId,
+        state_override: Option<Vec<ContractOverride>>,
+        block_override: Option<BlockOverrides>,
     ) -> RpcResult<Vec<FeeEstimation>> {
         trace!("Estimating fee of transactions: {:#?}", transactions);
         let validate = !simulation_flags.contains(&SimulationFlag::SkipValidate);
@@ -1003,6 +1124,8 @@ impl JsonRpcServer for JsonRpcServerImpl {
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
 
+        let overrides = execution_overrides(state_override, block_override);
+
         let estimate_fee_result = tokio::task::spawn_blocking(move || {
             exec_estimate_fee(
                 executable_txns,
@@ -1012,6 +1135,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 state_number,
                 block_number,
                 &execution_config,
+                &overrides,
                 validate,
                 DONT_IGNORE_L1_DA_MODE,
             )
@@ -1041,6 +1165,8 @@ impl JsonRpcServer for JsonRpcServerImpl {
         block_id: BlockId,
         transactions: Vec<BroadcastedTransaction>,
         simulation_flags: Vec<SimulationFlag>,
+        state_override: Option<Vec<ContractOverride>>,
+        block_override: Option<BlockOverrides>,
     ) -> RpcResult<Vec<SimulatedTransaction>> {
         trace!("Simulating transactions: {:#?}", transactions);
         let executable_txns =
@@ -1069,6 +1195,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
 
         let charge_fee = !simulation_flags.contains(&SimulationFlag::SkipFeeCharge);
         let validate = !simulation_flags.contains(&SimulationFlag::SkipValidate);
+        let overrides = execution_overrides(state_override, block_override);
 
         let simulation_results = tokio::task::spawn_blocking(move || {
             exec_simulate_transactions(
@@ -1080,6 +1207,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 state_number,
                 block_number,
                 &execution_config,
+                &overrides,
                 charge_fee,
                 validate,
                 DONT_IGNORE_L1_DA_MODE,
@@ -1229,6 +1357,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 state_number,
                 block_number,
                 &execution_config,
+                &ExecutionOverrides::default(),
                 true,
                 true,
                 DONT_IGNORE_L1_DA_MODE,
@@ -1345,6 +1474,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 state_number,
                 block_number,
                 &execution_config,
+                &ExecutionOverrides::default(),
                 true,
                 true,
                 DONT_IGNORE_L1_DA_MODE,
@@ -1412,6 +1542,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 state_number,
                 block_number,
                 &execution_config,
+                &ExecutionOverrides::default(),
                 false,
                 DONT_IGNORE_L1_DA_MODE,
             )
@@ -1489,9 +1620,43 @@ impl JsonRpcServer for JsonRpcServerImpl {
             SierraVersion::DEPRECATED,
         ))
     }
+
+    #[instrument(skip(self), level = "debug", err)]
+    fn get_storage_proof(
+        &self,
+        block_id: BlockId,
+        class_hashes: Option<Vec<ClassHash>>,
+        contract_addresses: Option<Vec<ContractAddress>>,
+        contracts_storage_keys: Option<Vec<ContractStorageKeys>>,
+    ) -> RpcResult<StorageProof> {
+        // The sync stores the tries only if it's configured to.
+        if !self.storage_proofs_enabled {
+            return Err(ErrorObjectOwned::from(STORAGE_PROOF_NOT_SUPPORTED));
+        }
+        // The tries don't contain the state of the pending block.
+        if let BlockId::Tag(Tag::Pending) = block_id {
+            return Err(ErrorObjectOwned::from(STORAGE_PROOF_NOT_SUPPORTED));
+        }
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let block_number = get_accepted_block_number(&txn, block_id)?;
+        let block_hash = get_block_header_by_number(&txn, block_number)?.block_hash;
+        let roots = txn
+            .get_global_trie_roots(block_number)
+            .map_err(internal_server_error)?
+            .ok_or_else(|| ErrorObjectOwned::from(STORAGE_PROOF_NOT_SUPPORTED))?;
+
+        get_storage_proof(
+            &txn,
+            roots,
+            block_hash,
+            &class_hashes.unwrap_or_default(),
+            &contract_addresses.unwrap_or_default(),
+            &contracts_storage_keys.unwrap_or_default(),
+        )
+    }
 }
 
-async fn read_pending_data<Mode: TransactionKind>(
+pub(crate) async fn read_pending_data<Mode: TransactionKind>(
     pending_data: &Arc<RwLock<PendingData>>,
     txn: &StorageTxn<'_, Mode>,
 ) -> RpcResult<PendingData> {
@@ -1594,6 +1759,10 @@ fn get_non_pending_receipt<Mode: TransactionKind>(
     msg_hash: Option<L1L2MsgHash>,
 ) -> RpcResult<GeneralTransactionReceipt> {
     let block_number = transaction_index.0;
+    // The events of a block may be stored after its body, and a receipt without them is incomplete.
+    if block_number >= txn.get_event_marker().map_err(internal_server_error)? {
+        return Err(ErrorObjectOwned::from(TRANSACTION_HASH_NOT_FOUND));
+    }
     let status = get_block_status(txn, block_number)?;
 
     // rejected blocks should not be a part of the API so we early return here.
@@ -1646,6 +1815,16 @@ fn client_receipt_to_rpc_pending_receipt(
     }))
 }
 
+fn execution_overrides(
+    state_override: Option<Vec<ContractOverride>>,
+    block_override: Option<BlockOverrides>,
+) -> ExecutionOverrides {
+    ExecutionOverrides {
+        state: state_override.unwrap_or_default(),
+        block: block_override.unwrap_or_default(),
+    }
+}
+
 fn do_event_keys_match_filter(event_content: &EventContent, filter: &EventFilter) -> bool {
     filter.keys.iter().enumerate().all(|(i, keys)| {
         event_content.keys.len() > i && (keys.is_empty() || keys.contains(&event_content.keys[i]))
@@ -1659,6 +1838,7 @@ impl JsonRpcServerTrait for JsonRpcServerImpl {
         storage_reader: StorageReader,
         max_events_chunk_size: usize,
         max_events_keys: usize,
+        storage_proofs_enabled: bool,
         starting_block: BlockHashAndNumber,
         shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
         pending_data: Arc<RwLock<PendingData>>,
@@ -1671,6 +1851,7 @@ impl JsonRpcServerTrait for JsonRpcServerImpl {
             storage_reader,
             max_events_chunk_size,
             max_events_keys,
+            storage_proofs_enabled,
             starting_block,
             shared_highest_block,
             pending_data,
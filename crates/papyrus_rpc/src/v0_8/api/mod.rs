@@ -8,7 +8,7 @@ use jsonrpsee::proc_macros::rpc;
 use jsonrpsee::types::ErrorObjectOwned;
 use papyrus_common::deprecated_class_abi::calculate_deprecated_class_abi_length;
 use papyrus_common::pending_classes::ApiContractClass;
-use papyrus_execution::objects::FeeEstimation;
+use papyrus_execution::objects::{BlockOverrides, ContractOverride, FeeEstimation};
 use papyrus_execution::{AbiSize, ExecutableTransactionInput, ExecutionError, SierraSize};
 use papyrus_proc_macros::versioned_rpc;
 use papyrus_storage::compiled_class::CasmStorageReader;
@@ -26,7 +26,12 @@ use starknet_api::deprecated_contract_class::{
 };
 use starknet_api::state::{StateNumber, StorageKey};
 use starknet_api::transaction::fields::Fee;
-use starknet_api::transaction::{EventKey, TransactionHash, TransactionOffsetInBlock};
+use starknet_api::transaction::{
+    EventKey,
+    L1TransactionHash,
+    TransactionHash,
+    TransactionOffsetInBlock,
+};
 use starknet_types_core::felt::Felt;
 use tracing::debug;
 
@@ -46,6 +51,7 @@ use super::error::{
 };
 use super::execution::TransactionTrace;
 use super::state::{ContractClass, StateUpdate};
+use super::storage_proof::{ContractStorageKeys, StorageProof};
 use super::transaction::{
     DeployAccountTransaction,
     DeployAccountTransactionV1,
@@ -57,6 +63,7 @@ use super::transaction::{
     InvokeTransactionV1,
     InvokeTransactionV3,
     MessageFromL1,
+    MessageStatus,
     TransactionStatus,
     TransactionWithHash,
     TypedDeployAccountTransaction,
@@ -140,6 +147,15 @@ pub trait JsonRpc {
         transaction_hash: TransactionHash,
     ) -> RpcResult<TransactionStatus>;
 
+    /// Gets the statuses of the L1 handler transactions that consumed the messages sent to L2 by
+    /// the given L1 transaction. Fails with a "not indexed" error for an L1 transaction that wasn't
+    /// found, unless the messages are indexed from the first L1 block.
+    #[method(name = "getMessagesStatus")]
+    async fn get_messages_status(
+        &self,
+        transaction_hash: L1TransactionHash,
+    ) -> RpcResult<Vec<MessageStatus>>;
+
     /// Gets the transaction receipt by the transaction hash.
     #[method(name = "getTransactionReceipt")]
     async fn get_transaction_receipt(
@@ -193,9 +209,16 @@ pub trait JsonRpc {
     async fn syncing(&self) -> RpcResult<SyncingState>;
 
     /// Executes the entry point of the contract at the given address with the given calldata,
-    /// returns the result (Retdata).
+    /// returns the result (Retdata). The state and the block context can optionally be
+    /// overridden.
     #[method(name = "call")]
-    async fn call(&self, request: CallRequest, block_id: BlockId) -> RpcResult<Vec<Felt>>;
+    async fn call(
+        &self,
+        request: CallRequest,
+        block_id: BlockId,
+        state_override: Option<Vec<ContractOverride>>,
+        block_override: Option<BlockOverrides>,
+    ) -> RpcResult<Vec<Felt>>;
 
     /// Submits a new invoke transaction to be added to the chain.
     #[method(name = "addInvokeTransaction")]
@@ -218,13 +241,16 @@ pub trait JsonRpc {
         declare_transaction: BroadcastedDeclareTransaction,
     ) -> RpcResult<AddDeclareOkResult>;
 
-    /// Estimates the fee of a series of transactions.
+    /// Estimates the fee of a series of transactions. The state and the block context can
+    /// optionally be overridden.
     #[method(name = "estimateFee")]
     async fn estimate_fee(
         &self,
         request: Vec<BroadcastedTransaction>,
         simulation_flags: Vec<SimulationFlag>,
         block_id: BlockId,
+        state_override: Option<Vec<ContractOverride>>,
+        block_override: Option<BlockOverrides>,
     ) -> RpcResult<Vec<FeeEstimation>>;
 
     /// Estimates the fee of a message from L1.
@@ -235,13 +261,16 @@ pub trait JsonRpc {
         block_id: BlockId,
     ) -> RpcResult<FeeEstimation>;
 
-    /// Simulates execution of a series of transactions.
+    /// Simulates execution of a series of transactions. The state and the block context can
+    /// optionally be overridden.
     #[method(name = "simulateTransactions")]
     async fn simulate_transactions(
         &self,
         block_id: BlockId,
         transactions: Vec<BroadcastedTransaction>,
         simulation_flags: Vec<SimulationFlag>,
+        state_override: Option<Vec<ContractOverride>>,
+        block_override: Option<BlockOverrides>,
     ) -> RpcResult<Vec<SimulatedTransaction>>;
 
     /// Calculates the transaction trace of a transaction that is already included in a block.
@@ -265,6 +294,19 @@ pub trait JsonRpc {
         block_id: BlockId,
         class_hash: ClassHash,
     ) -> RpcResult<(CompiledContractClass, SierraVersion)>;
+
+    /// Returns Merkle proofs of the given classes, contracts and storage keys in the given block,
+    /// together with the roots of the global state tries.
+    /// Unsupported unless storage proofs are enabled in the config, since the node doesn't write
+    /// the tries while syncing.
+    #[method(name = "getStorageProof")]
+    fn get_storage_proof(
+        &self,
+        block_id: BlockId,
+        class_hashes: Option<Vec<ClassHash>>,
+        contract_addresses: Option<Vec<ContractAddress>>,
+        contracts_storage_keys: Option<Vec<ContractStorageKeys>>,
+    ) -> RpcResult<StorageProof>;
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -9,8 +9,7 @@ use async_trait::async_trait;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use indexmap::{indexmap, IndexMap};
 use itertools::Itertools;
-use jsonrpsee::core::Error;
-use jsonrpsee::Methods;
+use jsonrpsee::{Methods, MethodsError};
 use jsonschema::JSONSchema;
 use lazy_static::lazy_static;
 use mockall::predicate::eq;
@@ -21,6 +20,7 @@ use papyrus_storage::body::{BodyStorageWriter, TransactionIndex};
 use papyrus_storage::class::ClassStorageWriter;
 use papyrus_storage::compiled_class::CasmStorageWriter;
 use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::pruning::HistoryPruningStorageWriter;
 use papyrus_storage::state::StateStorageWriter;
 use papyrus_storage::test_utils::get_test_storage;
 use papyrus_storage::StorageScope;
@@ -41,6 +41,7 @@ use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{
     Block as StarknetApiBlock,
+    BlockBody,
     BlockHash,
     BlockHashAndNumber,
     BlockHeader,
@@ -76,12 +77,17 @@ use starknet_api::transaction::{
     EventData,
     EventIndexInTransactionOutput,
     EventKey,
+    L1HandlerTransaction as StarknetApiL1HandlerTransaction,
+    L1HandlerTransactionOutput,
+    L1TransactionHash,
+    RevertedTransactionExecutionStatus,
     Transaction as StarknetApiTransaction,
+    TransactionExecutionStatus,
     TransactionHash,
     TransactionOffsetInBlock,
     TransactionOutput as StarknetApiTransactionOutput,
 };
-use starknet_api::{class_hash, contract_address, felt, storage_key, tx_hash};
+use starknet_api::{calldata, class_hash, contract_address, felt, storage_key, tx_hash};
 use starknet_client::reader::objects::pending_data::{
     DeprecatedPendingBlock,
     PendingBlockOrDeprecated,
@@ -95,6 +101,7 @@ use starknet_client::reader::objects::state::{
     StorageEntry as ClientStorageEntry,
 };
 use starknet_client::reader::objects::transaction::{
+    L1HandlerTransaction as ClientL1HandlerTransaction,
     Transaction as ClientTransaction,
     TransactionReceipt as ClientTransactionReceipt,
 };
@@ -118,6 +125,7 @@ use super::super::block::{Block, GeneralBlockHeader, PendingBlockHeader, Resourc
 use super::super::broadcasted_transaction::BroadcastedDeclareTransaction;
 use super::super::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use super::super::error::{
+    l1_transaction_not_indexed,
     unexpected_error,
     JsonRpcError,
     BLOCK_NOT_FOUND,
@@ -152,6 +160,8 @@ use super::super::transaction::{
     InvokeTransaction,
     L1HandlerMsgHash,
     L1L2MsgHash,
+    MessageExecutionStatus,
+    MessageStatus,
     PendingTransactionFinalityStatus,
     PendingTransactionOutput,
     PendingTransactionReceipt,
@@ -479,7 +489,53 @@ async fn get_block_transaction_count() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
+}
+
+#[tokio::test]
+async fn get_block_transaction_count_of_pruned_block() {
+    let method_name = "starknet_V0_8_getBlockTransactionCount";
+    let (module, mut storage_writer) =
+        get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
+    let transaction_count = 5;
+    let block = get_test_block(transaction_count, None, None, None);
+    let mut txn = storage_writer.begin_rw_txn().unwrap();
+    for (block_number, body) in
+        [(BlockNumber(0), BlockBody::default()), (BlockNumber(1), block.body)]
+    {
+        txn = txn
+            .append_header(
+                block_number,
+                &BlockHeader { block_hash: BlockHash(block_number.0.into()), ..Default::default() },
+            )
+            .unwrap()
+            .append_body(block_number, body)
+            .unwrap()
+            .append_state_diff(block_number, starknet_api::state::ThinStateDiff::default())
+            .unwrap()
+            .append_classes(block_number, &[], &[])
+            .unwrap();
+    }
+    txn.prune_history(BlockNumber(1)).unwrap().commit().unwrap();
+
+    // The history of a pruned block isn't available.
+    let err = module
+        .call::<_, usize>(
+            method_name,
+            [BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0)))],
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
+
+    let res = module
+        .call::<_, usize>(
+            method_name,
+            [BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1)))],
+        )
+        .await
+        .unwrap();
+    assert_eq!(res, transaction_count);
 }
 
 #[tokio::test]
@@ -582,7 +638,7 @@ async fn get_block_w_full_transactions() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
 
     // Ask for an invalid block number.
     let err = module
@@ -592,7 +648,7 @@ async fn get_block_w_full_transactions() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
 
     // Get pending block.
     let mut rng = get_rng();
@@ -767,7 +823,7 @@ async fn get_block_w_full_transactions_and_receipts() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
 
     // Ask for an invalid block number.
     let err = module
@@ -777,7 +833,7 @@ async fn get_block_w_full_transactions_and_receipts() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
 
     // Get pending block.
     let mut rng = get_rng();
@@ -976,7 +1032,7 @@ async fn get_block_w_transaction_hashes() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
 
     // Get pending block.
     let mut rng = get_rng();
@@ -1210,7 +1266,7 @@ async fn get_class() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == CLASS_HASH_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == CLASS_HASH_NOT_FOUND.into());
 
     // Ask for an invalid block hash.
     call_api_then_assert_and_validate_schema_for_err::<_, DeprecatedContractClass>(
@@ -1236,7 +1292,7 @@ async fn get_class() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
 }
 
 #[tokio::test]
@@ -1358,6 +1414,150 @@ async fn get_transaction_status() {
     .await;
 }
 
+#[tokio::test]
+async fn get_messages_status() {
+    let method_name = "starknet_V0_8_getMessagesStatus";
+    let pending_data = get_test_pending_data();
+    let (module, mut storage_writer) = get_test_rpc_server_and_storage_writer_from_params::<
+        JsonRpcServerImpl,
+    >(None, None, Some(pending_data.clone()), None, None);
+    let l1_transaction_hash = L1TransactionHash([1; 32]);
+
+    // The first two messages are consumed in block 0, the third is consumed in the pending block
+    // and the fourth isn't consumed yet.
+    let l1_handler_tx = |nonce: u8| {
+        StarknetApiTransaction::L1Handler(StarknetApiL1HandlerTransaction {
+            nonce: Nonce(felt!(nonce)),
+            ..Default::default()
+        })
+    };
+    let revert_reason = "Message handler failed".to_string();
+    let body = BlockBody {
+        transactions: vec![l1_handler_tx(1), l1_handler_tx(2)],
+        transaction_outputs: vec![
+            StarknetApiTransactionOutput::L1Handler(L1HandlerTransactionOutput::default()),
+            StarknetApiTransactionOutput::L1Handler(L1HandlerTransactionOutput {
+                execution_status: TransactionExecutionStatus::Reverted(
+                    RevertedTransactionExecutionStatus { revert_reason: revert_reason.clone() },
+                ),
+                ..Default::default()
+            }),
+        ],
+        transaction_hashes: vec![tx_hash!(1), tx_hash!(2)],
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .append_body(BlockNumber(0), body)
+        .unwrap()
+        .write_l1_transaction_messages(
+            &(1..=4_u8).map(|nonce| (l1_transaction_hash, Nonce(felt!(nonce)))).collect::<Vec<_>>(),
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+    {
+        let pending_block = &mut pending_data.write().await.block;
+        pending_block.transactions_mutable().push(ClientTransaction::L1Handler(
+            ClientL1HandlerTransaction {
+                transaction_hash: tx_hash!(3),
+                nonce: Nonce(felt!(3_u8)),
+                // The receipt of an L1 handler hashes its message, which starts with the sender.
+                calldata: calldata![felt!("0x1")],
+                ..Default::default()
+            },
+        ));
+        pending_block
+            .transaction_receipts_mutable()
+            .push(ClientTransactionReceipt { transaction_hash: tx_hash!(3), ..Default::default() });
+    }
+
+    let succeeded_status = |transaction_hash, finality_status| MessageStatus {
+        transaction_hash,
+        finality_status,
+        execution_status: MessageExecutionStatus::Succeeded,
+        failure_reason: None,
+    };
+    let reverted_status = MessageStatus {
+        transaction_hash: tx_hash!(2),
+        finality_status: TransactionFinalityStatus::AcceptedOnL2,
+        execution_status: MessageExecutionStatus::Reverted,
+        failure_reason: Some(revert_reason),
+    };
+    let res =
+        module.call::<_, Vec<MessageStatus>>(method_name, [l1_transaction_hash]).await.unwrap();
+    assert_eq!(
+        res,
+        vec![
+            succeeded_status(tx_hash!(1), TransactionFinalityStatus::AcceptedOnL2),
+            reverted_status.clone(),
+            succeeded_status(tx_hash!(3), TransactionFinalityStatus::AcceptedOnL2),
+        ]
+    );
+
+    // Ask again after block 0 was accepted on L1.
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_base_layer_block_marker(&BlockNumber(1))
+        .unwrap()
+        .commit()
+        .unwrap();
+    let res =
+        module.call::<_, Vec<MessageStatus>>(method_name, [l1_transaction_hash]).await.unwrap();
+    assert_eq!(res[0].finality_status, TransactionFinalityStatus::AcceptedOnL1);
+    assert_eq!(res[1].finality_status, TransactionFinalityStatus::AcceptedOnL1);
+    assert_eq!(res[2].finality_status, TransactionFinalityStatus::AcceptedOnL2);
+
+    // Ask for an L1 transaction that didn't send any indexed message, before any L1 block was
+    // indexed, after the messages were indexed from a later L1 block and after they were indexed
+    // from the first L1 block.
+    let unindexed_l1_transaction_hash = L1TransactionHash([2; 32]);
+    let err = module
+        .call::<_, Vec<MessageStatus>>(method_name, [unindexed_l1_transaction_hash])
+        .await
+        .unwrap_err();
+    assert_matches!(
+        err,
+        MethodsError::JsonRpc(err)
+        if err == l1_transaction_not_indexed("The messages to L2 aren't indexed.".to_string()).into()
+    );
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_base_layer_messages_start(5)
+        .unwrap()
+        .commit()
+        .unwrap();
+    let err = module
+        .call::<_, Vec<MessageStatus>>(method_name, [unindexed_l1_transaction_hash])
+        .await
+        .unwrap_err();
+    assert_matches!(
+        err,
+        MethodsError::JsonRpc(err)
+        if err == l1_transaction_not_indexed(
+            "The messages to L2 are indexed only from L1 block 5.".to_string()
+        ).into()
+    );
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_base_layer_messages_start(0)
+        .unwrap()
+        .commit()
+        .unwrap();
+    let err = module
+        .call::<_, Vec<MessageStatus>>(method_name, [unindexed_l1_transaction_hash])
+        .await
+        .unwrap_err();
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == TRANSACTION_HASH_NOT_FOUND.into());
+}
+
 #[tokio::test]
 async fn get_transaction_receipt() {
     let method_name = "starknet_V0_8_getTransactionReceipt";
@@ -1424,6 +1624,27 @@ async fn get_transaction_receipt() {
     let res = module.call::<_, TransactionReceipt>(method_name, [transaction_hash]).await.unwrap();
     assert_eq!(res.finality_status, TransactionFinalityStatus::AcceptedOnL1);
 
+    // Ask for a transaction in a block whose events weren't stored yet.
+    let block_number = BlockNumber(1);
+    let mut body = get_test_body(1, None, None, None);
+    body.transaction_hashes[0] = TransactionHash(felt!("0x1"));
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(
+            block_number,
+            &BlockHeader { block_hash: BlockHash(felt!("0x1")), ..Default::default() },
+        )
+        .unwrap()
+        .append_body_without_events(block_number, body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+    let (_, res) =
+        raw_call::<_, _, TransactionReceipt>(&module, method_name, &[body.transaction_hashes[0]])
+            .await;
+    assert_eq!(res.unwrap_err(), TRANSACTION_HASH_NOT_FOUND.into());
+
     // Add a pending transaction and ask for its receipt.
     let mut rng = get_rng();
     let (client_transaction, client_transaction_receipt, _, expected_receipt) =
@@ -1668,7 +1889,7 @@ async fn get_class_at() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == CONTRACT_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == CONTRACT_NOT_FOUND.into());
 
     // Ask for an invalid block hash.
     call_api_then_assert_and_validate_schema_for_err::<_, DeprecatedContractClass>(
@@ -1694,7 +1915,7 @@ async fn get_class_at() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
 }
 
 #[tokio::test]
@@ -1857,7 +2078,7 @@ async fn get_class_hash_at() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
 }
 
 #[tokio::test]
@@ -2001,7 +2222,7 @@ async fn get_nonce() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
 }
 
 #[tokio::test]
@@ -2196,7 +2417,7 @@ async fn get_storage_at() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
 }
 
 fn generate_client_transaction_client_receipt_rpc_transaction_and_rpc_receipt(
@@ -2476,7 +2697,7 @@ async fn get_transaction_by_block_id_and_index() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
 
     // Ask for an invalid transaction index.
     call_api_then_assert_and_validate_schema_for_err::<_, TransactionWithHash>(
@@ -2655,7 +2876,7 @@ async fn get_state_update() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
 }
 
 #[tokio::test]
@@ -3750,7 +3971,7 @@ async fn get_compiled_class() {
         )
         .await
         .unwrap_err();
-    assert_matches!(err, Error::Call(err) if err == CLASS_HASH_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == CLASS_HASH_NOT_FOUND.into());
 }
 
 #[async_trait]
@@ -3836,7 +4057,7 @@ where
             None,
         );
         let result = module.call::<_, Self::Response>(Self::METHOD_NAME, [tx]).await;
-        let jsonrpsee::core::Error::Call(error) = result.unwrap_err() else {
+        let MethodsError::JsonRpc(error) = result.unwrap_err() else {
             panic!("Got an error which is not a call error");
         };
         assert_eq!(error, expected_error);
@@ -3870,7 +4091,7 @@ where
             None,
         );
         let result = module.call::<_, Self::Response>(Self::METHOD_NAME, [tx]).await;
-        let jsonrpsee::core::Error::Call(error) = result.unwrap_err() else {
+        let MethodsError::JsonRpc(error) = result.unwrap_err() else {
             panic!("Got an error which is not a call error");
         };
         assert_eq!(error, expected_error.into());
@@ -3901,7 +4122,7 @@ where
             None,
         );
         let result = module.call::<_, Self::Response>(Self::METHOD_NAME, [tx]).await;
-        let jsonrpsee::core::Error::Call(error) = result.unwrap_err() else {
+        let MethodsError::JsonRpc(error) = result.unwrap_err() else {
             panic!("Got an error which is not a call error");
         };
         assert_eq!(error, unexpected_error(MESSAGE.to_owned()).into());
@@ -36,6 +36,12 @@ pub const CLASS_HASH_NOT_FOUND: JsonRpcError<String> =
 pub const TRANSACTION_HASH_NOT_FOUND: JsonRpcError<String> =
     JsonRpcError { code: 29, message: "Transaction hash not found", data: None };
 
+// Has the code of TRANSACTION_HASH_NOT_FOUND, so that clients that don't know this error handle it
+// as a transaction that wasn't found.
+pub fn l1_transaction_not_indexed(data: String) -> JsonRpcError<String> {
+    JsonRpcError { code: 29, message: "L1 transaction isn't indexed", data: Some(data) }
+}
+
 pub const PAGE_SIZE_TOO_BIG: JsonRpcError<String> =
     JsonRpcError { code: 31, message: "Requested page size is too big", data: None };
 
@@ -72,6 +78,12 @@ impl From<TransactionExecutionError> for JsonRpcError<TransactionExecutionError>
         Self { code: 41, message: "Transaction execution error", data: Some(tx_execution_error) }
     }
 }
+
+pub const STORAGE_PROOF_NOT_SUPPORTED: JsonRpcError<String> = JsonRpcError {
+    code: 42,
+    message: "the node doesn't support storage proofs for blocks that are too far in the past",
+    data: None,
+};
 pub const CLASS_ALREADY_DECLARED: JsonRpcError<String> =
     JsonRpcError { code: 51, message: "Class already declared", data: None };
 
@@ -125,6 +137,18 @@ pub fn unexpected_error(data: String) -> JsonRpcError<String> {
     JsonRpcError { code: 63, message: "An unexpected error occurred", data: Some(data) }
 }
 
+pub const INVALID_SUBSCRIPTION_ID: JsonRpcError<String> =
+    JsonRpcError { code: 66, message: "Invalid subscription id", data: None };
+
+pub const TOO_MANY_ADDRESSES_IN_FILTER: JsonRpcError<String> = JsonRpcError {
+    code: 67,
+    message: "Too many addresses in filter sender_address filter",
+    data: None,
+};
+
+pub const TOO_MANY_BLOCKS_BACK: JsonRpcError<String> =
+    JsonRpcError { code: 68, message: "Cannot go back more than 1024 blocks", data: None };
+
 impl<T: Serialize> From<JsonRpcError<T>> for ErrorObjectOwned {
     fn from(err: JsonRpcError<T>) -> Self {
         ErrorObjectOwned::owned(err.code, err.message, err.data)
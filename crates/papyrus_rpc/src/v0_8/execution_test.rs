@@ -4,8 +4,7 @@ use std::sync::Arc;
 use assert_matches::assert_matches;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use indexmap::indexmap;
-use jsonrpsee::core::Error;
-use jsonrpsee::RpcModule;
+use jsonrpsee::{MethodsError, RpcModule};
 use lazy_static::lazy_static;
 use papyrus_common::pending_classes::{ApiContractClass, PendingClasses, PendingClassesTrait};
 use papyrus_common::state::{
@@ -15,7 +14,9 @@ use papyrus_common::state::{
 };
 use papyrus_execution::execution_utils::selector_from_name;
 use papyrus_execution::objects::{
+    BlockOverrides,
     CallType,
+    ContractOverride,
     FeeEstimation,
     FunctionCall,
     OrderedEvent,
@@ -247,7 +248,7 @@ async fn execution_call() {
         .await
         .unwrap_err();
 
-    assert_matches!(err, Error::Call(err) if err == CONTRACT_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == CONTRACT_NOT_FOUND.into());
 
     // Calling a non-existent block.
     let err = module
@@ -265,7 +266,7 @@ async fn execution_call() {
         .await
         .unwrap_err();
 
-    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
 
     // Calling a non-existent function (contract error).
     let err = module
@@ -286,14 +287,14 @@ async fn execution_call() {
     const CONTRACT_ERROR_CODE: i32 = 40;
 
     match err {
-        Error::Call(err) => {
+        MethodsError::JsonRpc(err) => {
             assert_eq!(err.code(), CONTRACT_ERROR_CODE);
             assert_eq!(
                 err.data().unwrap().get(),
                 r##"{"revert_error":"0x454e545259504f494e545f4e4f545f464f554e44 ('ENTRYPOINT_NOT_FOUND')"}"##
             );
         }
-        _ => panic!("Expected Error::Call"),
+        _ => panic!("Expected MethodsError::JsonRpc"),
     }
 
     // Test that the block context is passed correctly to blockifier.
@@ -324,6 +325,71 @@ async fn execution_call() {
         .unwrap();
 }
 
+#[tokio::test]
+async fn execution_call_with_overrides() {
+    let (module, storage_writer) = get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
+
+    prepare_storage_for_execution(storage_writer);
+
+    // Test that the overridden block context is passed to blockifier.
+    let overridden_block_number = BlockNumber(5);
+    let overridden_block_timestamp = BlockTimestamp(BLOCK_TIMESTAMP.0 + 100);
+    let mut calldata = get_calldata_for_test_execution_info(
+        overridden_block_number,
+        overridden_block_timestamp,
+        *SEQUENCER_ADDRESS,
+        &InvokeTransactionV1::default(),
+        tx_hash!(0),
+        Some(Felt::ZERO),
+    );
+    // Calling the contract directly and not through the account contract.
+    let contract_address = ContractAddress(
+        PatriciaKey::try_from(Arc::get_mut(&mut calldata.0).unwrap().remove(0)).unwrap(),
+    );
+    let entry_point_selector = EntryPointSelector(Arc::get_mut(&mut calldata.0).unwrap().remove(0));
+    let _calldata_length = Arc::get_mut(&mut calldata.0).unwrap().remove(0);
+
+    module
+        .call::<_, Vec<Felt>>(
+            "starknet_V0_8_call",
+            (
+                CallRequest { contract_address, entry_point_selector, calldata },
+                BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0))),
+                Option::<Vec<ContractOverride>>::None,
+                BlockOverrides {
+                    block_number: Some(overridden_block_number),
+                    timestamp: Some(overridden_block_timestamp),
+                    ..Default::default()
+                },
+            ),
+        )
+        .await
+        .unwrap();
+
+    // Test that a balance override is visible to the fee token contract.
+    let balance = felt!(7_u8);
+    let res = module
+        .call::<_, Vec<Felt>>(
+            "starknet_V0_8_call",
+            (
+                CallRequest {
+                    contract_address: *TEST_ERC20_CONTRACT_ADDRESS,
+                    entry_point_selector: selector_from_name("balanceOf"),
+                    calldata: calldata![*ACCOUNT_ADDRESS.0.key()],
+                },
+                BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0))),
+                vec![ContractOverride {
+                    address: *ACCOUNT_ADDRESS,
+                    balance: Some(balance),
+                    ..Default::default()
+                }],
+            ),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res, vec![balance, Felt::ZERO]);
+}
+
 #[tokio::test]
 async fn pending_execution_call() {
     let pending_data = get_test_pending_data();
@@ -490,7 +556,7 @@ async fn call_estimate_fee() {
         )
         .await
         .expect_err("Expecting error");
-    let Error::Call(err) = res else {
+    let MethodsError::JsonRpc(err) = res else {
         panic!("Expecting error");
     };
     assert_eq!(err.code(), 41);
@@ -7,6 +7,8 @@ pub mod execution;
 #[cfg(test)]
 mod execution_test;
 pub mod state;
+pub mod storage_proof;
+pub mod subscriptions;
 pub mod transaction;
 pub mod write_api_error;
 pub mod write_api_result;
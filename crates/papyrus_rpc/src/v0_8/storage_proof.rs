@@ -0,0 +1,204 @@
+use jsonrpsee::types::ErrorObjectOwned;
+use papyrus_storage::db::TransactionKind;
+use papyrus_storage::patricia::GlobalTrieRoots;
+use papyrus_storage::StorageTxn;
+use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockHash;
+use starknet_api::core::{ClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkHash;
+use starknet_api::state::StorageKey;
+use starknet_committer::block_committer::input::StarknetStorageValue;
+use starknet_committer::patricia_merkle_tree::leaf::leaf_impl::ContractState;
+use starknet_committer::patricia_merkle_tree::types::CompiledClassHash;
+use starknet_patricia::felt::Felt as PatriciaFelt;
+use starknet_patricia::hash::hash_trait::HashOutput;
+use starknet_patricia::patricia_merkle_tree::merkle_proof::errors::MerkleProofError;
+use starknet_patricia::patricia_merkle_tree::merkle_proof::proof::{
+    MerkleProof,
+    Preimage,
+    PreimageMap,
+};
+use starknet_patricia::patricia_merkle_tree::node_data::inner_node::{BinaryData, EdgeData};
+use starknet_patricia::patricia_merkle_tree::node_data::leaf::Leaf;
+use starknet_patricia::patricia_merkle_tree::types::{NodeIndex, SortedLeafIndices};
+use starknet_patricia::storage::errors::StorageError;
+use starknet_types_core::felt::Felt;
+
+use super::error::STORAGE_PROOF_NOT_SUPPORTED;
+use crate::internal_server_error;
+
+#[cfg(test)]
+#[path = "storage_proof_test.rs"]
+mod storage_proof_test;
+
+/// The storage keys of a contract to prove.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ContractStorageKeys {
+    pub contract_address: ContractAddress,
+    pub storage_keys: Vec<StorageKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum MerkleNode {
+    BinaryNode { left: Felt, right: Felt },
+    EdgeNode { path: Felt, length: u8, child: Felt },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct NodeHashToNode {
+    pub node_hash: Felt,
+    pub node: MerkleNode,
+}
+
+/// The nodes of a proof, sorted by their hashes.
+pub type NodeHashToNodeMapping = Vec<NodeHashToNode>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ContractLeafData {
+    pub nonce: Nonce,
+    pub class_hash: ClassHash,
+    pub storage_root: Felt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ContractsProof {
+    pub nodes: NodeHashToNodeMapping,
+    /// The data of the requested contracts, in the order they were requested.
+    pub contract_leaves_data: Vec<ContractLeafData>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GlobalRoots {
+    pub contracts_tree_root: Felt,
+    pub classes_tree_root: Felt,
+    pub block_hash: BlockHash,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StorageProof {
+    pub classes_proof: NodeHashToNodeMapping,
+    pub contracts_proof: ContractsProof,
+    /// The proofs of the requested storage keys, in the order the contracts were requested.
+    pub contracts_storage_proofs: Vec<NodeHashToNodeMapping>,
+    pub global_roots: GlobalRoots,
+}
+
+/// Extracts the proofs of the given classes, contracts and storage keys from the tries with the
+/// given roots.
+pub(crate) fn get_storage_proof<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    roots: GlobalTrieRoots,
+    block_hash: BlockHash,
+    class_hashes: &[ClassHash],
+    contract_addresses: &[ContractAddress],
+    contracts_storage_keys: &[ContractStorageKeys],
+) -> Result<StorageProof, ErrorObjectOwned> {
+    let classes_proof = fetch_proof::<CompiledClassHash>(
+        txn,
+        roots.classes_trie_root,
+        class_hashes.iter().map(|class_hash| class_hash.0),
+    )?;
+
+    let contracts_proof = fetch_proof::<ContractState>(
+        txn,
+        roots.contracts_trie_root,
+        contract_addresses.iter().map(|address| *address.0.key()),
+    )?;
+    let contract_leaves_data = contract_addresses
+        .iter()
+        .map(|address| {
+            let contract_state = get_leaf(&contracts_proof, *address.0.key());
+            ContractLeafData {
+                nonce: Nonce(contract_state.nonce.0.0),
+                class_hash: ClassHash(contract_state.class_hash.0.0),
+                storage_root: contract_state.storage_root_hash.0.0,
+            }
+        })
+        .collect();
+
+    // The storage roots of the contracts are the only data needed from their leaves, so the nodes
+    // of these paths are not part of the contracts proof.
+    let storage_contracts = fetch_proof::<ContractState>(
+        txn,
+        roots.contracts_trie_root,
+        contracts_storage_keys.iter().map(|storage_keys| *storage_keys.contract_address.0.key()),
+    )?;
+    let contracts_storage_proofs = contracts_storage_keys
+        .iter()
+        .map(|ContractStorageKeys { contract_address, storage_keys }| {
+            let storage_root =
+                get_leaf(&storage_contracts, *contract_address.0.key()).storage_root_hash.0.0;
+            let storage_proof = fetch_proof::<StarknetStorageValue>(
+                txn,
+                storage_root,
+                storage_keys.iter().map(|key| *key.0.key()),
+            )?;
+            Ok(to_node_hash_to_node_mapping(storage_proof.nodes))
+        })
+        .collect::<Result<_, ErrorObjectOwned>>()?;
+
+    Ok(StorageProof {
+        classes_proof: to_node_hash_to_node_mapping(classes_proof.nodes),
+        contracts_proof: ContractsProof {
+            nodes: to_node_hash_to_node_mapping(contracts_proof.nodes),
+            contract_leaves_data,
+        },
+        contracts_storage_proofs,
+        global_roots: GlobalRoots {
+            contracts_tree_root: roots.contracts_trie_root,
+            classes_tree_root: roots.classes_trie_root,
+            block_hash,
+        },
+    })
+}
+
+fn fetch_proof<L: Leaf>(
+    txn: &StorageTxn<'_, impl TransactionKind>,
+    root: StarkHash,
+    leaves: impl Iterator<Item = Felt>,
+) -> Result<MerkleProof<L>, ErrorObjectOwned> {
+    let mut leaf_indices: Vec<NodeIndex> =
+        leaves.map(|leaf| NodeIndex::from_leaf_felt(&PatriciaFelt(leaf))).collect();
+    // The proof extraction assumes that the indices are unique.
+    leaf_indices.sort();
+    leaf_indices.dedup();
+    MerkleProof::fetch(
+        txn,
+        HashOutput(PatriciaFelt(root)),
+        SortedLeafIndices::new(&mut leaf_indices),
+    )
+    .map_err(|err| match err {
+        // The nodes of the tries of old blocks might have been deleted.
+        MerkleProofError::StorageRead(StorageError::MissingKey(_)) => {
+            ErrorObjectOwned::from(STORAGE_PROOF_NOT_SUPPORTED)
+        }
+        err => internal_server_error(err),
+    })
+}
+
+fn get_leaf<L: Leaf>(proof: &MerkleProof<L>, leaf: Felt) -> L {
+    // Requested leaves that are not in the tree are part of the proof as empty leaves.
+    proof.leaves.get(&NodeIndex::from_leaf_felt(&PatriciaFelt(leaf))).cloned().unwrap_or_default()
+}
+
+fn to_node_hash_to_node_mapping(nodes: PreimageMap) -> NodeHashToNodeMapping {
+    let mut mapping: NodeHashToNodeMapping = nodes
+        .into_iter()
+        .map(|(hash, preimage)| NodeHashToNode {
+            node_hash: hash.0.0,
+            node: match preimage {
+                Preimage::Binary(BinaryData { left_hash, right_hash }) => {
+                    MerkleNode::BinaryNode { left: left_hash.0.0, right: right_hash.0.0 }
+                }
+                Preimage::Edge(EdgeData { bottom_hash, path_to_bottom }) => MerkleNode::EdgeNode {
+                    path: PatriciaFelt::from(&path_to_bottom.path).0,
+                    length: path_to_bottom.length.into(),
+                    child: bottom_hash.0.0,
+                },
+            },
+        })
+        .collect();
+    mapping.sort_by_key(|node| node.node_hash);
+    mapping
+}
@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
+use jsonrpsee::{MethodsError, RpcModule};
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::patricia::{GlobalTrieRoots, PatriciaStorageWriter};
+use papyrus_storage::state::StateStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageWriter;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{
+    BlockHash,
+    BlockHashAndNumber,
+    BlockHeader,
+    BlockHeaderWithoutHash,
+    BlockNumber,
+};
+use starknet_api::core::{ClassHash, ContractAddress, Nonce};
+use starknet_api::state::ThinStateDiff;
+use starknet_api::{class_hash, contract_address, felt, storage_key};
+use starknet_client::writer::MockStarknetWriter;
+use starknet_committer::block_committer::commit::commit_block;
+use starknet_committer::block_committer::input::{
+    ConfigImpl,
+    ContractAddress as CommitterContractAddress,
+    Input,
+    StarknetStorageKey,
+    StarknetStorageValue,
+    StateDiff,
+};
+use starknet_committer::patricia_merkle_tree::types::{
+    ClassHash as CommitterClassHash,
+    CompiledClassHash,
+    Nonce as CommitterNonce,
+};
+use starknet_patricia::felt::Felt as PatriciaFelt;
+use starknet_patricia::hash::hash_trait::HashOutput;
+use starknet_patricia::patricia_merkle_tree::filled_tree::tree::FilledTree;
+use starknet_types_core::felt::Felt;
+use tracing::level_filters::LevelFilter;
+
+use super::{
+    ContractLeafData,
+    ContractStorageKeys,
+    MerkleNode,
+    NodeHashToNodeMapping,
+    StorageProof,
+};
+use crate::api::{BlockHashOrNumber, BlockId, JsonRpcServerTrait, Tag};
+use crate::test_utils::{
+    get_test_highest_block,
+    get_test_pending_classes,
+    get_test_pending_data,
+    get_test_rpc_config,
+    get_test_rpc_server_and_storage_writer,
+};
+use crate::v0_8::api::api_impl::JsonRpcServerImpl;
+use crate::v0_8::error::{BLOCK_NOT_FOUND, STORAGE_PROOF_NOT_SUPPORTED};
+
+const METHOD_NAME: &str = "starknet_V0_8_getStorageProof";
+const TREE_HEIGHT: usize = 251;
+
+const CONTRACT_ADDRESS: &str = "0x100";
+const CLASS_HASH: &str = "0x10";
+const NONCE: u8 = 1;
+
+fn block_header() -> BlockHeader {
+    BlockHeader {
+        block_hash: BlockHash(felt!("0x1234")),
+        block_header_without_hash: BlockHeaderWithoutHash {
+            block_number: BlockNumber(0),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn block_id() -> BlockId {
+    BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0)))
+}
+
+fn append_block(storage_writer: &mut StorageWriter) {
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &block_header())
+        .unwrap()
+        .append_state_diff(BlockNumber(0), ThinStateDiff::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+}
+
+/// Commits a state with a single contract and class to the tries of block 0 and returns the roots
+/// of the tries and the storage root of the contract.
+async fn commit_state(storage_writer: &mut StorageWriter) -> (GlobalTrieRoots, Felt) {
+    let address = CommitterContractAddress(PatriciaFelt(felt!(CONTRACT_ADDRESS)));
+    let class_hash = CommitterClassHash(PatriciaFelt(felt!(CLASS_HASH)));
+    let storage_updates = [("0x5", "0x7"), ("0x6", "0x8")]
+        .into_iter()
+        .map(|(key, value)| {
+            (
+                StarknetStorageKey(PatriciaFelt(felt!(key))),
+                StarknetStorageValue(PatriciaFelt(felt!(value))),
+            )
+        })
+        .collect();
+    let state_diff = StateDiff {
+        address_to_class_hash: HashMap::from([(address, class_hash)]),
+        address_to_nonce: HashMap::from([(address, CommitterNonce(PatriciaFelt(felt!(NONCE))))]),
+        class_hash_to_compiled_class_hash: HashMap::from([(
+            class_hash,
+            CompiledClassHash(PatriciaFelt(felt!("0x11"))),
+        )]),
+        storage_updates: HashMap::from([(address, storage_updates)]),
+    };
+    let filled_forest = commit_block(Input {
+        storage: HashMap::new(),
+        state_diff,
+        contracts_trie_root_hash: HashOutput::default(),
+        classes_trie_root_hash: HashOutput::default(),
+        config: ConfigImpl::new(false, LevelFilter::OFF),
+    })
+    .await
+    .unwrap();
+
+    let roots = GlobalTrieRoots {
+        contracts_trie_root: filled_forest.get_contract_root_hash().0.0,
+        classes_trie_root: filled_forest.get_compiled_class_root_hash().0.0,
+    };
+    let mut txn = storage_writer.begin_rw_txn().unwrap();
+    filled_forest.write_to_storage(&mut txn);
+    txn.write_global_trie_roots(BlockNumber(0), &roots).unwrap().commit().unwrap();
+    (roots, filled_forest.storage_tries[&address].get_root_hash().0.0)
+}
+
+/// Walks the proof from the root along the path of the given leaf. Returns the hash of the leaf,
+/// or None if an edge node on the path proves that the leaf is not in the tree.
+fn walk_proof(nodes: &NodeHashToNodeMapping, root: Felt, leaf: Felt) -> Option<Felt> {
+    let nodes: HashMap<_, _> = nodes.iter().map(|node| (node.node_hash, &node.node)).collect();
+    let leaf_bits = leaf.to_bits_le();
+    let mut height = TREE_HEIGHT;
+    let mut hash = root;
+    while height > 0 {
+        match nodes[&hash] {
+            MerkleNode::BinaryNode { left, right } => {
+                height -= 1;
+                hash = if leaf_bits[height] { *right } else { *left };
+            }
+            MerkleNode::EdgeNode { path, length, child } => {
+                let length = usize::from(*length);
+                let path_bits = path.to_bits_le();
+                if (0..length).any(|i| path_bits[i] != leaf_bits[height - length + i]) {
+                    return None;
+                }
+                height -= length;
+                hash = *child;
+            }
+        }
+    }
+    Some(hash)
+}
+
+#[tokio::test]
+async fn get_storage_proof() {
+    let (module, mut storage_writer) =
+        get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
+    append_block(&mut storage_writer);
+    let (roots, storage_root) = commit_state(&mut storage_writer).await;
+
+    let proof = module
+        .call::<_, StorageProof>(
+            METHOD_NAME,
+            (
+                block_id(),
+                vec![class_hash!(CLASS_HASH), class_hash!("0x30")],
+                vec![contract_address!(CONTRACT_ADDRESS), contract_address!("0x300")],
+                vec![ContractStorageKeys {
+                    contract_address: contract_address!(CONTRACT_ADDRESS),
+                    storage_keys: vec![storage_key!("0x5"), storage_key!("0x9")],
+                }],
+            ),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(proof.global_roots.contracts_tree_root, roots.contracts_trie_root);
+    assert_eq!(proof.global_roots.classes_tree_root, roots.classes_trie_root);
+    assert_eq!(proof.global_roots.block_hash, block_header().block_hash);
+
+    let classes_proof = &proof.classes_proof;
+    assert!(walk_proof(classes_proof, roots.classes_trie_root, felt!(CLASS_HASH)).is_some());
+    assert_eq!(walk_proof(classes_proof, roots.classes_trie_root, felt!("0x30")), None);
+
+    let contracts_proof = &proof.contracts_proof.nodes;
+    assert!(
+        walk_proof(contracts_proof, roots.contracts_trie_root, felt!(CONTRACT_ADDRESS)).is_some()
+    );
+    assert_eq!(walk_proof(contracts_proof, roots.contracts_trie_root, felt!("0x300")), None);
+    assert_eq!(
+        proof.contracts_proof.contract_leaves_data,
+        vec![
+            ContractLeafData {
+                nonce: Nonce(felt!(NONCE)),
+                class_hash: class_hash!(CLASS_HASH),
+                storage_root,
+            },
+            ContractLeafData {
+                nonce: Nonce::default(),
+                class_hash: ClassHash::default(),
+                storage_root: Felt::ZERO,
+            },
+        ]
+    );
+
+    // The hash of a storage leaf is its value.
+    assert_eq!(proof.contracts_storage_proofs.len(), 1);
+    let storage_proof = &proof.contracts_storage_proofs[0];
+    assert_eq!(walk_proof(storage_proof, storage_root, felt!("0x5")), Some(felt!("0x7")));
+    assert_eq!(walk_proof(storage_proof, storage_root, felt!("0x9")), None);
+}
+
+#[tokio::test]
+async fn get_storage_proof_of_empty_request() {
+    let (module, mut storage_writer) =
+        get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
+    append_block(&mut storage_writer);
+    let (roots, _) = commit_state(&mut storage_writer).await;
+
+    let proof = module.call::<_, StorageProof>(METHOD_NAME, [block_id()]).await.unwrap();
+    assert!(proof.classes_proof.is_empty());
+    assert!(proof.contracts_proof.nodes.is_empty());
+    assert!(proof.contracts_proof.contract_leaves_data.is_empty());
+    assert!(proof.contracts_storage_proofs.is_empty());
+    assert_eq!(proof.global_roots.contracts_tree_root, roots.contracts_trie_root);
+}
+
+async fn call_and_get_err(module: &RpcModule<JsonRpcServerImpl>, block_id: BlockId) -> MethodsError {
+    module
+        .call::<_, StorageProof>(
+            METHOD_NAME,
+            (
+                block_id,
+                Vec::<ClassHash>::new(),
+                Vec::<ContractAddress>::new(),
+                Vec::<ContractStorageKeys>::new(),
+            ),
+        )
+        .await
+        .unwrap_err()
+}
+
+#[tokio::test]
+async fn get_storage_proof_errors() {
+    let (module, mut storage_writer) =
+        get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
+    append_block(&mut storage_writer);
+
+    // The tries of the block were not written.
+    let err = call_and_get_err(&module, block_id()).await;
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == STORAGE_PROOF_NOT_SUPPORTED.into());
+
+    commit_state(&mut storage_writer).await;
+    let err = call_and_get_err(&module, BlockId::Tag(Tag::Pending)).await;
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == STORAGE_PROOF_NOT_SUPPORTED.into());
+
+    let err =
+        call_and_get_err(&module, BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1))))
+            .await;
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == BLOCK_NOT_FOUND.into());
+}
+
+#[tokio::test]
+async fn get_storage_proof_when_disabled() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let config = get_test_rpc_config();
+    let module = JsonRpcServerImpl::new(
+        config.chain_id,
+        config.execution_config,
+        storage_reader,
+        config.max_events_chunk_size,
+        config.max_events_keys,
+        false,
+        BlockHashAndNumber::default(),
+        get_test_highest_block(),
+        get_test_pending_data(),
+        get_test_pending_classes(),
+        Arc::new(MockStarknetWriter::new()),
+    )
+    .into_rpc_module();
+    append_block(&mut storage_writer);
+    commit_state(&mut storage_writer).await;
+
+    let err = call_and_get_err(&module, block_id()).await;
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == STORAGE_PROOF_NOT_SUPPORTED.into());
+}
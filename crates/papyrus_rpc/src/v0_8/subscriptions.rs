@@ -0,0 +1,709 @@
+//! The subscriptions of the API, which are served over WebSocket.
+//!
+//! Each subscription runs in its own task, which computes the notifications of the subscription
+//! whenever the chain is updated (see [`crate::chain_updates`]). The methods of the subscriptions
+//! aren't prefixed with the version, since the messages of WebSocket connections aren't proxied.
+#[cfg(test)]
+#[path = "subscriptions_test.rs"]
+mod subscriptions_test;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use jsonrpsee::core::{RpcResult, SubscriptionResult};
+use jsonrpsee::types::{ErrorObjectOwned, Params, SubscriptionId};
+use jsonrpsee::{
+    ConnectionId,
+    Extensions,
+    PendingSubscriptionSink,
+    RpcModule,
+    SubscriptionMessage,
+    SubscriptionSink,
+};
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::db::RO;
+use papyrus_storage::{StorageReader, StorageScope, StorageTxn};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{BlockHash, BlockHashAndNumber, BlockNumber};
+use starknet_api::core::ContractAddress;
+use starknet_api::transaction::{
+    Event as StarknetApiEvent,
+    EventKey,
+    Transaction as StarknetApiTransaction,
+    TransactionHash,
+};
+use starknet_client::reader::objects::transaction::Transaction as ClientTransaction;
+use starknet_client::reader::PendingData;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, oneshot, RwLock};
+
+use super::api::api_impl::read_pending_data;
+use super::block::{get_accepted_block_number, get_block_header_by_number, BlockHeader};
+use super::error::{
+    INVALID_SUBSCRIPTION_ID,
+    TOO_MANY_ADDRESSES_IN_FILTER,
+    TOO_MANY_BLOCKS_BACK,
+    TOO_MANY_KEYS_IN_FILTER,
+};
+use super::transaction::{
+    get_block_tx_hashes_by_number,
+    Event,
+    TransactionFinalityStatus,
+    TransactionStatus,
+    TransactionWithHash,
+};
+use crate::api::BlockId;
+use crate::chain_updates::{get_accepted_block_marker, ChainUpdate};
+use crate::{get_block_status, internal_server_error, verify_storage_scope};
+
+/// The maximal number of blocks before the latest block that a subscription can start from.
+pub const MAX_BLOCKS_BACK: u64 = 1024;
+/// The maximal number of addresses in the sender_address filter of
+/// starknet_subscribePendingTransactions.
+pub const MAX_SENDER_ADDRESSES: usize = 100;
+
+const REORG_NOTIFICATION: &str = "starknet_subscriptionReorg";
+
+/// The reverted blocks that a reorg notification is sent about.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ReorgData {
+    pub starting_block_hash: BlockHash,
+    pub starting_block_number: BlockNumber,
+    pub ending_block_hash: BlockHash,
+    pub ending_block_number: BlockNumber,
+}
+
+impl ReorgData {
+    fn new(first_reverted: &BlockHashAndNumber, last_reverted: &BlockHashAndNumber) -> Self {
+        Self {
+            starting_block_hash: first_reverted.hash,
+            starting_block_number: first_reverted.number,
+            ending_block_hash: last_reverted.hash,
+            ending_block_number: last_reverted.number,
+        }
+    }
+}
+
+/// A notification of starknet_subscribeTransactionStatus.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct NewTransactionStatus {
+    pub transaction_hash: TransactionHash,
+    pub status: TransactionStatus,
+}
+
+/// A notification of starknet_subscribePendingTransactions.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum PendingTransaction {
+    Full(TransactionWithHash),
+    Hash(TransactionHash),
+}
+
+/// The context of the subscriptions module.
+pub(crate) struct SubscriptionsContext {
+    storage_reader: StorageReader,
+    pending_data: Arc<RwLock<PendingData>>,
+    chain_updates: broadcast::Sender<ChainUpdate>,
+    max_events_keys: usize,
+    // Signals the task of each subscription to end when starknet_unsubscribe is called on the
+    // connection of the subscription.
+    unsubscribe_senders:
+        Mutex<HashMap<(ConnectionId, SubscriptionId<'static>), oneshot::Sender<()>>>,
+}
+
+/// Returns the module with the methods of the subscriptions.
+pub(crate) fn get_subscriptions_module(
+    storage_reader: StorageReader,
+    pending_data: Arc<RwLock<PendingData>>,
+    chain_updates: broadcast::Sender<ChainUpdate>,
+    max_events_keys: usize,
+) -> RpcModule<SubscriptionsContext> {
+    let mut module = RpcModule::new(SubscriptionsContext {
+        storage_reader,
+        pending_data,
+        chain_updates,
+        max_events_keys,
+        unsubscribe_senders: Mutex::new(HashMap::new()),
+    });
+    module
+        .register_subscription(
+            "starknet_subscribeNewHeads",
+            "starknet_subscriptionNewHeads",
+            "starknet_unsubscribeNewHeads",
+            run_subscription::<NewHeadsSubscription>,
+        )
+        .expect("Subscription methods should be unique.");
+    module
+        .register_subscription(
+            "starknet_subscribeEvents",
+            "starknet_subscriptionEvents",
+            "starknet_unsubscribeEvents",
+            run_subscription::<EventsSubscription>,
+        )
+        .expect("Subscription methods should be unique.");
+    module
+        .register_subscription(
+            "starknet_subscribeTransactionStatus",
+            "starknet_subscriptionTransactionStatus",
+            "starknet_unsubscribeTransactionStatus",
+            run_subscription::<TransactionStatusSubscription>,
+        )
+        .expect("Subscription methods should be unique.");
+    module
+        .register_subscription(
+            "starknet_subscribePendingTransactions",
+            "starknet_subscriptionPendingTransactions",
+            "starknet_unsubscribePendingTransactions",
+            run_subscription::<PendingTransactionsSubscription>,
+        )
+        .expect("Subscription methods should be unique.");
+    module
+        .register_method("starknet_unsubscribe", |params, context, extensions| {
+            let subscription_id = params.one::<SubscriptionId<'_>>()?.into_owned();
+            // The server sets the connection of every call.
+            let connection_id = extensions
+                .get::<ConnectionId>()
+                .copied()
+                .ok_or_else(|| ErrorObjectOwned::from(INVALID_SUBSCRIPTION_ID))?;
+            end_subscription(context, connection_id, subscription_id)
+        })
+        .expect("Subscription methods should be unique.");
+    module
+}
+
+// Ends a subscription of the given connection. The subscriptions of other connections can't be
+// ended, so their IDs are as invalid as unknown IDs.
+fn end_subscription(
+    context: &SubscriptionsContext,
+    connection_id: ConnectionId,
+    subscription_id: SubscriptionId<'static>,
+) -> RpcResult<bool> {
+    let unsubscribe_sender = context
+        .unsubscribe_senders
+        .lock()
+        .expect("Unsubscribe senders lock should not be poisoned.")
+        .remove(&(connection_id, subscription_id))
+        .ok_or_else(|| ErrorObjectOwned::from(INVALID_SUBSCRIPTION_ID))?;
+    // Sending fails only if the subscription already ended.
+    let _ = unsubscribe_sender.send(());
+    Ok(true)
+}
+
+enum Notification<T> {
+    Item(T),
+    Reorg(ReorgData),
+}
+
+trait Subscription: Sized + Send + 'static {
+    type Params: DeserializeOwned;
+    type Item: Serialize + Send;
+    // Whether the notifications depend on the pending data.
+    const USES_PENDING_DATA: bool;
+
+    fn new(
+        params: Self::Params,
+        context: &SubscriptionsContext,
+        txn: &StorageTxn<'_, RO>,
+    ) -> RpcResult<Self>;
+
+    // Returns the notifications about the chain since the previous call. The update is None for the
+    // initial notifications of the subscription, and the pending data is given only if the
+    // subscription uses it.
+    fn notifications(
+        &mut self,
+        txn: &StorageTxn<'_, RO>,
+        pending_data: Option<&PendingData>,
+        update: Option<&ChainUpdate>,
+    ) -> RpcResult<Vec<Notification<Self::Item>>>;
+}
+
+async fn run_subscription<S: Subscription>(
+    params: Params<'static>,
+    pending: PendingSubscriptionSink,
+    context: Arc<SubscriptionsContext>,
+    _extensions: Extensions,
+) -> SubscriptionResult {
+    // Receive the updates from before the chain is read, so that no update is missed.
+    let mut updates = context.chain_updates.subscribe();
+    let (mut subscription, notifications) = match new_subscription::<S>(params, &context).await {
+        Ok(subscription_and_notifications) => subscription_and_notifications,
+        Err(err) => {
+            pending.reject(err).await;
+            return Ok(());
+        }
+    };
+    let Ok(sink) = pending.accept().await else {
+        // The connection was closed.
+        return Ok(());
+    };
+    let (unsubscribe_sender, mut unsubscribe_receiver) = oneshot::channel();
+    context
+        .unsubscribe_senders
+        .lock()
+        .expect("Unsubscribe senders lock should not be poisoned.")
+        .insert((sink.connection_id(), sink.subscription_id()), unsubscribe_sender);
+
+    let result = async {
+        if !send_notifications(&sink, notifications).await? {
+            return Ok(());
+        }
+        loop {
+            // Biased, so that no update is notified after starknet_unsubscribe returned.
+            let update = tokio::select! {
+                biased;
+                _ = sink.closed() => return Ok(()),
+                _ = &mut unsubscribe_receiver => return Ok(()),
+                update = updates.recv() => update,
+            };
+            let update = match update {
+                Ok(update) => update,
+                Err(RecvError::Lagged(_)) => {
+                    return Err("The subscription fell behind the updates of the chain.".into());
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            };
+            let txn = context.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+            let notifications =
+                get_notifications(&mut subscription, &txn, &context, Some(&update)).await?;
+            drop(txn);
+            if !send_notifications(&sink, notifications).await? {
+                return Ok(());
+            }
+        }
+    }
+    .await;
+
+    context
+        .unsubscribe_senders
+        .lock()
+        .expect("Unsubscribe senders lock should not be poisoned.")
+        .remove(&(sink.connection_id(), sink.subscription_id()));
+    result
+}
+
+// Creates the subscription and returns it along with its initial notifications.
+async fn new_subscription<S: Subscription>(
+    params: Params<'static>,
+    context: &SubscriptionsContext,
+) -> RpcResult<(S, Vec<Notification<S::Item>>)> {
+    // Missing params are parsed as empty params, since most of the params are optional.
+    let params = if params.as_str().is_some() { params } else { Params::new(Some("[]")) };
+    let txn = context.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+    let mut subscription = S::new(params.parse()?, context, &txn)?;
+    let notifications = get_notifications(&mut subscription, &txn, context, None).await?;
+    Ok((subscription, notifications))
+}
+
+async fn get_notifications<S: Subscription>(
+    subscription: &mut S,
+    txn: &StorageTxn<'_, RO>,
+    context: &SubscriptionsContext,
+    update: Option<&ChainUpdate>,
+) -> RpcResult<Vec<Notification<S::Item>>> {
+    let pending_data = match S::USES_PENDING_DATA {
+        true => Some(read_pending_data(&context.pending_data, txn).await?),
+        false => None,
+    };
+    subscription.notifications(txn, pending_data.as_ref(), update)
+}
+
+// Sends the notifications and returns false if the subscription was closed.
+async fn send_notifications<T: Serialize>(
+    sink: &SubscriptionSink,
+    notifications: Vec<Notification<T>>,
+) -> Result<bool, serde_json::Error> {
+    for notification in notifications {
+        let message = match notification {
+            Notification::Item(item) => SubscriptionMessage::from_json(&item)?,
+            Notification::Reorg(reorg_data) => {
+                SubscriptionMessage::new(REORG_NOTIFICATION, sink.subscription_id(), &reorg_data)?
+            }
+        };
+        if sink.send(message).await.is_err() {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+// The accepted blocks that a subscription notifies about, in ascending order.
+struct AcceptedBlocks {
+    storage_scope: StorageScope,
+    // The first block that wasn't notified about.
+    next_block_number: BlockNumber,
+}
+
+impl AcceptedBlocks {
+    // Starts from the given block, or from the latest block if no block is given.
+    fn new(
+        txn: &StorageTxn<'_, RO>,
+        storage_scope: StorageScope,
+        block_id: Option<BlockId>,
+    ) -> RpcResult<Self> {
+        let latest_block_number = get_accepted_block_marker(txn, storage_scope)
+            .map_err(internal_server_error)?
+            .prev()
+            .unwrap_or_default();
+        let next_block_number = match block_id {
+            None | Some(BlockId::Tag(_)) => latest_block_number,
+            Some(block_id) => {
+                let block_number = get_accepted_block_number(txn, block_id)?;
+                if latest_block_number.0.saturating_sub(block_number.0) > MAX_BLOCKS_BACK {
+                    return Err(ErrorObjectOwned::from(TOO_MANY_BLOCKS_BACK));
+                }
+                block_number
+            }
+        };
+        Ok(Self { storage_scope, next_block_number })
+    }
+
+    // Returns the data of a reorg notification if the update reverted blocks that were notified
+    // about. The reverted blocks will be notified about again once they're replaced.
+    fn reorg(&mut self, update: Option<&ChainUpdate>) -> Option<ReorgData> {
+        let Some(ChainUpdate::Reorg { first_reverted, last_reverted }) = update else {
+            return None;
+        };
+        if first_reverted.number >= self.next_block_number {
+            return None;
+        }
+        self.next_block_number = first_reverted.number;
+        Some(ReorgData::new(first_reverted, last_reverted))
+    }
+
+    // Returns the accepted blocks that weren't notified about, and marks them as notified.
+    fn new_blocks(
+        &mut self,
+        txn: &StorageTxn<'_, RO>,
+    ) -> RpcResult<impl Iterator<Item = BlockNumber>> {
+        let accepted_block_marker =
+            get_accepted_block_marker(txn, self.storage_scope).map_err(internal_server_error)?;
+        let first_block_number = self.next_block_number;
+        self.next_block_number = self.next_block_number.max(accepted_block_marker);
+        Ok(first_block_number.iter_up_to(accepted_block_marker))
+    }
+}
+
+#[derive(Deserialize)]
+struct SubscribeNewHeadsParams {
+    #[serde(default)]
+    block_id: Option<BlockId>,
+}
+
+struct NewHeadsSubscription {
+    blocks: AcceptedBlocks,
+}
+
+impl Subscription for NewHeadsSubscription {
+    type Params = SubscribeNewHeadsParams;
+    type Item = BlockHeader;
+    const USES_PENDING_DATA: bool = false;
+
+    fn new(
+        params: Self::Params,
+        context: &SubscriptionsContext,
+        txn: &StorageTxn<'_, RO>,
+    ) -> RpcResult<Self> {
+        let blocks = AcceptedBlocks::new(txn, context.storage_reader.get_scope(), params.block_id)?;
+        Ok(Self { blocks })
+    }
+
+    fn notifications(
+        &mut self,
+        txn: &StorageTxn<'_, RO>,
+        _pending_data: Option<&PendingData>,
+        update: Option<&ChainUpdate>,
+    ) -> RpcResult<Vec<Notification<Self::Item>>> {
+        let mut notifications: Vec<_> =
+            self.blocks.reorg(update).map(Notification::Reorg).into_iter().collect();
+        for block_number in self.blocks.new_blocks(txn)? {
+            let header = get_block_header_by_number(txn, block_number)?;
+            notifications.push(Notification::Item(header.into()));
+        }
+        Ok(notifications)
+    }
+}
+
+#[derive(Deserialize)]
+struct SubscribeEventsParams {
+    #[serde(default)]
+    from_address: Option<ContractAddress>,
+    #[serde(default)]
+    keys: Vec<HashSet<EventKey>>,
+    #[serde(default)]
+    block_id: Option<BlockId>,
+}
+
+struct EventsSubscription {
+    from_address: Option<ContractAddress>,
+    keys: Vec<HashSet<EventKey>>,
+    blocks: AcceptedBlocks,
+}
+
+impl EventsSubscription {
+    fn matches(&self, event: &StarknetApiEvent) -> bool {
+        self.from_address.map_or(true, |from_address| event.from_address == from_address)
+            && self.keys.iter().enumerate().all(|(i, keys)| {
+                event.content.keys.len() > i
+                    && (keys.is_empty() || keys.contains(&event.content.keys[i]))
+            })
+    }
+}
+
+impl Subscription for EventsSubscription {
+    type Params = SubscribeEventsParams;
+    type Item = Event;
+    const USES_PENDING_DATA: bool = false;
+
+    fn new(
+        params: Self::Params,
+        context: &SubscriptionsContext,
+        txn: &StorageTxn<'_, RO>,
+    ) -> RpcResult<Self> {
+        verify_storage_scope(&context.storage_reader)?;
+        if params.keys.len() > context.max_events_keys {
+            return Err(ErrorObjectOwned::from(TOO_MANY_KEYS_IN_FILTER));
+        }
+        let blocks = AcceptedBlocks::new(txn, context.storage_reader.get_scope(), params.block_id)?;
+        Ok(Self { from_address: params.from_address, keys: params.keys, blocks })
+    }
+
+    fn notifications(
+        &mut self,
+        txn: &StorageTxn<'_, RO>,
+        _pending_data: Option<&PendingData>,
+        update: Option<&ChainUpdate>,
+    ) -> RpcResult<Vec<Notification<Self::Item>>> {
+        let mut notifications: Vec<_> =
+            self.blocks.reorg(update).map(Notification::Reorg).into_iter().collect();
+        for block_number in self.blocks.new_blocks(txn)? {
+            let block_hash = get_block_header_by_number(txn, block_number)?.block_hash;
+            let transaction_outputs = txn
+                .get_block_transaction_outputs(block_number)
+                .map_err(internal_server_error)?
+                .ok_or_else(|| internal_server_error(format!("Missing body of {block_number}")))?;
+            let transaction_hashes = get_block_tx_hashes_by_number(txn, block_number)?;
+            for (transaction_output, transaction_hash) in
+                transaction_outputs.iter().zip(transaction_hashes)
+            {
+                for event in transaction_output.events() {
+                    if self.matches(event) {
+                        notifications.push(Notification::Item(Event {
+                            block_hash: Some(block_hash),
+                            block_number: Some(block_number),
+                            transaction_hash,
+                            event: event.clone(),
+                        }));
+                    }
+                }
+            }
+        }
+        Ok(notifications)
+    }
+}
+
+#[derive(Deserialize)]
+struct SubscribeTransactionStatusParams {
+    transaction_hash: TransactionHash,
+}
+
+struct TransactionStatusSubscription {
+    transaction_hash: TransactionHash,
+    // The last status that was notified about.
+    status: Option<TransactionStatus>,
+    // The block of the transaction, if it's accepted.
+    block_number: Option<BlockNumber>,
+}
+
+impl TransactionStatusSubscription {
+    fn get_status(
+        &self,
+        txn: &StorageTxn<'_, RO>,
+        pending_data: &PendingData,
+    ) -> RpcResult<(Option<TransactionStatus>, Option<BlockNumber>)> {
+        let accepted_block_marker = get_accepted_block_marker(txn, StorageScope::FullArchive)
+            .map_err(internal_server_error)?;
+        if let Some(transaction_index) = txn
+            .get_transaction_idx_by_hash(&self.transaction_hash)
+            .map_err(internal_server_error)?
+        {
+            let block_number = transaction_index.0;
+            if block_number < accepted_block_marker {
+                let transaction_output = txn
+                    .get_transaction_output(transaction_index)
+                    .map_err(internal_server_error)?
+                    .ok_or_else(|| {
+                        internal_server_error(format!(
+                            "Missing output of {}",
+                            self.transaction_hash
+                        ))
+                    })?;
+                let status = TransactionStatus {
+                    finality_status: get_block_status(txn, block_number)?.into(),
+                    execution_status: transaction_output.execution_status().clone(),
+                };
+                return Ok((Some(status), Some(block_number)));
+            }
+        }
+
+        let pending_block = &pending_data.block;
+        let Some(transaction_receipt) = pending_block
+            .transaction_receipts()
+            .iter()
+            .find(|receipt| receipt.transaction_hash == self.transaction_hash)
+        else {
+            return Ok((None, None));
+        };
+        let Some(transaction) = pending_block
+            .transactions()
+            .iter()
+            .find(|transaction| transaction.transaction_hash() == self.transaction_hash)
+        else {
+            return Ok((None, None));
+        };
+        let transaction_output =
+            transaction_receipt.clone().into_starknet_api_transaction_output(transaction);
+        let status = TransactionStatus {
+            // ACCEPTED_ON_L2 is the only finality status of a pending transaction.
+            finality_status: TransactionFinalityStatus::AcceptedOnL2,
+            execution_status: transaction_output.execution_status().clone(),
+        };
+        Ok((Some(status), None))
+    }
+}
+
+impl Subscription for TransactionStatusSubscription {
+    type Params = SubscribeTransactionStatusParams;
+    type Item = NewTransactionStatus;
+    const USES_PENDING_DATA: bool = true;
+
+    fn new(
+        params: Self::Params,
+        context: &SubscriptionsContext,
+        _txn: &StorageTxn<'_, RO>,
+    ) -> RpcResult<Self> {
+        verify_storage_scope(&context.storage_reader)?;
+        Ok(Self { transaction_hash: params.transaction_hash, status: None, block_number: None })
+    }
+
+    fn notifications(
+        &mut self,
+        txn: &StorageTxn<'_, RO>,
+        pending_data: Option<&PendingData>,
+        update: Option<&ChainUpdate>,
+    ) -> RpcResult<Vec<Notification<Self::Item>>> {
+        let mut notifications = Vec::new();
+        if let (Some(ChainUpdate::Reorg { first_reverted, last_reverted }), Some(block_number)) =
+            (update, self.block_number)
+        {
+            if block_number >= first_reverted.number {
+                notifications
+                    .push(Notification::Reorg(ReorgData::new(first_reverted, last_reverted)));
+                self.status = None;
+            }
+        }
+
+        let pending_data =
+            pending_data.expect("Transaction status subscriptions use pending data.");
+        let (status, block_number) = self.get_status(txn, pending_data)?;
+        if let Some(new_status) = &status {
+            if Some(new_status) != self.status.as_ref() {
+                notifications.push(Notification::Item(NewTransactionStatus {
+                    transaction_hash: self.transaction_hash,
+                    status: new_status.clone(),
+                }));
+            }
+        }
+        self.status = status;
+        self.block_number = block_number;
+        Ok(notifications)
+    }
+}
+
+#[derive(Deserialize)]
+struct SubscribePendingTransactionsParams {
+    #[serde(default)]
+    transaction_details: bool,
+    #[serde(default)]
+    sender_address: HashSet<ContractAddress>,
+}
+
+struct PendingTransactionsSubscription {
+    transaction_details: bool,
+    sender_addresses: HashSet<ContractAddress>,
+    // The parent of the pending block and the number of its transactions that were notified about.
+    notified_pending_block: (BlockHash, usize),
+}
+
+impl Subscription for PendingTransactionsSubscription {
+    type Params = SubscribePendingTransactionsParams;
+    type Item = PendingTransaction;
+    const USES_PENDING_DATA: bool = true;
+
+    fn new(
+        params: Self::Params,
+        _context: &SubscriptionsContext,
+        _txn: &StorageTxn<'_, RO>,
+    ) -> RpcResult<Self> {
+        if params.sender_address.len() > MAX_SENDER_ADDRESSES {
+            return Err(ErrorObjectOwned::from(TOO_MANY_ADDRESSES_IN_FILTER));
+        }
+        Ok(Self {
+            transaction_details: params.transaction_details,
+            sender_addresses: params.sender_address,
+            notified_pending_block: (BlockHash::default(), 0),
+        })
+    }
+
+    fn notifications(
+        &mut self,
+        _txn: &StorageTxn<'_, RO>,
+        pending_data: Option<&PendingData>,
+        _update: Option<&ChainUpdate>,
+    ) -> RpcResult<Vec<Notification<Self::Item>>> {
+        let pending_block =
+            &pending_data.expect("Pending transactions subscriptions use pending data.").block;
+        let (parent_block_hash, notified_transactions) = self.notified_pending_block;
+        let first_new_transaction = match parent_block_hash == pending_block.parent_block_hash() {
+            true => notified_transactions,
+            false => 0,
+        };
+        let transactions = pending_block.transactions();
+        self.notified_pending_block = (pending_block.parent_block_hash(), transactions.len());
+
+        let mut notifications = Vec::new();
+        for transaction in transactions.iter().skip(first_new_transaction) {
+            if !self.sender_addresses.is_empty()
+                && !get_sender_address(transaction)
+                    .is_some_and(|sender_address| self.sender_addresses.contains(&sender_address))
+            {
+                continue;
+            }
+            let transaction_hash = transaction.transaction_hash();
+            let pending_transaction = match self.transaction_details {
+                true => {
+                    let starknet_api_transaction: StarknetApiTransaction =
+                        transaction.clone().try_into().map_err(internal_server_error)?;
+                    PendingTransaction::Full(TransactionWithHash {
+                        transaction: starknet_api_transaction
+                            .try_into()
+                            .map_err(internal_server_error)?,
+                        transaction_hash,
+                    })
+                }
+                false => PendingTransaction::Hash(transaction_hash),
+            };
+            notifications.push(Notification::Item(pending_transaction));
+        }
+        Ok(notifications)
+    }
+}
+
+fn get_sender_address(transaction: &ClientTransaction) -> Option<ContractAddress> {
+    match transaction {
+        ClientTransaction::Declare(tx) => Some(tx.sender_address),
+        ClientTransaction::DeployAccount(tx) => Some(tx.sender_address),
+        ClientTransaction::Invoke(tx) => Some(tx.sender_address),
+        ClientTransaction::Deploy(_) | ClientTransaction::L1Handler(_) => None,
+    }
+}
@@ -0,0 +1,503 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use assert_matches::assert_matches;
+use jsonrpsee::types::SubscriptionId;
+use jsonrpsee::{rpc_params, ConnectionId, MethodsError, RpcModule};
+use papyrus_storage::base_layer::BaseLayerStorageWriter;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::state::StateStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::{StorageReader, StorageWriter};
+use papyrus_test_utils::{get_rng, get_test_body, GetTestInstance};
+use pretty_assertions::assert_eq;
+use starknet_api::block::{
+    BlockBody,
+    BlockHash,
+    BlockHeader as StarknetApiBlockHeader,
+    BlockHeaderWithoutHash,
+    BlockNumber,
+};
+use starknet_api::core::ContractAddress;
+use starknet_api::state::ThinStateDiff;
+use starknet_api::transaction::{
+    EventKey,
+    Transaction as StarknetApiTransaction,
+    TransactionExecutionStatus,
+};
+use starknet_api::{contract_address, felt, tx_hash};
+use starknet_client::reader::objects::transaction::{
+    IntermediateInvokeTransaction,
+    Transaction as ClientTransaction,
+    TransactionReceipt as ClientTransactionReceipt,
+};
+use starknet_client::reader::PendingData;
+use tokio::sync::oneshot::error::TryRecvError;
+use tokio::sync::{broadcast, oneshot, RwLock};
+
+use super::{
+    end_subscription,
+    get_subscriptions_module,
+    NewTransactionStatus,
+    PendingTransaction,
+    ReorgData,
+    SubscriptionsContext,
+    MAX_BLOCKS_BACK,
+    MAX_SENDER_ADDRESSES,
+};
+use crate::api::{BlockHashOrNumber, BlockId};
+use crate::chain_updates::ChainUpdatesPoller;
+use crate::test_utils::get_test_pending_data;
+use crate::v0_8::block::BlockHeader;
+use crate::v0_8::error::{
+    INVALID_SUBSCRIPTION_ID,
+    TOO_MANY_ADDRESSES_IN_FILTER,
+    TOO_MANY_BLOCKS_BACK,
+    TOO_MANY_KEYS_IN_FILTER,
+};
+use crate::v0_8::transaction::{
+    Event,
+    TransactionFinalityStatus,
+    TransactionStatus,
+    TransactionWithHash,
+};
+
+const MAX_EVENTS_KEYS: usize = 2;
+
+fn get_test_subscriptions_module(
+    storage_reader: StorageReader,
+    pending_data: Arc<RwLock<PendingData>>,
+) -> (RpcModule<SubscriptionsContext>, ChainUpdatesPoller) {
+    let poller = ChainUpdatesPoller::new(storage_reader.clone(), pending_data.clone()).unwrap();
+    let module = get_subscriptions_module(
+        storage_reader,
+        pending_data,
+        poller.updates_sender(),
+        MAX_EVENTS_KEYS,
+    );
+    (module, poller)
+}
+
+fn append_block(
+    storage_writer: &mut StorageWriter,
+    block_number: u64,
+    block_hash: u64,
+    body: BlockBody,
+) -> StarknetApiBlockHeader {
+    let block_number = BlockNumber(block_number);
+    let header = StarknetApiBlockHeader {
+        block_hash: BlockHash(block_hash.into()),
+        block_header_without_hash: BlockHeaderWithoutHash { block_number, ..Default::default() },
+        ..Default::default()
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &header)
+        .unwrap()
+        .append_body(block_number, body)
+        .unwrap()
+        .append_state_diff(block_number, ThinStateDiff::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+    header
+}
+
+fn revert_block(storage_writer: &mut StorageWriter, block_number: u64) {
+    let block_number = BlockNumber(block_number);
+    let (txn, _) = storage_writer.begin_rw_txn().unwrap().revert_state_diff(block_number).unwrap();
+    let (txn, _) = txn.revert_body(block_number).unwrap();
+    let (txn, _, _) = txn.revert_header(block_number).unwrap();
+    txn.commit().unwrap();
+}
+
+#[tokio::test]
+async fn new_heads() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let headers: Vec<_> = (0..3)
+        .map(|block_number| {
+            append_block(&mut storage_writer, block_number, block_number, BlockBody::default())
+        })
+        .collect();
+    let (module, mut poller) =
+        get_test_subscriptions_module(storage_reader, get_test_pending_data());
+
+    // Without a block id, the notifications start from the latest block.
+    let mut subscription =
+        module.subscribe_unbounded("starknet_subscribeNewHeads", rpc_params![]).await.unwrap();
+    let (header, _) = subscription.next::<BlockHeader>().await.unwrap().unwrap();
+    assert_eq!(header, headers[2].clone().into());
+
+    let new_header = append_block(&mut storage_writer, 3, 3, BlockBody::default());
+    poller.poll().await.unwrap();
+    let (header, _) = subscription.next::<BlockHeader>().await.unwrap().unwrap();
+    assert_eq!(header, new_header.into());
+
+    revert_block(&mut storage_writer, 3);
+    let replacing_header = append_block(&mut storage_writer, 3, 13, BlockBody::default());
+    poller.poll().await.unwrap();
+    let (reorg_data, _) = subscription.next::<ReorgData>().await.unwrap().unwrap();
+    assert_eq!(
+        reorg_data,
+        ReorgData {
+            starting_block_hash: BlockHash(3_u64.into()),
+            starting_block_number: BlockNumber(3),
+            ending_block_hash: BlockHash(3_u64.into()),
+            ending_block_number: BlockNumber(3),
+        }
+    );
+    let (header, _) = subscription.next::<BlockHeader>().await.unwrap().unwrap();
+    assert_eq!(header, replacing_header.clone().into());
+
+    // With a block id, the notifications start from the given block.
+    let mut subscription = module
+        .subscribe_unbounded(
+            "starknet_subscribeNewHeads",
+            rpc_params![BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1)))],
+        )
+        .await
+        .unwrap();
+    for expected_header in [headers[1].clone(), headers[2].clone(), replacing_header] {
+        let (header, _) = subscription.next::<BlockHeader>().await.unwrap().unwrap();
+        assert_eq!(header, expected_header.into());
+    }
+}
+
+#[tokio::test]
+async fn new_heads_too_many_blocks_back() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let block_count = MAX_BLOCKS_BACK + 2;
+    let mut txn = storage_writer.begin_rw_txn().unwrap();
+    for block_number in 0..block_count {
+        let block_number = BlockNumber(block_number);
+        let header = StarknetApiBlockHeader {
+            block_hash: BlockHash(block_number.0.into()),
+            block_header_without_hash: BlockHeaderWithoutHash {
+                block_number,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        txn = txn
+            .append_header(block_number, &header)
+            .unwrap()
+            .append_body(block_number, BlockBody::default())
+            .unwrap()
+            .append_state_diff(block_number, ThinStateDiff::default())
+            .unwrap();
+    }
+    txn.commit().unwrap();
+    let (module, _poller) = get_test_subscriptions_module(storage_reader, get_test_pending_data());
+
+    let err = module
+        .subscribe_unbounded(
+            "starknet_subscribeNewHeads",
+            rpc_params![BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0)))],
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == TOO_MANY_BLOCKS_BACK.into());
+
+    module
+        .subscribe_unbounded(
+            "starknet_subscribeNewHeads",
+            rpc_params![BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1)))],
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn events() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let from_address = contract_address!("0x11");
+    let other_address = contract_address!("0x22");
+    let key = EventKey(felt!("0x33"));
+    let other_key = EventKey(felt!("0x44"));
+    let body = get_test_body(
+        3,
+        Some(4),
+        Some(vec![from_address, other_address]),
+        Some(vec![vec![key.clone(), other_key.clone()]]),
+    );
+    append_block(&mut storage_writer, 0, 0, body.clone());
+    let (module, mut poller) =
+        get_test_subscriptions_module(storage_reader, get_test_pending_data());
+
+    let mut subscription = module
+        .subscribe_unbounded(
+            "starknet_subscribeEvents",
+            rpc_params![from_address, vec![vec![key.clone()]]],
+        )
+        .await
+        .unwrap();
+
+    let mut new_body = get_test_body(
+        2,
+        Some(4),
+        Some(vec![from_address, other_address]),
+        Some(vec![vec![key.clone(), other_key]]),
+    );
+    for (i, transaction_hash) in new_body.transaction_hashes.iter_mut().enumerate() {
+        *transaction_hash = tx_hash!(100 + i);
+    }
+    append_block(&mut storage_writer, 1, 1, new_body.clone());
+    poller.poll().await.unwrap();
+
+    for (block_number, body) in [(0, body), (1, new_body)] {
+        for (transaction_output, transaction_hash) in
+            body.transaction_outputs.iter().zip(body.transaction_hashes)
+        {
+            for event in transaction_output.events() {
+                if event.from_address != from_address || event.content.keys[0] != key {
+                    continue;
+                }
+                let (notified_event, _) = subscription.next::<Event>().await.unwrap().unwrap();
+                assert_eq!(
+                    notified_event,
+                    Event {
+                        block_hash: Some(BlockHash(block_number.into())),
+                        block_number: Some(BlockNumber(block_number)),
+                        transaction_hash,
+                        event: event.clone(),
+                    }
+                );
+            }
+        }
+    }
+
+    let err = module
+        .subscribe_unbounded(
+            "starknet_subscribeEvents",
+            rpc_params![from_address, vec![vec![key.clone()]; MAX_EVENTS_KEYS + 1]],
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == TOO_MANY_KEYS_IN_FILTER.into());
+}
+
+#[tokio::test]
+async fn transaction_status() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let pending_data = get_test_pending_data();
+    let (module, mut poller) = get_test_subscriptions_module(storage_reader, pending_data.clone());
+    let transaction_hash = tx_hash!(0x55);
+
+    // The transaction is in the pending block.
+    {
+        let pending_block = &mut pending_data.write().await.block;
+        let mut transaction = ClientTransaction::get_test_instance(&mut get_rng());
+        *transaction.transaction_hash_mut() = transaction_hash;
+        pending_block.transactions_mutable().push(transaction);
+        pending_block
+            .transaction_receipts_mutable()
+            .push(ClientTransactionReceipt { transaction_hash, ..Default::default() });
+    }
+    let mut subscription = module
+        .subscribe_unbounded("starknet_subscribeTransactionStatus", rpc_params![transaction_hash])
+        .await
+        .unwrap();
+    let (new_status, _) = subscription.next::<NewTransactionStatus>().await.unwrap().unwrap();
+    assert_eq!(
+        new_status,
+        NewTransactionStatus {
+            transaction_hash,
+            status: TransactionStatus {
+                finality_status: TransactionFinalityStatus::AcceptedOnL2,
+                execution_status: TransactionExecutionStatus::Succeeded,
+            },
+        }
+    );
+
+    // The transaction is accepted, with the same status.
+    let mut body = get_test_body(1, None, None, None);
+    body.transaction_hashes[0] = transaction_hash;
+    append_block(&mut storage_writer, 0, 1, body.clone());
+    poller.poll().await.unwrap();
+
+    // The transaction is accepted on L1.
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_base_layer_block_marker(&BlockNumber(1))
+        .unwrap()
+        .commit()
+        .unwrap();
+    poller.poll().await.unwrap();
+    let (new_status, _) = subscription.next::<NewTransactionStatus>().await.unwrap().unwrap();
+    assert_eq!(
+        new_status,
+        NewTransactionStatus {
+            transaction_hash,
+            status: TransactionStatus {
+                finality_status: TransactionFinalityStatus::AcceptedOnL1,
+                execution_status: body.transaction_outputs[0].execution_status().clone(),
+            },
+        }
+    );
+
+    // The block of the transaction is reverted.
+    revert_block(&mut storage_writer, 0);
+    poller.poll().await.unwrap();
+    let (reorg_data, _) = subscription.next::<ReorgData>().await.unwrap().unwrap();
+    assert_eq!(
+        reorg_data,
+        ReorgData {
+            starting_block_hash: BlockHash(1_u64.into()),
+            starting_block_number: BlockNumber(0),
+            ending_block_hash: BlockHash(1_u64.into()),
+            ending_block_number: BlockNumber(0),
+        }
+    );
+}
+
+#[tokio::test]
+async fn pending_transactions() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let pending_data = get_test_pending_data();
+    let (module, mut poller) = get_test_subscriptions_module(storage_reader, pending_data.clone());
+    let sender_address = contract_address!("0x66");
+    let invoke_transaction = |transaction_hash, sender_address| {
+        ClientTransaction::Invoke(IntermediateInvokeTransaction {
+            transaction_hash,
+            sender_address,
+            ..Default::default()
+        })
+    };
+    pending_data
+        .write()
+        .await
+        .block
+        .transactions_mutable()
+        .push(invoke_transaction(tx_hash!(1), sender_address));
+
+    let mut all_subscription = module
+        .subscribe_unbounded("starknet_subscribePendingTransactions", rpc_params![])
+        .await
+        .unwrap();
+    let mut filtered_subscription = module
+        .subscribe_unbounded(
+            "starknet_subscribePendingTransactions",
+            rpc_params![false, vec![sender_address]],
+        )
+        .await
+        .unwrap();
+
+    pending_data.write().await.block.transactions_mutable().extend([
+        invoke_transaction(tx_hash!(2), ContractAddress::default()),
+        invoke_transaction(tx_hash!(3), sender_address),
+    ]);
+    poller.poll().await.unwrap();
+
+    for expected_transaction_hash in [tx_hash!(1), tx_hash!(2), tx_hash!(3)] {
+        let (transaction, _) =
+            all_subscription.next::<PendingTransaction>().await.unwrap().unwrap();
+        assert_eq!(transaction, PendingTransaction::Hash(expected_transaction_hash));
+    }
+    for expected_transaction_hash in [tx_hash!(1), tx_hash!(3)] {
+        let (transaction, _) =
+            filtered_subscription.next::<PendingTransaction>().await.unwrap().unwrap();
+        assert_eq!(transaction, PendingTransaction::Hash(expected_transaction_hash));
+    }
+
+    let too_many_addresses = (0..=MAX_SENDER_ADDRESSES)
+        .map(|i| ContractAddress::try_from(felt!(u64::try_from(i).unwrap())).unwrap())
+        .collect::<Vec<_>>();
+    let err = module
+        .subscribe_unbounded(
+            "starknet_subscribePendingTransactions",
+            rpc_params![false, too_many_addresses],
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == TOO_MANY_ADDRESSES_IN_FILTER.into());
+}
+
+#[tokio::test]
+async fn pending_transactions_with_details() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let pending_data = get_test_pending_data();
+    let (module, _poller) = get_test_subscriptions_module(storage_reader, pending_data.clone());
+    let mut rng = get_rng();
+    // TODO(shahak): Remove retry once v3 transactions are supported and the impl of TryInto will
+    // become impl of Into.
+    let (client_transaction, expected_transaction) = loop {
+        let client_transaction = ClientTransaction::get_test_instance(&mut rng);
+        let Ok(starknet_api_transaction): Result<StarknetApiTransaction, _> =
+            client_transaction.clone().try_into()
+        else {
+            continue;
+        };
+        let Ok(rpc_transaction) = starknet_api_transaction.try_into() else {
+            continue;
+        };
+        let transaction_hash = client_transaction.transaction_hash();
+        break (
+            client_transaction,
+            TransactionWithHash { transaction: rpc_transaction, transaction_hash },
+        );
+    };
+    pending_data.write().await.block.transactions_mutable().push(client_transaction);
+
+    let mut subscription = module
+        .subscribe_unbounded("starknet_subscribePendingTransactions", rpc_params![true])
+        .await
+        .unwrap();
+    let (transaction, _) = subscription.next::<PendingTransaction>().await.unwrap().unwrap();
+    assert_eq!(transaction, PendingTransaction::Full(expected_transaction));
+}
+
+#[tokio::test]
+async fn unsubscribe() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let (module, mut poller) =
+        get_test_subscriptions_module(storage_reader, get_test_pending_data());
+    let mut subscription =
+        module.subscribe_unbounded("starknet_subscribeNewHeads", rpc_params![]).await.unwrap();
+    let subscription_id = subscription.subscription_id().clone().into_owned();
+
+    let unsubscribed = module
+        .call::<_, bool>("starknet_unsubscribe", rpc_params![subscription_id.clone()])
+        .await
+        .unwrap();
+    assert!(unsubscribed);
+    append_block(&mut storage_writer, 0, 0, BlockBody::default());
+    poller.poll().await.unwrap();
+    // The subscription ended, so it isn't notified about the new block.
+    let notification =
+        tokio::time::timeout(Duration::from_millis(100), subscription.next::<BlockHeader>()).await;
+    assert_matches!(notification, Err(_) | Ok(None));
+
+    let err = module
+        .call::<_, bool>("starknet_unsubscribe", rpc_params![subscription_id])
+        .await
+        .unwrap_err();
+    assert_matches!(err, MethodsError::JsonRpc(err) if err == INVALID_SUBSCRIPTION_ID.into());
+}
+
+#[tokio::test]
+async fn unsubscribe_of_other_connection() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let subscription_id = SubscriptionId::Num(1);
+    let (unsubscribe_sender, mut unsubscribe_receiver) = oneshot::channel();
+    let context = SubscriptionsContext {
+        storage_reader,
+        pending_data: get_test_pending_data(),
+        chain_updates: broadcast::channel(1).0,
+        max_events_keys: MAX_EVENTS_KEYS,
+        unsubscribe_senders: Mutex::new(HashMap::from([(
+            (ConnectionId(1), subscription_id.clone()),
+            unsubscribe_sender,
+        )])),
+    };
+
+    let err = end_subscription(&context, ConnectionId(0), subscription_id.clone()).unwrap_err();
+    assert_eq!(err, INVALID_SUBSCRIPTION_ID.into());
+    assert_matches!(unsubscribe_receiver.try_recv(), Err(TryRecvError::Empty));
+
+    assert!(end_subscription(&context, ConnectionId(1), subscription_id).unwrap());
+    assert_matches!(unsubscribe_receiver.try_recv(), Ok(()));
+}
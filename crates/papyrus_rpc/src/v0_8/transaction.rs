@@ -655,6 +655,44 @@ pub enum TransactionFinalityStatus {
     AcceptedOnL1,
 }
 
+/// The status of the L1 handler transaction that consumed a message sent from L1 to L2.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
+pub struct MessageStatus {
+    pub transaction_hash: TransactionHash,
+    pub finality_status: TransactionFinalityStatus,
+    pub execution_status: MessageExecutionStatus,
+    /// The revert reason of the L1 handler transaction, if it was reverted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<String>,
+}
+
+impl MessageStatus {
+    pub fn new(transaction_hash: TransactionHash, status: TransactionStatus) -> Self {
+        let (execution_status, failure_reason) = match status.execution_status {
+            TransactionExecutionStatus::Succeeded => (MessageExecutionStatus::Succeeded, None),
+            TransactionExecutionStatus::Reverted(reverted) => {
+                (MessageExecutionStatus::Reverted, Some(reverted.revert_reason))
+            }
+        };
+        Self {
+            transaction_hash,
+            finality_status: status.finality_status,
+            execution_status,
+            failure_reason,
+        }
+    }
+}
+
+/// The execution status of the L1 handler transaction that consumed a message. Unlike
+/// [`TransactionExecutionStatus`], the revert reason is not part of it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
+pub enum MessageExecutionStatus {
+    #[serde(rename = "SUCCEEDED")]
+    Succeeded,
+    #[serde(rename = "REVERTED")]
+    Reverted,
+}
+
 /// Transaction Finality status on starknet for transactions in the pending block.
 #[derive(
     Debug, Copy, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord, Default,
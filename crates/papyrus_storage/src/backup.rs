@@ -0,0 +1,106 @@
+//! Online backup of the storage.
+//!
+//! A backup is taken with a [`StorageReader`], so the node can keep writing to the storage while
+//! it's backed up. The backup directory has the same layout as the storage directory
+//! ([`DbConfig::path`]), so the storage is restored by opening a storage whose path is the backup
+//! directory (or a copy of it).
+//!
+//! The database is copied first, from a snapshot that MDBX takes for the copy, and without its free
+//! pages. The memory mapped files are copied afterwards, up to their offsets in the copied
+//! database, which are read in a single transaction over the copy. Since the files are append
+//! only, the objects that the copied database refers to are in these offsets, and the objects that
+//! were appended after the snapshot of the copy aren't copied.
+//!
+//! [`DbConfig::path`]: crate::db::DbConfig::path
+//!
+//! # Example
+//! ```
+//! use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+//! use papyrus_storage::open_storage;
+//! # use papyrus_storage::{db::DbConfig, StorageConfig};
+//! use starknet_api::block::{BlockHeader, BlockNumber};
+//! # use starknet_api::core::ChainId;
+//!
+//! # let dir_handle = tempfile::tempdir().unwrap();
+//! # let dir = dir_handle.path().to_path_buf();
+//! # let db_config = DbConfig {
+//! #     path_prefix: dir,
+//! #     chain_id: ChainId::Mainnet,
+//! #     enforce_file_exists: false,
+//! #     min_size: 1 << 20,    // 1MB
+//! #     max_size: 1 << 35,    // 32GB
+//! #     growth_step: 1 << 26, // 64MB
+//! # };
+//! # let storage_config = StorageConfig{db_config, ..Default::default()};
+//! # let backup_dir_handle = tempfile::tempdir().unwrap();
+//! # let backup_path_prefix = backup_dir_handle.path().to_path_buf();
+//! let (reader, mut writer) = open_storage(storage_config.clone())?;
+//! writer.begin_rw_txn()?.append_header(BlockNumber(0), &BlockHeader::default())?.commit()?;
+//!
+//! let mut backup_config = storage_config.clone();
+//! backup_config.db_config.path_prefix = backup_path_prefix;
+//! reader.backup(&backup_config.db_config.path())?;
+//!
+//! let (backup_reader, _) = open_storage(backup_config)?;
+//! let header = backup_reader.begin_ro_txn()?.get_block_header(BlockNumber(0))?;
+//! assert_eq!(header, Some(BlockHeader::default()));
+//! # Ok::<(), papyrus_storage::StorageError>(())
+//! ```
+#[cfg(test)]
+#[path = "backup_test.rs"]
+mod backup_test;
+
+use std::fs;
+use std::path::Path;
+
+use tracing::info;
+
+use crate::db::table_types::Table;
+use crate::db::DbReader;
+use crate::{mmap_file_path, OffsetKind, StorageReader, StorageResult};
+
+impl StorageReader {
+    /// Backs up the storage to the given directory, see the module documentation. The directory is
+    /// created if it doesn't exist, and it must not contain a storage.
+    pub fn backup(&self, backup_dir: &Path) -> StorageResult<()> {
+        info!("Backing up the storage to {backup_dir:?}.");
+        fs::create_dir_all(backup_dir)?;
+        self.db_reader.copy_compacted(backup_dir)?;
+
+        // MDBX can't copy the database from a given transaction, so the offsets are read from the
+        // copy itself, where they match the copied snapshot.
+        let backup_db_reader = DbReader::open_copy(backup_dir)?;
+        let txn = backup_db_reader.begin_ro_txn()?;
+        let file_offsets_table = txn.open_table(&self.tables.file_offsets)?;
+        let offset = |offset_kind: OffsetKind| -> StorageResult<usize> {
+            Ok(file_offsets_table.get(&txn, &offset_kind)?.unwrap_or_default())
+        };
+        let file_readers = &self.file_readers;
+        file_readers.thin_state_diff.copy_to_file(
+            mmap_file_path(backup_dir, OffsetKind::ThinStateDiff),
+            offset(OffsetKind::ThinStateDiff)?,
+        )?;
+        file_readers.contract_class.copy_to_file(
+            mmap_file_path(backup_dir, OffsetKind::ContractClass),
+            offset(OffsetKind::ContractClass)?,
+        )?;
+        file_readers.casm.copy_to_file(
+            mmap_file_path(backup_dir, OffsetKind::Casm),
+            offset(OffsetKind::Casm)?,
+        )?;
+        file_readers.deprecated_contract_class.copy_to_file(
+            mmap_file_path(backup_dir, OffsetKind::DeprecatedContractClass),
+            offset(OffsetKind::DeprecatedContractClass)?,
+        )?;
+        file_readers.transaction_output.copy_to_file(
+            mmap_file_path(backup_dir, OffsetKind::TransactionOutput),
+            offset(OffsetKind::TransactionOutput)?,
+        )?;
+        file_readers.transaction.copy_to_file(
+            mmap_file_path(backup_dir, OffsetKind::Transaction),
+            offset(OffsetKind::Transaction)?,
+        )?;
+        info!("Finished backing up the storage to {backup_dir:?}.");
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use indexmap::indexmap;
+use papyrus_test_utils::get_test_block;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockNumber};
+use starknet_api::state::ThinStateDiff;
+use starknet_api::{contract_address, felt, storage_key};
+
+use crate::body::{BodyStorageReader, BodyStorageWriter};
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::get_test_config;
+use crate::{open_storage, StorageConfig, StorageWriter};
+
+fn state_diff(block_number: BlockNumber) -> ThinStateDiff {
+    ThinStateDiff {
+        storage_diffs: indexmap! {
+            contract_address!("0x100") => indexmap! { storage_key!("0x10") => felt!(block_number.0) }
+        },
+        ..Default::default()
+    }
+}
+
+const TXS_PER_BLOCK: usize = 2;
+
+fn block_body(block_number: BlockNumber) -> BlockBody {
+    let body = get_test_block(2 * TXS_PER_BLOCK, None, None, None).body;
+    let block_number = usize::try_from(block_number.0).unwrap();
+    let txs = block_number * TXS_PER_BLOCK..(block_number + 1) * TXS_PER_BLOCK;
+    BlockBody {
+        transactions: body.transactions[txs.clone()].to_vec(),
+        transaction_outputs: body.transaction_outputs[txs.clone()].to_vec(),
+        transaction_hashes: body.transaction_hashes[txs].to_vec(),
+    }
+}
+
+// Returns the appended body, since the test bodies are random.
+fn append_block(writer: &mut StorageWriter, block_number: BlockNumber) -> BlockBody {
+    let body = block_body(block_number);
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(block_number, body.clone())
+        .unwrap()
+        .append_state_diff(block_number, state_diff(block_number))
+        .unwrap()
+        .commit()
+        .unwrap();
+    body
+}
+
+fn backup_config(config: &StorageConfig, backup_temp_dir: &tempfile::TempDir) -> StorageConfig {
+    let mut backup_config = config.clone();
+    backup_config.db_config.path_prefix = backup_temp_dir.path().to_path_buf();
+    backup_config
+}
+
+#[test]
+fn backup_while_writing() {
+    let (config, _temp_dir) = get_test_config(None);
+    let backup_temp_dir = tempfile::tempdir().unwrap();
+    let backup_config = backup_config(&config, &backup_temp_dir);
+
+    let (reader, mut writer) = open_storage(config).unwrap();
+    let body_0 = append_block(&mut writer, BlockNumber(0));
+    // The objects of a block that isn't committed yet are already in the files.
+    let txn = writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(BlockNumber(1), block_body(BlockNumber(1)))
+        .unwrap()
+        .append_state_diff(BlockNumber(1), state_diff(BlockNumber(1)))
+        .unwrap();
+    // MDBX doesn't allow a thread to begin a read transaction while it has a write transaction.
+    let (backup_reader, backup_dir) = (reader.clone(), backup_config.db_config.path());
+    std::thread::spawn(move || backup_reader.backup(&backup_dir)).join().unwrap().unwrap();
+    txn.commit().unwrap();
+
+    let (backup_reader, mut backup_writer) = open_storage(backup_config).unwrap();
+    let txn = backup_reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_state_diff(BlockNumber(0)).unwrap(), Some(state_diff(BlockNumber(0))));
+    assert_eq!(txn.get_block_transactions(BlockNumber(0)).unwrap(), Some(body_0.transactions));
+    drop(txn);
+
+    // The restored storage can be written.
+    let body_1 = append_block(&mut backup_writer, BlockNumber(1));
+    let txn = backup_reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_diff(BlockNumber(1)).unwrap(), Some(state_diff(BlockNumber(1))));
+    assert_eq!(txn.get_block_transactions(BlockNumber(1)).unwrap(), Some(body_1.transactions));
+}
+
+#[test]
+fn backup_to_an_existing_storage_fails() {
+    let (config, _temp_dir) = get_test_config(None);
+    let backup_temp_dir = tempfile::tempdir().unwrap();
+    let backup_config = backup_config(&config, &backup_temp_dir);
+
+    let (reader, _writer) = open_storage(config).unwrap();
+    reader.backup(&backup_config.db_config.path()).unwrap();
+    assert!(reader.backup(&backup_config.db_config.path()).is_err());
+}
@@ -7,6 +7,10 @@
 //!
 //! Import [`BaseLayerStorageReader`] and [`BaseLayerStorageWriter`] to read and write data related
 //! to the base layer using a [`StorageTxn`].
+//!
+//! Besides the proved blocks, the messages to L2 are indexed by the L1 transactions that sent them.
+//! Together with the L1 handler transactions, which are indexed by the nonces of their messages,
+//! this allows following a message from L1 to its L1 handler transaction.
 //! # Example
 //! ```
 //! use papyrus_storage::base_layer::{BaseLayerStorageReader, BaseLayerStorageWriter};
@@ -40,6 +44,8 @@
 mod base_layer_test;
 
 use starknet_api::block::BlockNumber;
+use starknet_api::core::Nonce;
+use starknet_api::transaction::L1TransactionHash;
 
 use crate::db::table_types::Table;
 use crate::db::{TransactionKind, RW};
@@ -49,6 +55,21 @@ use crate::{MarkerKind, StorageResult, StorageTxn};
 pub trait BaseLayerStorageReader {
     /// The block number marker is the first block number that doesn't exist yet in the base layer.
     fn get_base_layer_block_marker(&self) -> StorageResult<BlockNumber>;
+
+    /// Returns the first L1 block whose messages to L2 weren't indexed yet, or None if no L1 block
+    /// was indexed.
+    fn get_base_layer_messages_marker(&self) -> StorageResult<Option<u64>>;
+
+    /// Returns the first L1 block whose messages to L2 were indexed, or None if no L1 block was
+    /// indexed. The messages of the L1 blocks before it aren't indexed.
+    fn get_base_layer_messages_start(&self) -> StorageResult<Option<u64>>;
+
+    /// Returns the nonces of the messages to L2 sent by the given L1 transaction, in ascending
+    /// order. Returns an empty vector if no message of the transaction was indexed.
+    fn get_l1_transaction_message_nonces(
+        &self,
+        l1_tx_hash: &L1TransactionHash,
+    ) -> StorageResult<Vec<Nonce>>;
 }
 
 /// Interface for writing data related to the base layer.
@@ -66,6 +87,19 @@ where
         self,
         reverted_block_number: BlockNumber,
     ) -> StorageResult<Self>;
+
+    /// Updates the first L1 block whose messages to L2 weren't indexed yet.
+    fn update_base_layer_messages_marker(self, l1_block_number: u64) -> StorageResult<Self>;
+
+    /// Updates the first L1 block whose messages to L2 were indexed.
+    fn update_base_layer_messages_start(self, l1_block_number: u64) -> StorageResult<Self>;
+
+    /// Indexes messages to L2 by the L1 transactions that sent them. Indexing a message again has
+    /// no effect.
+    fn write_l1_transaction_messages(
+        self,
+        messages: &[(L1TransactionHash, Nonce)],
+    ) -> StorageResult<Self>;
 }
 
 impl<Mode: TransactionKind> BaseLayerStorageReader for StorageTxn<'_, Mode> {
@@ -73,6 +107,30 @@ impl<Mode: TransactionKind> BaseLayerStorageReader for StorageTxn<'_, Mode> {
         let markers_table = self.open_table(&self.tables.markers)?;
         Ok(markers_table.get(&self.txn, &MarkerKind::BaseLayerBlock)?.unwrap_or_default())
     }
+
+    fn get_base_layer_messages_marker(&self) -> StorageResult<Option<u64>> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        // The markers table holds block numbers, so the L1 block number is stored as one.
+        Ok(markers_table
+            .get(&self.txn, &MarkerKind::BaseLayerMessages)?
+            .map(|l1_block_number| l1_block_number.0))
+    }
+
+    fn get_base_layer_messages_start(&self) -> StorageResult<Option<u64>> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        Ok(markers_table
+            .get(&self.txn, &MarkerKind::BaseLayerMessagesStart)?
+            .map(|l1_block_number| l1_block_number.0))
+    }
+
+    fn get_l1_transaction_message_nonces(
+        &self,
+        l1_tx_hash: &L1TransactionHash,
+    ) -> StorageResult<Vec<Nonce>> {
+        let l1_transaction_messages_table =
+            self.open_table(&self.tables.l1_transaction_messages)?;
+        Ok(l1_transaction_messages_table.get(&self.txn, l1_tx_hash)?.unwrap_or_default())
+    }
 }
 
 impl BaseLayerStorageWriter for StorageTxn<'_, RW> {
@@ -94,4 +152,42 @@ impl BaseLayerStorageWriter for StorageTxn<'_, RW> {
             Ok(self)
         }
     }
+
+    fn update_base_layer_messages_marker(self, l1_block_number: u64) -> StorageResult<Self> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        markers_table.upsert(
+            &self.txn,
+            &MarkerKind::BaseLayerMessages,
+            &BlockNumber(l1_block_number),
+        )?;
+        Ok(self)
+    }
+
+    fn update_base_layer_messages_start(self, l1_block_number: u64) -> StorageResult<Self> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        markers_table.upsert(
+            &self.txn,
+            &MarkerKind::BaseLayerMessagesStart,
+            &BlockNumber(l1_block_number),
+        )?;
+        Ok(self)
+    }
+
+    fn write_l1_transaction_messages(
+        self,
+        messages: &[(L1TransactionHash, Nonce)],
+    ) -> StorageResult<Self> {
+        let l1_transaction_messages_table =
+            self.open_table(&self.tables.l1_transaction_messages)?;
+        for (l1_tx_hash, nonce) in messages {
+            let mut nonces =
+                l1_transaction_messages_table.get(&self.txn, l1_tx_hash)?.unwrap_or_default();
+            // The nonces of the messages increase in the order they were sent.
+            if let Err(index) = nonces.binary_search(nonce) {
+                nonces.insert(index, *nonce);
+                l1_transaction_messages_table.upsert(&self.txn, l1_tx_hash, &nonces)?;
+            }
+        }
+        Ok(self)
+    }
 }
@@ -1,4 +1,7 @@
 use starknet_api::block::BlockNumber;
+use starknet_api::core::Nonce;
+use starknet_api::felt;
+use starknet_api::transaction::L1TransactionHash;
 
 use crate::base_layer::{BaseLayerStorageReader, BaseLayerStorageWriter};
 use crate::test_utils::get_test_storage;
@@ -50,3 +53,71 @@ fn try_revert_base_layer_marker() {
     let cur_marker = reader.begin_ro_txn().unwrap().get_base_layer_block_marker().unwrap();
     assert_eq!(cur_marker, BlockNumber(1));
 }
+
+#[test]
+fn rw_base_layer_messages_marker() {
+    let (reader, mut writer) = get_test_storage().0;
+
+    let marker = reader.begin_ro_txn().unwrap().get_base_layer_messages_marker().unwrap();
+    assert_eq!(marker, None);
+
+    writer.begin_rw_txn().unwrap().update_base_layer_messages_marker(7).unwrap().commit().unwrap();
+    let marker = reader.begin_ro_txn().unwrap().get_base_layer_messages_marker().unwrap();
+    assert_eq!(marker, Some(7));
+
+    // The messages marker is independent of the proved blocks marker.
+    let block_marker = reader.begin_ro_txn().unwrap().get_base_layer_block_marker().unwrap();
+    assert_eq!(block_marker, BlockNumber(0));
+}
+
+#[test]
+fn rw_base_layer_messages_start() {
+    let (reader, mut writer) = get_test_storage().0;
+
+    let start = reader.begin_ro_txn().unwrap().get_base_layer_messages_start().unwrap();
+    assert_eq!(start, None);
+
+    writer.begin_rw_txn().unwrap().update_base_layer_messages_start(5).unwrap().commit().unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_base_layer_messages_start().unwrap(), Some(5));
+    // The start of the indexed L1 blocks is independent of the messages marker.
+    assert_eq!(txn.get_base_layer_messages_marker().unwrap(), None);
+}
+
+#[test]
+fn rw_l1_transaction_messages() {
+    let (reader, mut writer) = get_test_storage().0;
+    let l1_tx_hash = L1TransactionHash([1; 32]);
+    let other_l1_tx_hash = L1TransactionHash([2; 32]);
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .write_l1_transaction_messages(&[
+            (l1_tx_hash, Nonce(felt!(3_u8))),
+            (other_l1_tx_hash, Nonce(felt!(2_u8))),
+            (l1_tx_hash, Nonce(felt!(1_u8))),
+        ])
+        .unwrap()
+        .commit()
+        .unwrap();
+    // Indexing a message again has no effect.
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .write_l1_transaction_messages(&[(l1_tx_hash, Nonce(felt!(3_u8)))])
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(
+        txn.get_l1_transaction_message_nonces(&l1_tx_hash).unwrap(),
+        vec![Nonce(felt!(1_u8)), Nonce(felt!(3_u8))]
+    );
+    assert_eq!(
+        txn.get_l1_transaction_message_nonces(&other_l1_tx_hash).unwrap(),
+        vec![Nonce(felt!(2_u8))]
+    );
+    assert!(txn.get_l1_transaction_message_nonces(&L1TransactionHash([3; 32])).unwrap().is_empty());
+}
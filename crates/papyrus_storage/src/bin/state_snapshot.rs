@@ -0,0 +1,89 @@
+use std::fs::File;
+
+use clap::{Arg, Command};
+use papyrus_storage::db::DbConfig;
+use papyrus_storage::snapshot::{export_state_snapshot, import_state_snapshot};
+use papyrus_storage::{StorageConfig, StorageScope};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ChainId;
+
+// Exports a state snapshot of a block from a storage, or imports a state snapshot into a new
+// storage with the StateOnly scope.
+pub fn main() {
+    let matches = Command::new("State snapshot")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("export")
+                .about("Exports a snapshot of the state right after the given block")
+                .args(common_args())
+                .arg(
+                    Arg::new("block_number")
+                        .short('b')
+                        .long("block_number")
+                        .required(true)
+                        .value_parser(clap::value_parser!(u64))
+                        .help("The block number of the snapshot"),
+                ),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Imports a snapshot into a new storage with the StateOnly scope")
+                .args(common_args()),
+        )
+        .get_matches();
+
+    let (subcommand, matches) = matches.subcommand().expect("Missing subcommand");
+    let db_path = matches.get_one::<String>("db_path").expect("Missing db_path");
+    let chain_id = matches.get_one::<String>("chain_id").expect("Missing chain_id");
+    let snapshot_path = matches.get_one::<String>("snapshot_path").expect("Missing snapshot_path");
+    let scope = if subcommand == "import" { StorageScope::StateOnly } else { Default::default() };
+    let db_config = DbConfig {
+        path_prefix: db_path.into(),
+        chain_id: ChainId::from(chain_id.clone()),
+        enforce_file_exists: subcommand == "export",
+        ..Default::default()
+    };
+    let config = StorageConfig { db_config, scope, ..Default::default() };
+    let (reader, mut writer) =
+        papyrus_storage::open_storage(config).expect("Should be able to open storage");
+
+    match subcommand {
+        "export" => {
+            let block_number =
+                BlockNumber(*matches.get_one::<u64>("block_number").expect("Missing block_number"));
+            println!("Exporting a state snapshot of block {block_number}");
+            let file =
+                File::create(snapshot_path).expect("Should be able to create the snapshot file");
+            export_state_snapshot(&reader, block_number, file)
+                .expect("Should be able to export the snapshot");
+        }
+        "import" => {
+            println!("Importing a state snapshot");
+            let file = File::open(snapshot_path).expect("Should be able to open the snapshot file");
+            let block_number = import_state_snapshot(file, &mut writer)
+                .expect("Should be able to import the snapshot");
+            println!("Imported the state snapshot of block {block_number}");
+        }
+        _ => unreachable!("Unknown subcommand"),
+    }
+}
+
+fn common_args() -> [Arg; 3] {
+    [
+        Arg::new("db_path")
+            .short('d')
+            .long("db_path")
+            .required(true)
+            .help("The path to the database"),
+        Arg::new("chain_id")
+            .short('c')
+            .long("chain_id")
+            .required(true)
+            .help("The chain id SN_MAIN/SN_SEPOLIA for example"),
+        Arg::new("snapshot_path")
+            .short('s')
+            .long("snapshot_path")
+            .required(true)
+            .help("The path to the snapshot file"),
+    ]
+}
@@ -5,7 +5,7 @@ use clap::{Arg, Command};
 use papyrus_common::storage_query::StorageQuery;
 use papyrus_storage::db::DbConfig;
 use papyrus_storage::state::StateStorageReader;
-use papyrus_storage::StorageConfig;
+use papyrus_storage::{StorageConfig, StorageReader};
 use serde::{Deserialize, Serialize};
 use starknet_api::core::ChainId;
 use statistical::median;
@@ -76,7 +76,8 @@ pub fn main() {
     println!("Writing results to file");
     let results_file = File::create(cli_params.output_file_path)
         .expect("Should be able to create the output file");
-    let final_results = times.get_final_results();
+    let mut final_results = times.get_final_results();
+    final_results.extend(get_files_sizes(&reader));
     serde_json::to_writer(results_file, &final_results)
         .expect("Should be able to write to the output file");
 }
@@ -150,6 +151,20 @@ impl Times {
     }
 }
 
+// Returns the amount of data in the files of the compressed kinds of data, for measuring the
+// impact of the compression config.
+fn get_files_sizes(reader: &StorageReader) -> Vec<Entry> {
+    let files_stats = reader.mmap_files_stats();
+    ["thin_state_diff", "contract_class", "casm", "deprecated_contract_class"]
+        .into_iter()
+        .map(|file_name| Entry {
+            name: format!("{file_name}_size"),
+            unit: "Bytes".to_string(),
+            value: files_stats[file_name].offset().try_into().expect("usize should fit in u128"),
+        })
+        .collect()
+}
+
 // Represents a single entry in the results file.
 #[derive(Debug, Clone, Default, Serialize)]
 struct Entry {
@@ -0,0 +1,58 @@
+use clap::{Arg, ArgAction, Command};
+use papyrus_storage::db::DbConfig;
+use papyrus_storage::integrity::check_storage_integrity;
+use papyrus_storage::{StorageConfig, StorageScope};
+use starknet_api::core::ChainId;
+
+// Checks the integrity of a storage and prints a JSON report with the first inconsistency found in
+// each table. Exits with a non-zero code if an inconsistency was found.
+pub fn main() {
+    let matches = Command::new("Storage integrity checker")
+        .arg(
+            Arg::new("db_path")
+                .short('d')
+                .long("db_path")
+                .required(true)
+                .help("The path to the database"),
+        )
+        .arg(
+            Arg::new("chain_id")
+                .short('c')
+                .long("chain_id")
+                .required(true)
+                .help("The chain id SN_MAIN/SN_SEPOLIA for example"),
+        )
+        .arg(
+            Arg::new("state_only")
+                .long("state_only")
+                .action(ArgAction::SetTrue)
+                .help("Whether the storage was created with the StateOnly scope"),
+        )
+        .get_matches();
+
+    let db_path = matches.get_one::<String>("db_path").expect("Missing db_path");
+    let chain_id = matches.get_one::<String>("chain_id").expect("Missing chain_id");
+    let scope = if matches.get_flag("state_only") {
+        StorageScope::StateOnly
+    } else {
+        StorageScope::FullArchive
+    };
+    let db_config = DbConfig {
+        path_prefix: db_path.into(),
+        chain_id: ChainId::from(chain_id.clone()),
+        enforce_file_exists: true,
+        ..Default::default()
+    };
+    let config = StorageConfig { db_config, scope, ..Default::default() };
+    let (reader, _writer) =
+        papyrus_storage::open_storage(config).expect("Should be able to open storage");
+
+    let report = check_storage_integrity(&reader).expect("Should be able to check the storage");
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).expect("Should be able to serialize the report")
+    );
+    if !report.is_consistent() {
+        std::process::exit(1);
+    }
+}
@@ -2,14 +2,26 @@ use assert_matches::assert_matches;
 use papyrus_test_utils::{get_test_block, get_test_body};
 use pretty_assertions::assert_eq;
 use starknet_api::block::{BlockBody, BlockNumber};
-use starknet_api::transaction::TransactionOffsetInBlock;
+use starknet_api::core::Nonce;
+use starknet_api::felt;
+use starknet_api::transaction::{
+    Event,
+    EventIndexInTransactionOutput,
+    L1HandlerTransaction,
+    L1HandlerTransactionOutput,
+    Transaction,
+    TransactionHash,
+    TransactionOffsetInBlock,
+    TransactionOutput,
+};
 use test_case::test_case;
 
+use crate::body::events::{EventIndex, EventsReader};
 use crate::body::{BodyStorageReader, BodyStorageWriter, TransactionIndex};
 use crate::db::table_types::Table;
 use crate::db::{DbError, KeyAlreadyExistsError};
 use crate::test_utils::{get_test_storage, get_test_storage_by_scope};
-use crate::{OffsetKind, StorageError, StorageScope, StorageWriter};
+use crate::{OffsetKind, StorageError, StorageReader, StorageScope, StorageWriter};
 
 #[tokio::test]
 async fn append_body() {
@@ -376,6 +388,49 @@ async fn revert_transactions() {
     );
 }
 
+fn l1_handler_body(nonce: u8) -> BlockBody {
+    BlockBody {
+        transactions: vec![Transaction::L1Handler(L1HandlerTransaction {
+            nonce: Nonce(felt!(nonce)),
+            ..Default::default()
+        })],
+        transaction_outputs: vec![TransactionOutput::L1Handler(
+            L1HandlerTransactionOutput::default(),
+        )],
+        transaction_hashes: vec![TransactionHash(felt!(nonce))],
+    }
+}
+
+#[test]
+fn l1_handler_nonce_index() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(BlockNumber(0), l1_handler_body(1))
+        .unwrap()
+        .append_body(BlockNumber(1), l1_handler_body(2))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    for (block_number, nonce) in [(0, 1_u8), (1, 2_u8)] {
+        assert_eq!(
+            txn.get_l1_handler_transaction_idx_by_nonce(&Nonce(felt!(nonce))).unwrap(),
+            Some(TransactionIndex(BlockNumber(block_number), TransactionOffsetInBlock(0)))
+        );
+    }
+    assert!(txn.get_l1_handler_transaction_idx_by_nonce(&Nonce(felt!(3_u8))).unwrap().is_none());
+    drop(txn);
+
+    // Reverting a block removes the nonces of its L1 handler transactions.
+    writer.begin_rw_txn().unwrap().revert_body(BlockNumber(1)).unwrap().0.commit().unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert!(txn.get_l1_handler_transaction_idx_by_nonce(&Nonce(felt!(2_u8))).unwrap().is_none());
+    assert!(txn.get_l1_handler_transaction_idx_by_nonce(&Nonce(felt!(1_u8))).unwrap().is_some());
+}
+
 fn append_2_bodies(writer: &mut StorageWriter) {
     writer
         .begin_rw_txn()
@@ -411,3 +466,106 @@ fn update_offset_table() {
         file_offset_table.get(&txn.txn, &OffsetKind::TransactionOutput).unwrap().unwrap()
     );
 }
+
+#[test]
+fn append_events_of_body_without_events() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let body = get_test_block(3, Some(2), None, None).body;
+    let events: Vec<Vec<Event>> =
+        body.transaction_outputs.iter().map(|tx_output| tx_output.events().to_vec()).collect();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body_without_events(BlockNumber(0), body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_event_marker().unwrap(), BlockNumber(0));
+    let tx_outputs = txn.get_block_transaction_outputs(BlockNumber(0)).unwrap().unwrap();
+    assert!(tx_outputs.iter().all(|tx_output| tx_output.events().is_empty()));
+    drop(txn);
+
+    // A body with events can't be appended before the events of the previous blocks.
+    let Err(err) = writer.begin_rw_txn().unwrap().append_body(BlockNumber(1), BlockBody::default())
+    else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(
+        err,
+        StorageError::MarkerMismatch { expected: BlockNumber(0), found: BlockNumber(1) }
+    );
+
+    // The events should be given for each of the block's transactions.
+    let Err(err) = writer.begin_rw_txn().unwrap().append_events(BlockNumber(0), vec![]) else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(
+        err,
+        StorageError::WrongNumberOfTransactionsEvents {
+            num_transactions: 3,
+            num_transactions_events: 0,
+            ..
+        }
+    );
+
+    let transaction_output_offset = |reader: &StorageReader| {
+        let txn = reader.begin_ro_txn().unwrap();
+        let file_offset_table = txn.txn.open_table(&txn.tables.file_offsets).unwrap();
+        file_offset_table.get(&txn.txn, &OffsetKind::TransactionOutput).unwrap().unwrap()
+    };
+    let offset_before_events = transaction_output_offset(&reader);
+    writer.begin_rw_txn().unwrap().append_events(BlockNumber(0), events).unwrap().commit().unwrap();
+    // The events are stored apart from the transaction outputs, which aren't written again.
+    assert_eq!(transaction_output_offset(&reader), offset_before_events);
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_event_marker().unwrap(), BlockNumber(1));
+    assert_eq!(
+        txn.get_block_transaction_outputs(BlockNumber(0)).unwrap().unwrap(),
+        body.transaction_outputs
+    );
+    let event_index = EventIndex(
+        TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(0)),
+        EventIndexInTransactionOutput(0),
+    );
+    assert_eq!(txn.iter_events(None, event_index, BlockNumber(0), None).unwrap().count(), 6);
+}
+
+#[test]
+fn append_events_of_non_existing_body_fails() {
+    let ((_, mut writer), _temp_dir) = get_test_storage();
+    let Err(err) = writer.begin_rw_txn().unwrap().append_events(BlockNumber(0), vec![]) else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(err, StorageError::EventsForNonExistingBody { block_number: BlockNumber(0) });
+}
+
+#[test]
+fn revert_body_without_events() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body_without_events(BlockNumber(0), BlockBody::default())
+        .unwrap()
+        .append_body_without_events(BlockNumber(1), BlockBody::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // Reverting a block whose events weren't appended doesn't advance the event marker.
+    writer.begin_rw_txn().unwrap().revert_body(BlockNumber(1)).unwrap().0.commit().unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_event_marker().unwrap(), BlockNumber(0));
+    drop(txn);
+
+    writer.begin_rw_txn().unwrap().append_events(BlockNumber(0), vec![]).unwrap().commit().unwrap();
+    writer.begin_rw_txn().unwrap().revert_body(BlockNumber(0)).unwrap().0.commit().unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(0));
+    assert_eq!(txn.get_event_marker().unwrap(), BlockNumber(0));
+}
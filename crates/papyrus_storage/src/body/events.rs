@@ -5,6 +5,8 @@
 //!
 //! # Example
 //! ```
+//! use std::collections::HashSet;
+//!
 //! use papyrus_storage::open_storage;
 //! use papyrus_storage::body::TransactionIndex;
 //! use papyrus_storage::body::events::{EventIndex, EventsReader};
@@ -13,7 +15,7 @@
 //! # use starknet_api::block::BlockNumber;
 //! use starknet_api::core::ContractAddress;
 //! use starknet_api::transaction::TransactionOffsetInBlock;
-//! use starknet_api::transaction::EventIndexInTransactionOutput;
+//! use starknet_api::transaction::{EventIndexInTransactionOutput, EventKey};
 //!
 //! # let dir_handle = tempfile::tempdir().unwrap();
 //! # let dir = dir_handle.path().to_path_buf();
@@ -34,21 +36,27 @@
 //!     EventIndexInTransactionOutput(0),
 //! );
 //! let txn = reader.begin_ro_txn()?; // The transaction must live longer than the iterator.
-//! let events_iterator = txn.iter_events(None, event_index, BlockNumber(0))?;
+//! let events_iterator = txn.iter_events(None, event_index, BlockNumber(0), None)?;
 //! for ((contract_address, event_index), event_content) in events_iterator {
 //!    // Do something with the event.
 //! }
 //! // iterate events from a specific contract.
-//! let contract_events_iterator = txn.iter_events(Some(ContractAddress::default()), event_index, BlockNumber(0))?;
+//! let contract_events_iterator = txn.iter_events(Some(ContractAddress::default()), event_index, BlockNumber(0), None)?;
 //! for ((contract_address, event_index), event_content) in contract_events_iterator {
 //!    // Do something with the event.
 //! }
+//! // iterate events whose first key is one of the given keys.
+//! let first_keys = HashSet::from([EventKey::default()]);
+//! let events_by_key_iterator = txn.iter_events(None, event_index, BlockNumber(0), Some(&first_keys))?;
+//! for ((contract_address, event_index), event_content) in events_by_key_iterator {
+//!    // Do something with the event.
+//! }
 //! # Ok::<(), papyrus_storage::StorageError>(())
 #[cfg(test)]
 #[path = "events_test.rs"]
 mod events_test;
 
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 
 use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockNumber;
@@ -57,15 +65,16 @@ use starknet_api::transaction::{
     Event,
     EventContent,
     EventIndexInTransactionOutput,
+    EventKey,
     TransactionOutput,
 };
 
-use super::TransactionMetadataTable;
-use crate::body::{EventsTableKey, TransactionIndex};
+use super::{get_transaction_output_with_events, TransactionEventsTable, TransactionMetadataTable};
+use crate::body::{EventsByKeyTableKey, EventsTableKey, TransactionIndex};
 use crate::db::serialization::{NoVersionValueWrapper, VersionZeroWrapper};
 use crate::db::table_types::{CommonPrefix, DbCursor, DbCursorTrait, NoValue, SimpleTable, Table};
 use crate::db::{DbTransaction, RO};
-use crate::{FileHandlers, StorageResult, StorageTxn, TransactionMetadata};
+use crate::{FileHandlers, MarkerKind, StorageResult, StorageTxn, TransactionMetadata};
 
 /// An identifier of an event.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize, PartialOrd, Ord)]
@@ -74,14 +83,18 @@ pub struct EventIndex(pub TransactionIndex, pub EventIndexInTransactionOutput);
 
 /// An interface for reading events.
 pub trait EventsReader<'txn, 'env> {
-    /// Returns an iterator over events, which is a wrapper of two iterators.
+    /// Returns an iterator over events, which is a wrapper of several iterators.
     /// If the address is none it iterates the events by the order of the event index,
     /// else, it iterated the events by the order of the contract addresses.
+    /// If first keys are given, only the events whose first key is one of them are returned. In
+    /// this case, if the events by key index covers the requested events, the events are iterated
+    /// by the order of the event index using the index.
     ///
     /// # Arguments
     /// * address - contract address to iterate over events was emitted by it.
     /// * event_index - event index to start iterate from it.
     /// * to_block_number - block number to stop iterate at it.
+    /// * first_keys - the keys that the first key of the events should be one of.
     ///
     /// # Errors
     /// Returns [`StorageError`](crate::StorageError) if there was an error.
@@ -90,6 +103,7 @@ pub trait EventsReader<'txn, 'env> {
         address: Option<ContractAddress>,
         event_index: EventIndex,
         to_block_number: BlockNumber,
+        first_keys: Option<&HashSet<EventKey>>,
     ) -> StorageResult<EventIter<'txn, 'env>>;
 }
 
@@ -100,7 +114,22 @@ impl<'txn, 'env> EventsReader<'txn, 'env> for StorageTxn<'env, RO> {
         optional_address: Option<ContractAddress>,
         event_index: EventIndex,
         to_block_number: BlockNumber,
+        first_keys: Option<&HashSet<EventKey>>,
     ) -> StorageResult<EventIter<'txn, 'env>> {
+        if let Some(first_keys) = first_keys {
+            if self.is_events_by_key_index_available(event_index.0.0)? {
+                return Ok(EventIter::ByEventKey(self.iter_events_by_key(
+                    optional_address,
+                    first_keys,
+                    event_index,
+                    to_block_number,
+                )?));
+            }
+            let event_iter =
+                self.iter_events(optional_address, event_index, to_block_number, None)?;
+            return Ok(EventIter::FilteredByFirstKey(Box::new(event_iter), first_keys.clone()));
+        }
+
         if let Some(address) = optional_address {
             return Ok(EventIter::ByContractAddress(
                 self.iter_events_by_contract_address((address, event_index))?,
@@ -115,14 +144,18 @@ impl<'txn, 'env> EventsReader<'txn, 'env> for StorageTxn<'env, RO> {
 // with the transaction hash. We can do it efficiently here because we anyway read the relevant
 // entry in the transaction_metadata table..
 #[allow(missing_docs)]
-/// A wrapper of two iterators [`EventIterByContractAddress`] and [`EventIterByEventIndex`].
+/// A wrapper of the iterators [`EventIterByContractAddress`], [`EventIterByEventIndex`] and
+/// [`EventIterByEventKey`].
 pub enum EventIter<'txn, 'env> {
     ByContractAddress(EventIterByContractAddress<'env, 'txn>),
-    ByEventIndex(EventIterByEventIndex<'txn>),
+    ByEventIndex(EventIterByEventIndex<'env, 'txn>),
+    ByEventKey(EventIterByEventKey<'env, 'txn>),
+    /// Returns only the events of the inner iterator whose first key is in the given set.
+    FilteredByFirstKey(Box<EventIter<'txn, 'env>>, HashSet<EventKey>),
 }
 
-/// This iterator is a wrapper of two iterators [`EventIterByContractAddress`]
-/// and [`EventIterByEventIndex`].
+/// This iterator is a wrapper of the iterators [`EventIterByContractAddress`],
+/// [`EventIterByEventIndex`] and [`EventIterByEventKey`].
 /// With this wrapper we can execute the same code, regardless the
 /// type of iteration used.
 impl Iterator for EventIter<'_, '_> {
@@ -132,6 +165,12 @@ impl Iterator for EventIter<'_, '_> {
         match self {
             EventIter::ByContractAddress(it) => it.next(),
             EventIter::ByEventIndex(it) => it.next(),
+            EventIter::ByEventKey(it) => it.next(),
+            EventIter::FilteredByFirstKey(it, first_keys) => {
+                return it.find(|(_, content)| {
+                    content.keys.first().is_some_and(|key| first_keys.contains(key))
+                });
+            }
         }
         .unwrap_or(None)
     }
@@ -150,6 +189,7 @@ pub struct EventIterByContractAddress<'env, 'txn> {
     events_queue: VecDeque<((ContractAddress, EventIndex), EventContent)>,
     cursor: EventsTableCursor<'txn>,
     transaction_metadata_table: TransactionMetadataTable<'env>,
+    transaction_events_table: TransactionEventsTable<'env>,
 }
 
 impl EventIterByContractAddress<'_, '_> {
@@ -168,9 +208,13 @@ impl EventIterByContractAddress<'_, '_> {
                 self.transaction_metadata_table.get(self.txn, &tx_index)?.unwrap_or_else(|| {
                     panic!("Transaction metadata not found for transaction index: {tx_index:?}")
                 });
-            let tx_output = self
-                .file_handles
-                .get_transaction_output_unchecked(tx_metadata.tx_output_location)?;
+            let tx_output = get_transaction_output_with_events(
+                self.txn,
+                self.file_handles,
+                &self.transaction_events_table,
+                tx_index,
+                tx_metadata.tx_output_location,
+            )?;
             // TODO(dvir): don't clone the events here.
             self.events_queue =
                 get_events_from_tx(tx_output.events().into(), tx_index, contract_address, 0);
@@ -185,15 +229,17 @@ impl EventIterByContractAddress<'_, '_> {
 /// That is, the events are iterated by the order they are emitted.
 /// First by the block number, then by the transaction offset in the block,
 /// and finally, by the event index in the transaction output.
-pub struct EventIterByEventIndex<'txn> {
+pub struct EventIterByEventIndex<'env, 'txn> {
+    txn: &'txn DbTransaction<'env, RO>,
     file_handlers: &'txn FileHandlers<RO>,
+    transaction_events_table: TransactionEventsTable<'env>,
     tx_current: Option<(TransactionIndex, TransactionOutput)>,
     tx_cursor: TransactionMetadataTableCursor<'txn>,
     event_index_in_tx_current: EventIndexInTransactionOutput,
     to_block_number: BlockNumber,
 }
 
-impl EventIterByEventIndex<'_> {
+impl EventIterByEventIndex<'_, '_> {
     /// Returns the next event. If there are no more events, returns None.
     ///
     /// # Errors
@@ -239,8 +285,13 @@ impl EventIterByEventIndex<'_> {
             };
             self.tx_current = Some((
                 tx_index,
-                self.file_handlers
-                    .get_transaction_output_unchecked(tx_metadata.tx_output_location)?,
+                get_transaction_output_with_events(
+                    self.txn,
+                    self.file_handlers,
+                    &self.transaction_events_table,
+                    tx_index,
+                    tx_metadata.tx_output_location,
+                )?,
             ));
             self.event_index_in_tx_current = EventIndexInTransactionOutput(0);
         }
@@ -249,10 +300,138 @@ impl EventIterByEventIndex<'_> {
     }
 }
 
+/// This iterator goes over the events whose first key is one of the given keys, in the order of
+/// the event index, using the events by key index.
+pub struct EventIterByEventKey<'env, 'txn> {
+    txn: &'txn DbTransaction<'env, RO>,
+    file_handlers: &'txn FileHandlers<RO>,
+    address: Option<ContractAddress>,
+    to_block_number: BlockNumber,
+    // For each of the keys, the next entry of the key in the events by key table, and a cursor
+    // that points to it. The entry is None if there are no more events with the key.
+    cursors: Vec<(Option<(EventsByKeyTableKey, ContractAddress)>, EventsByKeyTableCursor<'txn>)>,
+    // The output of the last transaction that had a returned event, since the events of the same
+    // transaction are usually returned one after the other.
+    tx_current: Option<(TransactionIndex, TransactionOutput)>,
+    transaction_metadata_table: TransactionMetadataTable<'env>,
+    transaction_events_table: TransactionEventsTable<'env>,
+}
+
+impl EventIterByEventKey<'_, '_> {
+    /// Returns the next event. If there are no more events, returns None.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`](crate::StorageError) if there was an error.
+    fn next(&mut self) -> StorageResult<Option<((ContractAddress, EventIndex), EventContent)>> {
+        loop {
+            // The entry with the smallest event index among the keys.
+            let Some((entry, cursor)) =
+                self.cursors.iter_mut().filter(|(entry, _)| entry.is_some()).min_by_key(
+                    |(entry, _)| entry.as_ref().map(|((_, event_index), _)| *event_index),
+                )
+            else {
+                return Ok(None);
+            };
+            let Some(((event_key, event_index), from_address)) = entry.take() else {
+                unreachable!("Only keys with a next entry are considered.");
+            };
+            if event_index.0.0 > self.to_block_number {
+                return Ok(None);
+            }
+            *entry = cursor.next()?.filter(|((next_event_key, _), _)| *next_event_key == event_key);
+            if self.address.is_some_and(|address| address != from_address) {
+                continue;
+            }
+
+            let EventIndex(tx_index, EventIndexInTransactionOutput(index_in_tx)) = event_index;
+            if self.tx_current.as_ref().map(|(current_tx_index, _)| *current_tx_index)
+                != Some(tx_index)
+            {
+                let tx_metadata = self
+                    .transaction_metadata_table
+                    .get(self.txn, &tx_index)?
+                    .unwrap_or_else(|| {
+                        panic!("Transaction metadata not found for transaction index: {tx_index:?}")
+                    });
+                let tx_output = get_transaction_output_with_events(
+                    self.txn,
+                    self.file_handlers,
+                    &self.transaction_events_table,
+                    tx_index,
+                    tx_metadata.tx_output_location,
+                )?;
+                self.tx_current = Some((tx_index, tx_output));
+            }
+            let (_, tx_output) = self.tx_current.as_ref().expect("tx_current was just set.");
+            let content = tx_output
+                .events()
+                .get(index_in_tx)
+                .unwrap_or_else(|| panic!("Event not found for event index: {event_index:?}"))
+                .content
+                .clone();
+            return Ok(Some(((from_address, event_index), content)));
+        }
+    }
+}
+
 impl<'txn, 'env> StorageTxn<'env, RO>
 where
     'env: 'txn,
 {
+    // Returns whether the events by key index holds all the events from the given block.
+    fn is_events_by_key_index_available(&self, block_number: BlockNumber) -> StorageResult<bool> {
+        if !self.index_events_by_key {
+            return Ok(false);
+        }
+        let markers_table = self.open_table(&self.tables.markers)?;
+        Ok(markers_table
+            .get(&self.txn, &MarkerKind::EventsByKeyStart)?
+            .is_some_and(|events_by_key_start| events_by_key_start <= block_number))
+    }
+
+    /// Returns an events iterator that iterates the events whose first key is one of the given
+    /// keys by event index, using the events by key index.
+    ///
+    /// # Arguments
+    /// * address - if given, only the events emitted by this address are returned.
+    /// * first_keys - the keys that the first key of the events should be one of.
+    /// * event_index - event index to start from the first event with an index greater or equals
+    ///   to.
+    /// * to_block_number - block number to stop iterate at it.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`](crate::StorageError) if there was an error.
+    fn iter_events_by_key(
+        &'env self,
+        address: Option<ContractAddress>,
+        first_keys: &HashSet<EventKey>,
+        event_index: EventIndex,
+        to_block_number: BlockNumber,
+    ) -> StorageResult<EventIterByEventKey<'env, 'txn>> {
+        let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
+        let transaction_events_table = self.open_table(&self.tables.transaction_events)?;
+        let events_by_key_table = self.open_table(&self.tables.events_by_key)?;
+        let mut cursors = Vec::with_capacity(first_keys.len());
+        for event_key in first_keys {
+            let mut cursor = events_by_key_table.cursor(&self.txn)?;
+            let entry = cursor
+                .lower_bound(&(event_key.clone(), event_index))?
+                .filter(|((got_event_key, _), _)| got_event_key == event_key);
+            cursors.push((entry, cursor));
+        }
+
+        Ok(EventIterByEventKey {
+            txn: &self.txn,
+            file_handlers: &self.file_handlers,
+            address,
+            to_block_number,
+            cursors,
+            tx_current: None,
+            transaction_metadata_table,
+            transaction_events_table,
+        })
+    }
+
     /// Returns an events iterator that iterates events by the events table key from the given key.
     ///
     /// # Arguments
@@ -265,6 +444,7 @@ where
         key: (ContractAddress, EventIndex),
     ) -> StorageResult<EventIterByContractAddress<'env, 'txn>> {
         let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
+        let transaction_events_table = self.open_table(&self.tables.transaction_events)?;
         let events_table = self.open_table(&self.tables.events)?;
         let mut cursor = events_table.cursor(&self.txn)?;
         let events_queue = if let Some((contract_address, tx_index)) =
@@ -274,9 +454,13 @@ where
                 transaction_metadata_table.get(&self.txn, &tx_index)?.unwrap_or_else(|| {
                     panic!("Transaction metadata not found for transaction index: {tx_index:?}")
                 });
-            let tx_output = self
-                .file_handlers
-                .get_transaction_output_unchecked(tx_metadata.tx_output_location)?;
+            let tx_output = get_transaction_output_with_events(
+                &self.txn,
+                &self.file_handlers,
+                &transaction_events_table,
+                tx_index,
+                tx_metadata.tx_output_location,
+            )?;
 
             // In case of we get tx_index different from the key, it means we need to start a new
             // transaction which means the first event.
@@ -300,6 +484,7 @@ where
             events_queue,
             cursor,
             transaction_metadata_table,
+            transaction_events_table,
         })
     }
 
@@ -316,21 +501,29 @@ where
         &'env self,
         event_index: EventIndex,
         to_block_number: BlockNumber,
-    ) -> StorageResult<EventIterByEventIndex<'txn>> {
+    ) -> StorageResult<EventIterByEventIndex<'env, 'txn>> {
         let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
+        let transaction_events_table = self.open_table(&self.tables.transaction_events)?;
         let mut tx_cursor = transaction_metadata_table.cursor(&self.txn)?;
         let first_txn_location = tx_cursor.lower_bound(&event_index.0)?;
         let first_relevant_transaction = match first_txn_location {
             None => None,
             Some((tx_index, tx_metadata)) => Some((
                 tx_index,
-                self.file_handlers
-                    .get_transaction_output_unchecked(tx_metadata.tx_output_location)?,
+                get_transaction_output_with_events(
+                    &self.txn,
+                    &self.file_handlers,
+                    &transaction_events_table,
+                    tx_index,
+                    tx_metadata.tx_output_location,
+                )?,
             )),
         };
 
         let mut it = EventIterByEventIndex {
+            txn: &self.txn,
             file_handlers: &self.file_handlers,
+            transaction_events_table,
             tx_current: first_relevant_transaction,
             tx_cursor,
             event_index_in_tx_current: event_index.1,
@@ -360,6 +553,9 @@ fn get_events_from_tx(
 /// A cursor of the events table.
 type EventsTableCursor<'txn> =
     DbCursor<'txn, RO, EventsTableKey, NoVersionValueWrapper<NoValue>, CommonPrefix>;
+/// A cursor of the events by key table.
+type EventsByKeyTableCursor<'txn> =
+    DbCursor<'txn, RO, EventsByKeyTableKey, NoVersionValueWrapper<ContractAddress>, CommonPrefix>;
 /// A cursor of the transaction outputs table.
 type TransactionMetadataTableCursor<'txn> =
     DbCursor<'txn, RO, TransactionIndex, VersionZeroWrapper<TransactionMetadata>, SimpleTable>;
@@ -1,22 +1,29 @@
+use std::collections::HashSet;
 use std::vec;
 
 use assert_matches::assert_matches;
 use papyrus_test_utils::get_test_block;
 use pretty_assertions::assert_eq;
-use starknet_api::block::BlockNumber;
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::ContractAddress;
 use starknet_api::transaction::{
     Event,
     EventContent,
     EventData,
     EventIndexInTransactionOutput,
+    EventKey,
+    TransactionHash,
     TransactionOffsetInBlock,
 };
+use starknet_api::{contract_address, felt};
+use test_case::test_case;
 
-use crate::body::events::{get_events_from_tx, EventIndex, EventsReader};
+use crate::body::events::{get_events_from_tx, EventIndex, EventIter, EventsReader};
 use crate::body::{BodyStorageWriter, TransactionIndex};
-use crate::db::table_types::Table;
+use crate::db::table_types::{DbCursorTrait, Table};
 use crate::header::HeaderStorageWriter;
-use crate::test_utils::get_test_storage;
+use crate::open_storage;
+use crate::test_utils::{get_test_config, get_test_storage};
 
 #[test]
 fn iter_events_by_key() {
@@ -64,7 +71,7 @@ fn iter_events_by_key() {
         TransactionIndex(block_number, TransactionOffsetInBlock(0)),
         EventIndexInTransactionOutput(0),
     );
-    let event_iter = txn.iter_events(Some(ca1), event_index, block_number).unwrap();
+    let event_iter = txn.iter_events(Some(ca1), event_index, block_number, None).unwrap();
     assert_eq!(event_iter.into_iter().collect::<Vec<_>>(), all_events);
 
     // Start from not existing event index.
@@ -72,7 +79,7 @@ fn iter_events_by_key() {
         TransactionIndex(block_number, TransactionOffsetInBlock(5)),
         EventIndexInTransactionOutput(0),
     );
-    let event_iter = txn.iter_events(Some(ca2), event_index, block_number).unwrap();
+    let event_iter = txn.iter_events(Some(ca2), event_index, block_number, None).unwrap();
     assert_eq!(event_iter.into_iter().collect::<Vec<_>>(), vec![]);
 
     // TODO(dvir): add non random test that checks the iterator when there are no more relevant
@@ -86,7 +93,7 @@ fn iter_events_by_key() {
     } else {
         events_ca1.iter().cloned().chain(events_ca2.iter().cloned()).collect::<Vec<_>>()
     };
-    let event_iter = txn.iter_events(Some(ca1), event_index, block_number).unwrap();
+    let event_iter = txn.iter_events(Some(ca1), event_index, block_number, None).unwrap();
     assert_eq!(event_iter.into_iter().collect::<Vec<_>>(), expected_events);
 }
 
@@ -125,7 +132,7 @@ fn iter_events_by_index() {
         EventIndexInTransactionOutput(2),
     );
     let txn = storage_reader.begin_ro_txn().unwrap();
-    let event_iter = txn.iter_events(None, event_index, block_number).unwrap();
+    let event_iter = txn.iter_events(None, event_index, block_number, None).unwrap();
     assert_eq!(event_iter.into_iter().collect::<Vec<_>>(), emitted_events);
 }
 
@@ -154,7 +161,7 @@ fn revert_events() {
         storage_reader
             .begin_ro_txn()
             .unwrap()
-            .iter_events(None, event_index, block_number)
+            .iter_events(None, event_index, block_number, None)
             .unwrap()
             .last()
             .is_some()
@@ -188,7 +195,7 @@ fn revert_events() {
         storage_reader
             .begin_ro_txn()
             .unwrap()
-            .iter_events(None, event_index, block_number)
+            .iter_events(None, event_index, block_number, None)
             .unwrap()
             .last()
             .is_none()
@@ -253,3 +260,132 @@ fn get_events_from_tx_test() {
     assert_eq!(get_events_from_tx(events.clone(), tx_index, ca1, 3), vec![]);
     assert_eq!(get_events_from_tx(events.clone(), tx_index, ca2, 3), vec![]);
 }
+
+#[test_case(true, None; "with index")]
+#[test_case(true, Some(contract_address!("0x1")); "with index and address")]
+#[test_case(false, None; "without index")]
+#[test_case(false, Some(contract_address!("0x1")); "without index and with address")]
+fn iter_events_by_first_key(index_events_by_key: bool, address: Option<ContractAddress>) {
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.index_events_by_key = index_events_by_key;
+    let (storage_reader, mut storage_writer) = open_storage(config).unwrap();
+    let (key1, key2, key3) =
+        (EventKey(felt!("0x1")), EventKey(felt!("0x2")), EventKey(felt!("0x3")));
+    let block = get_test_block(
+        4,
+        Some(5),
+        Some(vec![contract_address!("0x1"), contract_address!("0x2")]),
+        Some(vec![vec![key1.clone()], vec![key2.clone(), key1.clone()], vec![key3.clone()]]),
+    );
+    let block_number = block.header.block_header_without_hash.block_number;
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let first_keys = HashSet::from([key1, key2]);
+    let start_event_index = EventIndex(
+        TransactionIndex(block_number, TransactionOffsetInBlock(1)),
+        EventIndexInTransactionOutput(2),
+    );
+    let mut expected_events = vec![];
+    for (tx_i, tx_output) in block.body.transaction_outputs.iter().enumerate() {
+        for (event_i, event) in tx_output.events().iter().enumerate() {
+            let event_index = EventIndex(
+                TransactionIndex(block_number, TransactionOffsetInBlock(tx_i)),
+                EventIndexInTransactionOutput(event_i),
+            );
+            if event_index >= start_event_index
+                && first_keys.contains(&event.content.keys[0])
+                && address.unwrap_or(event.from_address) == event.from_address
+            {
+                expected_events.push(((event.from_address, event_index), event.content.clone()));
+            }
+        }
+    }
+    assert!(!expected_events.is_empty());
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let event_iter =
+        txn.iter_events(address, start_event_index, block_number, Some(&first_keys)).unwrap();
+    assert_eq!(matches!(event_iter, EventIter::ByEventKey(_)), index_events_by_key);
+    // Without the index, the iterator by contract address continues to the events of the next
+    // addresses.
+    let events = event_iter
+        .take_while(|((from_address, _), _)| address.unwrap_or(*from_address) == *from_address)
+        .collect::<Vec<_>>();
+    assert_eq!(events, expected_events);
+}
+
+// Blocks that were written while indexing was off aren't in the index, so the index is used only
+// for the blocks written after indexing was turned back on.
+#[test]
+fn events_by_key_index_after_indexing_was_off() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    let key = EventKey(felt!("0x1"));
+    let first_keys = HashSet::from([key.clone()]);
+    for (i, index_events_by_key) in [true, false, true].into_iter().enumerate() {
+        config.index_events_by_key = index_events_by_key;
+        let (_reader, mut writer) = open_storage(config.clone()).unwrap();
+        let mut block = get_test_block(1, Some(1), None, Some(vec![vec![key.clone()]]));
+        let block_number = BlockNumber(i.try_into().unwrap());
+        block.header.block_header_without_hash.block_number = block_number;
+        block.header.block_hash = BlockHash(felt!(block_number.0));
+        block.body.transaction_hashes = vec![TransactionHash(felt!(block_number.0))];
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(block_number, &block.header)
+            .unwrap()
+            .append_body(block_number, block.body)
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+
+    let (reader, _writer) = open_storage(config).unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    for (block_number, uses_index) in [(0, false), (1, false), (2, true)] {
+        let event_index = EventIndex(
+            TransactionIndex(BlockNumber(block_number), TransactionOffsetInBlock(0)),
+            EventIndexInTransactionOutput(0),
+        );
+        let event_iter =
+            txn.iter_events(None, event_index, BlockNumber(2), Some(&first_keys)).unwrap();
+        assert_eq!(matches!(event_iter, EventIter::ByEventKey(_)), uses_index);
+    }
+}
+
+#[test]
+fn revert_events_by_key() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.index_events_by_key = true;
+    let (storage_reader, mut storage_writer) = open_storage(config).unwrap();
+    let block = get_test_block(2, Some(5), None, None);
+    let block_number = block.header.block_header_without_hash.block_number;
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let events_by_key_table = txn.txn.open_table(&txn.tables.events_by_key).unwrap();
+    assert!(events_by_key_table.cursor(&txn.txn).unwrap().next().unwrap().is_some());
+    drop(txn);
+
+    storage_writer.begin_rw_txn().unwrap().revert_body(block_number).unwrap().0.commit().unwrap();
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let events_by_key_table = txn.txn.open_table(&txn.tables.events_by_key).unwrap();
+    assert!(events_by_key_table.cursor(&txn.txn).unwrap().next().unwrap().is_none());
+}
@@ -48,8 +48,11 @@ use std::fmt::Debug;
 use papyrus_proc_macros::latency_histogram;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockBody, BlockNumber};
-use starknet_api::core::ContractAddress;
+use starknet_api::core::{ContractAddress, Nonce};
 use starknet_api::transaction::{
+    Event,
+    EventIndexInTransactionOutput,
+    EventKey,
     Transaction,
     TransactionHash,
     TransactionOffsetInBlock,
@@ -57,9 +60,12 @@ use starknet_api::transaction::{
 };
 use tracing::debug;
 
+use crate::body::events::EventIndex;
 use crate::db::serialization::{NoVersionValueWrapper, VersionZeroWrapper};
 use crate::db::table_types::{CommonPrefix, DbCursorTrait, NoValue, SimpleTable, Table};
 use crate::db::{DbTransaction, TableHandle, TransactionKind, RW};
+use crate::mmap_file::LocationInFile;
+use crate::pruning::{verify_block_not_pruned, HistoryPruningStorageReader};
 use crate::{
     FileHandlers,
     MarkerKind,
@@ -78,9 +84,16 @@ type TransactionMetadataTable<'env> =
     TableHandle<'env, TransactionIndex, VersionZeroWrapper<TransactionMetadata>, SimpleTable>;
 type TransactionHashToIdxTable<'env> =
     TableHandle<'env, TransactionHash, NoVersionValueWrapper<TransactionIndex>, SimpleTable>;
+pub(crate) type L1HandlerNonceToIdxTable<'env> =
+    TableHandle<'env, Nonce, NoVersionValueWrapper<TransactionIndex>, SimpleTable>;
 type EventsTableKey = (ContractAddress, TransactionIndex);
 type EventsTable<'env> =
     TableHandle<'env, EventsTableKey, NoVersionValueWrapper<NoValue>, CommonPrefix>;
+pub(crate) type EventsByKeyTableKey = (EventKey, EventIndex);
+pub(crate) type EventsByKeyTable<'env> =
+    TableHandle<'env, EventsByKeyTableKey, NoVersionValueWrapper<ContractAddress>, CommonPrefix>;
+pub(crate) type TransactionEventsTable<'env> =
+    TableHandle<'env, TransactionIndex, VersionZeroWrapper<Vec<Event>>, SimpleTable>;
 
 /// The index of a transaction in a block.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize, PartialOrd, Ord)]
@@ -92,6 +105,10 @@ pub trait BodyStorageReader {
     /// The body marker is the first block number that doesn't exist yet.
     fn get_body_marker(&self) -> StorageResult<BlockNumber>;
 
+    /// The event marker is the first block number whose events don't exist yet. It's smaller than
+    /// the body marker if some bodies were appended without their events.
+    fn get_event_marker(&self) -> StorageResult<BlockNumber>;
+
     /// Returns the transaction and its execution status at the given index.
     fn get_transaction(
         &self,
@@ -116,6 +133,13 @@ pub trait BodyStorageReader {
         tx_index: &TransactionIndex,
     ) -> StorageResult<Option<TransactionHash>>;
 
+    /// Returns the index of the L1 handler transaction of the L1 to L2 message with the given
+    /// nonce.
+    fn get_l1_handler_transaction_idx_by_nonce(
+        &self,
+        nonce: &Nonce,
+    ) -> StorageResult<Option<TransactionIndex>>;
+
     /// Returns the transactions and their execution status of the block with the given number.
     fn get_block_transactions(
         &self,
@@ -155,6 +179,23 @@ where
     // TODO(yair): make this work without consuming the body.
     fn append_body(self, block_number: BlockNumber, block_body: BlockBody) -> StorageResult<Self>;
 
+    /// Appends a block body to the storage without advancing the event marker. The events in the
+    /// transaction outputs are dropped, and should be appended later with
+    /// [`append_events`](BodyStorageWriter::append_events).
+    fn append_body_without_events(
+        self,
+        block_number: BlockNumber,
+        block_body: BlockBody,
+    ) -> StorageResult<Self>;
+
+    /// Appends the events of a block whose body was appended without events. The events are given
+    /// per transaction, in the order of the block's transactions.
+    fn append_events(
+        self,
+        block_number: BlockNumber,
+        events: Vec<Vec<Event>>,
+    ) -> StorageResult<Self>;
+
     /// Removes a block body from the storage and returns the removed data.
     fn revert_body(
         self,
@@ -168,6 +209,11 @@ impl<Mode: TransactionKind> BodyStorageReader for StorageTxn<'_, Mode> {
         Ok(markers_table.get(&self.txn, &MarkerKind::Body)?.unwrap_or_default())
     }
 
+    fn get_event_marker(&self) -> StorageResult<BlockNumber> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        Ok(markers_table.get(&self.txn, &MarkerKind::Event)?.unwrap_or_default())
+    }
+
     // TODO(dvir): add option to get transaction with its hash.
     fn get_transaction(
         &self,
@@ -191,8 +237,14 @@ impl<Mode: TransactionKind> BodyStorageReader for StorageTxn<'_, Mode> {
         else {
             return Ok(None);
         };
-        let transaction_output =
-            self.file_handlers.get_transaction_output_unchecked(tx_metadata.tx_output_location)?;
+        let transaction_events_table = self.open_table(&self.tables.transaction_events)?;
+        let transaction_output = get_transaction_output_with_events(
+            &self.txn,
+            &self.file_handlers,
+            &transaction_events_table,
+            transaction_index,
+            tx_metadata.tx_output_location,
+        )?;
         Ok(Some(transaction_output))
     }
 
@@ -217,6 +269,15 @@ impl<Mode: TransactionKind> BodyStorageReader for StorageTxn<'_, Mode> {
         Ok(Some(tx_metadata.tx_hash))
     }
 
+    fn get_l1_handler_transaction_idx_by_nonce(
+        &self,
+        nonce: &Nonce,
+    ) -> StorageResult<Option<TransactionIndex>> {
+        let l1_handler_nonce_to_idx_table =
+            self.open_table(&self.tables.l1_handler_nonce_to_idx)?;
+        Ok(l1_handler_nonce_to_idx_table.get(&self.txn, nonce)?)
+    }
+
     fn get_block_transactions(
         &self,
         block_number: BlockNumber,
@@ -247,7 +308,9 @@ impl<Mode: TransactionKind> BodyStorageReader for StorageTxn<'_, Mode> {
     ) -> StorageResult<Option<usize>> {
         // After this condition, we know that the block exists, so if something goes wrong is only
         // because there are no transactions in it.
-        if self.get_body_marker()? <= block_number {
+        if self.get_body_marker()? <= block_number
+            || block_number < self.get_history_pruning_marker()?
+        {
             return Ok(None);
         }
 
@@ -277,9 +340,11 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
         &self,
         block_number: BlockNumber,
         transaction_metadata_table: TransactionMetadataTable<'env>,
-        tx_metadata_to_tx_object: fn(TransactionMetadata, &FileHandlers<Mode>) -> StorageResult<T>,
+        tx_metadata_to_tx_object: impl Fn(TransactionIndex, TransactionMetadata) -> StorageResult<T>,
     ) -> StorageResult<Option<Vec<T>>> {
-        if self.get_body_marker()? <= block_number {
+        if self.get_body_marker()? <= block_number
+            || block_number < self.get_history_pruning_marker()?
+        {
             return Ok(None);
         }
         let mut cursor = transaction_metadata_table.cursor(&self.txn)?;
@@ -289,11 +354,11 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
         // TODO(dvir): consider initializing with capacity based on the get_block_transactions_count
         // function.
         let mut res = Vec::new();
-        while let Some((TransactionIndex(current_block_number, _), tx_metadata)) = current {
-            if current_block_number != block_number {
+        while let Some((tx_index, tx_metadata)) = current {
+            if tx_index.0 != block_number {
                 break;
             }
-            let tx_output = tx_metadata_to_tx_object(tx_metadata, &self.file_handlers)?;
+            let tx_output = tx_metadata_to_tx_object(tx_index, tx_metadata)?;
             res.push(tx_output);
             current = cursor.next()?;
         }
@@ -305,11 +370,18 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
         block_number: BlockNumber,
         transaction_metadata_table: TransactionMetadataTable<'env>,
     ) -> StorageResult<Option<Vec<TransactionOutput>>> {
+        let transaction_events_table = self.open_table(&self.tables.transaction_events)?;
         self.get_vector_of_transaction_objects(
             block_number,
             transaction_metadata_table,
-            |tx_metadata, file_handlers| {
-                file_handlers.get_transaction_output_unchecked(tx_metadata.tx_output_location)
+            |tx_index, tx_metadata| {
+                get_transaction_output_with_events(
+                    &self.txn,
+                    &self.file_handlers,
+                    &transaction_events_table,
+                    tx_index,
+                    tx_metadata.tx_output_location,
+                )
             },
         )
     }
@@ -322,8 +394,8 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
         self.get_vector_of_transaction_objects(
             block_number,
             transaction_metadata_table,
-            |tx_metadata, file_handlers| {
-                file_handlers.get_transaction_unchecked(tx_metadata.tx_location)
+            |_tx_index, tx_metadata| {
+                self.file_handlers.get_transaction_unchecked(tx_metadata.tx_location)
             },
         )
     }
@@ -336,7 +408,7 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
         self.get_vector_of_transaction_objects(
             block_number,
             transaction_metadata_table,
-            |tx_metadata, _file_handlers| Ok(tx_metadata.tx_hash),
+            |_tx_index, tx_metadata| Ok(tx_metadata.tx_hash),
         )
     }
 }
@@ -344,26 +416,70 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
 impl BodyStorageWriter for StorageTxn<'_, RW> {
     #[latency_histogram("storage_append_body_latency_seconds", false)]
     fn append_body(self, block_number: BlockNumber, block_body: BlockBody) -> StorageResult<Self> {
-        let markers_table = self.open_table(&self.tables.markers)?;
-        update_marker(&self.txn, &markers_table, block_number)?;
+        self.write_body(block_number, block_body, true)
+    }
 
-        if self.scope != StorageScope::StateOnly {
-            let events_table = self.open_table(&self.tables.events)?;
-            let transaction_hash_to_idx_table =
-                self.open_table(&self.tables.transaction_hash_to_idx)?;
-            let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
-            let file_offset_table = self.txn.open_table(&self.tables.file_offsets)?;
+    fn append_body_without_events(
+        self,
+        block_number: BlockNumber,
+        block_body: BlockBody,
+    ) -> StorageResult<Self> {
+        self.write_body(block_number, block_body, false)
+    }
 
-            write_transactions(
-                &block_body,
-                &self.txn,
-                &self.file_handlers,
-                &file_offset_table,
-                &transaction_hash_to_idx_table,
-                &transaction_metadata_table,
-                &events_table,
+    fn append_events(
+        self,
+        block_number: BlockNumber,
+        events: Vec<Vec<Event>>,
+    ) -> StorageResult<Self> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        let event_marker = markers_table.get(&self.txn, &MarkerKind::Event)?.unwrap_or_default();
+        if event_marker != block_number {
+            return Err(StorageError::MarkerMismatch {
+                expected: event_marker,
+                found: block_number,
+            });
+        };
+        if self.get_body_marker()? <= block_number {
+            return Err(StorageError::EventsForNonExistingBody { block_number });
+        }
+        markers_table.upsert(&self.txn, &MarkerKind::Event, &block_number.unchecked_next())?;
+
+        if self.scope == StorageScope::StateOnly {
+            return Ok(self);
+        }
+        let num_transactions = self.get_block_transactions_count(block_number)?.unwrap_or_default();
+        if num_transactions != events.len() {
+            return Err(StorageError::WrongNumberOfTransactionsEvents {
                 block_number,
-            )?;
+                num_transactions,
+                num_transactions_events: events.len(),
+            });
+        }
+        let events_table = self.open_table(&self.tables.events)?;
+        let transaction_events_table = self.open_table(&self.tables.transaction_events)?;
+        let events_by_key_table = self.open_events_by_key_table(&markers_table, block_number)?;
+
+        // The transaction outputs were written without their events, so the events are stored
+        // apart from them instead of writing the outputs again.
+        for (index, transaction_events) in events.into_iter().enumerate() {
+            let transaction_index = TransactionIndex(block_number, TransactionOffsetInBlock(index));
+            write_events(&transaction_events, &self.txn, &events_table, transaction_index)?;
+            if let Some(events_by_key_table) = events_by_key_table.as_ref() {
+                write_events_by_key(
+                    &transaction_events,
+                    &self.txn,
+                    events_by_key_table,
+                    transaction_index,
+                )?;
+            }
+            if !transaction_events.is_empty() {
+                transaction_events_table.insert(
+                    &self.txn,
+                    &transaction_index,
+                    &transaction_events,
+                )?;
+            }
         }
 
         Ok(self)
@@ -388,6 +504,7 @@ impl BodyStorageWriter for StorageTxn<'_, RW> {
             );
             return Ok((self, None));
         }
+        verify_block_not_pruned(&self, block_number)?;
 
         let reverted_block_body = 'reverted_block_body: {
             if self.scope == StorageScope::StateOnly {
@@ -397,7 +514,11 @@ impl BodyStorageWriter for StorageTxn<'_, RW> {
             let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
             let transaction_hash_to_idx_table =
                 self.open_table(&self.tables.transaction_hash_to_idx)?;
+            let l1_handler_nonce_to_idx_table =
+                self.open_table(&self.tables.l1_handler_nonce_to_idx)?;
             let events_table = self.open_table(&self.tables.events)?;
+            let events_by_key_table = self.open_table(&self.tables.events_by_key)?;
+            let transaction_events_table = self.open_table(&self.tables.transaction_events)?;
 
             let transactions = self
                 .get_block_transactions(block_number)?
@@ -410,14 +531,20 @@ impl BodyStorageWriter for StorageTxn<'_, RW> {
                 .unwrap_or_else(|| panic!("Missing transaction hashes for block {block_number}."));
 
             // Delete the transactions data.
-            for (offset, (tx_hash, tx_output)) in
-                transaction_hashes.iter().zip(transaction_outputs.iter()).enumerate()
+            for (offset, ((tx, tx_hash), tx_output)) in transactions
+                .iter()
+                .zip(transaction_hashes.iter())
+                .zip(transaction_outputs.iter())
+                .enumerate()
             {
                 let tx_index = TransactionIndex(block_number, TransactionOffsetInBlock(offset));
 
                 for event in tx_output.events().iter() {
                     events_table.delete(&self.txn, &(event.from_address, tx_index))?;
                 }
+                delete_events_by_key(tx_output, &self.txn, &events_by_key_table, tx_index)?;
+                transaction_events_table.delete(&self.txn, &tx_index)?;
+                delete_l1_handler_nonce(tx, &self.txn, &l1_handler_nonce_to_idx_table, tx_index)?;
                 transaction_hash_to_idx_table.delete(&self.txn, tx_hash)?;
                 transaction_metadata_table.delete(&self.txn, &tx_index)?;
             }
@@ -425,11 +552,76 @@ impl BodyStorageWriter for StorageTxn<'_, RW> {
         };
 
         markers_table.upsert(&self.txn, &MarkerKind::Body, &block_number)?;
-        markers_table.upsert(&self.txn, &MarkerKind::Event, &block_number)?;
+        // The events of the reverted block might not have been appended yet.
+        let event_marker = markers_table.get(&self.txn, &MarkerKind::Event)?.unwrap_or_default();
+        markers_table.upsert(&self.txn, &MarkerKind::Event, &event_marker.min(block_number))?;
         Ok((self, reverted_block_body))
     }
 }
 
+impl StorageTxn<'_, RW> {
+    fn write_body(
+        self,
+        block_number: BlockNumber,
+        mut block_body: BlockBody,
+        with_events: bool,
+    ) -> StorageResult<Self> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        update_marker(&self.txn, &markers_table, block_number, with_events)?;
+
+        if self.scope != StorageScope::StateOnly {
+            if !with_events {
+                for tx_output in block_body.transaction_outputs.iter_mut() {
+                    set_events(tx_output, Vec::new());
+                }
+            }
+            let events_table = self.open_table(&self.tables.events)?;
+            let transaction_hash_to_idx_table =
+                self.open_table(&self.tables.transaction_hash_to_idx)?;
+            let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
+            let l1_handler_nonce_to_idx_table =
+                self.open_table(&self.tables.l1_handler_nonce_to_idx)?;
+            let file_offset_table = self.txn.open_table(&self.tables.file_offsets)?;
+            let events_by_key_table =
+                self.open_events_by_key_table(&markers_table, block_number)?;
+
+            write_transactions(
+                &block_body,
+                &self.txn,
+                &self.file_handlers,
+                &file_offset_table,
+                &transaction_hash_to_idx_table,
+                &transaction_metadata_table,
+                &l1_handler_nonce_to_idx_table,
+                &events_table,
+                events_by_key_table.as_ref(),
+                block_number,
+            )?;
+        }
+
+        Ok(self)
+    }
+
+    // Returns the events by key table if events are indexed by their keys, and marks the given
+    // block as the first indexed block if no block was indexed yet. The index must hold all the
+    // events from its first block, so writing a block without indexing it drops the index's first
+    // block, and the index starts over once indexing is turned back on.
+    fn open_events_by_key_table<'txn>(
+        &'txn self,
+        markers_table: &'txn MarkersTable<'txn>,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<EventsByKeyTable<'txn>>> {
+        if !self.index_events_by_key {
+            markers_table.delete(&self.txn, &MarkerKind::EventsByKeyStart)?;
+            return Ok(None);
+        }
+        if markers_table.get(&self.txn, &MarkerKind::EventsByKeyStart)?.is_none() {
+            markers_table.insert(&self.txn, &MarkerKind::EventsByKeyStart, &block_number)?;
+        }
+        Ok(Some(self.open_table(&self.tables.events_by_key)?))
+    }
+}
+
 // TODO(dvir): consider enforcing that the block_body transactions, transaction_outputs and
 // transaction_hashes to be the same size.
 #[allow(clippy::too_many_arguments)]
@@ -440,7 +632,9 @@ fn write_transactions<'env>(
     file_offset_table: &'env FileOffsetsTable<'env>,
     transaction_hash_to_idx_table: &'env TransactionHashToIdxTable<'env>,
     transaction_metadata_table: &'env TransactionMetadataTable<'env>,
+    l1_handler_nonce_to_idx_table: &'env L1HandlerNonceToIdxTable<'env>,
     events_table: &'env EventsTable<'env>,
+    events_by_key_table: Option<&'env EventsByKeyTable<'env>>,
     block_number: BlockNumber,
 ) -> StorageResult<()> {
     for (index, ((tx, tx_output), tx_hash)) in block_body
@@ -454,8 +648,16 @@ fn write_transactions<'env>(
         let transaction_index = TransactionIndex(block_number, tx_offset_in_block);
         let tx_location = file_handlers.append_transaction(tx);
         let tx_output_location = file_handlers.append_transaction_output(tx_output);
-        write_events(tx_output, txn, events_table, transaction_index)?;
+        write_events(tx_output.events(), txn, events_table, transaction_index)?;
+        if let Some(events_by_key_table) = events_by_key_table {
+            write_events_by_key(tx_output.events(), txn, events_by_key_table, transaction_index)?;
+        }
         transaction_hash_to_idx_table.insert(txn, tx_hash, &transaction_index)?;
+        if let Transaction::L1Handler(l1_handler_tx) = tx {
+            // Nonces aren't guaranteed to be unique (e.g., in transactions from before messages had
+            // nonces), so a nonce is indexed to its latest transaction.
+            l1_handler_nonce_to_idx_table.upsert(txn, &l1_handler_tx.nonce, &transaction_index)?;
+        }
         transaction_metadata_table.append(
             txn,
             &transaction_index,
@@ -478,14 +680,14 @@ fn write_transactions<'env>(
 
 // This function assumes that the `transaction_index` is the last index used to call it.
 fn write_events<'env>(
-    tx_output: &TransactionOutput,
+    events: &[Event],
     txn: &DbTransaction<'env, RW>,
     events_table: &'env EventsTable<'env>,
     transaction_index: TransactionIndex,
 ) -> StorageResult<()> {
     let mut contract_addresses_set = HashSet::new();
 
-    for event in tx_output.events().iter() {
+    for event in events {
         contract_addresses_set.insert(event.from_address);
     }
 
@@ -498,23 +700,111 @@ fn write_events<'env>(
     Ok(())
 }
 
+// This function assumes that the `transaction_index` is the last index used to call it.
+fn write_events_by_key<'env>(
+    events: &[Event],
+    txn: &DbTransaction<'env, RW>,
+    events_by_key_table: &'env EventsByKeyTable<'env>,
+    transaction_index: TransactionIndex,
+) -> StorageResult<()> {
+    for (index, event) in events.iter().enumerate() {
+        let Some(first_key) = event.content.keys.first() else {
+            continue;
+        };
+        let key = (
+            first_key.clone(),
+            EventIndex(transaction_index, EventIndexInTransactionOutput(index)),
+        );
+        events_by_key_table.append_greater_sub_key(txn, &key, &event.from_address)?;
+    }
+    Ok(())
+}
+
+// Deletes the entries of the events of the given transaction from the events by key table. Does
+// nothing for events that weren't indexed.
+pub(crate) fn delete_events_by_key<'env>(
+    tx_output: &TransactionOutput,
+    txn: &DbTransaction<'env, RW>,
+    events_by_key_table: &'env EventsByKeyTable<'env>,
+    transaction_index: TransactionIndex,
+) -> StorageResult<()> {
+    for (index, event) in tx_output.events().iter().enumerate() {
+        if let Some(first_key) = event.content.keys.first() {
+            let event_index = EventIndex(transaction_index, EventIndexInTransactionOutput(index));
+            events_by_key_table.delete(txn, &(first_key.clone(), event_index))?;
+        }
+    }
+    Ok(())
+}
+
+// Deletes the entry of the given transaction from the L1 handler nonce index, if the transaction is
+// an L1 handler transaction and its nonce is indexed to it.
+pub(crate) fn delete_l1_handler_nonce<'env>(
+    tx: &Transaction,
+    txn: &DbTransaction<'env, RW>,
+    l1_handler_nonce_to_idx_table: &'env L1HandlerNonceToIdxTable<'env>,
+    transaction_index: TransactionIndex,
+) -> StorageResult<()> {
+    let Transaction::L1Handler(l1_handler_tx) = tx else {
+        return Ok(());
+    };
+    if l1_handler_nonce_to_idx_table.get(txn, &l1_handler_tx.nonce)? == Some(transaction_index) {
+        l1_handler_nonce_to_idx_table.delete(txn, &l1_handler_tx.nonce)?;
+    }
+    Ok(())
+}
+
 fn update_marker<'env>(
     txn: &DbTransaction<'env, RW>,
     markers_table: &'env MarkersTable<'env>,
     block_number: BlockNumber,
+    with_events: bool,
 ) -> StorageResult<()> {
     // Make sure marker is consistent.
     let body_marker = markers_table.get(txn, &MarkerKind::Body)?.unwrap_or_default();
     if body_marker != block_number {
         return Err(StorageError::MarkerMismatch { expected: body_marker, found: block_number });
     };
-    let event_marker = markers_table.get(txn, &MarkerKind::Event)?.unwrap_or_default();
-    if event_marker != block_number {
-        return Err(StorageError::MarkerMismatch { expected: event_marker, found: block_number });
-    };
+    if with_events {
+        let event_marker = markers_table.get(txn, &MarkerKind::Event)?.unwrap_or_default();
+        if event_marker != block_number {
+            return Err(StorageError::MarkerMismatch {
+                expected: event_marker,
+                found: block_number,
+            });
+        };
+    }
 
     // Advance marker.
     markers_table.upsert(txn, &MarkerKind::Body, &block_number.unchecked_next())?;
-    markers_table.upsert(txn, &MarkerKind::Event, &block_number.unchecked_next())?;
+    if with_events {
+        markers_table.upsert(txn, &MarkerKind::Event, &block_number.unchecked_next())?;
+    }
     Ok(())
 }
+
+// Reads the output of the given transaction along with its events, which are stored apart from the
+// output if they were appended after the body of its block.
+pub(crate) fn get_transaction_output_with_events<Mode: TransactionKind>(
+    txn: &DbTransaction<'_, Mode>,
+    file_handlers: &FileHandlers<Mode>,
+    transaction_events_table: &TransactionEventsTable<'_>,
+    transaction_index: TransactionIndex,
+    tx_output_location: LocationInFile,
+) -> StorageResult<TransactionOutput> {
+    let mut tx_output = file_handlers.get_transaction_output_unchecked(tx_output_location)?;
+    if let Some(events) = transaction_events_table.get(txn, &transaction_index)? {
+        set_events(&mut tx_output, events);
+    }
+    Ok(tx_output)
+}
+
+fn set_events(tx_output: &mut TransactionOutput, events: Vec<Event>) {
+    match tx_output {
+        TransactionOutput::Declare(output) => output.events = events,
+        TransactionOutput::Deploy(output) => output.events = events,
+        TransactionOutput::DeployAccount(output) => output.events = events,
+        TransactionOutput::Invoke(output) => output.events = events,
+        TransactionOutput::L1Handler(output) => output.events = events,
+    }
+}
@@ -0,0 +1,241 @@
+//! Compaction of the memory mapped files of the storage.
+//!
+//! The mmap files are append only, so the objects of reverted and pruned blocks remain in them as
+//! dead space. Compacting the storage copies the objects that are still referenced from the tables
+//! to new files, rewrites their locations in the tables and replaces the original files with the
+//! new ones.
+//!
+//! The compaction is offline: the storage must not be opened by anyone else while it's compacted.
+//! Each file is compacted in a single write transaction, which rewrites all the locations in the
+//! file together with the file offset. The compacted file is written next to the original file
+//! before this transaction is committed, and replaces it right after the commit. If the compaction
+//! is interrupted in between, the next [`open_storage`] completes the replacement if the
+//! transaction was committed, and otherwise removes the compacted file.
+//!
+//! # Example
+//! ```
+//! use papyrus_storage::compaction::compact_storage;
+//! use papyrus_storage::open_storage;
+//! # use papyrus_storage::{db::DbConfig, StorageConfig};
+//! # use starknet_api::core::ChainId;
+//!
+//! # let dir_handle = tempfile::tempdir().unwrap();
+//! # let dir = dir_handle.path().to_path_buf();
+//! # let db_config = DbConfig {
+//! #     path_prefix: dir,
+//! #     chain_id: ChainId::Mainnet,
+//! #     enforce_file_exists: false,
+//! #     min_size: 1 << 20,    // 1MB
+//! #     max_size: 1 << 35,    // 32GB
+//! #     growth_step: 1 << 26, // 64MB
+//! # };
+//! # let storage_config = StorageConfig{db_config, ..Default::default()};
+//! let files_stats = compact_storage(storage_config.clone())?;
+//! assert_eq!(files_stats.len(), 6);
+//! let (_reader, _writer) = open_storage(storage_config)?;
+//! # Ok::<(), papyrus_storage::StorageError>(())
+//! ```
+#[cfg(test)]
+#[path = "compaction_test.rs"]
+mod compaction_test;
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, info, warn};
+
+use crate::db::serialization::{Key, ValueSerde};
+use crate::db::table_types::{DbCursorTrait, SimpleTable, Table};
+use crate::db::{DbTransaction, TableHandle, RW};
+use crate::mmap_file::{CompactedFile, MMapFileError, MMapFileStats};
+use crate::state::data::IndexedDeprecatedContractClass;
+use crate::{
+    mmap_file_path,
+    open_storage,
+    OffsetKind,
+    StorageConfig,
+    StorageResult,
+    StorageScope,
+    StorageTxn,
+    TransactionMetadata,
+};
+
+/// Compacts the mmap files of the storage, see the module documentation. Returns the stats of the
+/// compacted files by their name, including the amount of data reclaimed from each file.
+pub fn compact_storage(
+    storage_config: StorageConfig,
+) -> StorageResult<HashMap<String, MMapFileStats>> {
+    let db_path = storage_config.db_config.path();
+    let (reader, mut writer) = open_storage(storage_config)?;
+    let mut offset_kinds = vec![
+        OffsetKind::ThinStateDiff,
+        OffsetKind::ContractClass,
+        OffsetKind::Casm,
+        OffsetKind::DeprecatedContractClass,
+    ];
+    if writer.scope != StorageScope::StateOnly {
+        offset_kinds.extend([OffsetKind::TransactionOutput, OffsetKind::Transaction]);
+    }
+
+    let mut files_stats = HashMap::new();
+    let mut compacted_files = Vec::new();
+    for offset_kind in offset_kinds {
+        // The compacted file is written under a temporary name, so that a file that was only
+        // partially written is never mistaken for a compacted file.
+        let temporary_path = temporary_file_path(&db_path, offset_kind);
+        let txn = writer.begin_rw_txn()?;
+        let file_stats = txn
+            .copy_referenced_objects(offset_kind, CompactedFile::create(temporary_path.clone())?)?;
+        files_stats.insert(offset_kind.file_name().to_owned(), file_stats);
+        if file_stats.reclaimed == 0 {
+            debug!("There is no dead space in the {} file.", offset_kind.file_name());
+            fs::remove_file(temporary_path)?;
+            continue;
+        }
+        fs::rename(temporary_path, compacted_file_path(&db_path, offset_kind))?;
+        let file_offsets_table = txn.open_table(&txn.tables.file_offsets)?;
+        file_offsets_table.upsert(&txn.txn, &offset_kind, &file_stats.offset)?;
+        txn.commit()?;
+        compacted_files.push((offset_kind, file_stats.offset));
+    }
+
+    // Release the memory mapping of the original files before replacing them.
+    drop((reader, writer));
+    for (offset_kind, offset) in compacted_files {
+        complete_interrupted_compaction(&db_path, offset_kind, offset)?;
+    }
+    Ok(files_stats)
+}
+
+// Replaces the file of the given kind with its compacted file, if the compaction of the file was
+// committed. Otherwise, removes the compacted file. A committed compacted file ends exactly at the
+// file offset, while the offset of a file with dead space is beyond the end of its compacted file.
+pub(crate) fn complete_interrupted_compaction(
+    db_path: &Path,
+    offset_kind: OffsetKind,
+    offset: usize,
+) -> StorageResult<()> {
+    let temporary_path = temporary_file_path(db_path, offset_kind);
+    if temporary_path.exists() {
+        warn!("Removing the partially compacted {} file.", offset_kind.file_name());
+        fs::remove_file(temporary_path)?;
+    }
+
+    let compacted_path = compacted_file_path(db_path, offset_kind);
+    if !compacted_path.exists() {
+        return Ok(());
+    }
+    let compacted_len =
+        usize::try_from(fs::metadata(&compacted_path)?.len()).map_err(MMapFileError::from)?;
+    if compacted_len == offset {
+        info!("Replacing the {} file with its compacted file.", offset_kind.file_name());
+        fs::rename(compacted_path, mmap_file_path(db_path, offset_kind))?;
+    } else {
+        warn!(
+            "Removing the compacted {} file of an uncommitted compaction.",
+            offset_kind.file_name()
+        );
+        fs::remove_file(compacted_path)?;
+    }
+    Ok(())
+}
+
+fn temporary_file_path(db_path: &Path, offset_kind: OffsetKind) -> PathBuf {
+    db_path.join(format!("{}.dat.compacting", offset_kind.file_name()))
+}
+
+fn compacted_file_path(db_path: &Path, offset_kind: OffsetKind) -> PathBuf {
+    db_path.join(format!("{}.dat.compacted", offset_kind.file_name()))
+}
+
+impl StorageTxn<'_, RW> {
+    // Copies the objects of the file of the given kind that are referenced from the tables to the
+    // compacted file, and rewrites their locations in the tables.
+    fn copy_referenced_objects(
+        &self,
+        offset_kind: OffsetKind,
+        mut compacted_file: CompactedFile,
+    ) -> StorageResult<MMapFileStats> {
+        let file_handlers = &self.file_handlers;
+        let file_stats = match offset_kind {
+            OffsetKind::ThinStateDiff => {
+                let table = self.open_table(&self.tables.state_diffs)?;
+                rewrite_values(&self.txn, &table, |location| {
+                    Ok(compacted_file.copy(&file_handlers.thin_state_diff, location)?)
+                })?;
+                compacted_file.finish(&file_handlers.thin_state_diff)?
+            }
+            OffsetKind::ContractClass => {
+                let table = self.open_table(&self.tables.declared_classes)?;
+                rewrite_values(&self.txn, &table, |location| {
+                    Ok(compacted_file.copy(&file_handlers.contract_class, location)?)
+                })?;
+                compacted_file.finish(&file_handlers.contract_class)?
+            }
+            OffsetKind::Casm => {
+                let table = self.open_table(&self.tables.casms)?;
+                rewrite_values(&self.txn, &table, |location| {
+                    Ok(compacted_file.copy(&file_handlers.casm, location)?)
+                })?;
+                compacted_file.finish(&file_handlers.casm)?
+            }
+            OffsetKind::DeprecatedContractClass => {
+                let table = self.open_table(&self.tables.deprecated_declared_classes)?;
+                rewrite_values(&self.txn, &table, |indexed_class| {
+                    Ok(IndexedDeprecatedContractClass {
+                        location_in_file: compacted_file.copy(
+                            &file_handlers.deprecated_contract_class,
+                            indexed_class.location_in_file,
+                        )?,
+                        ..indexed_class
+                    })
+                })?;
+                compacted_file.finish(&file_handlers.deprecated_contract_class)?
+            }
+            OffsetKind::TransactionOutput => {
+                let table = self.open_table(&self.tables.transaction_metadata)?;
+                rewrite_values(&self.txn, &table, |tx_metadata| {
+                    Ok(TransactionMetadata {
+                        tx_output_location: compacted_file.copy(
+                            &file_handlers.transaction_output,
+                            tx_metadata.tx_output_location,
+                        )?,
+                        ..tx_metadata
+                    })
+                })?;
+                compacted_file.finish(&file_handlers.transaction_output)?
+            }
+            OffsetKind::Transaction => {
+                let table = self.open_table(&self.tables.transaction_metadata)?;
+                rewrite_values(&self.txn, &table, |tx_metadata| {
+                    Ok(TransactionMetadata {
+                        tx_location: compacted_file
+                            .copy(&file_handlers.transaction, tx_metadata.tx_location)?,
+                        ..tx_metadata
+                    })
+                })?;
+                compacted_file.finish(&file_handlers.transaction)?
+            }
+        };
+        Ok(file_stats)
+    }
+}
+
+// Replaces every value of the table with its rewritten value. The cursor is positioned again after
+// each update, since the table is modified while it's iterated.
+fn rewrite_values<'env, K: Key + Debug, V: ValueSerde + Debug>(
+    txn: &'env DbTransaction<'env, RW>,
+    table: &'env TableHandle<'env, K, V, SimpleTable>,
+    mut rewrite: impl FnMut(V::Value) -> StorageResult<V::Value>,
+) -> StorageResult<()> {
+    let mut entry = table.cursor(txn)?.next()?;
+    while let Some((key, value)) = entry {
+        table.upsert(txn, &key, &rewrite(value)?)?;
+        let mut cursor = table.cursor(txn)?;
+        cursor.lower_bound(&key)?;
+        entry = cursor.next()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use std::fs;
+
+use indexmap::indexmap;
+use papyrus_test_utils::get_test_block;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockNumber};
+use starknet_api::state::ThinStateDiff;
+use starknet_api::{contract_address, felt, storage_key};
+
+use crate::body::{BodyStorageReader, BodyStorageWriter};
+use crate::compaction::{compact_storage, compacted_file_path, temporary_file_path};
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::get_test_config;
+use crate::{open_storage, OffsetKind, StorageWriter};
+
+const TXS_PER_BLOCK: usize = 2;
+
+fn block_body(block_number: BlockNumber) -> BlockBody {
+    let body = get_test_block(2 * TXS_PER_BLOCK, None, None, None).body;
+    let block_number = usize::try_from(block_number.0).unwrap();
+    let txs = block_number * TXS_PER_BLOCK..(block_number + 1) * TXS_PER_BLOCK;
+    BlockBody {
+        transactions: body.transactions[txs.clone()].to_vec(),
+        transaction_outputs: body.transaction_outputs[txs.clone()].to_vec(),
+        transaction_hashes: body.transaction_hashes[txs].to_vec(),
+    }
+}
+
+fn state_diff(block_number: BlockNumber) -> ThinStateDiff {
+    ThinStateDiff {
+        storage_diffs: indexmap! {
+            contract_address!("0x100") => indexmap! { storage_key!("0x10") => felt!(block_number.0) }
+        },
+        ..Default::default()
+    }
+}
+
+// Returns the appended body, since the test bodies are random.
+fn append_block(writer: &mut StorageWriter, block_number: BlockNumber) -> BlockBody {
+    let body = block_body(block_number);
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(block_number, body.clone())
+        .unwrap()
+        .append_state_diff(block_number, state_diff(block_number))
+        .unwrap()
+        .commit()
+        .unwrap();
+    body
+}
+
+#[test]
+fn compact_after_revert() {
+    let (config, _temp_dir) = get_test_config(None);
+    let body_0;
+    {
+        let (_reader, mut writer) = open_storage(config.clone()).unwrap();
+        body_0 = append_block(&mut writer, BlockNumber(0));
+        append_block(&mut writer, BlockNumber(1));
+        let (txn, _) = writer.begin_rw_txn().unwrap().revert_body(BlockNumber(1)).unwrap();
+        let (txn, _) = txn.revert_state_diff(BlockNumber(1)).unwrap();
+        txn.commit().unwrap();
+    }
+
+    let files_stats = compact_storage(config.clone()).unwrap();
+    for file_name in ["thin_state_diff", "transaction", "transaction_output"] {
+        assert!(files_stats[file_name].reclaimed > 0, "Nothing was reclaimed in {file_name}.");
+    }
+    assert_eq!(files_stats["casm"].reclaimed, 0);
+
+    let (reader, mut writer) = open_storage(config).unwrap();
+    assert_eq!(
+        reader.mmap_files_stats()["thin_state_diff"].offset,
+        files_stats["thin_state_diff"].offset
+    );
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_diff(BlockNumber(0)).unwrap(), Some(state_diff(BlockNumber(0))));
+    assert_eq!(txn.get_block_transactions(BlockNumber(0)).unwrap(), Some(body_0.transactions));
+    assert_eq!(
+        txn.get_block_transaction_outputs(BlockNumber(0)).unwrap().unwrap().len(),
+        TXS_PER_BLOCK
+    );
+    drop(txn);
+
+    // The compacted files can be appended to.
+    let body_1 = append_block(&mut writer, BlockNumber(1));
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_diff(BlockNumber(1)).unwrap(), Some(state_diff(BlockNumber(1))));
+    assert_eq!(txn.get_block_transactions(BlockNumber(1)).unwrap(), Some(body_1.transactions));
+}
+
+#[test]
+fn uncommitted_compaction_is_removed() {
+    let (config, _temp_dir) = get_test_config(None);
+    let db_path = config.db_config.path();
+    {
+        let (_reader, mut writer) = open_storage(config.clone()).unwrap();
+        append_block(&mut writer, BlockNumber(0));
+    }
+    // Compacted files that don't end at the file offset.
+    let temporary_path = temporary_file_path(&db_path, OffsetKind::ThinStateDiff);
+    let compacted_path = compacted_file_path(&db_path, OffsetKind::ThinStateDiff);
+    fs::write(&temporary_path, [1, 2, 3]).unwrap();
+    fs::write(&compacted_path, [1, 2, 3]).unwrap();
+
+    let (reader, _writer) = open_storage(config).unwrap();
+    assert!(!temporary_path.exists());
+    assert!(!compacted_path.exists());
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_state_diff(BlockNumber(0)).unwrap(),
+        Some(state_diff(BlockNumber(0)))
+    );
+}
@@ -1,19 +1,281 @@
+//! Compression of the data in the storage.
+//!
+//! The data is compressed with zstd. The state diffs and the classes are compressed according to
+//! the [`StorageCompressionConfig`], which sets for each kind of data whether it's compressed, the
+//! compression level and optionally a trained zstd dictionary. Data that isn't compressed is
+//! stored in a zstd skippable frame, and data that is compressed with a dictionary is stored in a
+//! zstd frame that holds the id of the dictionary. This way, the data is readable regardless of the
+//! configuration it was written with. The dictionaries are stored in the storage when it's opened,
+//! so data that was compressed with a dictionary remains readable after the dictionary is replaced.
 #[cfg(test)]
 #[path = "compression_utils_test.rs"]
 mod compression_utils_test;
 
-use crate::db::serialization::{StorageSerde, StorageSerdeError};
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_optional_param,
+    ser_param,
+    SerializeConfig,
+};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use zstd::dict::DecoderDictionary;
 
-// TODO(dvir): create one compressor/decompressor only once (maybe only once per thread) to prevent
-// buffer reallocation.
-// TODO: fine tune the compression hyperparameters (and maybe even the compression algorithm).
+use crate::db::serialization::{StorageSerde, StorageSerdeError};
 
 // The maximum size of the decompressed data.
 // TODO(Dvir): consider defining this for each type separately and pass it as an argument to the
 // decompress function.
 pub(crate) const MAX_DECOMPRESSED_SIZE: usize = 1 << 28; // 256 MB
-// The compression level to use. Higher levels are slower but compress better.
+// The compression level to use for data without a compression config. Higher levels are slower but
+// compress better.
 const COMPRESSION_LEVEL: i32 = zstd::DEFAULT_COMPRESSION_LEVEL;
+// The magic number of the zstd skippable frames in which uncompressed data is stored. A skippable
+// frame can't be mistaken for a compressed frame, which starts with a different magic number.
+const SKIPPABLE_FRAME_MAGIC_NUMBER: u32 = 0x184D2A50;
+
+/// The compression settings of a kind of data.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Validate)]
+pub struct CompressionConfig {
+    /// Whether to compress the data.
+    pub enabled: bool,
+    /// The zstd compression level. Higher levels are slower but compress better.
+    #[validate(range(min = -7, max = 22))]
+    pub level: i32,
+    /// The path of a trained zstd dictionary to compress the data with.
+    pub dictionary_path: Option<PathBuf>,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self { enabled: true, level: COMPRESSION_LEVEL, dictionary_path: None }
+    }
+}
+
+impl SerializeConfig for CompressionConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        let mut dumped_config = BTreeMap::from_iter([
+            ser_param(
+                "enabled",
+                &self.enabled,
+                "Whether to compress the data. Data that was written compressed remains readable \
+                 after the compression is disabled.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "level",
+                &self.level,
+                "The zstd compression level, between -7 and 22. Higher levels are slower but \
+                 compress better.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
+        dumped_config.extend(ser_optional_param(
+            &self.dictionary_path,
+            PathBuf::new(),
+            "dictionary_path",
+            "The path of a trained zstd dictionary (see zstd --train) to compress the data with. \
+             The dictionary is kept in the storage, so the data remains readable after the \
+             dictionary is replaced.",
+            ParamPrivacyInput::Public,
+        ));
+        dumped_config
+    }
+}
+
+/// The compression settings of the kinds of data that are stored in the memory mapped files.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Validate)]
+pub struct StorageCompressionConfig {
+    /// The compression settings of the state diffs.
+    #[validate]
+    pub state_diffs: CompressionConfig,
+    /// The compression settings of the Sierra classes.
+    #[validate]
+    pub classes: CompressionConfig,
+    /// The compression settings of the CASMs.
+    #[validate]
+    pub casms: CompressionConfig,
+    /// The compression settings of the deprecated (Cairo 0) classes.
+    #[validate]
+    pub deprecated_classes: CompressionConfig,
+}
+
+impl SerializeConfig for StorageCompressionConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        let mut dumped_config = BTreeMap::new();
+        dumped_config.extend(append_sub_config_name(self.state_diffs.dump(), "state_diffs"));
+        dumped_config.extend(append_sub_config_name(self.classes.dump(), "classes"));
+        dumped_config.extend(append_sub_config_name(self.casms.dump(), "casms"));
+        dumped_config
+            .extend(append_sub_config_name(self.deprecated_classes.dump(), "deprecated_classes"));
+        dumped_config
+    }
+}
+
+// A zstd dictionary to compress with. Each thread loads it once into a compression context of its
+// own (see DICTIONARY_COMPRESSORS).
+pub(crate) struct EncodingDictionary {
+    id: u32,
+    dictionary: Vec<u8>,
+}
+
+impl std::fmt::Debug for EncodingDictionary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncodingDictionary").field("id", &self.id).finish()
+    }
+}
+
+// The zstd dictionaries that were used to compress data in the storage, by their ids.
+pub(crate) type DecodingDictionaries = HashMap<u32, Arc<DecoderDictionary<'static>>>;
+
+// Returns the id of a trained zstd dictionary, or None if the given bytes aren't a dictionary.
+pub(crate) fn dictionary_id(dictionary: &[u8]) -> Option<u32> {
+    zstd::zstd_safe::get_dict_id_from_dict(dictionary).map(|id| id.get())
+}
+
+// The compression of a kind of data, as used while it's serialized and deserialized.
+pub(crate) struct Compression {
+    enabled: bool,
+    level: i32,
+    dictionary: Option<EncodingDictionary>,
+    decoding_dictionaries: Arc<DecodingDictionaries>,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            level: COMPRESSION_LEVEL,
+            dictionary: None,
+            decoding_dictionaries: Arc::default(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Compression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Compression")
+            .field("enabled", &self.enabled)
+            .field("level", &self.level)
+            .field("dictionary", &self.dictionary)
+            .finish()
+    }
+}
+
+impl Compression {
+    // Creates the compression of a kind of data. `dictionary` is the dictionary of the config, if
+    // it has one, and `decoding_dictionaries` must contain it.
+    pub(crate) fn new(
+        config: &CompressionConfig,
+        dictionary: Option<&[u8]>,
+        decoding_dictionaries: Arc<DecodingDictionaries>,
+    ) -> Self {
+        let dictionary = dictionary.and_then(|dictionary| {
+            Some(EncodingDictionary {
+                id: dictionary_id(dictionary)?,
+                dictionary: dictionary.to_vec(),
+            })
+        });
+        Self { enabled: config.enabled, level: config.level, dictionary, decoding_dictionaries }
+    }
+
+    // Runs `f` with this compression, i.e. the data that `f` compresses is compressed according to
+    // this compression, and the data that it decompresses may be compressed with any of the
+    // dictionaries of this compression.
+    pub(crate) fn apply<T>(self: &Arc<Self>, f: impl FnOnce() -> T) -> T {
+        let previous_compression = CURRENT_COMPRESSION.replace(self.clone());
+        let result = f();
+        CURRENT_COMPRESSION.set(previous_compression);
+        result
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, io::Error> {
+        if !self.enabled {
+            return to_skippable_frame(data);
+        }
+        match &self.dictionary {
+            Some(EncodingDictionary { id, dictionary }) => {
+                DICTIONARY_COMPRESSORS.with_borrow_mut(|compressors| {
+                    let compressor = match compressors.entry((*id, self.level)) {
+                        Entry::Occupied(entry) => entry.into_mut(),
+                        Entry::Vacant(entry) => entry.insert(
+                            zstd::bulk::Compressor::with_dictionary(self.level, dictionary)?,
+                        ),
+                    };
+                    compressor.compress(data)
+                })
+            }
+            None => COMPRESSOR.with_borrow_mut(|compressor| {
+                compressor.set_compression_level(self.level)?;
+                compressor.compress(data)
+            }),
+        }
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, io::Error> {
+        if let Some(uncompressed_data) = from_skippable_frame(data) {
+            return Ok(uncompressed_data.to_vec());
+        }
+        let Some(dictionary_id) = zstd::zstd_safe::get_dict_id_from_frame(data) else {
+            return DECOMPRESSOR.with_borrow_mut(|decompressor| {
+                decompressor.decompress(data, MAX_DECOMPRESSED_SIZE)
+            });
+        };
+        let dictionary = self.decoding_dictionaries.get(&dictionary_id.get()).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("The zstd dictionary {dictionary_id} is not in the storage."),
+            )
+        })?;
+        zstd::bulk::Decompressor::with_prepared_dictionary(dictionary)?
+            .decompress(data, MAX_DECOMPRESSED_SIZE)
+    }
+}
+
+thread_local! {
+    // The compression of the data that the thread currently serializes or deserializes.
+    static CURRENT_COMPRESSION: RefCell<Arc<Compression>> = RefCell::new(Arc::default());
+    // The compression contexts are reused to prevent their reallocation.
+    static COMPRESSOR: RefCell<zstd::bulk::Compressor<'static>> = RefCell::new(
+        zstd::bulk::Compressor::new(COMPRESSION_LEVEL).expect("Failed to create a zstd compressor")
+    );
+    static DECOMPRESSOR: RefCell<zstd::bulk::Decompressor<'static>> = RefCell::new(
+        zstd::bulk::Decompressor::new().expect("Failed to create a zstd decompressor")
+    );
+    // The compression contexts with a loaded dictionary, by the id of the dictionary and the
+    // compression level, since loading a dictionary is costly.
+    static DICTIONARY_COMPRESSORS: RefCell<HashMap<(u32, i32), zstd::bulk::Compressor<'static>>> =
+        RefCell::new(HashMap::new());
+}
+
+// Wraps the data in a zstd skippable frame: the magic number, the length of the data and the data.
+fn to_skippable_frame(data: &[u8]) -> Result<Vec<u8>, io::Error> {
+    let len = u32::try_from(data.len())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut frame = Vec::with_capacity(data.len() + 8);
+    frame.extend(SKIPPABLE_FRAME_MAGIC_NUMBER.to_le_bytes());
+    frame.extend(len.to_le_bytes());
+    frame.extend(data);
+    Ok(frame)
+}
+
+// Returns the data of a zstd skippable frame, or None if the given bytes aren't a skippable frame.
+fn from_skippable_frame(frame: &[u8]) -> Option<&[u8]> {
+    let (magic_number, rest) = frame.split_first_chunk::<4>()?;
+    if u32::from_le_bytes(*magic_number) != SKIPPABLE_FRAME_MAGIC_NUMBER {
+        return None;
+    }
+    let (len, data) = rest.split_first_chunk::<4>()?;
+    data.get(..usize::try_from(u32::from_le_bytes(*len)).ok()?)
+}
 
 /// Returns the compressed data in a vector.
 ///
@@ -23,7 +285,7 @@ const COMPRESSION_LEVEL: i32 = zstd::DEFAULT_COMPRESSION_LEVEL;
 /// # Errors
 /// Returns [`std::io::Error`] if any read error is encountered.
 pub fn compress(data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
-    zstd::bulk::compress(data, COMPRESSION_LEVEL)
+    CURRENT_COMPRESSION.with_borrow(|compression| compression.compress(data))
 }
 
 /// Serialized and then compress object.
@@ -47,7 +309,7 @@ pub fn serialize_and_compress(object: &impl StorageSerde) -> Result<Vec<u8>, Sto
 /// # Errors
 /// Returns [`std::io::Error`] if any read error is encountered.
 pub fn decompress(data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
-    zstd::bulk::decompress(data, MAX_DECOMPRESSED_SIZE)
+    CURRENT_COMPRESSION.with_borrow(|compression| compression.decompress(data))
 }
 
 /// Decompress a vector directly from a reader.
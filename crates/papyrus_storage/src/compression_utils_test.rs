@@ -1,9 +1,30 @@
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
+use indexmap::indexmap;
 use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
 use starknet_api::deprecated_contract_class::Program;
+use starknet_api::state::ThinStateDiff;
 use starknet_api::test_utils::read_json_file;
+use starknet_api::{contract_address, felt, storage_key};
+use zstd::dict::DecoderDictionary;
 
-use super::{compress, decompress, decompress_from_reader, serialize_and_compress};
+use super::{
+    compress,
+    decompress,
+    decompress_from_reader,
+    dictionary_id,
+    serialize_and_compress,
+    Compression,
+    CompressionConfig,
+    DecodingDictionaries,
+    SKIPPABLE_FRAME_MAGIC_NUMBER,
+};
 use crate::db::serialization::StorageSerde;
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::get_test_config;
+use crate::{open_storage, StorageError};
 
 #[test]
 fn bytes_compression() {
@@ -23,3 +44,111 @@ fn object_compression() {
     let restored_program = Program::deserialize_from(&mut decompressed.as_slice()).unwrap();
     assert_eq!(program, restored_program);
 }
+
+fn train_dictionary() -> Vec<u8> {
+    let samples: Vec<Vec<u8>> = (0..1000)
+        .map(|i| format!("{{\"sample\": {i}, \"entry_points\": [\"0x{:x}\"]}}", i * 7).into_bytes())
+        .collect();
+    zstd::dict::from_samples(&samples, 1024).unwrap()
+}
+
+#[test]
+fn disabled_compression() {
+    let bytes = vec![30, 5, 23, 12, 47];
+    let config = CompressionConfig { enabled: false, ..Default::default() };
+    let compression = Arc::new(Compression::new(&config, None, Arc::default()));
+
+    let stored = compression.apply(|| compress(&bytes)).unwrap();
+    assert_eq!(stored[..4], SKIPPABLE_FRAME_MAGIC_NUMBER.to_le_bytes());
+    assert_eq!(stored[8..], bytes);
+    // Data that isn't compressed is readable regardless of the compression.
+    assert_eq!(decompress(&stored).unwrap(), bytes);
+}
+
+#[test]
+fn dictionary_compression() {
+    let dictionary = train_dictionary();
+    let id = dictionary_id(&dictionary).unwrap();
+    let decoding_dictionaries = Arc::new(DecodingDictionaries::from([(
+        id,
+        Arc::new(DecoderDictionary::copy(&dictionary)),
+    )]));
+    let compression = Arc::new(Compression::new(
+        &CompressionConfig::default(),
+        Some(&dictionary),
+        decoding_dictionaries,
+    ));
+    let bytes = b"{\"sample\": 1234, \"entry_points\": [\"0x21be\"]}".to_vec();
+
+    let compressed = compression.apply(|| compress(&bytes)).unwrap();
+    assert_eq!(zstd::zstd_safe::get_dict_id_from_frame(&compressed).map(|id| id.get()), Some(id));
+    // The compression context of the dictionary is reused.
+    assert_eq!(compression.apply(|| compress(&bytes)).unwrap(), compressed);
+    // The dictionary is needed for decompressing the data.
+    assert!(decompress(&compressed).is_err());
+    assert_eq!(compression.apply(|| decompress(&compressed)).unwrap(), bytes);
+    // Data that was compressed without a dictionary is readable as well.
+    let compressed_without_dictionary = compress(&bytes).unwrap();
+    assert_eq!(compression.apply(|| decompress(&compressed_without_dictionary)).unwrap(), bytes);
+}
+
+#[test]
+fn not_a_dictionary() {
+    assert_eq!(dictionary_id(b"not a dictionary"), None);
+}
+
+fn state_diff() -> ThinStateDiff {
+    ThinStateDiff {
+        storage_diffs: indexmap! {
+            contract_address!("0x100") => indexmap! { storage_key!("0x10") => felt!("0x1") }
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn stored_dictionary_remains_in_use() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    let dictionary_dir = tempfile::tempdir().unwrap();
+    let dictionary_path = dictionary_dir.path().join("state_diffs.dict");
+    std::fs::write(&dictionary_path, train_dictionary()).unwrap();
+    config.compression_config.state_diffs.dictionary_path = Some(dictionary_path);
+
+    let (reader, mut writer) = open_storage(config.clone()).unwrap();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), state_diff())
+        .unwrap()
+        .commit()
+        .unwrap();
+    drop((reader, writer));
+
+    // The state diff is readable after the dictionary is removed from the config.
+    config.compression_config.state_diffs.dictionary_path = None;
+    let (reader, mut writer) = open_storage(config).unwrap();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(1), state_diff())
+        .unwrap()
+        .commit()
+        .unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_diff(BlockNumber(0)).unwrap(), Some(state_diff()));
+    assert_eq!(txn.get_state_diff(BlockNumber(1)).unwrap(), Some(state_diff()));
+}
+
+#[test]
+fn invalid_dictionary_file() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    let dictionary_dir = tempfile::tempdir().unwrap();
+    let dictionary_path = dictionary_dir.path().join("classes.dict");
+    std::fs::write(&dictionary_path, b"not a dictionary").unwrap();
+    config.compression_config.classes.dictionary_path = Some(dictionary_path.clone());
+
+    assert_matches!(
+        open_storage(config).map(|_| ()),
+        Err(StorageError::InvalidCompressionDictionary { path }) if path == dictionary_path
+    );
+}
@@ -5,8 +5,17 @@ use tempfile::TempDir;
 
 use crate::db::serialization::{NoVersionValueWrapper, ValueSerde, VersionZeroWrapper};
 use crate::db::table_types::Table;
-use crate::db::{get_page_size, open_env, DbError, DbIter, DbReader, DbResult, DbWriter};
-use crate::test_utils::get_test_config;
+use crate::db::{
+    get_page_size,
+    open_env,
+    DbError,
+    DbIter,
+    DbReader,
+    DbResult,
+    DbWriter,
+    MAX_DBS,
+};
+use crate::test_utils::{get_test_config, get_test_storage};
 
 pub(crate) fn get_test_env() -> ((DbReader, DbWriter), TempDir) {
     let (config, temp_dir) = get_test_config(None);
@@ -18,6 +27,13 @@ fn open_env_scenario() {
     get_test_env();
 }
 
+// Opening the storage creates all of its tables, which fails if they don't fit in MAX_DBS.
+#[test]
+fn open_storage_with_all_tables() {
+    let ((reader, _writer), _temp_dir) = get_test_storage();
+    assert_eq!(reader.db_tables_stats().unwrap().tables_stats.len(), MAX_DBS);
+}
+
 #[test]
 fn open_env_with_enforce_file_exists() {
     let (config, _temp_dir) = get_test_config(None);
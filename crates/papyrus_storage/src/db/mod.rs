@@ -23,9 +23,11 @@ pub(crate) mod table_types;
 
 use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::ffi::CString;
 use std::fmt::Debug;
 use std::marker::PhantomData;
-use std::path::PathBuf;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
 use std::result;
 use std::sync::Arc;
 
@@ -43,7 +45,7 @@ use self::table_types::{DbCursor, DbCursorTrait};
 use crate::db::table_types::TableType;
 
 // Maximum number of Sub-Databases.
-const MAX_DBS: usize = 18;
+const MAX_DBS: usize = 25;
 
 // Note that NO_TLS mode is used by default.
 type EnvironmentKind = WriteMap;
@@ -248,6 +250,27 @@ impl DbReader {
     pub(crate) fn begin_ro_txn(&self) -> DbResult<DbReadTransaction<'_>> {
         Ok(DbReadTransaction { txn: self.env.begin_ro_txn()? })
     }
+
+    // Copies the environment to an mdbx.dat file in the given directory, omitting the free pages.
+    // MDBX copies a snapshot that it takes by itself, so the environment can be written meanwhile.
+    pub(crate) fn copy_compacted(&self, dir: &Path) -> DbResult<()> {
+        let dest = CString::new(dir.join("mdbx.dat").as_os_str().as_bytes())
+            .map_err(|_| libmdbx::Error::Invalid)?;
+        let err_code = unsafe {
+            mdbx_sys::mdbx_env_copy(self.env.ptr(), dest.as_ptr(), mdbx_sys::MDBX_CP_COMPACT)
+        };
+        if err_code != mdbx_sys::MDBX_SUCCESS {
+            return Err(libmdbx::Error::from_err_code(err_code).into());
+        }
+        Ok(())
+    }
+
+    // Opens the database that copy_compacted copied to the given directory, taking its geometry
+    // from the copied file.
+    pub(crate) fn open_copy(dir: &Path) -> DbResult<DbReader> {
+        let env = Environment::new().set_max_tables(MAX_DBS).open(dir)?;
+        Ok(DbReader { env: Arc::new(env) })
+    }
 }
 
 type DbReadTransaction<'env> = DbTransaction<'env, RO>;
@@ -0,0 +1,437 @@
+//! Verification of the integrity of the storage.
+//!
+//! The check reads the whole storage in a single RO transaction and verifies that:
+//! - The markers satisfy their invariants.
+//! - Every table holds exactly the data of the blocks up to its marker (and from the history
+//!   pruning marker for the history tables).
+//! - Every location in the mmap files is within the written part of its file, and the object in it
+//!   can be read and decompressed.
+//! - The block hashes and the state diff commitments match the stored data, where they can be
+//!   recomputed from it.
+//!
+//! The report holds the first inconsistency found in each table, and can be serialized to JSON.
+#[cfg(test)]
+#[path = "integrity_test.rs"]
+mod integrity_test;
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
+use starknet_api::block_hash::block_hash_calculator::{
+    calculate_block_hash,
+    concat_counts,
+    BlockHeaderCommitments,
+};
+use starknet_api::block_hash::state_diff_hash::calculate_state_diff_hash;
+use tracing::debug;
+
+use crate::base_layer::BaseLayerStorageReader;
+use crate::body::BodyStorageReader;
+use crate::class::ClassStorageReader;
+use crate::compiled_class::CasmStorageReader;
+use crate::db::table_types::{DbCursorTrait, Table};
+use crate::db::RO;
+use crate::header::HeaderStorageReader;
+use crate::mmap_file::LocationInFile;
+use crate::pruning::HistoryPruningStorageReader;
+use crate::state::StateStorageReader;
+use crate::{OffsetKind, StorageReader, StorageResult, StorageScope, StorageTxn};
+
+/// An inconsistency found in the storage.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Inconsistency {
+    /// The key of the inconsistent entry.
+    pub key: String,
+    /// A description of the inconsistency.
+    pub description: String,
+}
+
+/// The result of a storage integrity check.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct IntegrityReport {
+    /// The markers of the storage, by their name.
+    pub markers: BTreeMap<String, BlockNumber>,
+    /// The first inconsistency found in each table, by the table name. Inconsistencies between
+    /// the markers are reported under `markers`.
+    pub inconsistencies: BTreeMap<String, Inconsistency>,
+}
+
+impl IntegrityReport {
+    /// Returns true if no inconsistency was found.
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+}
+
+type CheckResult = StorageResult<Option<Inconsistency>>;
+
+/// Checks the integrity of the storage. See the module documentation for the checks done.
+pub fn check_storage_integrity(reader: &StorageReader) -> StorageResult<IntegrityReport> {
+    let txn = reader.begin_ro_txn()?;
+    let markers = Markers::read(&txn)?;
+    let mut report = IntegrityReport { markers: markers.by_name(), ..Default::default() };
+
+    let mut checks: Vec<(&str, CheckResult)> = vec![
+        ("markers", Ok(markers.check_invariants())),
+        (txn.tables.headers.name, check_headers(&txn, &markers)),
+        (txn.tables.state_diffs.name, check_state_diffs(&txn, &markers)),
+        (txn.tables.declared_classes.name, check_declared_classes(&txn)),
+        (txn.tables.declared_classes_block.name, check_declared_classes_block(&txn, &markers)),
+        (txn.tables.deprecated_declared_classes.name, check_deprecated_classes(&txn, &markers)),
+        (txn.tables.casms.name, check_casms(&txn)),
+    ];
+    if txn.scope != StorageScope::StateOnly {
+        checks.push((txn.tables.transaction_metadata.name, check_transactions(&txn, &markers)));
+    }
+    for (table_name, result) in checks {
+        if let Some(inconsistency) = result? {
+            debug!("Found an inconsistency in {table_name}: {inconsistency:?}.");
+            report.inconsistencies.insert(table_name.to_owned(), inconsistency);
+        }
+    }
+    Ok(report)
+}
+
+struct Markers {
+    header: BlockNumber,
+    body: BlockNumber,
+    state: BlockNumber,
+    class: BlockNumber,
+    compiled_class: BlockNumber,
+    base_layer: BlockNumber,
+    history_pruning: BlockNumber,
+}
+
+impl Markers {
+    fn read(txn: &StorageTxn<'_, RO>) -> StorageResult<Self> {
+        Ok(Self {
+            header: txn.get_header_marker()?,
+            body: txn.get_body_marker()?,
+            state: txn.get_state_marker()?,
+            class: txn.get_class_marker()?,
+            compiled_class: txn.get_compiled_class_marker()?,
+            base_layer: txn.get_base_layer_block_marker()?,
+            history_pruning: txn.get_history_pruning_marker()?,
+        })
+    }
+
+    fn by_name(&self) -> BTreeMap<String, BlockNumber> {
+        BTreeMap::from_iter(
+            [
+                ("header", self.header),
+                ("body", self.body),
+                ("state", self.state),
+                ("class", self.class),
+                ("compiled_class", self.compiled_class),
+                ("base_layer_block", self.base_layer),
+                ("history_pruning", self.history_pruning),
+            ]
+            .map(|(name, marker)| (name.to_owned(), marker)),
+        )
+    }
+
+    // Returns the first invariant that doesn't hold, out of the invariants documented in
+    // MarkerKind. The compiled class marker is advanced over blocks without classes even if the
+    // class marker isn't, so it's only bounded by the state marker.
+    fn check_invariants(&self) -> Option<Inconsistency> {
+        [
+            ("compiled_class", self.compiled_class, "state", self.state),
+            ("class", self.class, "state", self.state),
+            ("state", self.state, "header", self.header),
+            ("body", self.body, "header", self.header),
+            ("base_layer_block", self.base_layer, "header", self.header),
+            ("history_pruning", self.history_pruning, "compiled_class", self.compiled_class),
+            ("history_pruning", self.history_pruning, "body", self.body),
+        ]
+        .into_iter()
+        .find(|(_, lower, _, upper)| lower > upper)
+        .map(|(lower_name, lower, upper_name, upper)| Inconsistency {
+            key: format!("{lower_name}, {upper_name}"),
+            description: format!(
+                "The {lower_name} marker ({lower}) is greater than the {upper_name} marker \
+                 ({upper})."
+            ),
+        })
+    }
+}
+
+fn inconsistency(key: impl ToString, description: String) -> Option<Inconsistency> {
+    Some(Inconsistency { key: key.to_string(), description })
+}
+
+// Returns the offset up to which the file of the given kind was written.
+fn file_offset(txn: &StorageTxn<'_, RO>, offset_kind: OffsetKind) -> StorageResult<usize> {
+    let file_offsets_table = txn.open_table(&txn.tables.file_offsets)?;
+    Ok(file_offsets_table.get(&txn.txn, &offset_kind)?.unwrap_or_default())
+}
+
+// Reads the object at the given location, or returns a description of why it can't be read.
+// Locations beyond the written part of the file aren't read, since they may be beyond the end of
+// the memory mapped file.
+fn read_location<T>(
+    location: LocationInFile,
+    file_offset: usize,
+    read: impl FnOnce(LocationInFile) -> StorageResult<T>,
+) -> Result<T, String> {
+    if location.next_offset() > file_offset {
+        return Err(format!(
+            "The location {location:?} is beyond the written part of the file (up to offset \
+             {file_offset})."
+        ));
+    }
+    read(location).map_err(|err| format!("Failed to read the object at {location:?}: {err}"))
+}
+
+// Returns the hash of the block, if the header holds all the data needed to calculate it.
+fn calculate_header_block_hash(header: &BlockHeader) -> Option<BlockHash> {
+    let block_commitments = BlockHeaderCommitments {
+        transaction_commitment: header.transaction_commitment?,
+        event_commitment: header.event_commitment?,
+        receipt_commitment: header.receipt_commitment?,
+        state_diff_commitment: header.state_diff_commitment?,
+        concatenated_counts: concat_counts(
+            header.n_transactions,
+            header.n_events,
+            header.state_diff_length?,
+            header.block_header_without_hash.l1_da_mode,
+        ),
+    };
+    // Fails for blocks whose hash isn't calculated from the header (before Starknet 0.13.2).
+    calculate_block_hash(header.block_header_without_hash.clone(), block_commitments).ok()
+}
+
+fn check_headers(txn: &StorageTxn<'_, RO>, markers: &Markers) -> CheckResult {
+    for block_number in BlockNumber(0).iter_up_to(markers.header) {
+        let Some(header) = txn.get_block_header(block_number)? else {
+            return Ok(inconsistency(
+                block_number,
+                format!("Missing the header below the header marker ({}).", markers.header),
+            ));
+        };
+        if header.block_header_without_hash.block_number != block_number {
+            return Ok(inconsistency(
+                block_number,
+                format!(
+                    "The header holds block number {}.",
+                    header.block_header_without_hash.block_number
+                ),
+            ));
+        }
+        if txn.get_block_number_by_hash(&header.block_hash)? != Some(block_number) {
+            return Ok(inconsistency(
+                block_number,
+                format!("The block hash {} isn't mapped to the block.", header.block_hash),
+            ));
+        }
+        if let Some(block_hash) = calculate_header_block_hash(&header) {
+            if block_hash != header.block_hash {
+                return Ok(inconsistency(
+                    block_number,
+                    format!(
+                        "The block hash is {}, but the hash calculated from the header is \
+                         {block_hash}.",
+                        header.block_hash
+                    ),
+                ));
+            }
+        }
+    }
+    let headers_table = txn.open_table(&txn.tables.headers)?;
+    if let Some((block_number, _)) = headers_table.cursor(&txn.txn)?.lower_bound(&markers.header)? {
+        return Ok(inconsistency(
+            block_number,
+            format!("A header exists at or above the header marker ({}).", markers.header),
+        ));
+    }
+    Ok(None)
+}
+
+fn check_state_diffs(txn: &StorageTxn<'_, RO>, markers: &Markers) -> CheckResult {
+    let state_diffs_table = txn.open_table(&txn.tables.state_diffs)?;
+    let file_offset = file_offset(txn, OffsetKind::ThinStateDiff)?;
+    let mut cursor = state_diffs_table.cursor(&txn.txn)?;
+    if let Some((block_number, _)) = cursor.next()? {
+        if block_number < markers.history_pruning {
+            return Ok(inconsistency(
+                block_number,
+                format!(
+                    "A state diff exists below the history pruning marker ({}).",
+                    markers.history_pruning
+                ),
+            ));
+        }
+    }
+    if let Some((block_number, _)) = cursor.lower_bound(&markers.state)? {
+        return Ok(inconsistency(
+            block_number,
+            format!("A state diff exists at or above the state marker ({}).", markers.state),
+        ));
+    }
+
+    for block_number in markers.history_pruning.iter_up_to(markers.state) {
+        let Some(location) = state_diffs_table.get(&txn.txn, &block_number)? else {
+            return Ok(inconsistency(
+                block_number,
+                format!("Missing the state diff below the state marker ({}).", markers.state),
+            ));
+        };
+        let state_diff = match read_location(location, file_offset, |location| {
+            txn.file_handlers.get_thin_state_diff_unchecked(location)
+        }) {
+            Ok(state_diff) => state_diff,
+            Err(description) => return Ok(inconsistency(block_number, description)),
+        };
+        let Some(header) = txn.get_block_header(block_number)? else {
+            continue;
+        };
+        if let Some(state_diff_commitment) = header.state_diff_commitment {
+            let calculated_commitment = calculate_state_diff_hash(&state_diff);
+            if calculated_commitment != state_diff_commitment {
+                return Ok(inconsistency(
+                    block_number,
+                    format!(
+                        "The state diff commitment in the header is {:?}, but the commitment of \
+                         the state diff is {:?}.",
+                        state_diff_commitment, calculated_commitment
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn check_declared_classes(txn: &StorageTxn<'_, RO>) -> CheckResult {
+    let declared_classes_table = txn.open_table(&txn.tables.declared_classes)?;
+    let declared_classes_block_table = txn.open_table(&txn.tables.declared_classes_block)?;
+    let file_offset = file_offset(txn, OffsetKind::ContractClass)?;
+    let mut cursor = declared_classes_table.cursor(&txn.txn)?;
+    while let Some((class_hash, location)) = cursor.next()? {
+        if declared_classes_block_table.get(&txn.txn, &class_hash)?.is_none() {
+            return Ok(inconsistency(
+                class_hash,
+                "The class was never declared in a state diff.".to_owned(),
+            ));
+        }
+        if let Err(description) = read_location(location, file_offset, |location| {
+            txn.file_handlers.get_contract_class_unchecked(location)
+        }) {
+            return Ok(inconsistency(class_hash, description));
+        }
+    }
+    Ok(None)
+}
+
+fn check_declared_classes_block(txn: &StorageTxn<'_, RO>, markers: &Markers) -> CheckResult {
+    let declared_classes_block_table = txn.open_table(&txn.tables.declared_classes_block)?;
+    let mut cursor = declared_classes_block_table.cursor(&txn.txn)?;
+    while let Some((class_hash, block_number)) = cursor.next()? {
+        if block_number >= markers.state {
+            return Ok(inconsistency(
+                class_hash,
+                format!(
+                    "The class is declared in block {block_number}, which is at or above the \
+                     state marker ({}).",
+                    markers.state
+                ),
+            ));
+        }
+        if block_number < markers.class && txn.get_class(&class_hash)?.is_none() {
+            return Ok(inconsistency(
+                class_hash,
+                format!(
+                    "Missing the class declared in block {block_number}, which is below the class \
+                     marker ({}).",
+                    markers.class
+                ),
+            ));
+        }
+    }
+    Ok(None)
+}
+
+fn check_deprecated_classes(txn: &StorageTxn<'_, RO>, markers: &Markers) -> CheckResult {
+    let deprecated_declared_classes_table =
+        txn.open_table(&txn.tables.deprecated_declared_classes)?;
+    let file_offset = file_offset(txn, OffsetKind::DeprecatedContractClass)?;
+    let mut cursor = deprecated_declared_classes_table.cursor(&txn.txn)?;
+    while let Some((class_hash, indexed_class)) = cursor.next()? {
+        if indexed_class.block_number >= markers.class {
+            return Ok(inconsistency(
+                class_hash,
+                format!(
+                    "The class is declared in block {}, which is at or above the class marker \
+                     ({}).",
+                    indexed_class.block_number, markers.class
+                ),
+            ));
+        }
+        if let Err(description) =
+            read_location(indexed_class.location_in_file, file_offset, |location| {
+                txn.file_handlers.get_deprecated_contract_class_unchecked(location)
+            })
+        {
+            return Ok(inconsistency(class_hash, description));
+        }
+    }
+    Ok(None)
+}
+
+fn check_casms(txn: &StorageTxn<'_, RO>) -> CheckResult {
+    let casms_table = txn.open_table(&txn.tables.casms)?;
+    let file_offset = file_offset(txn, OffsetKind::Casm)?;
+    let mut cursor = casms_table.cursor(&txn.txn)?;
+    while let Some((class_hash, location)) = cursor.next()? {
+        if let Err(description) = read_location(location, file_offset, |location| {
+            txn.file_handlers.get_casm_unchecked(location)
+        }) {
+            return Ok(inconsistency(class_hash, description));
+        }
+    }
+    Ok(None)
+}
+
+fn check_transactions(txn: &StorageTxn<'_, RO>, markers: &Markers) -> CheckResult {
+    let transaction_metadata_table = txn.open_table(&txn.tables.transaction_metadata)?;
+    let transaction_hash_to_idx_table = txn.open_table(&txn.tables.transaction_hash_to_idx)?;
+    let transaction_file_offset = file_offset(txn, OffsetKind::Transaction)?;
+    let transaction_output_file_offset = file_offset(txn, OffsetKind::TransactionOutput)?;
+    let mut cursor = transaction_metadata_table.cursor(&txn.txn)?;
+    while let Some((tx_index, tx_metadata)) = cursor.next()? {
+        let block_number = tx_index.0;
+        if block_number < markers.history_pruning || block_number >= markers.body {
+            return Ok(inconsistency(
+                format!("{tx_index:?}"),
+                format!(
+                    "The transaction is outside of the blocks with a body ([{}, {})).",
+                    markers.history_pruning, markers.body
+                ),
+            ));
+        }
+        if transaction_hash_to_idx_table.get(&txn.txn, &tx_metadata.tx_hash)? != Some(tx_index) {
+            return Ok(inconsistency(
+                format!("{tx_index:?}"),
+                format!(
+                    "The transaction hash {} isn't mapped to the transaction.",
+                    tx_metadata.tx_hash
+                ),
+            ));
+        }
+        if let Err(description) =
+            read_location(tx_metadata.tx_location, transaction_file_offset, |location| {
+                txn.file_handlers.get_transaction_unchecked(location)
+            })
+        {
+            return Ok(inconsistency(format!("{tx_index:?}"), description));
+        }
+        if let Err(description) = read_location(
+            tx_metadata.tx_output_location,
+            transaction_output_file_offset,
+            |location| txn.file_handlers.get_transaction_output_unchecked(location),
+        ) {
+            return Ok(inconsistency(format!("{tx_index:?}"), description));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,182 @@
+use indexmap::indexmap;
+use papyrus_test_utils::get_test_block;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{
+    BlockHash,
+    BlockHeader,
+    BlockHeaderWithoutHash,
+    BlockNumber,
+    StarknetVersion,
+};
+use starknet_api::block_hash::block_hash_calculator::{
+    calculate_block_hash,
+    concat_counts,
+    BlockHeaderCommitments,
+};
+use starknet_api::block_hash::state_diff_hash::calculate_state_diff_hash;
+use starknet_api::core::{EventCommitment, ReceiptCommitment, TransactionCommitment};
+use starknet_api::data_availability::L1DataAvailabilityMode;
+use starknet_api::state::ThinStateDiff;
+use starknet_api::{contract_address, felt, storage_key};
+
+use crate::body::BodyStorageWriter;
+use crate::class::ClassStorageWriter;
+use crate::db::table_types::Table;
+use crate::header::HeaderStorageWriter;
+use crate::integrity::{check_storage_integrity, IntegrityReport};
+use crate::state::StateStorageWriter;
+use crate::test_utils::get_test_storage;
+use crate::{MarkerKind, OffsetKind, StorageReader, StorageWriter};
+
+const N_BLOCKS: u64 = 3;
+
+fn state_diff(block_number: BlockNumber) -> ThinStateDiff {
+    ThinStateDiff {
+        storage_diffs: indexmap! {
+            contract_address!("0x100") => indexmap! { storage_key!("0x10") => felt!(block_number.0) }
+        },
+        ..Default::default()
+    }
+}
+
+// A header whose block hash and state diff commitment can be recomputed.
+fn header(block_number: BlockNumber, parent_hash: BlockHash) -> BlockHeader {
+    let state_diff = state_diff(block_number);
+    let block_header_without_hash = BlockHeaderWithoutHash {
+        block_number,
+        parent_hash,
+        starknet_version: StarknetVersion::V0_13_2,
+        ..Default::default()
+    };
+    let block_commitments = BlockHeaderCommitments {
+        state_diff_commitment: calculate_state_diff_hash(&state_diff),
+        concatenated_counts: concat_counts(
+            0,
+            0,
+            state_diff.len(),
+            L1DataAvailabilityMode::Calldata,
+        ),
+        ..Default::default()
+    };
+    BlockHeader {
+        block_hash: calculate_block_hash(
+            block_header_without_hash.clone(),
+            block_commitments.clone(),
+        )
+        .unwrap(),
+        block_header_without_hash,
+        state_diff_commitment: Some(block_commitments.state_diff_commitment),
+        state_diff_length: Some(state_diff.len()),
+        transaction_commitment: Some(TransactionCommitment::default()),
+        event_commitment: Some(EventCommitment::default()),
+        receipt_commitment: Some(ReceiptCommitment::default()),
+        n_transactions: 0,
+        n_events: 0,
+    }
+}
+
+fn append_blocks(writer: &mut StorageWriter) {
+    let mut parent_hash = BlockHash::default();
+    for block_number in BlockNumber(0).iter_up_to(BlockNumber(N_BLOCKS)) {
+        let header = header(block_number, parent_hash);
+        parent_hash = header.block_hash;
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(block_number, &header)
+            .unwrap()
+            .append_body(block_number, get_test_block(0, None, None, None).body)
+            .unwrap()
+            .append_state_diff(block_number, state_diff(block_number))
+            .unwrap()
+            .append_classes(block_number, &[], &[])
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+}
+
+fn inconsistent_tables(reader: &StorageReader) -> Vec<String> {
+    check_storage_integrity(reader).unwrap().inconsistencies.into_keys().collect()
+}
+
+#[test]
+fn consistent_storage() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer);
+
+    let report = check_storage_integrity(&reader).unwrap();
+    assert!(report.is_consistent(), "{report:?}");
+    assert_eq!(report.markers["header"], BlockNumber(N_BLOCKS));
+    assert_eq!(report.markers["state"], BlockNumber(N_BLOCKS));
+    assert_eq!(report.markers["history_pruning"], BlockNumber(0));
+
+    // The report can be serialized.
+    let json = serde_json::to_string(&report).unwrap();
+    assert_eq!(serde_json::from_str::<IntegrityReport>(&json).unwrap(), report);
+}
+
+#[test]
+fn inconsistent_markers() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer);
+
+    let txn = writer.begin_rw_txn().unwrap();
+    let markers_table = txn.open_table(&txn.tables.markers).unwrap();
+    markers_table.upsert(&txn.txn, &MarkerKind::State, &BlockNumber(N_BLOCKS + 1)).unwrap();
+    txn.commit().unwrap();
+
+    let report = check_storage_integrity(&reader).unwrap();
+    let inconsistency = &report.inconsistencies["markers"];
+    assert_eq!(inconsistency.key, "state, header");
+    // The missing state diff is reported as well.
+    assert_eq!(report.inconsistencies["state_diffs"].key, N_BLOCKS.to_string());
+}
+
+#[test]
+fn location_beyond_file_offset() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer);
+
+    let txn = writer.begin_rw_txn().unwrap();
+    let file_offsets_table = txn.open_table(&txn.tables.file_offsets).unwrap();
+    file_offsets_table.upsert(&txn.txn, &OffsetKind::ThinStateDiff, &0).unwrap();
+    txn.commit().unwrap();
+
+    let report = check_storage_integrity(&reader).unwrap();
+    assert_eq!(report.inconsistencies.len(), 1);
+    assert_eq!(report.inconsistencies["state_diffs"].key, "0");
+}
+
+#[test]
+fn wrong_block_hash() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let mut header = header(BlockNumber(0), BlockHash::default());
+    header.block_hash = BlockHash(felt!("0x1234"));
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &header)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    assert_eq!(inconsistent_tables(&reader), vec!["headers".to_owned()]);
+}
+
+#[test]
+fn wrong_state_diff_commitment() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    // The header of block 0 with the state diff of block 1.
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &header(BlockNumber(0), BlockHash::default()))
+        .unwrap()
+        .append_state_diff(BlockNumber(0), state_diff(BlockNumber(1)))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    assert_eq!(inconsistent_tables(&reader), vec!["state_diffs".to_owned()]);
+}
@@ -75,9 +75,11 @@
 //! [`Starknet`]: https://starknet.io/
 //! [`libmdbx`]: https://docs.rs/libmdbx/latest/libmdbx/
 
+pub mod backup;
 pub mod base_layer;
 pub mod body;
 pub mod class;
+pub mod compaction;
 pub mod compiled_class;
 #[cfg(feature = "document_calls")]
 pub mod document_calls;
@@ -87,8 +89,12 @@ pub mod storage_metrics;
 pub mod compression_utils;
 pub mod db;
 pub mod header;
+pub mod integrity;
 pub mod mmap_file;
+pub mod patricia;
+pub mod pruning;
 mod serialization;
+pub mod snapshot;
 pub mod state;
 mod version;
 
@@ -103,13 +109,21 @@ pub mod test_utils;
 use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use body::events::EventIndex;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use compression_utils::{
+    dictionary_id,
+    Compression,
+    CompressionConfig,
+    DecodingDictionaries,
+    StorageCompressionConfig,
+};
 use db::db_stats::{DbTableStats, DbWholeStats};
 use db::serialization::{Key, NoVersionValueWrapper, ValueSerde, VersionZeroWrapper};
-use db::table_types::{CommonPrefix, NoValue, Table, TableType};
+use db::table_types::{CommonPrefix, DbCursorTrait, NoValue, Table, TableType};
 use mmap_file::{
     open_file,
     FileHandler,
@@ -119,19 +133,34 @@ use mmap_file::{
     Reader,
     Writer,
 };
-use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_optional_sub_config,
+    ser_param,
+    SerializeConfig,
+};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_proc_macros::latency_histogram;
+use pruning::HistoryPruningConfig;
 use serde::{Deserialize, Serialize};
+use snapshot::StateSnapshotError;
 use starknet_api::block::{BlockHash, BlockNumber, BlockSignature, StarknetVersion};
 use starknet_api::core::{ClassHash, ContractAddress, Nonce};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::state::{SierraContractClass, StateNumber, StorageKey, ThinStateDiff};
-use starknet_api::transaction::{Transaction, TransactionHash, TransactionOutput};
+use starknet_api::transaction::{
+    Event,
+    EventKey,
+    L1TransactionHash,
+    Transaction,
+    TransactionHash,
+    TransactionOutput,
+};
 use starknet_types_core::felt::Felt;
 use tracing::{debug, info, warn};
 use validator::Validate;
 use version::{StorageVersionError, Version};
+use zstd::dict::DecoderDictionary;
 
 use crate::body::TransactionIndex;
 use crate::db::table_types::SimpleTable;
@@ -150,14 +179,15 @@ use crate::db::{
 };
 use crate::header::StorageBlockHeader;
 use crate::mmap_file::MMapFileStats;
+use crate::patricia::GlobalTrieRoots;
 use crate::state::data::IndexedDeprecatedContractClass;
 use crate::version::{VersionStorageReader, VersionStorageWriter};
 
 // For more details on the storage version, see the module documentation.
 /// The current version of the storage state code.
-pub const STORAGE_VERSION_STATE: Version = Version { major: 4, minor: 0 };
+pub const STORAGE_VERSION_STATE: Version = Version { major: 4, minor: 1 };
 /// The current version of the storage blocks code.
-pub const STORAGE_VERSION_BLOCKS: Version = Version { major: 4, minor: 0 };
+pub const STORAGE_VERSION_BLOCKS: Version = Version { major: 4, minor: 1 };
 
 /// Opens a storage and returns a [`StorageReader`] and a [`StorageWriter`].
 pub fn open_storage(
@@ -175,6 +205,7 @@ pub fn open_storage(
         block_hash_to_number: db_writer.create_simple_table("block_hash_to_number")?,
         block_signatures: db_writer.create_simple_table("block_signatures")?,
         casms: db_writer.create_simple_table("casms")?,
+        compression_dictionaries: db_writer.create_simple_table("compression_dictionaries")?,
         contract_storage: db_writer.create_common_prefix_table("contract_storage")?,
         declared_classes: db_writer.create_simple_table("declared_classes")?,
         declared_classes_block: db_writer.create_simple_table("declared_classes_block")?,
@@ -182,11 +213,17 @@ pub fn open_storage(
             .create_simple_table("deprecated_declared_classes")?,
         deployed_contracts: db_writer.create_simple_table("deployed_contracts")?,
         events: db_writer.create_common_prefix_table("events")?,
+        events_by_key: db_writer.create_common_prefix_table("events_by_key")?,
+        global_trie_roots: db_writer.create_simple_table("global_trie_roots")?,
         headers: db_writer.create_simple_table("headers")?,
+        l1_handler_nonce_to_idx: db_writer.create_simple_table("l1_handler_nonce_to_idx")?,
+        l1_transaction_messages: db_writer.create_simple_table("l1_transaction_messages")?,
         markers: db_writer.create_simple_table("markers")?,
         nonces: db_writer.create_common_prefix_table("nonces")?,
+        patricia_nodes: db_writer.create_simple_table("patricia_nodes")?,
         file_offsets: db_writer.create_simple_table("file_offsets")?,
         state_diffs: db_writer.create_simple_table("state_diffs")?,
+        transaction_events: db_writer.create_simple_table("transaction_events")?,
         transaction_hash_to_idx: db_writer.create_simple_table("transaction_hash_to_idx")?,
         transaction_metadata: db_writer.create_simple_table("transaction_metadata")?,
 
@@ -194,20 +231,34 @@ pub fn open_storage(
         starknet_version: db_writer.create_simple_table("starknet_version")?,
         storage_version: db_writer.create_simple_table("storage_version")?,
     });
+    let files_compression = load_files_compression(
+        &mut db_writer,
+        &tables.compression_dictionaries,
+        &storage_config.compression_config,
+    )?;
     let (file_writers, file_readers) = open_storage_files(
         &storage_config.db_config,
         storage_config.mmap_file_config,
         db_reader.clone(),
         &tables.file_offsets,
+        files_compression,
     )?;
 
     let reader = StorageReader {
         db_reader,
         tables: tables.clone(),
         scope: storage_config.scope,
+        index_events_by_key: storage_config.index_events_by_key,
         file_readers,
     };
-    let writer = StorageWriter { db_writer, tables, scope: storage_config.scope, file_writers };
+    let writer = StorageWriter {
+        db_writer,
+        tables,
+        scope: storage_config.scope,
+        index_events_by_key: storage_config.index_events_by_key,
+        file_writers,
+        history_pruning_config: storage_config.history_pruning_config,
+    };
 
     let writer = set_version_if_needed(reader.clone(), writer)?;
     verify_storage_version(reader.clone())?;
@@ -413,6 +464,7 @@ pub struct StorageReader {
     file_readers: FileHandlers<RO>,
     tables: Arc<Tables>,
     scope: StorageScope,
+    index_events_by_key: bool,
 }
 
 impl StorageReader {
@@ -424,6 +476,7 @@ impl StorageReader {
             file_handlers: self.file_readers.clone(),
             tables: self.tables.clone(),
             scope: self.scope,
+            index_events_by_key: self.index_events_by_key,
         })
     }
 
@@ -455,6 +508,8 @@ pub struct StorageWriter {
     file_writers: FileHandlers<RW>,
     tables: Arc<Tables>,
     scope: StorageScope,
+    index_events_by_key: bool,
+    history_pruning_config: Option<HistoryPruningConfig>,
 }
 
 impl StorageWriter {
@@ -466,6 +521,7 @@ impl StorageWriter {
             file_handlers: self.file_writers.clone(),
             tables: self.tables.clone(),
             scope: self.scope,
+            index_events_by_key: self.index_events_by_key,
         })
     }
 }
@@ -477,6 +533,7 @@ pub struct StorageTxn<'env, Mode: TransactionKind> {
     file_handlers: FileHandlers<Mode>,
     tables: Arc<Tables>,
     scope: StorageScope,
+    index_events_by_key: bool,
 }
 
 impl StorageTxn<'_, RW> {
@@ -496,6 +553,9 @@ impl<Mode: TransactionKind> StorageTxn<'_, Mode> {
         if self.scope == StorageScope::StateOnly {
             let unused_tables = [
                 self.tables.events.name,
+                self.tables.events_by_key.name,
+                self.tables.l1_handler_nonce_to_idx.name,
+                self.tables.transaction_events.name,
                 self.tables.transaction_hash_to_idx.name,
                 self.tables.transaction_metadata.name,
             ];
@@ -520,6 +580,8 @@ struct_field_names! {
         block_hash_to_number: TableIdentifier<BlockHash, NoVersionValueWrapper<BlockNumber>, SimpleTable>,
         block_signatures: TableIdentifier<BlockNumber, VersionZeroWrapper<BlockSignature>, SimpleTable>,
         casms: TableIdentifier<ClassHash, VersionZeroWrapper<LocationInFile>, SimpleTable>,
+        // Maps the id of each zstd dictionary that data in the storage was compressed with to the dictionary.
+        compression_dictionaries: TableIdentifier<u32, NoVersionValueWrapper<Vec<u8>>, SimpleTable>,
         // Empirically, defining the common prefix as (ContractAddress, StorageKey) is better space-wise than defining the
         // common prefix only as ContractAddress.
         contract_storage: TableIdentifier<((ContractAddress, StorageKey), BlockNumber), NoVersionValueWrapper<Felt>, CommonPrefix>,
@@ -529,11 +591,23 @@ struct_field_names! {
         // TODO(dvir): consider use here also the CommonPrefix table type.
         deployed_contracts: TableIdentifier<(ContractAddress, BlockNumber), VersionZeroWrapper<ClassHash>, SimpleTable>,
         events: TableIdentifier<(ContractAddress, TransactionIndex), NoVersionValueWrapper<NoValue>, CommonPrefix>,
+        // Maps the first key of each event to the event and the address that emitted it.
+        events_by_key: TableIdentifier<(EventKey, EventIndex), NoVersionValueWrapper<ContractAddress>, CommonPrefix>,
+        global_trie_roots: TableIdentifier<BlockNumber, VersionZeroWrapper<GlobalTrieRoots>, SimpleTable>,
         headers: TableIdentifier<BlockNumber, VersionZeroWrapper<StorageBlockHeader>, SimpleTable>,
+        // Maps the nonce of each L1 to L2 message to the L1 handler transaction that consumed it.
+        l1_handler_nonce_to_idx: TableIdentifier<Nonce, NoVersionValueWrapper<TransactionIndex>, SimpleTable>,
+        // Maps each L1 transaction that sent messages to L2 to the nonces of these messages.
+        l1_transaction_messages: TableIdentifier<L1TransactionHash, VersionZeroWrapper<Vec<Nonce>>, SimpleTable>,
         markers: TableIdentifier<MarkerKind, VersionZeroWrapper<BlockNumber>, SimpleTable>,
         nonces: TableIdentifier<(ContractAddress, BlockNumber), VersionZeroWrapper<Nonce>, CommonPrefix>,
+        // Maps the key of a node in the Patricia tries of the global state to its value.
+        patricia_nodes: TableIdentifier<Vec<u8>, NoVersionValueWrapper<Vec<u8>>, SimpleTable>,
         file_offsets: TableIdentifier<OffsetKind, NoVersionValueWrapper<usize>, SimpleTable>,
         state_diffs: TableIdentifier<BlockNumber, VersionZeroWrapper<LocationInFile>, SimpleTable>,
+        // The events of transactions whose outputs were written without them, because the events of their block were
+        // appended separately from its body.
+        transaction_events: TableIdentifier<TransactionIndex, VersionZeroWrapper<Vec<Event>>, SimpleTable>,
         transaction_hash_to_idx: TableIdentifier<TransactionHash, NoVersionValueWrapper<TransactionIndex>, SimpleTable>,
         // TODO(dvir): consider not saving transaction hash and calculating it from the transaction on demand.
         transaction_metadata: TableIdentifier<TransactionIndex, VersionZeroWrapper<TransactionMetadata>, SimpleTable>,
@@ -610,6 +684,31 @@ pub enum StorageError {
          {block_number}."
     )]
     BlockSignatureForNonExistingBlock { block_number: BlockNumber, block_signature: BlockSignature },
+    #[error("Attempt to write the events of block {block_number}, whose body doesn't exist.")]
+    EventsForNonExistingBody { block_number: BlockNumber },
+    #[error(
+        "Attempt to write the events of {num_transactions_events} transactions to block \
+         {block_number}, which has {num_transactions} transactions."
+    )]
+    WrongNumberOfTransactionsEvents {
+        block_number: BlockNumber,
+        num_transactions: usize,
+        num_transactions_events: usize,
+    },
+    #[error(
+        "Attempt to prune the history up to block {block_number}, which is beyond the blocks \
+         whose data was fully written (up to {marker})."
+    )]
+    HistoryPruningBeyondMarker { block_number: BlockNumber, marker: BlockNumber },
+    #[error(
+        "Attempt to revert block {block_number}, whose history was already pruned (up to \
+         {history_pruning_marker})."
+    )]
+    RevertPrunedBlock { block_number: BlockNumber, history_pruning_marker: BlockNumber },
+    #[error(transparent)]
+    StateSnapshot(#[from] StateSnapshotError),
+    #[error("The file {path:?} is not a trained zstd dictionary.")]
+    InvalidCompressionDictionary { path: PathBuf },
 }
 
 /// A type alias that maps to std::result::Result<T, StorageError>.
@@ -624,19 +723,42 @@ pub struct StorageConfig {
     #[validate]
     pub mmap_file_config: MmapFileConfig,
     pub scope: StorageScope,
+    pub index_events_by_key: bool,
+    /// None if the history of all the blocks should be kept.
+    #[validate]
+    pub history_pruning_config: Option<HistoryPruningConfig>,
+    /// The compression settings of the state diffs and the classes.
+    #[validate]
+    pub compression_config: StorageCompressionConfig,
 }
 
 impl SerializeConfig for StorageConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        let mut dumped_config = BTreeMap::from_iter([ser_param(
-            "scope",
-            &self.scope,
-            "The categories of data saved in storage.",
-            ParamPrivacyInput::Public,
-        )]);
+        let mut dumped_config = BTreeMap::from_iter([
+            ser_param(
+                "scope",
+                &self.scope,
+                "The categories of data saved in storage.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "index_events_by_key",
+                &self.index_events_by_key,
+                "Whether to index the events by their first key, which speeds up filtering events \
+                 by keys. Only the events of blocks written while the index is enabled are \
+                 indexed.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
         dumped_config
             .extend(append_sub_config_name(self.mmap_file_config.dump(), "mmap_file_config"));
         dumped_config.extend(append_sub_config_name(self.db_config.dump(), "db_config"));
+        dumped_config
+            .extend(append_sub_config_name(self.compression_config.dump(), "compression_config"));
+        dumped_config.extend(ser_optional_sub_config(
+            &self.history_pruning_config,
+            "history_pruning_config",
+        ));
         dumped_config
     }
 }
@@ -655,8 +777,14 @@ pub struct DbStats {
 // Invariants:
 // - CompiledClass <= Class <= State <= Header
 // - Body <= Header
+// - Event <= Body
 // - BaseLayerBlock <= Header
-// Event is currently unsupported.
+// - HistoryPruning <= CompiledClass, HistoryPruning <= Body
+// EventsByKeyStart isn't a marker. It's the first block of the blocks whose events were all
+// indexed by their keys.
+// BaseLayerMessages isn't a block marker either. It's the first L1 block whose messages to L2
+// weren't indexed yet, and BaseLayerMessagesStart is the first L1 block whose messages to L2 were
+// indexed.
 pub(crate) enum MarkerKind {
     Header,
     Body,
@@ -665,6 +793,10 @@ pub(crate) enum MarkerKind {
     Class,
     CompiledClass,
     BaseLayerBlock,
+    HistoryPruning,
+    EventsByKeyStart,
+    BaseLayerMessages,
+    BaseLayerMessagesStart,
 }
 
 pub(crate) type MarkersTable<'env> =
@@ -678,23 +810,34 @@ struct FileHandlers<Mode: TransactionKind> {
     deprecated_contract_class: FileHandler<VersionZeroWrapper<DeprecatedContractClass>, Mode>,
     transaction_output: FileHandler<VersionZeroWrapper<TransactionOutput>, Mode>,
     transaction: FileHandler<VersionZeroWrapper<Transaction>, Mode>,
+    compression: Arc<FilesCompression>,
+}
+
+// The compression of the kinds of data in the files that have a compression config. The other
+// kinds of data are compressed by the default compression.
+#[derive(Debug)]
+struct FilesCompression {
+    state_diffs: Arc<Compression>,
+    classes: Arc<Compression>,
+    casms: Arc<Compression>,
+    deprecated_classes: Arc<Compression>,
 }
 
 impl FileHandlers<RW> {
     // Appends a thin state diff to the corresponding file and returns its location.
     #[latency_histogram("storage_file_handler_append_state_diff_latency_seconds", true)]
     fn append_state_diff(&self, thin_state_diff: &ThinStateDiff) -> LocationInFile {
-        self.clone().thin_state_diff.append(thin_state_diff)
+        self.compression.state_diffs.apply(|| self.clone().thin_state_diff.append(thin_state_diff))
     }
 
     // Appends a contract class to the corresponding file and returns its location.
     fn append_contract_class(&self, contract_class: &SierraContractClass) -> LocationInFile {
-        self.clone().contract_class.append(contract_class)
+        self.compression.classes.apply(|| self.clone().contract_class.append(contract_class))
     }
 
     // Appends a CASM to the corresponding file and returns its location.
     fn append_casm(&self, casm: &CasmContractClass) -> LocationInFile {
-        self.clone().casm.append(casm)
+        self.compression.casms.apply(|| self.clone().casm.append(casm))
     }
 
     // Appends a deprecated contract class to the corresponding file and returns its location.
@@ -702,7 +845,9 @@ impl FileHandlers<RW> {
         &self,
         deprecated_contract_class: &DeprecatedContractClass,
     ) -> LocationInFile {
-        self.clone().deprecated_contract_class.append(deprecated_contract_class)
+        self.compression
+            .deprecated_classes
+            .apply(|| self.clone().deprecated_contract_class.append(deprecated_contract_class))
     }
 
     // Appends a thin transaction output to the corresponding file and returns its location.
@@ -746,7 +891,9 @@ impl<Mode: TransactionKind> FileHandlers<Mode> {
         &self,
         location: LocationInFile,
     ) -> StorageResult<ThinStateDiff> {
-        self.thin_state_diff.get(location)?.ok_or(StorageError::DBInconsistency {
+        let thin_state_diff =
+            self.compression.state_diffs.apply(|| self.thin_state_diff.get(location))?;
+        thin_state_diff.ok_or(StorageError::DBInconsistency {
             msg: format!("ThinStateDiff at location {:?} not found.", location),
         })
     }
@@ -756,14 +903,17 @@ impl<Mode: TransactionKind> FileHandlers<Mode> {
         &self,
         location: LocationInFile,
     ) -> StorageResult<SierraContractClass> {
-        self.contract_class.get(location)?.ok_or(StorageError::DBInconsistency {
+        let contract_class =
+            self.compression.classes.apply(|| self.contract_class.get(location))?;
+        contract_class.ok_or(StorageError::DBInconsistency {
             msg: format!("ContractClass at location {:?} not found.", location),
         })
     }
 
     // Returns the CASM at the given location or an error in case it doesn't exist.
     fn get_casm_unchecked(&self, location: LocationInFile) -> StorageResult<CasmContractClass> {
-        self.casm.get(location)?.ok_or(StorageError::DBInconsistency {
+        let casm = self.compression.casms.apply(|| self.casm.get(location))?;
+        casm.ok_or(StorageError::DBInconsistency {
             msg: format!("CasmContractClass at location {:?} not found.", location),
         })
     }
@@ -774,7 +924,11 @@ impl<Mode: TransactionKind> FileHandlers<Mode> {
         &self,
         location: LocationInFile,
     ) -> StorageResult<DeprecatedContractClass> {
-        self.deprecated_contract_class.get(location)?.ok_or(StorageError::DBInconsistency {
+        let deprecated_contract_class = self
+            .compression
+            .deprecated_classes
+            .apply(|| self.deprecated_contract_class.get(location))?;
+        deprecated_contract_class.ok_or(StorageError::DBInconsistency {
             msg: format!("DeprecatedContractClass at location {:?} not found.", location),
         })
     }
@@ -798,21 +952,101 @@ impl<Mode: TransactionKind> FileHandlers<Mode> {
     }
 }
 
+// Stores the compression dictionaries of the config in the storage and returns the compression of
+// the files, which can decompress data that was compressed with any dictionary in the storage.
+fn load_files_compression(
+    db_writer: &mut DbWriter,
+    compression_dictionaries_table: &TableIdentifier<
+        u32,
+        NoVersionValueWrapper<Vec<u8>>,
+        SimpleTable,
+    >,
+    compression_config: &StorageCompressionConfig,
+) -> StorageResult<Arc<FilesCompression>> {
+    let read_dictionary = |config: &CompressionConfig| -> StorageResult<Option<(u32, Vec<u8>)>> {
+        let Some(path) = &config.dictionary_path else {
+            return Ok(None);
+        };
+        let dictionary = fs::read(path)?;
+        let id = dictionary_id(&dictionary)
+            .ok_or_else(|| StorageError::InvalidCompressionDictionary { path: path.clone() })?;
+        Ok(Some((id, dictionary)))
+    };
+    let state_diffs_dictionary = read_dictionary(&compression_config.state_diffs)?;
+    let classes_dictionary = read_dictionary(&compression_config.classes)?;
+    let casms_dictionary = read_dictionary(&compression_config.casms)?;
+    let deprecated_classes_dictionary = read_dictionary(&compression_config.deprecated_classes)?;
+
+    let db_transaction = db_writer.begin_rw_txn()?;
+    let table = db_transaction.open_table(compression_dictionaries_table)?;
+    for (id, dictionary) in [
+        &state_diffs_dictionary,
+        &classes_dictionary,
+        &casms_dictionary,
+        &deprecated_classes_dictionary,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        table.upsert(&db_transaction, id, dictionary)?;
+    }
+    let mut decoding_dictionaries = DecodingDictionaries::new();
+    let mut cursor = table.cursor(&db_transaction)?;
+    while let Some((id, dictionary)) = cursor.next()? {
+        info!("Loaded the zstd dictionary {id} from the storage.");
+        decoding_dictionaries.insert(id, Arc::new(DecoderDictionary::copy(&dictionary)));
+    }
+    drop(cursor);
+    db_transaction.commit()?;
+
+    let decoding_dictionaries = Arc::new(decoding_dictionaries);
+    let compression = |config: &CompressionConfig, dictionary: &Option<(u32, Vec<u8>)>| {
+        Arc::new(Compression::new(
+            config,
+            dictionary.as_ref().map(|(_, dictionary)| dictionary.as_slice()),
+            decoding_dictionaries.clone(),
+        ))
+    };
+    Ok(Arc::new(FilesCompression {
+        state_diffs: compression(&compression_config.state_diffs, &state_diffs_dictionary),
+        classes: compression(&compression_config.classes, &classes_dictionary),
+        casms: compression(&compression_config.casms, &casms_dictionary),
+        deprecated_classes: compression(
+            &compression_config.deprecated_classes,
+            &deprecated_classes_dictionary,
+        ),
+    }))
+}
+
 fn open_storage_files(
     db_config: &DbConfig,
     mmap_file_config: MmapFileConfig,
     db_reader: DbReader,
     file_offsets_table: &TableIdentifier<OffsetKind, NoVersionValueWrapper<usize>, SimpleTable>,
+    files_compression: Arc<FilesCompression>,
 ) -> StorageResult<(FileHandlers<RW>, FileHandlers<RO>)> {
     let db_transaction = db_reader.begin_ro_txn()?;
     let table = db_transaction.open_table(file_offsets_table)?;
 
+    // A compaction may have been interrupted before the compacted files replaced the original ones.
+    for offset_kind in [
+        OffsetKind::ThinStateDiff,
+        OffsetKind::ContractClass,
+        OffsetKind::Casm,
+        OffsetKind::DeprecatedContractClass,
+        OffsetKind::TransactionOutput,
+        OffsetKind::Transaction,
+    ] {
+        let offset = table.get(&db_transaction, &offset_kind)?.unwrap_or_default();
+        compaction::complete_interrupted_compaction(&db_config.path(), offset_kind, offset)?;
+    }
+
     // TODO(dvir): consider using a loop here to avoid code duplication.
     let thin_state_diff_offset =
         table.get(&db_transaction, &OffsetKind::ThinStateDiff)?.unwrap_or_default();
     let (thin_state_diff_writer, thin_state_diff_reader) = open_file(
         mmap_file_config.clone(),
-        db_config.path().join("thin_state_diff.dat"),
+        mmap_file_path(&db_config.path(), OffsetKind::ThinStateDiff),
         thin_state_diff_offset,
     )?;
 
@@ -820,19 +1054,22 @@ fn open_storage_files(
         table.get(&db_transaction, &OffsetKind::ContractClass)?.unwrap_or_default();
     let (contract_class_writer, contract_class_reader) = open_file(
         mmap_file_config.clone(),
-        db_config.path().join("contract_class.dat"),
+        mmap_file_path(&db_config.path(), OffsetKind::ContractClass),
         contract_class_offset,
     )?;
 
     let casm_offset = table.get(&db_transaction, &OffsetKind::Casm)?.unwrap_or_default();
-    let (casm_writer, casm_reader) =
-        open_file(mmap_file_config.clone(), db_config.path().join("casm.dat"), casm_offset)?;
+    let (casm_writer, casm_reader) = open_file(
+        mmap_file_config.clone(),
+        mmap_file_path(&db_config.path(), OffsetKind::Casm),
+        casm_offset,
+    )?;
 
     let deprecated_contract_class_offset =
         table.get(&db_transaction, &OffsetKind::DeprecatedContractClass)?.unwrap_or_default();
     let (deprecated_contract_class_writer, deprecated_contract_class_reader) = open_file(
         mmap_file_config.clone(),
-        db_config.path().join("deprecated_contract_class.dat"),
+        mmap_file_path(&db_config.path(), OffsetKind::DeprecatedContractClass),
         deprecated_contract_class_offset,
     )?;
 
@@ -840,14 +1077,17 @@ fn open_storage_files(
         table.get(&db_transaction, &OffsetKind::TransactionOutput)?.unwrap_or_default();
     let (transaction_output_writer, transaction_output_reader) = open_file(
         mmap_file_config.clone(),
-        db_config.path().join("transaction_output.dat"),
+        mmap_file_path(&db_config.path(), OffsetKind::TransactionOutput),
         transaction_output_offset,
     )?;
 
     let transaction_offset =
         table.get(&db_transaction, &OffsetKind::Transaction)?.unwrap_or_default();
-    let (transaction_writer, transaction_reader) =
-        open_file(mmap_file_config, db_config.path().join("transaction.dat"), transaction_offset)?;
+    let (transaction_writer, transaction_reader) = open_file(
+        mmap_file_config,
+        mmap_file_path(&db_config.path(), OffsetKind::Transaction),
+        transaction_offset,
+    )?;
 
     Ok((
         FileHandlers {
@@ -857,6 +1097,7 @@ fn open_storage_files(
             deprecated_contract_class: deprecated_contract_class_writer,
             transaction_output: transaction_output_writer,
             transaction: transaction_writer,
+            compression: files_compression.clone(),
         },
         FileHandlers {
             thin_state_diff: thin_state_diff_reader,
@@ -865,6 +1106,7 @@ fn open_storage_files(
             deprecated_contract_class: deprecated_contract_class_reader,
             transaction_output: transaction_output_reader,
             transaction: transaction_reader,
+            compression: files_compression,
         },
     ))
 }
@@ -886,6 +1128,25 @@ pub enum OffsetKind {
     Transaction,
 }
 
+impl OffsetKind {
+    // Returns the name of the file of this kind, as reported in the stats of the files.
+    pub(crate) fn file_name(self) -> &'static str {
+        match self {
+            OffsetKind::ThinStateDiff => "thin_state_diff",
+            OffsetKind::ContractClass => "contract_class",
+            OffsetKind::Casm => "casm",
+            OffsetKind::DeprecatedContractClass => "deprecated_contract_class",
+            OffsetKind::TransactionOutput => "transaction_output",
+            OffsetKind::Transaction => "transaction",
+        }
+    }
+}
+
+// Returns the path of the mmap file of the given kind.
+fn mmap_file_path(db_path: &Path, offset_kind: OffsetKind) -> PathBuf {
+    db_path.join(format!("{}.dat", offset_kind.file_name()))
+}
+
 /// A storage query. Used for benchmarking in the storage_benchmark binary.
 // TODO(dvir): add more queries (especially get casm).
 // TODO(dvir): consider move this, maybe to test_utils.
@@ -10,6 +10,7 @@ mod mmap_file_test;
 use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
 use std::marker::PhantomData;
 use std::path::PathBuf;
 use std::result;
@@ -191,7 +192,8 @@ pub(crate) fn open_file<V: ValueSerde>(
         mmap_file: shared_mmap_file.clone(),
         _mode: PhantomData,
     };
-    write_file_handler.grow_file_if_needed(0);
+    // A compacted file ends right at the offset, so it should be grown before appending to it.
+    write_file_handler.grow_file_if_needed(offset);
 
     let read_file_handler: FileHandler<V, RO> =
         FileHandler { memory_ptr: mmap_ptr, mmap_file: shared_mmap_file, _mode: PhantomData };
@@ -259,14 +261,79 @@ impl<V: ValueSerde, Mode: TransactionKind> Reader<V> for FileHandler<V, Mode> {
     /// Returns an object from the file.
     fn get(&self, location: LocationInFile) -> MmapFileResult<Option<V::Value>> {
         trace!("Reading object at location: {:?}", location);
-        let mut bytes = unsafe {
+        let mut bytes = self.get_bytes(location)?;
+        trace!("Deserializing object: {:?}", bytes);
+        Ok(V::deserialize(&mut bytes))
+    }
+}
+
+impl<V: ValueSerde, Mode: TransactionKind> FileHandler<V, Mode> {
+    // Returns the serialized object at the given location.
+    fn get_bytes(&self, location: LocationInFile) -> MmapFileResult<&[u8]> {
+        Ok(unsafe {
             std::slice::from_raw_parts(
                 self.memory_ptr.offset(location.offset.try_into()?),
                 location.len,
             )
-        };
-        trace!("Deserializing object: {:?}", bytes);
-        Ok(V::deserialize(&mut bytes))
+        })
+    }
+}
+
+impl<V: ValueSerde, Mode: TransactionKind> FileHandler<V, Mode> {
+    /// Copies the first `len` bytes of the file to a new file at the given path and syncs it to the
+    /// disk. The copied bytes must not be written concurrently, i.e. `len` must not exceed the
+    /// offset of the file.
+    pub(crate) fn copy_to_file(&self, path: PathBuf, len: usize) -> MmapFileResult<()> {
+        let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+        file.write_all(self.get_bytes(LocationInFile { offset: 0, len })?)?;
+        file.sync_all()?;
+        Ok(())
+    }
+}
+
+/// A new file to which the objects of a memory mapped file that are still in use are copied, in
+/// order to reclaim the space of the other objects. The compacted file is written without memory
+/// mapping and ends right after its last object.
+pub(crate) struct CompactedFile {
+    writer: BufWriter<File>,
+    offset: usize,
+}
+
+impl CompactedFile {
+    /// Creates an empty compacted file, overwriting the file at the given path if it exists.
+    pub(crate) fn create(path: PathBuf) -> MmapFileResult<Self> {
+        let file = OpenOptions::new().write(true).create(true).truncate(true).open(path)?;
+        Ok(Self { writer: BufWriter::new(file), offset: 0 })
+    }
+
+    /// Copies the object at the given location of the source file to the end of the compacted
+    /// file, returns the [`LocationInFile`] of the copy.
+    pub(crate) fn copy<V: ValueSerde, Mode: TransactionKind>(
+        &mut self,
+        source: &FileHandler<V, Mode>,
+        location: LocationInFile,
+    ) -> MmapFileResult<LocationInFile> {
+        self.writer.write_all(source.get_bytes(location)?)?;
+        let new_location = LocationInFile { offset: self.offset, len: location.len };
+        self.offset += location.len;
+        Ok(new_location)
+    }
+
+    /// Syncs the compacted file to the disk and returns its stats, where the reclaimed data is the
+    /// data of the source file that wasn't copied.
+    pub(crate) fn finish<V: ValueSerde, Mode: TransactionKind>(
+        self,
+        source: &FileHandler<V, Mode>,
+    ) -> MmapFileResult<MMapFileStats> {
+        let file = self.writer.into_inner().map_err(|err| err.into_error())?;
+        file.sync_all()?;
+        let source_offset = source.stats().offset;
+        debug!("Compacted a file from offset {} to offset {}.", source_offset, self.offset);
+        Ok(MMapFileStats {
+            size: self.offset,
+            offset: self.offset,
+            reclaimed: source_offset.saturating_sub(self.offset),
+        })
     }
 }
 
@@ -274,15 +341,25 @@ impl<V: ValueSerde, Mode: TransactionKind> Reader<V> for FileHandler<V, Mode> {
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, PartialOrd, Ord)]
 pub struct MMapFileStats {
     // The current size of the file.
-    size: usize,
+    pub(crate) size: usize,
     // The amount of data that has been written to the file.
-    offset: usize,
+    pub(crate) offset: usize,
+    // The amount of data that was removed from the file by compacting it. Only reported for the
+    // files written by a compaction.
+    pub(crate) reclaimed: usize,
+}
+
+impl MMapFileStats {
+    /// Returns the amount of data that has been written to the file.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
 }
 
 impl<V: ValueSerde, Mode: TransactionKind> FileHandler<V, Mode> {
     pub fn stats(&self) -> MMapFileStats {
         let mmap_file = self.mmap_file.lock().expect("Lock should not be poisoned");
-        MMapFileStats { size: mmap_file.size, offset: mmap_file.offset }
+        MMapFileStats { size: mmap_file.size, offset: mmap_file.offset, reclaimed: 0 }
     }
 }
 
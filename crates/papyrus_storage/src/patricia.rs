@@ -0,0 +1,210 @@
+//! Interface for handling the nodes of the Patricia tries of the global state.
+//!
+//! The nodes are stored by their keys, as created by the committer: a [`StarknetPrefix`] of the
+//! node kind followed by the hash of the node. This allows the committer to keep the tries on disk
+//! across blocks instead of getting all the nodes it needs as an input.
+//!
+//! Import [`PatriciaStorageReader`] and [`PatriciaStorageWriter`] to read and write Patricia nodes
+//! using a [`StorageTxn`]. Any [`StorageTxn`] implements [`TryReadOnlyStorage`], which proofs are
+//! extracted with, and which returns the storage errors. For the committer, any [`StorageTxn`] also
+//! implements [`ReadOnlyStorage`] and a RW one implements [`Storage`], where all the writes of a
+//! block can be done in a single transaction with [`Storage::mset`]. Since these traits are
+//! infallible, their methods panic on storage errors.
+//!
+//! The roots of the contracts trie and the classes trie after each block are stored as
+//! [`GlobalTrieRoots`], so that the tries can be traversed as of any block whose nodes were not
+//! deleted.
+//!
+//! [`StarknetPrefix`]: starknet_patricia::storage::storage_trait::StarknetPrefix
+//! # Example
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use papyrus_storage::open_storage;
+//! use papyrus_storage::patricia::PatriciaStorageReader;
+//! # use papyrus_storage::{db::DbConfig, StorageConfig};
+//! # use starknet_api::core::ChainId;
+//! use starknet_patricia::storage::storage_trait::{Storage, StorageKey, StorageValue};
+//!
+//! # let dir_handle = tempfile::tempdir().unwrap();
+//! # let dir = dir_handle.path().to_path_buf();
+//! # let db_config = DbConfig {
+//! #     path_prefix: dir,
+//! #     chain_id: ChainId::Mainnet,
+//! #     enforce_file_exists: false,
+//! #     min_size: 1 << 20,    // 1MB
+//! #     max_size: 1 << 35,    // 32GB
+//! #     growth_step: 1 << 26, // 64MB
+//! # };
+//! # let storage_config = StorageConfig{db_config, ..Default::default()};
+//! let key = StorageKey(b"patricia_node:0x1".to_vec());
+//! let value = StorageValue(vec![1, 2, 3]);
+//! let (reader, mut writer) = open_storage(storage_config)?;
+//! let mut txn = writer.begin_rw_txn()?;                     // Start a RW transaction.
+//! txn.mset(HashMap::from([(key.clone(), value.clone())]));  // Write the nodes of a block.
+//! txn.commit()?;                                            // Commit the transaction.
+//! let stored_value = reader.begin_ro_txn()?.get_patricia_node(&key)?;
+//! assert_eq!(stored_value, Some(value));
+//! # Ok::<(), papyrus_storage::StorageError>(())
+//! ```
+#[cfg(test)]
+#[path = "patricia_test.rs"]
+mod patricia_test;
+
+use std::collections::HashMap;
+
+use starknet_api::block::BlockNumber;
+use starknet_api::hash::StarkHash;
+use starknet_patricia::storage::errors::StorageError as PatriciaStorageError;
+use starknet_patricia::storage::storage_trait::{
+    ReadOnlyStorage,
+    Storage,
+    StorageKey,
+    StorageValue,
+    TryReadOnlyStorage,
+};
+
+use crate::db::table_types::Table;
+use crate::db::{TransactionKind, RW};
+use crate::{StorageResult, StorageTxn};
+
+/// The roots of the Patricia tries of the global state after a block.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct GlobalTrieRoots {
+    /// The root of the contracts trie.
+    pub contracts_trie_root: StarkHash,
+    /// The root of the classes trie.
+    pub classes_trie_root: StarkHash,
+}
+
+/// Interface for reading the nodes of the Patricia tries.
+pub trait PatriciaStorageReader {
+    /// Returns the value of the node with the given key.
+    fn get_patricia_node(&self, key: &StorageKey) -> StorageResult<Option<StorageValue>>;
+
+    /// Returns the roots of the tries after the given block, if they were written.
+    fn get_global_trie_roots(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<GlobalTrieRoots>>;
+}
+
+/// Interface for writing the nodes of the Patricia tries.
+pub trait PatriciaStorageWriter
+where
+    Self: Sized,
+{
+    /// Writes the given nodes, overwriting the values of existing keys.
+    // To enforce that no commit happen after a failure, we consume and return Self on success.
+    fn write_patricia_nodes(self, nodes: HashMap<StorageKey, StorageValue>) -> StorageResult<Self>;
+
+    /// Deletes the node with the given key and returns its value, if it exists.
+    fn delete_patricia_node(self, key: &StorageKey) -> StorageResult<(Self, Option<StorageValue>)>;
+
+    /// Writes the roots of the tries after the given block. The roots of a block are overwritten
+    /// when the block is reverted and committed again.
+    fn write_global_trie_roots(
+        self,
+        block_number: BlockNumber,
+        roots: &GlobalTrieRoots,
+    ) -> StorageResult<Self>;
+}
+
+impl<Mode: TransactionKind> PatriciaStorageReader for StorageTxn<'_, Mode> {
+    fn get_patricia_node(&self, key: &StorageKey) -> StorageResult<Option<StorageValue>> {
+        let patricia_nodes_table = self.open_table(&self.tables.patricia_nodes)?;
+        Ok(patricia_nodes_table.get(&self.txn, &key.0)?.map(StorageValue))
+    }
+
+    fn get_global_trie_roots(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<GlobalTrieRoots>> {
+        let global_trie_roots_table = self.open_table(&self.tables.global_trie_roots)?;
+        Ok(global_trie_roots_table.get(&self.txn, &block_number)?)
+    }
+}
+
+impl PatriciaStorageWriter for StorageTxn<'_, RW> {
+    fn write_patricia_nodes(self, nodes: HashMap<StorageKey, StorageValue>) -> StorageResult<Self> {
+        self.upsert_patricia_nodes(nodes)?;
+        Ok(self)
+    }
+
+    fn delete_patricia_node(self, key: &StorageKey) -> StorageResult<(Self, Option<StorageValue>)> {
+        let value = self.remove_patricia_node(key)?;
+        Ok((self, value))
+    }
+
+    fn write_global_trie_roots(
+        self,
+        block_number: BlockNumber,
+        roots: &GlobalTrieRoots,
+    ) -> StorageResult<Self> {
+        let global_trie_roots_table = self.open_table(&self.tables.global_trie_roots)?;
+        global_trie_roots_table.upsert(&self.txn, &block_number, roots)?;
+        Ok(self)
+    }
+}
+
+impl StorageTxn<'_, RW> {
+    fn upsert_patricia_nodes(
+        &self,
+        nodes: impl IntoIterator<Item = (StorageKey, StorageValue)>,
+    ) -> StorageResult<()> {
+        let patricia_nodes_table = self.open_table(&self.tables.patricia_nodes)?;
+        for (key, value) in nodes {
+            patricia_nodes_table.upsert(&self.txn, &key.0, &value.0)?;
+        }
+        Ok(())
+    }
+
+    fn remove_patricia_node(&self, key: &StorageKey) -> StorageResult<Option<StorageValue>> {
+        let value = self.get_patricia_node(key)?;
+        if value.is_some() {
+            let patricia_nodes_table = self.open_table(&self.tables.patricia_nodes)?;
+            patricia_nodes_table.delete(&self.txn, &key.0)?;
+        }
+        Ok(value)
+    }
+}
+
+impl<Mode: TransactionKind> ReadOnlyStorage for StorageTxn<'_, Mode> {
+    fn get(&self, key: &StorageKey) -> Option<StorageValue> {
+        self.get_patricia_node(key).expect("Failed to read a Patricia node from the storage")
+    }
+
+    fn mget(&self, keys: &[StorageKey]) -> Vec<Option<StorageValue>> {
+        keys.iter().map(|key| self.get(key)).collect()
+    }
+}
+
+impl<Mode: TransactionKind> TryReadOnlyStorage for StorageTxn<'_, Mode> {
+    fn try_mget(
+        &self,
+        keys: &[StorageKey],
+    ) -> Result<Vec<Option<StorageValue>>, PatriciaStorageError> {
+        keys.iter()
+            .map(|key| self.get_patricia_node(key))
+            .collect::<StorageResult<_>>()
+            .map_err(|err| PatriciaStorageError::ReadFailure(Box::new(err)))
+    }
+}
+
+impl Storage for StorageTxn<'_, RW> {
+    fn set(&mut self, key: StorageKey, value: StorageValue) -> Option<StorageValue> {
+        let old_value = self.get(&key);
+        self.upsert_patricia_nodes([(key, value)])
+            .expect("Failed to write a Patricia node to the storage");
+        old_value
+    }
+
+    fn mset(&mut self, key_to_value: HashMap<StorageKey, StorageValue>) {
+        self.upsert_patricia_nodes(key_to_value)
+            .expect("Failed to write Patricia nodes to the storage");
+    }
+
+    fn delete(&mut self, key: &StorageKey) -> Option<StorageValue> {
+        self.remove_patricia_node(key).expect("Failed to delete a Patricia node from the storage")
+    }
+}
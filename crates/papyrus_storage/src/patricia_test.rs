@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::felt;
+use starknet_patricia::storage::storage_trait::{
+    ReadOnlyStorage,
+    StarknetPrefix,
+    Storage,
+    StorageKey,
+    StorageValue,
+    TryReadOnlyStorage,
+};
+
+use crate::patricia::{GlobalTrieRoots, PatriciaStorageReader, PatriciaStorageWriter};
+use crate::test_utils::get_test_storage;
+
+fn node_key(prefix: StarknetPrefix, hash: u8) -> StorageKey {
+    StorageKey([prefix.to_storage_prefix(), b":".to_vec(), vec![hash; 32]].concat())
+}
+
+#[test]
+fn write_and_read_nodes() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let inner_node = (node_key(StarknetPrefix::InnerNode, 1), StorageValue(vec![1; 64]));
+    let leaf = (node_key(StarknetPrefix::StorageLeaf, 1), StorageValue(vec![2]));
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .write_patricia_nodes(HashMap::from([inner_node.clone(), leaf.clone()]))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_patricia_node(&inner_node.0).unwrap(), Some(inner_node.1));
+    assert_eq!(txn.get_patricia_node(&leaf.0).unwrap(), Some(leaf.1));
+    // The same hash under a different prefix is a different node.
+    assert_eq!(txn.get_patricia_node(&node_key(StarknetPrefix::StateTreeLeaf, 1)).unwrap(), None);
+}
+
+#[test]
+fn delete_node() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let (key, value) = (node_key(StarknetPrefix::InnerNode, 1), StorageValue(vec![1; 64]));
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .write_patricia_nodes(HashMap::from([(key.clone(), value.clone())]))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let (txn, deleted_value) = writer.begin_rw_txn().unwrap().delete_patricia_node(&key).unwrap();
+    assert_eq!(deleted_value, Some(value));
+    let (txn, deleted_value) = txn.delete_patricia_node(&key).unwrap();
+    assert_eq!(deleted_value, None);
+    txn.commit().unwrap();
+
+    assert_eq!(reader.begin_ro_txn().unwrap().get_patricia_node(&key).unwrap(), None);
+}
+
+#[test]
+fn storage_trait_in_a_single_transaction() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let nodes: HashMap<_, _> = (0..10)
+        .map(|hash| (node_key(StarknetPrefix::InnerNode, hash), StorageValue(vec![hash; 64])))
+        .collect();
+    let keys: Vec<_> = nodes.keys().cloned().collect();
+
+    let mut txn = writer.begin_rw_txn().unwrap();
+    txn.mset(nodes.clone());
+    // The nodes are visible inside the transaction before it's committed.
+    let values: Vec<_> = keys.iter().map(|key| nodes.get(key).cloned()).collect();
+    assert_eq!(txn.mget(&keys), values);
+    assert_eq!(reader.begin_ro_txn().unwrap().get_patricia_node(&keys[0]).unwrap(), None);
+
+    let new_value = StorageValue(vec![100]);
+    assert_eq!(txn.set(keys[0].clone(), new_value.clone()), nodes.get(&keys[0]).cloned());
+    assert_eq!(txn.delete(&keys[1]), nodes.get(&keys[1]).cloned());
+    txn.commit().unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_patricia_node(&keys[0]).unwrap(), Some(new_value));
+    assert_eq!(txn.get_patricia_node(&keys[1]).unwrap(), None);
+    assert_eq!(txn.get_patricia_node(&keys[2]).unwrap(), nodes.get(&keys[2]).cloned());
+}
+
+#[test]
+fn write_and_read_global_trie_roots() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let roots =
+        GlobalTrieRoots { contracts_trie_root: felt!(1_u8), classes_trie_root: felt!(2_u8) };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .write_global_trie_roots(BlockNumber(0), &roots)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_global_trie_roots(BlockNumber(0)).unwrap(), Some(roots));
+    assert_eq!(txn.get_global_trie_roots(BlockNumber(1)).unwrap(), None);
+    drop(txn);
+
+    // The roots of a reverted block are overwritten when it is committed again.
+    let new_roots = GlobalTrieRoots { classes_trie_root: felt!(3_u8), ..roots };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .write_global_trie_roots(BlockNumber(0), &new_roots)
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_global_trie_roots(BlockNumber(0)).unwrap(),
+        Some(new_roots)
+    );
+}
+
+#[test]
+fn read_only_storage_in_a_read_only_transaction() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let node = (node_key(StarknetPrefix::InnerNode, 1), StorageValue(vec![1; 64]));
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .write_patricia_nodes(HashMap::from([node.clone()]))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let missing_key = node_key(StarknetPrefix::InnerNode, 2);
+    assert_eq!(txn.get(&node.0), Some(node.1.clone()));
+    assert_eq!(
+        txn.try_mget(&[node.0.clone(), missing_key.clone()]).unwrap(),
+        vec![Some(node.1.clone()), None]
+    );
+    assert_eq!(txn.mget(&[node.0, missing_key]), vec![Some(node.1), None]);
+}
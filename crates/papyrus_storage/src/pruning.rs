@@ -0,0 +1,366 @@
+//! Interface for pruning the history of old blocks.
+//!
+//! Pruning a block removes its transactions, transaction outputs, events and state diff, and the
+//! values of the state that it overwrote. The headers, the classes and the current state are kept,
+//! so the state right after the last pruned block can still be read. The space of the pruned
+//! objects in the mmap files is only reclaimed by compacting the storage (see
+//! [`crate::compaction`]).
+//!
+//! The history pruning marker is the first block whose history wasn't pruned, i.e. the lowest block
+//! whose data is fully available.
+//!
+//! Import [`HistoryPruningStorageReader`] and [`HistoryPruningStorageWriter`] to read and write
+//! data related to the history pruning using a [`StorageTxn`].
+//! # Example
+//! ```
+//! use papyrus_storage::open_storage;
+//! use papyrus_storage::pruning::{HistoryPruningStorageReader, HistoryPruningStorageWriter};
+//! # use papyrus_storage::{db::DbConfig, StorageConfig};
+//! # use starknet_api::core::ChainId;
+//! use starknet_api::block::BlockNumber;
+//!
+//! # let dir_handle = tempfile::tempdir().unwrap();
+//! # let dir = dir_handle.path().to_path_buf();
+//! # let db_config = DbConfig {
+//! #     path_prefix: dir,
+//! #     chain_id: ChainId::Mainnet,
+//! #     enforce_file_exists: false,
+//! #     min_size: 1 << 20,    // 1MB
+//! #     max_size: 1 << 35,    // 32GB
+//! #     growth_step: 1 << 26, // 64MB
+//! # };
+//! # let storage_config = StorageConfig{db_config, ..Default::default()};
+//! let (reader, mut writer) = open_storage(storage_config)?;
+//! writer
+//!     .begin_rw_txn()?                        // Start a RW transaction.
+//!     .prune_history(BlockNumber(0))?         // Nothing to prune in an empty storage.
+//!     .commit()?; // Commit the transaction.
+//! let marker = reader.begin_ro_txn()?.get_history_pruning_marker()?;
+//! assert_eq!(marker, BlockNumber(0));
+//! # Ok::<(), papyrus_storage::StorageError>(())
+//! ```
+#[cfg(test)]
+#[path = "pruning_test.rs"]
+mod pruning_test;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use papyrus_config::converters::deserialize_seconds_to_duration;
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
+use starknet_api::state::ThinStateDiff;
+use starknet_api::transaction::{TransactionOffsetInBlock, TransactionOutput};
+use tracing::debug;
+use validator::Validate;
+
+use crate::body::{
+    delete_events_by_key,
+    delete_l1_handler_nonce,
+    BodyStorageReader,
+    TransactionIndex,
+};
+use crate::class::ClassStorageReader;
+use crate::compiled_class::CasmStorageReader;
+use crate::db::table_types::{DbCursorTrait, Table};
+use crate::db::{DbTransaction, TransactionKind, RW};
+use crate::state::{ContractStorageTable, DeployedContractsTable, NoncesTable, StateStorageReader};
+use crate::{MarkerKind, StorageError, StorageResult, StorageScope, StorageTxn, StorageWriter};
+
+/// The configuration of the history pruning.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Validate)]
+pub struct HistoryPruningConfig {
+    /// The number of most recent blocks whose history is kept.
+    #[validate(range(min = 1))]
+    pub blocks_to_keep: u64,
+    /// The maximal number of blocks pruned in a single write transaction.
+    #[validate(range(min = 1))]
+    pub max_blocks_per_txn: u64,
+    /// The time between two pruning iterations.
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub interval: Duration,
+}
+
+impl Default for HistoryPruningConfig {
+    fn default() -> Self {
+        Self { blocks_to_keep: 100_000, max_blocks_per_txn: 100, interval: Duration::from_secs(1) }
+    }
+}
+
+impl SerializeConfig for HistoryPruningConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "blocks_to_keep",
+                &self.blocks_to_keep,
+                "The number of most recent blocks whose transactions, events, state diffs and \
+                 historical state are kept.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_blocks_per_txn",
+                &self.max_blocks_per_txn,
+                "The maximal number of blocks pruned in a single write transaction.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "interval",
+                &self.interval.as_secs(),
+                "Time in seconds between two pruning iterations.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+/// Interface for reading data related to the history pruning.
+pub trait HistoryPruningStorageReader {
+    /// The history pruning marker is the first block whose history wasn't pruned.
+    fn get_history_pruning_marker(&self) -> StorageResult<BlockNumber>;
+}
+
+/// Interface for pruning the history of old blocks.
+pub trait HistoryPruningStorageWriter
+where
+    Self: Sized,
+{
+    /// Prunes the history of the blocks from the history pruning marker up to `block_number`
+    /// (exclusive) and advances the marker to `block_number`.
+    // To enforce that no commit happen after a failure, we consume and return Self on success.
+    fn prune_history(self, block_number: BlockNumber) -> StorageResult<Self>;
+}
+
+impl<Mode: TransactionKind> HistoryPruningStorageReader for StorageTxn<'_, Mode> {
+    fn get_history_pruning_marker(&self) -> StorageResult<BlockNumber> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        Ok(markers_table.get(&self.txn, &MarkerKind::HistoryPruning)?.unwrap_or_default())
+    }
+}
+
+// The data of a pruned block is partially deleted, so it can't be reverted.
+pub(crate) fn verify_block_not_pruned<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    block_number: BlockNumber,
+) -> StorageResult<()> {
+    let history_pruning_marker = txn.get_history_pruning_marker()?;
+    if block_number < history_pruning_marker {
+        return Err(StorageError::RevertPrunedBlock { block_number, history_pruning_marker });
+    }
+    Ok(())
+}
+
+impl HistoryPruningStorageWriter for StorageTxn<'_, RW> {
+    fn prune_history(self, block_number: BlockNumber) -> StorageResult<Self> {
+        let marker = self.get_history_pruning_marker()?;
+        if block_number <= marker {
+            return Ok(self);
+        }
+        // Blocks whose data wasn't written yet can't be pruned, and the state diffs of blocks whose
+        // classes weren't written yet are still needed to sync the classes.
+        let prunable_marker = self.get_prunable_marker()?;
+        if block_number > prunable_marker {
+            return Err(StorageError::HistoryPruningBeyondMarker {
+                block_number,
+                marker: prunable_marker,
+            });
+        }
+
+        debug!("Pruning the history of blocks [{marker}, {block_number}).");
+        for pruned_block_number in marker.iter_up_to(block_number) {
+            if self.scope != StorageScope::StateOnly {
+                self.prune_body(pruned_block_number)?;
+            }
+            self.prune_state_diff(pruned_block_number)?;
+        }
+
+        let markers_table = self.open_table(&self.tables.markers)?;
+        markers_table.upsert(&self.txn, &MarkerKind::HistoryPruning, &block_number)?;
+        Ok(self)
+    }
+}
+
+impl StorageTxn<'_, RW> {
+    fn get_prunable_marker(&self) -> StorageResult<BlockNumber> {
+        Ok([
+            self.get_body_marker()?,
+            self.get_state_marker()?,
+            self.get_class_marker()?,
+            self.get_compiled_class_marker()?,
+        ]
+        .into_iter()
+        .min()
+        .expect("Markers should not be empty."))
+    }
+
+    fn prune_body(&self, block_number: BlockNumber) -> StorageResult<()> {
+        let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
+        let transaction_hash_to_idx_table =
+            self.open_table(&self.tables.transaction_hash_to_idx)?;
+        let events_table = self.open_table(&self.tables.events)?;
+        let events_by_key_table = self.open_table(&self.tables.events_by_key)?;
+        let l1_handler_nonce_to_idx_table =
+            self.open_table(&self.tables.l1_handler_nonce_to_idx)?;
+        let transaction_events_table = self.open_table(&self.tables.transaction_events)?;
+
+        let transaction_outputs = self.get_block_transaction_outputs(block_number)?.ok_or(
+            StorageError::DBInconsistency {
+                msg: format!("Missing transaction outputs for block {block_number}."),
+            },
+        )?;
+        let transaction_hashes = self.get_block_transaction_hashes(block_number)?.ok_or(
+            StorageError::DBInconsistency {
+                msg: format!("Missing transaction hashes for block {block_number}."),
+            },
+        )?;
+        for (offset, (tx_hash, tx_output)) in
+            transaction_hashes.iter().zip(transaction_outputs.iter()).enumerate()
+        {
+            let tx_index = TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+            for event in tx_output.events().iter() {
+                events_table.delete(&self.txn, &(event.from_address, tx_index))?;
+            }
+            delete_events_by_key(tx_output, &self.txn, &events_by_key_table, tx_index)?;
+            transaction_events_table.delete(&self.txn, &tx_index)?;
+            if let TransactionOutput::L1Handler(_) = tx_output {
+                let tx = self.get_transaction(tx_index)?.ok_or(StorageError::DBInconsistency {
+                    msg: format!("Missing transaction {tx_index:?}."),
+                })?;
+                delete_l1_handler_nonce(&tx, &self.txn, &l1_handler_nonce_to_idx_table, tx_index)?;
+            }
+            transaction_hash_to_idx_table.delete(&self.txn, tx_hash)?;
+            transaction_metadata_table.delete(&self.txn, &tx_index)?;
+        }
+        Ok(())
+    }
+
+    fn prune_state_diff(&self, block_number: BlockNumber) -> StorageResult<()> {
+        let deployed_contracts_table = self.open_table(&self.tables.deployed_contracts)?;
+        let nonces_table = self.open_table(&self.tables.nonces)?;
+        let storage_table = self.open_table(&self.tables.contract_storage)?;
+        let state_diffs_table = self.open_table(&self.tables.state_diffs)?;
+
+        let thin_state_diff =
+            self.get_state_diff(block_number)?.ok_or(StorageError::DBInconsistency {
+                msg: format!("Missing state diff for block {block_number}."),
+            })?;
+        delete_overwritten_class_hashes(
+            &self.txn,
+            block_number,
+            &thin_state_diff,
+            &deployed_contracts_table,
+        )?;
+        delete_overwritten_nonces(&self.txn, block_number, &thin_state_diff, &nonces_table)?;
+        delete_overwritten_storage_values(
+            &self.txn,
+            block_number,
+            &thin_state_diff,
+            &storage_table,
+        )?;
+        state_diffs_table.delete(&self.txn, &block_number)?;
+        Ok(())
+    }
+}
+
+impl StorageWriter {
+    /// Returns the configuration of the history pruning, if it's enabled.
+    pub fn history_pruning_config(&self) -> Option<HistoryPruningConfig> {
+        self.history_pruning_config
+    }
+
+    /// Prunes the history of the oldest blocks, in a single write transaction, such that the
+    /// history of the last `blocks_to_keep` blocks is kept. At most `max_blocks_per_txn` blocks are
+    /// pruned. Returns the history pruning marker after the pruning.
+    ///
+    /// Does nothing if the history pruning is disabled.
+    pub fn prune_history_batch(&mut self) -> StorageResult<BlockNumber> {
+        let history_pruning_config = self.history_pruning_config;
+        let txn = self.begin_rw_txn()?;
+        let marker = txn.get_history_pruning_marker()?;
+        let Some(HistoryPruningConfig { blocks_to_keep, max_blocks_per_txn, .. }) =
+            history_pruning_config
+        else {
+            return Ok(marker);
+        };
+        let target = BlockNumber(txn.get_prunable_marker()?.0.saturating_sub(blocks_to_keep));
+        let block_number = target.min(BlockNumber(marker.0.saturating_add(max_blocks_per_txn)));
+        if block_number <= marker {
+            return Ok(marker);
+        }
+        txn.prune_history(block_number)?.commit()?;
+        Ok(block_number)
+    }
+}
+
+// Deletes the class hashes of the contracts that were replaced in the given block, keeping the one
+// set in the block.
+fn delete_overwritten_class_hashes<'env>(
+    txn: &'env DbTransaction<'env, RW>,
+    block_number: BlockNumber,
+    thin_state_diff: &ThinStateDiff,
+    deployed_contracts_table: &'env DeployedContractsTable<'env>,
+) -> StorageResult<()> {
+    for contract_address in
+        thin_state_diff.deployed_contracts.keys().chain(thin_state_diff.replaced_classes.keys())
+    {
+        loop {
+            let mut cursor = deployed_contracts_table.cursor(txn)?;
+            cursor.lower_bound(&(*contract_address, block_number))?;
+            match cursor.prev()? {
+                Some(((got_address, got_block_number), _)) if got_address == *contract_address => {
+                    deployed_contracts_table.delete(txn, &(got_address, got_block_number))?;
+                }
+                _ => break,
+            }
+        }
+    }
+    Ok(())
+}
+
+// Deletes the nonces of the contracts whose nonce was set in the given block, keeping the one set
+// in the block.
+fn delete_overwritten_nonces<'env>(
+    txn: &'env DbTransaction<'env, RW>,
+    block_number: BlockNumber,
+    thin_state_diff: &ThinStateDiff,
+    nonces_table: &'env NoncesTable<'env>,
+) -> StorageResult<()> {
+    for contract_address in thin_state_diff.nonces.keys() {
+        loop {
+            let mut cursor = nonces_table.cursor(txn)?;
+            cursor.lower_bound(&(*contract_address, block_number))?;
+            match cursor.prev()? {
+                Some(((got_address, got_block_number), _)) if got_address == *contract_address => {
+                    nonces_table.delete(txn, &(got_address, got_block_number))?;
+                }
+                _ => break,
+            }
+        }
+    }
+    Ok(())
+}
+
+// Deletes the storage values that were set in the given block, keeping the ones set in the block.
+fn delete_overwritten_storage_values<'env>(
+    txn: &'env DbTransaction<'env, RW>,
+    block_number: BlockNumber,
+    thin_state_diff: &ThinStateDiff,
+    storage_table: &'env ContractStorageTable<'env>,
+) -> StorageResult<()> {
+    for (address, storage_entries) in &thin_state_diff.storage_diffs {
+        for key in storage_entries.keys() {
+            loop {
+                let mut cursor = storage_table.cursor(txn)?;
+                cursor.lower_bound(&((*address, *key), block_number))?;
+                match cursor.prev()? {
+                    Some((got_key, _)) if got_key.0 == (*address, *key) => {
+                        storage_table.delete(txn, &got_key)?;
+                    }
+                    _ => break,
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,237 @@
+use assert_matches::assert_matches;
+use indexmap::indexmap;
+use papyrus_test_utils::get_test_block;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockNumber};
+use starknet_api::core::Nonce;
+use starknet_api::state::{StateNumber, ThinStateDiff};
+use starknet_api::transaction::{EventIndexInTransactionOutput, TransactionOffsetInBlock};
+use starknet_api::{class_hash, contract_address, felt, storage_key};
+
+use crate::body::events::{EventIndex, EventsReader};
+use crate::body::{BodyStorageReader, BodyStorageWriter, TransactionIndex};
+use crate::class::ClassStorageWriter;
+use crate::pruning::{
+    HistoryPruningConfig,
+    HistoryPruningStorageReader,
+    HistoryPruningStorageWriter,
+};
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::{get_test_config, get_test_storage};
+use crate::{open_storage, StorageError, StorageWriter};
+
+const N_BLOCKS: usize = 4;
+const TXS_PER_BLOCK: usize = 2;
+
+// Appends N_BLOCKS blocks. In block i the nonce and the storage value of the same contract are set
+// to i + 1, and the contract's class is replaced in the middle block.
+fn append_blocks(writer: &mut StorageWriter) -> Vec<BlockBody> {
+    let address = contract_address!("0x100");
+    let key = storage_key!("0x10");
+    let body = get_test_block(N_BLOCKS * TXS_PER_BLOCK, Some(1), None, None).body;
+
+    let mut bodies = Vec::new();
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for i in 0..N_BLOCKS {
+        let block_number = BlockNumber(i.try_into().unwrap());
+        let txs = i * TXS_PER_BLOCK..(i + 1) * TXS_PER_BLOCK;
+        let block_body = BlockBody {
+            transactions: body.transactions[txs.clone()].to_vec(),
+            transaction_outputs: body.transaction_outputs[txs.clone()].to_vec(),
+            transaction_hashes: body.transaction_hashes[txs].to_vec(),
+        };
+        let value = felt!(u64::try_from(i + 1).unwrap());
+        let mut state_diff = ThinStateDiff {
+            nonces: indexmap! { address => Nonce(value) },
+            storage_diffs: indexmap! { address => indexmap! { key => value } },
+            ..Default::default()
+        };
+        if i == 0 {
+            state_diff.deployed_contracts = indexmap! { address => class_hash!("0x1") };
+        }
+        if i == N_BLOCKS / 2 {
+            state_diff.replaced_classes = indexmap! { address => class_hash!("0x2") };
+        }
+        txn = txn
+            .append_body(block_number, block_body.clone())
+            .unwrap()
+            .append_state_diff(block_number, state_diff)
+            .unwrap()
+            .append_classes(block_number, &[], &[])
+            .unwrap();
+        bodies.push(block_body);
+    }
+    txn.commit().unwrap();
+    bodies
+}
+
+#[test]
+fn prune_history() {
+    let address = contract_address!("0x100");
+    let key = storage_key!("0x10");
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let bodies = append_blocks(&mut writer);
+
+    let pruned_block = BlockNumber(2);
+    writer.begin_rw_txn().unwrap().prune_history(pruned_block).unwrap().commit().unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_history_pruning_marker().unwrap(), pruned_block);
+
+    // The history of the pruned blocks is gone.
+    for block_number in BlockNumber(0).iter_up_to(pruned_block) {
+        assert!(txn.get_state_diff(block_number).unwrap().is_none());
+        assert!(txn.get_block_transactions(block_number).unwrap().is_none());
+        assert!(txn.get_block_transaction_hashes(block_number).unwrap().is_none());
+        assert!(txn.get_block_transactions_count(block_number).unwrap().is_none());
+        let body = &bodies[usize::try_from(block_number.0).unwrap()];
+        for tx_hash in &body.transaction_hashes {
+            assert!(txn.get_transaction_idx_by_hash(tx_hash).unwrap().is_none());
+        }
+    }
+    let first_event_index = EventIndex(
+        TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(0)),
+        EventIndexInTransactionOutput(0),
+    );
+    let last_block = BlockNumber(N_BLOCKS.try_into().unwrap());
+    let events_blocks = txn
+        .iter_events(None, first_event_index, last_block, None)
+        .unwrap()
+        .map(|((_, EventIndex(TransactionIndex(block_number, _), _)), _)| block_number)
+        .collect::<Vec<_>>();
+    assert!(!events_blocks.is_empty());
+    assert!(events_blocks.iter().all(|block_number| *block_number >= pruned_block));
+
+    // The history of the other blocks is kept.
+    for block_number in pruned_block.iter_up_to(last_block) {
+        assert!(txn.get_state_diff(block_number).unwrap().is_some());
+        assert_eq!(
+            txn.get_block_transaction_hashes(block_number).unwrap().unwrap(),
+            bodies[usize::try_from(block_number.0).unwrap()].transaction_hashes
+        );
+    }
+
+    // The state right after the last pruned block and the current state are intact.
+    let state_reader = txn.get_state_reader().unwrap();
+    for (state_number, value, class_hash) in [
+        (StateNumber::right_before_block(pruned_block), felt!(2_u8), class_hash!("0x1")),
+        (StateNumber::right_before_block(BlockNumber(3)), felt!(3_u8), class_hash!("0x2")),
+        (StateNumber::right_before_block(last_block), felt!(4_u8), class_hash!("0x2")),
+    ] {
+        assert_eq!(state_reader.get_storage_at(state_number, &address, &key).unwrap(), value);
+        assert_eq!(state_reader.get_nonce_at(state_number, &address).unwrap(), Some(Nonce(value)));
+        assert_eq!(
+            state_reader.get_class_hash_at(state_number, &address).unwrap(),
+            Some(class_hash)
+        );
+    }
+}
+
+#[test]
+fn prune_history_is_idempotent() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer);
+
+    writer.begin_rw_txn().unwrap().prune_history(BlockNumber(2)).unwrap().commit().unwrap();
+    // Pruning up to an already pruned block does nothing.
+    writer.begin_rw_txn().unwrap().prune_history(BlockNumber(1)).unwrap().commit().unwrap();
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_history_pruning_marker().unwrap(),
+        BlockNumber(2)
+    );
+}
+
+#[test]
+fn prune_history_beyond_marker_fails() {
+    let ((_reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer);
+
+    let block_number = BlockNumber((N_BLOCKS + 1).try_into().unwrap());
+    let Err(err) = writer.begin_rw_txn().unwrap().prune_history(block_number) else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(
+        err,
+        StorageError::HistoryPruningBeyondMarker { block_number: got_block_number, marker }
+        if got_block_number == block_number && marker == BlockNumber(N_BLOCKS.try_into().unwrap())
+    );
+}
+
+#[test]
+fn revert_pruned_block_fails() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer);
+    let last_block = BlockNumber((N_BLOCKS - 1).try_into().unwrap());
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .prune_history(last_block.unchecked_next())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let Err(err) = writer.begin_rw_txn().unwrap().revert_body(last_block) else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(
+        err,
+        StorageError::RevertPrunedBlock { block_number, history_pruning_marker }
+        if block_number == last_block && history_pruning_marker == last_block.unchecked_next()
+    );
+    let Err(err) = writer.begin_rw_txn().unwrap().revert_state_diff(last_block) else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(err, StorageError::RevertPrunedBlock { .. });
+
+    // Nothing was reverted.
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_body_marker().unwrap(), last_block.unchecked_next());
+    assert_eq!(txn.get_state_marker().unwrap(), last_block.unchecked_next());
+}
+
+#[test]
+fn revert_block_above_pruning_marker() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer);
+    let last_block = BlockNumber((N_BLOCKS - 1).try_into().unwrap());
+    writer.begin_rw_txn().unwrap().prune_history(last_block).unwrap().commit().unwrap();
+
+    let (txn, reverted_body) = writer.begin_rw_txn().unwrap().revert_body(last_block).unwrap();
+    let (txn, reverted_state_diff) = txn.revert_state_diff(last_block).unwrap();
+    txn.commit().unwrap();
+    assert!(reverted_body.is_some());
+    assert!(reverted_state_diff.is_some());
+    assert_eq!(reader.begin_ro_txn().unwrap().get_body_marker().unwrap(), last_block);
+}
+
+#[test]
+fn prune_history_batch() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.history_pruning_config = Some(HistoryPruningConfig {
+        blocks_to_keep: 1,
+        max_blocks_per_txn: 2,
+        ..Default::default()
+    });
+    let (reader, mut writer) = open_storage(config).unwrap();
+    append_blocks(&mut writer);
+
+    // At most max_blocks_per_txn blocks are pruned in each batch, until only the last
+    // blocks_to_keep blocks are left.
+    assert_eq!(writer.prune_history_batch().unwrap(), BlockNumber(2));
+    assert_eq!(writer.prune_history_batch().unwrap(), BlockNumber(3));
+    assert_eq!(writer.prune_history_batch().unwrap(), BlockNumber(3));
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_history_pruning_marker().unwrap(),
+        BlockNumber(3)
+    );
+}
+
+#[test]
+fn prune_history_batch_disabled() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer);
+
+    assert!(writer.history_pruning_config().is_none());
+    assert_eq!(writer.prune_history_batch().unwrap(), BlockNumber(0));
+    assert!(reader.begin_ro_txn().unwrap().get_state_diff(BlockNumber(0)).unwrap().is_some());
+}
@@ -20,6 +20,8 @@ use parity_scale_codec::{Decode, Encode};
 use primitive_types::H160;
 use starknet_api::block::{
     BlockHash,
+    BlockHeader,
+    BlockHeaderWithoutHash,
     BlockNumber,
     BlockSignature,
     BlockStatus,
@@ -112,6 +114,7 @@ use starknet_api::transaction::{
     L1HandlerTransaction,
     L1HandlerTransactionOutput,
     L1ToL2Payload,
+    L1TransactionHash,
     L2ToL1Payload,
     MessageToL1,
     MessageToL2,
@@ -139,6 +142,7 @@ use crate::db::serialization::{StorageSerde, StorageSerdeError};
 use crate::db::table_types::NoValue;
 use crate::header::StorageBlockHeader;
 use crate::mmap_file::LocationInFile;
+use crate::patricia::GlobalTrieRoots;
 #[cfg(test)]
 use crate::serialization::serializers_test::{create_storage_serde_test, StorageSerdeTest};
 use crate::state::data::IndexedDeprecatedContractClass;
@@ -156,6 +160,29 @@ auto_storage_serde! {
         pub l1_data_gas: ResourceBounds,
     }
     pub struct BlockHash(pub StarkHash);
+    pub struct BlockHeader {
+        pub block_hash: BlockHash,
+        pub block_header_without_hash: BlockHeaderWithoutHash,
+        pub state_diff_commitment: Option<StateDiffCommitment>,
+        pub state_diff_length: Option<usize>,
+        pub transaction_commitment: Option<TransactionCommitment>,
+        pub event_commitment: Option<EventCommitment>,
+        pub n_transactions: usize,
+        pub n_events: usize,
+        pub receipt_commitment: Option<ReceiptCommitment>,
+    }
+    pub struct BlockHeaderWithoutHash {
+        pub parent_hash: BlockHash,
+        pub block_number: BlockNumber,
+        pub l1_gas_price: GasPricePerToken,
+        pub l1_data_gas_price: GasPricePerToken,
+        pub l2_gas_price: GasPricePerToken,
+        pub state_root: GlobalRoot,
+        pub sequencer: SequencerContractAddress,
+        pub timestamp: BlockTimestamp,
+        pub l1_da_mode: L1DataAvailabilityMode,
+        pub starknet_version: StarknetVersion,
+    }
     pub struct StorageBlockHeader {
         pub block_hash: BlockHash,
         pub parent_hash: BlockHash,
@@ -297,6 +324,10 @@ auto_storage_serde! {
         pub l2_gas: GasAmount,
     }
     pub struct GlobalRoot(pub StarkHash);
+    pub struct GlobalTrieRoots {
+        pub contracts_trie_root: StarkHash,
+        pub classes_trie_root: StarkHash,
+    }
     pub struct H160(pub [u8; 20]);
     pub struct IndexedDeprecatedContractClass {
         pub block_number: BlockNumber,
@@ -316,6 +347,7 @@ auto_storage_serde! {
         Blob = 1,
     }
     pub struct L1ToL2Payload(pub Vec<Felt>);
+    pub struct L1TransactionHash(pub [u8; 32]);
     pub struct L2ToL1Payload(pub Vec<Felt>);
     enum MarkerKind {
         Header = 0,
@@ -325,6 +357,10 @@ auto_storage_serde! {
         Class = 4,
         CompiledClass = 5,
         BaseLayerBlock = 6,
+        HistoryPruning = 7,
+        EventsByKeyStart = 8,
+        BaseLayerMessages = 9,
+        BaseLayerMessagesStart = 10,
     }
     pub struct MessageToL1 {
         pub to_address: EthAddress,
@@ -510,6 +546,7 @@ auto_storage_serde! {
     (ContractAddress, StorageKey);
     (ContractAddress, TransactionIndex);
     ((ContractAddress, StorageKey), BlockNumber);
+    (EventKey, EventIndex);
     (usize, Vec<Hint>);
     (usize, Vec<String>);
 }
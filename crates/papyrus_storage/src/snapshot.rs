@@ -0,0 +1,535 @@
+//! Export and import of state snapshots, for bootstrapping a node without replaying the state
+//! diffs from genesis.
+//!
+//! A state snapshot of block N holds the state right after block N: the storage values, the
+//! nonces and the class hashes of the contracts, the classes and the CASMs declared up to block N,
+//! and the headers of the blocks up to block N. Every value is kept with the block that set it.
+//! The snapshot is read in a single RO transaction, so it is consistent even if the storage is
+//! written concurrently.
+//!
+//! Importing a snapshot into an empty [`StorageScope::StateOnly`] storage writes this data and
+//! sets the markers to N + 1, so sync can continue from block N + 1. The history of the blocks up
+//! to N isn't part of the snapshot, so after the import these blocks are considered pruned (see
+//! [`crate::pruning`]). In particular, their state diffs are unavailable.
+//!
+//! # Compiled class hashes
+//! The storage keeps the compiled class hash of a class only in the state diff of the block that
+//! declared it, so the snapshot doesn't hold the compiled class hashes of the classes declared up
+//! to block N, and they are unavailable after the import. The CASMs of these classes are imported,
+//! so their compiled class hashes can be recomputed from them if needed.
+//!
+//! # Format
+//! The magic bytes [`SNAPSHOT_MAGIC`], the format version and the block number, followed by the
+//! sections of the storage values, the nonces, the class hashes, the classes, the deprecated
+//! classes and the headers. Each entry of a section is preceded by a 1 byte, and each section ends
+//! with a 0 byte. The snapshot ends with the SHA-256 checksum of all the preceding bytes.
+//!
+//! # Example
+//! ```
+//! use papyrus_storage::class::ClassStorageWriter;
+//! use papyrus_storage::compiled_class::CasmStorageWriter;
+//! use papyrus_storage::header::HeaderStorageWriter;
+//! use papyrus_storage::open_storage;
+//! use papyrus_storage::snapshot::{export_state_snapshot, import_state_snapshot};
+//! use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
+//! # use papyrus_storage::{db::DbConfig, StorageConfig, StorageScope};
+//! # use starknet_api::core::ChainId;
+//! use starknet_api::block::{BlockHeader, BlockNumber};
+//! use starknet_api::state::ThinStateDiff;
+//!
+//! # let make_config = |dir: &std::path::Path, scope| StorageConfig {
+//! #     db_config: DbConfig {
+//! #         path_prefix: dir.to_path_buf(),
+//! #         chain_id: ChainId::Mainnet,
+//! #         enforce_file_exists: false,
+//! #         min_size: 1 << 20,    // 1MB
+//! #         max_size: 1 << 35,    // 32GB
+//! #         growth_step: 1 << 26, // 64MB
+//! #     },
+//! #     scope,
+//! #     ..Default::default()
+//! # };
+//! # let source_dir = tempfile::tempdir().unwrap();
+//! # let target_dir = tempfile::tempdir().unwrap();
+//! let (reader, mut writer) =
+//!     open_storage(make_config(source_dir.path(), StorageScope::FullArchive))?;
+//! writer
+//!     .begin_rw_txn()?
+//!     .append_header(BlockNumber(0), &BlockHeader::default())?
+//!     .append_state_diff(BlockNumber(0), ThinStateDiff::default())?
+//!     .append_classes(BlockNumber(0), &[], &[])?
+//!     .commit()?;
+//!
+//! let mut snapshot = Vec::new();
+//! export_state_snapshot(&reader, BlockNumber(0), &mut snapshot)?;
+//!
+//! let (reader, mut writer) =
+//!     open_storage(make_config(target_dir.path(), StorageScope::StateOnly))?;
+//! let block_number = import_state_snapshot(std::io::Cursor::new(snapshot), &mut writer)?;
+//! assert_eq!(block_number, BlockNumber(0));
+//! assert_eq!(reader.begin_ro_txn()?.get_state_marker()?, BlockNumber(1));
+//! # Ok::<(), papyrus_storage::StorageError>(())
+//! ```
+#[cfg(test)]
+#[path = "snapshot_test.rs"]
+mod snapshot_test;
+
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use sha2::{Digest, Sha256};
+use starknet_api::block::{BlockHeader, BlockNumber};
+use starknet_api::core::{ClassHash, ContractAddress, Nonce};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::state::{SierraContractClass, StorageKey};
+use starknet_types_core::felt::Felt;
+use tracing::debug;
+
+use crate::class::ClassStorageReader;
+use crate::compiled_class::CasmStorageReader;
+use crate::db::serialization::{StorageSerde, StorageSerdeError, ValueSerde};
+use crate::db::table_types::{DbCursorTrait, Table};
+use crate::db::RW;
+use crate::header::{HeaderStorageReader, HeaderStorageWriter};
+use crate::pruning::HistoryPruningStorageReader;
+use crate::state::data::IndexedDeprecatedContractClass;
+use crate::state::StateStorageReader;
+use crate::{
+    MarkerKind,
+    OffsetKind,
+    StorageError,
+    StorageReader,
+    StorageResult,
+    StorageScope,
+    StorageTxn,
+    StorageWriter,
+};
+
+/// The magic bytes at the beginning of a state snapshot.
+pub const SNAPSHOT_MAGIC: &[u8] = b"PAPYRUS_STATE_SNAPSHOT";
+/// The version of the state snapshot format.
+pub const SNAPSHOT_FORMAT_VERSION: u8 = 0;
+
+// The number of entries written to the storage in a single transaction during an import.
+const IMPORT_ENTRIES_PER_TXN: usize = 10_000;
+const ENTRY_PREFIX: u8 = 1;
+const SECTION_END: u8 = 0;
+// The length of the SHA-256 checksum at the end of the snapshot.
+const CHECKSUM_LEN: u64 = 32;
+
+/// Errors of the state snapshot export and import.
+#[allow(missing_docs)]
+#[derive(thiserror::Error, Debug)]
+pub enum StateSnapshotError {
+    #[error(
+        "Can't take a snapshot of block {block_number}. The state is available right after the \
+         blocks in [{first_available_block}, {marker})."
+    )]
+    BlockNotAvailable {
+        block_number: BlockNumber,
+        first_available_block: BlockNumber,
+        marker: BlockNumber,
+    },
+    #[error("Invalid state snapshot: {msg}.")]
+    InvalidSnapshot { msg: String },
+    #[error("The checksum of the state snapshot doesn't match its content.")]
+    ChecksumMismatch,
+    #[error(
+        "A state snapshot can only be imported into an empty storage with the StateOnly scope \
+         (got a {scope:?} storage with header marker {header_marker})."
+    )]
+    InvalidTargetStorage { scope: StorageScope, header_marker: BlockNumber },
+    #[error(transparent)]
+    Serde(#[from] StorageSerdeError),
+}
+
+/// Writes a snapshot of the state right after the given block to `output`.
+pub fn export_state_snapshot(
+    reader: &StorageReader,
+    block_number: BlockNumber,
+    output: impl Write,
+) -> StorageResult<()> {
+    let txn = reader.begin_ro_txn()?;
+    let marker = [
+        txn.get_header_marker()?,
+        txn.get_state_marker()?,
+        txn.get_class_marker()?,
+        txn.get_compiled_class_marker()?,
+    ]
+    .into_iter()
+    .min()
+    .expect("Markers should not be empty.");
+    // The state right after the last pruned block is kept.
+    let first_available_block = txn.get_history_pruning_marker()?.prev().unwrap_or(BlockNumber(0));
+    if block_number < first_available_block || block_number >= marker {
+        return Err(StateSnapshotError::BlockNotAvailable {
+            block_number,
+            first_available_block,
+            marker,
+        }
+        .into());
+    }
+    debug!("Exporting a state snapshot of block {block_number}.");
+
+    let mut output = ChecksumWriter::new(BufWriter::new(output));
+    output.write_all(SNAPSHOT_MAGIC)?;
+    write_value(&mut output, &SNAPSHOT_FORMAT_VERSION)?;
+    write_value(&mut output, &block_number)?;
+
+    let contract_storage_table = txn.open_table(&txn.tables.contract_storage)?;
+    export_latest_values(&mut contract_storage_table.cursor(&txn.txn)?, block_number, &mut output)?;
+    let nonces_table = txn.open_table(&txn.tables.nonces)?;
+    export_latest_values(&mut nonces_table.cursor(&txn.txn)?, block_number, &mut output)?;
+    let deployed_contracts_table = txn.open_table(&txn.tables.deployed_contracts)?;
+    export_latest_values(
+        &mut deployed_contracts_table.cursor(&txn.txn)?,
+        block_number,
+        &mut output,
+    )?;
+
+    let declared_classes_block_table = txn.open_table(&txn.tables.declared_classes_block)?;
+    let declared_classes_table = txn.open_table(&txn.tables.declared_classes)?;
+    let casms_table = txn.open_table(&txn.tables.casms)?;
+    let mut cursor = declared_classes_block_table.cursor(&txn.txn)?;
+    while let Some((class_hash, declared_block_number)) = cursor.next()? {
+        if declared_block_number > block_number {
+            continue;
+        }
+        let location = declared_classes_table.get(&txn.txn, &class_hash)?.ok_or(
+            StorageError::DBInconsistency {
+                msg: format!("Missing the class of the declared class hash {class_hash}."),
+            },
+        )?;
+        let class = txn.file_handlers.get_contract_class_unchecked(location)?;
+        let casm = casms_table
+            .get(&txn.txn, &class_hash)?
+            .map(|location| txn.file_handlers.get_casm_unchecked(location))
+            .transpose()?;
+        write_value(&mut output, &ENTRY_PREFIX)?;
+        write_value(&mut output, &class_hash)?;
+        write_value(&mut output, &declared_block_number)?;
+        write_value(&mut output, &class)?;
+        write_value(&mut output, &casm)?;
+    }
+    write_value(&mut output, &SECTION_END)?;
+
+    let deprecated_declared_classes_table =
+        txn.open_table(&txn.tables.deprecated_declared_classes)?;
+    let mut cursor = deprecated_declared_classes_table.cursor(&txn.txn)?;
+    while let Some((class_hash, indexed_class)) = cursor.next()? {
+        if indexed_class.block_number > block_number {
+            continue;
+        }
+        let class = txn
+            .file_handlers
+            .get_deprecated_contract_class_unchecked(indexed_class.location_in_file)?;
+        write_value(&mut output, &ENTRY_PREFIX)?;
+        write_value(&mut output, &class_hash)?;
+        write_value(&mut output, &indexed_class.block_number)?;
+        write_value(&mut output, &class)?;
+    }
+    write_value(&mut output, &SECTION_END)?;
+
+    for header_block_number in BlockNumber(0).iter_up_to(block_number.unchecked_next()) {
+        let header =
+            txn.get_block_header(header_block_number)?.ok_or(StorageError::DBInconsistency {
+                msg: format!("Missing the header of block {header_block_number}."),
+            })?;
+        write_value(&mut output, &ENTRY_PREFIX)?;
+        write_value(&mut output, &header)?;
+    }
+    write_value(&mut output, &SECTION_END)?;
+
+    let checksum = output.hasher.finalize_reset();
+    output.inner.write_all(&checksum)?;
+    output.inner.flush()?;
+    Ok(())
+}
+
+/// Imports a state snapshot into an empty storage with the [`StorageScope::StateOnly`] scope.
+/// Returns the block number of the snapshot.
+///
+/// The input is read twice: the checksum of the snapshot is verified first, so nothing is written
+/// from a corrupted snapshot. The data is then written in multiple transactions, and the markers
+/// are set only in the last one. If the import fails after the checksum was verified, e.g., on an
+/// I/O error, the storage should be deleted before importing again.
+///
+/// The compiled class hashes of the classes declared up to the snapshot block aren't imported (see
+/// the [module docs](self)).
+pub fn import_state_snapshot(
+    mut input: impl Read + Seek,
+    writer: &mut StorageWriter,
+) -> StorageResult<BlockNumber> {
+    {
+        let txn = writer.begin_rw_txn()?;
+        let header_marker = txn.get_header_marker()?;
+        if txn.scope != StorageScope::StateOnly
+            || header_marker != BlockNumber(0)
+            || txn.get_state_marker()? != BlockNumber(0)
+        {
+            return Err(StateSnapshotError::InvalidTargetStorage {
+                scope: txn.scope,
+                header_marker,
+            }
+            .into());
+        }
+    }
+
+    let content_len = verify_checksum(&mut input)?;
+    input.rewind()?;
+    let mut input = BufReader::new(input.take(content_len));
+    let mut magic = vec![0; SNAPSHOT_MAGIC.len()];
+    input.read_exact(&mut magic)?;
+    if magic != SNAPSHOT_MAGIC {
+        return Err(invalid_snapshot("the magic bytes are wrong"));
+    }
+    let version = read_value::<u8>(&mut input, "format version")?;
+    if version != SNAPSHOT_FORMAT_VERSION {
+        return Err(invalid_snapshot(&format!("unsupported format version {version}")));
+    }
+    let block_number = read_value::<BlockNumber>(&mut input, "block number")?;
+    debug!("Importing a state snapshot of block {block_number}.");
+
+    import_section(writer, &mut input, import_storage_value)?;
+    import_section(writer, &mut input, import_nonce)?;
+    import_section(writer, &mut input, import_class_hash)?;
+    import_section(writer, &mut input, import_class)?;
+    import_section(writer, &mut input, import_deprecated_class)?;
+    import_section(writer, &mut input, import_header)?;
+
+    if input.read(&mut [0])? != 0 {
+        return Err(invalid_snapshot("unexpected data before the checksum"));
+    }
+
+    let txn = writer.begin_rw_txn()?;
+    let header_marker = txn.get_header_marker()?;
+    if header_marker != block_number.unchecked_next() {
+        return Err(invalid_snapshot(&format!(
+            "expected the headers up to block {block_number}, got the headers up to block \
+             {header_marker} (exclusive)"
+        )));
+    }
+    let markers_table = txn.open_table(&txn.tables.markers)?;
+    let next_block_number = block_number.unchecked_next();
+    for marker_kind in [
+        MarkerKind::Body,
+        MarkerKind::Event,
+        MarkerKind::State,
+        MarkerKind::Class,
+        MarkerKind::CompiledClass,
+        MarkerKind::HistoryPruning,
+    ] {
+        markers_table.upsert(&txn.txn, &marker_kind, &next_block_number)?;
+    }
+    txn.commit()?;
+    Ok(block_number)
+}
+
+// Verifies the checksum at the end of the snapshot. Returns the length of the snapshot without the
+// checksum.
+fn verify_checksum(input: &mut (impl Read + Seek)) -> StorageResult<u64> {
+    let content_len = input
+        .seek(SeekFrom::End(0))?
+        .checked_sub(CHECKSUM_LEN)
+        .ok_or_else(|| invalid_snapshot("the snapshot is shorter than its checksum"))?;
+    input.rewind()?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut BufReader::new((&mut *input).take(content_len)), &mut hasher)?;
+    let mut checksum = [0; 32];
+    input.read_exact(&mut checksum)?;
+    if checksum[..] != hasher.finalize()[..] {
+        return Err(StateSnapshotError::ChecksumMismatch.into());
+    }
+    Ok(content_len)
+}
+
+// Writes the latest value of each key whose block number is at most the given block number, as a
+// snapshot section.
+fn export_latest_values<K, V, Cursor>(
+    cursor: &mut Cursor,
+    block_number: BlockNumber,
+    output: &mut impl Write,
+) -> StorageResult<()>
+where
+    K: PartialEq,
+    (K, BlockNumber): StorageSerde,
+    V: ValueSerde,
+    Cursor: DbCursorTrait<Key = (K, BlockNumber), Value = V>,
+{
+    let mut latest: Option<((K, BlockNumber), V::Value)> = None;
+    while let Some(((key, value_block_number), value)) = cursor.next()? {
+        if value_block_number > block_number {
+            continue;
+        }
+        if let Some((latest_key, _)) = &latest {
+            if latest_key.0 != key {
+                write_entry(output, latest.take().expect("Latest should be set."))?;
+            }
+        }
+        latest = Some(((key, value_block_number), value));
+    }
+    if let Some(entry) = latest {
+        write_entry(output, entry)?;
+    }
+    write_value(output, &SECTION_END)
+}
+
+fn write_entry<K: StorageSerde, V: StorageSerde>(
+    output: &mut impl Write,
+    (key, value): (K, V),
+) -> StorageResult<()> {
+    write_value(output, &ENTRY_PREFIX)?;
+    write_value(output, &key)?;
+    write_value(output, &value)
+}
+
+fn write_value<T: StorageSerde>(output: &mut impl Write, value: &T) -> StorageResult<()> {
+    value.serialize_into(output).map_err(StateSnapshotError::from)?;
+    Ok(())
+}
+
+fn read_value<T: StorageSerde>(input: &mut impl Read, what: &str) -> StorageResult<T> {
+    T::deserialize_from(input).ok_or_else(|| invalid_snapshot(&format!("failed to read a {what}")))
+}
+
+fn invalid_snapshot(msg: &str) -> StorageError {
+    StateSnapshotError::InvalidSnapshot { msg: msg.to_owned() }.into()
+}
+
+// Reads the entries of a snapshot section and writes them to the storage, committing a
+// transaction every IMPORT_ENTRIES_PER_TXN entries.
+fn import_section<R: Read>(
+    writer: &mut StorageWriter,
+    input: &mut R,
+    import_entry: impl for<'env> Fn(StorageTxn<'env, RW>, &mut R) -> StorageResult<StorageTxn<'env, RW>>,
+) -> StorageResult<()> {
+    let mut txn = writer.begin_rw_txn()?;
+    let mut n_entries = 0;
+    loop {
+        match read_value::<u8>(input, "section entry prefix")? {
+            ENTRY_PREFIX => {}
+            SECTION_END => break,
+            prefix => return Err(invalid_snapshot(&format!("unexpected entry prefix {prefix}"))),
+        }
+        txn = import_entry(txn, input)?;
+        n_entries += 1;
+        if n_entries % IMPORT_ENTRIES_PER_TXN == 0 {
+            txn.commit()?;
+            txn = writer.begin_rw_txn()?;
+        }
+    }
+    txn.commit()
+}
+
+fn import_storage_value<'env, R: Read>(
+    txn: StorageTxn<'env, RW>,
+    input: &mut R,
+) -> StorageResult<StorageTxn<'env, RW>> {
+    let key = read_value::<((ContractAddress, StorageKey), BlockNumber)>(input, "storage key")?;
+    let value = read_value::<Felt>(input, "storage value")?;
+    let contract_storage_table = txn.open_table(&txn.tables.contract_storage)?;
+    contract_storage_table.insert(&txn.txn, &key, &value)?;
+    Ok(txn)
+}
+
+fn import_nonce<'env, R: Read>(
+    txn: StorageTxn<'env, RW>,
+    input: &mut R,
+) -> StorageResult<StorageTxn<'env, RW>> {
+    let key = read_value::<(ContractAddress, BlockNumber)>(input, "nonce key")?;
+    let nonce = read_value::<Nonce>(input, "nonce")?;
+    let nonces_table = txn.open_table(&txn.tables.nonces)?;
+    nonces_table.insert(&txn.txn, &key, &nonce)?;
+    Ok(txn)
+}
+
+fn import_class_hash<'env, R: Read>(
+    txn: StorageTxn<'env, RW>,
+    input: &mut R,
+) -> StorageResult<StorageTxn<'env, RW>> {
+    let key = read_value::<(ContractAddress, BlockNumber)>(input, "class hash key")?;
+    let class_hash = read_value::<ClassHash>(input, "class hash")?;
+    let deployed_contracts_table = txn.open_table(&txn.tables.deployed_contracts)?;
+    deployed_contracts_table.insert(&txn.txn, &key, &class_hash)?;
+    Ok(txn)
+}
+
+fn import_class<'env, R: Read>(
+    txn: StorageTxn<'env, RW>,
+    input: &mut R,
+) -> StorageResult<StorageTxn<'env, RW>> {
+    let class_hash = read_value::<ClassHash>(input, "class hash")?;
+    let block_number = read_value::<BlockNumber>(input, "class block number")?;
+    let class = read_value::<SierraContractClass>(input, "class")?;
+    let casm = read_value::<Option<CasmContractClass>>(input, "CASM")?;
+    let file_offset_table = txn.open_table(&txn.tables.file_offsets)?;
+    let declared_classes_table = txn.open_table(&txn.tables.declared_classes)?;
+    let declared_classes_block_table = txn.open_table(&txn.tables.declared_classes_block)?;
+
+    let location = txn.file_handlers.append_contract_class(&class);
+    declared_classes_table.insert(&txn.txn, &class_hash, &location)?;
+    file_offset_table.upsert(&txn.txn, &OffsetKind::ContractClass, &location.next_offset())?;
+    declared_classes_block_table.insert(&txn.txn, &class_hash, &block_number)?;
+    if let Some(casm) = casm {
+        let casms_table = txn.open_table(&txn.tables.casms)?;
+        let location = txn.file_handlers.append_casm(&casm);
+        casms_table.insert(&txn.txn, &class_hash, &location)?;
+        file_offset_table.upsert(&txn.txn, &OffsetKind::Casm, &location.next_offset())?;
+    }
+    Ok(txn)
+}
+
+fn import_deprecated_class<'env, R: Read>(
+    txn: StorageTxn<'env, RW>,
+    input: &mut R,
+) -> StorageResult<StorageTxn<'env, RW>> {
+    let class_hash = read_value::<ClassHash>(input, "deprecated class hash")?;
+    let block_number = read_value::<BlockNumber>(input, "deprecated class block number")?;
+    let class = read_value::<DeprecatedContractClass>(input, "deprecated class")?;
+    let file_offset_table = txn.open_table(&txn.tables.file_offsets)?;
+    let deprecated_declared_classes_table =
+        txn.open_table(&txn.tables.deprecated_declared_classes)?;
+
+    let location = txn.file_handlers.append_deprecated_contract_class(&class);
+    file_offset_table.upsert(
+        &txn.txn,
+        &OffsetKind::DeprecatedContractClass,
+        &location.next_offset(),
+    )?;
+    let value = IndexedDeprecatedContractClass { block_number, location_in_file: location };
+    deprecated_declared_classes_table.insert(&txn.txn, &class_hash, &value)?;
+    Ok(txn)
+}
+
+fn import_header<'env, R: Read>(
+    txn: StorageTxn<'env, RW>,
+    input: &mut R,
+) -> StorageResult<StorageTxn<'env, RW>> {
+    let header = read_value::<BlockHeader>(input, "header")?;
+    // Appending the headers out of order fails on a marker mismatch.
+    txn.append_header(header.block_header_without_hash.block_number, &header)
+}
+
+// Computes the checksum of the written bytes.
+struct ChecksumWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> ChecksumWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, hasher: Sha256::new() }
+    }
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,244 @@
+use std::io::Cursor;
+
+use assert_matches::assert_matches;
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use indexmap::indexmap;
+use papyrus_test_utils::{get_rng, GetTestInstance};
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockHeader, BlockHeaderWithoutHash, BlockNumber};
+use starknet_api::core::{ClassHash, CompiledClassHash, Nonce};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::state::{SierraContractClass, StateNumber, ThinStateDiff};
+use starknet_api::{class_hash, contract_address, felt, storage_key};
+
+use crate::class::{ClassStorageReader, ClassStorageWriter};
+use crate::compiled_class::{CasmStorageReader, CasmStorageWriter};
+use crate::header::{HeaderStorageReader, HeaderStorageWriter};
+use crate::pruning::HistoryPruningStorageReader;
+use crate::snapshot::{export_state_snapshot, import_state_snapshot, StateSnapshotError};
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::{get_test_storage, get_test_storage_by_scope};
+use crate::{StorageError, StorageScope, StorageWriter};
+
+const N_BLOCKS: u64 = 3;
+const SNAPSHOT_BLOCK: BlockNumber = BlockNumber(1);
+
+fn header(block_number: BlockNumber) -> BlockHeader {
+    BlockHeader {
+        block_hash: BlockHash(felt!(block_number.0 + 1)),
+        block_header_without_hash: BlockHeaderWithoutHash { block_number, ..Default::default() },
+        ..Default::default()
+    }
+}
+
+// Appends a block to the storage. The storage value and the nonce of the same contract are set to
+// block_number + 1 in each block.
+fn append_block(
+    writer: &mut StorageWriter,
+    block_number: BlockNumber,
+    mut state_diff: ThinStateDiff,
+    classes: &[(ClassHash, &SierraContractClass, &CasmContractClass)],
+    deprecated_classes: &[(ClassHash, &DeprecatedContractClass)],
+) {
+    let address = contract_address!("0x100");
+    let value = felt!(block_number.0 + 1);
+    state_diff.nonces = indexmap! { address => Nonce(value) };
+    state_diff.storage_diffs = indexmap! { address => indexmap! { storage_key!("0x10") => value } };
+    for (class_hash, _, _) in classes {
+        state_diff.declared_classes.insert(*class_hash, CompiledClassHash::default());
+    }
+    state_diff.deprecated_declared_classes =
+        deprecated_classes.iter().map(|(class_hash, _)| *class_hash).collect();
+
+    let sierras =
+        classes.iter().map(|(class_hash, sierra, _)| (*class_hash, *sierra)).collect::<Vec<_>>();
+    let mut txn = writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &header(block_number))
+        .unwrap()
+        .append_state_diff(block_number, state_diff)
+        .unwrap()
+        .append_classes(block_number, &sierras, deprecated_classes)
+        .unwrap();
+    for (class_hash, _, casm) in classes {
+        txn = txn.append_casm(class_hash, casm).unwrap();
+    }
+    txn.commit().unwrap();
+}
+
+#[test]
+fn export_and_import() {
+    let mut rng = get_rng();
+    let address = contract_address!("0x100");
+    let key = storage_key!("0x10");
+    let deprecated_class_hash = class_hash!("0x1");
+    let deprecated_class = DeprecatedContractClass::default();
+    let class_hashes = [class_hash!("0xa"), class_hash!("0xb")];
+    let sierras = [
+        <SierraContractClass as GetTestInstance>::get_test_instance(&mut rng),
+        <SierraContractClass as GetTestInstance>::get_test_instance(&mut rng),
+    ];
+    let casms = [
+        CasmContractClass::get_test_instance(&mut rng),
+        CasmContractClass::get_test_instance(&mut rng),
+    ];
+
+    let ((source_reader, mut source_writer), _source_temp_dir) = get_test_storage();
+    append_block(
+        &mut source_writer,
+        BlockNumber(0),
+        ThinStateDiff {
+            deployed_contracts: indexmap! { address => deprecated_class_hash },
+            ..Default::default()
+        },
+        &[(class_hashes[0], &sierras[0], &casms[0])],
+        &[(deprecated_class_hash, &deprecated_class)],
+    );
+    append_block(
+        &mut source_writer,
+        BlockNumber(1),
+        ThinStateDiff {
+            replaced_classes: indexmap! { address => class_hashes[0] },
+            ..Default::default()
+        },
+        &[],
+        &[],
+    );
+    // Not part of the snapshot.
+    append_block(
+        &mut source_writer,
+        BlockNumber(2),
+        ThinStateDiff::default(),
+        &[(class_hashes[1], &sierras[1], &casms[1])],
+        &[],
+    );
+
+    let mut snapshot = Vec::new();
+    export_state_snapshot(&source_reader, SNAPSHOT_BLOCK, &mut snapshot).unwrap();
+    let ((reader, mut writer), _temp_dir) = get_test_storage_by_scope(StorageScope::StateOnly);
+    assert_eq!(import_state_snapshot(Cursor::new(&snapshot), &mut writer).unwrap(), SNAPSHOT_BLOCK);
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let next_block = SNAPSHOT_BLOCK.unchecked_next();
+    assert_eq!(txn.get_header_marker().unwrap(), next_block);
+    assert_eq!(txn.get_state_marker().unwrap(), next_block);
+    assert_eq!(txn.get_class_marker().unwrap(), next_block);
+    assert_eq!(txn.get_compiled_class_marker().unwrap(), next_block);
+    assert_eq!(txn.get_history_pruning_marker().unwrap(), next_block);
+    for block_number in BlockNumber(0).iter_up_to(next_block) {
+        assert_eq!(txn.get_block_header(block_number).unwrap(), Some(header(block_number)));
+    }
+
+    let state_number = StateNumber::right_before_block(next_block);
+    let state_reader = txn.get_state_reader().unwrap();
+    assert_eq!(state_reader.get_storage_at(state_number, &address, &key).unwrap(), felt!(2_u8));
+    assert_eq!(
+        state_reader.get_nonce_at(state_number, &address).unwrap(),
+        Some(Nonce(felt!(2_u8)))
+    );
+    assert_eq!(
+        state_reader.get_class_hash_at(state_number, &address).unwrap(),
+        Some(class_hashes[0])
+    );
+    assert_eq!(txn.get_class(&class_hashes[0]).unwrap(), Some(sierras[0].clone()));
+    assert_eq!(txn.get_casm(&class_hashes[0]).unwrap(), Some(casms[0].clone()));
+    assert_eq!(
+        state_reader.get_class_definition_block_number(&class_hashes[0]).unwrap(),
+        Some(BlockNumber(0))
+    );
+    assert_eq!(txn.get_deprecated_class(&deprecated_class_hash).unwrap(), Some(deprecated_class));
+    assert!(txn.get_class(&class_hashes[1]).unwrap().is_none());
+    drop(txn);
+
+    // Sync can continue from the block after the snapshot.
+    append_block(
+        &mut writer,
+        BlockNumber(2),
+        ThinStateDiff::default(),
+        &[(class_hashes[1], &sierras[1], &casms[1])],
+        &[],
+    );
+    let txn = reader.begin_ro_txn().unwrap();
+    let state_number = StateNumber::right_before_block(BlockNumber(N_BLOCKS));
+    let state_reader = txn.get_state_reader().unwrap();
+    assert_eq!(state_reader.get_storage_at(state_number, &address, &key).unwrap(), felt!(3_u8));
+    assert_eq!(txn.get_class(&class_hashes[1]).unwrap(), Some(sierras[1].clone()));
+}
+
+fn export_snapshot() -> Vec<u8> {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    for block_number in BlockNumber(0).iter_up_to(BlockNumber(N_BLOCKS)) {
+        append_block(&mut writer, block_number, ThinStateDiff::default(), &[], &[]);
+    }
+    let mut snapshot = Vec::new();
+    export_state_snapshot(&reader, SNAPSHOT_BLOCK, &mut snapshot).unwrap();
+    snapshot
+}
+
+#[test]
+fn export_unavailable_block_fails() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_block(&mut writer, BlockNumber(0), ThinStateDiff::default(), &[], &[]);
+
+    let result = export_state_snapshot(&reader, BlockNumber(1), &mut Vec::new());
+    assert_matches!(
+        result,
+        Err(StorageError::StateSnapshot(StateSnapshotError::BlockNotAvailable {
+            block_number: BlockNumber(1),
+            first_available_block: BlockNumber(0),
+            marker: BlockNumber(1),
+        }))
+    );
+}
+
+#[test]
+fn import_corrupted_snapshot_fails() {
+    let mut snapshot = export_snapshot();
+    let middle = snapshot.len() / 2;
+    snapshot[middle] ^= 1;
+
+    let ((reader, mut writer), _temp_dir) = get_test_storage_by_scope(StorageScope::StateOnly);
+    assert_matches!(
+        import_state_snapshot(Cursor::new(&snapshot), &mut writer),
+        Err(StorageError::StateSnapshot(StateSnapshotError::ChecksumMismatch))
+    );
+    // Nothing is written before the checksum is verified.
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(0));
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(0));
+}
+
+#[test]
+fn import_truncated_snapshot_fails() {
+    let snapshot = export_snapshot();
+
+    let ((_reader, mut writer), _temp_dir) = get_test_storage_by_scope(StorageScope::StateOnly);
+    assert!(
+        import_state_snapshot(Cursor::new(&snapshot[..snapshot.len() / 2]), &mut writer).is_err()
+    );
+}
+
+#[test]
+fn import_into_invalid_storage_fails() {
+    let snapshot = export_snapshot();
+
+    let ((_reader, mut writer), _temp_dir) = get_test_storage_by_scope(StorageScope::FullArchive);
+    assert_matches!(
+        import_state_snapshot(Cursor::new(&snapshot), &mut writer),
+        Err(StorageError::StateSnapshot(StateSnapshotError::InvalidTargetStorage {
+            scope: StorageScope::FullArchive,
+            ..
+        }))
+    );
+
+    let ((_reader, mut writer), _temp_dir) = get_test_storage_by_scope(StorageScope::StateOnly);
+    import_state_snapshot(Cursor::new(&snapshot), &mut writer).unwrap();
+    assert_matches!(
+        import_state_snapshot(Cursor::new(&snapshot), &mut writer),
+        Err(StorageError::StateSnapshot(StateSnapshotError::InvalidTargetStorage {
+            header_marker: BlockNumber(2),
+            ..
+        }))
+    );
+}
@@ -72,6 +72,7 @@ use crate::db::{DbTransaction, TableHandle, TransactionKind, RW};
 #[cfg(feature = "document_calls")]
 use crate::document_calls::{add_query, StorageQuery};
 use crate::mmap_file::LocationInFile;
+use crate::pruning::verify_block_not_pruned;
 use crate::state::data::IndexedDeprecatedContractClass;
 use crate::{
     FileHandlers,
@@ -515,6 +516,7 @@ impl StateStorageWriter for StorageTxn<'_, RW> {
             );
             return Ok((self, None));
         };
+        verify_block_not_pruned(&self, block_number)?;
 
         let thin_state_diff = self
             .get_state_diff(block_number)?
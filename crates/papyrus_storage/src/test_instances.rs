@@ -9,6 +9,7 @@ use starknet_api::core::{
     TransactionCommitment,
 };
 use starknet_api::data_availability::L1DataAvailabilityMode;
+use starknet_api::hash::StarkHash;
 use starknet_api::transaction::{
     EventIndexInTransactionOutput,
     TransactionHash,
@@ -19,6 +20,7 @@ use crate::body::TransactionIndex;
 use crate::compression_utils::IsCompressed;
 use crate::header::StorageBlockHeader;
 use crate::mmap_file::LocationInFile;
+use crate::patricia::GlobalTrieRoots;
 use crate::state::data::IndexedDeprecatedContractClass;
 use crate::version::Version;
 use crate::{EventIndex, MarkerKind, OffsetKind, TransactionMetadata};
@@ -45,6 +47,10 @@ auto_impl_get_test_instance! {
     }
 
     struct EventIndex(pub TransactionIndex, pub EventIndexInTransactionOutput);
+    pub struct GlobalTrieRoots {
+        pub contracts_trie_root: StarkHash,
+        pub classes_trie_root: StarkHash,
+    }
     pub struct IndexedDeprecatedContractClass {
         pub block_number: BlockNumber,
         pub location_in_file: LocationInFile,
@@ -61,6 +67,10 @@ auto_impl_get_test_instance! {
         Class = 4,
         CompiledClass = 5,
         BaseLayerBlock = 6,
+        HistoryPruning = 7,
+        EventsByKeyStart = 8,
+        BaseLayerMessages = 9,
+        BaseLayerMessagesStart = 10,
     }
     pub enum OffsetKind {
         ThinStateDiff = 0,
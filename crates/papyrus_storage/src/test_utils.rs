@@ -6,6 +6,7 @@ use std::sync::LazyLock;
 use starknet_api::core::ChainId;
 use tempfile::{tempdir, TempDir};
 
+use crate::compression_utils::StorageCompressionConfig;
 use crate::db::DbConfig;
 use crate::mmap_file::MmapFileConfig;
 use crate::{open_storage, StorageConfig, StorageReader, StorageScope, StorageWriter};
@@ -34,7 +35,10 @@ pub(crate) fn get_test_config(storage_scope: Option<StorageScope>) -> (StorageCo
                 growth_step: 1 << 26, // 64MB
             },
             scope: storage_scope,
+            index_events_by_key: false,
             mmap_file_config: get_mmap_file_test_config(),
+            history_pruning_config: None,
+            compression_config: StorageCompressionConfig::default(),
         },
         dir,
     )
@@ -21,7 +21,7 @@ use indexmap::IndexMap;
 use papyrus_common::metrics as papyrus_metrics;
 use papyrus_common::pending_classes::PendingClasses;
 use papyrus_config::converters::deserialize_seconds_to_duration;
-use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::dumping::{ser_optional_param, ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_proc_macros::latency_histogram;
 use papyrus_storage::base_layer::{BaseLayerStorageReader, BaseLayerStorageWriter};
@@ -30,16 +30,41 @@ use papyrus_storage::class::ClassStorageWriter;
 use papyrus_storage::compiled_class::{CasmStorageReader, CasmStorageWriter};
 use papyrus_storage::db::DbError;
 use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::patricia::{GlobalTrieRoots, PatriciaStorageReader, PatriciaStorageWriter};
 use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
 use papyrus_storage::{StorageError, StorageReader, StorageWriter};
 use serde::{Deserialize, Serialize};
 use sources::base_layer::BaseLayerSourceError;
 use starknet_api::block::{Block, BlockHash, BlockHashAndNumber, BlockNumber, BlockSignature};
-use starknet_api::core::{ClassHash, CompiledClassHash, SequencerPublicKey};
+use starknet_api::core::{
+    ClassHash,
+    CompiledClassHash,
+    ContractAddress,
+    Nonce,
+    SequencerPublicKey,
+};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::state::{StateDiff, ThinStateDiff};
+use starknet_api::transaction::L1TransactionHash;
 use starknet_client::reader::PendingData;
+use starknet_committer::block_committer::commit::commit_state_diff;
+use starknet_committer::block_committer::errors::BlockCommitmentError;
+use starknet_committer::block_committer::input::{
+    ConfigImpl,
+    ContractAddress as CommitterContractAddress,
+    StarknetStorageKey,
+    StarknetStorageValue,
+    StateDiff as CommitterStateDiff,
+};
+use starknet_committer::forest::filled_forest::FilledForest;
+use starknet_committer::patricia_merkle_tree::types::{
+    ClassHash as CommitterClassHash,
+    CompiledClassHash as CommitterCompiledClassHash,
+    Nonce as CommitterNonce,
+};
+use starknet_patricia::hash::hash_trait::HashOutput;
 use tokio::sync::RwLock;
+use tracing::level_filters::LevelFilter;
 use tracing::{debug, error, info, instrument, trace, warn};
 
 use crate::pending_sync::sync_pending_data;
@@ -59,6 +84,10 @@ const PENDING_SLEEP_DURATION: Duration = Duration::from_millis(500);
 // Sleep duration, in seconds, between sync progress checks.
 const SLEEP_TIME_SYNC_PROGRESS: Duration = Duration::from_secs(300);
 
+// The maximal number of L1 blocks to fetch the messages to L2 of in a single request, since base
+// layer providers limit the range of log queries.
+const L1_MESSAGES_MAX_BLOCK_RANGE: u64 = 1000;
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
 pub struct SyncConfig {
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
@@ -71,11 +100,15 @@ pub struct SyncConfig {
     pub state_updates_max_stream_size: u32,
     pub verify_blocks: bool,
     pub collect_pending_data: bool,
+    pub index_l1_messages: bool,
+    pub l1_messages_start_block: Option<u64>,
+    pub l1_messages_finality: u64,
+    pub store_patricia_tries: bool,
 }
 
 impl SerializeConfig for SyncConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        BTreeMap::from_iter([
+        let mut config = BTreeMap::from_iter([
             ser_param(
                 "block_propagation_sleep_duration",
                 &self.block_propagation_sleep_duration.as_secs(),
@@ -119,7 +152,39 @@ impl SerializeConfig for SyncConfig {
                 "Whether to collect data on pending blocks.",
                 ParamPrivacyInput::Public,
             ),
-        ])
+            ser_param(
+                "index_l1_messages",
+                &self.index_l1_messages,
+                "Whether to index the messages sent from L1 to L2 by their L1 transactions.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "l1_messages_finality",
+                &self.l1_messages_finality,
+                "The number of confirmations an L1 block needs before its messages to L2 are \
+                 indexed. Reorged L1 blocks are not unindexed.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "store_patricia_tries",
+                &self.store_patricia_tries,
+                "Whether to commit the state diffs to the Patricia tries of the global state and \
+                 store the nodes of the tries, from which the storage proofs are extracted. The \
+                 tries of a block are built on those of its parent, so they are stored only if \
+                 this is enabled since the first block.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
+        config.extend(ser_optional_param(
+            &self.l1_messages_start_block,
+            0,
+            "l1_messages_start_block",
+            "The first L1 block whose messages to L2 are indexed. If it's before the first indexed \
+             L1 block, the messages from it are backfilled. If it isn't set, the indexing starts \
+             from the latest L1 block when first enabled.",
+            ParamPrivacyInput::Public,
+        ));
+        config
     }
 }
 
@@ -133,6 +198,10 @@ impl Default for SyncConfig {
             state_updates_max_stream_size: 1000,
             verify_blocks: true,
             collect_pending_data: false,
+            index_l1_messages: false,
+            l1_messages_start_block: None,
+            l1_messages_finality: 10,
+            store_patricia_tries: false,
         }
     }
 }
@@ -194,6 +263,8 @@ pub enum StateSyncError {
     },
     #[error("Sequencer public key changed from {old:?} to {new:?}.")]
     SequencerPubKeyChanged { old: SequencerPublicKey, new: SequencerPublicKey },
+    #[error(transparent)]
+    BlockCommitmentError(#[from] BlockCommitmentError),
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -224,6 +295,14 @@ pub enum SyncEvent {
         block_number: BlockNumber,
         block_hash: BlockHash,
     },
+    NewL1Messages {
+        messages: Vec<(L1TransactionHash, Nonce)>,
+        // The first L1 block whose messages were fetched.
+        from_l1_block: u64,
+        // The first L1 block whose messages weren't fetched yet.
+        next_l1_block: u64,
+    },
+    PruneHistory,
 }
 
 impl<
@@ -268,7 +347,8 @@ impl<
                 | StateSyncError::ParentBlockHashMismatch { .. }
                 | StateSyncError::BaseLayerHashMismatch { .. }
                 | StateSyncError::BaseLayerBlockWithoutMatchingHeader { .. } => true,
-                StateSyncError::SequencerPubKeyChanged { .. } => false,
+                StateSyncError::SequencerPubKeyChanged { .. }
+                | StateSyncError::BlockCommitmentError(_) => false,
             }
         }
     }
@@ -341,6 +421,19 @@ impl<
             self.config.base_layer_propagation_sleep_duration,
         )
         .fuse();
+        let l1_messages_stream = stream_new_l1_messages(
+            self.reader.clone(),
+            self.base_layer_source.clone(),
+            self.config.base_layer_propagation_sleep_duration,
+            self.config.index_l1_messages,
+            self.config.l1_messages_start_block,
+            self.config.l1_messages_finality,
+        )
+        .fuse();
+        let history_pruning_stream = stream_history_pruning(
+            self.writer.history_pruning_config().map(|config| config.interval),
+        )
+        .fuse();
         // TODO(dvir): try use interval instead of stream.
         // TODO: fix the bug and remove this check.
         let check_sync_progress = check_sync_progress(self.reader.clone()).fuse();
@@ -349,6 +442,8 @@ impl<
             state_diff_stream,
             compiled_class_stream,
             base_layer_block_stream,
+            l1_messages_stream,
+            history_pruning_stream,
             check_sync_progress
         );
 
@@ -359,6 +454,8 @@ impl<
               res = state_diff_stream.next() => res,
               res = compiled_class_stream.next() => res,
               res = base_layer_block_stream.next() => res,
+              res = l1_messages_stream.next() => res,
+              res = history_pruning_stream.next() => res,
               res = check_sync_progress.next() => res,
               complete => break,
             }
@@ -380,12 +477,15 @@ impl<
                 block_hash,
                 state_diff,
                 deployed_contract_class_definitions,
-            } => self.store_state_diff(
-                block_number,
-                block_hash,
-                state_diff,
-                deployed_contract_class_definitions,
-            ),
+            } => {
+                self.store_state_diff(
+                    block_number,
+                    block_hash,
+                    state_diff,
+                    deployed_contract_class_definitions,
+                )
+                .await
+            }
             SyncEvent::CompiledClassAvailable {
                 class_hash,
                 compiled_class_hash,
@@ -394,6 +494,10 @@ impl<
             SyncEvent::NewBaseLayerBlock { block_number, block_hash } => {
                 self.store_base_layer_block(block_number, block_hash)
             }
+            SyncEvent::NewL1Messages { messages, from_l1_block, next_l1_block } => {
+                self.store_l1_messages(&messages, from_l1_block, next_l1_block)
+            }
+            SyncEvent::PruneHistory => self.prune_history(),
             SyncEvent::NoProgress => Err(StateSyncError::NoProgress),
         }
     }
@@ -448,7 +552,7 @@ impl<
     #[latency_histogram("sync_store_state_diff_latency_seconds", false)]
     #[instrument(skip(self, state_diff, deployed_contract_class_definitions), level = "debug", err)]
     #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
-    fn store_state_diff(
+    async fn store_state_diff(
         &mut self,
         block_number: BlockNumber,
         block_hash: BlockHash,
@@ -463,7 +567,13 @@ impl<
         // classes.
         let (thin_state_diff, classes, deprecated_classes) =
             ThinStateDiff::from_state_diff(state_diff);
-        self.writer
+        let committed_tries = if self.config.store_patricia_tries {
+            self.commit_patricia_tries(block_number, &thin_state_diff).await?
+        } else {
+            None
+        };
+        let mut txn = self
+            .writer
             .begin_rw_txn()?
             .append_state_diff(block_number, thin_state_diff)?
             .append_classes(
@@ -474,8 +584,12 @@ impl<
                     .chain(deployed_contract_class_definitions.iter())
                     .map(|(class_hash, deprecated_class)| (*class_hash, deprecated_class))
                     .collect::<Vec<_>>(),
-            )?
-            .commit()?;
+            )?;
+        if let Some((filled_forest, roots)) = committed_tries {
+            filled_forest.write_to_storage(&mut txn);
+            txn = txn.write_global_trie_roots(block_number, &roots)?;
+        }
+        txn.commit()?;
 
         metrics::gauge!(
             papyrus_metrics::PAPYRUS_STATE_MARKER,
@@ -493,6 +607,44 @@ impl<
         Ok(())
     }
 
+    // Commits the state diff to the Patricia tries of the previous block. Returns None if the roots
+    // of the previous block weren't stored, since the tries are built on top of them.
+    async fn commit_patricia_tries(
+        &self,
+        block_number: BlockNumber,
+        state_diff: &ThinStateDiff,
+    ) -> Result<Option<(FilledForest, GlobalTrieRoots)>, StateSyncError> {
+        let txn = self.reader.begin_ro_txn()?;
+        let previous_roots = match block_number.prev() {
+            None => GlobalTrieRoots::default(),
+            Some(previous_block_number) => {
+                match txn.get_global_trie_roots(previous_block_number)? {
+                    Some(previous_roots) => previous_roots,
+                    None => {
+                        debug!(
+                            "Not storing the Patricia tries of block {block_number}, since the \
+                             tries of its parent weren't stored."
+                        );
+                        return Ok(None);
+                    }
+                }
+            }
+        };
+        let filled_forest = commit_state_diff(
+            &txn,
+            &to_committer_state_diff(state_diff),
+            HashOutput(previous_roots.contracts_trie_root.into()),
+            HashOutput(previous_roots.classes_trie_root.into()),
+            &ConfigImpl::new(false, LevelFilter::INFO),
+        )
+        .await?;
+        let roots = GlobalTrieRoots {
+            contracts_trie_root: filled_forest.get_contract_root_hash().0.into(),
+            classes_trie_root: filled_forest.get_compiled_class_root_hash().0.into(),
+        };
+        Ok(Some((filled_forest, roots)))
+    }
+
     #[latency_histogram("sync_store_compiled_class_latency_seconds", false)]
     #[instrument(skip(self, compiled_class), level = "debug", err)]
     fn store_compiled_class(
@@ -562,6 +714,30 @@ impl<
         Ok(())
     }
 
+    fn store_l1_messages(
+        &mut self,
+        messages: &[(L1TransactionHash, Nonce)],
+        from_l1_block: u64,
+        next_l1_block: u64,
+    ) -> StateSyncResult {
+        debug!("Storing {} messages to L2, up to L1 block {next_l1_block}.", messages.len());
+        let mut txn = self.writer.begin_rw_txn()?;
+        if txn.get_base_layer_messages_start()?.map_or(true, |start| from_l1_block < start) {
+            txn = txn.update_base_layer_messages_start(from_l1_block)?;
+        }
+        txn.write_l1_transaction_messages(messages)?
+            .update_base_layer_messages_marker(next_l1_block)?
+            .commit()?;
+        Ok(())
+    }
+
+    // Prunes the history of a batch of old blocks, if the history pruning is enabled.
+    fn prune_history(&mut self) -> StateSyncResult {
+        let history_pruning_marker = self.writer.prune_history_batch()?;
+        debug!("The history was pruned up to block {history_pruning_marker}.");
+        Ok(())
+    }
+
     // Compares the block's parent hash to the stored block.
     fn verify_parent_block_hash(
         &self,
@@ -781,6 +957,54 @@ pub fn sort_state_diff(diff: &mut StateDiff) {
     }
 }
 
+// Converts the state diff to the input of the committer. The deprecated declared classes aren't
+// leaves of the classes trie, so they are omitted.
+fn to_committer_state_diff(state_diff: &ThinStateDiff) -> CommitterStateDiff {
+    let to_committer_address =
+        |address: &ContractAddress| CommitterContractAddress((*address.0.key()).into());
+    CommitterStateDiff {
+        address_to_class_hash: state_diff
+            .deployed_contracts
+            .iter()
+            .chain(state_diff.replaced_classes.iter())
+            .map(|(address, class_hash)| {
+                (to_committer_address(address), CommitterClassHash(class_hash.0.into()))
+            })
+            .collect(),
+        address_to_nonce: state_diff
+            .nonces
+            .iter()
+            .map(|(address, nonce)| (to_committer_address(address), CommitterNonce(nonce.0.into())))
+            .collect(),
+        class_hash_to_compiled_class_hash: state_diff
+            .declared_classes
+            .iter()
+            .map(|(class_hash, compiled_class_hash)| {
+                (
+                    CommitterClassHash(class_hash.0.into()),
+                    CommitterCompiledClassHash(compiled_class_hash.0.into()),
+                )
+            })
+            .collect(),
+        storage_updates: state_diff
+            .storage_diffs
+            .iter()
+            .map(|(address, storage_diff)| {
+                let storage_updates = storage_diff
+                    .iter()
+                    .map(|(key, value)| {
+                        (
+                            StarknetStorageKey((*key.0.key()).into()),
+                            StarknetStorageValue((*value).into()),
+                        )
+                    })
+                    .collect();
+                (to_committer_address(address), storage_updates)
+            })
+            .collect(),
+    }
+}
+
 pub type StateSync = GenericStateSync<CentralSource, PendingSource, EthereumBaseLayerSource>;
 
 impl StateSync {
@@ -892,6 +1116,72 @@ fn stream_new_base_layer_block<TBaseLayerSource: BaseLayerSourceTrait + Sync>(
     }
 }
 
+// Yields the messages to L2 of new L1 blocks, or never if the indexing of the messages is disabled.
+// Only L1 blocks with at least `l1_messages_finality` confirmations are indexed. L1 reorgs deeper
+// than that are not handled, so a message of a reorged L1 transaction remains indexed.
+// If the start block is before the first indexed L1 block, the indexing restarts from it, which
+// reindexes the L1 blocks that were already indexed, since indexing a message again has no effect.
+fn stream_new_l1_messages<TBaseLayerSource: BaseLayerSourceTrait + Sync>(
+    reader: StorageReader,
+    base_layer_source: Arc<TBaseLayerSource>,
+    base_layer_propagation_sleep_duration: Duration,
+    index_l1_messages: bool,
+    l1_messages_start_block: Option<u64>,
+    l1_messages_finality: u64,
+) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
+    try_stream! {
+        if !index_l1_messages {
+            futures_util::future::pending::<()>().await;
+        }
+        loop {
+            tokio::time::sleep(base_layer_propagation_sleep_duration).await;
+            let Some(latest_l1_block) =
+                base_layer_source.latest_l1_block_number(l1_messages_finality).await?
+            else {
+                continue;
+            };
+            let from_block = {
+                let txn = reader.begin_ro_txn()?;
+                match (l1_messages_start_block, txn.get_base_layer_messages_start()?) {
+                    (Some(start_block), None) => start_block,
+                    (Some(start_block), Some(indexed_start)) if start_block < indexed_start => {
+                        start_block
+                    }
+                    _ => txn.get_base_layer_messages_marker()?.unwrap_or(latest_l1_block),
+                }
+            };
+            if from_block > latest_l1_block {
+                continue;
+            }
+            let until_block =
+                min(latest_l1_block, from_block + L1_MESSAGES_MAX_BLOCK_RANGE - 1);
+            debug!("Fetching the messages to L2 of L1 blocks {from_block} to {until_block}.");
+            let messages = base_layer_source.messages_to_l2(from_block, until_block).await?;
+            yield SyncEvent::NewL1Messages {
+                messages,
+                from_l1_block: from_block,
+                next_l1_block: until_block + 1,
+            };
+        }
+    }
+}
+
+// Yields a history pruning event every `history_pruning_interval`, or never if the history pruning
+// is disabled.
+fn stream_history_pruning(
+    history_pruning_interval: Option<Duration>,
+) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
+    try_stream! {
+        loop {
+            match history_pruning_interval {
+                Some(interval) => tokio::time::sleep(interval).await,
+                None => futures_util::future::pending().await,
+            }
+            yield SyncEvent::PruneHistory;
+        }
+    }
+}
+
 // This function is used to check if the sync is stuck.
 // TODO: fix the bug and remove this function.
 // TODO(dvir): add a test for this scenario.
@@ -1,9 +1,12 @@
 use async_trait::async_trait;
 #[cfg(test)]
 use mockall::automock;
+use papyrus_base_layer::constants::LOG_MESSAGE_TO_L2_EVENT_IDENTIFIER;
 use papyrus_base_layer::ethereum_base_layer_contract::EthereumBaseLayerContract;
-use papyrus_base_layer::BaseLayerContract;
+use papyrus_base_layer::{BaseLayerContract, L1Event};
 use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::Nonce;
+use starknet_api::transaction::L1TransactionHash;
 
 pub type EthereumBaseLayerSource = EthereumBaseLayerContract;
 
@@ -25,6 +28,20 @@ pub trait BaseLayerSourceTrait {
     async fn latest_proved_block(
         &self,
     ) -> Result<Option<(BlockNumber, BlockHash)>, BaseLayerSourceError>;
+
+    /// Returns the number of the latest L1 block with at least `finality` confirmations.
+    async fn latest_l1_block_number(
+        &self,
+        finality: u64,
+    ) -> Result<Option<u64>, BaseLayerSourceError>;
+
+    /// Returns the messages to L2 sent between the given L1 blocks (inclusive), each with the hash
+    /// of the L1 transaction that sent it.
+    async fn messages_to_l2(
+        &self,
+        from_block: u64,
+        until_block: u64,
+    ) -> Result<Vec<(L1TransactionHash, Nonce)>, BaseLayerSourceError>;
 }
 
 #[async_trait]
@@ -42,4 +59,31 @@ impl<
             .map(|block| block.map(|block| (block.number, block.hash)))
             .map_err(|e| BaseLayerSourceError::BaseLayerContractError(Box::new(e)))
     }
+
+    async fn latest_l1_block_number(
+        &self,
+        finality: u64,
+    ) -> Result<Option<u64>, BaseLayerSourceError> {
+        self.latest_l1_block_number(finality)
+            .await
+            .map_err(|e| BaseLayerSourceError::BaseLayerContractError(Box::new(e)))
+    }
+
+    async fn messages_to_l2(
+        &self,
+        from_block: u64,
+        until_block: u64,
+    ) -> Result<Vec<(L1TransactionHash, Nonce)>, BaseLayerSourceError> {
+        let events = self
+            .events(from_block, until_block, &[LOG_MESSAGE_TO_L2_EVENT_IDENTIFIER])
+            .await
+            .map_err(|e| BaseLayerSourceError::BaseLayerContractError(Box::new(e)))?;
+        Ok(events
+            .into_iter()
+            .filter_map(|event| match event {
+                L1Event::LogMessageToL2 { tx, l1_tx_hash, .. } => Some((l1_tx_hash, tx.nonce)),
+                _ => None,
+            })
+            .collect())
+    }
 }
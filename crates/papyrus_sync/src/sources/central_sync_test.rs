@@ -103,6 +103,10 @@ fn get_test_sync_config(verify_blocks: bool) -> SyncConfig {
         state_updates_max_stream_size: STREAM_SIZE,
         verify_blocks,
         collect_pending_data: false,
+        index_l1_messages: false,
+        l1_messages_start_block: None,
+        l1_messages_finality: 0,
+        store_patricia_tries: false,
     }
 }
 
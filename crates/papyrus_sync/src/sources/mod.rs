@@ -3,3 +3,4 @@ pub mod central;
 #[cfg(test)]
 mod central_sync_test;
 pub mod pending;
+pub mod sequencer_pending;
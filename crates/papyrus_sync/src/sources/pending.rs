@@ -7,6 +7,9 @@ use std::sync::Arc;
 use async_trait::async_trait;
 #[cfg(test)]
 use mockall::automock;
+use papyrus_storage::StorageError;
+use starknet_api::transaction::TransactionHash;
+use starknet_batcher_types::communication::BatcherClientError;
 use starknet_client::reader::{
     PendingData,
     ReaderClientError,
@@ -30,6 +33,12 @@ pub enum PendingError {
     ClientError(#[from] Arc<ReaderClientError>),
     #[error("Pending block not found")]
     PendingBlockNotFound,
+    #[error(transparent)]
+    BatcherClientError(#[from] Arc<BatcherClientError>),
+    #[error(transparent)]
+    StorageError(#[from] StorageError),
+    #[error("Transaction {0} of the pending proposal has an unsupported version.")]
+    UnsupportedTransactionVersion(TransactionHash),
 }
 #[cfg_attr(test, automock)]
 #[async_trait]
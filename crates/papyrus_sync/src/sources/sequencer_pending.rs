@@ -0,0 +1,354 @@
+//! A pending source that reflects the proposal the local sequencer is executing, instead of the
+//! pending block polled from the central feeder gateway.
+#[cfg(test)]
+#[path = "sequencer_pending_test.rs"]
+mod sequencer_pending_test;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use papyrus_config::converters::deserialize_milliseconds_to_duration;
+use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::StorageReader;
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{BlockHash, GasPricePerToken, StarknetVersion};
+use starknet_api::core::{GlobalRoot, SequencerContractAddress};
+use starknet_api::data_availability::L1DataAvailabilityMode;
+use starknet_api::executable_transaction::{AccountTransaction, Transaction};
+use starknet_api::state::ThinStateDiff;
+use starknet_api::transaction::{
+    DeclareTransaction,
+    DeployAccountTransaction,
+    InvokeTransaction,
+    TransactionOffsetInBlock,
+    TransactionVersion,
+};
+use starknet_batcher_types::batcher_types::{PendingProposal, PendingTransactionOutput};
+use starknet_batcher_types::communication::{RemoteBatcherClient, SharedBatcherClient};
+use starknet_client::reader::objects::pending_data::{
+    PendingBlock,
+    PendingBlockOrDeprecated,
+    PendingStateUpdate,
+};
+use starknet_client::reader::objects::transaction::{
+    IntermediateDeclareTransaction,
+    IntermediateDeployAccountTransaction,
+    IntermediateInvokeTransaction,
+    L1HandlerTransaction as ClientL1HandlerTransaction,
+    L2ToL1Message,
+    ReservedDataAvailabilityMode,
+    Transaction as ClientTransaction,
+    TransactionExecutionStatus,
+    TransactionReceipt,
+};
+use starknet_client::reader::{
+    DeclaredClassHashEntry,
+    DeployedContract,
+    PendingData,
+    ReplacedClass,
+    StateDiff,
+    StorageEntry,
+};
+use starknet_sequencer_infra::component_definitions::RemoteClientConfig;
+use starknet_types_core::felt::Felt;
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+use crate::sources::pending::{PendingError, PendingSourceTrait};
+use crate::GENESIS_HASH;
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct SequencerPendingSourceConfig {
+    pub batcher_client: RemoteClientConfig,
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub poll_interval: Duration,
+}
+
+impl Default for SequencerPendingSourceConfig {
+    fn default() -> Self {
+        Self {
+            batcher_client: RemoteClientConfig::default(),
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+impl SerializeConfig for SequencerPendingSourceConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        let mut dump = append_sub_config_name(self.batcher_client.dump(), "batcher_client");
+        dump.append(&mut BTreeMap::from([ser_param(
+            "poll_interval",
+            &u64::try_from(self.poll_interval.as_millis()).expect("Poll interval should fit u64."),
+            "Time in milliseconds between requests for the proposal the batcher is executing.",
+            ParamPrivacyInput::Public,
+        )]));
+        dump
+    }
+}
+
+/// Serves the proposal that the batcher of the local sequencer is executing as the pending block.
+/// The classes declared in the proposal aren't served as pending classes, since the batcher holds
+/// only their compiled classes and not their Sierra.
+pub struct SequencerPendingSource {
+    pub batcher_client: SharedBatcherClient,
+    pub storage_reader: StorageReader,
+    pub poll_interval: Duration,
+}
+
+impl SequencerPendingSource {
+    pub fn new(config: &SequencerPendingSourceConfig, storage_reader: StorageReader) -> Self {
+        Self {
+            batcher_client: Arc::new(RemoteBatcherClient::new(config.batcher_client.clone())),
+            storage_reader,
+            poll_interval: config.poll_interval,
+        }
+    }
+
+    /// Keeps the pending data equal to the proposal the batcher is executing. Unlike the pending
+    /// data of the feeder gateway, the pending data may shrink, since the batcher may abort a
+    /// proposal and execute a different one.
+    pub async fn run(self, pending_data: Arc<RwLock<PendingData>>) {
+        loop {
+            match self.get_pending_data().await {
+                Ok(new_pending_data) => *pending_data.write().await = new_pending_data,
+                // The RPC serves an empty pending block on top of the latest block instead.
+                Err(PendingError::PendingBlockNotFound) => {
+                    debug!("The batcher isn't executing a proposal on top of the latest block.");
+                    *pending_data.write().await = PendingData::default();
+                }
+                Err(err) => warn!("Failed to get the pending proposal from the batcher: {err}"),
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
+
+#[async_trait]
+impl PendingSourceTrait for SequencerPendingSource {
+    async fn get_pending_data(&self) -> Result<PendingData, PendingError> {
+        let pending_proposal = self
+            .batcher_client
+            .get_pending_proposal()
+            .await
+            .map_err(|err| PendingError::BatcherClientError(Arc::new(err)))?
+            .pending_proposal;
+        let txn = self.storage_reader.begin_ro_txn()?;
+        let header_marker = txn.get_header_marker()?;
+        // A proposal on top of a block that wasn't synced yet can't be served as pending.
+        let Some(pending_proposal) =
+            pending_proposal.filter(|proposal| proposal.block_info.block_number == header_marker)
+        else {
+            return Err(PendingError::PendingBlockNotFound);
+        };
+        let (parent_block_hash, old_root) = match header_marker.prev() {
+            None => (BlockHash(Felt::from_hex_unchecked(GENESIS_HASH)), GlobalRoot::default()),
+            Some(latest_block_number) => {
+                let latest_header = txn
+                    .get_block_header(latest_block_number)?
+                    .expect("Block before the header marker must have header in the database.");
+                (latest_header.block_hash, latest_header.block_header_without_hash.state_root)
+            }
+        };
+        pending_proposal_to_pending_data(pending_proposal, parent_block_hash, old_root)
+    }
+}
+
+fn pending_proposal_to_pending_data(
+    pending_proposal: PendingProposal,
+    parent_block_hash: BlockHash,
+    old_root: GlobalRoot,
+) -> Result<PendingData, PendingError> {
+    let PendingProposal { block_info, transactions, transaction_outputs, state_diff } =
+        pending_proposal;
+    let transaction_receipts = transactions
+        .iter()
+        .zip(transaction_outputs)
+        .enumerate()
+        .map(|(index, (transaction, output))| client_receipt(index, transaction, output))
+        .collect();
+    let transactions =
+        transactions.into_iter().map(client_transaction).collect::<Result<_, _>>()?;
+    let gas_prices = &block_info.gas_prices;
+    let block = PendingBlock {
+        parent_block_hash,
+        l1_gas_price: GasPricePerToken {
+            price_in_fri: gas_prices.strk_gas_prices.l1_gas_price.get(),
+            price_in_wei: gas_prices.eth_gas_prices.l1_gas_price.get(),
+        },
+        l1_data_gas_price: GasPricePerToken {
+            price_in_fri: gas_prices.strk_gas_prices.l1_data_gas_price.get(),
+            price_in_wei: gas_prices.eth_gas_prices.l1_data_gas_price.get(),
+        },
+        l2_gas_price: GasPricePerToken {
+            price_in_fri: gas_prices.strk_gas_prices.l2_gas_price.get(),
+            price_in_wei: gas_prices.eth_gas_prices.l2_gas_price.get(),
+        },
+        transactions,
+        timestamp: block_info.block_timestamp,
+        sequencer_address: SequencerContractAddress(block_info.sequencer_address),
+        transaction_receipts,
+        starknet_version: StarknetVersion::LATEST.to_string(),
+        l1_da_mode: if block_info.use_kzg_da {
+            L1DataAvailabilityMode::Blob
+        } else {
+            L1DataAvailabilityMode::Calldata
+        },
+        ..Default::default()
+    };
+    Ok(PendingData {
+        block: PendingBlockOrDeprecated::Current(block),
+        state_update: PendingStateUpdate { old_root, state_diff: client_state_diff(state_diff) },
+    })
+}
+
+// The local sequencer only accepts transactions of version 3, so older versions aren't converted.
+fn client_transaction(transaction: Transaction) -> Result<ClientTransaction, PendingError> {
+    let tx_hash = transaction.tx_hash();
+    let unsupported_version = || PendingError::UnsupportedTransactionVersion(tx_hash);
+    let client_transaction = match transaction {
+        Transaction::Account(AccountTransaction::Declare(declare_tx)) => {
+            let DeclareTransaction::V3(tx) = declare_tx.tx else {
+                return Err(unsupported_version());
+            };
+            ClientTransaction::Declare(IntermediateDeclareTransaction {
+                resource_bounds: Some(tx.resource_bounds),
+                tip: Some(tx.tip),
+                signature: tx.signature,
+                nonce: tx.nonce,
+                class_hash: tx.class_hash,
+                compiled_class_hash: Some(tx.compiled_class_hash),
+                sender_address: tx.sender_address,
+                nonce_data_availability_mode: Some(ReservedDataAvailabilityMode::Reserved),
+                fee_data_availability_mode: Some(ReservedDataAvailabilityMode::Reserved),
+                paymaster_data: Some(tx.paymaster_data),
+                account_deployment_data: Some(tx.account_deployment_data),
+                max_fee: None,
+                version: TransactionVersion::THREE,
+                transaction_hash: tx_hash,
+            })
+        }
+        Transaction::Account(AccountTransaction::DeployAccount(deploy_account_tx)) => {
+            let DeployAccountTransaction::V3(tx) = deploy_account_tx.tx else {
+                return Err(unsupported_version());
+            };
+            ClientTransaction::DeployAccount(IntermediateDeployAccountTransaction {
+                resource_bounds: Some(tx.resource_bounds),
+                tip: Some(tx.tip),
+                signature: tx.signature,
+                nonce: tx.nonce,
+                class_hash: tx.class_hash,
+                contract_address_salt: tx.contract_address_salt,
+                constructor_calldata: tx.constructor_calldata,
+                nonce_data_availability_mode: Some(ReservedDataAvailabilityMode::Reserved),
+                fee_data_availability_mode: Some(ReservedDataAvailabilityMode::Reserved),
+                paymaster_data: Some(tx.paymaster_data),
+                sender_address: deploy_account_tx.contract_address,
+                max_fee: None,
+                transaction_hash: tx_hash,
+                version: TransactionVersion::THREE,
+            })
+        }
+        Transaction::Account(AccountTransaction::Invoke(invoke_tx)) => {
+            let InvokeTransaction::V3(tx) = invoke_tx.tx else {
+                return Err(unsupported_version());
+            };
+            ClientTransaction::Invoke(IntermediateInvokeTransaction {
+                resource_bounds: Some(tx.resource_bounds),
+                tip: Some(tx.tip),
+                calldata: tx.calldata,
+                sender_address: tx.sender_address,
+                entry_point_selector: None,
+                nonce: Some(tx.nonce),
+                max_fee: None,
+                signature: tx.signature,
+                nonce_data_availability_mode: Some(ReservedDataAvailabilityMode::Reserved),
+                fee_data_availability_mode: Some(ReservedDataAvailabilityMode::Reserved),
+                paymaster_data: Some(tx.paymaster_data),
+                account_deployment_data: Some(tx.account_deployment_data),
+                transaction_hash: tx_hash,
+                version: TransactionVersion::THREE,
+            })
+        }
+        Transaction::L1Handler(l1_handler_tx) => {
+            ClientTransaction::L1Handler(ClientL1HandlerTransaction {
+                transaction_hash: tx_hash,
+                version: l1_handler_tx.tx.version,
+                nonce: l1_handler_tx.tx.nonce,
+                contract_address: l1_handler_tx.tx.contract_address,
+                entry_point_selector: l1_handler_tx.tx.entry_point_selector,
+                calldata: l1_handler_tx.tx.calldata,
+            })
+        }
+    };
+    Ok(client_transaction)
+}
+
+// The batcher doesn't report the execution resources of the transactions, so they are left empty.
+fn client_receipt(
+    index: usize,
+    transaction: &Transaction,
+    output: PendingTransactionOutput,
+) -> TransactionReceipt {
+    let execution_status = match output.revert_reason {
+        Some(_) => TransactionExecutionStatus::Reverted,
+        None => TransactionExecutionStatus::Succeeded,
+    };
+    TransactionReceipt {
+        transaction_index: TransactionOffsetInBlock(index),
+        transaction_hash: transaction.tx_hash(),
+        l2_to_l1_messages: output
+            .messages_sent
+            .into_iter()
+            .map(|message| L2ToL1Message {
+                from_address: message.from_address,
+                to_address: message.to_address,
+                payload: message.payload,
+            })
+            .collect(),
+        events: output.events,
+        actual_fee: output.actual_fee,
+        execution_status,
+        revert_error: output.revert_reason,
+        ..Default::default()
+    }
+}
+
+fn client_state_diff(state_diff: ThinStateDiff) -> StateDiff {
+    StateDiff {
+        storage_diffs: state_diff
+            .storage_diffs
+            .into_iter()
+            .map(|(address, storage_diff)| {
+                let entries = storage_diff
+                    .into_iter()
+                    .map(|(key, value)| StorageEntry { key, value })
+                    .collect();
+                (address, entries)
+            })
+            .collect(),
+        deployed_contracts: state_diff
+            .deployed_contracts
+            .into_iter()
+            .map(|(address, class_hash)| DeployedContract { address, class_hash })
+            .collect(),
+        declared_classes: state_diff
+            .declared_classes
+            .into_iter()
+            .map(|(class_hash, compiled_class_hash)| DeclaredClassHashEntry {
+                class_hash,
+                compiled_class_hash,
+            })
+            .collect(),
+        old_declared_contracts: state_diff.deprecated_declared_classes,
+        nonces: state_diff.nonces,
+        replaced_classes: state_diff
+            .replaced_classes
+            .into_iter()
+            .map(|(address, class_hash)| ReplacedClass { address, class_hash })
+            .collect(),
+    }
+}
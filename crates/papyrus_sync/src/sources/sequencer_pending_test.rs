@@ -0,0 +1,151 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use assert_matches::assert_matches;
+use indexmap::indexmap;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageReader;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockHeader, BlockHeaderWithoutHash, BlockInfo, BlockNumber};
+use starknet_api::core::{ClassHash, GlobalRoot, Nonce};
+use starknet_api::executable_transaction::Transaction;
+use starknet_api::state::ThinStateDiff;
+use starknet_api::test_utils::invoke::{executable_invoke_tx, InvokeTxArgs};
+use starknet_api::transaction::fields::Fee;
+use starknet_api::{contract_address, felt, tx_hash};
+use starknet_batcher_types::batcher_types::{
+    GetPendingProposalResponse,
+    PendingProposal,
+    PendingTransactionOutput,
+};
+use starknet_batcher_types::communication::MockBatcherClient;
+use starknet_client::reader::objects::pending_data::PendingBlockOrDeprecated;
+use starknet_client::reader::objects::transaction::TransactionExecutionStatus;
+use starknet_client::reader::{DeployedContract, ReplacedClass};
+use starknet_types_core::felt::Felt;
+use tempfile::TempDir;
+
+use crate::sources::pending::{PendingError, PendingSourceTrait};
+use crate::sources::sequencer_pending::SequencerPendingSource;
+
+const LATEST_BLOCK_HASH: BlockHash = BlockHash(Felt::from_hex_unchecked("0x1234"));
+const LATEST_STATE_ROOT: GlobalRoot = GlobalRoot(Felt::from_hex_unchecked("0x5678"));
+
+fn sequencer_pending_source(
+    storage_reader: StorageReader,
+    pending_proposal: Option<PendingProposal>,
+) -> SequencerPendingSource {
+    let mut batcher_client = MockBatcherClient::new();
+    batcher_client
+        .expect_get_pending_proposal()
+        .times(1)
+        .return_once(|| Ok(GetPendingProposalResponse { pending_proposal }));
+    SequencerPendingSource {
+        batcher_client: Arc::new(batcher_client),
+        storage_reader,
+        poll_interval: Duration::from_millis(500),
+    }
+}
+
+// The temporary directory of the storage is returned so that it outlives the test.
+fn storage_reader_with_one_block() -> (StorageReader, TempDir) {
+    let ((storage_reader, mut storage_writer), temp_dir) = get_test_storage();
+    let header = BlockHeader {
+        block_hash: LATEST_BLOCK_HASH,
+        block_header_without_hash: BlockHeaderWithoutHash {
+            state_root: LATEST_STATE_ROOT,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &header)
+        .unwrap()
+        .commit()
+        .unwrap();
+    (storage_reader, temp_dir)
+}
+
+#[tokio::test]
+async fn get_pending_data() {
+    let tx = Transaction::Account(executable_invoke_tx(InvokeTxArgs {
+        tx_hash: tx_hash!(1),
+        ..Default::default()
+    }));
+    let address = contract_address!("0x100");
+    let class_hash = ClassHash(felt!("0x200"));
+    let replacing_address = contract_address!("0x101");
+    let replacing_class_hash = ClassHash(felt!("0x201"));
+    let pending_proposal = PendingProposal {
+        block_info: BlockInfo { block_number: BlockNumber(1), ..Default::default() },
+        transactions: vec![tx],
+        transaction_outputs: vec![PendingTransactionOutput {
+            actual_fee: Fee(10),
+            revert_reason: Some("Reverted.".to_string()),
+            ..Default::default()
+        }],
+        state_diff: ThinStateDiff {
+            deployed_contracts: indexmap! { address => class_hash },
+            nonces: indexmap! { address => Nonce(felt!("0x1")) },
+            replaced_classes: indexmap! { replacing_address => replacing_class_hash },
+            ..Default::default()
+        },
+    };
+    let (storage_reader, _temp_dir) = storage_reader_with_one_block();
+    let pending_source = sequencer_pending_source(storage_reader, Some(pending_proposal));
+
+    let pending_data = pending_source.get_pending_data().await.unwrap();
+    let PendingBlockOrDeprecated::Current(block) = pending_data.block else {
+        panic!("Expected a current pending block.");
+    };
+    assert_eq!(block.parent_block_hash, LATEST_BLOCK_HASH);
+    assert_eq!(block.transactions.len(), 1);
+    assert_eq!(block.transactions[0].transaction_hash(), tx_hash!(1));
+    let receipt = &block.transaction_receipts[0];
+    assert_eq!(receipt.transaction_hash, tx_hash!(1));
+    assert_eq!(receipt.actual_fee, Fee(10));
+    assert_eq!(receipt.execution_status, TransactionExecutionStatus::Reverted);
+    assert_eq!(receipt.revert_error, Some("Reverted.".to_string()));
+    assert_eq!(pending_data.state_update.old_root, LATEST_STATE_ROOT);
+    assert_eq!(
+        pending_data.state_update.state_diff.deployed_contracts,
+        vec![DeployedContract { address, class_hash }]
+    );
+    assert_eq!(
+        pending_data.state_update.state_diff.replaced_classes,
+        vec![ReplacedClass { address: replacing_address, class_hash: replacing_class_hash }]
+    );
+    assert_eq!(
+        pending_data.state_update.state_diff.nonces,
+        indexmap! { address => Nonce(felt!("0x1")) }
+    );
+}
+
+#[tokio::test]
+async fn get_pending_data_of_proposal_not_on_top_of_latest_block() {
+    let pending_proposal = PendingProposal {
+        block_info: BlockInfo { block_number: BlockNumber(2), ..Default::default() },
+        ..Default::default()
+    };
+    let (storage_reader, _temp_dir) = storage_reader_with_one_block();
+    let pending_source = sequencer_pending_source(storage_reader, Some(pending_proposal));
+
+    assert_matches!(
+        pending_source.get_pending_data().await,
+        Err(PendingError::PendingBlockNotFound)
+    );
+}
+
+#[tokio::test]
+async fn get_pending_data_without_proposal() {
+    let (storage_reader, _temp_dir) = storage_reader_with_one_block();
+    let pending_source = sequencer_pending_source(storage_reader, None);
+
+    assert_matches!(
+        pending_source.get_pending_data().await,
+        Err(PendingError::PendingBlockNotFound)
+    );
+}
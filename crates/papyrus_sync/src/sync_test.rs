@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -6,8 +7,9 @@ use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use futures_util::StreamExt;
 use indexmap::IndexMap;
 use papyrus_common::pending_classes::{ApiContractClass, PendingClasses, PendingClassesTrait};
-use papyrus_storage::base_layer::BaseLayerStorageReader;
+use papyrus_storage::base_layer::{BaseLayerStorageReader, BaseLayerStorageWriter};
 use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::patricia::{GlobalTrieRoots, PatriciaStorageReader};
 use papyrus_storage::test_utils::get_test_storage;
 use papyrus_storage::{StorageReader, StorageWriter};
 use papyrus_test_utils::{get_rng, GetTestInstance};
@@ -17,6 +19,7 @@ use starknet_api::core::{ClassHash, CompiledClassHash, Nonce};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::hash::StarkHash;
 use starknet_api::state::{SierraContractClass, StateDiff};
+use starknet_api::transaction::L1TransactionHash;
 use starknet_api::{contract_address, felt, storage_key};
 use starknet_client::reader::objects::pending_data::{
     AcceptedOnL2ExtraData,
@@ -27,7 +30,24 @@ use starknet_client::reader::objects::pending_data::{
 use starknet_client::reader::objects::state::StateDiff as ClientStateDiff;
 use starknet_client::reader::objects::transaction::Transaction as ClientTransaction;
 use starknet_client::reader::{DeclaredClassHashEntry, PendingData};
+use starknet_committer::block_committer::commit::commit_block;
+use starknet_committer::block_committer::input::{
+    ConfigImpl,
+    ContractAddress as CommitterContractAddress,
+    Input,
+    StarknetStorageKey,
+    StarknetStorageValue,
+    StateDiff as CommitterStateDiff,
+};
+use starknet_committer::patricia_merkle_tree::types::{
+    ClassHash as CommitterClassHash,
+    CompiledClassHash as CommitterCompiledClassHash,
+    Nonce as CommitterNonce,
+};
+use starknet_patricia::hash::hash_trait::HashOutput;
+use starknet_patricia::storage::map_storage::MapStorage;
 use tokio::sync::RwLock;
+use tracing::level_filters::LevelFilter;
 
 use crate::sources::base_layer::MockBaseLayerSourceTrait;
 use crate::sources::central::MockCentralSourceTrait;
@@ -35,6 +55,7 @@ use crate::sources::pending::MockPendingSourceTrait;
 use crate::{
     sort_state_diff,
     stream_new_base_layer_block,
+    stream_new_l1_messages,
     sync_pending_data,
     GenericStateSync,
     StateSyncError,
@@ -43,6 +64,8 @@ use crate::{
     GENESIS_HASH,
 };
 
+const L1_MESSAGES_FINALITY: u64 = 5;
+
 // TODO(anatg): Add a test to check that the sync calls the sort_state_diff function
 // before writing to the storage.
 #[test]
@@ -171,6 +194,105 @@ async fn stream_new_base_layer_block_no_blocks_on_base_layer() {
     assert_matches!(event, SyncEvent::NewBaseLayerBlock { block_number: BlockNumber(1), .. });
 }
 
+#[tokio::test]
+async fn stream_new_l1_messages_test() {
+    let (reader, mut writer) = get_test_storage().0;
+    let message = (L1TransactionHash([1; 32]), Nonce(felt!("0x1")));
+
+    // The first polling starts from the latest L1 block. The second polling finds no new L1 block,
+    // and the third one finds more new L1 blocks than are fetched at once.
+    let mut latest_l1_blocks = vec![10, 10, 5000].into_iter();
+    let mut mock = MockBaseLayerSourceTrait::new();
+    mock.expect_latest_l1_block_number()
+        .withf(|finality| *finality == L1_MESSAGES_FINALITY)
+        .times(3)
+        .returning(move |_| Ok(latest_l1_blocks.next()));
+    mock.expect_messages_to_l2()
+        .withf(|from_block, until_block| (*from_block, *until_block) == (10, 10))
+        .times(1)
+        .returning(move |_, _| Ok(vec![message]));
+    mock.expect_messages_to_l2()
+        .withf(|from_block, until_block| (*from_block, *until_block) == (11, 1010))
+        .times(1)
+        .returning(|_, _| Ok(vec![]));
+    let mut stream = stream_new_l1_messages(
+        reader,
+        Arc::new(mock),
+        Duration::from_millis(0),
+        true,
+        None,
+        L1_MESSAGES_FINALITY,
+    )
+    .boxed();
+
+    let event = stream.next().await.unwrap().unwrap();
+    assert_matches!(
+        event,
+        SyncEvent::NewL1Messages { messages, from_l1_block: 10, next_l1_block: 11 }
+        if messages == vec![message]
+    );
+    writer.begin_rw_txn().unwrap().update_base_layer_messages_marker(11).unwrap().commit().unwrap();
+
+    let event = stream.next().await.unwrap().unwrap();
+    assert_matches!(
+        event,
+        SyncEvent::NewL1Messages { messages, from_l1_block: 11, next_l1_block: 1011 }
+        if messages.is_empty()
+    );
+}
+
+#[tokio::test]
+async fn stream_new_l1_messages_backfill() {
+    let (reader, mut writer) = get_test_storage().0;
+
+    // The L1 blocks 10 to 19 were indexed, and the start block is before them.
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_base_layer_messages_start(10)
+        .unwrap()
+        .update_base_layer_messages_marker(20)
+        .unwrap()
+        .commit()
+        .unwrap();
+    let mut mock = MockBaseLayerSourceTrait::new();
+    let mut latest_l1_blocks = vec![30, 40].into_iter();
+    mock.expect_latest_l1_block_number().times(2).returning(move |_| Ok(latest_l1_blocks.next()));
+    mock.expect_messages_to_l2()
+        .withf(|from_block, until_block| (*from_block, *until_block) == (5, 30))
+        .times(1)
+        .returning(|_, _| Ok(vec![]));
+    mock.expect_messages_to_l2()
+        .withf(|from_block, until_block| (*from_block, *until_block) == (31, 40))
+        .times(1)
+        .returning(|_, _| Ok(vec![]));
+    let mut stream = stream_new_l1_messages(
+        reader,
+        Arc::new(mock),
+        Duration::from_millis(0),
+        true,
+        Some(5),
+        L1_MESSAGES_FINALITY,
+    )
+    .boxed();
+
+    let event = stream.next().await.unwrap().unwrap();
+    assert_matches!(event, SyncEvent::NewL1Messages { from_l1_block: 5, next_l1_block: 31, .. });
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_base_layer_messages_start(5)
+        .unwrap()
+        .update_base_layer_messages_marker(31)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // Once the start block was indexed, the indexing continues from the messages marker.
+    let event = stream.next().await.unwrap().unwrap();
+    assert_matches!(event, SyncEvent::NewL1Messages { from_l1_block: 31, next_l1_block: 41, .. });
+}
+
 #[test]
 fn store_base_layer_block_test() {
     let (reader, mut writer) = get_test_storage().0;
@@ -221,6 +343,113 @@ fn store_base_layer_block_test() {
     assert_eq!(base_layer_marker, BlockNumber(1));
 }
 
+#[tokio::test]
+async fn store_state_diff_stores_patricia_tries() {
+    let (reader, writer) = get_test_storage().0;
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig { store_patricia_tries: true, ..Default::default() },
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        sequencer_pub_key: None,
+    };
+
+    let address = contract_address!("0x100");
+    let other_address = contract_address!("0x200");
+    let class_hash = ClassHash(felt!("0x1"));
+    let compiled_class_hash = CompiledClassHash(felt!("0x2"));
+    let first_state_diff = StateDiff {
+        deployed_contracts: IndexMap::from([(address, class_hash)]),
+        storage_diffs: IndexMap::from([(
+            address,
+            IndexMap::from([(storage_key!("0x10"), felt!("0x11"))]),
+        )]),
+        declared_classes: IndexMap::from([(
+            class_hash,
+            (compiled_class_hash, SierraContractClass::default()),
+        )]),
+        ..Default::default()
+    };
+    let second_state_diff = StateDiff {
+        storage_diffs: IndexMap::from([(
+            other_address,
+            IndexMap::from([(storage_key!("0x20"), felt!("0x21"))]),
+        )]),
+        nonces: IndexMap::from([(address, Nonce(felt!("0x1")))]),
+        ..Default::default()
+    };
+    for (block_number, state_diff) in [first_state_diff, second_state_diff].into_iter().enumerate()
+    {
+        gen_state_sync
+            .store_state_diff(
+                BlockNumber(block_number.try_into().unwrap()),
+                BlockHash::default(),
+                state_diff,
+                IndexMap::new(),
+            )
+            .await
+            .unwrap();
+    }
+
+    // The tries of the blocks are built one on top of the other, so they're the same as the tries
+    // of both state diffs together.
+    let combined_state_diff = CommitterStateDiff {
+        address_to_class_hash: HashMap::from([(
+            CommitterContractAddress((*address.0.key()).into()),
+            CommitterClassHash(class_hash.0.into()),
+        )]),
+        address_to_nonce: HashMap::from([(
+            CommitterContractAddress((*address.0.key()).into()),
+            CommitterNonce(felt!("0x1").into()),
+        )]),
+        class_hash_to_compiled_class_hash: HashMap::from([(
+            CommitterClassHash(class_hash.0.into()),
+            CommitterCompiledClassHash(compiled_class_hash.0.into()),
+        )]),
+        storage_updates: HashMap::from([
+            (
+                CommitterContractAddress((*address.0.key()).into()),
+                HashMap::from([(
+                    StarknetStorageKey(felt!("0x10").into()),
+                    StarknetStorageValue(felt!("0x11").into()),
+                )]),
+            ),
+            (
+                CommitterContractAddress((*other_address.0.key()).into()),
+                HashMap::from([(
+                    StarknetStorageKey(felt!("0x20").into()),
+                    StarknetStorageValue(felt!("0x21").into()),
+                )]),
+            ),
+        ]),
+    };
+    let filled_forest = commit_block(Input {
+        storage: HashMap::new(),
+        state_diff: combined_state_diff,
+        contracts_trie_root_hash: HashOutput::default(),
+        classes_trie_root_hash: HashOutput::default(),
+        config: ConfigImpl::new(false, LevelFilter::INFO),
+    })
+    .await
+    .unwrap();
+    let expected_roots = GlobalTrieRoots {
+        contracts_trie_root: filled_forest.get_contract_root_hash().0.into(),
+        classes_trie_root: filled_forest.get_compiled_class_root_hash().0.into(),
+    };
+    let txn = gen_state_sync.reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_global_trie_roots(BlockNumber(1)).unwrap(), Some(expected_roots));
+    let mut expected_nodes = MapStorage::default();
+    filled_forest.write_to_storage(&mut expected_nodes);
+    for (key, value) in expected_nodes.storage {
+        assert_eq!(txn.get_patricia_node(&key).unwrap(), Some(value));
+    }
+}
+
 // Adds to the storage 'headers_num' headers.
 fn add_headers(headers_num: u64, writer: &mut StorageWriter) {
     for i in 0..headers_num {
@@ -144,6 +144,7 @@ use starknet_api::transaction::{
     L1HandlerTransaction,
     L1HandlerTransactionOutput,
     L1ToL2Payload,
+    L1TransactionHash,
     L2ToL1Payload,
     MessageToL1,
     MessageToL2,
@@ -1152,6 +1153,12 @@ impl GetTestInstance for GasVector {
     }
 }
 
+impl GetTestInstance for L1TransactionHash {
+    fn get_test_instance(rng: &mut ChaCha8Rng) -> Self {
+        Self(rng.gen())
+    }
+}
+
 impl GetTestInstance for NonZeroU32 {
     fn get_test_instance(rng: &mut ChaCha8Rng) -> Self {
         max(1, rng.next_u32()).try_into().expect("Failed to convert a non-zero u32 to NonZeroU32")
@@ -2,7 +2,7 @@
 //!
 //! This runs multiple papyrus nodes communicating with each other to propose and vote on blocks. It
 //! uses the `run_consensus` binary which is able to simulate network issues for consensus messages.
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fs::{self, File};
 use std::os::unix::process::CommandExt;
 use std::process::Command;
@@ -15,6 +15,7 @@ use lazy_static::lazy_static;
 use nix::unistd::Pid;
 use papyrus_common::tcp::find_free_port;
 use papyrus_protobuf::consensus::DEFAULT_VALIDATOR_ID;
+use serde::Serialize;
 use tokio::process::Command as TokioCommand;
 
 lazy_static! {
@@ -26,6 +27,9 @@ const SECRET_KEY: &str = "0xabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcd
 const BOOT_NODE_PEER_ID: &str = "12D3KooWDFYi71juk6dYWo3UDvqs5gAzGDc124LSvcR5d187Tdvi";
 
 const MONITORING_PERIOD_SECONDS: u64 = 10;
+// Logged by the consensus context for each decision.
+const DECISION_LOG_PREFIX: &str = "Finished consensus for height: ";
+const DECISION_LOG_SEPARATOR: &str = ". Agreed on block: ";
 
 struct Node {
     validator_id: usize,
@@ -36,6 +40,8 @@ struct Node {
     height_and_timestamp: (Option<u64>, Option<Instant>), //(height, timestamp)
     // Number of times the nodes height was updated due to sync, instead of reaching a decision.
     sync_count: Option<u64>,
+    // The first height observed and the time since the simulation started.
+    first_height_and_elapsed: Option<(u64, Duration)>,
 }
 
 impl Node {
@@ -47,6 +53,7 @@ impl Node {
             process: None,
             height_and_timestamp: (None, None),
             sync_count: None,
+            first_height_and_elapsed: None,
         }
     }
 
@@ -142,6 +149,77 @@ struct PapyrusArgs {
         help = "Probability of sending an invalid message for test simulation."
     )]
     invalid_probability: Option<f64>,
+    #[arg(
+        long = "num_byzantine",
+        help = "Number of byzantine validators for test simulation, taken from the highest \
+                validator IDs."
+    )]
+    num_byzantine: Option<usize>,
+    #[arg(
+        long = "equivocation_probability",
+        help = "Probability of a byzantine validator voting for a different block."
+    )]
+    equivocation_probability: Option<f64>,
+    #[arg(
+        long = "withhold_proposal_probability",
+        help = "Probability of a byzantine validator withholding its proposal."
+    )]
+    withhold_proposal_probability: Option<f64>,
+    #[arg(
+        long = "invalid_proposal_probability",
+        help = "Probability of a byzantine validator proposing wrong content."
+    )]
+    invalid_proposal_probability: Option<f64>,
+    #[arg(
+        long = "partition_size",
+        help = "Splits the network into the validators with the lowest IDs and the rest."
+    )]
+    partition_size: Option<usize>,
+    #[arg(
+        long = "partition_start_height",
+        help = "The first height at which the network is partitioned."
+    )]
+    partition_start_height: Option<u64>,
+    #[arg(long = "partition_num_heights", help = "The number of heights which are partitioned.")]
+    partition_num_heights: Option<u64>,
+    #[arg(
+        long = "partition_rounds",
+        help = "The number of rounds in each partitioned height in which the network is \
+                partitioned."
+    )]
+    partition_rounds: Option<u32>,
+    #[arg(
+        long = "cross_partition_delay",
+        help = "The delay (seconds) of messages between network segments when not partitioned."
+    )]
+    cross_partition_delay: Option<f64>,
+}
+
+impl PapyrusArgs {
+    fn validator_id(i: usize) -> usize {
+        i + usize::try_from(DEFAULT_VALIDATOR_ID).expect("Conversion failed")
+    }
+
+    fn byzantine_validators(&self) -> Vec<usize> {
+        let num_byzantine = self.num_byzantine.unwrap_or(0);
+        assert!(num_byzantine <= self.num_validators, "Too many byzantine validators.");
+        (self.num_validators - num_byzantine..self.num_validators).map(Self::validator_id).collect()
+    }
+
+    // The validators in the same network segment as validator `i`.
+    fn partition_group(&self, i: usize) -> Option<Vec<usize>> {
+        let partition_size = self.partition_size?;
+        let group = if i < partition_size {
+            0..partition_size
+        } else {
+            partition_size..self.num_validators
+        };
+        Some(group.map(Self::validator_id).collect())
+    }
+}
+
+fn format_validator_ids(ids: &[usize]) -> String {
+    ids.iter().map(|id| format!("0x{id:x}")).collect::<Vec<_>>().join(",")
 }
 
 #[derive(Parser)]
@@ -210,7 +288,10 @@ async fn monitor_simulation(
         );
         // height is None when consensus has not been started yet.
         let elapsed = match height {
-            Some(_) => last_update.expect("Must be set if height is set").elapsed(),
+            Some(height) => {
+                node.first_height_and_elapsed.get_or_insert((height, start_time.elapsed()));
+                last_update.expect("Must be set if height is set").elapsed()
+            }
             None => start_time.elapsed(),
         };
         if elapsed > stagnation_timeout {
@@ -225,11 +306,12 @@ async fn monitor_simulation(
     false
 }
 
+// Runs the nodes until the simulation ends, returning the nodes and how long they ran.
 async fn run_simulation(
     mut nodes: Vec<Node>,
     max_test_duration: Duration,
     stagnation_timeout: Duration,
-) {
+) -> (Vec<Node>, Duration) {
     for node in nodes.iter_mut() {
         node.start();
     }
@@ -256,9 +338,119 @@ async fn run_simulation(
         }
     }
 
-    for mut node in nodes {
+    for node in nodes.iter_mut() {
         node.stop().await;
     }
+    (nodes, start_time.elapsed())
+}
+
+#[derive(Serialize)]
+struct NodeReport {
+    validator_id: String,
+    final_height: Option<u64>,
+    sync_count: Option<u64>,
+    heights_per_minute: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct SafetyViolation {
+    height: u64,
+    // Validator ID -> the blocks it decided on for this height.
+    decisions: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Serialize)]
+struct SimulationReport {
+    random_seed: Option<u64>,
+    num_validators: usize,
+    byzantine_validators: Vec<String>,
+    partition_size: Option<usize>,
+    duration_seconds: u64,
+    nodes: Vec<NodeReport>,
+    safety_violations: Vec<SafetyViolation>,
+}
+
+// Reads the decisions of a node from its logs, as a map from height to the decided blocks.
+fn read_decisions(logs_dir: &str, validator_id: usize) -> BTreeMap<u64, Vec<String>> {
+    let log_path = format!("{}/validator0x{:x}.txt", logs_dir, validator_id);
+    let logs = fs::read_to_string(&log_path).unwrap_or_default();
+    let mut decisions: BTreeMap<u64, Vec<String>> = BTreeMap::new();
+    for line in logs.lines() {
+        let Some((_, decision)) = line.split_once(DECISION_LOG_PREFIX) else {
+            continue;
+        };
+        let Some((height, block)) = decision.split_once(DECISION_LOG_SEPARATOR) else {
+            continue;
+        };
+        let Ok(height) = height.parse() else {
+            continue;
+        };
+        decisions.entry(height).or_default().push(block.trim().to_string());
+    }
+    decisions
+}
+
+// Safety is violated if 2 decisions were reached for the same height, by any of the nodes.
+fn find_safety_violations(logs_dir: &str, nodes: &[Node]) -> Vec<SafetyViolation> {
+    let mut decisions_by_height: BTreeMap<u64, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+    for node in nodes {
+        for (height, blocks) in read_decisions(logs_dir, node.validator_id) {
+            decisions_by_height
+                .entry(height)
+                .or_default()
+                .insert(format!("0x{:x}", node.validator_id), blocks);
+        }
+    }
+    decisions_by_height
+        .into_iter()
+        .filter(|(_, decisions)| decisions.values().flatten().collect::<HashSet<_>>().len() > 1)
+        .map(|(height, decisions)| SafetyViolation { height, decisions })
+        .collect()
+}
+
+fn build_report(
+    papyrus_args: &PapyrusArgs,
+    logs_dir: &str,
+    mut nodes: Vec<Node>,
+    duration: Duration,
+) -> SimulationReport {
+    nodes.sort_by_key(|node| node.validator_id);
+    let node_reports = nodes
+        .iter()
+        .map(|node| {
+            let final_height = node.height_and_timestamp.0;
+            // Measured from the first observed height, since startup time isn't consensus.
+            #[allow(clippy::as_conversions)] // Note: precision loss doesn't matter for a rate.
+            let heights_per_minute = match (node.first_height_and_elapsed, final_height) {
+                (Some((first_height, first_elapsed)), Some(final_height))
+                    if duration > first_elapsed =>
+                {
+                    let minutes = (duration - first_elapsed).as_secs_f64() / 60.0;
+                    Some((final_height - first_height) as f64 / minutes)
+                }
+                _ => None,
+            };
+            NodeReport {
+                validator_id: format!("0x{:x}", node.validator_id),
+                final_height,
+                sync_count: node.sync_count,
+                heights_per_minute,
+            }
+        })
+        .collect();
+    SimulationReport {
+        random_seed: papyrus_args.random_seed,
+        num_validators: papyrus_args.num_validators,
+        byzantine_validators: papyrus_args
+            .byzantine_validators()
+            .into_iter()
+            .map(|id| format!("0x{id:x}"))
+            .collect(),
+        partition_size: papyrus_args.partition_size,
+        duration_seconds: duration.as_secs(),
+        safety_violations: find_safety_violations(logs_dir, &nodes),
+        nodes: node_reports,
+    }
 }
 
 async fn build_node(data_dir: &str, logs_dir: &str, i: usize, papyrus_args: &PapyrusArgs) -> Node {
@@ -266,7 +458,7 @@ async fn build_node(data_dir: &str, logs_dir: &str, i: usize, papyrus_args: &Pap
     let tcp_port = if is_bootstrap { *BOOTNODE_TCP_PORT } else { find_free_port() };
     let monitoring_gateway_server_port = find_free_port();
     let data_dir = format!("{}/data{}", data_dir, i);
-    let validator_id = i + usize::try_from(DEFAULT_VALIDATOR_ID).expect("Conversion failed");
+    let validator_id = PapyrusArgs::validator_id(i);
 
     let mut cmd = format!(
         "RUST_LOG=papyrus_consensus=debug,papyrus=info target/release/run_consensus \
@@ -297,6 +489,15 @@ async fn build_node(data_dir: &str, logs_dir: &str, i: usize, papyrus_args: &Pap
     let conditional_test_params = [
         ("drop_probability", papyrus_args.drop_probability),
         ("invalid_probability", papyrus_args.invalid_probability),
+        ("equivocation_probability", papyrus_args.equivocation_probability),
+        ("withhold_proposal_probability", papyrus_args.withhold_proposal_probability),
+        ("invalid_proposal_probability", papyrus_args.invalid_proposal_probability),
+        ("cross_group_delay", papyrus_args.cross_partition_delay),
+        #[allow(clippy::as_conversions)] // Note: no precision loss for realistic heights.
+        ("partition_start_height", papyrus_args.partition_start_height.map(|v| v as f64)),
+        #[allow(clippy::as_conversions)] // Note: no precision loss for realistic heights.
+        ("partition_num_heights", papyrus_args.partition_num_heights.map(|v| v as f64)),
+        ("partition_rounds", papyrus_args.partition_rounds.map(f64::from)),
         // Convert optional parameters to f64 for consistency in the vector,
         // types were validated during parsing.
         #[allow(clippy::as_conversions)] // Note: no precision loss if cache size is < ~8PetaBytes.
@@ -309,6 +510,19 @@ async fn build_node(data_dir: &str, logs_dir: &str, i: usize, papyrus_args: &Pap
             cmd.push_str(&format!("--test.{} {} ", key, v));
         }
     }
+    let byzantine_validators = papyrus_args.byzantine_validators();
+    if !byzantine_validators.is_empty() {
+        cmd.push_str(&format!(
+            "--test.byzantine_validators {} ",
+            format_validator_ids(&byzantine_validators)
+        ));
+    }
+    if let Some(partition_group) = papyrus_args.partition_group(i) {
+        cmd.push_str(&format!(
+            "--test.partition_group {} ",
+            format_validator_ids(&partition_group)
+        ));
+    }
 
     if is_bootstrap {
         cmd.push_str(&format!(
@@ -414,13 +628,22 @@ async fn main() {
     let nodes = build_all_nodes(&db_lock.dirname, &logs_dir, &papyrus_args).await;
 
     println!("Running validators...");
-    run_simulation(
+    let (nodes, duration) = run_simulation(
         nodes,
         run_consensus_args.max_test_duration,
         run_consensus_args.stagnation_threshold,
     )
     .await;
 
+    let report = build_report(&papyrus_args, &logs_dir, nodes, duration);
+    let report_json = serde_json::to_string_pretty(&report).expect("Failed to serialize report");
+    fs::write(format!("{}/report.json", logs_dir), &report_json).expect("Failed to write report");
+    println!("\nSimulation report:\n{report_json}");
+
     println!("DB files were stored in: {}", &db_lock.dirname);
     println!("Logs were stored in: {}", &logs_dir);
+    if !report.safety_violations.is_empty() {
+        eprintln!("Safety was violated at {} heights.", report.safety_violations.len());
+        std::process::exit(1);
+    }
 }
@@ -1,6 +1,8 @@
 //! This module contains the configuration for consensus, including the `ConsensusConfig` struct
 //! and its implementation of the `SerializeConfig` trait. The configuration includes parameters
 //! such as the validator ID, the network topic of the consensus, and the starting block height.
+//! It also contains the `StreamHandlerConfig` struct, which bounds the resources that peers can
+//! consume in the stream handler.
 
 use std::collections::BTreeMap;
 use std::time::Duration;
@@ -45,6 +47,8 @@ pub struct ConsensusConfig {
     /// The duration (seconds) between sync attempts.
     #[serde(deserialize_with = "deserialize_float_seconds_to_duration")]
     pub sync_retry_interval: Duration,
+    /// The stream handler configuration for the proposal streams.
+    pub stream_handler: StreamHandlerConfig,
     /// The network configuration for the consensus.
     #[validate]
     pub network_config: NetworkConfig,
@@ -97,6 +101,7 @@ impl SerializeConfig for ConsensusConfig {
             ),
         ]);
         config.extend(append_sub_config_name(self.timeouts.dump(), "timeouts"));
+        config.extend(append_sub_config_name(self.stream_handler.dump(), "stream_handler"));
         config.extend(append_sub_config_name(self.network_config.dump(), "network_config"));
         config
     }
@@ -118,6 +123,7 @@ impl Default for ConsensusConfig {
             consensus_delay: Duration::from_secs(5),
             timeouts: TimeoutsConfig::default(),
             sync_retry_interval: Duration::from_secs_f64(1.0),
+            stream_handler: StreamHandlerConfig::default(),
             network_config,
         }
     }
@@ -171,3 +177,52 @@ impl Default for TimeoutsConfig {
         }
     }
 }
+
+/// Configuration for the stream handler. Bounds the resources a single peer can consume.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct StreamHandlerConfig {
+    /// The maximal number of concurrent inbound streams per peer.
+    pub max_streams_per_peer: usize,
+    /// The maximal distance between the next expected message id and any message id buffered in
+    /// a stream. Messages beyond this window are treated as a protocol violation.
+    pub max_message_id_window: u64,
+    /// Inbound streams that haven't received a message for this long are evicted.
+    #[serde(deserialize_with = "deserialize_float_seconds_to_duration")]
+    pub stream_timeout: Duration,
+}
+
+impl SerializeConfig for StreamHandlerConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "max_streams_per_peer",
+                &self.max_streams_per_peer,
+                "The maximal number of concurrent inbound streams per peer.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_message_id_window",
+                &self.max_message_id_window,
+                "The maximal distance between the next expected message id and any buffered \
+                 message id in a stream.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "stream_timeout",
+                &self.stream_timeout.as_secs_f64(),
+                "The duration (seconds) after which an idle inbound stream is evicted.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+impl Default for StreamHandlerConfig {
+    fn default() -> Self {
+        Self {
+            max_streams_per_peer: 50,
+            max_message_id_window: 1000,
+            stream_timeout: Duration::from_secs_f64(60.0),
+        }
+    }
+}
@@ -3,19 +3,179 @@
 mod simulation_network_receiver_test;
 
 use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::hash::{Hash, Hasher};
 use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::pin::Pin;
 use std::task::Poll;
+use std::time::Duration;
 
-use futures::{Stream, StreamExt};
+use futures::{Future, Stream, StreamExt};
 use lru::LruCache;
 use papyrus_network::network_manager::BroadcastTopicServer;
-use papyrus_network_types::network_types::BroadcastedMessageMetadata;
-use papyrus_protobuf::consensus::Vote;
+use papyrus_network_types::network_types::{BroadcastedMessageMetadata, OpaquePeerId};
+use papyrus_protobuf::consensus::{
+    ProposalFin,
+    ProposalPart,
+    StreamMessage,
+    StreamMessageBody,
+    Vote,
+};
 use papyrus_protobuf::converters::ProtobufConversionError;
+use starknet_api::block::BlockHash;
 use starknet_api::core::{ContractAddress, PatriciaKey};
+use starknet_types_core::felt::Felt;
+use tokio::time::{Instant, Sleep};
 use tracing::{debug, instrument};
 
+use crate::types::ValidatorId;
+
+/// Byzantine behaviors of other validators, as observed by this node.
+///
+/// Byzantine validators are simulated on the receiving side: each node decides, based on the seed
+/// and its own validator ID, how a message from a byzantine validator is altered. This means that
+/// different nodes observe different messages from the same byzantine validator (equivocation),
+/// while a single node always observes the same message.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ByzantineConfig {
+    /// The validators which behave byzantine.
+    pub byzantine_validators: HashSet<ValidatorId>,
+    /// Probability that a vote from a byzantine validator is for a different block [0, 1].
+    pub equivocation_probability: f64,
+    /// Probability that a proposal from a byzantine validator is withheld from this node [0, 1].
+    pub withhold_proposal_probability: f64,
+    /// Probability that a proposal from a byzantine validator reaches this node with the wrong
+    /// content [0, 1].
+    pub invalid_proposal_probability: f64,
+}
+
+/// Splits the validators into this node's group and the rest of the network.
+///
+/// Messages from validators outside of the local group are dropped while the partition is active,
+/// and are delayed by `cross_group_delay` otherwise. An empty local group means no partition.
+///
+/// The partition is driven by the height and round of the messages rather than by wall clock, so
+/// that a given seed partitions the same consensus steps regardless of how fast the nodes run.
+/// Since the partition ends after `partition_rounds`, each partitioned height can still be decided
+/// once the rounds time out.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PartitionConfig {
+    /// The validators in the same network segment as this node.
+    pub local_group: HashSet<ValidatorId>,
+    /// The heights at which the network is partitioned.
+    pub partition_heights: Range<u64>,
+    /// The number of rounds, from round 0 of each partitioned height, in which the network is
+    /// partitioned.
+    pub partition_rounds: u32,
+    /// The delay applied to messages from the other group, when not partitioned.
+    pub cross_group_delay: Duration,
+}
+
+impl PartitionConfig {
+    fn is_remote(&self, validator: &ValidatorId) -> bool {
+        !self.local_group.is_empty() && !self.local_group.contains(validator)
+    }
+
+    fn is_partitioned(&self, height: u64, round: u32) -> bool {
+        self.partition_heights.contains(&height) && round < self.partition_rounds
+    }
+}
+
+// The fate of a message from a validator in a different network segment.
+enum CrossGroupFate {
+    Drop,
+    Delay(Duration),
+    Deliver,
+}
+
+// State shared by the vote and proposal receivers for simulating byzantine validators and network
+// partitions.
+struct FaultSimulation {
+    seed: u64,
+    local_validator_id: ValidatorId,
+    byzantine: ByzantineConfig,
+    partition: PartitionConfig,
+}
+
+impl FaultSimulation {
+    fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            local_validator_id: ValidatorId::default(),
+            byzantine: ByzantineConfig::default(),
+            partition: PartitionConfig::default(),
+        }
+    }
+
+    fn is_byzantine(&self, validator: &ValidatorId) -> bool {
+        self.byzantine.byzantine_validators.contains(validator)
+    }
+
+    // A hash which is the same for every resend of the message, but differs between nodes.
+    fn local_hash(&self, msg: &impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        msg.hash(&mut hasher);
+        self.seed.hash(&mut hasher);
+        self.local_validator_id.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn cross_group_fate(&self, sender: &ValidatorId, height: u64, round: u32) -> CrossGroupFate {
+        if !self.partition.is_remote(sender) {
+            return CrossGroupFate::Deliver;
+        }
+        if self.partition.is_partitioned(height, round) {
+            return CrossGroupFate::Drop;
+        }
+        if self.partition.cross_group_delay.is_zero() {
+            return CrossGroupFate::Deliver;
+        }
+        CrossGroupFate::Delay(self.partition.cross_group_delay)
+    }
+}
+
+fn hash_to_probability(hash: u64) -> f64 {
+    #[allow(clippy::as_conversions)]
+    let prob = (hash as f64) / (u64::MAX as f64);
+    prob
+}
+
+// Holds messages until their delay has passed. Since all messages are delayed by the same
+// duration, they are released in the order they were received.
+struct DelayedMessages<Item> {
+    messages: VecDeque<(Instant, Item)>,
+    timer: Option<Pin<Box<Sleep>>>,
+}
+
+impl<Item> DelayedMessages<Item> {
+    fn new() -> Self {
+        Self { messages: VecDeque::new(), timer: None }
+    }
+
+    fn push(&mut self, delay: Duration, item: Item) {
+        self.messages.push_back((Instant::now() + delay, item));
+    }
+
+    fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Item> {
+        let Some((deadline, _)) = self.messages.front() else {
+            return Poll::Pending;
+        };
+        let deadline = *deadline;
+        let timer = self.timer.get_or_insert_with(|| Box::pin(tokio::time::sleep_until(deadline)));
+        if timer.deadline() != deadline {
+            timer.as_mut().reset(deadline);
+        }
+        match timer.as_mut().poll(cx) {
+            Poll::Ready(()) => {
+                let (_, item) = self.messages.pop_front().expect("Checked above");
+                Poll::Ready(item)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
 /// Receiver which can simulate network issues in a repeatable manner. Simulates drops and network
 /// corruption. The errors are meant to be repeatable regardless of the order of messages received.
 ///
@@ -23,6 +183,9 @@ use tracing::{debug, instrument};
 /// changes across all messages. If we were truly stateless though we would treat resends of
 /// messages all the same, meaning that a dropped message would always be dropped. To avoid this we
 /// have the cache, which allows us to treat resends of a specific message differently.
+///
+/// Can also simulate byzantine validators and network partitions, see
+/// [`with_byzantine_faults`](Self::with_byzantine_faults).
 pub struct NetworkReceiver {
     pub broadcasted_messages_receiver: BroadcastTopicServer<Vote>,
     // Cache is used so that repeat sends of a message can be processed differently. For example,
@@ -33,6 +196,8 @@ pub struct NetworkReceiver {
     pub drop_probability: f64,
     // Probability of making a message invalid [0, 1].
     pub invalid_probability: f64,
+    faults: FaultSimulation,
+    delayed: DelayedMessages<(Vote, BroadcastedMessageMetadata)>,
 }
 
 impl NetworkReceiver {
@@ -41,7 +206,8 @@ impl NetworkReceiver {
     /// Inputs:
     /// - `broadcasted_messages_receiver`: The receiver to listen to.
     /// - `cache_size`: Determines the size of the cache. A small cache risks acting the same across
-    ///   resends of a given message.    /// - `seed`: Seed for the random number generator.
+    ///   resends of a given message.
+    /// - `seed`: Seed for the random number generator.
     /// - `drop_probability`: Probability of dropping a message [0, 1].
     /// - `invalid_probability`: Probability of making a message invalid [0, 1].
     pub fn new(
@@ -59,9 +225,26 @@ impl NetworkReceiver {
             seed,
             drop_probability,
             invalid_probability,
+            faults: FaultSimulation::new(seed),
+            delayed: DelayedMessages::new(),
         }
     }
 
+    /// Simulates byzantine validators and network partitions, as observed by
+    /// `local_validator_id`.
+    pub fn with_byzantine_faults(
+        mut self,
+        local_validator_id: ValidatorId,
+        byzantine: ByzantineConfig,
+        partition: PartitionConfig,
+    ) -> Self {
+        assert!((0.0..=1.0).contains(&byzantine.equivocation_probability));
+        self.faults.local_validator_id = local_validator_id;
+        self.faults.byzantine = byzantine;
+        self.faults.partition = partition;
+        self
+    }
+
     /// Determine how to handle a message. If None then the message is silently dropped. If some,
     /// the returned message is what is sent to the consensus crate.
     ///
@@ -76,6 +259,7 @@ impl NetworkReceiver {
             return None;
         }
 
+        let msg = self.maybe_equivocate_msg(msg);
         Some(self.maybe_invalidate_msg(msg, msg_hash))
     }
 
@@ -96,14 +280,11 @@ impl NetworkReceiver {
     }
 
     fn should_drop_msg(&self, msg_hash: u64) -> bool {
-        #[allow(clippy::as_conversions)]
-        let prob = (msg_hash as f64) / (u64::MAX as f64);
-        prob <= self.drop_probability
+        hash_to_probability(msg_hash) <= self.drop_probability
     }
 
     fn maybe_invalidate_msg(&mut self, mut msg: Vote, msg_hash: u64) -> Vote {
-        #[allow(clippy::as_conversions)]
-        if (msg_hash as f64) / (u64::MAX as f64) > self.invalid_probability {
+        if hash_to_probability(msg_hash) > self.invalid_probability {
             return msg;
         }
         debug!("Invalidating message");
@@ -111,6 +292,21 @@ impl NetworkReceiver {
         msg.voter = ContractAddress(PatriciaKey::from(msg_hash));
         msg
     }
+
+    // A byzantine validator votes for a different block towards each node. The decision ignores
+    // resends, since a single honest node would detect the equivocation.
+    fn maybe_equivocate_msg(&self, mut msg: Vote) -> Vote {
+        if !self.faults.is_byzantine(&msg.voter) {
+            return msg;
+        }
+        let local_hash = self.faults.local_hash(&msg);
+        if hash_to_probability(local_hash) > self.faults.byzantine.equivocation_probability {
+            return msg;
+        }
+        debug!("Equivocating message");
+        msg.block_hash = Some(BlockHash(Felt::from(local_hash)));
+        msg
+    }
 }
 
 impl Stream for NetworkReceiver {
@@ -120,17 +316,175 @@ impl Stream for NetworkReceiver {
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> Poll<Option<Self::Item>> {
+        if let Poll::Ready((msg, broadcasted_message_metadata)) = self.delayed.poll_ready(cx) {
+            return Poll::Ready(Some((Ok(msg), broadcasted_message_metadata)));
+        }
         loop {
             let item = self.broadcasted_messages_receiver.poll_next_unpin(cx);
             let (msg, broadcasted_message_metadata) = match item {
                 Poll::Ready(Some((Ok(msg), broadcasted_message_metadata))) => {
                     (msg, broadcasted_message_metadata)
                 }
+                // Keep the stream alive while there are delayed messages.
+                Poll::Ready(None) if !self.delayed.messages.is_empty() => return Poll::Pending,
                 _ => return item,
             };
+            match self.faults.cross_group_fate(&msg.voter, msg.height, msg.round) {
+                CrossGroupFate::Drop => {
+                    debug!("Dropping message from a different network segment: {msg:?}");
+                    continue;
+                }
+                CrossGroupFate::Delay(delay) => {
+                    if let Some(msg) = self.filter_msg(msg) {
+                        self.delayed.push(delay, (msg, broadcasted_message_metadata));
+                        if let Poll::Ready((msg, metadata)) = self.delayed.poll_ready(cx) {
+                            return Poll::Ready(Some((Ok(msg), metadata)));
+                        }
+                    }
+                    continue;
+                }
+                CrossGroupFate::Deliver => {}
+            }
             if let Some(msg) = self.filter_msg(msg) {
                 return Poll::Ready(Some((Ok(msg), broadcasted_message_metadata)));
             }
         }
     }
 }
+
+// How a proposal stream from a byzantine or remote proposer is altered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ProposalFault {
+    Withhold,
+    InvalidContent(BlockHash),
+    Delay(Duration),
+    None,
+}
+
+type ProposalMessage =
+    (Result<StreamMessage<ProposalPart>, ProtobufConversionError>, BroadcastedMessageMetadata);
+
+/// Receiver for proposal streams which simulates byzantine proposers and network partitions in a
+/// repeatable manner, as observed by a single node. See [`ByzantineConfig`] and
+/// [`PartitionConfig`].
+///
+/// The proposer is only known from the first message of a stream, so messages which arrive before
+/// it are passed through as is.
+pub struct ProposalNetworkReceiver {
+    broadcasted_messages_receiver: BroadcastTopicServer<StreamMessage<ProposalPart>>,
+    faults: FaultSimulation,
+    stream_faults: HashMap<(OpaquePeerId, u64), ProposalFault>,
+    delayed: DelayedMessages<ProposalMessage>,
+}
+
+impl ProposalNetworkReceiver {
+    /// Creates a new ProposalNetworkReceiver, simulating faults as observed by
+    /// `local_validator_id`.
+    pub fn new(
+        broadcasted_messages_receiver: BroadcastTopicServer<StreamMessage<ProposalPart>>,
+        seed: u64,
+        local_validator_id: ValidatorId,
+        byzantine: ByzantineConfig,
+        partition: PartitionConfig,
+    ) -> Self {
+        assert!((0.0..=1.0).contains(&byzantine.withhold_proposal_probability));
+        assert!((0.0..=1.0).contains(&byzantine.invalid_proposal_probability));
+        let mut faults = FaultSimulation::new(seed);
+        faults.local_validator_id = local_validator_id;
+        faults.byzantine = byzantine;
+        faults.partition = partition;
+        Self {
+            broadcasted_messages_receiver,
+            faults,
+            stream_faults: HashMap::new(),
+            delayed: DelayedMessages::new(),
+        }
+    }
+
+    // Decide the fault for a stream, based on its first message.
+    fn decide_fault(&self, part: &ProposalPart) -> Option<ProposalFault> {
+        let ProposalPart::Init(init) = part else {
+            return None;
+        };
+        match self.faults.cross_group_fate(&init.proposer, init.height.0, init.round) {
+            CrossGroupFate::Drop => return Some(ProposalFault::Withhold),
+            CrossGroupFate::Delay(delay) => return Some(ProposalFault::Delay(delay)),
+            CrossGroupFate::Deliver => {}
+        }
+        if !self.faults.is_byzantine(&init.proposer) {
+            return Some(ProposalFault::None);
+        }
+        let local_hash = self.faults.local_hash(&(init.height.0, init.round, init.proposer));
+        let prob = hash_to_probability(local_hash);
+        let byzantine = &self.faults.byzantine;
+        if prob <= byzantine.withhold_proposal_probability {
+            return Some(ProposalFault::Withhold);
+        }
+        if prob <= byzantine.withhold_proposal_probability + byzantine.invalid_proposal_probability
+        {
+            return Some(ProposalFault::InvalidContent(BlockHash(Felt::from(local_hash))));
+        }
+        Some(ProposalFault::None)
+    }
+}
+
+impl Stream for ProposalNetworkReceiver {
+    type Item = ProposalMessage;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        if let Poll::Ready(item) = self.delayed.poll_ready(cx) {
+            return Poll::Ready(Some(item));
+        }
+        loop {
+            let item = self.broadcasted_messages_receiver.poll_next_unpin(cx);
+            let (mut msg, metadata) = match item {
+                Poll::Ready(Some((Ok(msg), metadata))) => (msg, metadata),
+                Poll::Ready(None) if !self.delayed.messages.is_empty() => return Poll::Pending,
+                _ => return item,
+            };
+            let key = (metadata.originator_id.clone(), msg.stream_id);
+            let fault = match self.stream_faults.get(&key) {
+                Some(fault) => *fault,
+                None => match &msg.message {
+                    StreamMessageBody::Content(part) => match self.decide_fault(part) {
+                        Some(fault) => {
+                            self.stream_faults.insert(key.clone(), fault);
+                            fault
+                        }
+                        None => ProposalFault::None,
+                    },
+                    StreamMessageBody::Fin => ProposalFault::None,
+                },
+            };
+            if msg.message == StreamMessageBody::Fin {
+                self.stream_faults.remove(&key);
+            }
+            match fault {
+                ProposalFault::Withhold => {
+                    debug!("Withholding proposal stream {key:?}");
+                    continue;
+                }
+                ProposalFault::InvalidContent(proposal_content_id) => {
+                    if let StreamMessageBody::Content(ProposalPart::Fin(_)) = msg.message {
+                        debug!("Invalidating proposal stream {key:?}");
+                        msg.message = StreamMessageBody::Content(ProposalPart::Fin(ProposalFin {
+                            proposal_content_id,
+                        }));
+                    }
+                }
+                ProposalFault::Delay(delay) => {
+                    self.delayed.push(delay, (Ok(msg), metadata));
+                    if let Poll::Ready(item) = self.delayed.poll_ready(cx) {
+                        return Poll::Ready(Some(item));
+                    }
+                    continue;
+                }
+                ProposalFault::None => {}
+            }
+            return Poll::Ready(Some((Ok(msg), metadata)));
+        }
+    }
+}
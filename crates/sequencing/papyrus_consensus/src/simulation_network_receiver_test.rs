@@ -1,14 +1,28 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
 use futures::{SinkExt, StreamExt};
 use papyrus_network::network_manager::test_utils::{
     mock_register_broadcast_topic,
     TestSubscriberChannels,
 };
 use papyrus_network_types::network_types::BroadcastedMessageMetadata;
-use papyrus_protobuf::consensus::Vote;
+use papyrus_protobuf::consensus::{
+    ProposalFin,
+    ProposalInit,
+    ProposalPart,
+    StreamMessage,
+    StreamMessageBody,
+    Vote,
+};
 use papyrus_test_utils::{get_rng, GetTestInstance};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::ContractAddress;
+use starknet_types_core::felt::Felt;
 use test_case::test_case;
 
-use super::NetworkReceiver;
+use super::{ByzantineConfig, NetworkReceiver, PartitionConfig, ProposalNetworkReceiver};
+use crate::types::ValidatorId;
 
 const CACHE_SIZE: usize = 10;
 const SEED: u64 = 123;
@@ -78,3 +92,206 @@ async fn test_drops(distinct_messages: bool) {
     }
     assert!((400..=600).contains(&num_received), "num_received={num_received}");
 }
+
+fn validator(id: u64) -> ValidatorId {
+    ContractAddress::from(id)
+}
+
+fn byzantine_config(validator_id: ValidatorId, probability: f64) -> ByzantineConfig {
+    ByzantineConfig {
+        byzantine_validators: HashSet::from([validator_id]),
+        equivocation_probability: probability,
+        withhold_proposal_probability: probability,
+        invalid_proposal_probability: probability,
+    }
+}
+
+#[tokio::test]
+async fn test_equivocation_is_consistent_per_node() {
+    const BYZANTINE: u64 = 1;
+    let mut received_block_hashes = Vec::new();
+    for local_validator in [2, 3] {
+        let TestSubscriberChannels { subscriber_channels, mut mock_network } =
+            mock_register_broadcast_topic().unwrap();
+        let mut receiver = NetworkReceiver::new(
+            subscriber_channels.broadcasted_messages_receiver,
+            CACHE_SIZE,
+            SEED,
+            0.0,
+            0.0,
+        )
+        .with_byzantine_faults(
+            validator(local_validator),
+            byzantine_config(validator(BYZANTINE), 1.0),
+            PartitionConfig::default(),
+        );
+
+        let msg = Vote { voter: validator(BYZANTINE), ..Default::default() };
+        let mut block_hashes = HashSet::new();
+        // Resends of the vote must look the same to a given node.
+        for _ in 0..3 {
+            mock_network
+                .broadcasted_messages_sender
+                .send((msg.clone(), BroadcastedMessageMetadata::get_test_instance(&mut get_rng())))
+                .await
+                .unwrap();
+            let received = receiver.next().await.unwrap().0.unwrap();
+            assert_eq!(received.voter, msg.voter);
+            block_hashes.insert(received.block_hash);
+        }
+        assert_eq!(block_hashes.len(), 1);
+        let block_hash = block_hashes.into_iter().next().unwrap();
+        assert_ne!(block_hash, msg.block_hash);
+        received_block_hashes.push(block_hash);
+    }
+    assert_ne!(received_block_hashes[0], received_block_hashes[1]);
+}
+
+#[tokio::test]
+async fn test_honest_votes_are_not_equivocated() {
+    let TestSubscriberChannels { subscriber_channels, mut mock_network } =
+        mock_register_broadcast_topic().unwrap();
+    let mut receiver = NetworkReceiver::new(
+        subscriber_channels.broadcasted_messages_receiver,
+        CACHE_SIZE,
+        SEED,
+        0.0,
+        0.0,
+    )
+    .with_byzantine_faults(
+        validator(2),
+        byzantine_config(validator(1), 1.0),
+        PartitionConfig::default(),
+    );
+
+    let msg = Vote { voter: validator(3), ..Default::default() };
+    mock_network
+        .broadcasted_messages_sender
+        .send((msg.clone(), BroadcastedMessageMetadata::get_test_instance(&mut get_rng())))
+        .await
+        .unwrap();
+    assert_eq!(receiver.next().await.unwrap().0.unwrap(), msg);
+}
+
+#[tokio::test]
+async fn test_partition_drops_and_delays_remote_votes() {
+    const PARTITION_ROUNDS: u32 = 2;
+    const CROSS_GROUP_DELAY: Duration = Duration::from_millis(50);
+    let partition = PartitionConfig {
+        local_group: HashSet::from([validator(1), validator(2)]),
+        partition_heights: 1..2,
+        partition_rounds: PARTITION_ROUNDS,
+        cross_group_delay: CROSS_GROUP_DELAY,
+    };
+    let TestSubscriberChannels { subscriber_channels, mut mock_network } =
+        mock_register_broadcast_topic().unwrap();
+    let mut receiver = NetworkReceiver::new(
+        subscriber_channels.broadcasted_messages_receiver,
+        CACHE_SIZE,
+        SEED,
+        0.0,
+        0.0,
+    )
+    .with_byzantine_faults(validator(1), ByzantineConfig::default(), partition);
+
+    // During the partition, only votes from the local group are received.
+    let remote_vote = Vote { voter: validator(3), height: 1, ..Default::default() };
+    let local_vote = Vote { voter: validator(2), height: 1, ..Default::default() };
+    for msg in [remote_vote.clone(), local_vote.clone()] {
+        mock_network
+            .broadcasted_messages_sender
+            .send((msg, BroadcastedMessageMetadata::get_test_instance(&mut get_rng())))
+            .await
+            .unwrap();
+    }
+    assert_eq!(receiver.next().await.unwrap().0.unwrap(), local_vote);
+
+    // Outside of the partitioned heights and rounds, remote votes arrive late.
+    for remote_vote in
+        [Vote { round: PARTITION_ROUNDS, ..remote_vote.clone() }, Vote { height: 2, ..remote_vote }]
+    {
+        mock_network
+            .broadcasted_messages_sender
+            .send((
+                remote_vote.clone(),
+                BroadcastedMessageMetadata::get_test_instance(&mut get_rng()),
+            ))
+            .await
+            .unwrap();
+        let sent_time = tokio::time::Instant::now();
+        assert_eq!(receiver.next().await.unwrap().0.unwrap(), remote_vote);
+        assert!(sent_time.elapsed() >= CROSS_GROUP_DELAY);
+    }
+}
+
+fn proposal_stream(proposer: ValidatorId) -> Vec<StreamMessage<ProposalPart>> {
+    let init = ProposalInit { height: BlockNumber(0), round: 0, proposer, ..Default::default() };
+    let fin = ProposalFin { proposal_content_id: BlockHash(Felt::ONE) };
+    vec![
+        StreamMessage {
+            message: StreamMessageBody::Content(ProposalPart::Init(init)),
+            stream_id: 0,
+            message_id: 0,
+        },
+        StreamMessage {
+            message: StreamMessageBody::Content(ProposalPart::Fin(fin)),
+            stream_id: 0,
+            message_id: 1,
+        },
+        StreamMessage { message: StreamMessageBody::Fin, stream_id: 0, message_id: 2 },
+    ]
+}
+
+async fn receive_proposal_stream(
+    byzantine: ByzantineConfig,
+    proposer: ValidatorId,
+) -> Vec<StreamMessage<ProposalPart>> {
+    let TestSubscriberChannels { subscriber_channels, mut mock_network } =
+        mock_register_broadcast_topic().unwrap();
+    let mut receiver = ProposalNetworkReceiver::new(
+        subscriber_channels.broadcasted_messages_receiver,
+        SEED,
+        validator(2),
+        byzantine,
+        PartitionConfig::default(),
+    );
+    let metadata = BroadcastedMessageMetadata::get_test_instance(&mut get_rng());
+    for msg in proposal_stream(proposer) {
+        mock_network.broadcasted_messages_sender.send((msg, metadata.clone())).await.unwrap();
+    }
+    drop(mock_network.broadcasted_messages_sender);
+
+    let mut received = Vec::new();
+    while let Some((msg, _)) = receiver.next().await {
+        received.push(msg.unwrap());
+    }
+    received
+}
+
+#[tokio::test]
+async fn test_withhold_proposal() {
+    let byzantine = ByzantineConfig {
+        withhold_proposal_probability: 1.0,
+        ..byzantine_config(validator(1), 0.0)
+    };
+    assert!(receive_proposal_stream(byzantine.clone(), validator(1)).await.is_empty());
+    assert_eq!(
+        receive_proposal_stream(byzantine, validator(3)).await,
+        proposal_stream(validator(3))
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_proposal() {
+    let byzantine = ByzantineConfig {
+        invalid_proposal_probability: 1.0,
+        ..byzantine_config(validator(1), 0.0)
+    };
+    let expected = proposal_stream(validator(1));
+    let received = receive_proposal_stream(byzantine, validator(1)).await;
+    assert_eq!(received.len(), expected.len());
+    assert_eq!(received[0], expected[0]);
+    assert_ne!(received[1], expected[1]);
+    assert!(matches!(received[1].message, StreamMessageBody::Content(ProposalPart::Fin(_))));
+    assert_eq!(received[2], expected[2]);
+}
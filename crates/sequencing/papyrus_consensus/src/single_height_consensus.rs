@@ -473,8 +473,13 @@ impl SingleHeightConsensus {
 
         // TODO: Figure out how to handle failed proposal building. I believe this should be handled
         // by applying timeoutPropose when we are the leader.
-        let init =
-            ProposalInit { height: self.height, round, proposer: self.id, valid_round: None };
+        let init = ProposalInit {
+            height: self.height,
+            round,
+            proposer: self.id,
+            valid_round: None,
+            ..Default::default()
+        };
         let fin_receiver = context.build_proposal(init, self.timeouts.proposal_timeout).await;
         vec![ShcTask::BuildProposal(round, fin_receiver)]
     }
@@ -504,6 +509,7 @@ impl SingleHeightConsensus {
             round,
             proposer: self.id,
             valid_round: Some(valid_round),
+            ..Default::default()
         };
         context.repropose(id, init).await;
         let old = self.proposals.insert(round, Some(proposal_id));
@@ -1,22 +1,25 @@
 //! Stream handler, see StreamManager struct.
 
-use std::cmp::Ordering;
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::collections::HashMap;
+use std::time::Duration;
 
 use futures::channel::mpsc;
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
 use papyrus_network::network_manager::{
     BroadcastTopicClient,
     BroadcastTopicClientTrait,
-    BroadcastTopicServer,
+    GenericReceiver,
 };
 use papyrus_network::utils::StreamHashMap;
 use papyrus_network_types::network_types::{BroadcastedMessageMetadata, OpaquePeerId};
 use papyrus_protobuf::consensus::{StreamMessage, StreamMessageBody};
 use papyrus_protobuf::converters::ProtobufConversionError;
+use tokio::time::Instant;
 use tracing::{instrument, warn};
 
+use crate::config::StreamHandlerConfig;
+
 #[cfg(test)]
 #[path = "stream_handler_test.rs"]
 mod stream_handler_test;
@@ -25,13 +28,54 @@ type PeerId = OpaquePeerId;
 type StreamId = u64;
 type MessageId = u64;
 type StreamKey = (PeerId, StreamId);
+type InboundMessage<T> =
+    (Result<StreamMessage<T>, ProtobufConversionError>, BroadcastedMessageMetadata);
 
 const CHANNEL_BUFFER_LENGTH: usize = 100;
+// How often to retry delivering buffered messages to full channels and to evict idle streams.
+const HOUSEKEEPING_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A violation of the streaming protocol by a peer. The peer is reported to the network and the
+/// stream is dropped.
+#[derive(thiserror::Error, Debug, PartialEq)]
+enum StreamProtocolViolation {
+    #[error("Failed to convert message: {0}")]
+    InvalidMessage(String),
+    #[error("Peer exceeded the limit of {0} concurrent streams.")]
+    TooManyStreams(usize),
+    #[error(
+        "Message id {message_id} is too far ahead of the next expected id {next_message_id} \
+         (window: {window})."
+    )]
+    MessageIdOutOfWindow { message_id: MessageId, next_message_id: MessageId, window: u64 },
+    #[error("Message id {message_id} is smaller than the next expected id {next_message_id}.")]
+    MessageIdTooSmall { message_id: MessageId, next_message_id: MessageId },
+    #[error("Received two messages with id {0}.")]
+    DuplicateMessageId(MessageId),
+    #[error(
+        "Fin message id {fin_message_id} is smaller than a received message id {max_message_id}."
+    )]
+    FinBeforeContent { fin_message_id: MessageId, max_message_id: MessageId },
+    #[error("Message id {message_id} is bigger than the fin message id {fin_message_id}.")]
+    MessageAfterFin { message_id: MessageId, fin_message_id: MessageId },
+}
+
+// The result of trying to pass an inbound message to the application.
+#[derive(Debug, PartialEq)]
+enum InboundSendResult {
+    Sent,
+    // The application's channel is full. The message was put back into the buffer.
+    ChannelFull,
+    // The stream should be closed, either because it is done or the application dropped it.
+    StreamClosed,
+}
 
 // Use this struct for each inbound stream.
 // Drop the struct when:
 // (1) receiver on the other end is dropped,
-// (2) fin message is received and all messages are sent.
+// (2) fin message is received and all messages are sent,
+// (3) the peer violated the streaming protocol,
+// (4) no message was received for longer than the stream timeout.
 #[derive(Debug)]
 struct StreamData<
     T: Clone + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversionError> + 'static,
@@ -43,8 +87,11 @@ struct StreamData<
     // Keep the receiver until it is time to send it to the application.
     receiver: Option<mpsc::Receiver<T>>,
     sender: mpsc::Sender<T>,
-    // A buffer for messages that were received out of order.
+    // A buffer for messages that were received out of order, or that could not be delivered
+    // because the application's channel was full.
     message_buffer: HashMap<MessageId, StreamMessage<T>>,
+    // The last time a message was received on this stream. Used to evict abandoned streams.
+    last_activity: Instant,
 }
 
 impl<T: Clone + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversionError>> StreamData<T> {
@@ -57,21 +104,29 @@ impl<T: Clone + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversionError
             sender,
             receiver: Some(receiver),
             message_buffer: HashMap::new(),
+            last_activity: Instant::now(),
         }
     }
+
+    // The stream is done once the fin message was delivered, which is when the buffer is drained.
+    fn is_finished(&self) -> bool {
+        self.message_buffer.is_empty() && self.fin_message_id.is_some()
+    }
 }
 
 /// A StreamHandler is responsible for:
 /// - Buffering inbound messages and reporting them to the application in order.
 /// - Sending outbound messages to the network, wrapped in StreamMessage.
+/// - Reporting peers that violate the streaming protocol, and bounding the resources they consume.
 pub struct StreamHandler<
     T: Clone + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversionError> + 'static,
 > {
+    config: StreamHandlerConfig,
     // For each stream ID from the network, send the application a Receiver
     // that will receive the messages in order. This allows sending such Receivers.
     inbound_channel_sender: mpsc::Sender<mpsc::Receiver<T>>,
     // This receives messages from the network.
-    inbound_receiver: BroadcastTopicServer<StreamMessage<T>>,
+    inbound_receiver: GenericReceiver<InboundMessage<T>>,
     // A map from (peer_id, stream_id) to a struct that contains all the information
     // about the stream. This includes both the message buffer and some metadata
     // (like the latest message ID).
@@ -93,14 +148,16 @@ impl<T: Clone + Send + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversi
 {
     /// Create a new StreamHandler.
     pub fn new(
+        config: StreamHandlerConfig,
         inbound_channel_sender: mpsc::Sender<mpsc::Receiver<T>>,
-        inbound_receiver: BroadcastTopicServer<StreamMessage<T>>,
+        inbound_receiver: impl Stream<Item = InboundMessage<T>> + Send + Unpin + 'static,
         outbound_channel_receiver: mpsc::Receiver<(StreamId, mpsc::Receiver<T>)>,
         outbound_sender: BroadcastTopicClient<StreamMessage<T>>,
     ) -> Self {
         Self {
+            config,
             inbound_channel_sender,
-            inbound_receiver,
+            inbound_receiver: Box::new(inbound_receiver),
             inbound_stream_data: HashMap::new(),
             outbound_channel_receiver,
             outbound_sender,
@@ -113,7 +170,8 @@ impl<T: Clone + Send + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversi
     /// Gets network input/output channels and returns application input/output channels.
     #[allow(clippy::type_complexity)]
     pub fn get_channels(
-        inbound_network_receiver: BroadcastTopicServer<StreamMessage<T>>,
+        config: StreamHandlerConfig,
+        inbound_network_receiver: impl Stream<Item = InboundMessage<T>> + Send + Unpin + 'static,
         outbound_network_sender: BroadcastTopicClient<StreamMessage<T>>,
     ) -> (
         mpsc::Sender<(StreamId, mpsc::Receiver<T>)>,
@@ -137,8 +195,9 @@ impl<T: Clone + Send + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversi
         ) = mpsc::channel(CHANNEL_BUFFER_LENGTH);
 
         let mut stream_handler = StreamHandler::<T>::new(
+            config,
             inbound_internal_sender,    // Sender<Receiver<T>>,
-            inbound_network_receiver,   // BroadcastTopicServer<StreamMessage<T>>,
+            inbound_network_receiver,   // Stream<Item = InboundMessage<T>>,
             outbound_internal_receiver, // Receiver<(StreamId, Receiver<T>)>,
             outbound_network_sender,    // BroadcastTopicClient<StreamMessage<T>>
         );
@@ -155,6 +214,7 @@ impl<T: Clone + Send + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversi
     ///   correct order to the application.
     #[instrument(skip_all)]
     pub async fn run(&mut self) {
+        let mut housekeeping_interval = tokio::time::interval(HOUSEKEEPING_INTERVAL);
         loop {
             tokio::select!(
                 // Go over the channel receiver to see if there is a new channel.
@@ -180,51 +240,68 @@ impl<T: Clone + Send + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversi
                 }
                 // Check if there is an inbound message from the network.
                 Some(message) = self.inbound_receiver.next() => {
-                    self.handle_message(message);
+                    self.handle_message(message).await;
+                }
+                // Retry delivering buffered messages and evict abandoned streams.
+                _ = housekeeping_interval.tick() => {
+                    self.housekeeping();
                 }
             );
         }
     }
 
-    // Returns true if the receiver for this stream is dropped.
-    fn inbound_send(&mut self, data: &mut StreamData<T>, message: StreamMessage<T>) -> bool {
-        // TODO(guyn): reconsider the "expect" here.
-        let sender = &mut data.sender;
-        if let StreamMessageBody::Content(content) = message.message {
-            if message.message_id == 0 {
-                // TODO(guyn): consider the expect in both cases.
-                // If this is the first message, send the receiver to the application.
-                let receiver = data.receiver.take().expect("Receiver should exist");
-                // Send the receiver to the application.
-                self.inbound_channel_sender.try_send(receiver).expect("Send should succeed");
-            }
-            match sender.try_send(content) {
-                Ok(_) => {}
-                Err(e) => {
-                    if e.is_disconnected() {
-                        warn!(
-                            "Sender is disconnected, dropping the message. StreamId: {}, \
-                             MessageId: {}",
-                            message.stream_id, message.message_id
-                        );
-                        return true;
-                    } else if e.is_full() {
-                        // TODO(guyn): replace panic with buffering of the message.
-                        panic!(
-                            "Sender is full, dropping the message. StreamId: {}, MessageId: {}",
-                            message.stream_id, message.message_id
-                        );
-                    } else {
-                        // TODO(guyn): replace panic with more graceful error handling
-                        panic!("Unexpected error: {:?}", e);
-                    }
+    // Try to pass the message to the application. If the application's channel is full, the
+    // message is put back into the buffer, to be retried later.
+    fn inbound_send(
+        &mut self,
+        data: &mut StreamData<T>,
+        message: StreamMessage<T>,
+    ) -> InboundSendResult {
+        let StreamMessage { message: body, stream_id, message_id } = message;
+        let StreamMessageBody::Content(content) = body else {
+            // A Fin message is not sent, it only closes the stream.
+            return InboundSendResult::StreamClosed;
+        };
+        if let Some(receiver) = data.receiver.take() {
+            // This is the first message, send the receiver to the application.
+            if let Err(e) = self.inbound_channel_sender.try_send(receiver) {
+                if e.is_disconnected() {
+                    warn!("Application stopped listening for new streams, dropping the stream.");
+                    return InboundSendResult::StreamClosed;
                 }
-            };
-            data.next_message_id += 1;
-            return false;
+                data.receiver = Some(e.into_inner());
+                data.message_buffer.insert(
+                    message_id,
+                    StreamMessage {
+                        message: StreamMessageBody::Content(content),
+                        stream_id,
+                        message_id,
+                    },
+                );
+                return InboundSendResult::ChannelFull;
+            }
         }
-        // A Fin message is not sent. This is a no-op, can safely return true.
-        true
+        if let Err(e) = data.sender.try_send(content) {
+            if e.is_disconnected() {
+                warn!(
+                    "Sender is disconnected, dropping the message. StreamId: {}, MessageId: {}",
+                    stream_id, message_id
+                );
+                return InboundSendResult::StreamClosed;
+            }
+            // The channel is full. Keep the message until the application catches up.
+            data.message_buffer.insert(
+                message_id,
+                StreamMessage {
+                    message: StreamMessageBody::Content(e.into_inner()),
+                    stream_id,
+                    message_id,
+                },
+            );
+            return InboundSendResult::ChannelFull;
+        }
+        data.next_message_id += 1;
+        InboundSendResult::Sent
     }
 
     // Send the message to the network.
@@ -234,8 +311,9 @@ impl<T: Clone + Send + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversi
             stream_id,
             message_id: *self.outbound_stream_number.get(&stream_id).unwrap_or(&0),
         };
-        // TODO(guyn): reconsider the "expect" here.
-        self.outbound_sender.broadcast_message(message).await.expect("Send should succeed");
+        if let Err(e) = self.outbound_sender.broadcast_message(message).await {
+            warn!("Failed to broadcast message on stream {stream_id}: {e:?}");
+        }
         self.outbound_stream_number
             .insert(stream_id, self.outbound_stream_number.get(&stream_id).unwrap_or(&0) + 1);
     }
@@ -247,25 +325,33 @@ impl<T: Clone + Send + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversi
             stream_id,
             message_id: *self.outbound_stream_number.get(&stream_id).unwrap_or(&0),
         };
-        self.outbound_sender.broadcast_message(message).await.expect("Send should succeed");
+        if let Err(e) = self.outbound_sender.broadcast_message(message).await {
+            warn!("Failed to broadcast fin message on stream {stream_id}: {e:?}");
+        }
         self.outbound_stream_number.remove(&stream_id);
     }
 
     // Handle a message that was received from the network.
     #[instrument(skip_all, level = "warn")]
-    fn handle_message(
-        &mut self,
-        message: (Result<StreamMessage<T>, ProtobufConversionError>, BroadcastedMessageMetadata),
-    ) {
+    async fn handle_message(&mut self, message: InboundMessage<T>) {
         let (message, metadata) = message;
-        let message = match message {
-            Ok(message) => message,
-            Err(e) => {
-                warn!("Error converting message: {:?}", e);
-                return;
-            }
+        let result = match message {
+            Ok(message) => self.handle_valid_message(message, &metadata),
+            Err(e) => Err(StreamProtocolViolation::InvalidMessage(e.to_string())),
         };
+        if let Err(violation) = result {
+            warn!("Peer {:?} violated the stream protocol: {violation}", metadata.originator_id);
+            if let Err(e) = self.outbound_sender.report_peer(metadata).await {
+                warn!("Failed to report peer: {e:?}");
+            }
+        }
+    }
 
+    fn handle_valid_message(
+        &mut self,
+        message: StreamMessage<T>,
+        metadata: &BroadcastedMessageMetadata,
+    ) -> Result<(), StreamProtocolViolation> {
         let peer_id = metadata.originator_id.clone();
         let stream_id = message.stream_id;
         let key = (peer_id, stream_id);
@@ -276,122 +362,118 @@ impl<T: Clone + Send + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversi
             Vacant(_) => {
                 // If we received a message for a stream that we have not seen before,
                 // we need to create a new receiver for it.
+                let num_peer_streams =
+                    self.inbound_stream_data.keys().filter(|(peer, _)| *peer == key.0).count();
+                if num_peer_streams >= self.config.max_streams_per_peer {
+                    return Err(StreamProtocolViolation::TooManyStreams(
+                        self.config.max_streams_per_peer,
+                    ));
+                }
                 StreamData::new()
             }
         };
-        if let Some(data) = self.handle_message_inner(message, metadata, data) {
+        if let Some(data) = self.handle_message_inner(message, data)? {
             self.inbound_stream_data.insert(key, data);
         }
+        Ok(())
     }
 
     /// Returns the StreamData struct if it should be put back into the hash map. None if the data
-    /// should be dropped.
+    /// should be dropped. On a protocol violation the data is dropped as well.
     fn handle_message_inner(
         &mut self,
         message: StreamMessage<T>,
-        metadata: BroadcastedMessageMetadata,
         mut data: StreamData<T>,
-    ) -> Option<StreamData<T>> {
-        let peer_id = metadata.originator_id;
-        let stream_id = message.stream_id;
-        let key = (peer_id, stream_id);
+    ) -> Result<Option<StreamData<T>>, StreamProtocolViolation> {
         let message_id = message.message_id;
+        data.last_activity = Instant::now();
+
+        if message_id < data.next_message_id {
+            return Err(StreamProtocolViolation::MessageIdTooSmall {
+                message_id,
+                next_message_id: data.next_message_id,
+            });
+        }
+        if message_id - data.next_message_id > self.config.max_message_id_window {
+            return Err(StreamProtocolViolation::MessageIdOutOfWindow {
+                message_id,
+                next_message_id: data.next_message_id,
+                window: self.config.max_message_id_window,
+            });
+        }
+        if data.message_buffer.contains_key(&message_id) {
+            return Err(StreamProtocolViolation::DuplicateMessageId(message_id));
+        }
 
         if data.max_message_id_received < message_id {
             data.max_message_id_received = message_id;
         }
 
         // Check for Fin type message.
-        match message.message {
-            StreamMessageBody::Content(_) => {}
-            StreamMessageBody::Fin => {
-                data.fin_message_id = Some(message_id);
-                if data.max_message_id_received > message_id {
-                    // TODO(guyn): replace warnings with more graceful error handling
-                    warn!(
-                        "Received fin message with id that is smaller than a previous message! \
-                         key: {:?}, fin_message_id: {}, max_message_id_received: {}",
-                        key.clone(),
-                        message_id,
-                        data.max_message_id_received
-                    );
-                    return None;
-                }
+        if let StreamMessageBody::Fin = message.message {
+            if data.max_message_id_received > message_id {
+                return Err(StreamProtocolViolation::FinBeforeContent {
+                    fin_message_id: message_id,
+                    max_message_id: data.max_message_id_received,
+                });
             }
+            if data.fin_message_id.is_some() {
+                return Err(StreamProtocolViolation::DuplicateMessageId(message_id));
+            }
+            data.fin_message_id = Some(message_id);
         }
 
-        if message_id > data.fin_message_id.unwrap_or(u64::MAX) {
-            // TODO(guyn): replace warnings with more graceful error handling
-            warn!(
-                "Received message with id that is bigger than the id of the fin message! key: \
-                 {:?}, message_id: {}, fin_message_id: {}",
-                key.clone(),
-                message_id,
-                data.fin_message_id.unwrap_or(u64::MAX)
-            );
-            return None;
-        }
-
-        // This means we can just send the message without buffering it.
-        match message_id.cmp(&data.next_message_id) {
-            Ordering::Equal => {
-                let mut receiver_dropped = self.inbound_send(&mut data, message);
-                if !receiver_dropped {
-                    receiver_dropped = self.process_buffer(&mut data);
-                }
-
-                if data.message_buffer.is_empty() && data.fin_message_id.is_some()
-                    || receiver_dropped
-                {
-                    data.sender.close_channel();
-                    return None;
-                }
-            }
-            Ordering::Greater => {
-                Self::store(&mut data, key.clone(), message);
-            }
-            Ordering::Less => {
-                // TODO(guyn): replace warnings with more graceful error handling
-                warn!(
-                    "Received message with id that is smaller than the next message expected! \
-                     key: {:?}, message_id: {}, next_message_id: {}",
-                    key.clone(),
+        if let Some(fin_message_id) = data.fin_message_id {
+            if message_id > fin_message_id {
+                return Err(StreamProtocolViolation::MessageAfterFin {
                     message_id,
-                    data.next_message_id
-                );
-                return None;
+                    fin_message_id,
+                });
             }
         }
-        Some(data)
-    }
 
-    // Store an inbound message in the buffer.
-    fn store(data: &mut StreamData<T>, key: StreamKey, message: StreamMessage<T>) {
-        let message_id = message.message_id;
-
-        match data.message_buffer.entry(message_id) {
-            Vacant(e) => {
-                e.insert(message);
-            }
-            Occupied(_) => {
-                // TODO(guyn): replace warnings with more graceful error handling
-                warn!(
-                    "Two messages with the same message_id in buffer! key: {:?}, message_id: {}",
-                    key, message_id
-                );
-            }
+        // Buffer the message, then deliver as many messages as possible in order.
+        data.message_buffer.insert(message_id, message);
+        if self.process_buffer(&mut data) == InboundSendResult::StreamClosed || data.is_finished() {
+            data.sender.close_channel();
+            return Ok(None);
         }
+        Ok(Some(data))
     }
 
     // Tries to drain as many messages as possible from the buffer (in order),
     // DOES NOT guarantee that the buffer will be empty after calling this function.
-    // Returns true if the receiver for this stream is dropped.
-    fn process_buffer(&mut self, data: &mut StreamData<T>) -> bool {
+    // Returns the result of the last attempt to send a message.
+    fn process_buffer(&mut self, data: &mut StreamData<T>) -> InboundSendResult {
         while let Some(message) = data.message_buffer.remove(&data.next_message_id) {
-            if self.inbound_send(data, message) {
-                return true;
+            match self.inbound_send(data, message) {
+                InboundSendResult::Sent => {}
+                result => return result,
             }
         }
-        false
+        InboundSendResult::Sent
+    }
+
+    // Retry delivering messages that are waiting on full channels, and evict streams that have
+    // been idle for longer than the configured timeout.
+    fn housekeeping(&mut self) {
+        let keys: Vec<StreamKey> = self.inbound_stream_data.keys().cloned().collect();
+        for key in keys {
+            let Some(mut data) = self.inbound_stream_data.remove(&key) else {
+                continue;
+            };
+            if self.process_buffer(&mut data) == InboundSendResult::StreamClosed
+                || data.is_finished()
+            {
+                data.sender.close_channel();
+                continue;
+            }
+            if data.last_activity.elapsed() > self.config.stream_timeout {
+                warn!("Evicting inbound stream {:?} after it was idle for too long.", key);
+                data.sender.close_channel();
+                continue;
+            }
+            self.inbound_stream_data.insert(key, data);
+        }
     }
 }
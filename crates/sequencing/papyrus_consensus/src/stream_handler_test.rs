@@ -1,8 +1,10 @@
 use std::time::Duration;
 
 use futures::channel::mpsc;
+use futures::channel::mpsc::Receiver;
 use futures::stream::StreamExt;
 use futures::SinkExt;
+use libp2p::PeerId;
 use papyrus_network::network_manager::test_utils::{
     mock_register_broadcast_topic,
     MockBroadcastedMessagesSender,
@@ -14,6 +16,7 @@ use papyrus_protobuf::consensus::{StreamMessage, StreamMessageBody};
 use papyrus_test_utils::{get_rng, GetTestInstance};
 
 use super::{MessageId, StreamHandler, StreamId};
+use crate::config::StreamHandlerConfig;
 
 const TIMEOUT: Duration = Duration::from_millis(100);
 const CHANNEL_SIZE: usize = 100;
@@ -64,6 +67,23 @@ mod tests {
         BroadcastedMessageMetadata,
         mpsc::Sender<(StreamId, mpsc::Receiver<ProposalPart>)>,
         futures::stream::Map<mpsc::Receiver<Vec<u8>>, fn(Vec<u8>) -> StreamMessage<ProposalPart>>,
+    ) {
+        let (handler, network_sender, inbound_channel_receiver, metadata, outbound_sender, rx, _) =
+            setup_test_with_config(StreamHandlerConfig::default());
+        (handler, network_sender, inbound_channel_receiver, metadata, outbound_sender, rx)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn setup_test_with_config(
+        config: StreamHandlerConfig,
+    ) -> (
+        StreamHandler<ProposalPart>,
+        MockBroadcastedMessagesSender<StreamMessage<ProposalPart>>,
+        mpsc::Receiver<mpsc::Receiver<ProposalPart>>,
+        BroadcastedMessageMetadata,
+        mpsc::Sender<(StreamId, mpsc::Receiver<ProposalPart>)>,
+        futures::stream::Map<mpsc::Receiver<Vec<u8>>, fn(Vec<u8>) -> StreamMessage<ProposalPart>>,
+        Receiver<PeerId>,
     ) {
         // The outbound_sender is the network connector for broadcasting messages.
         // The network_broadcast_receiver is used to catch those messages in the test.
@@ -75,6 +95,8 @@ mod tests {
         } = subscriber_channels;
 
         let network_broadcast_receiver = mock_broadcast_network.messages_to_broadcast_receiver;
+        // Peers reported by the StreamHandler are reported through its outbound sender.
+        let reported_peers_receiver = mock_broadcast_network.reported_messages_receiver;
 
         // This is used to feed receivers of messages to StreamHandler for broadcasting.
         // The receiver goes into StreamHandler, sender is used by the test (as mock Consensus).
@@ -100,9 +122,8 @@ mod tests {
         let (inbound_channel_sender, inbound_channel_receiver) =
             mpsc::channel::<mpsc::Receiver<ProposalPart>>(CHANNEL_SIZE);
 
-        // TODO(guyn): We should also give the broadcast_topic_client to the StreamHandler
-        // This will allow reporting to the network things like bad peers.
         let handler = StreamHandler::new(
+            config,
             inbound_channel_sender,
             inbound_receiver,
             outbound_channel_receiver,
@@ -118,9 +139,21 @@ mod tests {
             inbound_metadata,
             outbound_channel_sender,
             network_broadcast_receiver,
+            reported_peers_receiver,
         )
     }
 
+    async fn run_handler_for(
+        mut stream_handler: StreamHandler<ProposalPart>,
+        duration: Duration,
+    ) -> StreamHandler<ProposalPart> {
+        let join_handle = tokio::spawn(async move {
+            let _ = tokio::time::timeout(duration, stream_handler.run()).await;
+            stream_handler
+        });
+        join_handle.await.expect("Task should succeed")
+    }
+
     #[tokio::test]
     async fn inbound_in_order() {
         let (mut stream_handler, mut network_sender, mut inbound_channel_receiver, metadata, _, _) =
@@ -548,4 +581,103 @@ mod tests {
             vec![&stream_id2]
         );
     }
+
+    #[tokio::test]
+    async fn inbound_full_channel_buffers_messages() {
+        let (stream_handler, mut network_sender, mut inbound_channel_receiver, metadata, _, _) =
+            setup_test();
+
+        // Send more messages than the application's channel can hold, without reading any.
+        let stream_id = 127;
+        let num_messages = 2 * u64::try_from(CHANNEL_SIZE).unwrap();
+        for i in 0..num_messages {
+            send(&mut network_sender, &metadata, make_test_message(stream_id, i, false)).await;
+        }
+        let stream_handler = run_handler_for(stream_handler, TIMEOUT).await;
+
+        // The messages that didn't fit in the channel are kept in the buffer.
+        let key = (metadata.originator_id.clone(), stream_id);
+        assert!(!stream_handler.inbound_stream_data[&key].message_buffer.is_empty());
+
+        // Once the application reads, the buffered messages are delivered.
+        let mut receiver = inbound_channel_receiver.next().await.unwrap();
+        let join_handle = tokio::spawn(run_handler_for(stream_handler, TIMEOUT));
+        for _ in 0..num_messages {
+            let _ = receiver.next().await.unwrap();
+        }
+        let stream_handler = join_handle.await.expect("Task should succeed");
+        assert!(stream_handler.inbound_stream_data[&key].message_buffer.is_empty());
+        assert_eq!(stream_handler.inbound_stream_data[&key].next_message_id, num_messages);
+    }
+
+    #[tokio::test]
+    async fn inbound_message_out_of_window_reports_peer() {
+        let config = StreamHandlerConfig { max_message_id_window: 5, ..Default::default() };
+        let (stream_handler, mut network_sender, _, metadata, _, _, mut reported_peers_receiver) =
+            setup_test_with_config(config);
+
+        let stream_id = 127;
+        send(&mut network_sender, &metadata, make_test_message(stream_id, 1, false)).await;
+        send(&mut network_sender, &metadata, make_test_message(stream_id, 10, false)).await;
+        let stream_handler = run_handler_for(stream_handler, TIMEOUT).await;
+
+        assert_eq!(
+            reported_peers_receiver.next().await.unwrap(),
+            metadata.originator_id.private_get_peer_id()
+        );
+        // The stream of the violating peer is dropped.
+        assert!(stream_handler.inbound_stream_data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn inbound_duplicate_message_reports_peer() {
+        let (stream_handler, mut network_sender, _, metadata, _, _, mut reported_peers_receiver) =
+            setup_test_with_config(StreamHandlerConfig::default());
+
+        let stream_id = 127;
+        send(&mut network_sender, &metadata, make_test_message(stream_id, 3, false)).await;
+        send(&mut network_sender, &metadata, make_test_message(stream_id, 3, false)).await;
+        let stream_handler = run_handler_for(stream_handler, TIMEOUT).await;
+
+        assert_eq!(
+            reported_peers_receiver.next().await.unwrap(),
+            metadata.originator_id.private_get_peer_id()
+        );
+        assert!(stream_handler.inbound_stream_data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn inbound_too_many_streams_reports_peer() {
+        let config = StreamHandlerConfig { max_streams_per_peer: 2, ..Default::default() };
+        let (stream_handler, mut network_sender, _, metadata, _, _, mut reported_peers_receiver) =
+            setup_test_with_config(config);
+
+        for stream_id in 0..3 {
+            send(&mut network_sender, &metadata, make_test_message(stream_id, 1, false)).await;
+        }
+        let stream_handler = run_handler_for(stream_handler, TIMEOUT).await;
+
+        assert_eq!(
+            reported_peers_receiver.next().await.unwrap(),
+            metadata.originator_id.private_get_peer_id()
+        );
+        // The existing streams are kept, the new one is rejected.
+        assert_eq!(stream_handler.inbound_stream_data.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn inbound_idle_stream_is_evicted() {
+        let config =
+            StreamHandlerConfig { stream_timeout: Duration::from_millis(10), ..Default::default() };
+        let (stream_handler, mut network_sender, _, metadata, _, _, mut reported_peers_receiver) =
+            setup_test_with_config(config);
+
+        // Message 0 never arrives, so the stream is stuck.
+        send(&mut network_sender, &metadata, make_test_message(127, 1, false)).await;
+        let stream_handler = run_handler_for(stream_handler, 5 * TIMEOUT).await;
+
+        assert!(stream_handler.inbound_stream_data.is_empty());
+        // Abandoning a stream is not a protocol violation.
+        assert!(reported_peers_receiver.try_next().is_err());
+    }
 }
@@ -0,0 +1,304 @@
+//! Computes the gas prices of new blocks from the gas prices of recent L1 blocks.
+//!
+//! The L1 gas price is the median of the base fee over a window of recent L1 blocks, and the L1
+//! data gas price is the median of their blob fee. The median ignores short spikes, which smooths
+//! the prices across consecutive blocks.
+//!
+//! The proposer sends its L1 gas prices in the proposal init, and validators execute the proposal
+//! with them, as long as they are close enough to their own.
+#[cfg(test)]
+#[path = "gas_price_oracle_test.rs"]
+mod gas_price_oracle_test;
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+#[cfg(test)]
+use mockall::automock;
+use papyrus_base_layer::{BaseLayerContract, PriceSample};
+use papyrus_protobuf::consensus::ProposalInit;
+use starknet_api::block::{
+    GasPrice,
+    GasPricePerToken,
+    GasPriceVector,
+    GasPrices,
+    NonzeroGasPrice,
+};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+/// The ETH to STRK rate is given as the number of fri per `ETH_TO_STRK_RATE_SCALE` wei.
+pub const ETH_TO_STRK_RATE_SCALE: u128 = 1_000_000_000;
+
+/// The gas prices used when no gas prices are available.
+pub const DEFAULT_GAS_PRICES: GasPrices = GasPrices {
+    eth_gas_prices: GasPriceVector {
+        l1_gas_price: NonzeroGasPrice::MIN,
+        l1_data_gas_price: NonzeroGasPrice::MIN,
+        l2_gas_price: NonzeroGasPrice::MIN,
+    },
+    strk_gas_prices: GasPriceVector {
+        l1_gas_price: NonzeroGasPrice::MIN,
+        l1_data_gas_price: NonzeroGasPrice::MIN,
+        l2_gas_price: NonzeroGasPrice::MIN,
+    },
+};
+
+#[derive(Debug, Error)]
+pub enum GasPriceProviderError {
+    #[error("Base layer error: {0}")]
+    BaseLayer(String),
+    #[error("Failed to get the ETH to STRK rate: {0}")]
+    EthToStrkRate(String),
+    #[error("No L1 gas price samples were collected yet.")]
+    NoSamples,
+    #[error(
+        "The proposed {name} {proposed} differs from {expected} by more than {tolerance_percent}%."
+    )]
+    ProposedPriceOutOfTolerance {
+        name: &'static str,
+        proposed: u128,
+        expected: u128,
+        tolerance_percent: u64,
+    },
+    #[error("The proposed {0} is zero.")]
+    ZeroProposedPrice(&'static str),
+}
+
+pub type GasPriceProviderResult<T> = Result<T, GasPriceProviderError>;
+
+/// Provides the gas prices of new blocks.
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait GasPriceProvider: Send + Sync {
+    async fn gas_prices(&self) -> GasPriceProviderResult<GasPrices>;
+}
+
+/// Always provides the same gas prices.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixedGasPriceProvider(pub GasPrices);
+
+#[async_trait]
+impl GasPriceProvider for FixedGasPriceProvider {
+    async fn gas_prices(&self) -> GasPriceProviderResult<GasPrices> {
+        Ok(self.0.clone())
+    }
+}
+
+/// Provides the rate used to convert prices in wei to prices in fri, as the number of fri per
+/// [`ETH_TO_STRK_RATE_SCALE`] wei.
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait EthToStrkRateSource: Send + Sync {
+    async fn eth_to_strk_rate(&self) -> GasPriceProviderResult<u128>;
+}
+
+/// A rate which is set in the configuration.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FixedEthToStrkRate(pub u128);
+
+#[async_trait]
+impl EthToStrkRateSource for FixedEthToStrkRate {
+    async fn eth_to_strk_rate(&self) -> GasPriceProviderResult<u128> {
+        Ok(self.0)
+    }
+}
+
+/// Samples the gas prices of recent L1 blocks. `update` should be called periodically, e.g., by
+/// `run`, to follow new L1 blocks.
+pub struct L1GasPriceOracle<B: BaseLayerContract> {
+    base_layer: B,
+    eth_to_strk_rate_source: Box<dyn EthToStrkRateSource>,
+    // The number of L1 blocks to compute the median over.
+    num_blocks_for_median: usize,
+    // Only L1 blocks which are `finality` blocks deep are sampled.
+    finality: u64,
+    // The samples of the last sampled L1 blocks, and the number of the next block to sample.
+    samples: Mutex<(VecDeque<PriceSample>, Option<u64>)>,
+}
+
+impl<B> L1GasPriceOracle<B>
+where
+    B: BaseLayerContract + Send + Sync,
+    B::Error: std::error::Error,
+{
+    pub fn new(
+        base_layer: B,
+        eth_to_strk_rate_source: Box<dyn EthToStrkRateSource>,
+        num_blocks_for_median: usize,
+        finality: u64,
+    ) -> Self {
+        assert!(num_blocks_for_median > 0, "The gas price median needs at least one block.");
+        Self {
+            base_layer,
+            eth_to_strk_rate_source,
+            num_blocks_for_median,
+            finality,
+            samples: Mutex::new((VecDeque::new(), None)),
+        }
+    }
+
+    /// Samples the L1 blocks which reached finality since the last update.
+    pub async fn update(&self) -> GasPriceProviderResult<()> {
+        let Some(latest_block_number) = self
+            .base_layer
+            .latest_l1_block_number(self.finality)
+            .await
+            .map_err(|err| GasPriceProviderError::BaseLayer(err.to_string()))?
+        else {
+            return Ok(());
+        };
+        let window_start = (latest_block_number + 1).saturating_sub(
+            u64::try_from(self.num_blocks_for_median).expect("Window fits in u64."),
+        );
+        let next_block_number = self.samples.lock().expect("Lock poisoned.").1;
+        let first_block_number = next_block_number.unwrap_or(window_start).max(window_start);
+
+        for block_number in first_block_number..=latest_block_number {
+            let Some(sample) = self
+                .base_layer
+                .get_price_sample(block_number)
+                .await
+                .map_err(|err| GasPriceProviderError::BaseLayer(err.to_string()))?
+            else {
+                return Ok(());
+            };
+            debug!("Sampled the gas prices of L1 block {block_number}: {sample:?}");
+            let mut guard = self.samples.lock().expect("Lock poisoned.");
+            let (samples, next_block_number) = &mut *guard;
+            samples.push_back(sample);
+            while samples.len() > self.num_blocks_for_median {
+                samples.pop_front();
+            }
+            *next_block_number = Some(block_number + 1);
+        }
+        Ok(())
+    }
+
+    /// Updates the samples every `polling_interval`, forever.
+    pub async fn run(&self, polling_interval: Duration) {
+        loop {
+            if let Err(err) = self.update().await {
+                warn!("Failed to sample the L1 gas prices: {err}");
+            }
+            tokio::time::sleep(polling_interval).await;
+        }
+    }
+}
+
+#[async_trait]
+impl<B> GasPriceProvider for L1GasPriceOracle<B>
+where
+    B: BaseLayerContract + Send + Sync,
+    B::Error: std::error::Error,
+{
+    async fn gas_prices(&self) -> GasPriceProviderResult<GasPrices> {
+        let (l1_gas_price, l1_data_gas_price) = {
+            let guard = self.samples.lock().expect("Lock poisoned.");
+            let samples = &guard.0;
+            if samples.is_empty() {
+                return Err(GasPriceProviderError::NoSamples);
+            }
+            (
+                median(samples.iter().map(|sample| sample.base_fee_per_gas).collect()),
+                median(samples.iter().map(|sample| sample.blob_fee).collect()),
+            )
+        };
+        let rate = self.eth_to_strk_rate_source.eth_to_strk_rate().await?;
+        let to_fri =
+            |price_in_wei: u128| price_in_wei.saturating_mul(rate) / ETH_TO_STRK_RATE_SCALE;
+
+        Ok(GasPrices {
+            eth_gas_prices: GasPriceVector {
+                l1_gas_price: nonzero(l1_gas_price),
+                l1_data_gas_price: nonzero(l1_data_gas_price),
+                l2_gas_price: NonzeroGasPrice::MIN,
+            },
+            strk_gas_prices: GasPriceVector {
+                l1_gas_price: nonzero(to_fri(l1_gas_price)),
+                l1_data_gas_price: nonzero(to_fri(l1_data_gas_price)),
+                l2_gas_price: NonzeroGasPrice::MIN,
+            },
+        })
+    }
+}
+
+/// Sets the L1 gas prices of a proposal init to the ones of `gas_prices`.
+pub fn set_proposal_gas_prices(proposal_init: &mut ProposalInit, gas_prices: &GasPrices) {
+    proposal_init.l1_gas_price = GasPricePerToken {
+        price_in_wei: gas_prices.eth_gas_prices.l1_gas_price.into(),
+        price_in_fri: gas_prices.strk_gas_prices.l1_gas_price.into(),
+    };
+    proposal_init.l1_data_gas_price = GasPricePerToken {
+        price_in_wei: gas_prices.eth_gas_prices.l1_data_gas_price.into(),
+        price_in_fri: gas_prices.strk_gas_prices.l1_data_gas_price.into(),
+    };
+}
+
+/// Returns `gas_prices` with the L1 gas prices of the proposal init. If `tolerance_percent` is set,
+/// each proposed price must be within that percentage of the price in `gas_prices`.
+pub fn proposal_gas_prices(
+    proposal_init: &ProposalInit,
+    gas_prices: &GasPrices,
+    tolerance_percent: Option<u64>,
+) -> GasPriceProviderResult<GasPrices> {
+    let proposed_price = |name: &'static str, proposed: GasPrice, expected: NonzeroGasPrice| {
+        if let Some(tolerance_percent) = tolerance_percent {
+            let expected = expected.get().0;
+            let max_diff = expected.saturating_mul(tolerance_percent.into()) / 100;
+            if proposed.0.abs_diff(expected) > max_diff {
+                return Err(GasPriceProviderError::ProposedPriceOutOfTolerance {
+                    name,
+                    proposed: proposed.0,
+                    expected,
+                    tolerance_percent,
+                });
+            }
+        }
+        NonzeroGasPrice::new(proposed).map_err(|_| GasPriceProviderError::ZeroProposedPrice(name))
+    };
+
+    let GasPrices { eth_gas_prices, strk_gas_prices } = gas_prices;
+    Ok(GasPrices {
+        eth_gas_prices: GasPriceVector {
+            l1_gas_price: proposed_price(
+                "L1 gas price in wei",
+                proposal_init.l1_gas_price.price_in_wei,
+                eth_gas_prices.l1_gas_price,
+            )?,
+            l1_data_gas_price: proposed_price(
+                "L1 data gas price in wei",
+                proposal_init.l1_data_gas_price.price_in_wei,
+                eth_gas_prices.l1_data_gas_price,
+            )?,
+            l2_gas_price: eth_gas_prices.l2_gas_price,
+        },
+        strk_gas_prices: GasPriceVector {
+            l1_gas_price: proposed_price(
+                "L1 gas price in fri",
+                proposal_init.l1_gas_price.price_in_fri,
+                strk_gas_prices.l1_gas_price,
+            )?,
+            l1_data_gas_price: proposed_price(
+                "L1 data gas price in fri",
+                proposal_init.l1_data_gas_price.price_in_fri,
+                strk_gas_prices.l1_data_gas_price,
+            )?,
+            l2_gas_price: strk_gas_prices.l2_gas_price,
+        },
+    })
+}
+
+// The median of a non-empty list, rounded down for an even number of values.
+fn median(mut values: Vec<u128>) -> u128 {
+    values.sort_unstable();
+    let middle = values.len() / 2;
+    if values.len() % 2 == 0 { (values[middle - 1] + values[middle]) / 2 } else { values[middle] }
+}
+
+// Blocks from before EIP-1559 or EIP-4844 have zero prices, which are not valid on Starknet.
+fn nonzero(price: u128) -> NonzeroGasPrice {
+    NonzeroGasPrice::new(GasPrice(price)).unwrap_or(NonzeroGasPrice::MIN)
+}
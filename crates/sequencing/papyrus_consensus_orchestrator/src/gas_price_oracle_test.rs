@@ -0,0 +1,184 @@
+use papyrus_base_layer::test_utils::FakeBaseLayerContract;
+use papyrus_protobuf::consensus::ProposalInit;
+use starknet_api::block::{GasPrice, GasPriceVector, GasPrices, NonzeroGasPrice};
+
+use crate::gas_price_oracle::{
+    proposal_gas_prices,
+    set_proposal_gas_prices,
+    FixedEthToStrkRate,
+    GasPriceProvider,
+    GasPriceProviderError,
+    L1GasPriceOracle,
+    MockEthToStrkRateSource,
+    ETH_TO_STRK_RATE_SCALE,
+};
+
+const NUM_BLOCKS_FOR_MEDIAN: usize = 3;
+const FINALITY: u64 = 1;
+
+fn oracle(
+    base_layer: FakeBaseLayerContract,
+    eth_to_strk_rate: u128,
+) -> L1GasPriceOracle<FakeBaseLayerContract> {
+    L1GasPriceOracle::new(
+        base_layer,
+        Box::new(FixedEthToStrkRate(eth_to_strk_rate)),
+        NUM_BLOCKS_FOR_MEDIAN,
+        FINALITY,
+    )
+}
+
+fn gas_price(price: u128) -> NonzeroGasPrice {
+    NonzeroGasPrice::new(GasPrice(price)).unwrap()
+}
+
+#[tokio::test]
+async fn no_samples() {
+    let base_layer = FakeBaseLayerContract::default();
+    // Not final yet.
+    base_layer.add_block_with_prices(10, 10);
+    let oracle = oracle(base_layer, ETH_TO_STRK_RATE_SCALE);
+
+    oracle.update().await.unwrap();
+    assert!(matches!(oracle.gas_prices().await, Err(GasPriceProviderError::NoSamples)));
+}
+
+#[tokio::test]
+async fn median_over_window() {
+    let base_layer = FakeBaseLayerContract::default();
+    for (base_fee, blob_fee) in [(1000, 1), (10, 5), (30, 2), (20, 7), (0, 0)] {
+        base_layer.add_block_with_prices(base_fee, blob_fee);
+    }
+    let oracle = oracle(base_layer, 2 * ETH_TO_STRK_RATE_SCALE);
+
+    // Blocks 1-3 are in the window: block 0 is too old and block 4 isn't final.
+    oracle.update().await.unwrap();
+    let gas_prices = oracle.gas_prices().await.unwrap();
+    assert_eq!(gas_prices.eth_gas_prices.l1_gas_price, gas_price(20));
+    assert_eq!(gas_prices.eth_gas_prices.l1_data_gas_price, gas_price(5));
+    assert_eq!(gas_prices.eth_gas_prices.l2_gas_price, NonzeroGasPrice::MIN);
+    assert_eq!(gas_prices.strk_gas_prices.l1_gas_price, gas_price(40));
+    assert_eq!(gas_prices.strk_gas_prices.l1_data_gas_price, gas_price(10));
+    assert_eq!(gas_prices.strk_gas_prices.l2_gas_price, NonzeroGasPrice::MIN);
+}
+
+#[tokio::test]
+async fn window_follows_new_blocks() {
+    let base_layer = FakeBaseLayerContract::default();
+    for _ in 0..=FINALITY {
+        base_layer.add_block_with_prices(10, 10);
+    }
+    let oracle = oracle(base_layer, ETH_TO_STRK_RATE_SCALE);
+    oracle.update().await.unwrap();
+    assert_eq!(oracle.gas_prices().await.unwrap().eth_gas_prices.l1_gas_price, gas_price(10));
+
+    // Samples: [10, 10].
+    oracle.base_layer.add_block_with_prices(40, 10);
+    oracle.update().await.unwrap();
+    assert_eq!(oracle.gas_prices().await.unwrap().eth_gas_prices.l1_gas_price, gas_price(10));
+
+    // Samples: [10, 10, 40].
+    oracle.base_layer.add_block_with_prices(40, 10);
+    oracle.update().await.unwrap();
+    assert_eq!(oracle.gas_prices().await.unwrap().eth_gas_prices.l1_gas_price, gas_price(10));
+
+    // Samples: [10, 40, 40]. The first block left the window.
+    oracle.base_layer.add_block_with_prices(40, 10);
+    oracle.update().await.unwrap();
+    assert_eq!(oracle.gas_prices().await.unwrap().eth_gas_prices.l1_gas_price, gas_price(40));
+}
+
+#[tokio::test]
+async fn zero_prices_are_replaced_by_minimal_prices() {
+    let base_layer = FakeBaseLayerContract::default();
+    for _ in 0..=FINALITY {
+        base_layer.add_block_with_prices(0, 0);
+    }
+    let oracle = oracle(base_layer, ETH_TO_STRK_RATE_SCALE);
+
+    oracle.update().await.unwrap();
+    let gas_prices = oracle.gas_prices().await.unwrap();
+    assert_eq!(gas_prices.eth_gas_prices.l1_gas_price, NonzeroGasPrice::MIN);
+    assert_eq!(gas_prices.eth_gas_prices.l1_data_gas_price, NonzeroGasPrice::MIN);
+    assert_eq!(gas_prices.strk_gas_prices.l1_gas_price, NonzeroGasPrice::MIN);
+}
+
+#[tokio::test]
+async fn rate_source_failure() {
+    let base_layer = FakeBaseLayerContract::default();
+    for _ in 0..=FINALITY {
+        base_layer.add_block_with_prices(10, 10);
+    }
+    let mut rate_source = MockEthToStrkRateSource::new();
+    rate_source
+        .expect_eth_to_strk_rate()
+        .returning(|| Err(GasPriceProviderError::EthToStrkRate("Unavailable.".to_string())));
+    let oracle =
+        L1GasPriceOracle::new(base_layer, Box::new(rate_source), NUM_BLOCKS_FOR_MEDIAN, FINALITY);
+
+    oracle.update().await.unwrap();
+    assert!(matches!(oracle.gas_prices().await, Err(GasPriceProviderError::EthToStrkRate(_))));
+}
+
+// Gas prices whose L1 gas prices are derived from `l1_gas_price`, and whose L2 gas prices are 1.
+fn l1_gas_prices(l1_gas_price: u128) -> GasPrices {
+    GasPrices {
+        eth_gas_prices: GasPriceVector {
+            l1_gas_price: gas_price(l1_gas_price),
+            l1_data_gas_price: gas_price(l1_gas_price + 1),
+            l2_gas_price: NonzeroGasPrice::MIN,
+        },
+        strk_gas_prices: GasPriceVector {
+            l1_gas_price: gas_price(l1_gas_price + 2),
+            l1_data_gas_price: gas_price(l1_gas_price + 3),
+            l2_gas_price: NonzeroGasPrice::MIN,
+        },
+    }
+}
+
+#[test]
+fn proposal_gas_prices_within_tolerance() {
+    let mut proposal_init = ProposalInit::default();
+    set_proposal_gas_prices(&mut proposal_init, &l1_gas_prices(110));
+    let mut own_gas_prices = l1_gas_prices(100);
+    own_gas_prices.eth_gas_prices.l2_gas_price = gas_price(5);
+
+    // The L1 gas prices are the proposer's, and the L2 gas prices are ours.
+    let mut expected_gas_prices = l1_gas_prices(110);
+    expected_gas_prices.eth_gas_prices.l2_gas_price = gas_price(5);
+    assert_eq!(
+        proposal_gas_prices(&proposal_init, &own_gas_prices, Some(10)).unwrap(),
+        expected_gas_prices
+    );
+}
+
+#[test]
+fn proposal_gas_prices_out_of_tolerance() {
+    let mut proposal_init = ProposalInit::default();
+    set_proposal_gas_prices(&mut proposal_init, &l1_gas_prices(90));
+
+    assert!(matches!(
+        proposal_gas_prices(&proposal_init, &l1_gas_prices(100), Some(5)),
+        Err(GasPriceProviderError::ProposedPriceOutOfTolerance {
+            proposed: 90,
+            expected: 100,
+            tolerance_percent: 5,
+            ..
+        })
+    ));
+    // Without a tolerance, any nonzero price is accepted.
+    assert_eq!(
+        proposal_gas_prices(&proposal_init, &l1_gas_prices(100), None).unwrap(),
+        l1_gas_prices(90)
+    );
+}
+
+#[test]
+fn zero_proposal_gas_prices() {
+    let proposal_init = ProposalInit::default();
+
+    assert!(matches!(
+        proposal_gas_prices(&proposal_init, &l1_gas_prices(100), None),
+        Err(GasPriceProviderError::ZeroProposedPrice(_))
+    ));
+}
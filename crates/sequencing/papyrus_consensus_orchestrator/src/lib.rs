@@ -8,6 +8,14 @@ pub mod papyrus_consensus_context;
 #[allow(missing_docs)]
 pub mod sequencer_consensus_context;
 
+/// Resolves the validator set for each height.
+#[allow(missing_docs)]
+pub mod validator_set;
+
 /// Centralized and decentralized communication types and functionallity.
 #[allow(missing_docs)]
 pub mod cende;
+
+/// Gas prices of new blocks, derived from the gas prices of recent L1 blocks.
+#[allow(missing_docs)]
+pub mod gas_price_oracle;
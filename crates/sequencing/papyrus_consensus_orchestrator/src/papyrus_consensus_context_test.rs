@@ -2,6 +2,7 @@ use std::time::Duration;
 
 use futures::channel::{mpsc, oneshot};
 use futures::StreamExt;
+use papyrus_consensus::config::StreamHandlerConfig;
 use papyrus_consensus::stream_handler::StreamHandler;
 use papyrus_consensus::types::ConsensusContext;
 use papyrus_network::network_manager::test_utils::{
@@ -134,8 +135,11 @@ fn test_setup()
         broadcasted_messages_receiver: inbound_network_receiver,
         broadcast_topic_client: outbound_network_sender,
     } = network_proposal_channels.subscriber_channels;
-    let (outbound_internal_sender, _inbound_internal_receiver, _) =
-        StreamHandler::get_channels(inbound_network_receiver, outbound_network_sender);
+    let (outbound_internal_sender, _inbound_internal_receiver, _) = StreamHandler::get_channels(
+        StreamHandlerConfig::default(),
+        inbound_network_receiver,
+        outbound_network_sender,
+    );
 
     let sync_channels = mock_register_broadcast_topic().unwrap();
 
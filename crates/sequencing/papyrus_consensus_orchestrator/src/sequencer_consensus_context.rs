@@ -26,7 +26,6 @@ use papyrus_protobuf::consensus::{
     ProposalPart,
     TransactionBatch,
     Vote,
-    DEFAULT_VALIDATOR_ID,
 };
 use starknet_api::block::{
     BlockHash,
@@ -34,9 +33,7 @@ use starknet_api::block::{
     BlockInfo,
     BlockNumber,
     BlockTimestamp,
-    GasPriceVector,
     GasPrices,
-    NonzeroGasPrice,
 };
 use starknet_api::core::ChainId;
 use starknet_api::executable_transaction::Transaction as ExecutableTransaction;
@@ -60,27 +57,21 @@ use tokio_util::sync::CancellationToken;
 use tracing::{debug, debug_span, info, instrument, trace, warn, Instrument};
 
 use crate::cende::{BlobParameters, CendeContext};
-
-// TODO(Dan, Matan): Remove this once and replace with real gas prices.
-const TEMPORARY_GAS_PRICES: GasPrices = GasPrices {
-    eth_gas_prices: GasPriceVector {
-        l1_gas_price: NonzeroGasPrice::MIN,
-        l1_data_gas_price: NonzeroGasPrice::MIN,
-        l2_gas_price: NonzeroGasPrice::MIN,
-    },
-    strk_gas_prices: GasPriceVector {
-        l1_gas_price: NonzeroGasPrice::MIN,
-        l1_data_gas_price: NonzeroGasPrice::MIN,
-        l2_gas_price: NonzeroGasPrice::MIN,
-    },
+use crate::gas_price_oracle::{
+    proposal_gas_prices,
+    set_proposal_gas_prices,
+    GasPriceProvider,
+    GasPriceProviderResult,
+    DEFAULT_GAS_PRICES,
 };
+use crate::validator_set::ValidatorSetProvider;
 
 // {height: {proposal_id: (content, [proposal_ids])}}
 // Note that multiple proposals IDs can be associated with the same content, but we only need to
 // store one of them.
 type HeightToIdToContent =
     BTreeMap<BlockNumber, HashMap<ProposalContentId, (Vec<ExecutableTransaction>, ProposalId)>>;
-type ValidationParams = (BlockNumber, ValidatorId, Duration, mpsc::Receiver<ProposalPart>);
+type ValidationParams = (ProposalInit, Duration, mpsc::Receiver<ProposalPart>);
 
 const CHANNEL_SIZE: usize = 100;
 
@@ -96,11 +87,23 @@ enum HandledProposalPart {
 // TODO(Guy): Move this to the context config.
 const BUILD_PROPOSAL_MARGIN: Duration = Duration::from_millis(1000);
 
+// The validator set may depend on state which isn't synced yet, in which case we retry.
+const VALIDATOR_SET_RETRY_INTERVAL: Duration = Duration::from_millis(500);
+
 pub struct SequencerConsensusContext {
     #[allow(dead_code)]
     state_sync_client: SharedStateSyncClient,
     batcher: Arc<dyn BatcherClient>,
-    validators: Vec<ValidatorId>,
+    validator_set_provider: Arc<dyn ValidatorSetProvider>,
+    // The gas prices of proposals are taken from here when the proposal starts.
+    gas_price_provider: Arc<dyn GasPriceProvider>,
+    // The last gas prices taken from the gas price provider, used while it fails.
+    last_gas_prices: Option<GasPrices>,
+    // The maximal difference, in percent, between the L1 gas prices of a proposal and ours.
+    gas_price_tolerance_percent: u64,
+    // Cache of the validator sets, since the proposer is calculated synchronously. Contains the
+    // current height, and may contain future heights.
+    validator_sets: Mutex<BTreeMap<BlockNumber, Vec<ValidatorId>>>,
     // Proposal building/validating returns immediately, leaving the actual processing to a spawned
     // task. The spawned task processes the proposal asynchronously and updates the
     // valid_proposals map upon completion, ensuring consistency across tasks.
@@ -128,12 +131,15 @@ pub struct SequencerConsensusContext {
 }
 
 impl SequencerConsensusContext {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         state_sync_client: SharedStateSyncClient,
         batcher: Arc<dyn BatcherClient>,
         outbound_proposal_sender: mpsc::Sender<(u64, mpsc::Receiver<ProposalPart>)>,
         vote_broadcast_client: BroadcastTopicClient<Vote>,
-        num_validators: u64,
+        validator_set_provider: Arc<dyn ValidatorSetProvider>,
+        gas_price_provider: Arc<dyn GasPriceProvider>,
+        gas_price_tolerance_percent: u64,
         chain_id: ChainId,
         cende_ambassador: Arc<dyn CendeContext>,
     ) -> Self {
@@ -142,10 +148,11 @@ impl SequencerConsensusContext {
             batcher,
             outbound_proposal_sender,
             vote_broadcast_client,
-            // TODO(Matan): Set the actual validator IDs (contract addresses).
-            validators: (0..num_validators)
-                .map(|i| ValidatorId::from(DEFAULT_VALIDATOR_ID + i))
-                .collect(),
+            validator_set_provider,
+            gas_price_provider,
+            last_gas_prices: None,
+            gas_price_tolerance_percent,
+            validator_sets: Mutex::new(BTreeMap::new()),
             valid_proposals: Arc::new(Mutex::new(HeightToIdToContent::new())),
             proposal_id: 0,
             current_height: None,
@@ -165,7 +172,7 @@ impl ConsensusContext for SequencerConsensusContext {
     #[instrument(level = "info", skip_all, fields(proposal_init))]
     async fn build_proposal(
         &mut self,
-        proposal_init: ProposalInit,
+        mut proposal_init: ProposalInit,
         timeout: Duration,
     ) -> oneshot::Receiver<ProposalContentId> {
         info!("Building proposal: timeout={timeout:?}");
@@ -178,6 +185,8 @@ impl ConsensusContext for SequencerConsensusContext {
         let valid_proposals = Arc::clone(&self.valid_proposals);
         let proposal_id = ProposalId(self.proposal_id);
         self.proposal_id += 1;
+        let gas_prices = self.gas_prices().await;
+        set_proposal_gas_prices(&mut proposal_init, &gas_prices);
         assert!(timeout > BUILD_PROPOSAL_MARGIN);
         let (proposal_sender, proposal_receiver) = mpsc::channel(CHANNEL_SIZE);
         let stream_id = proposal_init.height.0;
@@ -197,6 +206,7 @@ impl ConsensusContext for SequencerConsensusContext {
                     valid_proposals,
                     proposal_id,
                     cende_write_success,
+                    gas_prices,
                 )
                 .await;
             }
@@ -224,17 +234,13 @@ impl ConsensusContext for SequencerConsensusContext {
                 debug!("Queuing proposal for future round: current_round={}", self.current_round);
                 self.queued_proposals.insert(
                     proposal_init.round,
-                    (
-                        (proposal_init.height, proposal_init.proposer, timeout, content_receiver),
-                        fin_sender,
-                    ),
+                    ((proposal_init, timeout, content_receiver), fin_sender),
                 );
                 fin_receiver
             }
             std::cmp::Ordering::Equal => {
                 self.validate_current_round_proposal(
-                    proposal_init.height,
-                    proposal_init.proposer,
+                    proposal_init,
                     timeout,
                     content_receiver,
                     fin_sender,
@@ -259,16 +265,43 @@ impl ConsensusContext for SequencerConsensusContext {
         // TODO(guyn): Stream the TXs to the network.
     }
 
-    async fn validators(&self, _height: BlockNumber) -> Vec<ValidatorId> {
-        self.validators.clone()
+    async fn validators(&self, height: BlockNumber) -> Vec<ValidatorId> {
+        if let Some(validators) = self
+            .validator_sets
+            .lock()
+            .expect("Lock on validator sets was poisoned due to a previous panic")
+            .get(&height)
+        {
+            return validators.clone();
+        }
+        let validators = loop {
+            match self.validator_set_provider.validators(height).await {
+                Ok(validators) => break validators,
+                Err(err) => {
+                    warn!("Failed to get the validators for height {height}: {err}. Retrying.");
+                    tokio::time::sleep(VALIDATOR_SET_RETRY_INTERVAL).await;
+                }
+            }
+        };
+        self.validator_sets
+            .lock()
+            .expect("Lock on validator sets was poisoned due to a previous panic")
+            .insert(height, validators.clone());
+        validators
     }
 
     fn proposer(&self, height: BlockNumber, round: Round) -> ValidatorId {
+        let validator_sets = self
+            .validator_sets
+            .lock()
+            .expect("Lock on validator sets was poisoned due to a previous panic");
+        let validators = validator_sets
+            .get(&height)
+            .unwrap_or_else(|| panic!("The validators for height {height} are unknown"));
         let height: usize = height.0.try_into().expect("Cannot convert to usize");
         let round: usize = round.try_into().expect("Cannot convert to usize");
-        *self
-            .validators
-            .get((height + round) % self.validators.len())
+        *validators
+            .get((height + round) % validators.len())
             .expect("There should be at least one validator")
     }
 
@@ -321,6 +354,12 @@ impl ConsensusContext for SequencerConsensusContext {
             self.interrupt_active_proposal().await;
             self.queued_proposals.clear();
             self.active_proposal = None;
+            // Make sure the proposer can be calculated for the new height.
+            self.validators(height).await;
+            self.validator_sets
+                .lock()
+                .expect("Lock on validator sets was poisoned due to a previous panic")
+                .retain(|&h, _| h >= height);
             // The Batcher must be told when we begin to work on a new height. The implicit model is
             // that consensus works on a given height until it is done (either a decision is reached
             // or sync causes us to move on) and then moves on to a different height, never to
@@ -352,24 +391,70 @@ impl ConsensusContext for SequencerConsensusContext {
             }
         }
         // Validate the proposal for the current round if exists.
-        let Some(((height, validator, timeout, content), fin_sender)) = to_process else {
+        let Some(((proposal_init, timeout, content), fin_sender)) = to_process else {
             return;
         };
-        self.validate_current_round_proposal(height, validator, timeout, content, fin_sender).await;
+        self.validate_current_round_proposal(proposal_init, timeout, content, fin_sender).await;
     }
 }
 
 impl SequencerConsensusContext {
+    // Returns the latest gas prices of the provider. If they are unavailable, e.g., since the L1
+    // node is unreachable, returns the last gas prices it provided, if any.
+    async fn local_gas_prices(&mut self) -> GasPriceProviderResult<GasPrices> {
+        match self.gas_price_provider.gas_prices().await {
+            Ok(gas_prices) => {
+                self.last_gas_prices = Some(gas_prices.clone());
+                Ok(gas_prices)
+            }
+            Err(err) => match &self.last_gas_prices {
+                Some(last_gas_prices) => {
+                    warn!("Failed to get the gas prices, using the last known gas prices: {err}");
+                    Ok(last_gas_prices.clone())
+                }
+                None => Err(err),
+            },
+        }
+    }
+
+    // Proposals are built with the local gas prices. If no gas prices are known, the minimal gas
+    // prices are used.
+    async fn gas_prices(&mut self) -> GasPrices {
+        self.local_gas_prices().await.unwrap_or_else(|err| {
+            warn!("Failed to get the gas prices, using the default gas prices: {err}");
+            DEFAULT_GAS_PRICES
+        })
+    }
+
+    // Proposals are validated with the proposer's L1 gas prices, so that all validators build the
+    // same block, as long as they are close enough to the local gas prices. If no local gas prices
+    // are known, the proposal can't be checked and is rejected.
+    async fn validation_gas_prices(
+        &mut self,
+        proposal_init: &ProposalInit,
+    ) -> GasPriceProviderResult<GasPrices> {
+        let gas_prices = self.local_gas_prices().await?;
+        proposal_gas_prices(proposal_init, &gas_prices, Some(self.gas_price_tolerance_percent))
+    }
+
     #[instrument(level = "info", skip(self, timeout, content_receiver, fin_sender))]
     async fn validate_current_round_proposal(
         &mut self,
-        height: BlockNumber,
-        proposer: ValidatorId,
+        proposal_init: ProposalInit,
         timeout: Duration,
         content_receiver: mpsc::Receiver<ProposalPart>,
         fin_sender: oneshot::Sender<(ProposalContentId, ProposalFin)>,
     ) {
         info!("Validating proposal with timeout: {timeout:?}");
+        // Dropping the fin sender marks the proposal as invalid.
+        let gas_prices = match self.validation_gas_prices(&proposal_init).await {
+            Ok(gas_prices) => gas_prices,
+            Err(err) => {
+                warn!("Rejecting the proposal due to its gas prices: {err}");
+                return;
+            }
+        };
+        let ProposalInit { height, proposer, .. } = proposal_init;
         let cancel_token = CancellationToken::new();
         let cancel_token_clone = cancel_token.clone();
         let batcher = Arc::clone(&self.batcher);
@@ -386,6 +471,7 @@ impl SequencerConsensusContext {
                 height,
                 proposer,
                 timeout,
+                gas_prices,
                 valid_proposals,
                 content_receiver,
                 fin_sender,
@@ -415,8 +501,9 @@ async fn build_proposal(
     valid_proposals: Arc<Mutex<HeightToIdToContent>>,
     proposal_id: ProposalId,
     cende_write_success: oneshot::Receiver<bool>,
+    gas_prices: GasPrices,
 ) {
-    initialize_build(proposal_id, &proposal_init, timeout, batcher.as_ref()).await;
+    initialize_build(proposal_id, &proposal_init, timeout, gas_prices, batcher.as_ref()).await;
     debug!("Broadcasting proposal init: {proposal_init:?}");
     proposal_sender
         .send(ProposalPart::Init(proposal_init))
@@ -452,6 +539,7 @@ async fn initialize_build(
     proposal_id: ProposalId,
     proposal_init: &ProposalInit,
     timeout: Duration,
+    gas_prices: GasPrices,
     batcher: &dyn BatcherClient,
 ) {
     let batcher_timeout = chrono::Duration::from_std(timeout - BUILD_PROPOSAL_MARGIN)
@@ -469,7 +557,7 @@ async fn initialize_build(
         // TODO(Dan, Matan): Fill block info.
         block_info: BlockInfo {
             block_number: proposal_init.height,
-            gas_prices: TEMPORARY_GAS_PRICES,
+            gas_prices,
             block_timestamp: BlockTimestamp(
                 now.timestamp().try_into().expect("Failed to convert timestamp"),
             ),
@@ -570,12 +658,13 @@ async fn validate_proposal(
     height: BlockNumber,
     proposer: ValidatorId,
     timeout: Duration,
+    gas_prices: GasPrices,
     valid_proposals: Arc<Mutex<HeightToIdToContent>>,
     mut content_receiver: mpsc::Receiver<ProposalPart>,
     fin_sender: oneshot::Sender<(ProposalContentId, ProposalFin)>,
     cancel_token: CancellationToken,
 ) {
-    initiate_validation(batcher, proposal_id, height, proposer, timeout).await;
+    initiate_validation(batcher, proposal_id, height, proposer, timeout, gas_prices).await;
 
     let mut content = Vec::new();
     let (built_block, received_fin) = loop {
@@ -629,6 +718,7 @@ async fn initiate_validation(
     height: BlockNumber,
     proposer: ValidatorId,
     timeout: Duration,
+    gas_prices: GasPrices,
 ) {
     // Initiate the validation.
     let chrono_timeout =
@@ -645,7 +735,7 @@ async fn initiate_validation(
         // TODO(Dan, Matan): Fill block info.
         block_info: BlockInfo {
             block_number: height,
-            gas_prices: TEMPORARY_GAS_PRICES,
+            gas_prices,
             block_timestamp: BlockTimestamp(
                 now.timestamp().try_into().expect("Failed to convert timestamp"),
             ),
@@ -5,8 +5,9 @@ use std::vec;
 use futures::channel::{mpsc, oneshot};
 use futures::{FutureExt, SinkExt};
 use lazy_static::lazy_static;
+use papyrus_consensus::config::StreamHandlerConfig;
 use papyrus_consensus::stream_handler::StreamHandler;
-use papyrus_consensus::types::ConsensusContext;
+use papyrus_consensus::types::{ConsensusContext, Round, ValidatorId};
 use papyrus_network::network_manager::test_utils::{
     mock_register_broadcast_topic,
     BroadcastNetworkMock,
@@ -20,8 +21,9 @@ use papyrus_protobuf::consensus::{
     StreamMessage,
     TransactionBatch,
     Vote,
+    DEFAULT_VALIDATOR_ID,
 };
-use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::block::{BlockHash, BlockNumber, GasPrice, GasPrices, NonzeroGasPrice};
 use starknet_api::core::{ChainId, Nonce, StateDiffCommitment};
 use starknet_api::executable_transaction::Transaction as ExecutableTransaction;
 use starknet_api::felt;
@@ -43,15 +45,31 @@ use starknet_batcher_types::batcher_types::{
 use starknet_batcher_types::communication::MockBatcherClient;
 use starknet_state_sync_types::communication::MockStateSyncClient;
 use starknet_types_core::felt::Felt;
+use test_case::test_case;
 
 use crate::cende::MockCendeContext;
+use crate::gas_price_oracle::{
+    set_proposal_gas_prices,
+    FixedGasPriceProvider,
+    GasPriceProvider,
+    GasPriceProviderError,
+    MockGasPriceProvider,
+    DEFAULT_GAS_PRICES,
+};
 use crate::sequencer_consensus_context::SequencerConsensusContext;
+use crate::validator_set::{
+    Epochs,
+    ScheduledValidatorSet,
+    ScheduledValidatorSetProvider,
+    ValidatorSetProvider,
+};
 
 const TIMEOUT: Duration = Duration::from_millis(1200);
 const CHANNEL_SIZE: usize = 5000;
 const NUM_VALIDATORS: u64 = 4;
 const STATE_DIFF_COMMITMENT: StateDiffCommitment = StateDiffCommitment(PoseidonHash(Felt::ZERO));
 const CHAIN_ID: ChainId = ChainId::Mainnet;
+const GAS_PRICE_TOLERANCE_PERCENT: u64 = 10;
 
 lazy_static! {
     static ref TX_BATCH: Vec<Transaction> = (0..3).map(generate_invoke_tx).collect();
@@ -72,9 +90,34 @@ struct NetworkDependencies {
     _new_proposal_network: BroadcastNetworkMock<StreamMessage<ProposalPart>>,
 }
 
+fn validator_ids(ids: impl Iterator<Item = u64>) -> Vec<ValidatorId> {
+    ids.map(|i| ValidatorId::from(DEFAULT_VALIDATOR_ID + i)).collect()
+}
+
 fn setup(
     batcher: MockBatcherClient,
     cende_ambassador: MockCendeContext,
+) -> (SequencerConsensusContext, NetworkDependencies) {
+    let validator_set_provider = ScheduledValidatorSetProvider::new(
+        Epochs::new(1).unwrap(),
+        vec![ScheduledValidatorSet {
+            start_height: BlockNumber(0),
+            validators: validator_ids(0..NUM_VALIDATORS),
+        }],
+    );
+    setup_with_providers(
+        batcher,
+        cende_ambassador,
+        Arc::new(validator_set_provider),
+        Arc::new(FixedGasPriceProvider(DEFAULT_GAS_PRICES)),
+    )
+}
+
+fn setup_with_providers(
+    batcher: MockBatcherClient,
+    cende_ambassador: MockCendeContext,
+    validator_set_provider: Arc<dyn ValidatorSetProvider>,
+    gas_price_provider: Arc<dyn GasPriceProvider>,
 ) -> (SequencerConsensusContext, NetworkDependencies) {
     let TestSubscriberChannels { mock_network: mock_proposal_stream_network, subscriber_channels } =
         mock_register_broadcast_topic().expect("Failed to create mock network");
@@ -82,8 +125,11 @@ fn setup(
         broadcasted_messages_receiver: inbound_network_receiver,
         broadcast_topic_client: outbound_network_sender,
     } = subscriber_channels;
-    let (outbound_proposal_stream_sender, _, _) =
-        StreamHandler::get_channels(inbound_network_receiver, outbound_network_sender);
+    let (outbound_proposal_stream_sender, _, _) = StreamHandler::get_channels(
+        StreamHandlerConfig::default(),
+        inbound_network_receiver,
+        outbound_network_sender,
+    );
 
     let TestSubscriberChannels { mock_network: mock_vote_network, subscriber_channels } =
         mock_register_broadcast_topic().expect("Failed to create mock network");
@@ -96,7 +142,9 @@ fn setup(
         Arc::new(batcher),
         outbound_proposal_stream_sender,
         votes_topic_client,
-        NUM_VALIDATORS,
+        validator_set_provider,
+        gas_price_provider,
+        GAS_PRICE_TOLERANCE_PERCENT,
         CHAIN_ID,
         Arc::new(cende_ambassador),
     );
@@ -109,6 +157,13 @@ fn setup(
     (context, network_dependencies)
 }
 
+// A proposal init with the gas prices of the gas price provider of `setup`.
+fn proposal_init(round: Round) -> ProposalInit {
+    let mut init = ProposalInit { round, ..Default::default() };
+    set_proposal_gas_prices(&mut init, &DEFAULT_GAS_PRICES);
+    init
+}
+
 // Setup for test of the `build_proposal` function.
 async fn build_proposal_setup(
     mock_cende_context: MockCendeContext,
@@ -211,8 +266,7 @@ async fn validate_proposal_success() {
         }))
         .await
         .unwrap();
-    let fin_receiver =
-        context.validate_proposal(ProposalInit::default(), TIMEOUT, content_receiver).await;
+    let fin_receiver = context.validate_proposal(proposal_init(0), TIMEOUT, content_receiver).await;
     content_sender.close_channel();
     assert_eq!(fin_receiver.await.unwrap().0.0, STATE_DIFF_COMMITMENT.0.0);
 }
@@ -261,8 +315,7 @@ async fn repropose() {
         }))
         .await
         .unwrap();
-    let fin_receiver =
-        context.validate_proposal(ProposalInit::default(), TIMEOUT, content_receiver).await;
+    let fin_receiver = context.validate_proposal(proposal_init(0), TIMEOUT, content_receiver).await;
     content_sender.close_channel();
     assert_eq!(fin_receiver.await.unwrap().0.0, STATE_DIFF_COMMITMENT.0.0);
 
@@ -322,7 +375,7 @@ async fn proposals_from_different_rounds() {
     let (mut content_sender, content_receiver) = mpsc::channel(CHANNEL_SIZE);
     content_sender.send(prop_part_txs.clone()).await.unwrap();
 
-    let mut init = ProposalInit { round: 0, ..Default::default() };
+    let mut init = proposal_init(0);
     let fin_receiver_past_round = context.validate_proposal(init, TIMEOUT, content_receiver).await;
     // No fin was sent, channel remains open.
     assert!(fin_receiver_past_round.await.is_err());
@@ -339,13 +392,8 @@ async fn proposals_from_different_rounds() {
     let (mut content_sender, content_receiver) = mpsc::channel(CHANNEL_SIZE);
     content_sender.send(prop_part_txs.clone()).await.unwrap();
     content_sender.send(prop_part_fin.clone()).await.unwrap();
-    let fin_receiver_future_round = context
-        .validate_proposal(
-            ProposalInit { round: 2, ..Default::default() },
-            TIMEOUT,
-            content_receiver,
-        )
-        .await;
+    let fin_receiver_future_round =
+        context.validate_proposal(proposal_init(2), TIMEOUT, content_receiver).await;
     content_sender.close_channel();
     // Even with sending fin and closing the channel.
     assert!(fin_receiver_future_round.now_or_never().is_none());
@@ -407,7 +455,7 @@ async fn interrupt_active_proposal() {
     // without needing interrupt.
     let (mut _content_sender_0, content_receiver) = mpsc::channel(CHANNEL_SIZE);
     let fin_receiver_0 =
-        context.validate_proposal(ProposalInit::default(), TIMEOUT, content_receiver).await;
+        context.validate_proposal(proposal_init(0), TIMEOUT, content_receiver).await;
 
     let (mut content_sender_1, content_receiver) = mpsc::channel(CHANNEL_SIZE);
     content_sender_1
@@ -420,13 +468,8 @@ async fn interrupt_active_proposal() {
         }))
         .await
         .unwrap();
-    let fin_receiver_1 = context
-        .validate_proposal(
-            ProposalInit { round: 1, ..Default::default() },
-            TIMEOUT,
-            content_receiver,
-        )
-        .await;
+    let fin_receiver_1 =
+        context.validate_proposal(proposal_init(1), TIMEOUT, content_receiver).await;
     // Move the context to the next round.
     context.set_height_and_round(BlockNumber(0), 1).await;
 
@@ -467,3 +510,149 @@ async fn build_proposal_cende_incomplete() {
     assert_eq!(fin_receiver.await, Err(oneshot::Canceled));
     drop(sender);
 }
+
+#[tokio::test]
+async fn proposer_follows_validator_set_of_height() {
+    const EPOCH_LENGTH: u64 = 2;
+    let mut batcher = MockBatcherClient::new();
+    batcher.expect_start_height().returning(|_| Ok(()));
+    // The second set is scheduled mid-epoch, so it takes effect from the next epoch.
+    let validator_set_provider = ScheduledValidatorSetProvider::new(
+        Epochs::new(EPOCH_LENGTH).unwrap(),
+        vec![
+            ScheduledValidatorSet {
+                start_height: BlockNumber(0),
+                validators: validator_ids(0..NUM_VALIDATORS),
+            },
+            ScheduledValidatorSet {
+                start_height: BlockNumber(1),
+                validators: validator_ids(NUM_VALIDATORS..NUM_VALIDATORS + 2),
+            },
+        ],
+    );
+    let (mut context, _network) = setup_with_providers(
+        batcher,
+        MockCendeContext::default(),
+        Arc::new(validator_set_provider),
+        Arc::new(FixedGasPriceProvider(DEFAULT_GAS_PRICES)),
+    );
+
+    for height in 0..EPOCH_LENGTH {
+        let height = BlockNumber(height);
+        assert_eq!(context.validators(height).await, validator_ids(0..NUM_VALIDATORS));
+        context.set_height_and_round(height, 0).await;
+        assert_eq!(context.proposer(height, 1), validator_ids(height.0 + 1..height.0 + 2)[0]);
+    }
+
+    let height = BlockNumber(EPOCH_LENGTH);
+    let validators = validator_ids(NUM_VALIDATORS..NUM_VALIDATORS + 2);
+    assert_eq!(context.validators(height).await, validators);
+    context.set_height_and_round(height, 0).await;
+    // Proposer rotation is over the validators of the current height.
+    assert_eq!(context.proposer(height, 0), validators[0]);
+    assert_eq!(context.proposer(height, 1), validators[1]);
+}
+
+fn gas_prices(l1_gas_price: u128) -> GasPrices {
+    let mut gas_prices = DEFAULT_GAS_PRICES;
+    gas_prices.eth_gas_prices.l1_gas_price = NonzeroGasPrice::new(GasPrice(l1_gas_price)).unwrap();
+    gas_prices
+}
+
+// The proposal is validated with the proposer's gas prices, if they are close enough to ours.
+#[test_case(Ok(gas_prices(100)), 105, Some(gas_prices(105)); "within_tolerance")]
+#[test_case(Ok(gas_prices(100)), 120, None; "out_of_tolerance")]
+#[test_case(Err(GasPriceProviderError::NoSamples), 120, None; "rejected_on_error")]
+#[tokio::test]
+async fn validate_proposal_with_gas_prices(
+    own_gas_prices: Result<GasPrices, GasPriceProviderError>,
+    proposed_l1_gas_price: u128,
+    expected_gas_prices: Option<GasPrices>,
+) {
+    let mut batcher = MockBatcherClient::new();
+    batcher
+        .expect_start_height()
+        .withf(|input| input.height == BlockNumber(0))
+        .return_once(|_| Ok(()));
+    let (validate_input_sender, mut validate_input_receiver) = oneshot::channel();
+    batcher.expect_validate_block().return_once(move |input: ValidateBlockInput| {
+        validate_input_sender.send(input).unwrap();
+        Ok(())
+    });
+    // The proposal content is never sent, so the proposal is aborted.
+    batcher
+        .expect_send_proposal_content()
+        .withf(|input| matches!(input.content, SendProposalContent::Abort))
+        .returning(|_| Ok(SendProposalContentResponse { response: ProposalStatus::Aborted }));
+    let mut gas_price_provider = MockGasPriceProvider::new();
+    gas_price_provider.expect_gas_prices().return_once(move || own_gas_prices);
+    let (mut context, _network) = setup_with_providers(
+        batcher,
+        success_cende_ammbassador(),
+        Arc::new(ScheduledValidatorSetProvider::new(
+            Epochs::new(1).unwrap(),
+            vec![ScheduledValidatorSet {
+                start_height: BlockNumber(0),
+                validators: validator_ids(0..NUM_VALIDATORS),
+            }],
+        )),
+        Arc::new(gas_price_provider),
+    );
+
+    context.set_height_and_round(BlockNumber(0), 0).await;
+    let mut init = ProposalInit::default();
+    set_proposal_gas_prices(&mut init, &gas_prices(proposed_l1_gas_price));
+    let (_content_sender, content_receiver) = mpsc::channel(CHANNEL_SIZE);
+    let fin_receiver = context.validate_proposal(init, TIMEOUT, content_receiver).await;
+
+    match expected_gas_prices {
+        Some(expected_gas_prices) => {
+            let validate_input = validate_input_receiver.await.unwrap();
+            assert_eq!(validate_input.block_info.gas_prices, expected_gas_prices);
+        }
+        // The proposal is rejected before it reaches the batcher.
+        None => {
+            assert_eq!(fin_receiver.await, Err(oneshot::Canceled));
+            assert!(matches!(validate_input_receiver.try_recv(), Ok(None)));
+        }
+    }
+}
+
+// While the gas price provider fails, proposals are checked against the last gas prices it gave.
+#[tokio::test]
+async fn validation_gas_prices_fall_back_to_last_known() {
+    let mut own_gas_prices = std::iter::once(Ok(gas_prices(100)))
+        .chain(std::iter::repeat_with(|| Err(GasPriceProviderError::NoSamples)));
+    let mut gas_price_provider = MockGasPriceProvider::new();
+    gas_price_provider.expect_gas_prices().returning(move || own_gas_prices.next().unwrap());
+    let (mut context, _network) = setup_with_providers(
+        MockBatcherClient::new(),
+        success_cende_ammbassador(),
+        Arc::new(ScheduledValidatorSetProvider::new(
+            Epochs::new(1).unwrap(),
+            vec![ScheduledValidatorSet {
+                start_height: BlockNumber(0),
+                validators: validator_ids(0..NUM_VALIDATORS),
+            }],
+        )),
+        Arc::new(gas_price_provider),
+    );
+    let proposal_init = |l1_gas_price| {
+        let mut init = ProposalInit::default();
+        set_proposal_gas_prices(&mut init, &gas_prices(l1_gas_price));
+        init
+    };
+
+    assert_eq!(
+        context.validation_gas_prices(&proposal_init(100)).await.unwrap(),
+        gas_prices(100)
+    );
+    assert_eq!(
+        context.validation_gas_prices(&proposal_init(105)).await.unwrap(),
+        gas_prices(105)
+    );
+    assert!(matches!(
+        context.validation_gas_prices(&proposal_init(120)).await,
+        Err(GasPriceProviderError::ProposedPriceOutOfTolerance { .. })
+    ));
+}
@@ -0,0 +1,187 @@
+//! Resolves the set of validators for each height.
+//!
+//! The validator set can only change at epoch boundaries, so all the heights in an epoch share the
+//! same validators (and therefore the same proposer rotation).
+#[cfg(test)]
+#[path = "validator_set_test.rs"]
+mod validator_set_test;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::Path;
+
+use async_trait::async_trait;
+#[cfg(test)]
+use mockall::automock;
+use papyrus_consensus::types::ValidatorId;
+use serde::{Deserialize, Serialize};
+use starknet_api::abi::abi_utils::get_storage_var_address;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ContractAddress;
+use starknet_api::StarknetApiError;
+use starknet_state_sync_types::communication::{SharedStateSyncClient, StateSyncClientError};
+use starknet_types_core::felt::Felt;
+use thiserror::Error;
+use tracing::debug;
+
+/// The storage variable of the staking contract holding the validators, laid out as a Cairo
+/// storage `Vec`: the length is stored at the variable's address and the element at index `i` at
+/// `pedersen(address, i)`.
+pub const STAKING_VALIDATORS_STORAGE_VAR: &str = "validators";
+
+#[derive(Debug, Error)]
+pub enum ValidatorSetProviderError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse the validator set schedule: {0}")]
+    InvalidSchedule(#[from] serde_json::Error),
+    #[error("Epoch length must be positive.")]
+    ZeroEpochLength,
+    #[error("No validators are scheduled for height {0}.")]
+    NoValidators(BlockNumber),
+    #[error("The staking contract holds an invalid validator set: {0}")]
+    InvalidStakingState(#[from] StarknetApiError),
+    #[error(transparent)]
+    StateSyncClientError(#[from] StateSyncClientError),
+}
+
+pub type ValidatorSetProviderResult<T> = Result<T, ValidatorSetProviderError>;
+
+/// Resolves the validator set for each height. The order of the validators determines the proposer
+/// rotation.
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait ValidatorSetProvider: Send + Sync {
+    /// Returns the validators for `height`. Fails if the state they are derived from isn't known
+    /// yet, in which case the caller may retry.
+    async fn validators(&self, height: BlockNumber)
+    -> ValidatorSetProviderResult<Vec<ValidatorId>>;
+}
+
+/// Splits the heights into epochs of `epoch_length` consecutive blocks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Epochs {
+    epoch_length: u64,
+}
+
+impl Epochs {
+    pub fn new(epoch_length: u64) -> ValidatorSetProviderResult<Self> {
+        if epoch_length == 0 {
+            return Err(ValidatorSetProviderError::ZeroEpochLength);
+        }
+        Ok(Self { epoch_length })
+    }
+
+    /// The first height of the epoch containing `height`.
+    pub fn epoch_start(&self, height: BlockNumber) -> BlockNumber {
+        BlockNumber(height.0 - height.0 % self.epoch_length)
+    }
+}
+
+/// A validator set which takes effect from `start_height` onwards.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ScheduledValidatorSet {
+    pub start_height: BlockNumber,
+    pub validators: Vec<ValidatorId>,
+}
+
+/// Reads the validator sets from a fixed schedule. A set scheduled in the middle of an epoch takes
+/// effect from the following epoch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduledValidatorSetProvider {
+    epochs: Epochs,
+    schedule: BTreeMap<BlockNumber, Vec<ValidatorId>>,
+}
+
+impl ScheduledValidatorSetProvider {
+    pub fn new(epochs: Epochs, schedule: Vec<ScheduledValidatorSet>) -> Self {
+        let schedule =
+            schedule.into_iter().map(|entry| (entry.start_height, entry.validators)).collect();
+        Self { epochs, schedule }
+    }
+
+    /// Reads the schedule from a JSON file containing a list of [`ScheduledValidatorSet`].
+    pub fn from_file(epochs: Epochs, path: &Path) -> ValidatorSetProviderResult<Self> {
+        let schedule: Vec<ScheduledValidatorSet> = serde_json::from_reader(File::open(path)?)?;
+        Ok(Self::new(epochs, schedule))
+    }
+}
+
+#[async_trait]
+impl ValidatorSetProvider for ScheduledValidatorSetProvider {
+    async fn validators(
+        &self,
+        height: BlockNumber,
+    ) -> ValidatorSetProviderResult<Vec<ValidatorId>> {
+        self.schedule
+            .range(..=self.epochs.epoch_start(height))
+            .next_back()
+            .map(|(_, validators)| validators.clone())
+            .filter(|validators| !validators.is_empty())
+            .ok_or(ValidatorSetProviderError::NoValidators(height))
+    }
+}
+
+/// Reads the validator sets from the storage of a staking contract.
+///
+/// The validators of an epoch are those in the staking contract at the end of the previous epoch,
+/// so they are known once the previous epoch is synced. The first epoch uses `initial_validators`.
+pub struct StakingContractValidatorSetProvider {
+    state_sync_client: SharedStateSyncClient,
+    staking_contract_address: ContractAddress,
+    epochs: Epochs,
+    initial_validators: Vec<ValidatorId>,
+}
+
+impl StakingContractValidatorSetProvider {
+    pub fn new(
+        state_sync_client: SharedStateSyncClient,
+        staking_contract_address: ContractAddress,
+        epochs: Epochs,
+        initial_validators: Vec<ValidatorId>,
+    ) -> Self {
+        Self { state_sync_client, staking_contract_address, epochs, initial_validators }
+    }
+
+    async fn read_storage_var(
+        &self,
+        block_number: BlockNumber,
+        args: &[Felt],
+    ) -> ValidatorSetProviderResult<Felt> {
+        Ok(self
+            .state_sync_client
+            .get_storage_at(
+                block_number,
+                self.staking_contract_address,
+                get_storage_var_address(STAKING_VALIDATORS_STORAGE_VAR, args),
+            )
+            .await?)
+    }
+}
+
+#[async_trait]
+impl ValidatorSetProvider for StakingContractValidatorSetProvider {
+    async fn validators(
+        &self,
+        height: BlockNumber,
+    ) -> ValidatorSetProviderResult<Vec<ValidatorId>> {
+        let epoch_start = self.epochs.epoch_start(height);
+        let Some(block_number) = epoch_start.prev() else {
+            return Ok(self.initial_validators.clone());
+        };
+        debug!("Reading the validators for height {height} from the state at {block_number}.");
+
+        let num_validators = self.read_storage_var(block_number, &[]).await?;
+        let num_validators = u64::try_from(num_validators)
+            .map_err(|_| StarknetApiError::OutOfRange { string: num_validators.to_string() })?;
+        let mut validators = Vec::new();
+        for index in 0..num_validators {
+            let validator = self.read_storage_var(block_number, &[Felt::from(index)]).await?;
+            validators.push(ValidatorId::try_from(validator)?);
+        }
+        if validators.is_empty() {
+            return Err(ValidatorSetProviderError::NoValidators(height));
+        }
+        Ok(validators)
+    }
+}
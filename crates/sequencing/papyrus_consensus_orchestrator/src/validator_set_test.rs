@@ -0,0 +1,151 @@
+use std::io::Write;
+use std::sync::Arc;
+
+use mockall::predicate::eq;
+use papyrus_consensus::types::ValidatorId;
+use starknet_api::abi::abi_utils::get_storage_var_address;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ContractAddress;
+use starknet_state_sync_types::communication::{MockStateSyncClient, StateSyncClientError};
+use starknet_state_sync_types::errors::StateSyncError;
+use starknet_types_core::felt::Felt;
+use tempfile::NamedTempFile;
+
+use super::{
+    Epochs,
+    ScheduledValidatorSet,
+    ScheduledValidatorSetProvider,
+    StakingContractValidatorSetProvider,
+    ValidatorSetProvider,
+    ValidatorSetProviderError,
+    STAKING_VALIDATORS_STORAGE_VAR,
+};
+
+const EPOCH_LENGTH: u64 = 10;
+
+fn validators(ids: &[u64]) -> Vec<ValidatorId> {
+    ids.iter().map(|id| ValidatorId::from(*id)).collect()
+}
+
+#[test]
+fn zero_epoch_length() {
+    assert!(matches!(Epochs::new(0), Err(ValidatorSetProviderError::ZeroEpochLength)));
+}
+
+#[tokio::test]
+async fn schedule_changes_at_epoch_boundaries() {
+    let provider = ScheduledValidatorSetProvider::new(
+        Epochs::new(EPOCH_LENGTH).unwrap(),
+        vec![
+            ScheduledValidatorSet { start_height: BlockNumber(0), validators: validators(&[1, 2]) },
+            // Takes effect from the start of the next epoch.
+            ScheduledValidatorSet { start_height: BlockNumber(15), validators: validators(&[3]) },
+            ScheduledValidatorSet {
+                start_height: BlockNumber(30),
+                validators: validators(&[4, 5, 6]),
+            },
+        ],
+    );
+
+    for (height, expected) in [
+        (0, validators(&[1, 2])),
+        (15, validators(&[1, 2])),
+        (19, validators(&[1, 2])),
+        (20, validators(&[3])),
+        (29, validators(&[3])),
+        (30, validators(&[4, 5, 6])),
+        (1000, validators(&[4, 5, 6])),
+    ] {
+        assert_eq!(provider.validators(BlockNumber(height)).await.unwrap(), expected, "{height}");
+    }
+}
+
+#[tokio::test]
+async fn schedule_without_validators_for_height() {
+    let provider = ScheduledValidatorSetProvider::new(
+        Epochs::new(EPOCH_LENGTH).unwrap(),
+        vec![ScheduledValidatorSet { start_height: BlockNumber(10), validators: validators(&[1]) }],
+    );
+    assert!(matches!(
+        provider.validators(BlockNumber(5)).await,
+        Err(ValidatorSetProviderError::NoValidators(BlockNumber(5)))
+    ));
+}
+
+#[tokio::test]
+async fn schedule_from_file() {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(
+        br#"[
+            {"start_height": 0, "validators": ["0x1", "0x2"]},
+            {"start_height": 10, "validators": ["0x3"]}
+        ]"#,
+    )
+    .unwrap();
+    let provider =
+        ScheduledValidatorSetProvider::from_file(Epochs::new(EPOCH_LENGTH).unwrap(), file.path())
+            .unwrap();
+
+    assert_eq!(provider.validators(BlockNumber(9)).await.unwrap(), validators(&[1, 2]));
+    assert_eq!(provider.validators(BlockNumber(10)).await.unwrap(), validators(&[3]));
+}
+
+const STAKING_CONTRACT: u64 = 0x1234;
+
+fn expect_staking_storage(
+    state_sync_client: &mut MockStateSyncClient,
+    block_number: BlockNumber,
+    validator_ids: &[u64],
+) {
+    let len_key = get_storage_var_address(STAKING_VALIDATORS_STORAGE_VAR, &[]);
+    let num_validators = Felt::from(validator_ids.len());
+    state_sync_client
+        .expect_get_storage_at()
+        .with(eq(block_number), eq(ContractAddress::from(STAKING_CONTRACT)), eq(len_key))
+        .returning(move |_, _, _| Ok(num_validators));
+    for (index, id) in validator_ids.iter().enumerate() {
+        let key = get_storage_var_address(STAKING_VALIDATORS_STORAGE_VAR, &[Felt::from(index)]);
+        let id = Felt::from(*id);
+        state_sync_client
+            .expect_get_storage_at()
+            .with(eq(block_number), eq(ContractAddress::from(STAKING_CONTRACT)), eq(key))
+            .returning(move |_, _, _| Ok(id));
+    }
+}
+
+#[tokio::test]
+async fn staking_contract_reads_previous_epoch_state() {
+    let mut state_sync_client = MockStateSyncClient::new();
+    expect_staking_storage(&mut state_sync_client, BlockNumber(EPOCH_LENGTH - 1), &[7, 8, 9]);
+    let provider = StakingContractValidatorSetProvider::new(
+        Arc::new(state_sync_client),
+        ContractAddress::from(STAKING_CONTRACT),
+        Epochs::new(EPOCH_LENGTH).unwrap(),
+        validators(&[1]),
+    );
+
+    // The first epoch has no previous state.
+    assert_eq!(provider.validators(BlockNumber(3)).await.unwrap(), validators(&[1]));
+    for height in EPOCH_LENGTH..2 * EPOCH_LENGTH {
+        assert_eq!(provider.validators(BlockNumber(height)).await.unwrap(), validators(&[7, 8, 9]));
+    }
+}
+
+#[tokio::test]
+async fn staking_contract_state_not_synced() {
+    let mut state_sync_client = MockStateSyncClient::new();
+    state_sync_client.expect_get_storage_at().returning(|block_number, _, _| {
+        Err(StateSyncClientError::StateSyncError(StateSyncError::BlockNotFound(block_number)))
+    });
+    let provider = StakingContractValidatorSetProvider::new(
+        Arc::new(state_sync_client),
+        ContractAddress::from(STAKING_CONTRACT),
+        Epochs::new(EPOCH_LENGTH).unwrap(),
+        validators(&[1]),
+    );
+
+    assert!(matches!(
+        provider.validators(BlockNumber(EPOCH_LENGTH)).await,
+        Err(ValidatorSetProviderError::StateSyncClientError(_))
+    ));
+}
@@ -17,6 +17,7 @@ use crate::core::{
 use crate::data_availability::DataAvailabilityMode;
 use crate::execution_resources::ExecutionResources;
 use crate::hash::StarkHash;
+use crate::serde_utils::{BytesAsHex, PrefixedBytesAsHex};
 use crate::transaction::fields::{
     AccountDeploymentData,
     Calldata,
@@ -833,6 +834,26 @@ macro_rules! tx_hash {
     };
 }
 
+/// The hash of a transaction on L1, e.g., of a transaction that sent messages to L2. Unlike
+/// [`TransactionHash`], it is not necessarily a valid field element.
+#[derive(
+    Debug, Default, Copy, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord,
+)]
+#[serde(from = "PrefixedBytesAsHex<32_usize>", into = "PrefixedBytesAsHex<32_usize>")]
+pub struct L1TransactionHash(pub [u8; 32]);
+
+impl From<PrefixedBytesAsHex<32_usize>> for L1TransactionHash {
+    fn from(val: PrefixedBytesAsHex<32_usize>) -> Self {
+        Self(val.0)
+    }
+}
+
+impl From<L1TransactionHash> for PrefixedBytesAsHex<32_usize> {
+    fn from(val: L1TransactionHash) -> Self {
+        BytesAsHex(val.0)
+    }
+}
+
 /// A transaction version.
 #[derive(
     Debug,
@@ -1,6 +1,6 @@
 use rstest::{fixture, rstest};
 
-use super::Transaction;
+use super::{L1TransactionHash, Transaction};
 use crate::block::NonzeroGasPrice;
 use crate::core::ChainId;
 use crate::executable_transaction::{
@@ -90,3 +90,21 @@ fn test_l1_handler_executable_transaction_conversion(
 
     verify_transaction_conversion(&transaction_data.transaction, expected_executable_tx);
 }
+
+#[test]
+fn l1_transaction_hash_serde() {
+    let mut bytes = [0_u8; 32];
+    bytes[0] = 0xff;
+    bytes[31] = 0x01;
+    let l1_tx_hash = L1TransactionHash(bytes);
+    let serialized = serde_json::to_string(&l1_tx_hash).unwrap();
+    assert_eq!(serialized, format!(r#""0xff{}01""#, "0".repeat(60)));
+
+    let restored = serde_json::from_str::<L1TransactionHash>(&serialized).unwrap();
+    assert_eq!(restored, l1_tx_hash);
+
+    // Leading zeros can be omitted.
+    let mut bytes = [0_u8; 32];
+    bytes[31] = 0x01;
+    assert_eq!(serde_json::from_str::<L1TransactionHash>(r#""0x1""#).unwrap().0, bytes);
+}
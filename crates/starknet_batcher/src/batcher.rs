@@ -5,7 +5,7 @@ use blockifier::state::contract_class_manager::ContractClassManager;
 #[cfg(test)]
 use mockall::automock;
 use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
-use starknet_api::block::BlockNumber;
+use starknet_api::block::{BlockInfo, BlockNumber};
 use starknet_api::core::{ContractAddress, Nonce};
 use starknet_api::executable_transaction::Transaction;
 use starknet_api::state::ThinStateDiff;
@@ -15,9 +15,11 @@ use starknet_batcher_types::batcher_types::{
     DecisionReachedInput,
     DecisionReachedResponse,
     GetHeightResponse,
+    GetPendingProposalResponse,
     GetProposalContent,
     GetProposalContentInput,
     GetProposalContentResponse,
+    PendingProposal,
     ProposalCommitment,
     ProposalId,
     ProposalStatus,
@@ -29,7 +31,7 @@ use starknet_batcher_types::batcher_types::{
     ValidateBlockInput,
 };
 use starknet_batcher_types::errors::BatcherError;
-use starknet_l1_provider_types::SharedL1ProviderClient;
+use starknet_l1_provider_types::{SessionState, SharedL1ProviderClient};
 use starknet_mempool_types::communication::SharedMempoolClient;
 use starknet_mempool_types::mempool_types::CommitBlockArgs;
 use starknet_sequencer_infra::component_definitions::ComponentStarter;
@@ -45,6 +47,7 @@ use crate::block_builder::{
     BlockBuilderTrait,
     BlockExecutionArtifacts,
     BlockMetadata,
+    SharedPendingProposal,
 };
 use crate::config::BatcherConfig;
 use crate::transaction_provider::{ProposeTransactionProvider, ValidateTransactionProvider};
@@ -91,6 +94,10 @@ pub struct Batcher {
     // Each stream is kept until SendProposalContent::Finish/Abort is received, or a new height is
     // started.
     validate_tx_streams: HashMap<ProposalId, InputStreamSender>,
+
+    // The content of the latest proposal that started execution in the current height, served as
+    // the pending block. Kept after the execution is done, until the height is committed.
+    pending_proposal: Option<(ProposalId, SharedPendingProposal)>,
 }
 
 impl Batcher {
@@ -115,6 +122,7 @@ impl Batcher {
             executed_proposals: Arc::new(Mutex::new(HashMap::new())),
             propose_tx_streams: HashMap::new(),
             validate_tx_streams: HashMap::new(),
+            pending_proposal: None,
         }
     }
 
@@ -159,6 +167,7 @@ impl Batcher {
         )?;
 
         self.set_active_proposal(propose_block_input.proposal_id).await?;
+        self.notify_l1_provider_block_start(SessionState::Propose, active_height).await;
 
         let tx_provider = ProposeTransactionProvider::new(
             self.mempool_client.clone(),
@@ -168,6 +177,10 @@ impl Batcher {
 
         // A channel to receive the transactions included in the proposed block.
         let (output_tx_sender, output_tx_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let pending_proposal = self.track_pending_proposal(
+            propose_block_input.proposal_id,
+            propose_block_input.block_info.clone(),
+        );
 
         let (block_builder, abort_signal_sender) = self
             .block_builder_factory
@@ -182,6 +195,7 @@ impl Batcher {
                 },
                 Box::new(tx_provider),
                 Some(output_tx_sender),
+                pending_proposal,
             )
             .map_err(|_| BatcherError::InternalError)?;
 
@@ -205,6 +219,7 @@ impl Batcher {
         )?;
 
         self.set_active_proposal(validate_block_input.proposal_id).await?;
+        self.notify_l1_provider_block_start(SessionState::Validate, active_height).await;
 
         // A channel to send the transactions to include in the block being validated.
         let (input_tx_sender, input_tx_receiver) =
@@ -214,6 +229,10 @@ impl Batcher {
             tx_receiver: input_tx_receiver,
             l1_provider_client: self.l1_provider_client.clone(),
         };
+        let pending_proposal = self.track_pending_proposal(
+            validate_block_input.proposal_id,
+            validate_block_input.block_info.clone(),
+        );
 
         let (block_builder, abort_signal_sender) = self
             .block_builder_factory
@@ -228,6 +247,7 @@ impl Batcher {
                 },
                 Box::new(tx_provider),
                 None,
+                pending_proposal,
             )
             .map_err(|_| BatcherError::InternalError)?;
 
@@ -263,6 +283,7 @@ impl Batcher {
         self.executed_proposals.lock().await.clear();
         self.propose_tx_streams.clear();
         self.validate_tx_streams.clear();
+        self.pending_proposal = None;
     }
 
     async fn handle_send_txs_request(
@@ -326,6 +347,9 @@ impl Batcher {
                 .insert(proposal_id, Err(Arc::new(BlockBuilderError::Aborted)));
         }
         self.validate_tx_streams.remove(&proposal_id);
+        if self.pending_proposal.as_ref().is_some_and(|(id, _)| *id == proposal_id) {
+            self.pending_proposal = None;
+        }
         Ok(SendProposalContentResponse { response: ProposalStatus::Aborted })
     }
 
@@ -377,6 +401,20 @@ impl Batcher {
         Ok(GetProposalContentResponse { content: GetProposalContent::Finished(commitment) })
     }
 
+    #[instrument(skip(self), err)]
+    pub async fn get_pending_proposal(&self) -> BatcherResult<GetPendingProposalResponse> {
+        let Some((proposal_id, pending_proposal)) = &self.pending_proposal else {
+            return Ok(GetPendingProposalResponse { pending_proposal: None });
+        };
+        // A proposal that failed will never become a block.
+        if let Some(Err(_)) = self.executed_proposals.lock().await.get(proposal_id) {
+            return Ok(GetPendingProposalResponse { pending_proposal: None });
+        }
+        let pending_proposal =
+            pending_proposal.lock().expect("Pending proposal lock should not be poisoned.").clone();
+        Ok(GetPendingProposalResponse { pending_proposal: Some(pending_proposal) })
+    }
+
     #[instrument(skip(self), err)]
     pub async fn add_sync_block(&mut self, sync_block: SyncBlock) -> BatcherResult<()> {
         if let Some(height) = self.active_height {
@@ -429,14 +467,23 @@ impl Batcher {
         address_to_nonce: HashMap<ContractAddress, Nonce>,
         tx_hashes: HashSet<TransactionHash>,
     ) -> BatcherResult<()> {
-        info!("Committing block at height {} and notifying mempool of the block.", height);
+        info!(
+            "Committing block at height {} and notifying mempool and L1 provider of the block.",
+            height
+        );
         trace!("Transactions: {:#?}, State diff: {:#?}.", tx_hashes, state_diff);
+        self.pending_proposal = None;
 
-        // Commit the proposal to the storage and notify the mempool.
+        // Commit the proposal to the storage and notify the mempool and the L1 provider.
         self.storage_writer.commit_proposal(height, state_diff).map_err(|err| {
             error!("Failed to commit proposal to storage: {}", err);
             BatcherError::InternalError
         })?;
+        let l1_provider_result =
+            self.l1_provider_client.commit_block(tx_hashes.iter().copied().collect(), height).await;
+        if let Err(l1_provider_err) = l1_provider_result {
+            error!("Failed to commit block to L1 provider: {}", l1_provider_err);
+        }
         let mempool_result =
             self.mempool_client.commit_block(CommitBlockArgs { address_to_nonce, tx_hashes }).await;
 
@@ -448,6 +495,29 @@ impl Batcher {
         Ok(())
     }
 
+    async fn notify_l1_provider_block_start(&self, state: SessionState, height: BlockNumber) {
+        if let Err(err) = self.l1_provider_client.start_block(state, height).await {
+            error!("Failed to start {:?} of height {} in L1 provider: {}", state, height, err);
+        }
+    }
+
+    // Replaces the pending proposal with a new, empty one for the given proposal. Returns None,
+    // and drops the previous pending proposal, if tracking it is disabled.
+    fn track_pending_proposal(
+        &mut self,
+        proposal_id: ProposalId,
+        block_info: BlockInfo,
+    ) -> Option<SharedPendingProposal> {
+        if !self.config.track_pending_proposal {
+            self.pending_proposal = None;
+            return None;
+        }
+        let pending_proposal =
+            Arc::new(std::sync::Mutex::new(PendingProposal { block_info, ..Default::default() }));
+        self.pending_proposal = Some((proposal_id, pending_proposal.clone()));
+        Some(pending_proposal)
+    }
+
     async fn is_active(&self, proposal_id: ProposalId) -> bool {
         *self.active_proposal.lock().await == Some(proposal_id)
     }
@@ -15,6 +15,7 @@ use starknet_api::{contract_address, nonce, tx_hash};
 use starknet_batcher_types::batcher_types::{
     DecisionReachedInput,
     GetHeightResponse,
+    GetPendingProposalResponse,
     GetProposalContent,
     GetProposalContentInput,
     GetProposalContentResponse,
@@ -87,10 +88,13 @@ impl Default for MockDependencies {
     fn default() -> Self {
         let mut storage_reader = MockBatcherStorageReaderTrait::new();
         storage_reader.expect_height().returning(|| Ok(INITIAL_HEIGHT));
+        let mut l1_provider_client = MockL1ProviderClient::new();
+        l1_provider_client.expect_start_block().returning(|_, _| Ok(()));
+        l1_provider_client.expect_commit_block().returning(|_, _| Ok(()));
         Self {
             storage_reader,
             storage_writer: MockBatcherStorageWriterTrait::new(),
-            l1_provider_client: MockL1ProviderClient::new(),
+            l1_provider_client,
             mempool_client: MockMempoolClient::new(),
             block_builder_factory: MockBlockBuilderFactoryTrait::new(),
         }
@@ -117,7 +121,7 @@ fn mock_create_builder_for_validate_block(
     build_block_result: BlockBuilderResult<BlockExecutionArtifacts>,
 ) {
     block_builder_factory.expect_create_block_builder().times(1).return_once(
-        |_, _, tx_provider, _| {
+        |_, _, tx_provider, _, _| {
             let block_builder = FakeValidateBlockBuilder {
                 tx_provider,
                 build_block_result: Some(build_block_result),
@@ -133,9 +137,10 @@ fn mock_create_builder_for_propose_block(
     build_block_result: BlockBuilderResult<BlockExecutionArtifacts>,
 ) {
     block_builder_factory.expect_create_block_builder().times(1).return_once(
-        move |_, _, _, output_content_sender| {
+        move |_, _, _, output_content_sender, pending_proposal| {
             let block_builder = FakeProposeBlockBuilder {
                 output_content_sender: output_content_sender.unwrap(),
+                pending_proposal,
                 output_txs,
                 build_block_result: Some(build_block_result),
             };
@@ -505,6 +510,16 @@ async fn add_sync_block() {
         }))
         .returning(|_| Ok(()));
 
+    mock_dependencies.l1_provider_client = MockL1ProviderClient::new();
+    mock_dependencies
+        .l1_provider_client
+        .expect_commit_block()
+        .times(1)
+        .withf(|tx_hashes, height| {
+            *height == INITIAL_HEIGHT && HashSet::from_iter(tx_hashes.clone()) == test_tx_hashes()
+        })
+        .returning(|_, _| Ok(()));
+
     let mut batcher = create_batcher(mock_dependencies);
 
     let sync_block = SyncBlock {
@@ -568,6 +583,78 @@ async fn decision_reached() {
     assert_eq!(response.state_diff, expected_artifacts.state_diff());
 }
 
+#[rstest]
+#[tokio::test]
+async fn get_pending_proposal() {
+    let mut mock_dependencies = MockDependencies::default();
+    mock_dependencies.mempool_client.expect_commit_block().times(1).returning(|_| Ok(()));
+    mock_dependencies.storage_writer.expect_commit_proposal().times(1).returning(|_, _| Ok(()));
+    let expected_txs = test_txs(0..3);
+    mock_create_builder_for_propose_block(
+        &mut mock_dependencies.block_builder_factory,
+        expected_txs.clone(),
+        Ok(BlockExecutionArtifacts::create_for_testing()),
+    );
+
+    let mut batcher = create_batcher(mock_dependencies);
+    batcher.config.track_pending_proposal = true;
+    batcher.start_height(StartHeightInput { height: INITIAL_HEIGHT }).await.unwrap();
+    let no_pending_proposal = GetPendingProposalResponse { pending_proposal: None };
+    assert_eq!(batcher.get_pending_proposal().await, Ok(no_pending_proposal.clone()));
+
+    batcher.propose_block(propose_block_input(PROPOSAL_ID)).await.unwrap();
+    batcher.await_active_proposal().await;
+    let pending_proposal = batcher.get_pending_proposal().await.unwrap().pending_proposal.unwrap();
+    assert_eq!(pending_proposal.block_info, propose_block_input(PROPOSAL_ID).block_info);
+    assert_eq!(pending_proposal.transactions, expected_txs);
+    assert_eq!(pending_proposal.transaction_outputs.len(), expected_txs.len());
+
+    // Once the proposal is committed it is no longer pending.
+    batcher.decision_reached(DecisionReachedInput { proposal_id: PROPOSAL_ID }).await.unwrap();
+    assert_eq!(batcher.get_pending_proposal().await, Ok(no_pending_proposal));
+}
+
+#[rstest]
+#[tokio::test]
+async fn get_pending_proposal_when_not_tracked() {
+    let mut block_builder_factory = MockBlockBuilderFactoryTrait::new();
+    block_builder_factory.expect_create_block_builder().times(1).return_once(
+        |_, _, _, output_content_sender, pending_proposal| {
+            assert!(pending_proposal.is_none());
+            let block_builder = FakeProposeBlockBuilder {
+                output_content_sender: output_content_sender.unwrap(),
+                pending_proposal,
+                output_txs: test_txs(0..3),
+                build_block_result: Some(Ok(BlockExecutionArtifacts::create_for_testing())),
+            };
+            Ok((Box::new(block_builder), abort_signal_sender()))
+        },
+    );
+
+    let mut batcher =
+        create_batcher(MockDependencies { block_builder_factory, ..Default::default() });
+    batcher.start_height(StartHeightInput { height: INITIAL_HEIGHT }).await.unwrap();
+    batcher.propose_block(propose_block_input(PROPOSAL_ID)).await.unwrap();
+    batcher.await_active_proposal().await;
+
+    assert_eq!(
+        batcher.get_pending_proposal().await,
+        Ok(GetPendingProposalResponse { pending_proposal: None })
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn get_pending_proposal_of_failed_proposal() {
+    let mut batcher = batcher_with_active_validate_block(Err(BUILD_BLOCK_FAIL_ON_ERROR)).await;
+    batcher.await_active_proposal().await;
+
+    assert_eq!(
+        batcher.get_pending_proposal().await,
+        Ok(GetPendingProposalResponse { pending_proposal: None })
+    );
+}
+
 #[rstest]
 #[tokio::test]
 async fn decision_reached_no_executed_proposal() {
@@ -1,4 +1,5 @@
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 
 use async_trait::async_trait;
 use blockifier::blockifier::config::TransactionExecutorConfig;
@@ -10,6 +11,7 @@ use blockifier::blockifier::transaction_executor::{
 };
 use blockifier::bouncer::{BouncerConfig, BouncerWeights};
 use blockifier::context::{BlockContext, ChainInfo};
+use blockifier::execution::call_info::{OrderedEvent, OrderedL2ToL1Message};
 use blockifier::state::cached_state::CommitmentStateDiff;
 use blockifier::state::contract_class_manager::ContractClassManager;
 use blockifier::state::errors::StateError;
@@ -30,8 +32,12 @@ use starknet_api::core::{ContractAddress, Nonce};
 use starknet_api::executable_transaction::Transaction;
 use starknet_api::execution_resources::GasAmount;
 use starknet_api::state::ThinStateDiff;
-use starknet_api::transaction::TransactionHash;
-use starknet_batcher_types::batcher_types::ProposalCommitment;
+use starknet_api::transaction::{Event, MessageToL1, TransactionHash};
+use starknet_batcher_types::batcher_types::{
+    PendingProposal,
+    PendingTransactionOutput,
+    ProposalCommitment,
+};
 use thiserror::Error;
 use tracing::{debug, error, info, trace};
 
@@ -110,6 +116,10 @@ impl BlockExecutionArtifacts {
     }
 }
 
+/// The content of the block being built, updated by the block builder after every executed chunk of
+/// transactions and read by the batcher to serve the pending proposal.
+pub type SharedPendingProposal = Arc<Mutex<PendingProposal>>;
+
 /// The BlockBuilderTrait is responsible for building a new block from transactions provided by the
 /// tx_provider. The block building will stop at time deadline.
 /// The transactions that were added to the block will be streamed to the output_content_sender.
@@ -129,6 +139,7 @@ pub struct BlockBuilder {
     executor: Box<dyn TransactionExecutorTrait>,
     tx_provider: Box<dyn TransactionProvider>,
     output_content_sender: Option<tokio::sync::mpsc::UnboundedSender<Transaction>>,
+    pending_proposal: Option<SharedPendingProposal>,
     abort_signal_receiver: tokio::sync::oneshot::Receiver<()>,
 
     // Parameters to configure the block builder behavior.
@@ -141,6 +152,7 @@ impl BlockBuilder {
         executor: Box<dyn TransactionExecutorTrait>,
         tx_provider: Box<dyn TransactionProvider>,
         output_content_sender: Option<tokio::sync::mpsc::UnboundedSender<Transaction>>,
+        pending_proposal: Option<SharedPendingProposal>,
         abort_signal_receiver: tokio::sync::oneshot::Receiver<()>,
         tx_chunk_size: usize,
         execution_params: BlockBuilderExecutionParams,
@@ -149,6 +161,7 @@ impl BlockBuilder {
             executor,
             tx_provider,
             output_content_sender,
+            pending_proposal,
             abort_signal_receiver,
             tx_chunk_size,
             execution_params,
@@ -201,9 +214,17 @@ impl BlockBuilderTrait for BlockBuilder {
                 &mut l2_gas_used,
                 &mut execution_infos,
                 &self.output_content_sender,
+                &self.pending_proposal,
                 self.execution_params.fail_on_err,
             )
             .await?;
+            if let Some(pending_proposal) = &self.pending_proposal {
+                let pending_state_diff = self.executor.pending_state_diff()?;
+                pending_proposal
+                    .lock()
+                    .expect("Pending proposal lock should not be poisoned.")
+                    .state_diff = pending_state_diff;
+            }
         }
         let (commitment_state_diff, visited_segments_mapping, bouncer_weights) =
             self.executor.close_block()?;
@@ -224,12 +245,22 @@ async fn collect_execution_results_and_stream_txs(
     l2_gas_used: &mut GasAmount,
     execution_infos: &mut IndexMap<TransactionHash, TransactionExecutionInfo>,
     output_content_sender: &Option<tokio::sync::mpsc::UnboundedSender<Transaction>>,
+    pending_proposal: &Option<SharedPendingProposal>,
     fail_on_err: bool,
 ) -> BlockBuilderResult<bool> {
     for (input_tx, result) in tx_chunk.into_iter().zip(results.into_iter()) {
         match result {
             Ok(tx_execution_info) => {
                 *l2_gas_used += tx_execution_info.receipt.gas.l2_gas;
+                if let Some(pending_proposal) = pending_proposal {
+                    let mut pending_proposal = pending_proposal
+                        .lock()
+                        .expect("Pending proposal lock should not be poisoned.");
+                    pending_proposal.transactions.push(input_tx.clone());
+                    pending_proposal
+                        .transaction_outputs
+                        .push(pending_transaction_output(&tx_execution_info));
+                }
                 execution_infos.insert(input_tx.tx_hash(), tx_execution_info);
                 if let Some(output_content_sender) = output_content_sender {
                     output_content_sender.send(input_tx)?;
@@ -257,6 +288,47 @@ async fn collect_execution_results_and_stream_txs(
     Ok(false)
 }
 
+fn pending_transaction_output(
+    execution_info: &TransactionExecutionInfo,
+) -> PendingTransactionOutput {
+    let mut events = Vec::new();
+    let mut messages_sent = Vec::new();
+    // The order of the events and messages is kept within each of the validate, execute and fee
+    // transfer calls, including their inner calls.
+    for call_info in execution_info.non_optional_call_infos() {
+        let mut ordered_events = Vec::new();
+        let mut ordered_messages = Vec::new();
+        for inner_call_info in call_info.iter() {
+            let from_address = inner_call_info.call.storage_address;
+            ordered_events.extend(inner_call_info.execution.events.iter().map(
+                |OrderedEvent { order, event }| {
+                    (*order, Event { from_address, content: event.clone() })
+                },
+            ));
+            ordered_messages.extend(inner_call_info.execution.l2_to_l1_messages.iter().map(
+                |OrderedL2ToL1Message { order, message }| {
+                    let message = MessageToL1 {
+                        from_address,
+                        to_address: message.to_address,
+                        payload: message.payload.clone(),
+                    };
+                    (*order, message)
+                },
+            ));
+        }
+        ordered_events.sort_by_key(|(order, _)| *order);
+        ordered_messages.sort_by_key(|(order, _)| *order);
+        events.extend(ordered_events.into_iter().map(|(_, event)| event));
+        messages_sent.extend(ordered_messages.into_iter().map(|(_, message)| message));
+    }
+    PendingTransactionOutput {
+        actual_fee: execution_info.receipt.fee,
+        events,
+        messages_sent,
+        revert_reason: execution_info.revert_error.as_ref().map(|error| error.to_string()),
+    }
+}
+
 pub struct BlockMetadata {
     pub block_info: BlockInfo,
     pub retrospective_block_hash: Option<BlockHashAndNumber>,
@@ -274,6 +346,7 @@ pub trait BlockBuilderFactoryTrait: Send + Sync {
         execution_params: BlockBuilderExecutionParams,
         tx_provider: Box<dyn TransactionProvider>,
         output_content_sender: Option<tokio::sync::mpsc::UnboundedSender<Transaction>>,
+        pending_proposal: Option<SharedPendingProposal>,
     ) -> BlockBuilderResult<(Box<dyn BlockBuilderTrait>, AbortSignalSender)>;
 }
 
@@ -365,6 +438,7 @@ impl BlockBuilderFactoryTrait for BlockBuilderFactory {
         execution_params: BlockBuilderExecutionParams,
         tx_provider: Box<dyn TransactionProvider>,
         output_content_sender: Option<tokio::sync::mpsc::UnboundedSender<Transaction>>,
+        pending_proposal: Option<SharedPendingProposal>,
     ) -> BlockBuilderResult<(Box<dyn BlockBuilderTrait>, AbortSignalSender)> {
         let executor = self.preprocess_and_create_transaction_executor(block_metadata)?;
         let (abort_signal_sender, abort_signal_receiver) = tokio::sync::oneshot::channel();
@@ -372,6 +446,7 @@ impl BlockBuilderFactoryTrait for BlockBuilderFactory {
             Box::new(executor),
             tx_provider,
             output_content_sender,
+            pending_proposal,
             abort_signal_receiver,
             self.block_builder_config.tx_chunk_size,
             execution_params,
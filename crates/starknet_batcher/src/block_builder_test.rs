@@ -12,9 +12,11 @@ use mockall::Sequence;
 use rstest::rstest;
 use starknet_api::executable_transaction::Transaction;
 use starknet_api::execution_resources::{GasAmount, GasVector};
+use starknet_api::state::ThinStateDiff;
 use starknet_api::transaction::fields::Fee;
 use starknet_api::transaction::TransactionHash;
-use starknet_api::tx_hash;
+use starknet_api::{class_hash, contract_address, nonce, tx_hash};
+use starknet_batcher_types::batcher_types::{PendingProposal, PendingTransactionOutput};
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 
 use crate::block_builder::{
@@ -25,6 +27,7 @@ use crate::block_builder::{
     BlockBuilderTrait,
     BlockExecutionArtifacts,
     FailOnErrorCause,
+    SharedPendingProposal,
 };
 use crate::test_utils::test_txs;
 use crate::transaction_executor::MockTransactionExecutorTrait;
@@ -395,6 +398,7 @@ async fn run_build_block(
         Box::new(mock_transaction_executor),
         Box::new(tx_provider),
         output_sender,
+        None,
         abort_receiver,
         TX_CHUNK_SIZE,
         BlockBuilderExecutionParams { deadline, fail_on_err },
@@ -436,6 +440,53 @@ async fn test_build_block(#[case] test_expectations: TestExpectations) {
     .await;
 }
 
+#[tokio::test]
+async fn test_pending_proposal() {
+    let input_txs = test_txs(0..3);
+    let (mut mock_transaction_executor, _) = one_chunk_mock_executor(&input_txs, input_txs.len());
+    let pending_state_diff = ThinStateDiff {
+        nonces: indexmap! {contract_address!("0x1") => nonce!(3_u64)},
+        replaced_classes: indexmap! {contract_address!("0x2") => class_hash!("0x20")},
+        ..Default::default()
+    };
+    let pending_state_diff_cloned = pending_state_diff.clone();
+    mock_transaction_executor
+        .expect_pending_state_diff()
+        .times(1)
+        .return_once(move || Ok(pending_state_diff_cloned));
+    let mock_tx_provider = mock_tx_provider_stream_done(input_txs.clone());
+
+    let pending_proposal = SharedPendingProposal::default();
+    let (_abort_sender, abort_receiver) = tokio::sync::oneshot::channel();
+    let deadline = tokio::time::Instant::now()
+        + tokio::time::Duration::from_secs(BLOCK_GENERATION_DEADLINE_SECS);
+    let mut block_builder = BlockBuilder::new(
+        Box::new(mock_transaction_executor),
+        Box::new(mock_tx_provider),
+        None,
+        Some(pending_proposal.clone()),
+        abort_receiver,
+        TX_CHUNK_SIZE,
+        BlockBuilderExecutionParams { deadline, fail_on_err: true },
+    );
+    block_builder.build_block().await.unwrap();
+
+    let expected_output = PendingTransactionOutput {
+        revert_reason: execution_info().revert_error.map(|error| error.to_string()),
+        ..Default::default()
+    };
+    let pending_proposal = pending_proposal.lock().unwrap().clone();
+    assert_eq!(
+        pending_proposal,
+        PendingProposal {
+            transactions: input_txs,
+            transaction_outputs: vec![expected_output; 3],
+            state_diff: pending_state_diff,
+            ..Default::default()
+        }
+    );
+}
+
 #[tokio::test]
 async fn test_validate_block() {
     let input_txs = test_txs(0..3);
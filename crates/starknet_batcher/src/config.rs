@@ -18,6 +18,7 @@ pub struct BatcherConfig {
     pub block_builder_config: BlockBuilderConfig,
     pub contract_class_manager_config: ContractClassManagerConfig,
     pub max_l1_handler_txs_per_block_proposal: usize,
+    pub track_pending_proposal: bool,
 }
 
 impl SerializeConfig for BatcherConfig {
@@ -43,6 +44,14 @@ impl SerializeConfig for BatcherConfig {
                 "The maximum number of L1 handler transactions to include in a block proposal.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "track_pending_proposal",
+                &self.track_pending_proposal,
+                "Whether to serve the proposal being built as the pending proposal. Tracking it \
+                 copies every executed transaction and recomputes the state diff after every \
+                 chunk of transactions.",
+                ParamPrivacyInput::Public,
+            ),
         ]);
         dump.append(&mut append_sub_config_name(self.storage.dump(), "storage"));
         dump.append(&mut append_sub_config_name(
@@ -76,6 +85,7 @@ impl Default for BatcherConfig {
             block_builder_config: BlockBuilderConfig::default(),
             contract_class_manager_config: ContractClassManagerConfig::default(),
             max_l1_handler_txs_per_block_proposal: 3,
+            track_pending_proposal: false,
         }
     }
 }
@@ -10,6 +10,8 @@ pub mod fee_market;
 #[cfg(test)]
 mod test_utils;
 mod transaction_executor;
+#[cfg(test)]
+mod transaction_executor_test;
 mod transaction_provider;
 #[cfg(test)]
 mod transaction_provider_test;
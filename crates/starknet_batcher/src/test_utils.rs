@@ -11,7 +11,12 @@ use starknet_api::test_utils::invoke::{executable_invoke_tx, InvokeTxArgs};
 use starknet_api::{class_hash, contract_address, nonce, tx_hash};
 use tokio::sync::mpsc::UnboundedSender;
 
-use crate::block_builder::{BlockBuilderResult, BlockBuilderTrait, BlockExecutionArtifacts};
+use crate::block_builder::{
+    BlockBuilderResult,
+    BlockBuilderTrait,
+    BlockExecutionArtifacts,
+    SharedPendingProposal,
+};
 use crate::transaction_provider::{NextTxs, TransactionProvider};
 
 // A fake block builder for validate flow, that fetches transactions from the transaction provider
@@ -40,9 +45,10 @@ impl BlockBuilderTrait for FakeValidateBlockBuilder {
 }
 
 // A fake block builder for propose flow, that sends the given transactions to the output content
-// sender.
+// sender and adds them to the pending proposal.
 pub(crate) struct FakeProposeBlockBuilder {
     pub output_content_sender: UnboundedSender<Transaction>,
+    pub pending_proposal: Option<SharedPendingProposal>,
     pub output_txs: Vec<Transaction>,
     pub build_block_result: Option<BlockBuilderResult<BlockExecutionArtifacts>>,
 }
@@ -53,6 +59,12 @@ impl BlockBuilderTrait for FakeProposeBlockBuilder {
         for tx in &self.output_txs {
             self.output_content_sender.send(tx.clone()).unwrap();
         }
+        if let Some(pending_proposal) = &self.pending_proposal {
+            let mut pending_proposal = pending_proposal.lock().unwrap();
+            pending_proposal.transactions.extend(self.output_txs.iter().cloned());
+            let n_txs = pending_proposal.transactions.len();
+            pending_proposal.transaction_outputs.resize(n_txs, Default::default());
+        }
 
         // build_block should be called only once, so we can safely take the result.
         self.build_block_result.take().unwrap()
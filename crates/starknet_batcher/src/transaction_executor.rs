@@ -8,8 +8,11 @@ use blockifier::state::cached_state::CommitmentStateDiff;
 use blockifier::state::state_api::StateReader;
 use blockifier::transaction::objects::TransactionExecutionInfo;
 use blockifier::transaction::transaction_execution::Transaction as BlockifierTransaction;
+use indexmap::IndexMap;
 #[cfg(test)]
 use mockall::automock;
+use starknet_api::core::ClassHash;
+use starknet_api::state::ThinStateDiff;
 
 #[cfg_attr(test, automock)]
 pub trait TransactionExecutorTrait: Send {
@@ -17,6 +20,7 @@ pub trait TransactionExecutorTrait: Send {
         &mut self,
         txs: &[BlockifierTransaction],
     ) -> Vec<TransactionExecutorResult<TransactionExecutionInfo>>;
+    fn pending_state_diff(&mut self) -> TransactionExecutorResult<ThinStateDiff>;
     fn close_block(
         &mut self,
     ) -> TransactionExecutorResult<(CommitmentStateDiff, VisitedSegmentsMapping, BouncerWeights)>;
@@ -30,6 +34,33 @@ impl<S: StateReader + Send + Sync> TransactionExecutorTrait for TransactionExecu
     ) -> Vec<TransactionExecutorResult<TransactionExecutionInfo>> {
         self.execute_txs(txs)
     }
+    /// Returns the state diff of the transactions added to the block so far, without closing it.
+    fn pending_state_diff(&mut self) -> TransactionExecutorResult<ThinStateDiff> {
+        let block_state = self
+            .block_state
+            .as_mut()
+            .expect("The block state should exist until the block is closed.");
+        let state_diff = CommitmentStateDiff::from(block_state.to_state_diff()?.state_maps);
+        // The state diff holds the new class hash of every address whose class changed, which is
+        // a deployed contract if the address had no class before the block.
+        let mut deployed_contracts = IndexMap::new();
+        let mut replaced_classes = IndexMap::new();
+        for (address, class_hash) in state_diff.address_to_class_hash {
+            if block_state.state.get_class_hash_at(address)? == ClassHash::default() {
+                deployed_contracts.insert(address, class_hash);
+            } else {
+                replaced_classes.insert(address, class_hash);
+            }
+        }
+        Ok(ThinStateDiff {
+            deployed_contracts,
+            storage_diffs: state_diff.storage_updates,
+            declared_classes: state_diff.class_hash_to_compiled_class_hash,
+            deprecated_declared_classes: Vec::new(),
+            nonces: state_diff.address_to_nonce,
+            replaced_classes,
+        })
+    }
     /// Finalizes the block creation and returns the commitment state diff, visited
     /// segments mapping and bouncer.
     fn close_block(
@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+
+use blockifier::blockifier::config::TransactionExecutorConfig;
+use blockifier::blockifier::transaction_executor::TransactionExecutor;
+use blockifier::context::BlockContext;
+use blockifier::state::cached_state::CachedState;
+use blockifier::state::state_api::{State, StateReader};
+use blockifier::test_utils::dict_state_reader::DictStateReader;
+use indexmap::indexmap;
+use starknet_api::{class_hash, contract_address};
+
+use crate::transaction_executor::TransactionExecutorTrait;
+
+#[test]
+fn pending_state_diff_separates_deployed_contracts_and_replaced_classes() {
+    let replaced_address = contract_address!("0x1");
+    let deployed_address = contract_address!("0x2");
+    let state_reader = DictStateReader {
+        address_to_class_hash: HashMap::from([(replaced_address, class_hash!("0x10"))]),
+        ..Default::default()
+    };
+    let mut executor = TransactionExecutor::new(
+        CachedState::new(state_reader),
+        BlockContext::create_for_testing(),
+        TransactionExecutorConfig::default(),
+    );
+    let block_state = executor.block_state.as_mut().unwrap();
+    // Execution reads the class hash of an address before writing it.
+    for address in [replaced_address, deployed_address] {
+        block_state.get_class_hash_at(address).unwrap();
+    }
+    block_state.set_class_hash_at(replaced_address, class_hash!("0x11")).unwrap();
+    block_state.set_class_hash_at(deployed_address, class_hash!("0x20")).unwrap();
+
+    let state_diff = executor.pending_state_diff().unwrap();
+    assert_eq!(
+        state_diff.deployed_contracts,
+        indexmap! { deployed_address => class_hash!("0x20") }
+    );
+    assert_eq!(state_diff.replaced_classes, indexmap! { replaced_address => class_hash!("0x11") });
+}
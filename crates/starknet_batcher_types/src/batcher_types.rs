@@ -6,6 +6,8 @@ use starknet_api::block::{BlockHashAndNumber, BlockInfo, BlockNumber};
 use starknet_api::core::StateDiffCommitment;
 use starknet_api::executable_transaction::Transaction;
 use starknet_api::state::ThinStateDiff;
+use starknet_api::transaction::fields::Fee;
+use starknet_api::transaction::{Event, MessageToL1};
 
 use crate::errors::BatcherError;
 
@@ -106,6 +108,31 @@ pub enum ProposalStatus {
     InvalidProposal,
 }
 
+/// The proposal that is currently being executed, as far as it was executed.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PendingProposal {
+    pub block_info: BlockInfo,
+    pub transactions: Vec<Transaction>,
+    // The i-th output belongs to the i-th transaction.
+    pub transaction_outputs: Vec<PendingTransactionOutput>,
+    pub state_diff: ThinStateDiff,
+}
+
+/// The outcome of executing a transaction of a [`PendingProposal`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PendingTransactionOutput {
+    pub actual_fee: Fee,
+    pub events: Vec<Event>,
+    pub messages_sent: Vec<MessageToL1>,
+    pub revert_reason: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GetPendingProposalResponse {
+    // None if no proposal is being executed in the current height.
+    pub pending_proposal: Option<PendingProposal>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StartHeightInput {
     pub height: BlockNumber,
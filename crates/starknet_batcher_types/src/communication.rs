@@ -22,6 +22,7 @@ use crate::batcher_types::{
     DecisionReachedInput,
     DecisionReachedResponse,
     GetHeightResponse,
+    GetPendingProposalResponse,
     GetProposalContentInput,
     GetProposalContentResponse,
     ProposeBlockInput,
@@ -79,6 +80,9 @@ pub trait BatcherClient: Send + Sync {
         &self,
         input: DecisionReachedInput,
     ) -> BatcherClientResult<DecisionReachedResponse>;
+    /// Gets the transactions executed so far in the proposal that is currently being executed,
+    /// together with their outputs and the state diff they produced.
+    async fn get_pending_proposal(&self) -> BatcherClientResult<GetPendingProposalResponse>;
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -91,6 +95,7 @@ pub enum BatcherRequest {
     GetCurrentHeight,
     DecisionReached(DecisionReachedInput),
     AddSyncBlock(SyncBlock),
+    GetPendingProposal,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -103,6 +108,7 @@ pub enum BatcherResponse {
     StartHeight(BatcherResult<()>),
     DecisionReached(BatcherResult<DecisionReachedResponse>),
     AddSyncBlock(BatcherResult<()>),
+    GetPendingProposal(BatcherResult<GetPendingProposalResponse>),
 }
 
 #[derive(Clone, Debug, Error)]
@@ -194,4 +200,15 @@ where
         let response = self.send(request).await;
         handle_response_variants!(BatcherResponse, AddSyncBlock, BatcherClientError, BatcherError)
     }
+
+    async fn get_pending_proposal(&self) -> BatcherClientResult<GetPendingProposalResponse> {
+        let request = BatcherRequest::GetPendingProposal;
+        let response = self.send(request).await;
+        handle_response_variants!(
+            BatcherResponse,
+            GetPendingProposal,
+            BatcherClientError,
+            BatcherError
+        )
+    }
 }
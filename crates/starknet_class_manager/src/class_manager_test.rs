@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use cairo_lang_starknet_classes::contract_class::ContractClass as CairoLangContractClass;
+use papyrus_test_utils::{get_rng, GetTestInstance};
+use starknet_api::contract_class::{ContractClass, SierraVersion};
+use starknet_api::core::ClassHash;
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedClass;
+use starknet_api::hash::StarkHash;
+use starknet_api::state::SierraContractClass;
+use starknet_class_manager_types::ClassManagerError;
+use starknet_sierra_compile::errors::CompilationUtilError;
+use starknet_sierra_compile::SierraToCasmCompiler;
+use tempfile::TempDir;
+
+use crate::class_storage::{FsClassStorage, FsClassStorageConfig};
+use crate::ClassManager;
+
+const CLASS_ID: ClassHash = ClassHash(StarkHash::from_hex_unchecked("0x1234"));
+
+// Returns the same Casm for every class, or fails if no Casm was given.
+struct FakeCompiler(Option<CasmContractClass>);
+
+fn class_manager(compiler: FakeCompiler, persistent_root: &TempDir) -> ClassManager {
+    let storage = FsClassStorage::new(FsClassStorageConfig {
+        persistent_root: persistent_root.path().to_path_buf(),
+    });
+    ClassManager::new(Arc::new(compiler), Box::new(storage))
+}
+
+impl SierraToCasmCompiler for FakeCompiler {
+    fn compile(
+        &self,
+        _contract_class: CairoLangContractClass,
+    ) -> Result<CasmContractClass, CompilationUtilError> {
+        self.0.clone().ok_or(CompilationUtilError::CompilationError("Failed.".to_owned()))
+    }
+}
+
+#[test]
+fn add_class() {
+    // The segment lengths of a random instance don't match its bytecode, which fails hashing it.
+    let casm = CasmContractClass {
+        bytecode_segment_lengths: None,
+        ..CasmContractClass::get_test_instance(&mut get_rng())
+    };
+    let persistent_root = TempDir::new().unwrap();
+    let mut class_manager = class_manager(FakeCompiler(Some(casm.clone())), &persistent_root);
+    let class = SierraContractClass::default();
+
+    let executable_class_hash = class_manager.add_class(CLASS_ID, class.clone()).unwrap();
+
+    let sierra_version = SierraVersion::extract_from_program(&class.sierra_program).unwrap();
+    let expected_executable = ContractClass::V1((casm, sierra_version));
+    assert_eq!(executable_class_hash, expected_executable.compiled_class_hash());
+    assert_eq!(class_manager.get_executable(CLASS_ID).unwrap(), expected_executable);
+    assert_eq!(class_manager.get_sierra(CLASS_ID).unwrap(), class);
+
+    // The class outlives the class manager that stored it.
+    let class_manager = self::class_manager(FakeCompiler(None), &persistent_root);
+    assert_eq!(class_manager.get_executable(CLASS_ID).unwrap(), expected_executable);
+    assert_eq!(class_manager.get_sierra(CLASS_ID).unwrap(), class);
+}
+
+#[test]
+fn add_class_fails_on_compilation_error() {
+    let persistent_root = TempDir::new().unwrap();
+    let mut class_manager = class_manager(FakeCompiler(None), &persistent_root);
+
+    assert_matches!(
+        class_manager.add_class(CLASS_ID, SierraContractClass::default()),
+        Err(ClassManagerError::CompilationUtilError(_))
+    );
+    assert_eq!(
+        class_manager.get_sierra(CLASS_ID),
+        Err(ClassManagerError::ClassNotFound { class_id: CLASS_ID })
+    );
+}
+
+#[test]
+fn compile_class_doesnt_store_the_class() {
+    let casm = CasmContractClass {
+        bytecode_segment_lengths: None,
+        ..CasmContractClass::get_test_instance(&mut get_rng())
+    };
+    let persistent_root = TempDir::new().unwrap();
+    let mut class_manager = class_manager(FakeCompiler(Some(casm.clone())), &persistent_root);
+    let class = SierraContractClass::default();
+
+    let executable = class_manager.compile_class(&class).unwrap();
+
+    let sierra_version = SierraVersion::extract_from_program(&class.sierra_program).unwrap();
+    assert_eq!(executable, ContractClass::V1((casm, sierra_version)));
+    assert_eq!(
+        class_manager.get_sierra(CLASS_ID),
+        Err(ClassManagerError::ClassNotFound { class_id: CLASS_ID })
+    );
+
+    let executable_class_hash = executable.compiled_class_hash();
+    class_manager
+        .add_class_and_executable_unsafe(
+            CLASS_ID,
+            class.clone(),
+            executable_class_hash,
+            executable.clone(),
+        )
+        .unwrap();
+    assert_eq!(class_manager.get_executable(CLASS_ID).unwrap(), executable);
+    assert_eq!(class_manager.get_sierra(CLASS_ID).unwrap(), class);
+    // The class isn't compiled again when it's added with the same Sierra.
+    let mut class_manager = self::class_manager(FakeCompiler(None), &persistent_root);
+    assert_eq!(class_manager.add_class(CLASS_ID, class).unwrap(), executable_class_hash);
+}
+
+#[test]
+fn add_deprecated_class() {
+    let persistent_root = TempDir::new().unwrap();
+    let mut class_manager = class_manager(FakeCompiler(None), &persistent_root);
+    let class = DeprecatedClass::default();
+
+    class_manager.add_deprecated_class(CLASS_ID, class.clone()).unwrap();
+
+    assert_eq!(class_manager.get_executable(CLASS_ID).unwrap(), ContractClass::V0(class));
+    assert_eq!(
+        class_manager.get_sierra(CLASS_ID),
+        Err(ClassManagerError::ClassNotFound { class_id: CLASS_ID })
+    );
+}
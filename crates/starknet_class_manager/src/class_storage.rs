@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedClass;
+use starknet_class_manager_types::{Class, ClassId, ExecutableClass, ExecutableClassHash};
+use thiserror::Error;
+use validator::Validate;
+
+#[cfg(test)]
+#[path = "class_storage_test.rs"]
+mod class_storage_test;
+
+const SIERRA_FILE_NAME: &str = "sierra.json";
+const CASM_FILE_NAME: &str = "casm.json";
+const EXECUTABLE_CLASS_HASH_FILE_NAME: &str = "executable_class_hash.json";
+const DEPRECATED_CLASS_FILE_NAME: &str = "deprecated_class.json";
+
+pub type ClassStorageResult<T> = Result<T, ClassStorageError>;
+
+#[derive(Debug, Error)]
+pub enum ClassStorageError {
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    SerdeError(#[from] serde_json::Error),
+}
+
+/// Stores the classes of the class manager along with their compiled artifacts.
+/// Getters return `None` for classes that weren't stored.
+pub trait ClassStorage: Send + Sync {
+    fn get_sierra(&self, class_id: ClassId) -> ClassStorageResult<Option<Class>>;
+
+    fn get_executable(&self, class_id: ClassId) -> ClassStorageResult<Option<ExecutableClass>>;
+
+    fn get_executable_class_hash(
+        &self,
+        class_id: ClassId,
+    ) -> ClassStorageResult<Option<ExecutableClassHash>>;
+
+    /// Stores the class and its artifacts, replacing a class previously stored under the same id.
+    fn set_class(
+        &mut self,
+        class_id: ClassId,
+        class: &Class,
+        executable_class: &ExecutableClass,
+        executable_class_hash: ExecutableClassHash,
+    ) -> ClassStorageResult<()>;
+
+    fn get_deprecated_class(
+        &self,
+        class_id: ClassId,
+    ) -> ClassStorageResult<Option<DeprecatedClass>>;
+
+    fn set_deprecated_class(
+        &mut self,
+        class_id: ClassId,
+        class: &DeprecatedClass,
+    ) -> ClassStorageResult<()>;
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Validate, PartialEq)]
+pub struct FsClassStorageConfig {
+    pub persistent_root: PathBuf,
+}
+
+impl Default for FsClassStorageConfig {
+    fn default() -> Self {
+        Self { persistent_root: PathBuf::from("./sequencer_data/classes") }
+    }
+}
+
+impl SerializeConfig for FsClassStorageConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([ser_param(
+            "persistent_root",
+            &self.persistent_root,
+            "Path of the directory the classes are stored in. The directory is created if it \
+             doesn't exist.",
+            ParamPrivacyInput::Public,
+        )])
+    }
+}
+
+/// Stores each class in its own directory under the persistent root, one file per artifact.
+/// A class directory is written aside and then renamed into place, so a crash mid-write never
+/// leaves a partially stored class behind.
+pub struct FsClassStorage {
+    persistent_root: PathBuf,
+}
+
+impl FsClassStorage {
+    pub fn new(config: FsClassStorageConfig) -> Self {
+        Self { persistent_root: config.persistent_root }
+    }
+
+    fn class_dir(&self, class_id: ClassId) -> PathBuf {
+        self.persistent_root.join("classes").join(format!("{:#x}", class_id.0))
+    }
+
+    fn deprecated_class_dir(&self, class_id: ClassId) -> PathBuf {
+        self.persistent_root.join("deprecated_classes").join(format!("{:#x}", class_id.0))
+    }
+
+    // Writes the given files to a temporary directory and moves it to `dir`.
+    fn write_dir(&self, dir: &Path, files: &[(&str, Vec<u8>)]) -> ClassStorageResult<()> {
+        let relative_dir =
+            dir.strip_prefix(&self.persistent_root).expect("Class directories are under the root.");
+        let tmp_dir = self.persistent_root.join("tmp").join(relative_dir);
+        if tmp_dir.exists() {
+            fs::remove_dir_all(&tmp_dir)?;
+        }
+        fs::create_dir_all(&tmp_dir)?;
+        for (file_name, content) in files {
+            fs::write(tmp_dir.join(file_name), content)?;
+        }
+
+        fs::create_dir_all(dir.parent().expect("Class directories are under the root."))?;
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+        fs::rename(&tmp_dir, dir)?;
+        Ok(())
+    }
+}
+
+// Returns `None` if the file doesn't exist.
+fn read_file<T: DeserializeOwned>(path: &Path) -> ClassStorageResult<Option<T>> {
+    match fs::read(path) {
+        Ok(content) => Ok(Some(serde_json::from_slice(&content)?)),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+impl ClassStorage for FsClassStorage {
+    fn get_sierra(&self, class_id: ClassId) -> ClassStorageResult<Option<Class>> {
+        read_file(&self.class_dir(class_id).join(SIERRA_FILE_NAME))
+    }
+
+    fn get_executable(&self, class_id: ClassId) -> ClassStorageResult<Option<ExecutableClass>> {
+        read_file(&self.class_dir(class_id).join(CASM_FILE_NAME))
+    }
+
+    fn get_executable_class_hash(
+        &self,
+        class_id: ClassId,
+    ) -> ClassStorageResult<Option<ExecutableClassHash>> {
+        read_file(&self.class_dir(class_id).join(EXECUTABLE_CLASS_HASH_FILE_NAME))
+    }
+
+    fn set_class(
+        &mut self,
+        class_id: ClassId,
+        class: &Class,
+        executable_class: &ExecutableClass,
+        executable_class_hash: ExecutableClassHash,
+    ) -> ClassStorageResult<()> {
+        self.write_dir(
+            &self.class_dir(class_id),
+            &[
+                (SIERRA_FILE_NAME, serde_json::to_vec(class)?),
+                (CASM_FILE_NAME, serde_json::to_vec(executable_class)?),
+                (EXECUTABLE_CLASS_HASH_FILE_NAME, serde_json::to_vec(&executable_class_hash)?),
+            ],
+        )
+    }
+
+    fn get_deprecated_class(
+        &self,
+        class_id: ClassId,
+    ) -> ClassStorageResult<Option<DeprecatedClass>> {
+        read_file(&self.deprecated_class_dir(class_id).join(DEPRECATED_CLASS_FILE_NAME))
+    }
+
+    fn set_deprecated_class(
+        &mut self,
+        class_id: ClassId,
+        class: &DeprecatedClass,
+    ) -> ClassStorageResult<()> {
+        self.write_dir(
+            &self.deprecated_class_dir(class_id),
+            &[(DEPRECATED_CLASS_FILE_NAME, serde_json::to_vec(class)?)],
+        )
+    }
+}
@@ -0,0 +1,61 @@
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use papyrus_test_utils::{get_rng, GetTestInstance};
+use starknet_api::contract_class::{ContractClass, SierraVersion};
+use starknet_api::core::{ClassHash, CompiledClassHash};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedClass;
+use starknet_api::hash::StarkHash;
+use starknet_api::state::SierraContractClass;
+use tempfile::TempDir;
+
+use crate::class_storage::{ClassStorage, FsClassStorage, FsClassStorageConfig};
+
+const CLASS_ID: ClassHash = ClassHash(StarkHash::from_hex_unchecked("0x1234"));
+
+fn fs_class_storage(persistent_root: &TempDir) -> FsClassStorage {
+    FsClassStorage::new(FsClassStorageConfig {
+        persistent_root: persistent_root.path().to_path_buf(),
+    })
+}
+
+#[test]
+fn set_and_get_class() {
+    let persistent_root = TempDir::new().unwrap();
+    let mut storage = fs_class_storage(&persistent_root);
+    assert_eq!(storage.get_sierra(CLASS_ID).unwrap(), None);
+    assert_eq!(storage.get_executable(CLASS_ID).unwrap(), None);
+    assert_eq!(storage.get_executable_class_hash(CLASS_ID).unwrap(), None);
+
+    let class = SierraContractClass::default();
+    let casm = ContractClass::V1((
+        CasmContractClass::get_test_instance(&mut get_rng()),
+        SierraVersion::default(),
+    ));
+    let executable_class_hash = CompiledClassHash(StarkHash::ONE);
+    storage.set_class(CLASS_ID, &class, &casm, executable_class_hash).unwrap();
+
+    assert_eq!(storage.get_sierra(CLASS_ID).unwrap(), Some(class.clone()));
+    assert_eq!(storage.get_executable(CLASS_ID).unwrap(), Some(casm.clone()));
+    assert_eq!(storage.get_executable_class_hash(CLASS_ID).unwrap(), Some(executable_class_hash));
+    assert_eq!(storage.get_deprecated_class(CLASS_ID).unwrap(), None);
+
+    // Setting the class again replaces it.
+    let other_executable_class_hash = CompiledClassHash(StarkHash::TWO);
+    storage.set_class(CLASS_ID, &class, &casm, other_executable_class_hash).unwrap();
+    assert_eq!(
+        storage.get_executable_class_hash(CLASS_ID).unwrap(),
+        Some(other_executable_class_hash)
+    );
+}
+
+#[test]
+fn set_and_get_deprecated_class() {
+    let persistent_root = TempDir::new().unwrap();
+    let mut storage = fs_class_storage(&persistent_root);
+    assert_eq!(storage.get_deprecated_class(CLASS_ID).unwrap(), None);
+
+    let class = DeprecatedClass::default();
+    storage.set_deprecated_class(CLASS_ID, &class).unwrap();
+
+    assert_eq!(storage.get_deprecated_class(CLASS_ID).unwrap(), Some(class));
+    assert_eq!(storage.get_sierra(CLASS_ID).unwrap(), None);
+}
@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use starknet_class_manager_types::{ClassManagerRequest, ClassManagerResponse};
+use starknet_sequencer_infra::component_client::{LocalComponentClient, RemoteComponentClient};
+use starknet_sequencer_infra::component_definitions::{
+    ComponentRequestAndResponseSender,
+    ComponentRequestHandler,
+};
+use starknet_sequencer_infra::component_server::{LocalComponentServer, RemoteComponentServer};
+use tracing::instrument;
+
+use crate::ClassManager;
+
+pub type LocalClassManagerServer =
+    LocalComponentServer<ClassManager, ClassManagerRequest, ClassManagerResponse>;
+pub type RemoteClassManagerServer =
+    RemoteComponentServer<ClassManagerRequest, ClassManagerResponse>;
+pub type ClassManagerRequestAndResponseSender =
+    ComponentRequestAndResponseSender<ClassManagerRequest, ClassManagerResponse>;
+pub type LocalClassManagerClient = LocalComponentClient<ClassManagerRequest, ClassManagerResponse>;
+pub type RemoteClassManagerClient =
+    RemoteComponentClient<ClassManagerRequest, ClassManagerResponse>;
+
+#[async_trait]
+impl ComponentRequestHandler<ClassManagerRequest, ClassManagerResponse> for ClassManager {
+    #[instrument(skip(self))]
+    async fn handle_request(&mut self, request: ClassManagerRequest) -> ClassManagerResponse {
+        match request {
+            ClassManagerRequest::AddClass(class_id, class) => {
+                ClassManagerResponse::AddClass(self.add_class(class_id, class))
+            }
+            ClassManagerRequest::AddClassAndExecutableUnsafe(
+                class_id,
+                class,
+                executable_class_hash,
+                executable_class,
+            ) => ClassManagerResponse::AddClassAndExecutableUnsafe(
+                self.add_class_and_executable_unsafe(
+                    class_id,
+                    class,
+                    executable_class_hash,
+                    executable_class,
+                ),
+            ),
+            ClassManagerRequest::AddDeprecatedClass(class_id, class) => {
+                ClassManagerResponse::AddDeprecatedClass(self.add_deprecated_class(class_id, class))
+            }
+            ClassManagerRequest::CompileClass(class) => {
+                ClassManagerResponse::CompileClass(self.compile_class(&class))
+            }
+            ClassManagerRequest::GetExecutable(class_id) => {
+                ClassManagerResponse::GetExecutable(self.get_executable(class_id))
+            }
+            ClassManagerRequest::GetSierra(class_id) => {
+                ClassManagerResponse::GetSierra(self.get_sierra(class_id))
+            }
+        }
+    }
+}
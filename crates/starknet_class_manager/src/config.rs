@@ -0,0 +1,28 @@
+use std::collections::BTreeMap;
+
+use papyrus_config::dumping::{append_sub_config_name, SerializeConfig};
+use papyrus_config::{ParamPath, SerializedParam};
+use serde::{Deserialize, Serialize};
+use starknet_sierra_compile::config::SierraToCasmCompilationConfig;
+use validator::Validate;
+
+use crate::class_storage::FsClassStorageConfig;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Validate, PartialEq)]
+pub struct ClassManagerConfig {
+    pub compiler_config: SierraToCasmCompilationConfig,
+    #[validate]
+    pub storage_config: FsClassStorageConfig,
+}
+
+impl SerializeConfig for ClassManagerConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        vec![
+            append_sub_config_name(self.compiler_config.dump(), "compiler_config"),
+            append_sub_config_name(self.storage_config.dump(), "storage_config"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
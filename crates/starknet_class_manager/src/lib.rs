@@ -0,0 +1,126 @@
+pub mod class_storage;
+pub mod communication;
+pub mod config;
+
+use std::sync::Arc;
+
+use starknet_api::contract_class::{ContractClass, SierraVersion};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedClass;
+use starknet_class_manager_types::{
+    Class,
+    ClassId,
+    ClassManagerError,
+    ClassManagerResult,
+    ExecutableClass,
+    ExecutableClassHash,
+};
+use starknet_sequencer_infra::component_definitions::ComponentStarter;
+use starknet_sierra_compile::command_line_compiler::CommandLineCompiler;
+use starknet_sierra_compile::utils::into_contract_class_for_compilation;
+use starknet_sierra_compile::SierraToCasmCompiler;
+use tracing::debug;
+
+use crate::class_storage::{ClassStorage, ClassStorageError, FsClassStorage};
+use crate::config::ClassManagerConfig;
+
+#[cfg(test)]
+#[path = "class_manager_test.rs"]
+mod class_manager_test;
+
+impl From<ClassStorageError> for ClassManagerError {
+    fn from(error: ClassStorageError) -> Self {
+        ClassManagerError::ClassStorageError(error.to_string())
+    }
+}
+
+/// Compiles the classes added to it and stores them along with their compiled artifacts.
+pub struct ClassManager {
+    compiler: Arc<dyn SierraToCasmCompiler>,
+    storage: Box<dyn ClassStorage>,
+}
+
+impl ClassManager {
+    pub fn new(compiler: Arc<dyn SierraToCasmCompiler>, storage: Box<dyn ClassStorage>) -> Self {
+        Self { compiler, storage }
+    }
+
+    /// Compiles the class to Casm, stores it and returns the hash of the compiled class.
+    /// Adding a class that was already added with the same Sierra doesn't recompile it, while
+    /// adding a different Sierra under the same id replaces the stored class.
+    pub fn add_class(
+        &mut self,
+        class_id: ClassId,
+        class: Class,
+    ) -> ClassManagerResult<ExecutableClassHash> {
+        if self.storage.get_sierra(class_id)?.as_ref() == Some(&class) {
+            if let Some(executable_class_hash) = self.storage.get_executable_class_hash(class_id)? {
+                return Ok(executable_class_hash);
+            }
+        }
+
+        let casm = self.compile_class(&class).inspect_err(|error| {
+            debug!("Failed compiling class {class_id}. Error: {error}");
+        })?;
+        let executable_class_hash = casm.compiled_class_hash();
+
+        self.storage.set_class(class_id, &class, &casm, executable_class_hash)?;
+        Ok(executable_class_hash)
+    }
+
+    /// Compiles the class to Casm without storing it, so that a caller that expects a certain
+    /// compiled class hash can check it before adding the class.
+    pub fn compile_class(&self, class: &Class) -> ClassManagerResult<ExecutableClass> {
+        let casm_contract_class = self
+            .compiler
+            .compile(into_contract_class_for_compilation(class))
+            .map_err(|error| ClassManagerError::CompilationUtilError(error.to_string()))?;
+        let sierra_version = SierraVersion::extract_from_program(&class.sierra_program)
+            .map_err(|error| ClassManagerError::CompilationUtilError(error.to_string()))?;
+        Ok(ContractClass::V1((casm_contract_class, sierra_version)))
+    }
+
+    /// Stores a class along with an executable class that was already compiled from it, e.g. by
+    /// [`ClassManager::compile_class`]. The caller is trusted to pass an executable class and hash
+    /// that match the class, as they aren't checked.
+    pub fn add_class_and_executable_unsafe(
+        &mut self,
+        class_id: ClassId,
+        class: Class,
+        executable_class_hash: ExecutableClassHash,
+        executable_class: ExecutableClass,
+    ) -> ClassManagerResult<()> {
+        Ok(self.storage.set_class(class_id, &class, &executable_class, executable_class_hash)?)
+    }
+
+    pub fn get_executable(&self, class_id: ClassId) -> ClassManagerResult<ExecutableClass> {
+        if let Some(casm) = self.storage.get_executable(class_id)? {
+            return Ok(casm);
+        }
+        self.storage
+            .get_deprecated_class(class_id)?
+            .map(ContractClass::V0)
+            .ok_or(ClassManagerError::ClassNotFound { class_id })
+    }
+
+    pub fn get_sierra(&self, class_id: ClassId) -> ClassManagerResult<Class> {
+        self.storage.get_sierra(class_id)?.ok_or(ClassManagerError::ClassNotFound { class_id })
+    }
+
+    /// Deprecated classes are executed as is, so they're stored without compilation.
+    pub fn add_deprecated_class(
+        &mut self,
+        class_id: ClassId,
+        class: DeprecatedClass,
+    ) -> ClassManagerResult<()> {
+        Ok(self.storage.set_deprecated_class(class_id, &class)?)
+    }
+}
+
+impl ComponentStarter for ClassManager {}
+
+pub fn create_class_manager(config: ClassManagerConfig) -> ClassManager {
+    ClassManager::new(
+        Arc::new(CommandLineCompiler::new(config.compiler_config)),
+        Box::new(FsClassStorage::new(config.storage_config)),
+    )
+}
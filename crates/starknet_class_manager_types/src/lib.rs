@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use papyrus_proc_macros::handle_response_variants;
 use serde::{Deserialize, Serialize};
@@ -11,6 +13,7 @@ use thiserror::Error;
 
 pub type ClassManagerResult<T> = Result<T, ClassManagerError>;
 pub type ClassManagerClientResult<T> = Result<T, ClassManagerClientError>;
+pub type SharedClassManagerClient = Arc<dyn ClassManagerClient>;
 
 // TODO: export.
 pub type ClassId = ClassHash;
@@ -30,6 +33,19 @@ pub trait ClassManagerClient: Send + Sync {
         class: Class,
     ) -> ClassManagerClientResult<ExecutableClassHash>;
 
+    /// Compiles the class without storing it.
+    async fn compile_class(&self, class: Class) -> ClassManagerClientResult<ExecutableClass>;
+
+    /// Stores the class along with an executable class that was already compiled from it, without
+    /// checking that they match.
+    async fn add_class_and_executable_unsafe(
+        &self,
+        class_id: ClassId,
+        class: Class,
+        executable_class_hash: ExecutableClassHash,
+        executable_class: ExecutableClass,
+    ) -> ClassManagerClientResult<()>;
+
     async fn get_executable(&self, class_id: ClassId) -> ClassManagerClientResult<ExecutableClass>;
 
     async fn get_sierra(&self, class_id: ClassId) -> ClassManagerClientResult<Class>;
@@ -47,6 +63,8 @@ pub enum ClassManagerError {
     CompilationUtilError(String),
     #[error("Class of hash: {class_id} not found")]
     ClassNotFound { class_id: ClassId },
+    #[error("Class storage error: {0}")]
+    ClassStorageError(String),
 }
 
 #[derive(Clone, Debug, Error)]
@@ -60,7 +78,9 @@ pub enum ClassManagerClientError {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ClassManagerRequest {
     AddClass(ClassId, Class),
+    AddClassAndExecutableUnsafe(ClassId, Class, ExecutableClassHash, ExecutableClass),
     AddDeprecatedClass(ClassId, DeprecatedClass),
+    CompileClass(Class),
     GetExecutable(ClassId),
     GetSierra(ClassId),
 }
@@ -68,7 +88,9 @@ pub enum ClassManagerRequest {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ClassManagerResponse {
     AddClass(ClassManagerResult<ExecutableClassHash>),
+    AddClassAndExecutableUnsafe(ClassManagerResult<()>),
     AddDeprecatedClass(ClassManagerResult<()>),
+    CompileClass(ClassManagerResult<ExecutableClass>),
     GetExecutable(ClassManagerResult<ExecutableClass>),
     GetSierra(ClassManagerResult<Class>),
 }
@@ -93,6 +115,39 @@ where
         )
     }
 
+    async fn compile_class(&self, class: Class) -> ClassManagerClientResult<ExecutableClass> {
+        let request = ClassManagerRequest::CompileClass(class);
+        let response = self.send(request).await;
+        handle_response_variants!(
+            ClassManagerResponse,
+            CompileClass,
+            ClassManagerClientError,
+            ClassManagerError
+        )
+    }
+
+    async fn add_class_and_executable_unsafe(
+        &self,
+        class_id: ClassId,
+        class: Class,
+        executable_class_hash: ExecutableClassHash,
+        executable_class: ExecutableClass,
+    ) -> ClassManagerClientResult<()> {
+        let request = ClassManagerRequest::AddClassAndExecutableUnsafe(
+            class_id,
+            class,
+            executable_class_hash,
+            executable_class,
+        );
+        let response = self.send(request).await;
+        handle_response_variants!(
+            ClassManagerResponse,
+            AddClassAndExecutableUnsafe,
+            ClassManagerClientError,
+            ClassManagerError
+        )
+    }
+
     async fn add_deprecated_class(
         &self,
         class_id: ClassId,
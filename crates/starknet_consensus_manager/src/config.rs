@@ -1,23 +1,168 @@
 use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
 
-use papyrus_config::dumping::{append_sub_config_name, SerializeConfig};
-use papyrus_config::{ParamPath, SerializedParam};
+use papyrus_config::converters::deserialize_milliseconds_to_duration;
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_optional_param,
+    ser_param,
+    SerializeConfig,
+};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_consensus::config::ConsensusConfig;
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use starknet_api::core::ContractAddress;
+use url::Url;
+use validator::{Validate, ValidationError};
 
 /// The consensus manager related configuration.
 /// TODO(Matan): Remove ConsensusManagerConfig if it's only field remains ConsensusConfig.
 #[derive(Clone, Default, Debug, Serialize, Deserialize, Validate, PartialEq)]
 pub struct ConsensusManagerConfig {
     pub consensus_config: ConsensusConfig,
+    #[validate]
+    pub validator_set_config: ValidatorSetConfig,
+    #[validate]
+    pub gas_price_oracle_config: GasPriceOracleConfig,
 }
 
 impl SerializeConfig for ConsensusManagerConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        let sub_configs =
-            vec![append_sub_config_name(self.consensus_config.dump(), "consensus_config")];
+        let sub_configs = vec![
+            append_sub_config_name(self.consensus_config.dump(), "consensus_config"),
+            append_sub_config_name(self.validator_set_config.dump(), "validator_set_config"),
+            append_sub_config_name(self.gas_price_oracle_config.dump(), "gas_price_oracle_config"),
+        ];
 
         sub_configs.into_iter().flatten().collect()
     }
 }
+
+/// Where the validator set of each height is read from. If neither a schedule file nor a staking
+/// contract is set, the validators are `consensus_config.num_validators` consecutive IDs starting
+/// from the default validator ID.
+#[derive(Clone, Debug, Serialize, Deserialize, Validate, PartialEq)]
+#[validate(schema(function = "validate_validator_set_config"))]
+pub struct ValidatorSetConfig {
+    #[validate(range(min = 1))]
+    pub epoch_length: u64,
+    pub schedule_file: Option<PathBuf>,
+    pub staking_contract_address: Option<ContractAddress>,
+}
+
+impl SerializeConfig for ValidatorSetConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        let mut config = BTreeMap::from_iter([ser_param(
+            "epoch_length",
+            &self.epoch_length,
+            "The number of blocks in an epoch. The validator set can only change at epoch \
+             boundaries.",
+            ParamPrivacyInput::Public,
+        )]);
+        config.extend(ser_optional_param(
+            &self.schedule_file,
+            PathBuf::new(),
+            "schedule_file",
+            "A JSON file with the validator set schedule, a list of {start_height, validators}.",
+            ParamPrivacyInput::Public,
+        ));
+        config.extend(ser_optional_param(
+            &self.staking_contract_address,
+            ContractAddress::default(),
+            "staking_contract_address",
+            "The staking contract to read the validator set of each epoch from, at the end of the \
+             previous epoch.",
+            ParamPrivacyInput::Public,
+        ));
+        config
+    }
+}
+
+impl Default for ValidatorSetConfig {
+    fn default() -> Self {
+        Self { epoch_length: 100, schedule_file: None, staking_contract_address: None }
+    }
+}
+
+fn validate_validator_set_config(config: &ValidatorSetConfig) -> Result<(), ValidationError> {
+    if config.schedule_file.is_some() && config.staking_contract_address.is_some() {
+        return Err(ValidationError::new(
+            "validator set can be read from either schedule_file or staking_contract_address",
+        ));
+    }
+    Ok(())
+}
+
+/// How the gas prices of proposals are set. The gas prices follow the median gas prices of recent
+/// L1 blocks, and the minimal gas prices are used while the L1 node is unavailable. Validators
+/// accept the L1 gas prices of a proposal if they are within `gas_price_tolerance_percent` of
+/// their own.
+#[derive(Clone, Debug, Serialize, Deserialize, Validate, PartialEq)]
+pub struct GasPriceOracleConfig {
+    pub node_url: Url,
+    #[validate(range(min = 1))]
+    pub num_blocks_for_median: usize,
+    pub finality: u64,
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub polling_interval: Duration,
+    pub eth_to_strk_rate: u64,
+    pub gas_price_tolerance_percent: u64,
+}
+
+impl SerializeConfig for GasPriceOracleConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "node_url",
+                &self.node_url,
+                "The Ethereum node to sample the L1 gas prices from.",
+                ParamPrivacyInput::Private,
+            ),
+            ser_param(
+                "num_blocks_for_median",
+                &self.num_blocks_for_median,
+                "The number of recent L1 blocks to take the median gas prices of.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "finality",
+                &self.finality,
+                "The number of confirmations an L1 block needs before its gas prices are sampled.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "polling_interval",
+                &self.polling_interval.as_millis(),
+                "Interval in milliseconds between each sampling of new L1 blocks.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "eth_to_strk_rate",
+                &self.eth_to_strk_rate,
+                "The number of fri per 10^9 wei, used to convert the L1 gas prices to STRK.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "gas_price_tolerance_percent",
+                &self.gas_price_tolerance_percent,
+                "The maximal difference, in percent, between the L1 gas prices of a proposal and \
+                 the validator's own L1 gas prices.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+impl Default for GasPriceOracleConfig {
+    fn default() -> Self {
+        Self {
+            node_url: Url::parse("http://localhost:8545").expect("Invalid URL."),
+            num_blocks_for_median: 10,
+            finality: 0,
+            polling_interval: Duration::from_secs(12),
+            eth_to_strk_rate: 1_000_000_000,
+            gas_price_tolerance_percent: 10,
+        }
+    }
+}
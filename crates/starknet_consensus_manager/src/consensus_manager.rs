@@ -2,21 +2,39 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use infra_utils::type_name::short_type_name;
+use papyrus_base_layer::ethereum_base_layer_contract::{
+    EthereumBaseLayerConfig,
+    EthereumBaseLayerContract,
+};
 use papyrus_consensus::stream_handler::StreamHandler;
-use papyrus_consensus::types::ConsensusError;
+use papyrus_consensus::types::{ConsensusError, ValidatorId};
 use papyrus_consensus_orchestrator::cende::CendeAmbassador;
+use papyrus_consensus_orchestrator::gas_price_oracle::{
+    FixedEthToStrkRate,
+    GasPriceProvider,
+    L1GasPriceOracle,
+};
 use papyrus_consensus_orchestrator::sequencer_consensus_context::SequencerConsensusContext;
+use papyrus_consensus_orchestrator::validator_set::{
+    Epochs,
+    ScheduledValidatorSet,
+    ScheduledValidatorSetProvider,
+    StakingContractValidatorSetProvider,
+    ValidatorSetProvider,
+    ValidatorSetProviderError,
+};
 use papyrus_network::gossipsub_impl::Topic;
 use papyrus_network::network_manager::{BroadcastTopicChannels, NetworkManager};
-use papyrus_protobuf::consensus::{ProposalPart, StreamMessage, Vote};
+use papyrus_protobuf::consensus::{ProposalPart, StreamMessage, Vote, DEFAULT_VALIDATOR_ID};
 use starknet_api::block::BlockNumber;
 use starknet_batcher_types::communication::SharedBatcherClient;
 use starknet_sequencer_infra::component_definitions::ComponentStarter;
 use starknet_sequencer_infra::errors::ComponentError;
 use starknet_state_sync_types::communication::SharedStateSyncClient;
+use tokio::task::JoinHandle;
 use tracing::{error, info};
 
-use crate::config::ConsensusManagerConfig;
+use crate::config::{ConsensusManagerConfig, GasPriceOracleConfig, ValidatorSetConfig};
 
 // TODO(Dan, Guy): move to config.
 pub const BROADCAST_BUFFER_SIZE: usize = 100;
@@ -63,7 +81,11 @@ impl ConsensusManager {
         } = proposals_broadcast_channels;
 
         let (outbound_internal_sender, inbound_internal_receiver, mut stream_handler_task_handle) =
-            StreamHandler::get_channels(inbound_network_receiver, outbound_network_sender);
+            StreamHandler::get_channels(
+                self.config.consensus_config.stream_handler.clone(),
+                inbound_network_receiver,
+                outbound_network_sender,
+            );
 
         let observer_height =
             self.batcher_client.get_height().await.map(|h| h.height).map_err(|e| {
@@ -80,12 +102,18 @@ impl ConsensusManager {
             BlockNumber(observer_height.0 + 1)
         };
 
+        let (gas_price_provider, mut gas_price_oracle_handle) = self.build_gas_price_provider();
         let context = SequencerConsensusContext::new(
             Arc::clone(&self.state_sync_client),
             Arc::clone(&self.batcher_client),
             outbound_internal_sender,
             votes_broadcast_channels.broadcast_topic_client.clone(),
-            self.config.consensus_config.num_validators,
+            self.build_validator_set_provider().map_err(|e| {
+                error!("Failed to build the validator set provider: {:?}", e);
+                ConsensusError::Other(format!("Failed to build the validator set provider: {e}"))
+            })?,
+            gas_price_provider,
+            self.config.gas_price_oracle_config.gas_price_tolerance_percent,
             self.config.consensus_config.chain_id.clone(),
             Arc::new(CendeAmbassador::new()),
         );
@@ -116,7 +144,67 @@ impl ConsensusManager {
             stream_handler_result = &mut stream_handler_task_handle => {
                 panic!("Consensus' stream handler task finished unexpectedly: {:?}", stream_handler_result);
             }
+            gas_price_oracle_result = &mut gas_price_oracle_handle => {
+                panic!("Consensus' gas price oracle task finished unexpectedly: {:?}", gas_price_oracle_result);
+            }
+        }
+    }
+
+    // The oracle samples new L1 blocks in the background, in the returned task.
+    fn build_gas_price_provider(&self) -> (Arc<dyn GasPriceProvider>, JoinHandle<()>) {
+        let GasPriceOracleConfig {
+            node_url,
+            num_blocks_for_median,
+            finality,
+            polling_interval,
+            eth_to_strk_rate,
+            gas_price_tolerance_percent: _,
+        } = &self.config.gas_price_oracle_config;
+        let base_layer = EthereumBaseLayerContract::new(EthereumBaseLayerConfig {
+            node_url: node_url.clone(),
+            ..Default::default()
+        });
+        let oracle = Arc::new(L1GasPriceOracle::new(
+            base_layer,
+            Box::new(FixedEthToStrkRate((*eth_to_strk_rate).into())),
+            *num_blocks_for_median,
+            *finality,
+        ));
+        let polling_interval = *polling_interval;
+        let oracle_clone = Arc::clone(&oracle);
+        let handle = tokio::spawn(async move { oracle_clone.run(polling_interval).await });
+        (oracle, handle)
+    }
+
+    // The config validation guarantees that at most one source is set.
+    fn build_validator_set_provider(
+        &self,
+    ) -> Result<Arc<dyn ValidatorSetProvider>, ValidatorSetProviderError> {
+        let ValidatorSetConfig { epoch_length, schedule_file, staking_contract_address } =
+            &self.config.validator_set_config;
+        let epochs = Epochs::new(*epoch_length)?;
+        // TODO: Remove the default validator IDs once all deployments set the validators.
+        let default_validators = (0..self.config.consensus_config.num_validators)
+            .map(|i| ValidatorId::from(DEFAULT_VALIDATOR_ID + i))
+            .collect::<Vec<_>>();
+        if let Some(schedule_file) = schedule_file {
+            return Ok(Arc::new(ScheduledValidatorSetProvider::from_file(epochs, schedule_file)?));
+        }
+        if let Some(staking_contract_address) = staking_contract_address {
+            return Ok(Arc::new(StakingContractValidatorSetProvider::new(
+                Arc::clone(&self.state_sync_client),
+                *staking_contract_address,
+                epochs,
+                default_validators,
+            )));
         }
+        Ok(Arc::new(ScheduledValidatorSetProvider::new(
+            epochs,
+            vec![ScheduledValidatorSet {
+                start_height: BlockNumber(0),
+                validators: default_validators,
+            }],
+        )))
     }
 }
 
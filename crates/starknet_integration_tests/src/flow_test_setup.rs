@@ -107,6 +107,7 @@ pub struct FlowSequencerSetup {
     pub batcher_storage_file_handle: TempDir,
     pub rpc_storage_file_handle: TempDir,
     pub state_sync_storage_file_handle: TempDir,
+    pub class_manager_storage_file_handle: TempDir,
 
     // Node configuration.
     pub config: SequencerNodeConfig,
@@ -144,6 +145,7 @@ impl FlowSequencerSetup {
             rpc_server_addr,
             storage_for_test.batcher_storage_config,
             storage_for_test.state_sync_storage_config,
+            storage_for_test.class_manager_storage_config,
             consensus_manager_config,
             mempool_p2p_config,
             component_config,
@@ -168,6 +170,7 @@ impl FlowSequencerSetup {
             batcher_storage_file_handle: storage_for_test.batcher_storage_handle,
             rpc_storage_file_handle: storage_for_test.rpc_storage_handle,
             state_sync_storage_file_handle: storage_for_test.state_sync_storage_handle,
+            class_manager_storage_file_handle: storage_for_test.class_manager_storage_handle,
             config,
             is_alive_test_client,
         }
@@ -139,6 +139,8 @@ pub struct IntegrationSequencerSetup {
     node_config_dir_handle: TempDir,
     #[allow(dead_code)]
     state_sync_storage_handle: TempDir,
+    #[allow(dead_code)]
+    class_manager_storage_handle: TempDir,
 }
 
 impl IntegrationSequencerSetup {
@@ -171,6 +173,7 @@ impl IntegrationSequencerSetup {
             rpc_server_addr,
             storage_for_test.batcher_storage_config,
             storage_for_test.state_sync_storage_config,
+            storage_for_test.class_manager_storage_config,
             consensus_manager_config,
             mempool_p2p_config,
             component_config,
@@ -202,6 +205,7 @@ impl IntegrationSequencerSetup {
             node_config_path,
             state_sync_storage_handle: storage_for_test.state_sync_storage_handle,
             state_sync_storage_config: config.state_sync_config.storage_config,
+            class_manager_storage_handle: storage_for_test.class_manager_storage_handle,
         }
     }
 
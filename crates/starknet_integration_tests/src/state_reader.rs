@@ -39,11 +39,12 @@ use starknet_api::test_utils::{
 };
 use starknet_api::transaction::fields::Fee;
 use starknet_api::{contract_address, felt};
+use starknet_class_manager::class_storage::FsClassStorageConfig;
 use starknet_client::reader::PendingData;
 use starknet_sequencer_infra::test_utils::get_available_socket;
 use starknet_types_core::felt::Felt;
 use strum::IntoEnumIterator;
-use tempfile::TempDir;
+use tempfile::{tempdir, TempDir};
 use tokio::sync::RwLock;
 
 type ContractClassesMap =
@@ -57,6 +58,8 @@ pub struct StorageTestSetup {
     pub batcher_storage_handle: TempDir,
     pub state_sync_storage_config: StorageConfig,
     pub state_sync_storage_handle: TempDir,
+    pub class_manager_storage_config: FsClassStorageConfig,
+    pub class_manager_storage_handle: TempDir,
 }
 
 impl StorageTestSetup {
@@ -79,6 +82,10 @@ impl StorageTestSetup {
             .chain_id(chain_info.chain_id.clone())
             .build();
         create_test_state(&mut state_sync_storage_writer, chain_info, test_defined_accounts);
+        let class_manager_storage_handle = tempdir().unwrap();
+        let class_manager_storage_config = FsClassStorageConfig {
+            persistent_root: class_manager_storage_handle.path().to_path_buf(),
+        };
         Self {
             rpc_storage_reader,
             rpc_storage_handle: rpc_storage_file_handle,
@@ -86,6 +93,8 @@ impl StorageTestSetup {
             batcher_storage_handle: batcher_storage_file_handle,
             state_sync_storage_config,
             state_sync_storage_handle,
+            class_manager_storage_config,
+            class_manager_storage_handle,
         }
     }
 }
@@ -6,6 +6,7 @@ use blockifier::context::ChainInfo;
 use blockifier::test_utils::contracts::FeatureContract;
 use blockifier::test_utils::{CairoVersion, RunnableCairo1};
 use mempool_test_utils::starknet_api_test_utils::{AccountId, MultiAccountTransactionGenerator};
+use papyrus_base_layer::ethereum_base_layer_contract::EthereumBaseLayerConfig;
 use papyrus_consensus::config::ConsensusConfig;
 use papyrus_consensus::types::ValidatorId;
 use papyrus_network::network_manager::test_utils::{
@@ -21,6 +22,8 @@ use starknet_api::rpc_transaction::RpcTransaction;
 use starknet_api::transaction::TransactionHash;
 use starknet_batcher::block_builder::BlockBuilderConfig;
 use starknet_batcher::config::BatcherConfig;
+use starknet_class_manager::class_storage::FsClassStorageConfig;
+use starknet_class_manager::config::ClassManagerConfig;
 use starknet_consensus_manager::config::ConsensusManagerConfig;
 use starknet_gateway::config::{
     GatewayConfig,
@@ -38,6 +41,7 @@ use starknet_sequencer_node::config::node_config::SequencerNodeConfig;
 use starknet_sequencer_node::config::test_utils::RequiredParams;
 use starknet_state_sync::config::StateSyncConfig;
 use starknet_types_core::felt::Felt;
+use url::Url;
 
 pub fn create_chain_info() -> ChainInfo {
     let mut chain_info = ChainInfo::create_for_testing();
@@ -58,6 +62,7 @@ pub async fn create_config(
     rpc_server_addr: SocketAddr,
     batcher_storage_config: StorageConfig,
     state_sync_storage_config: StorageConfig,
+    class_manager_storage_config: FsClassStorageConfig,
     mut consensus_manager_config: ConsensusManagerConfig,
     mempool_p2p_config: MempoolP2pConfig,
     component_config: ComponentConfig,
@@ -73,10 +78,21 @@ pub async fn create_config(
         MonitoringEndpointConfig { port: available_ports.get_next_port(), ..Default::default() };
     let state_sync_config =
         create_state_sync_config(state_sync_storage_config, available_ports.get_next_port());
+    let class_manager_config =
+        ClassManagerConfig { storage_config: class_manager_storage_config, ..Default::default() };
+    // Nothing listens on this address, so the L1 scraper and the gas price oracle only log that
+    // they can't reach L1.
+    let eth_node_url =
+        Url::parse(&format!("http://{}", available_ports.get_next_local_host_socket())).unwrap();
+    consensus_manager_config.gas_price_oracle_config.node_url = eth_node_url.clone();
+    let base_layer_config =
+        EthereumBaseLayerConfig { node_url: eth_node_url.clone(), ..Default::default() };
 
     (
         SequencerNodeConfig {
+            base_layer_config,
             batcher_config,
+            class_manager_config,
             consensus_manager_config,
             gateway_config,
             http_server_config,
@@ -92,6 +108,7 @@ pub async fn create_config(
             eth_fee_token_address: fee_token_addresses.eth_fee_token_address,
             strk_fee_token_address: fee_token_addresses.strk_fee_token_address,
             validator_id,
+            eth_node_url,
         },
     )
 }
@@ -129,6 +146,7 @@ pub fn create_consensus_manager_configs_and_channels(
                 timeouts: timeouts.clone(),
                 ..Default::default()
             },
+            ..Default::default()
         })
         .collect();
 
@@ -14,7 +14,7 @@ use papyrus_protobuf::consensus::{
 use papyrus_storage::test_utils::CHAIN_ID_FOR_TESTS;
 use pretty_assertions::assert_eq;
 use rstest::{fixture, rstest};
-use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::block::{BlockHash, BlockNumber, GasPricePerToken, NonzeroGasPrice};
 use starknet_api::transaction::TransactionHash;
 use starknet_integration_tests::flow_test_setup::{FlowSequencerSetup, FlowTestSetup};
 use starknet_integration_tests::test_identifiers::TestIdentifier;
@@ -118,9 +118,16 @@ async fn listen_to_broadcasted_messages(
     let broadcasted_messages_receiver =
         &mut consensus_proposals_channels.broadcasted_messages_receiver;
     // TODO (Dan, Guy): retrieve / calculate the expected proposal init and fin.
+    // There's no L1 node in the test, so proposals are built with the minimal gas prices.
+    let min_gas_price = GasPricePerToken {
+        price_in_wei: NonzeroGasPrice::MIN.into(),
+        price_in_fri: NonzeroGasPrice::MIN.into(),
+    };
     let expected_proposal_init = ProposalInit {
         height: expected_height,
         proposer: expected_proposer_id,
+        l1_gas_price: min_gas_price,
+        l1_data_gas_price: min_gas_price,
         ..Default::default()
     };
     let expected_proposal_fin = ProposalFin { proposal_content_id: BlockHash(expected_content_id) };
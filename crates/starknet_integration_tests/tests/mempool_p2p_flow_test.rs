@@ -68,6 +68,7 @@ async fn setup(
     // Derive the configuration for the mempool node.
     let components = ComponentConfig {
         consensus_manager: ActiveComponentExecutionConfig::disabled(),
+        l1_scraper: ActiveComponentExecutionConfig::disabled(),
         batcher: ReactiveComponentExecutionConfig {
             execution_mode: ReactiveComponentExecutionMode::Disabled,
             local_server_config: None,
@@ -23,9 +23,21 @@ impl ComponentRequestHandler<L1ProviderRequest, L1ProviderResponse> for L1Provid
     #[instrument(skip(self))]
     async fn handle_request(&mut self, request: L1ProviderRequest) -> L1ProviderResponse {
         match request {
+            L1ProviderRequest::AddEvents(events) => {
+                L1ProviderResponse::AddEvents(self.add_events(events))
+            }
+            L1ProviderRequest::CommitBlock { tx_hashes, height } => {
+                L1ProviderResponse::CommitBlock(self.commit_block(&tx_hashes, height))
+            }
             L1ProviderRequest::GetTransactions(n_txs) => {
                 L1ProviderResponse::GetTransactions(self.get_txs(n_txs))
             }
+            L1ProviderRequest::StartBlock { state, height } => {
+                L1ProviderResponse::StartBlock(self.start_block(state, height))
+            }
+            L1ProviderRequest::Validate(tx_hash) => {
+                L1ProviderResponse::Validate(self.validate(tx_hash))
+            }
         }
     }
 }
@@ -1,10 +1,11 @@
 use assert_matches::assert_matches;
 use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
 use starknet_api::test_utils::l1_handler::executable_l1_handler_tx;
 use starknet_api::transaction::TransactionHash;
 use starknet_api::{l1_handler_tx_args, tx_hash};
 use starknet_l1_provider_types::errors::L1ProviderError;
-use starknet_l1_provider_types::ValidationStatus;
+use starknet_l1_provider_types::{Event, SessionState, ValidationStatus};
 
 use crate::test_utils::L1ProviderContentBuilder;
 use crate::L1Provider;
@@ -127,3 +128,161 @@ fn validation_start_errors() {
         L1ProviderError::unexpected_transition(Validate, Propose)
     );
 }
+
+#[test]
+fn commit_block_purges_committed_txs() {
+    // Setup.
+    let txs = [tx!(tx_hash: 0), tx!(tx_hash: 1), tx!(tx_hash: 2)];
+    let mut l1_provider = L1ProviderContentBuilder::new()
+        .with_txs(txs.clone())
+        .with_state(Pending)
+        .build_into_l1_provider();
+
+    // Test.
+    l1_provider.start_block(SessionState::Propose, BlockNumber(0)).unwrap();
+    assert_eq!(l1_provider.get_txs(2).unwrap(), txs[..2]);
+    // Only some of the proposed transactions made it into the block.
+    l1_provider.commit_block(&[tx_hash!(1)], BlockNumber(0)).unwrap();
+    assert_eq!(l1_provider.state, Pending);
+
+    l1_provider.start_block(SessionState::Propose, BlockNumber(1)).unwrap();
+    assert_eq!(l1_provider.get_txs(3).unwrap(), [txs[0].clone(), txs[2].clone()]);
+    l1_provider.commit_block(&[], BlockNumber(1)).unwrap();
+
+    l1_provider.start_block(SessionState::Validate, BlockNumber(2)).unwrap();
+    assert_eq!(l1_provider.validate(tx_hash!(1)).unwrap(), ValidationStatus::AlreadyIncludedOnL2);
+}
+
+#[test]
+fn start_block_in_new_round() {
+    // Setup.
+    let txs = [tx!(tx_hash: 0), tx!(tx_hash: 1)];
+    let mut l1_provider = L1ProviderContentBuilder::new()
+        .with_txs(txs.clone())
+        .with_state(Pending)
+        .build_into_l1_provider();
+
+    // Test.
+    l1_provider.start_block(SessionState::Propose, BlockNumber(3)).unwrap();
+    assert_eq!(l1_provider.get_txs(1).unwrap(), [txs[0].clone()]);
+
+    // A new round of the same height, proposed transactions are available again.
+    l1_provider.start_block(SessionState::Validate, BlockNumber(3)).unwrap();
+    assert_eq!(l1_provider.validate(tx_hash!(0)).unwrap(), ValidationStatus::Validated);
+    l1_provider.start_block(SessionState::Propose, BlockNumber(3)).unwrap();
+    assert_eq!(l1_provider.get_txs(2).unwrap(), txs);
+}
+
+#[test]
+fn unexpected_height() {
+    // Setup.
+    let mut l1_provider =
+        L1ProviderContentBuilder::new().with_state(Pending).build_into_l1_provider();
+    l1_provider.start_block(SessionState::Propose, BlockNumber(1)).unwrap();
+
+    // Test.
+    assert_eq!(
+        l1_provider.start_block(SessionState::Propose, BlockNumber(2)).unwrap_err(),
+        L1ProviderError::UnexpectedHeight { expected_height: BlockNumber(1), got: BlockNumber(2) }
+    );
+    l1_provider.commit_block(&[], BlockNumber(1)).unwrap();
+    assert_eq!(
+        l1_provider.commit_block(&[], BlockNumber(1)).unwrap_err(),
+        L1ProviderError::UnexpectedHeight { expected_height: BlockNumber(2), got: BlockNumber(1) }
+    );
+}
+
+#[test]
+fn add_events() {
+    // Setup.
+    let mut l1_provider = L1ProviderContentBuilder::new()
+        .with_txs([tx!(tx_hash: 1), tx!(tx_hash: 2)])
+        .with_on_l2_awaiting_l1_consumption([tx_hash!(3), tx_hash!(4)])
+        .with_state(Validate)
+        .build_into_l1_provider();
+
+    // Test.
+    l1_provider
+        .add_events(vec![
+            // Already included on L2, e.g., after the scraper restarted.
+            Event::L1HandlerTransaction(tx!(tx_hash: 3)),
+            Event::L1HandlerTransaction(tx!(tx_hash: 5)),
+            Event::TransactionCancellationStarted(tx_hash!(1)),
+            Event::TransactionConsumed(tx_hash!(4)),
+        ])
+        .unwrap();
+
+    assert_eq!(l1_provider.validate(tx_hash!(1)).unwrap(), ValidationStatus::ConsumedOnL1OrUnknown);
+    assert_eq!(l1_provider.validate(tx_hash!(2)).unwrap(), ValidationStatus::Validated);
+    assert_eq!(l1_provider.validate(tx_hash!(3)).unwrap(), ValidationStatus::AlreadyIncludedOnL2);
+    assert_eq!(l1_provider.validate(tx_hash!(4)).unwrap(), ValidationStatus::ConsumedOnL1OrUnknown);
+    assert_eq!(l1_provider.validate(tx_hash!(5)).unwrap(), ValidationStatus::Validated);
+}
+
+#[test]
+fn reorged_txs_are_kept_once_proposed() {
+    // Setup.
+    let txs = [tx!(tx_hash: 0), tx!(tx_hash: 1), tx!(tx_hash: 2)];
+    let mut l1_provider = L1ProviderContentBuilder::new()
+        .with_txs(txs.clone())
+        .with_state(Pending)
+        .build_into_l1_provider();
+    l1_provider.start_block(SessionState::Propose, BlockNumber(0)).unwrap();
+    assert_eq!(l1_provider.get_txs(1).unwrap(), [txs[0].clone()]);
+
+    // Test.
+    l1_provider
+        .add_events(vec![
+            Event::TransactionReorged(tx_hash!(0)),
+            Event::TransactionReorged(tx_hash!(1)),
+        ])
+        .unwrap();
+    assert_eq!(l1_provider.get_txs(2).unwrap(), [txs[2].clone()]);
+
+    l1_provider.commit_block(&[tx_hash!(0)], BlockNumber(0)).unwrap();
+    l1_provider.add_events(vec![Event::TransactionReorged(tx_hash!(0))]).unwrap();
+    l1_provider.start_block(SessionState::Validate, BlockNumber(1)).unwrap();
+    assert_eq!(l1_provider.validate(tx_hash!(0)).unwrap(), ValidationStatus::AlreadyIncludedOnL2);
+    assert_eq!(l1_provider.validate(tx_hash!(1)).unwrap(), ValidationStatus::ConsumedOnL1OrUnknown);
+}
+
+#[test]
+fn reorged_consumptions_and_cancellations_are_rolled_back() {
+    // Setup.
+    let mut l1_provider = L1ProviderContentBuilder::new()
+        .with_txs([tx!(tx_hash: 1), tx!(tx_hash: 2)])
+        .with_on_l2_awaiting_l1_consumption([tx_hash!(3)])
+        .with_state(Validate)
+        .build_into_l1_provider();
+    l1_provider
+        .add_events(vec![
+            Event::TransactionCancellationStarted(tx_hash!(1)),
+            Event::TransactionCancellationStarted(tx_hash!(2)),
+            Event::TransactionConsumed(tx_hash!(3)),
+        ])
+        .unwrap();
+
+    // Test.
+    l1_provider
+        .add_events(vec![
+            Event::TransactionCancellationReorged(tx_hash!(1)),
+            // The message itself was reorged as well.
+            Event::TransactionCancellationReorged(tx_hash!(2)),
+            Event::TransactionReorged(tx_hash!(2)),
+            Event::TransactionConsumptionReorged(tx_hash!(3)),
+        ])
+        .unwrap();
+
+    assert_eq!(l1_provider.validate(tx_hash!(1)).unwrap(), ValidationStatus::Validated);
+    assert_eq!(l1_provider.validate(tx_hash!(2)).unwrap(), ValidationStatus::ConsumedOnL1OrUnknown);
+    assert_eq!(l1_provider.validate(tx_hash!(3)).unwrap(), ValidationStatus::AlreadyIncludedOnL2);
+}
+
+#[test]
+fn add_events_uninitialized() {
+    let mut uninitialized_l1_provider = L1Provider::default();
+    assert_eq!(
+        uninitialized_l1_provider.add_events(vec![]).unwrap_err(),
+        L1ProviderError::Uninitialized
+    );
+}
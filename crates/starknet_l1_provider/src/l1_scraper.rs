@@ -0,0 +1,365 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use papyrus_base_layer::constants::{
+    EventIdentifier,
+    CONSUMED_MESSAGE_TO_L2_EVENT_IDENTIFIER,
+    LOG_MESSAGE_TO_L2_EVENT_IDENTIFIER,
+    MESSAGE_TO_L2_CANCELED_EVENT_IDENTIFIER,
+    MESSAGE_TO_L2_CANCELLATION_STARTED_EVENT_IDENTIFIER,
+};
+use papyrus_base_layer::ethereum_base_layer_contract::{
+    EthereumBaseLayerConfig,
+    EthereumBaseLayerContract,
+};
+use papyrus_base_layer::{BaseLayerContract, L1BlockReference, L1Event};
+use papyrus_config::converters::deserialize_milliseconds_to_duration;
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+use starknet_api::core::ChainId;
+use starknet_api::executable_transaction::L1HandlerTransaction as ExecutableL1HandlerTransaction;
+use starknet_api::transaction::{L1HandlerTransaction, TransactionHash, TransactionHasher};
+use starknet_api::StarknetApiError;
+use starknet_l1_provider_types::errors::L1ProviderClientError;
+use starknet_l1_provider_types::{Event, SharedL1ProviderClient};
+use starknet_sequencer_infra::component_definitions::ComponentStarter;
+use starknet_sequencer_infra::component_server::WrapperServer;
+use starknet_sequencer_infra::errors::ComponentError;
+use thiserror::Error;
+use tracing::{debug, error, info, instrument, warn};
+use validator::Validate;
+
+#[cfg(test)]
+#[path = "l1_scraper_tests.rs"]
+pub mod l1_scraper_tests;
+
+type L1ScraperResult<T, E> = Result<T, L1ScraperError<E>>;
+
+/// The events of the Starknet core contract which affect the status of L1 to L2 messages.
+pub const TRACKED_EVENT_IDENTIFIERS: [EventIdentifier; 4] = [
+    LOG_MESSAGE_TO_L2_EVENT_IDENTIFIER,
+    CONSUMED_MESSAGE_TO_L2_EVENT_IDENTIFIER,
+    MESSAGE_TO_L2_CANCELLATION_STARTED_EVENT_IDENTIFIER,
+    MESSAGE_TO_L2_CANCELED_EVENT_IDENTIFIER,
+];
+
+/// Periodically fetches the messaging events of the Starknet core contract from L1 and forwards
+/// them to the L1 provider.
+///
+/// Only blocks which are `finality` blocks deep are scraped, but deeper reorgs are still handled:
+/// the scraper remembers the last block of each recently scraped range, and detects a reorg when
+/// the next block doesn't extend it. It then rolls back the events of the ranges which are no longer
+/// on L1, and scrapes them again.
+pub struct L1Scraper<B: BaseLayerContract> {
+    pub config: L1ScraperConfig,
+    pub base_layer: B,
+    pub next_block_number_to_scrape: u64,
+    pub l1_provider_client: SharedL1ProviderClient,
+    scraped_ranges: VecDeque<ScrapedRange>,
+}
+
+// A range of L1 blocks whose events were sent to the provider.
+#[derive(Debug)]
+struct ScrapedRange {
+    from_block_number: u64,
+    last_block: L1BlockReference,
+    // The events which undo the events sent for the range, in the order the latter were sent.
+    rollback_events: Vec<Event>,
+}
+
+impl<B> L1Scraper<B>
+where
+    B: BaseLayerContract + Send + Sync,
+    B::Error: std::error::Error + Send + Sync,
+{
+    pub fn new(
+        config: L1ScraperConfig,
+        l1_provider_client: SharedL1ProviderClient,
+        base_layer: B,
+    ) -> Self {
+        let next_block_number_to_scrape = config.l1_block_to_start_scraping_from;
+        Self {
+            config,
+            base_layer,
+            next_block_number_to_scrape,
+            l1_provider_client,
+            scraped_ranges: VecDeque::new(),
+        }
+    }
+
+    pub async fn run(&mut self) -> L1ScraperResult<(), B::Error> {
+        loop {
+            tokio::time::sleep(self.config.polling_interval).await;
+            match self.send_events_to_l1_provider().await {
+                // Base layer and provider errors are usually transient, retry on the next poll.
+                Err(L1ScraperError::BaseLayer(err)) => {
+                    error!("Failed to fetch events from the base layer: {err}");
+                }
+                Err(L1ScraperError::NetworkError(err)) => {
+                    error!("Failed to send events to the L1 provider: {err}");
+                }
+                result => result?,
+            }
+        }
+    }
+
+    /// Forwards the events of all the L1 blocks which reached finality since the last call, or
+    /// rolls back the previously sent events if L1 was reorged.
+    #[instrument(skip(self), err)]
+    pub async fn send_events_to_l1_provider(&mut self) -> L1ScraperResult<(), B::Error> {
+        let Some(latest_l1_block_number) = self
+            .base_layer
+            .latest_l1_block_number(self.config.finality)
+            .await
+            .map_err(L1ScraperError::BaseLayer)?
+        else {
+            debug!("No L1 block reached finality yet.");
+            return Ok(());
+        };
+        if latest_l1_block_number < self.next_block_number_to_scrape {
+            return Ok(());
+        }
+
+        let (Some(first_block), Some(last_block)) = (
+            self.l1_block_reference(self.next_block_number_to_scrape).await?,
+            self.l1_block_reference(latest_l1_block_number).await?,
+        ) else {
+            debug!("L1 blocks up to {latest_l1_block_number} are not available yet.");
+            return Ok(());
+        };
+        if let Some(previous_range) = self.scraped_ranges.back() {
+            if first_block.parent_hash != previous_range.last_block.hash {
+                return self.handle_reorg().await;
+            }
+        }
+
+        let events = self
+            .base_layer
+            .events(
+                self.next_block_number_to_scrape,
+                latest_l1_block_number,
+                &TRACKED_EVENT_IDENTIFIERS,
+            )
+            .await
+            .map_err(L1ScraperError::BaseLayer)?;
+        // The events must belong to the same chain as the block hash which is remembered for them.
+        if self.l1_block_reference(latest_l1_block_number).await? != Some(last_block) {
+            warn!("L1 block {latest_l1_block_number} was reorged while scraping, retrying.");
+            return Ok(());
+        }
+
+        let events = events
+            .into_iter()
+            .map(|event| self.event_from_l1_event(event))
+            .collect::<Result<Vec<_>, _>>()?;
+        let rollback_events = events.iter().filter_map(rollback_event).collect();
+        if !events.is_empty() {
+            info!(
+                "Sending {} events from L1 blocks {} to {} to the L1 provider.",
+                events.len(),
+                self.next_block_number_to_scrape,
+                latest_l1_block_number
+            );
+            self.l1_provider_client.add_events(events).await?;
+        }
+
+        self.scraped_ranges.push_back(ScrapedRange {
+            from_block_number: self.next_block_number_to_scrape,
+            last_block,
+            rollback_events,
+        });
+        self.prune_scraped_ranges(latest_l1_block_number);
+        self.next_block_number_to_scrape = latest_l1_block_number + 1;
+        Ok(())
+    }
+
+    /// Rolls back the scraped ranges which are no longer on L1, so they are scraped again.
+    async fn handle_reorg(&mut self) -> L1ScraperResult<(), B::Error> {
+        // The events are rolled back in the reverse order they were sent, e.g., the cancellation of
+        // a message is rolled back before the message itself.
+        let mut rollback_events = Vec::new();
+        while let Some(range) = self.scraped_ranges.back() {
+            let block_number = range.last_block.number;
+            if self.l1_block_reference(block_number).await? == Some(range.last_block) {
+                break;
+            }
+
+            let range = self.scraped_ranges.pop_back().expect("Range exists.");
+            self.next_block_number_to_scrape = range.from_block_number;
+            rollback_events.extend(range.rollback_events.into_iter().rev());
+        }
+
+        if self.scraped_ranges.is_empty() {
+            warn!(
+                "L1 reorg is deeper than the last {} scraped blocks, rescanning from block {}.",
+                self.config.reorg_window, self.next_block_number_to_scrape
+            );
+        } else {
+            info!("L1 reorg detected, rescanning from block {}.", self.next_block_number_to_scrape);
+        }
+        if rollback_events.is_empty() {
+            return Ok(());
+        }
+
+        Ok(self.l1_provider_client.add_events(rollback_events).await?)
+    }
+
+    // Keeps the ranges which may still be reorged, and at least one range to link new blocks to.
+    fn prune_scraped_ranges(&mut self, latest_l1_block_number: u64) {
+        let oldest_block_number = latest_l1_block_number.saturating_sub(self.config.reorg_window);
+        while self.scraped_ranges.len() > 1
+            && self.scraped_ranges.front().expect("Ranges are not empty.").last_block.number
+                < oldest_block_number
+        {
+            self.scraped_ranges.pop_front();
+        }
+    }
+
+    async fn l1_block_reference(
+        &self,
+        block_number: u64,
+    ) -> L1ScraperResult<Option<L1BlockReference>, B::Error> {
+        self.base_layer.l1_block_reference(block_number).await.map_err(L1ScraperError::BaseLayer)
+    }
+
+    fn event_from_l1_event(&self, event: L1Event) -> L1ScraperResult<Event, B::Error> {
+        Ok(match event {
+            L1Event::LogMessageToL2 { tx, fee, .. } => {
+                let tx_hash = self.tx_hash(&tx)?;
+                Event::L1HandlerTransaction(ExecutableL1HandlerTransaction {
+                    tx,
+                    tx_hash,
+                    paid_fee_on_l1: fee,
+                })
+            }
+            L1Event::ConsumedMessageToL2(event_data) => {
+                Event::TransactionConsumed(self.tx_hash(&event_data.into())?)
+            }
+            L1Event::MessageToL2CancellationStarted(event_data) => {
+                Event::TransactionCancellationStarted(self.tx_hash(&event_data.into())?)
+            }
+            L1Event::MessageToL2Canceled(event_data) => {
+                Event::TransactionCanceled(self.tx_hash(&event_data.into())?)
+            }
+        })
+    }
+
+    fn tx_hash(&self, tx: &L1HandlerTransaction) -> L1ScraperResult<TransactionHash, B::Error> {
+        Ok(tx.calculate_transaction_hash(&self.config.chain_id, &L1HandlerTransaction::VERSION)?)
+    }
+}
+
+// Returns the event which undoes the given event once the L1 block that emitted it is reorged.
+fn rollback_event(event: &Event) -> Option<Event> {
+    match event {
+        Event::L1HandlerTransaction(tx) => Some(Event::TransactionReorged(tx.tx_hash)),
+        Event::TransactionConsumed(tx_hash) => Some(Event::TransactionConsumptionReorged(*tx_hash)),
+        Event::TransactionCancellationStarted(tx_hash) => {
+            Some(Event::TransactionCancellationReorged(*tx_hash))
+        }
+        // A message is canceled only after its cancellation started, which still applies as long
+        // as it is not reorged as well.
+        Event::TransactionCanceled(_) => None,
+        Event::TransactionReorged(_)
+        | Event::TransactionConsumptionReorged(_)
+        | Event::TransactionCancellationReorged(_) => None,
+    }
+}
+
+#[async_trait]
+impl<B> ComponentStarter for L1Scraper<B>
+where
+    B: BaseLayerContract + Send + Sync,
+    B::Error: std::error::Error + Send + Sync,
+{
+    async fn start(&mut self) -> Result<(), ComponentError> {
+        info!("Starting to scrape L1 from block {}.", self.next_block_number_to_scrape);
+        self.run().await.map_err(|err| {
+            error!("L1 scraper failed: {err}");
+            ComponentError::InternalComponentError
+        })
+    }
+}
+
+pub type L1ScraperServer = WrapperServer<L1Scraper<EthereumBaseLayerContract>>;
+
+pub fn create_l1_scraper(
+    config: L1ScraperConfig,
+    base_layer_config: EthereumBaseLayerConfig,
+    l1_provider_client: SharedL1ProviderClient,
+) -> L1Scraper<EthereumBaseLayerContract> {
+    L1Scraper::new(config, l1_provider_client, EthereumBaseLayerContract::new(base_layer_config))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Validate, PartialEq)]
+pub struct L1ScraperConfig {
+    pub l1_block_to_start_scraping_from: u64,
+    pub chain_id: ChainId,
+    pub finality: u64,
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub polling_interval: Duration,
+    pub reorg_window: u64,
+}
+
+impl Default for L1ScraperConfig {
+    fn default() -> Self {
+        Self {
+            l1_block_to_start_scraping_from: 0,
+            chain_id: ChainId::Mainnet,
+            finality: 0,
+            polling_interval: Duration::from_secs(1),
+            reorg_window: 64,
+        }
+    }
+}
+
+impl SerializeConfig for L1ScraperConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from([
+            ser_param(
+                "l1_block_to_start_scraping_from",
+                &self.l1_block_to_start_scraping_from,
+                "The first L1 block number to scrape.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "chain_id",
+                &self.chain_id,
+                "The chain ID of the Starknet chain, used to compute the L1 handler transaction \
+                 hashes.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "finality",
+                &self.finality,
+                "Number of L1 blocks to wait for finality before scraping a block.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "polling_interval",
+                &self.polling_interval.as_millis(),
+                "Interval in milliseconds between each scraping attempt of L1.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "reorg_window",
+                &self.reorg_window,
+                "Number of recent L1 blocks, beyond finality, in which reorgs are detected and \
+                 rolled back.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum L1ScraperError<E> {
+    #[error("Base layer error: {0}")]
+    BaseLayer(E),
+    #[error(transparent)]
+    HashCalculationError(#[from] StarknetApiError),
+    #[error(transparent)]
+    NetworkError(#[from] L1ProviderClientError),
+}
@@ -0,0 +1,295 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use assert_matches::assert_matches;
+use papyrus_base_layer::test_utils::{FakeBaseLayerContract, FakeBaseLayerError};
+use papyrus_base_layer::{EventData, L1Event};
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ContractAddress, EntryPointSelector, EthAddress, Nonce};
+use starknet_api::transaction::fields::Fee;
+use starknet_api::transaction::{
+    L1HandlerTransaction,
+    L1TransactionHash,
+    TransactionHash,
+    TransactionHasher,
+};
+use starknet_api::{calldata, felt};
+use starknet_l1_provider_types::errors::{L1ProviderClientError, L1ProviderError};
+use starknet_l1_provider_types::{
+    Event,
+    L1ProviderClientResult,
+    MockL1ProviderClient,
+    SessionState,
+    ValidationStatus,
+};
+use crate::l1_scraper::{L1Scraper, L1ScraperConfig, L1ScraperError};
+use crate::{L1Provider, L1ProviderConfig};
+
+fn event_data(nonce: u8) -> EventData {
+    EventData {
+        from_address: EthAddress::try_from(felt!("0x1234")).unwrap(),
+        to_address: ContractAddress::from(0x5678_u128),
+        entry_point_selector: EntryPointSelector(felt!("0x9abc")),
+        payload: calldata![felt!(1_u8), felt!(2_u8)],
+        nonce: Nonce(felt!(nonce)),
+    }
+}
+
+fn log_message(nonce: u8) -> L1Event {
+    L1Event::LogMessageToL2 {
+        tx: L1HandlerTransaction::from(event_data(nonce)),
+        fee: Fee(1_000),
+        l1_tx_hash: L1TransactionHash::default(),
+    }
+}
+
+fn tx_hash(nonce: u8) -> TransactionHash {
+    L1HandlerTransaction::from(event_data(nonce))
+        .calculate_transaction_hash(
+            &L1ScraperConfig::default().chain_id,
+            &L1HandlerTransaction::VERSION,
+        )
+        .unwrap()
+}
+
+// Forwards the scraped events to a real provider, shared with the test.
+fn scraper_with_provider(
+    config: L1ScraperConfig,
+) -> (L1Scraper<FakeBaseLayerContract>, Arc<Mutex<L1Provider>>) {
+    let l1_provider = Arc::new(Mutex::new(L1Provider::new(L1ProviderConfig::default()).unwrap()));
+    let mut l1_provider_client = MockL1ProviderClient::new();
+    let provider = l1_provider.clone();
+    l1_provider_client.expect_add_events().returning(move |events| -> L1ProviderClientResult<()> {
+        Ok(provider.lock().unwrap().add_events(events)?)
+    });
+    let scraper =
+        L1Scraper::new(config, Arc::new(l1_provider_client), FakeBaseLayerContract::default());
+    (scraper, l1_provider)
+}
+
+#[tokio::test]
+async fn message_lifecycle() {
+    // Setup.
+    let (mut scraper, l1_provider) = scraper_with_provider(L1ScraperConfig::default());
+
+    // Test.
+    // A message is sent to L2 and proposed in the next block.
+    scraper.base_layer.add_block([log_message(0)]);
+    scraper.send_events_to_l1_provider().await.unwrap();
+    {
+        let mut l1_provider = l1_provider.lock().unwrap();
+        l1_provider.start_block(SessionState::Propose, BlockNumber(0)).unwrap();
+        let txs = l1_provider.get_txs(10).unwrap();
+        assert_eq!(txs.iter().map(|tx| tx.tx_hash).collect::<Vec<_>>(), [tx_hash(0)]);
+        assert_eq!(txs[0].paid_fee_on_l1, Fee(1_000));
+        l1_provider.commit_block(&[tx_hash(0)], BlockNumber(0)).unwrap();
+
+        l1_provider.start_block(SessionState::Validate, BlockNumber(1)).unwrap();
+        assert_eq!(
+            l1_provider.validate(tx_hash(0)).unwrap(),
+            ValidationStatus::AlreadyIncludedOnL2
+        );
+        l1_provider.commit_block(&[], BlockNumber(1)).unwrap();
+    }
+
+    // The message is consumed on L1, after which it is forgotten.
+    scraper.base_layer.add_block([L1Event::ConsumedMessageToL2(event_data(0))]);
+    scraper.send_events_to_l1_provider().await.unwrap();
+    let mut l1_provider = l1_provider.lock().unwrap();
+    l1_provider.start_block(SessionState::Validate, BlockNumber(2)).unwrap();
+    assert_eq!(l1_provider.validate(tx_hash(0)).unwrap(), ValidationStatus::ConsumedOnL1OrUnknown);
+}
+
+#[tokio::test]
+async fn canceled_messages_are_not_proposed() {
+    // Setup.
+    let (mut scraper, l1_provider) = scraper_with_provider(L1ScraperConfig::default());
+    scraper.base_layer.add_block([log_message(0), log_message(1), log_message(2)]);
+    scraper.base_layer.add_block([
+        L1Event::MessageToL2CancellationStarted(event_data(0)),
+        L1Event::MessageToL2Canceled(event_data(1)),
+    ]);
+
+    // Test.
+    scraper.send_events_to_l1_provider().await.unwrap();
+    let mut l1_provider = l1_provider.lock().unwrap();
+    l1_provider.start_block(SessionState::Propose, BlockNumber(0)).unwrap();
+    let txs = l1_provider.get_txs(10).unwrap();
+    assert_eq!(txs.iter().map(|tx| tx.tx_hash).collect::<Vec<_>>(), [tx_hash(2)]);
+}
+
+#[tokio::test]
+async fn scrapes_each_final_block_once() {
+    // Setup.
+    let config = L1ScraperConfig { finality: 1, ..Default::default() };
+    let mut l1_provider_client = MockL1ProviderClient::new();
+    l1_provider_client
+        .expect_add_events()
+        .withf(|events| events == &[Event::TransactionConsumed(tx_hash(0))])
+        .times(1)
+        .returning(|_| Ok(()));
+    l1_provider_client
+        .expect_add_events()
+        .withf(|events| events == &[Event::TransactionConsumed(tx_hash(1))])
+        .times(1)
+        .returning(|_| Ok(()));
+    let mut scraper =
+        L1Scraper::new(config, Arc::new(l1_provider_client), FakeBaseLayerContract::default());
+
+    // Test.
+    // No block reached finality yet.
+    scraper.base_layer.add_block([L1Event::ConsumedMessageToL2(event_data(0))]);
+    scraper.send_events_to_l1_provider().await.unwrap();
+    assert_eq!(scraper.next_block_number_to_scrape, 0);
+
+    scraper.base_layer.add_block([L1Event::ConsumedMessageToL2(event_data(1))]);
+    scraper.send_events_to_l1_provider().await.unwrap();
+    assert_eq!(scraper.next_block_number_to_scrape, 1);
+
+    // Nothing new to scrape.
+    scraper.send_events_to_l1_provider().await.unwrap();
+
+    scraper.base_layer.add_block([]);
+    scraper.send_events_to_l1_provider().await.unwrap();
+    assert_eq!(scraper.next_block_number_to_scrape, 2);
+}
+
+#[tokio::test]
+async fn base_layer_errors_are_retried_from_the_same_block() {
+    // Setup.
+    let (mut scraper, l1_provider) = scraper_with_provider(L1ScraperConfig::default());
+    scraper.base_layer.add_block([log_message(0)]);
+    scraper.base_layer.set_unavailable(true);
+
+    // Test.
+    assert_matches!(
+        scraper.send_events_to_l1_provider().await,
+        Err(L1ScraperError::BaseLayer(FakeBaseLayerError))
+    );
+    assert_eq!(scraper.next_block_number_to_scrape, 0);
+
+    scraper.base_layer.set_unavailable(false);
+    scraper.send_events_to_l1_provider().await.unwrap();
+    let mut l1_provider = l1_provider.lock().unwrap();
+    l1_provider.start_block(SessionState::Propose, BlockNumber(0)).unwrap();
+    assert_eq!(l1_provider.get_txs(10).unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn run_retries_failed_sends_to_the_provider() {
+    // Setup.
+    let l1_provider = Arc::new(Mutex::new(L1Provider::new(L1ProviderConfig::default()).unwrap()));
+    let mut l1_provider_client = MockL1ProviderClient::new();
+    let provider = l1_provider.clone();
+    let mut n_calls = 0;
+    l1_provider_client.expect_add_events().returning(move |events| -> L1ProviderClientResult<()> {
+        n_calls += 1;
+        if n_calls == 1 {
+            return Err(L1ProviderClientError::L1ProviderError(L1ProviderError::Uninitialized));
+        }
+        Ok(provider.lock().unwrap().add_events(events)?)
+    });
+    let config =
+        L1ScraperConfig { polling_interval: Duration::from_millis(1), ..Default::default() };
+    let mut scraper =
+        L1Scraper::new(config, Arc::new(l1_provider_client), FakeBaseLayerContract::default());
+    scraper.base_layer.add_block([log_message(0)]);
+
+    // Test.
+    // The scraper keeps running after the provider fails, and sends the events again.
+    tokio::time::timeout(Duration::from_millis(100), scraper.run())
+        .await
+        .expect_err("The scraper should keep running.");
+    assert_eq!(scraper.next_block_number_to_scrape, 1);
+    let mut l1_provider = l1_provider.lock().unwrap();
+    l1_provider.start_block(SessionState::Propose, BlockNumber(0)).unwrap();
+    assert_eq!(l1_provider.get_txs(10).unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn reorg_rolls_back_reorged_l1_handler_txs() {
+    // Setup.
+    let (mut scraper, l1_provider) = scraper_with_provider(L1ScraperConfig::default());
+    scraper.base_layer.add_block([log_message(0)]);
+    scraper.base_layer.add_block([log_message(1)]);
+    scraper.send_events_to_l1_provider().await.unwrap();
+    scraper.base_layer.add_block([log_message(2)]);
+    scraper.send_events_to_l1_provider().await.unwrap();
+
+    // Test.
+    // Block 2 is replaced, which is noticed once the new chain extends it.
+    scraper.base_layer.reorg(2);
+    scraper.base_layer.add_block([log_message(3)]);
+    scraper.base_layer.add_block([]);
+    scraper.send_events_to_l1_provider().await.unwrap();
+    assert_eq!(scraper.next_block_number_to_scrape, 2);
+    scraper.send_events_to_l1_provider().await.unwrap();
+    assert_eq!(scraper.next_block_number_to_scrape, 4);
+
+    let mut l1_provider = l1_provider.lock().unwrap();
+    l1_provider.start_block(SessionState::Propose, BlockNumber(0)).unwrap();
+    let txs = l1_provider.get_txs(10).unwrap();
+    assert_eq!(
+        txs.iter().map(|tx| tx.tx_hash).collect::<Vec<_>>(),
+        [tx_hash(0), tx_hash(1), tx_hash(3)]
+    );
+}
+
+#[tokio::test]
+async fn reorg_rolls_back_reorged_consumptions_and_cancellations() {
+    // Setup.
+    let (mut scraper, l1_provider) = scraper_with_provider(L1ScraperConfig::default());
+    scraper.base_layer.add_block([log_message(0), log_message(1)]);
+    scraper.send_events_to_l1_provider().await.unwrap();
+    {
+        let mut l1_provider = l1_provider.lock().unwrap();
+        l1_provider.start_block(SessionState::Propose, BlockNumber(0)).unwrap();
+        assert_eq!(l1_provider.get_txs(1).unwrap()[0].tx_hash, tx_hash(0));
+        l1_provider.commit_block(&[tx_hash(0)], BlockNumber(0)).unwrap();
+    }
+    scraper.base_layer.add_block([
+        L1Event::ConsumedMessageToL2(event_data(0)),
+        L1Event::MessageToL2CancellationStarted(event_data(1)),
+    ]);
+    scraper.send_events_to_l1_provider().await.unwrap();
+
+    // Test.
+    scraper.base_layer.reorg(1);
+    scraper.base_layer.add_block([]);
+    scraper.base_layer.add_block([]);
+    scraper.send_events_to_l1_provider().await.unwrap();
+    assert_eq!(scraper.next_block_number_to_scrape, 1);
+
+    let mut l1_provider = l1_provider.lock().unwrap();
+    l1_provider.start_block(SessionState::Validate, BlockNumber(1)).unwrap();
+    assert_eq!(l1_provider.validate(tx_hash(0)).unwrap(), ValidationStatus::AlreadyIncludedOnL2);
+    assert_eq!(l1_provider.validate(tx_hash(1)).unwrap(), ValidationStatus::Validated);
+}
+
+#[tokio::test]
+async fn reorg_deeper_than_window() {
+    // Setup.
+    let config = L1ScraperConfig { reorg_window: 0, ..Default::default() };
+    let (mut scraper, l1_provider) = scraper_with_provider(config);
+    for nonce in 0..3 {
+        scraper.base_layer.add_block([log_message(nonce)]);
+        scraper.send_events_to_l1_provider().await.unwrap();
+    }
+
+    // Test.
+    scraper.base_layer.reorg(1);
+    scraper.base_layer.add_block([]);
+    scraper.base_layer.add_block([]);
+    scraper.base_layer.add_block([]);
+    scraper.send_events_to_l1_provider().await.unwrap();
+    // Only the last scraped block is remembered, so it is the only one rolled back.
+    assert_eq!(scraper.next_block_number_to_scrape, 2);
+    scraper.send_events_to_l1_provider().await.unwrap();
+    assert_eq!(scraper.next_block_number_to_scrape, 4);
+
+    let mut l1_provider = l1_provider.lock().unwrap();
+    l1_provider.start_block(SessionState::Propose, BlockNumber(0)).unwrap();
+    let txs = l1_provider.get_txs(10).unwrap();
+    assert_eq!(txs.iter().map(|tx| tx.tx_hash).collect::<Vec<_>>(), [tx_hash(0), tx_hash(1)]);
+}
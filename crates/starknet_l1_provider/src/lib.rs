@@ -1,5 +1,5 @@
 pub mod communication;
-pub mod errors;
+pub mod l1_scraper;
 
 #[cfg(test)]
 pub mod test_utils;
@@ -12,11 +12,13 @@ use papyrus_config::converters::deserialize_milliseconds_to_duration;
 use papyrus_config::dumping::{ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
 use starknet_api::executable_transaction::L1HandlerTransaction;
 use starknet_api::transaction::TransactionHash;
 use starknet_l1_provider_types::errors::L1ProviderError;
-use starknet_l1_provider_types::{L1ProviderResult, ValidationStatus};
+use starknet_l1_provider_types::{Event, L1ProviderResult, SessionState, ValidationStatus};
 use starknet_sequencer_infra::component_definitions::ComponentStarter;
+use tracing::{debug, warn};
 use validator::Validate;
 
 #[cfg(test)]
@@ -31,11 +33,14 @@ pub struct L1Provider {
     // TODO(Gilad): consider transitioning to a generic phantom state once the infra is stabilized
     // and we see how well it handles consuming the L1Provider when moving between states.
     state: ProviderState,
+    // The height of the next block to be committed, known once the first block is started or
+    // committed.
+    current_height: Option<BlockNumber>,
 }
 
 impl L1Provider {
     pub fn new(_config: L1ProviderConfig) -> L1ProviderResult<Self> {
-        todo!("Init crawler in uninitialized_state from config, to initialize call `reset`.");
+        Ok(Self { state: ProviderState::Pending, ..Default::default() })
     }
 
     /// Retrieves up to `n_txs` transactions that have yet to be proposed or accepted on L2.
@@ -59,17 +64,73 @@ impl L1Provider {
         }
     }
 
-    // TODO: when deciding on consensus, if possible, have commit_block also tell the node if it's
-    // about to [optimistically-]propose or validate the next block.
-    pub fn commit_block(&mut self, _commited_txs: &[TransactionHash]) {
-        todo!(
-            "Purges txs from internal buffers, if was proposer clear staging buffer, 
-            reset state to Pending until we get proposing/validating notice from consensus."
-        )
+    /// Starts proposing or validating the block at `height`. A block that was already started is
+    /// restarted, which happens when consensus moves to a new round of the same height.
+    pub fn start_block(
+        &mut self,
+        state: SessionState,
+        height: BlockNumber,
+    ) -> L1ProviderResult<()> {
+        self.validate_height(height)?;
+        if self.state.is_in_session() {
+            debug!("Restarting block {height} in a new round, previously in {}.", self.state);
+            self.state = self.state.transition_to_pending()?;
+            self.tx_manager.clear_proposed();
+        }
+
+        match state {
+            SessionState::Propose => self.proposal_start(),
+            SessionState::Validate => self.validation_start(),
+        }
+    }
+
+    /// Purges the committed transactions from the internal buffers and waits in `Pending` until
+    /// the next block is started. The committed transactions may include any transaction type,
+    /// only the known L1 handler transactions among them are tracked.
+    pub fn commit_block(
+        &mut self,
+        committed_txs: &[TransactionHash],
+        height: BlockNumber,
+    ) -> L1ProviderResult<()> {
+        self.validate_height(height)?;
+        self.state = self.state.transition_to_pending()?;
+        self.tx_manager.clear_proposed();
+        for tx_hash in committed_txs {
+            self.tx_manager.mark_tx_included_on_l2(tx_hash);
+        }
+        self.current_height = Some(height.unchecked_next());
+        Ok(())
+    }
+
+    /// Applies the changes observed on L1 by the scraper.
+    pub fn add_events(&mut self, events: Vec<Event>) -> L1ProviderResult<()> {
+        if self.state == ProviderState::Uninitialized {
+            return Err(L1ProviderError::Uninitialized);
+        }
+
+        for event in events {
+            match event {
+                Event::L1HandlerTransaction(tx) => {
+                    self.tx_manager.add_unconsumed_l1_not_in_l2_block_tx(tx)
+                }
+                Event::TransactionConsumed(tx_hash) => self.tx_manager.consume_tx(&tx_hash),
+                // A message whose cancellation started may be canceled at any time, so it is no
+                // longer safe to include it in a block.
+                Event::TransactionCancellationStarted(tx_hash)
+                | Event::TransactionCanceled(tx_hash) => self.tx_manager.cancel_tx(&tx_hash),
+                Event::TransactionReorged(tx_hash) => self.handle_reorg(&tx_hash),
+                Event::TransactionConsumptionReorged(tx_hash) => {
+                    self.tx_manager.rollback_consumption(&tx_hash)
+                }
+                Event::TransactionCancellationReorged(tx_hash) => {
+                    self.tx_manager.rollback_cancellation(&tx_hash)
+                }
+            }
+        }
+        Ok(())
     }
 
     // TODO: pending formal consensus API, guessing the API here to keep things moving.
-    // TODO: consider adding block number, it isn't strictly necessary, but will help debugging.
     pub fn validation_start(&mut self) -> L1ProviderResult<()> {
         self.state = self.state.transition_to_validate()?;
         Ok(())
@@ -80,28 +141,22 @@ impl L1Provider {
         Ok(())
     }
 
-    /// Simple recovery from L1 and L2 reorgs by reseting the service, which rewinds L1 and L2
-    /// information.
-    pub async fn handle_reorg(&mut self) -> L1ProviderResult<()> {
-        self.reset().await
-    }
-
-    // TODO: this will likely change during integration with infra team.
-    pub async fn start(&self) {
-        todo!(
-            "Create a process that wakes up every config.poll_interval seconds and updates
-        internal L1 and L2 buffers according to collected L1 events and recent blocks created on
-        L2."
-        )
+    /// Recovery from an L1 reorg which dropped the message of the given transaction. L2 blocks are
+    /// final, so a transaction which was already proposed or included on L2 is kept.
+    pub fn handle_reorg(&mut self, tx_hash: &TransactionHash) {
+        self.tx_manager.rollback_tx(tx_hash);
     }
 
-    pub async fn reset(&mut self) -> L1ProviderResult<()> {
-        todo!(
-            "resets internal buffers and rewinds the internal crawler _pointer_ back for ~1 \
-             hour,so that the main loop will start collecting from that time gracefully. May hit \
-             base layer errors when finding the latest block on l1 to 'subtract' 1 hour from. \
-             Then, transition to Pending."
-        );
+    fn validate_height(&mut self, height: BlockNumber) -> L1ProviderResult<()> {
+        match self.current_height {
+            Some(expected_height) if expected_height != height => {
+                Err(L1ProviderError::UnexpectedHeight { expected_height, got: height })
+            }
+            _ => {
+                self.current_height = Some(height);
+                Ok(())
+            }
+        }
     }
 }
 
@@ -112,6 +167,10 @@ struct TransactionManager {
     txs: IndexMap<TransactionHash, L1HandlerTransaction>,
     proposed_txs: IndexSet<TransactionHash>,
     on_l2_awaiting_l1_consumption: IndexSet<TransactionHash>,
+    // Transactions whose messages were consumed or are being canceled on L1, kept in case the L1
+    // block which did so is reorged.
+    consumed_txs: IndexSet<TransactionHash>,
+    canceled_txs: IndexMap<TransactionHash, L1HandlerTransaction>,
 }
 
 impl TransactionManager {
@@ -138,15 +197,82 @@ impl TransactionManager {
         }
     }
 
-    pub fn _add_unconsumed_l1_not_in_l2_block_tx(&mut self, _tx: L1HandlerTransaction) {
-        todo!(
-            "Check if tx is in L2, if it isn't on L2 add it to the txs buffer, otherwise print
-             debug and do nothing."
-        )
+    pub fn add_unconsumed_l1_not_in_l2_block_tx(&mut self, tx: L1HandlerTransaction) {
+        let tx_hash = tx.tx_hash;
+        if self.on_l2_awaiting_l1_consumption.contains(&tx_hash) {
+            debug!("Transaction {tx_hash} is already included on L2, ignoring it.");
+            return;
+        }
+        self.txs.entry(tx_hash).or_insert(tx);
+    }
+
+    /// Marks a known L1 handler transaction as included on L2, other transactions are ignored.
+    pub fn mark_tx_included_on_l2(&mut self, tx_hash: &TransactionHash) {
+        if self.remove_tx(tx_hash).is_some() {
+            self.on_l2_awaiting_l1_consumption.insert(*tx_hash);
+        }
+    }
+
+    /// Forgets a transaction whose message was consumed on L1.
+    pub fn consume_tx(&mut self, tx_hash: &TransactionHash) {
+        if self.remove_tx(tx_hash).is_some() {
+            warn!("Transaction {tx_hash} was consumed on L1 before it was included on L2.");
+        }
+        if self.on_l2_awaiting_l1_consumption.shift_remove(tx_hash) {
+            self.consumed_txs.insert(*tx_hash);
+        }
+    }
+
+    /// Stops offering a transaction whose message is being canceled on L1.
+    pub fn cancel_tx(&mut self, tx_hash: &TransactionHash) {
+        match self.remove_tx(tx_hash) {
+            Some(tx) => {
+                self.canceled_txs.insert(*tx_hash, tx);
+            }
+            None => debug!("Canceled transaction {tx_hash} is not pending inclusion on L2."),
+        }
+    }
+
+    /// Forgets a transaction whose message is no longer on L1, unless it was already proposed.
+    pub fn rollback_tx(&mut self, tx_hash: &TransactionHash) {
+        self.canceled_txs.shift_remove(tx_hash);
+        if self.proposed_txs.contains(tx_hash) {
+            warn!("Reorged transaction {tx_hash} was already proposed, keeping it.");
+            return;
+        }
+        if self.remove_tx(tx_hash).is_none() {
+            debug!("Reorged transaction {tx_hash} is not pending inclusion on L2.");
+        }
+    }
+
+    /// Awaits the consumption of a transaction included on L2 again, since the L1 block which
+    /// consumed its message is no longer on L1. A transaction consumed on L1 before it was included
+    /// on L2 is not restored.
+    pub fn rollback_consumption(&mut self, tx_hash: &TransactionHash) {
+        if self.consumed_txs.shift_remove(tx_hash) {
+            self.on_l2_awaiting_l1_consumption.insert(*tx_hash);
+        } else {
+            debug!("Transaction {tx_hash} of the reorged consumption was not included on L2.");
+        }
+    }
+
+    /// Offers a transaction again, since the L1 block which started the cancellation of its message
+    /// is no longer on L1.
+    pub fn rollback_cancellation(&mut self, tx_hash: &TransactionHash) {
+        match self.canceled_txs.shift_remove(tx_hash) {
+            Some(tx) => self.add_unconsumed_l1_not_in_l2_block_tx(tx),
+            None => debug!("Transaction {tx_hash} of the reorged cancellation is not known."),
+        }
     }
 
-    pub fn _mark_tx_included_on_l2(&mut self, _tx_hash: &TransactionHash) {
-        todo!("Adds the tx hash to l2 buffer; remove tx from the txs storage if it's there.")
+    pub fn clear_proposed(&mut self) {
+        self.proposed_txs.clear();
+    }
+
+    // Proposed transactions are a prefix of `txs`, so they are removed from both to keep it so.
+    fn remove_tx(&mut self, tx_hash: &TransactionHash) -> Option<L1HandlerTransaction> {
+        self.proposed_txs.shift_remove(tx_hash);
+        self.txs.shift_remove(tx_hash)
     }
 }
 
@@ -175,8 +301,17 @@ impl ProviderState {
         }
     }
 
-    fn _transition_to_pending(self) -> L1ProviderResult<Self> {
-        todo!()
+    fn transition_to_pending(self) -> L1ProviderResult<Self> {
+        match self {
+            ProviderState::Uninitialized => {
+                Err(L1ProviderError::unexpected_transition(self, ProviderState::Pending))
+            }
+            _ => Ok(ProviderState::Pending),
+        }
+    }
+
+    fn is_in_session(self) -> bool {
+        matches!(self, ProviderState::Propose | ProviderState::Validate)
     }
 
     pub fn as_str(&self) -> &str {
@@ -212,6 +347,6 @@ impl SerializeConfig for L1ProviderConfig {
     }
 }
 
-pub fn create_l1_provider(_config: L1ProviderConfig) -> L1Provider {
-    L1Provider { state: ProviderState::Propose, ..Default::default() }
+pub fn create_l1_provider(config: L1ProviderConfig) -> L1Provider {
+    L1Provider::new(config).expect("Failed to create the L1 provider.")
 }
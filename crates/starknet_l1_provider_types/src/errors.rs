@@ -1,6 +1,7 @@
 use std::fmt::Debug;
 
 use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
 use starknet_sequencer_infra::component_client::ClientError;
 use thiserror::Error;
 
@@ -12,6 +13,8 @@ pub enum L1ProviderError {
     GetTransactionsInPendingState,
     #[error("`get_txs` while in validate state")]
     GetTransactionConsensusBug,
+    #[error("Expected a block at height {expected_height}, got {got}")]
+    UnexpectedHeight { expected_height: BlockNumber, got: BlockNumber },
     #[error("Cannot transition from {from} to {to}")]
     UnexpectedProviderStateTransition { from: String, to: String },
     #[error(
@@ -20,6 +23,8 @@ pub enum L1ProviderError {
     ValidateInPendingState,
     #[error("`validate` called while in `Propose`")]
     ValidateTransactionConsensusBug,
+    #[error("The L1 provider is uninitialized")]
+    Uninitialized,
 }
 
 impl L1ProviderError {
@@ -7,6 +7,7 @@ use async_trait::async_trait;
 use mockall::automock;
 use papyrus_proc_macros::handle_response_variants;
 use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
 use starknet_api::executable_transaction::L1HandlerTransaction;
 use starknet_api::transaction::TransactionHash;
 use starknet_sequencer_infra::component_client::ClientError;
@@ -19,21 +20,55 @@ pub type L1ProviderResult<T> = Result<T, L1ProviderError>;
 pub type L1ProviderClientResult<T> = Result<T, L1ProviderClientError>;
 pub type SharedL1ProviderClient = Arc<dyn L1ProviderClient>;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ValidationStatus {
     Validated,
     AlreadyIncludedOnL2,
     ConsumedOnL1OrUnknown,
 }
 
+/// The role of the node in the block currently being built.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SessionState {
+    Propose,
+    Validate,
+}
+
+/// Changes in the status of L1 to L2 messages, as observed on L1.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Event {
+    /// A message was sent to L2; it can be included in an L2 block until it is consumed on L1.
+    L1HandlerTransaction(L1HandlerTransaction),
+    /// The message was consumed on L1, after its transaction was included on L2.
+    TransactionConsumed(TransactionHash),
+    /// The sender started the cancellation of the message.
+    TransactionCancellationStarted(TransactionHash),
+    /// The message was canceled, and can no longer be included on L2.
+    TransactionCanceled(TransactionHash),
+    /// The L1 block which sent the message was reorged out of L1.
+    TransactionReorged(TransactionHash),
+    /// The L1 block which consumed the message was reorged out of L1.
+    TransactionConsumptionReorged(TransactionHash),
+    /// The L1 block which started the cancellation of the message was reorged out of L1.
+    TransactionCancellationReorged(TransactionHash),
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum L1ProviderRequest {
+    AddEvents(Vec<Event>),
+    CommitBlock { tx_hashes: Vec<TransactionHash>, height: BlockNumber },
     GetTransactions(usize),
+    StartBlock { state: SessionState, height: BlockNumber },
+    Validate(TransactionHash),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum L1ProviderResponse {
+    AddEvents(L1ProviderResult<()>),
+    CommitBlock(L1ProviderResult<()>),
     GetTransactions(L1ProviderResult<Vec<L1HandlerTransaction>>),
+    StartBlock(L1ProviderResult<()>),
+    Validate(L1ProviderResult<ValidationStatus>),
 }
 
 /// Serves as the provider's shared interface. Requires `Send + Sync` to allow transferring and
@@ -42,8 +77,21 @@ pub enum L1ProviderResponse {
 #[async_trait]
 pub trait L1ProviderClient: Send + Sync {
     async fn get_txs(&self, n_txs: usize) -> L1ProviderClientResult<Vec<L1HandlerTransaction>>;
-    async fn validate(&self, _tx_hash: TransactionHash)
-    -> L1ProviderClientResult<ValidationStatus>;
+    async fn validate(&self, tx_hash: TransactionHash) -> L1ProviderClientResult<ValidationStatus>;
+    /// Notifies the provider that the node starts proposing or validating the block at `height`.
+    async fn start_block(
+        &self,
+        state: SessionState,
+        height: BlockNumber,
+    ) -> L1ProviderClientResult<()>;
+    /// Notifies the provider that the block at `height` was committed with the given transactions.
+    /// Transactions which aren't L1 handler transactions known to the provider are ignored.
+    async fn commit_block(
+        &self,
+        tx_hashes: Vec<TransactionHash>,
+        height: BlockNumber,
+    ) -> L1ProviderClientResult<()>;
+    async fn add_events(&self, events: Vec<Event>) -> L1ProviderClientResult<()>;
 }
 
 #[async_trait]
@@ -62,10 +110,60 @@ where
             L1ProviderError
         )
     }
-    async fn validate(
+
+    #[instrument(skip(self))]
+    async fn validate(&self, tx_hash: TransactionHash) -> L1ProviderClientResult<ValidationStatus> {
+        let request = L1ProviderRequest::Validate(tx_hash);
+        let response = self.send(request).await;
+        handle_response_variants!(
+            L1ProviderResponse,
+            Validate,
+            L1ProviderClientError,
+            L1ProviderError
+        )
+    }
+
+    #[instrument(skip(self))]
+    async fn start_block(
         &self,
-        _tx_hash: TransactionHash,
-    ) -> L1ProviderClientResult<ValidationStatus> {
-        todo!();
+        state: SessionState,
+        height: BlockNumber,
+    ) -> L1ProviderClientResult<()> {
+        let request = L1ProviderRequest::StartBlock { state, height };
+        let response = self.send(request).await;
+        handle_response_variants!(
+            L1ProviderResponse,
+            StartBlock,
+            L1ProviderClientError,
+            L1ProviderError
+        )
+    }
+
+    #[instrument(skip(self))]
+    async fn commit_block(
+        &self,
+        tx_hashes: Vec<TransactionHash>,
+        height: BlockNumber,
+    ) -> L1ProviderClientResult<()> {
+        let request = L1ProviderRequest::CommitBlock { tx_hashes, height };
+        let response = self.send(request).await;
+        handle_response_variants!(
+            L1ProviderResponse,
+            CommitBlock,
+            L1ProviderClientError,
+            L1ProviderError
+        )
+    }
+
+    #[instrument(skip_all)]
+    async fn add_events(&self, events: Vec<Event>) -> L1ProviderClientResult<()> {
+        let request = L1ProviderRequest::AddEvents(events);
+        let response = self.send(request).await;
+        handle_response_variants!(
+            L1ProviderResponse,
+            AddEvents,
+            L1ProviderClientError,
+            L1ProviderError
+        )
     }
 }
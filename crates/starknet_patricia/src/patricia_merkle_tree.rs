@@ -1,5 +1,6 @@
 pub mod errors;
 pub mod filled_tree;
+pub mod merkle_proof;
 pub mod node_data;
 pub mod original_skeleton_tree;
 pub mod types;
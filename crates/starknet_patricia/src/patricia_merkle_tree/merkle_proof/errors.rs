@@ -0,0 +1,13 @@
+use std::fmt::Debug;
+
+use thiserror::Error;
+
+use crate::storage::errors::{DeserializationError, StorageError};
+
+#[derive(Debug, Error)]
+pub enum MerkleProofError {
+    #[error("Failed to deserialize the storage value: {0:?} while extracting a Merkle proof.")]
+    Deserialization(#[from] DeserializationError),
+    #[error("Unable to read from storage the storage key: {0:?} while extracting a Merkle proof.")]
+    StorageRead(#[from] StorageError),
+}
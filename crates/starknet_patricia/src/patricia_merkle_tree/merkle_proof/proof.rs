@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::node::FilledNode;
+use crate::patricia_merkle_tree::merkle_proof::errors::MerkleProofError;
+use crate::patricia_merkle_tree::node_data::inner_node::{BinaryData, EdgeData, NodeData};
+use crate::patricia_merkle_tree::node_data::leaf::Leaf;
+use crate::patricia_merkle_tree::original_skeleton_tree::create_tree::SubTree;
+use crate::patricia_merkle_tree::types::{NodeIndex, SortedLeafIndices};
+use crate::storage::errors::StorageError;
+use crate::storage::storage_trait::{StorageKey, TryReadOnlyStorage};
+
+#[cfg(test)]
+#[path = "proof_test.rs"]
+pub mod proof_test;
+
+pub type MerkleProofResult<T> = Result<T, MerkleProofError>;
+
+/// The data of an inner node in a Merkle proof, i.e., the pre-image of its hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Preimage {
+    Binary(BinaryData),
+    Edge(EdgeData),
+}
+
+pub type PreimageMap = HashMap<HashOutput, Preimage>;
+
+/// A proof of the values of some leaves in a Patricia-Merkle tree with a given root. Consists of
+/// the inner nodes on the paths from the root to the leaves, from which the root hash can be
+/// recomputed. A leaf that is not in the tree is proven by an edge node that diverges from its
+/// path, and its value is the empty leaf.
+#[derive(Debug, PartialEq)]
+pub struct MerkleProof<L: Leaf> {
+    pub nodes: PreimageMap,
+    pub leaves: HashMap<NodeIndex, L>,
+}
+
+impl<L: Leaf> MerkleProof<L> {
+    /// Extracts from storage the proof of the given leaves in the tree with the given root.
+    /// Traverses the tree from the root towards the leaves layer by layer, the same way the
+    /// original skeleton tree is built, and fetches every node on the way. Fails on the first
+    /// failed read.
+    pub fn fetch(
+        storage: &impl TryReadOnlyStorage,
+        root_hash: HashOutput,
+        sorted_leaf_indices: SortedLeafIndices<'_>,
+    ) -> MerkleProofResult<Self> {
+        let mut proof = Self { nodes: HashMap::new(), leaves: HashMap::new() };
+        if sorted_leaf_indices.is_empty() {
+            return Ok(proof);
+        }
+        if root_hash == HashOutput::ROOT_OF_EMPTY_TREE {
+            proof.add_empty_leaves(sorted_leaf_indices.get_indices());
+            return Ok(proof);
+        }
+        let mut subtrees =
+            vec![SubTree { sorted_leaf_indices, root_index: NodeIndex::ROOT, root_hash }];
+        while !subtrees.is_empty() {
+            let mut next_subtrees = Vec::new();
+            let filled_roots = Self::fetch_subtrees_roots(&subtrees, storage)?;
+            for (filled_root, subtree) in filled_roots.into_iter().zip(subtrees.iter()) {
+                match filled_root.data {
+                    NodeData::Binary(binary_data) => {
+                        let (left_subtree, right_subtree) = subtree
+                            .get_children_subtrees(binary_data.left_hash, binary_data.right_hash);
+                        proof.nodes.insert(filled_root.hash, Preimage::Binary(binary_data));
+                        next_subtrees.extend(
+                            [left_subtree, right_subtree]
+                                .into_iter()
+                                .filter(|child| !child.is_unmodified()),
+                        );
+                    }
+                    NodeData::Edge(edge_data) => {
+                        let (bottom_subtree, empty_leaves_indices) = subtree
+                            .get_bottom_subtree(&edge_data.path_to_bottom, edge_data.bottom_hash);
+                        proof.nodes.insert(filled_root.hash, Preimage::Edge(edge_data));
+                        proof.add_empty_leaves(empty_leaves_indices);
+                        if !bottom_subtree.is_unmodified() {
+                            next_subtrees.push(bottom_subtree);
+                        }
+                    }
+                    NodeData::Leaf(leaf) => {
+                        proof.leaves.insert(subtree.root_index, leaf);
+                    }
+                }
+            }
+            subtrees = next_subtrees;
+        }
+        Ok(proof)
+    }
+
+    fn fetch_subtrees_roots(
+        subtrees: &[SubTree<'_>],
+        storage: &impl TryReadOnlyStorage,
+    ) -> MerkleProofResult<Vec<FilledNode<L>>> {
+        let db_keys: Vec<StorageKey> =
+            subtrees.iter().map(|subtree| subtree.get_root_db_key::<L>()).collect();
+        let db_vals = storage.try_mget(&db_keys)?;
+        subtrees
+            .iter()
+            .zip(db_vals)
+            .zip(db_keys)
+            .map(|((subtree, optional_val), db_key)| -> MerkleProofResult<FilledNode<L>> {
+                let val = optional_val.ok_or(StorageError::MissingKey(db_key))?;
+                Ok(FilledNode::deserialize(subtree.root_hash, &val, subtree.is_leaf())?)
+            })
+            .collect()
+    }
+
+    fn add_empty_leaves<'a>(&mut self, indices: impl IntoIterator<Item = &'a NodeIndex>) {
+        self.leaves.extend(indices.into_iter().map(|index| (*index, L::default())));
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use pretty_assertions::assert_eq;
+use rstest::{fixture, rstest};
+
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::external_test_utils::{
+    create_binary_entry,
+    create_edge_entry,
+    create_root_edge_entry,
+};
+use crate::patricia_merkle_tree::filled_tree::node::FilledNode;
+use crate::patricia_merkle_tree::filled_tree::node_serde::SERIALIZE_HASH_BYTES;
+use crate::patricia_merkle_tree::internal_test_utils::MockLeaf;
+use crate::patricia_merkle_tree::merkle_proof::errors::MerkleProofError;
+use crate::patricia_merkle_tree::merkle_proof::proof::{MerkleProof, Preimage, PreimageMap};
+use crate::patricia_merkle_tree::node_data::inner_node::NodeData;
+use crate::patricia_merkle_tree::original_skeleton_tree::create_tree::create_tree_test::create_mock_leaf_entry;
+use crate::patricia_merkle_tree::types::{NodeIndex, SortedLeafIndices, SubTreeHeight};
+use crate::storage::errors::StorageError;
+use crate::storage::map_storage::MapStorage;
+use crate::storage::storage_trait::{StorageKey, StorageValue, TryReadOnlyStorage};
+
+const HEIGHT: SubTreeHeight = SubTreeHeight(3);
+
+// This tree assumes for simplicity that hash is addition (i.e hash(a,b) = a + b).
+///                 Tree structure:
+///
+///                             50
+///                           /   \
+///                         30     20
+///                        /  \     \
+///                       17  13     *
+///                      /  \   \     \
+///                     8    9  11     15
+#[fixture]
+fn tree_entries() -> Vec<(StorageKey, StorageValue)> {
+    vec![
+        create_root_edge_entry(50, HEIGHT),
+        create_binary_entry(30, 20),
+        create_binary_entry(17, 13),
+        create_edge_entry(15, 3, 2),
+        create_binary_entry(8, 9),
+        create_edge_entry(11, 1, 1),
+        create_mock_leaf_entry(8),
+        create_mock_leaf_entry(9),
+        create_mock_leaf_entry(11),
+        create_mock_leaf_entry(15),
+    ]
+}
+
+fn root_hash() -> HashOutput {
+    HashOutput(Felt::from(50_u128 + 248_u128))
+}
+
+fn to_full_indices(small_tree_indices: &[u128]) -> Vec<NodeIndex> {
+    small_tree_indices
+        .iter()
+        .map(|index| NodeIndex::from_subtree_index(NodeIndex::from(*index), HEIGHT))
+        .collect()
+}
+
+fn to_preimage_map(entries: Vec<(StorageKey, StorageValue)>) -> PreimageMap {
+    entries
+        .into_iter()
+        .map(|(key, value)| {
+            let hash =
+                HashOutput(Felt::from_bytes_be_slice(&key.0[key.0.len() - SERIALIZE_HASH_BYTES..]));
+            let preimage =
+                match FilledNode::<MockLeaf>::deserialize(hash, &value, false).unwrap().data {
+                    NodeData::Binary(binary_data) => Preimage::Binary(binary_data),
+                    NodeData::Edge(edge_data) => Preimage::Edge(edge_data),
+                    NodeData::Leaf(_) => panic!("Unexpected leaf in the expected inner nodes."),
+                };
+            (hash, preimage)
+        })
+        .collect()
+}
+
+#[rstest]
+#[case::all_nodes(
+    &[8, 10, 13],
+    vec![
+        create_root_edge_entry(50, HEIGHT),
+        create_binary_entry(30, 20),
+        create_binary_entry(17, 13),
+        create_edge_entry(15, 3, 2),
+        create_binary_entry(8, 9),
+        create_edge_entry(11, 1, 1),
+    ],
+    &[(8, 8), (10, 0), (13, 0)],
+)]
+#[case::single_leaf(
+    &[8],
+    vec![
+        create_root_edge_entry(50, HEIGHT),
+        create_binary_entry(30, 20),
+        create_binary_entry(17, 13),
+        create_binary_entry(8, 9),
+    ],
+    &[(8, 8)],
+)]
+#[case::leaf_below_edge(
+    &[15],
+    vec![
+        create_root_edge_entry(50, HEIGHT),
+        create_binary_entry(30, 20),
+        create_edge_entry(15, 3, 2),
+    ],
+    &[(15, 15)],
+)]
+#[case::non_existing_leaf(
+    &[12],
+    vec![
+        create_root_edge_entry(50, HEIGHT),
+        create_binary_entry(30, 20),
+        create_edge_entry(15, 3, 2),
+    ],
+    &[(12, 0)],
+)]
+fn fetch_proof(
+    tree_entries: Vec<(StorageKey, StorageValue)>,
+    #[case] small_tree_leaf_indices: &[u128],
+    #[case] expected_nodes: Vec<(StorageKey, StorageValue)>,
+    #[case] expected_leaves: &[(u128, u128)],
+) {
+    let storage = MapStorage::from(HashMap::from_iter(tree_entries));
+    let mut leaf_indices = to_full_indices(small_tree_leaf_indices);
+    let proof = MerkleProof::<MockLeaf>::fetch(
+        &storage,
+        root_hash(),
+        SortedLeafIndices::new(&mut leaf_indices),
+    )
+    .unwrap();
+
+    let expected_leaves = expected_leaves
+        .iter()
+        .map(|(index, value)| (to_full_indices(&[*index])[0], MockLeaf(Felt::from(*value))))
+        .collect();
+    assert_eq!(
+        proof,
+        MerkleProof { nodes: to_preimage_map(expected_nodes), leaves: expected_leaves }
+    );
+}
+
+#[rstest]
+fn fetch_proof_of_empty_tree(tree_entries: Vec<(StorageKey, StorageValue)>) {
+    let storage = MapStorage::from(HashMap::from_iter(tree_entries));
+    let mut leaf_indices = to_full_indices(&[8, 15]);
+    let proof = MerkleProof::<MockLeaf>::fetch(
+        &storage,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        SortedLeafIndices::new(&mut leaf_indices),
+    )
+    .unwrap();
+
+    let expected_leaves = leaf_indices.iter().map(|index| (*index, MockLeaf::default())).collect();
+    assert_eq!(proof, MerkleProof { nodes: HashMap::new(), leaves: expected_leaves });
+}
+
+#[rstest]
+fn fetch_proof_with_missing_node(tree_entries: Vec<(StorageKey, StorageValue)>) {
+    let (missing_key, _) = create_binary_entry(8, 9);
+    let storage = MapStorage::from(HashMap::from_iter(
+        tree_entries.into_iter().filter(|(key, _)| key != &missing_key),
+    ));
+    let mut leaf_indices = to_full_indices(&[8]);
+    let result = MerkleProof::<MockLeaf>::fetch(
+        &storage,
+        root_hash(),
+        SortedLeafIndices::new(&mut leaf_indices),
+    );
+
+    assert!(matches!(
+        result,
+        Err(MerkleProofError::StorageRead(StorageError::MissingKey(key))) if key == missing_key
+    ));
+}
+
+struct FailingStorage;
+
+impl TryReadOnlyStorage for FailingStorage {
+    fn try_mget(&self, _keys: &[StorageKey]) -> Result<Vec<Option<StorageValue>>, StorageError> {
+        Err(StorageError::ReadFailure("The storage is unavailable.".into()))
+    }
+}
+
+#[test]
+fn fetch_proof_with_failed_read() {
+    let mut leaf_indices = to_full_indices(&[8]);
+    let result = MerkleProof::<MockLeaf>::fetch(
+        &FailingStorage,
+        root_hash(),
+        SortedLeafIndices::new(&mut leaf_indices),
+    );
+
+    assert!(matches!(result, Err(MerkleProofError::StorageRead(StorageError::ReadFailure(_)))));
+}
@@ -22,7 +22,7 @@ use crate::patricia_merkle_tree::original_skeleton_tree::tree::{
 use crate::patricia_merkle_tree::original_skeleton_tree::utils::split_leaves;
 use crate::patricia_merkle_tree::types::{NodeIndex, SortedLeafIndices, SubTreeHeight};
 use crate::storage::errors::StorageError;
-use crate::storage::storage_trait::{create_db_key, StarknetPrefix, Storage, StorageKey};
+use crate::storage::storage_trait::{create_db_key, ReadOnlyStorage, StarknetPrefix, StorageKey};
 
 #[cfg(test)]
 #[path = "create_tree_test.rs"]
@@ -36,7 +36,7 @@ macro_rules! log_trivial_modification {
 }
 
 #[derive(Debug, PartialEq)]
-struct SubTree<'a> {
+pub(crate) struct SubTree<'a> {
     pub sorted_leaf_indices: SortedLeafIndices<'a>,
     pub root_index: NodeIndex,
     pub root_hash: HashOutput,
@@ -58,7 +58,7 @@ impl<'a> SubTree<'a> {
     /// Returns the bottom subtree which is referred from `self` by the given path. When creating
     /// the bottom subtree some indices that were modified under `self` are not modified under the
     /// bottom subtree (leaves that were previously empty). These indices are returned as well.
-    fn get_bottom_subtree(
+    pub(crate) fn get_bottom_subtree(
         &self,
         path_to_bottom: &PathToBottom,
         bottom_hash: HashOutput,
@@ -87,7 +87,11 @@ impl<'a> SubTree<'a> {
         )
     }
 
-    fn get_children_subtrees(&self, left_hash: HashOutput, right_hash: HashOutput) -> (Self, Self) {
+    pub(crate) fn get_children_subtrees(
+        &self,
+        left_hash: HashOutput,
+        right_hash: HashOutput,
+    ) -> (Self, Self) {
         let [left_leaves, right_leaves] = self.split_leaves();
         let left_root_index = self.root_index * 2.into();
         (
@@ -104,9 +108,21 @@ impl<'a> SubTree<'a> {
         )
     }
 
-    fn is_leaf(&self) -> bool {
+    pub(crate) fn is_leaf(&self) -> bool {
         self.root_index.is_leaf()
     }
+
+    /// Returns the storage key of the root of the subtree.
+    pub(crate) fn get_root_db_key<L: Leaf>(&self) -> StorageKey {
+        create_db_key(
+            if self.is_leaf() {
+                L::prefix()
+            } else {
+                StarknetPrefix::InnerNode.to_storage_prefix()
+            },
+            &self.root_hash.0.to_bytes_be(),
+        )
+    }
 }
 
 impl<'a> OriginalSkeletonTreeImpl<'a> {
@@ -117,7 +133,7 @@ impl<'a> OriginalSkeletonTreeImpl<'a> {
     fn fetch_nodes<L: Leaf>(
         &mut self,
         subtrees: Vec<SubTree<'a>>,
-        storage: &impl Storage,
+        storage: &impl ReadOnlyStorage,
         leaf_modifications: &LeafModifications<L>,
         config: &impl OriginalSkeletonTreeConfig<L>,
         mut previous_leaves: Option<&mut HashMap<NodeIndex, L>>,
@@ -214,35 +230,28 @@ impl<'a> OriginalSkeletonTreeImpl<'a> {
     // TODO(Aviv, 17/07/2024): Split between storage prefix implementation and function logic.
     fn calculate_subtrees_roots<L: Leaf>(
         subtrees: &[SubTree<'a>],
-        storage: &impl Storage,
+        storage: &impl ReadOnlyStorage,
     ) -> OriginalSkeletonTreeResult<Vec<FilledNode<L>>> {
         let mut subtrees_roots = vec![];
-        let db_keys: Vec<StorageKey> = subtrees
-            .iter()
-            .map(|subtree| {
-                create_db_key(
-                    if subtree.is_leaf() {
-                        L::prefix()
-                    } else {
-                        StarknetPrefix::InnerNode.to_storage_prefix()
-                    },
-                    &subtree.root_hash.0.to_bytes_be(),
-                )
-            })
-            .collect();
+        let db_keys: Vec<StorageKey> =
+            subtrees.iter().map(|subtree| subtree.get_root_db_key::<L>()).collect();
 
         let db_vals = storage.mget(&db_keys);
         for ((subtree, optional_val), db_key) in
-            subtrees.iter().zip(db_vals.iter()).zip(db_keys.into_iter())
+            subtrees.iter().zip(db_vals.into_iter()).zip(db_keys.into_iter())
         {
             let val = optional_val.ok_or(StorageError::MissingKey(db_key))?;
-            subtrees_roots.push(FilledNode::deserialize(subtree.root_hash, val, subtree.is_leaf())?)
+            subtrees_roots.push(FilledNode::deserialize(
+                subtree.root_hash,
+                &val,
+                subtree.is_leaf(),
+            )?)
         }
         Ok(subtrees_roots)
     }
 
     pub(crate) fn create_impl<L: Leaf>(
-        storage: &impl Storage,
+        storage: &impl ReadOnlyStorage,
         root_hash: HashOutput,
         sorted_leaf_indices: SortedLeafIndices<'a>,
         config: &impl OriginalSkeletonTreeConfig<L>,
@@ -272,7 +281,7 @@ impl<'a> OriginalSkeletonTreeImpl<'a> {
     }
 
     pub(crate) fn create_and_get_previous_leaves_impl<L: Leaf>(
-        storage: &impl Storage,
+        storage: &impl ReadOnlyStorage,
         root_hash: HashOutput,
         sorted_leaf_indices: SortedLeafIndices<'a>,
         leaf_modifications: &LeafModifications<L>,
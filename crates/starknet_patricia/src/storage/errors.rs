@@ -9,6 +9,8 @@ use crate::storage::storage_trait::StorageKey;
 pub enum StorageError {
     #[error("The key {0:?} does not exist in storage.")]
     MissingKey(StorageKey),
+    #[error("Failed to read from storage: {0}")]
+    ReadFailure(Box<dyn std::error::Error + Send + Sync>),
 }
 
 #[derive(thiserror::Error, Debug)]
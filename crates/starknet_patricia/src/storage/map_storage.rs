@@ -2,7 +2,14 @@ use std::collections::HashMap;
 
 use serde::Serialize;
 
-use crate::storage::storage_trait::{Storage, StorageKey, StorageValue};
+use crate::storage::errors::StorageError;
+use crate::storage::storage_trait::{
+    ReadOnlyStorage,
+    Storage,
+    StorageKey,
+    StorageValue,
+    TryReadOnlyStorage,
+};
 
 #[derive(Serialize, Debug, Default)]
 #[cfg_attr(any(test, feature = "testing"), derive(Clone))]
@@ -10,17 +17,25 @@ pub struct MapStorage {
     pub storage: HashMap<StorageKey, StorageValue>,
 }
 
-impl Storage for MapStorage {
-    fn get(&self, key: &StorageKey) -> Option<&StorageValue> {
-        self.storage.get(key)
+impl ReadOnlyStorage for MapStorage {
+    fn get(&self, key: &StorageKey) -> Option<StorageValue> {
+        self.storage.get(key).cloned()
     }
 
-    fn set(&mut self, key: StorageKey, value: StorageValue) -> Option<StorageValue> {
-        self.storage.insert(key, value)
+    fn mget(&self, keys: &[StorageKey]) -> Vec<Option<StorageValue>> {
+        keys.iter().map(|key| self.get(key)).collect::<Vec<_>>()
     }
+}
 
-    fn mget(&self, keys: &[StorageKey]) -> Vec<Option<&StorageValue>> {
-        keys.iter().map(|key| self.get(key)).collect::<Vec<_>>()
+impl TryReadOnlyStorage for MapStorage {
+    fn try_mget(&self, keys: &[StorageKey]) -> Result<Vec<Option<StorageValue>>, StorageError> {
+        Ok(self.mget(keys))
+    }
+}
+
+impl Storage for MapStorage {
+    fn set(&mut self, key: StorageKey, value: StorageValue) -> Option<StorageValue> {
+        self.storage.insert(key, value)
     }
 
     fn mset(&mut self, key_to_value: HashMap<StorageKey, StorageValue>) {
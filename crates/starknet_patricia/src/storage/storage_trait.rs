@@ -3,27 +3,38 @@ use std::collections::HashMap;
 use serde::{Serialize, Serializer};
 
 use crate::felt::Felt;
+use crate::storage::errors::StorageError;
 
 #[derive(Debug, Eq, Hash, PartialEq)]
 #[cfg_attr(any(test, feature = "testing"), derive(Clone))]
 pub struct StorageKey(pub Vec<u8>);
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
-#[cfg_attr(any(test, feature = "testing"), derive(Clone))]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 pub struct StorageValue(pub Vec<u8>);
 
-pub trait Storage: From<HashMap<StorageKey, StorageValue>> {
+/// Read access to the storage, e.g. for extracting proofs from a snapshot of the tries.
+pub trait ReadOnlyStorage {
     /// Returns value from storage, if it exists.
-    fn get(&self, key: &StorageKey) -> Option<&StorageValue>;
+    fn get(&self, key: &StorageKey) -> Option<StorageValue>;
+
+    /// Returns values from storage in same order of given keys. Value is None for keys that do not
+    /// exist.
+    fn mget(&self, keys: &[StorageKey]) -> Vec<Option<StorageValue>>;
+}
 
+/// Read access to a storage whose reads may fail, e.g. a database that is read while serving
+/// requests, where a failed read should be returned rather than panic.
+pub trait TryReadOnlyStorage {
+    /// Returns values from storage in same order of given keys, or the error of a failed read.
+    /// Value is None for keys that do not exist.
+    fn try_mget(&self, keys: &[StorageKey]) -> Result<Vec<Option<StorageValue>>, StorageError>;
+}
+
+pub trait Storage: ReadOnlyStorage {
     /// Sets value in storage. If key already exists, its value is overwritten and the old value is
     /// returned.
     fn set(&mut self, key: StorageKey, value: StorageValue) -> Option<StorageValue>;
 
-    /// Returns values from storage in same order of given keys. Value is None for keys that do not
-    /// exist.
-    fn mget(&self, keys: &[StorageKey]) -> Vec<Option<&StorageValue>>;
-
     /// Sets values in storage.
     fn mset(&mut self, key_to_value: HashMap<StorageKey, StorageValue>);
 
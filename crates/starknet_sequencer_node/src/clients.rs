@@ -7,6 +7,15 @@ use starknet_batcher_types::communication::{
     RemoteBatcherClient,
     SharedBatcherClient,
 };
+use starknet_class_manager::communication::{
+    LocalClassManagerClient,
+    RemoteClassManagerClient,
+};
+use starknet_class_manager_types::{
+    ClassManagerRequest,
+    ClassManagerResponse,
+    SharedClassManagerClient,
+};
 use starknet_gateway_types::communication::{
     GatewayRequest,
     GatewayResponse,
@@ -45,6 +54,7 @@ use crate::config::node_config::SequencerNodeConfig;
 
 pub struct SequencerNodeClients {
     batcher_client: Client<BatcherRequest, BatcherResponse>,
+    class_manager_client: Client<ClassManagerRequest, ClassManagerResponse>,
     mempool_client: Client<MempoolRequest, MempoolResponse>,
     gateway_client: Client<GatewayRequest, GatewayResponse>,
     mempool_p2p_propagator_client:
@@ -106,6 +116,16 @@ impl SequencerNodeClients {
         self.batcher_client.get_local_client()
     }
 
+    pub fn get_class_manager_shared_client(&self) -> Option<SharedClassManagerClient> {
+        get_shared_client!(self, class_manager_client)
+    }
+
+    pub fn get_class_manager_local_client(
+        &self,
+    ) -> Option<LocalComponentClient<ClassManagerRequest, ClassManagerResponse>> {
+        self.class_manager_client.get_local_client()
+    }
+
     pub fn get_mempool_shared_client(&self) -> Option<SharedMempoolClient> {
         get_shared_client!(self, mempool_client)
     }
@@ -226,6 +246,13 @@ pub fn create_node_clients(
         channels.take_batcher_tx(),
         &config.components.batcher.remote_client_config
     );
+    let class_manager_client = create_client!(
+        &config.components.class_manager.execution_mode,
+        LocalClassManagerClient,
+        RemoteClassManagerClient,
+        channels.take_class_manager_tx(),
+        &config.components.class_manager.remote_client_config
+    );
     let mempool_client = create_client!(
         &config.components.mempool.execution_mode,
         LocalMempoolClient,
@@ -267,6 +294,7 @@ pub fn create_node_clients(
 
     SequencerNodeClients {
         batcher_client,
+        class_manager_client,
         mempool_client,
         gateway_client,
         mempool_p2p_propagator_client,
@@ -1,4 +1,5 @@
 use starknet_batcher_types::communication::BatcherRequestAndResponseSender;
+use starknet_class_manager::communication::ClassManagerRequestAndResponseSender;
 use starknet_gateway_types::communication::GatewayRequestAndResponseSender;
 use starknet_l1_provider::communication::L1ProviderRequestAndResponseSender;
 use starknet_mempool_p2p_types::communication::MempoolP2pPropagatorRequestAndResponseSender;
@@ -9,6 +10,7 @@ use tokio::sync::mpsc::{channel, Receiver, Sender};
 
 pub struct SequencerNodeCommunication {
     batcher_channel: ComponentCommunication<BatcherRequestAndResponseSender>,
+    class_manager_channel: ComponentCommunication<ClassManagerRequestAndResponseSender>,
     gateway_channel: ComponentCommunication<GatewayRequestAndResponseSender>,
     l1_provider_channel: ComponentCommunication<L1ProviderRequestAndResponseSender>,
     mempool_channel: ComponentCommunication<MempoolRequestAndResponseSender>,
@@ -26,6 +28,14 @@ impl SequencerNodeCommunication {
         self.batcher_channel.take_rx()
     }
 
+    pub fn take_class_manager_tx(&mut self) -> Sender<ClassManagerRequestAndResponseSender> {
+        self.class_manager_channel.take_tx()
+    }
+
+    pub fn take_class_manager_rx(&mut self) -> Receiver<ClassManagerRequestAndResponseSender> {
+        self.class_manager_channel.take_rx()
+    }
+
     pub fn take_gateway_tx(&mut self) -> Sender<GatewayRequestAndResponseSender> {
         self.gateway_channel.take_tx()
     }
@@ -75,6 +85,9 @@ pub fn create_node_channels() -> SequencerNodeCommunication {
     let (tx_batcher, rx_batcher) =
         channel::<BatcherRequestAndResponseSender>(DEFAULT_INVOCATIONS_QUEUE_SIZE);
 
+    let (tx_class_manager, rx_class_manager) =
+        channel::<ClassManagerRequestAndResponseSender>(DEFAULT_INVOCATIONS_QUEUE_SIZE);
+
     let (tx_gateway, rx_gateway) =
         channel::<GatewayRequestAndResponseSender>(DEFAULT_INVOCATIONS_QUEUE_SIZE);
 
@@ -92,6 +105,10 @@ pub fn create_node_channels() -> SequencerNodeCommunication {
 
     SequencerNodeCommunication {
         batcher_channel: ComponentCommunication::new(Some(tx_batcher), Some(rx_batcher)),
+        class_manager_channel: ComponentCommunication::new(
+            Some(tx_class_manager),
+            Some(rx_class_manager),
+        ),
         gateway_channel: ComponentCommunication::new(Some(tx_gateway), Some(rx_gateway)),
         l1_provider_channel: ComponentCommunication::new(
             Some(tx_l1_provider),
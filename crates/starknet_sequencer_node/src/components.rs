@@ -1,7 +1,10 @@
+use papyrus_base_layer::ethereum_base_layer_contract::EthereumBaseLayerContract;
 use starknet_batcher::batcher::{create_batcher, Batcher};
+use starknet_class_manager::{create_class_manager, ClassManager};
 use starknet_consensus_manager::consensus_manager::ConsensusManager;
 use starknet_gateway::gateway::{create_gateway, Gateway};
 use starknet_http_server::http_server::{create_http_server, HttpServer};
+use starknet_l1_provider::l1_scraper::{create_l1_scraper, L1Scraper};
 use starknet_l1_provider::{create_l1_provider, L1Provider};
 use starknet_mempool::communication::{create_mempool, MempoolCommunicationWrapper};
 use starknet_mempool_p2p::create_p2p_propagator_and_runner;
@@ -24,10 +27,12 @@ use crate::version::VERSION_FULL;
 
 pub struct SequencerNodeComponents {
     pub batcher: Option<Batcher>,
+    pub class_manager: Option<ClassManager>,
     pub consensus_manager: Option<ConsensusManager>,
     pub gateway: Option<Gateway>,
     pub http_server: Option<HttpServer>,
     pub l1_provider: Option<L1Provider>,
+    pub l1_scraper: Option<L1Scraper<EthereumBaseLayerContract>>,
     pub mempool: Option<MempoolCommunicationWrapper>,
     pub monitoring_endpoint: Option<MonitoringEndpoint>,
     pub mempool_p2p_propagator: Option<MempoolP2pPropagator>,
@@ -52,6 +57,13 @@ pub fn create_node_components(
         }
         ReactiveComponentExecutionMode::Disabled | ReactiveComponentExecutionMode::Remote => None,
     };
+    let class_manager = match config.components.class_manager.execution_mode {
+        ReactiveComponentExecutionMode::LocalExecutionWithRemoteDisabled
+        | ReactiveComponentExecutionMode::LocalExecutionWithRemoteEnabled => {
+            Some(create_class_manager(config.class_manager_config.clone()))
+        }
+        ReactiveComponentExecutionMode::Disabled | ReactiveComponentExecutionMode::Remote => None,
+    };
     let consensus_manager = match config.components.consensus_manager.execution_mode {
         ActiveComponentExecutionMode::Enabled => {
             let batcher_client =
@@ -133,8 +145,13 @@ pub fn create_node_components(
     let (state_sync, state_sync_runner) = match config.components.state_sync.execution_mode {
         ReactiveComponentExecutionMode::LocalExecutionWithRemoteDisabled
         | ReactiveComponentExecutionMode::LocalExecutionWithRemoteEnabled => {
-            let (state_sync, state_sync_runner) =
-                create_state_sync_and_runner(config.state_sync_config.clone());
+            let class_manager_client = clients
+                .get_class_manager_shared_client()
+                .expect("Class Manager Client should be available");
+            let (state_sync, state_sync_runner) = create_state_sync_and_runner(
+                config.state_sync_config.clone(),
+                class_manager_client,
+            );
             (Some(state_sync), Some(state_sync_runner))
         }
         ReactiveComponentExecutionMode::Disabled | ReactiveComponentExecutionMode::Remote => {
@@ -150,12 +167,28 @@ pub fn create_node_components(
         ReactiveComponentExecutionMode::Disabled | ReactiveComponentExecutionMode::Remote => None,
     };
 
+    let l1_scraper = match config.components.l1_scraper.execution_mode {
+        ActiveComponentExecutionMode::Enabled => {
+            let l1_provider_client = clients
+                .get_l1_provider_shared_client()
+                .expect("L1 Provider Client should be available");
+            Some(create_l1_scraper(
+                config.l1_scraper_config.clone(),
+                config.base_layer_config.clone(),
+                l1_provider_client,
+            ))
+        }
+        ActiveComponentExecutionMode::Disabled => None,
+    };
+
     SequencerNodeComponents {
         batcher,
+        class_manager,
         consensus_manager,
         gateway,
         http_server,
         l1_provider,
+        l1_scraper,
         mempool,
         monitoring_endpoint,
         mempool_p2p_propagator,
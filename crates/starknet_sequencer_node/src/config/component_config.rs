@@ -17,6 +17,8 @@ pub struct ComponentConfig {
     #[validate]
     pub batcher: ReactiveComponentExecutionConfig,
     #[validate]
+    pub class_manager: ReactiveComponentExecutionConfig,
+    #[validate]
     pub gateway: ReactiveComponentExecutionConfig,
     #[validate]
     pub mempool: ReactiveComponentExecutionConfig,
@@ -33,6 +35,8 @@ pub struct ComponentConfig {
     #[validate]
     pub http_server: ActiveComponentExecutionConfig,
     #[validate]
+    pub l1_scraper: ActiveComponentExecutionConfig,
+    #[validate]
     pub monitoring_endpoint: ActiveComponentExecutionConfig,
 }
 
@@ -40,11 +44,13 @@ impl SerializeConfig for ComponentConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
         let sub_configs = vec![
             append_sub_config_name(self.batcher.dump(), "batcher"),
+            append_sub_config_name(self.class_manager.dump(), "class_manager"),
             append_sub_config_name(self.consensus_manager.dump(), "consensus_manager"),
             append_sub_config_name(self.gateway.dump(), "gateway"),
             append_sub_config_name(self.http_server.dump(), "http_server"),
             append_sub_config_name(self.mempool.dump(), "mempool"),
             append_sub_config_name(self.l1_provider.dump(), "l1_provider"),
+            append_sub_config_name(self.l1_scraper.dump(), "l1_scraper"),
             append_sub_config_name(self.mempool_p2p.dump(), "mempool_p2p"),
             append_sub_config_name(self.monitoring_endpoint.dump(), "monitoring_endpoint"),
             append_sub_config_name(self.state_sync.dump(), "state_sync"),
@@ -59,6 +65,7 @@ impl ComponentConfig {
     pub fn disabled() -> ComponentConfig {
         ComponentConfig {
             batcher: ReactiveComponentExecutionConfig::disabled(),
+            class_manager: ReactiveComponentExecutionConfig::disabled(),
             gateway: ReactiveComponentExecutionConfig::disabled(),
             mempool: ReactiveComponentExecutionConfig::disabled(),
             mempool_p2p: ReactiveComponentExecutionConfig::disabled(),
@@ -66,6 +73,7 @@ impl ComponentConfig {
             l1_provider: ReactiveComponentExecutionConfig::disabled(),
             consensus_manager: ActiveComponentExecutionConfig::disabled(),
             http_server: ActiveComponentExecutionConfig::disabled(),
+            l1_scraper: ActiveComponentExecutionConfig::disabled(),
             monitoring_endpoint: ActiveComponentExecutionConfig::disabled(),
         }
     }
@@ -6,6 +6,7 @@ use std::vec::Vec;
 
 use clap::Command;
 use infra_utils::path::resolve_project_relative_path;
+use papyrus_base_layer::ethereum_base_layer_contract::EthereumBaseLayerConfig;
 use papyrus_config::dumping::{
     append_sub_config_name,
     generate_struct_pointer,
@@ -20,9 +21,11 @@ use papyrus_config::{ConfigError, ParamPath, SerializationType, SerializedParam}
 use serde::{Deserialize, Serialize};
 use starknet_batcher::config::BatcherConfig;
 use starknet_batcher::VersionedConstantsOverrides;
+use starknet_class_manager::config::ClassManagerConfig;
 use starknet_consensus_manager::config::ConsensusManagerConfig;
 use starknet_gateway::config::{GatewayConfig, RpcStateReaderConfig};
 use starknet_http_server::config::HttpServerConfig;
+use starknet_l1_provider::l1_scraper::L1ScraperConfig;
 use starknet_l1_provider::L1ProviderConfig;
 use starknet_mempool_p2p::config::MempoolP2pConfig;
 use starknet_monitoring_endpoint::config::MonitoringEndpointConfig;
@@ -51,6 +54,7 @@ pub static CONFIG_POINTERS: LazyLock<ConfigPointers> = LazyLock::new(|| {
                 "consensus_manager_config.consensus_config.chain_id",
                 "consensus_manager_config.consensus_config.network_config.chain_id",
                 "gateway_config.chain_info.chain_id",
+                "l1_scraper_config.chain_id",
                 "mempool_p2p_config.network_config.chain_id",
                 "state_sync_config.storage_config.db_config.chain_id",
                 "state_sync_config.network_config.chain_id",
@@ -89,6 +93,18 @@ pub static CONFIG_POINTERS: LazyLock<ConfigPointers> = LazyLock::new(|| {
             ),
             set_pointing_param_paths(&["consensus_manager_config.consensus_config.validator_id"]),
         ),
+        (
+            ser_pointer_target_required_param(
+                "eth_node_url",
+                SerializationType::String,
+                "The URL of the Ethereum node which the Starknet messaging events and the L1 gas \
+                 prices are read from.",
+            ),
+            set_pointing_param_paths(&[
+                "base_layer_config.node_url",
+                "consensus_manager_config.gas_price_oracle_config.node_url",
+            ]),
+        ),
     ];
     let mut common_execution_config = generate_struct_pointer(
         "versioned_constants_overrides".to_owned(),
@@ -111,9 +127,12 @@ pub static CONFIG_NON_POINTERS_WHITELIST: LazyLock<Pointers> =
 pub struct SequencerNodeConfig {
     #[validate]
     pub components: ComponentConfig,
+    pub base_layer_config: EthereumBaseLayerConfig,
     #[validate]
     pub batcher_config: BatcherConfig,
     #[validate]
+    pub class_manager_config: ClassManagerConfig,
+    #[validate]
     pub consensus_manager_config: ConsensusManagerConfig,
     #[validate]
     pub gateway_config: GatewayConfig,
@@ -126,6 +145,8 @@ pub struct SequencerNodeConfig {
     #[validate]
     pub l1_provider_config: L1ProviderConfig,
     #[validate]
+    pub l1_scraper_config: L1ScraperConfig,
+    #[validate]
     pub mempool_p2p_config: MempoolP2pConfig,
     #[validate]
     pub monitoring_endpoint_config: MonitoringEndpointConfig,
@@ -137,7 +158,9 @@ impl SerializeConfig for SequencerNodeConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
         let sub_configs = vec![
             append_sub_config_name(self.components.dump(), "components"),
+            append_sub_config_name(self.base_layer_config.dump(), "base_layer_config"),
             append_sub_config_name(self.batcher_config.dump(), "batcher_config"),
+            append_sub_config_name(self.class_manager_config.dump(), "class_manager_config"),
             append_sub_config_name(
                 self.consensus_manager_config.dump(),
                 "consensus_manager_config",
@@ -153,6 +176,7 @@ impl SerializeConfig for SequencerNodeConfig {
             ),
             append_sub_config_name(self.state_sync_config.dump(), "state_sync_config"),
             append_sub_config_name(self.l1_provider_config.dump(), "l1_provider_config"),
+            append_sub_config_name(self.l1_scraper_config.dump(), "l1_scraper_config"),
         ];
 
         sub_configs.into_iter().flatten().collect()
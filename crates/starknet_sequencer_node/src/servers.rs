@@ -3,10 +3,12 @@ use std::pin::Pin;
 
 use futures::{Future, FutureExt};
 use starknet_batcher::communication::{LocalBatcherServer, RemoteBatcherServer};
+use starknet_class_manager::communication::{LocalClassManagerServer, RemoteClassManagerServer};
 use starknet_consensus_manager::communication::ConsensusManagerServer;
 use starknet_gateway::communication::{LocalGatewayServer, RemoteGatewayServer};
 use starknet_http_server::communication::HttpServer;
 use starknet_l1_provider::communication::{LocalL1ProviderServer, RemoteL1ProviderServer};
+use starknet_l1_provider::l1_scraper::L1ScraperServer;
 use starknet_mempool::communication::{LocalMempoolServer, RemoteMempoolServer};
 use starknet_mempool_p2p::propagator::{
     LocalMempoolP2pPropagatorServer,
@@ -38,6 +40,7 @@ use crate::config::node_config::SequencerNodeConfig;
 // Component servers that can run locally.
 struct LocalServers {
     pub(crate) batcher: Option<Box<LocalBatcherServer>>,
+    pub(crate) class_manager: Option<Box<LocalClassManagerServer>>,
     pub(crate) gateway: Option<Box<LocalGatewayServer>>,
     pub(crate) l1_provider: Option<Box<LocalL1ProviderServer>>,
     pub(crate) mempool: Option<Box<LocalMempoolServer>>,
@@ -49,6 +52,7 @@ struct LocalServers {
 struct WrapperServers {
     pub(crate) consensus_manager: Option<Box<ConsensusManagerServer>>,
     pub(crate) http_server: Option<Box<HttpServer>>,
+    pub(crate) l1_scraper: Option<Box<L1ScraperServer>>,
     pub(crate) monitoring_endpoint: Option<Box<MonitoringEndpointServer>>,
     pub(crate) mempool_p2p_runner: Option<Box<MempoolP2pRunnerServer>>,
     pub(crate) state_sync_runner: Option<Box<StateSyncRunnerServer>>,
@@ -58,6 +62,7 @@ struct WrapperServers {
 // TODO(Nadin): Remove pub from the struct and update the fields to be pub(crate).
 pub struct RemoteServers {
     pub batcher: Option<Box<RemoteBatcherServer>>,
+    pub class_manager: Option<Box<RemoteClassManagerServer>>,
     pub gateway: Option<Box<RemoteGatewayServer>>,
     pub l1_provider: Option<Box<RemoteL1ProviderServer>>,
     pub mempool: Option<Box<RemoteMempoolServer>>,
@@ -225,6 +230,11 @@ fn create_local_servers(
         components.batcher,
         communication.take_batcher_rx()
     );
+    let class_manager_server = create_local_server!(
+        &config.components.class_manager.execution_mode,
+        components.class_manager,
+        communication.take_class_manager_rx()
+    );
     let gateway_server = create_local_server!(
         &config.components.gateway.execution_mode,
         components.gateway,
@@ -253,6 +263,7 @@ fn create_local_servers(
 
     LocalServers {
         batcher: batcher_server,
+        class_manager: class_manager_server,
         gateway: gateway_server,
         l1_provider: l1_provider_server,
         mempool: mempool_server,
@@ -280,6 +291,7 @@ impl LocalServers {
     async fn run(self) -> JoinSet<(Result<(), ComponentServerError>, String)> {
         create_servers(vec![
             server_future_and_label(self.batcher, "Local Batcher"),
+            server_future_and_label(self.class_manager, "Local Class Manager"),
             server_future_and_label(self.gateway, "Local Gateway"),
             server_future_and_label(self.l1_provider, "Local L1 Provider"),
             server_future_and_label(self.mempool, "Local Mempool"),
@@ -300,6 +312,12 @@ pub fn create_remote_servers(
         config.components.batcher.remote_server_config
     );
 
+    let class_manager_server = create_remote_server!(
+        &config.components.class_manager.execution_mode,
+        || { clients.get_class_manager_local_client() },
+        config.components.class_manager.remote_server_config
+    );
+
     let gateway_server = create_remote_server!(
         &config.components.gateway.execution_mode,
         || { clients.get_gateway_local_client() },
@@ -332,6 +350,7 @@ pub fn create_remote_servers(
 
     RemoteServers {
         batcher: batcher_server,
+        class_manager: class_manager_server,
         gateway: gateway_server,
         l1_provider: l1_provider_server,
         mempool: mempool_server,
@@ -344,6 +363,7 @@ impl RemoteServers {
     async fn run(self) -> JoinSet<(Result<(), ComponentServerError>, String)> {
         create_servers(vec![
             server_future_and_label(self.batcher, "Remote Batcher"),
+            server_future_and_label(self.class_manager, "Remote Class Manager"),
             server_future_and_label(self.gateway, "Remote Gateway"),
             server_future_and_label(self.l1_provider, "Remote L1 Provider"),
             server_future_and_label(self.mempool, "Remote Mempool"),
@@ -366,6 +386,8 @@ fn create_wrapper_servers(
         &config.components.http_server.execution_mode,
         components.http_server
     );
+    let l1_scraper_server =
+        create_wrapper_server!(&config.components.l1_scraper.execution_mode, components.l1_scraper);
 
     let monitoring_endpoint_server = create_wrapper_server!(
         &config.components.monitoring_endpoint.execution_mode,
@@ -384,6 +406,7 @@ fn create_wrapper_servers(
     WrapperServers {
         consensus_manager: consensus_manager_server,
         http_server,
+        l1_scraper: l1_scraper_server,
         monitoring_endpoint: monitoring_endpoint_server,
         mempool_p2p_runner: mempool_p2p_runner_server,
         state_sync_runner: state_sync_runner_server,
@@ -395,6 +418,7 @@ impl WrapperServers {
         create_servers(vec![
             server_future_and_label(self.consensus_manager, "Consensus Manager"),
             server_future_and_label(self.http_server, "Http"),
+            server_future_and_label(self.l1_scraper, "L1 Scraper"),
             server_future_and_label(self.monitoring_endpoint, "Monitoring Endpoint"),
             server_future_and_label(self.mempool_p2p_runner, "Mempool P2P Runner"),
             server_future_and_label(self.state_sync_runner, "State Sync Runner"),
@@ -11,6 +11,7 @@ use papyrus_p2p_sync::server::{P2PSyncServer, P2PSyncServerChannels};
 use papyrus_p2p_sync::{Protocol, BUFFER_SIZE};
 use papyrus_storage::{open_storage, StorageReader};
 use starknet_api::block::BlockNumber;
+use starknet_class_manager_types::SharedClassManagerClient;
 use starknet_sequencer_infra::component_definitions::ComponentStarter;
 use starknet_sequencer_infra::component_server::WrapperServer;
 use starknet_sequencer_infra::errors::ComponentError;
@@ -44,7 +45,9 @@ impl StateSyncRunner {
     pub fn new(
         config: StateSyncConfig,
         new_block_receiver: Receiver<(BlockNumber, SyncBlock)>,
+        class_manager_client: SharedClassManagerClient,
     ) -> (Self, StorageReader) {
+        let chain_id = config.storage_config.db_config.chain_id.clone();
         let (storage_reader, storage_writer) =
             open_storage(config.storage_config).expect("StateSyncRunner failed opening storage");
 
@@ -61,18 +64,23 @@ impl StateSyncRunner {
             .register_sqmr_protocol_client(Protocol::Transaction.into(), BUFFER_SIZE);
         let class_client_sender =
             network_manager.register_sqmr_protocol_client(Protocol::Class.into(), BUFFER_SIZE);
+        let event_client_sender =
+            network_manager.register_sqmr_protocol_client(Protocol::Event.into(), BUFFER_SIZE);
         let p2p_sync_client_channels = P2PSyncClientChannels::new(
             header_client_sender,
             state_diff_client_sender,
             transaction_client_sender,
             class_client_sender,
+            event_client_sender,
         );
         let p2p_sync_client = P2PSyncClient::new(
             config.p2p_sync_client_config,
+            chain_id,
             storage_reader.clone(),
             storage_writer,
             p2p_sync_client_channels,
             new_block_receiver.boxed(),
+            Some(class_manager_client),
         );
 
         let header_server_receiver = network_manager